@@ -1,6 +1,7 @@
 use cairo_vm::{
-    types::{layout_name::LayoutName, program::Program},
-    vm::runners::cairo_runner::CairoRunner,
+    types::{layout_name::LayoutName, program::Program, relocatable::Relocatable},
+    vm::{runners::cairo_runner::CairoRunner, vm_core::VirtualMachine},
+    Felt252,
 };
 use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
 
@@ -52,5 +53,27 @@ fn load_program_data(c: &mut Criterion) {
     });
 }
 
+fn memory_access(c: &mut Criterion) {
+    const CELLS: usize = 1 << 16;
+    c.bench_function("memory insert and get", |b| {
+        b.iter_with_large_drop(|| {
+            let mut vm = VirtualMachine::new(false);
+            let base = vm.add_memory_segment();
+            for offset in 0..CELLS {
+                vm.insert_value((base + offset).unwrap(), Felt252::from(offset))
+                    .unwrap();
+            }
+            for offset in 0..CELLS {
+                _ = black_box(
+                    vm.get_integer(black_box(Relocatable::from((0, offset))))
+                        .unwrap(),
+                );
+            }
+            vm
+        })
+    });
+}
+
+criterion_group!(memory, memory_access);
 criterion_group!(runner, build_many_runners, load_program_data, parse_program);
-criterion_main!(runner);
+criterion_main!(memory, runner);
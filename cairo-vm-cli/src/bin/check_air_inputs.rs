@@ -0,0 +1,176 @@
+#![deny(warnings)]
+#![forbid(unsafe_code)]
+use cairo_vm::air_input_checker::{check_air_inputs, AirInputCheckError};
+use cairo_vm::air_private_input::{AirPrivateInput, AirPrivateInputSerializable};
+use cairo_vm::air_public_input::PublicInput;
+use cairo_vm::cairo_run::{read_encoded_memory, read_encoded_trace};
+use clap::{Parser, ValueHint};
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Checks that the trace, memory and AIR inputs of a proof mode run are consistent with each
+/// other before proving them
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(long = "trace_file", value_parser, value_hint=ValueHint::FilePath)]
+    trace_file: PathBuf,
+    #[clap(long = "memory_file", value_parser, value_hint=ValueHint::FilePath)]
+    memory_file: PathBuf,
+    #[clap(long = "air_public_input", value_parser, value_hint=ValueHint::FilePath)]
+    air_public_input: PathBuf,
+    #[clap(long = "air_private_input", value_parser, value_hint=ValueHint::FilePath)]
+    air_private_input: PathBuf,
+}
+
+#[derive(Debug, Error)]
+enum Error {
+    #[error("Invalid arguments")]
+    Cli(#[from] clap::Error),
+    #[error("Failed to interact with the file system")]
+    IO(#[from] std::io::Error),
+    #[error("Failed to parse the AIR inputs: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("The {0} file is truncated")]
+    Truncated(&'static str),
+    #[error(transparent)]
+    Check(#[from] AirInputCheckError),
+}
+
+fn run(args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let args = Args::try_parse_from(args)?;
+
+    let trace =
+        read_encoded_trace(&std::fs::read(&args.trace_file)?).ok_or(Error::Truncated("trace"))?;
+    let memory = read_encoded_memory(&std::fs::read(&args.memory_file)?)
+        .ok_or(Error::Truncated("memory"))?;
+    let public_input_json = std::fs::read_to_string(&args.air_public_input)?;
+    let public_input: PublicInput = serde_json::from_str(&public_input_json)?;
+    let private_input: AirPrivateInputSerializable =
+        serde_json::from_str(&std::fs::read_to_string(&args.air_private_input)?)?;
+
+    check_air_inputs(
+        &trace,
+        &memory,
+        &public_input,
+        &AirPrivateInput::from(private_input),
+    )?;
+    println!("The AIR inputs are consistent with the trace and memory");
+    Ok(())
+}
+
+fn main() -> Result<(), Error> {
+    match run(std::env::args()) {
+        Err(Error::Cli(err)) => err.exit(),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use bincode::enc::write::Writer;
+    use cairo_vm::cairo_run::{
+        cairo_run_program, write_encoded_memory, write_encoded_trace, CairoRunConfig,
+    };
+    use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+    use cairo_vm::types::layout_name::LayoutName;
+    use cairo_vm::types::program::Program;
+
+    struct VecWriter(Vec<u8>);
+
+    impl Writer for VecWriter {
+        fn write(&mut self, bytes: &[u8]) -> Result<(), bincode::error::EncodeError> {
+            self.0.extend_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    /// Runs a program and writes its trace, memory and AIR inputs to a temporary directory,
+    /// returning the arguments to check them
+    fn write_run_files(name: &str, corrupt_memory: bool) -> Vec<String> {
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let config = CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            layout: LayoutName::small,
+            ..Default::default()
+        };
+        let mut runner =
+            cairo_run_program(&program, &config, &mut BuiltinHintProcessor::new_empty()).unwrap();
+        if corrupt_memory {
+            let pc = runner.relocated_trace.as_ref().unwrap()[0].pc;
+            runner.relocated_memory[pc] = None;
+        }
+
+        let dir = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |file: &str| dir.join(file).to_str().unwrap().to_string();
+
+        let mut trace_writer = VecWriter(Vec::new());
+        write_encoded_trace(runner.relocated_trace.as_ref().unwrap(), &mut trace_writer).unwrap();
+        std::fs::write(path("trace"), trace_writer.0).unwrap();
+        let mut memory_writer = VecWriter(Vec::new());
+        write_encoded_memory(&runner.relocated_memory, &mut memory_writer).unwrap();
+        std::fs::write(path("memory"), memory_writer.0).unwrap();
+        std::fs::write(
+            path("public.json"),
+            runner
+                .get_air_public_input()
+                .unwrap()
+                .serialize_json()
+                .unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            path("private.json"),
+            runner
+                .get_air_private_input()
+                .to_serializable(path("trace"), path("memory"))
+                .serialize_json()
+                .unwrap(),
+        )
+        .unwrap();
+
+        [
+            "check_air_inputs".to_string(),
+            "--trace_file".to_string(),
+            path("trace"),
+            "--memory_file".to_string(),
+            path("memory"),
+            "--air_public_input".to_string(),
+            path("public.json"),
+            "--air_private_input".to_string(),
+            path("private.json"),
+        ]
+        .to_vec()
+    }
+
+    #[test]
+    fn test_check_air_inputs_ok() {
+        let args = write_run_files("check_air_inputs_ok", false);
+        assert_matches!(run(args.into_iter()), Ok(()));
+    }
+
+    #[test]
+    fn test_check_air_inputs_corrupt_memory() {
+        let args = write_run_files("check_air_inputs_corrupt_memory", true);
+        assert_matches!(
+            run(args.into_iter()),
+            Err(Error::Check(AirInputCheckError::MissingMemory(_)))
+        );
+    }
+
+    #[test]
+    fn test_check_air_inputs_missing_args() {
+        let args = ["check_air_inputs", "--trace_file", "/dev/null"]
+            .into_iter()
+            .map(String::from);
+        assert_matches!(run(args), Err(Error::Cli(_)));
+    }
+}
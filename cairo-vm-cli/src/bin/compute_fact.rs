@@ -0,0 +1,150 @@
+#![deny(warnings)]
+#![forbid(unsafe_code)]
+use cairo_vm::cairo_run::{cairo_run_program, CairoRunConfig};
+use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+use cairo_vm::program_fact::{
+    compute_cairo_pie_fact, compute_runner_fact, ProgramFact, ProgramFactError,
+};
+use cairo_vm::types::errors::program_errors::ProgramError;
+use cairo_vm::types::layout_name::LayoutName;
+use cairo_vm::types::program::Program;
+use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
+use cairo_vm::vm::runners::cairo_pie::CairoPie;
+use clap::{Parser, ValueHint};
+use std::fmt::Write;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Computes the fact registered on-chain for the execution of a program: the keccak hash of its
+/// program hash and of its output, along with its fact topology
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Compiled program to run, or zipped Cairo PIE of a previous execution with --cairo_pie
+    #[clap(value_parser, value_hint=ValueHint::FilePath)]
+    filename: PathBuf,
+    #[clap(long = "cairo_pie", conflicts_with_all = ["layout", "proof_mode"])]
+    cairo_pie: bool,
+    #[clap(long = "layout", default_value = "plain", value_enum)]
+    layout: LayoutName,
+    #[clap(long = "proof_mode")]
+    proof_mode: bool,
+}
+
+#[derive(Debug, Error)]
+enum Error {
+    #[error("Invalid arguments")]
+    Cli(#[from] clap::Error),
+    #[error("Failed to interact with the file system")]
+    IO(#[from] std::io::Error),
+    #[error(transparent)]
+    Program(#[from] ProgramError),
+    #[error("The program execution failed")]
+    Runner(#[from] CairoRunError),
+    #[error(transparent)]
+    Fact(#[from] ProgramFactError),
+}
+
+fn run(args: impl Iterator<Item = String>) -> Result<ProgramFact, Error> {
+    let args = Args::try_parse_from(args)?;
+
+    if args.cairo_pie {
+        let pie = CairoPie::read_zip_file(&args.filename)?;
+        return Ok(compute_cairo_pie_fact(&pie)?);
+    }
+
+    let program = Program::from_bytes(&std::fs::read(&args.filename)?, Some("main"))?;
+    let config = CairoRunConfig {
+        layout: args.layout,
+        proof_mode: args.proof_mode,
+        ..Default::default()
+    };
+    let runner = cairo_run_program(&program, &config, &mut BuiltinHintProcessor::new_empty())?;
+    Ok(compute_runner_fact(&runner)?)
+}
+
+fn to_hex(hash: &[u8; 32]) -> String {
+    hash.iter().fold(String::from("0x"), |mut hex, byte| {
+        // Writing to a String can't fail
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+fn main() -> Result<(), Error> {
+    let fact = match run(std::env::args()) {
+        Err(Error::Cli(err)) => err.exit(),
+        other => other?,
+    };
+    let json = serde_json::json!({
+        "program_hash": format!("{:#x}", fact.program_hash),
+        "output_hash": to_hex(&fact.output_hash),
+        "fact": to_hex(&fact.fact),
+        "fact_topology": fact.fact_topology,
+    });
+    println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    const PROGRAM_PATH: &str = "../cairo_programs/manually_compiled/valid_program_b.json";
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        ["compute_fact"]
+            .iter()
+            .chain(args)
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_compute_fact_program_and_cairo_pie() {
+        let program_fact = run(args(&[PROGRAM_PATH, "--layout", "small"])).unwrap();
+
+        let program = Program::from_file(PROGRAM_PATH.as_ref(), Some("main")).unwrap();
+        let config = CairoRunConfig {
+            layout: LayoutName::small,
+            ..Default::default()
+        };
+        let runner =
+            cairo_run_program(&program, &config, &mut BuiltinHintProcessor::new_empty()).unwrap();
+        let pie_path = std::env::temp_dir().join("test_compute_fact.zip");
+        runner
+            .get_cairo_pie()
+            .unwrap()
+            .write_zip_file(&pie_path)
+            .unwrap();
+
+        let pie_fact = run(args(&[pie_path.to_str().unwrap(), "--cairo_pie"])).unwrap();
+        assert_eq!(pie_fact, program_fact);
+    }
+
+    #[test]
+    fn test_compute_fact_missing_file() {
+        assert_matches!(run(args(&["missing_program.json"])), Err(Error::IO(_)));
+        assert_matches!(
+            run(args(&["missing_pie.zip", "--cairo_pie"])),
+            Err(Error::IO(_))
+        );
+    }
+
+    #[test]
+    fn test_compute_fact_conflicting_args() {
+        assert_matches!(
+            run(args(&[PROGRAM_PATH, "--cairo_pie", "--proof_mode"])),
+            Err(Error::Cli(_))
+        );
+    }
+
+    #[test]
+    fn test_to_hex_keeps_leading_zeros() {
+        let mut hash = [0; 32];
+        hash[31] = 0xab;
+        assert_eq!(to_hex(&hash), format!("0x{}ab", "0".repeat(62)));
+    }
+}
@@ -0,0 +1,118 @@
+#![deny(warnings)]
+#![forbid(unsafe_code)]
+use cairo_vm::program_hash::{compute_program_hash, ProgramHashError, ProgramHashFunction};
+use cairo_vm::types::errors::program_errors::ProgramError;
+use cairo_vm::types::program::Program;
+use clap::{Parser, ValueHint};
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Prints the hash of a compiled program, as computed by the bootloader. Prints the hash of every
+/// hash function unless one is selected.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(value_parser, value_hint=ValueHint::FilePath)]
+    filename: PathBuf,
+    #[clap(long = "hash_function", value_enum)]
+    hash_function: Option<ProgramHashFunction>,
+    #[clap(long = "bootloader_version", default_value = "0")]
+    bootloader_version: usize,
+}
+
+#[derive(Debug, Error)]
+enum Error {
+    #[error("Invalid arguments")]
+    Cli(#[from] clap::Error),
+    #[error(transparent)]
+    Program(#[from] ProgramError),
+    #[error(transparent)]
+    ProgramHash(#[from] ProgramHashError),
+}
+
+const HASH_FUNCTIONS: [(ProgramHashFunction, &str); 3] = [
+    (ProgramHashFunction::Pedersen, "pedersen"),
+    (ProgramHashFunction::Poseidon, "poseidon"),
+    (ProgramHashFunction::Blake2s, "blake2s"),
+];
+
+/// Returns the requested program hashes, along with the name of their hash function
+fn run(args: impl Iterator<Item = String>) -> Result<Vec<(&'static str, String)>, Error> {
+    let args = Args::try_parse_from(args)?;
+
+    let program = Program::from_file(&args.filename, Some("main"))?.get_stripped_program()?;
+    HASH_FUNCTIONS
+        .iter()
+        .filter(|(hash_function, _)| args.hash_function.map_or(true, |h| h == *hash_function))
+        .map(|(hash_function, name)| {
+            let hash = compute_program_hash(&program, *hash_function, args.bootloader_version)?;
+            Ok((*name, format!("{:#x}", hash)))
+        })
+        .collect()
+}
+
+fn main() -> Result<(), Error> {
+    let hashes = match run(std::env::args()) {
+        Err(Error::Cli(err)) => err.exit(),
+        other => other?,
+    };
+    match hashes.as_slice() {
+        [(_, hash)] => println!("{hash}"),
+        _ => {
+            for (name, hash) in hashes {
+                println!("{name}: {hash}");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    const PROGRAM_PATH: &str = "../cairo_programs/manually_compiled/valid_program_b.json";
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        ["program_hash"]
+            .iter()
+            .chain(args)
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_program_hash_all_functions() {
+        let hashes = run(args(&[PROGRAM_PATH])).unwrap();
+        let names: Vec<_> = hashes.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["pedersen", "poseidon", "blake2s"]);
+
+        let poseidon = run(args(&[PROGRAM_PATH, "--hash_function", "poseidon"])).unwrap();
+        assert_eq!(poseidon.as_slice(), &hashes[1..2]);
+    }
+
+    #[test]
+    fn test_program_hash_bootloader_version() {
+        let version_0 = run(args(&[PROGRAM_PATH, "--hash_function", "pedersen"])).unwrap();
+        let version_1 = run(args(&[
+            PROGRAM_PATH,
+            "--hash_function",
+            "pedersen",
+            "--bootloader_version",
+            "1",
+        ]))
+        .unwrap();
+        assert_ne!(version_0, version_1);
+    }
+
+    #[test]
+    fn test_program_hash_invalid_args() {
+        assert_matches!(
+            run(args(&[PROGRAM_PATH, "--hash_function", "sha256"])),
+            Err(Error::Cli(_))
+        );
+        assert_matches!(run(args(&["missing_program.json"])), Err(Error::Program(_)));
+    }
+}
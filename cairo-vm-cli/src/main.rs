@@ -4,9 +4,14 @@ use bincode::enc::write::Writer;
 use cairo_vm::air_public_input::PublicInputError;
 use cairo_vm::cairo_run::{self, EncodeTraceError};
 use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+use cairo_vm::run_artifacts::RunArtifacts;
 #[cfg(feature = "with_tracer")]
 use cairo_vm::serde::deserialize_program::DebugInfo;
+use cairo_vm::types::layout::CairoLayoutParams;
 use cairo_vm::types::layout_name::LayoutName;
+use cairo_vm::types::program::Program;
+use cairo_vm::vm::debugger::Debugger;
+use cairo_vm::vm::differential::DifferentialChecker;
 use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
 use cairo_vm::vm::errors::trace_errors::TraceError;
 use cairo_vm::vm::errors::vm_errors::VirtualMachineError;
@@ -14,6 +19,7 @@ use cairo_vm::vm::runners::cairo_pie::CairoPie;
 #[cfg(feature = "with_tracer")]
 use cairo_vm::vm::runners::cairo_runner::CairoRunner;
 use cairo_vm::vm::runners::cairo_runner::RunResources;
+use cairo_vm::vm::trace::trace_sink::{write_relocated_raw_trace, RawTraceWriter};
 #[cfg(feature = "with_tracer")]
 use cairo_vm_tracer::error::trace_data_errors::TraceDataError;
 #[cfg(feature = "with_tracer")]
@@ -45,6 +51,9 @@ struct Args {
     memory_file: Option<PathBuf>,
     #[clap(long = "layout", default_value = "plain", value_enum)]
     layout: LayoutName,
+    /// JSON file with the parameters of the dynamic layout
+    #[clap(long = "cairo_layout_params_file", value_parser)]
+    cairo_layout_params_file: Option<PathBuf>,
     #[structopt(long = "proof_mode")]
     proof_mode: bool,
     #[structopt(long = "secure_run")]
@@ -56,6 +65,10 @@ struct Args {
         requires_all = ["proof_mode", "trace_file", "memory_file"]
     )]
     air_private_input: Option<String>,
+    /// Writes the trace, memory, AIR public and private inputs and layout params to a single
+    /// compressed bundle
+    #[clap(long = "run_artifacts", requires = "proof_mode")]
+    run_artifacts: Option<PathBuf>,
     #[clap(
         long = "cairo_pie_output",
         // We need to add these air_private_input & air_public_input or else
@@ -75,6 +88,30 @@ struct Args {
         conflicts_with_all = ["proof_mode", "air_private_input", "air_public_input"]
     )]
     run_from_cairo_pie: bool,
+    #[structopt(long = "debug", conflicts_with = "run_from_cairo_pie")]
+    debug: bool,
+    /// Directory where the lcov (lcov.info) and HTML (index.html) coverage reports are written
+    #[clap(long = "coverage", value_parser)]
+    coverage: Option<PathBuf>,
+    /// Write the trace to disk while running and the memory file without relocating the whole
+    /// memory first, to bound the memory used by very long runs
+    #[clap(
+        long = "streaming",
+        conflicts_with_all = ["air_public_input", "run_artifacts", "coverage", "debug", "run_from_cairo_pie"]
+    )]
+    streaming: bool,
+    /// Trace file of a reference run of the program (e.g. made with the Python VM) to compare the
+    /// execution with step by step, stopping at the first difference
+    #[clap(
+        long = "reference_trace",
+        value_parser,
+        requires = "reference_memory",
+        conflicts_with_all = ["debug", "streaming", "run_from_cairo_pie"]
+    )]
+    reference_trace: Option<PathBuf>,
+    /// Memory file of the reference run
+    #[clap(long = "reference_memory", value_parser, requires = "reference_trace")]
+    reference_memory: Option<PathBuf>,
 }
 
 #[derive(Debug, Error)]
@@ -93,6 +130,10 @@ enum Error {
     Trace(#[from] TraceError),
     #[error(transparent)]
     PublicInput(#[from] PublicInputError),
+    #[error("The reference {0} file is truncated")]
+    TruncatedReference(&'static str),
+    #[error("The execution diverged from the reference after {0} steps")]
+    Divergence(usize),
     #[error(transparent)]
     #[cfg(feature = "with_tracer")]
     TraceData(#[from] TraceDataError),
@@ -160,19 +201,38 @@ fn start_tracer(cairo_runner: &CairoRunner) -> Result<(), TraceDataError> {
 fn run(args: impl Iterator<Item = String>) -> Result<(), Error> {
     let args = Args::try_parse_from(args)?;
 
-    let trace_enabled = args.trace_file.is_some() || args.air_public_input.is_some();
+    let trace_enabled = args.trace_file.is_some()
+        || args.air_public_input.is_some()
+        || args.run_artifacts.is_some()
+        || args.coverage.is_some();
+
+    let dynamic_layout_params = args
+        .cairo_layout_params_file
+        .as_deref()
+        .map(CairoLayoutParams::from_file)
+        .transpose()?;
 
     let cairo_run_config = cairo_run::CairoRunConfig {
         entrypoint: &args.entrypoint,
         trace_enabled,
-        relocate_mem: args.memory_file.is_some() || args.air_public_input.is_some(),
+        relocate_mem: (args.memory_file.is_some() && !args.streaming)
+            || args.air_public_input.is_some()
+            || args.run_artifacts.is_some(),
         layout: args.layout,
+        dynamic_layout_params,
         proof_mode: args.proof_mode,
         secure_run: args.secure_run,
         allow_missing_builtins: args.allow_missing_builtins,
         ..Default::default()
     };
 
+    // With streaming, the unrelocated trace is written next to the trace file during the run
+    let raw_trace_path = args
+        .trace_file
+        .as_ref()
+        .filter(|_| args.streaming)
+        .map(|trace_path| trace_path.with_extension("raw"));
+
     let mut cairo_runner = match if args.run_from_cairo_pie {
         let pie = CairoPie::read_zip_file(&args.filename)?;
         let mut hint_processor = BuiltinHintProcessor::new(
@@ -183,7 +243,57 @@ fn run(args: impl Iterator<Item = String>) -> Result<(), Error> {
     } else {
         let program_content = std::fs::read(args.filename).map_err(Error::IO)?;
         let mut hint_processor = BuiltinHintProcessor::new_empty();
-        cairo_run::cairo_run(&program_content, &cairo_run_config, &mut hint_processor)
+        if args.debug {
+            let program = Program::from_bytes(&program_content, Some(&args.entrypoint))
+                .map_err(CairoRunError::from)?;
+            let mut debugger = Debugger::new(io::stdin().lock(), io::stdout());
+            cairo_run::cairo_run_program_with_debugger(
+                &program,
+                &cairo_run_config,
+                &mut hint_processor,
+                &mut debugger,
+            )
+        } else if let (Some(ref trace_path), Some(ref memory_path)) =
+            (&args.reference_trace, &args.reference_memory)
+        {
+            let program = Program::from_bytes(&program_content, Some(&args.entrypoint))
+                .map_err(CairoRunError::from)?;
+            let trace = cairo_run::read_encoded_trace(&std::fs::read(trace_path)?)
+                .ok_or(Error::TruncatedReference("trace"))?;
+            let memory = cairo_run::read_encoded_memory(&std::fs::read(memory_path)?)
+                .ok_or(Error::TruncatedReference("memory"))?;
+            let mut checker = DifferentialChecker::new(trace, memory);
+            let result = cairo_run::cairo_run_program_with_reference(
+                &program,
+                &cairo_run_config,
+                &mut hint_processor,
+                &mut checker,
+            );
+            if let Some(divergence) = checker.divergence() {
+                eprint!("{divergence}");
+                return Err(Error::Divergence(divergence.step));
+            }
+            if result.is_ok() {
+                println!("The execution matches the reference");
+            }
+            result
+        } else if let Some(ref raw_trace_path) = raw_trace_path {
+            let program = Program::from_bytes(&program_content, Some(&args.entrypoint))
+                .map_err(CairoRunError::from)?;
+            let raw_trace_file = std::fs::File::create(raw_trace_path)?;
+            let trace_sink = RawTraceWriter::new(io::BufWriter::with_capacity(
+                3 * 1024 * 1024,
+                raw_trace_file,
+            ));
+            cairo_run::cairo_run_program_with_trace_sink(
+                &program,
+                &cairo_run_config,
+                &mut hint_processor,
+                Box::new(trace_sink),
+            )
+        } else {
+            cairo_run::cairo_run(&program_content, &cairo_run_config, &mut hint_processor)
+        }
     } {
         Ok(runner) => runner,
         Err(error) => {
@@ -198,7 +308,30 @@ fn run(args: impl Iterator<Item = String>) -> Result<(), Error> {
         print!("{output_buffer}");
     }
 
-    if let Some(ref trace_path) = args.trace_file {
+    let relocation_table = if args.streaming {
+        Some(
+            cairo_runner
+                .vm
+                .segments
+                .relocate_segments()
+                .map_err(TraceError::MemoryError)?,
+        )
+    } else {
+        None
+    };
+
+    if let (Some(ref trace_path), Some(raw_trace_path), Some(relocation_table)) =
+        (&args.trace_file, &raw_trace_path, &relocation_table)
+    {
+        let trace_file = std::fs::File::create(trace_path)?;
+        let mut trace_writer =
+            FileWriter::new(io::BufWriter::with_capacity(3 * 1024 * 1024, trace_file));
+        let mut raw_trace = io::BufReader::new(std::fs::File::open(raw_trace_path)?);
+
+        write_relocated_raw_trace(&mut raw_trace, relocation_table, &mut trace_writer)?;
+        trace_writer.flush()?;
+        std::fs::remove_file(raw_trace_path)?;
+    } else if let Some(ref trace_path) = args.trace_file {
         let relocated_trace = cairo_runner
             .relocated_trace
             .as_ref()
@@ -217,7 +350,16 @@ fn run(args: impl Iterator<Item = String>) -> Result<(), Error> {
         let mut memory_writer =
             FileWriter::new(io::BufWriter::with_capacity(5 * 1024 * 1024, memory_file));
 
-        cairo_run::write_encoded_memory(&cairo_runner.relocated_memory, &mut memory_writer)?;
+        match relocation_table {
+            Some(ref relocation_table) => cairo_run::write_relocated_memory(
+                &cairo_runner.vm,
+                relocation_table,
+                &mut memory_writer,
+            )?,
+            None => {
+                cairo_run::write_encoded_memory(&cairo_runner.relocated_memory, &mut memory_writer)?
+            }
+        }
         memory_writer.flush()?;
     }
 
@@ -226,6 +368,20 @@ fn run(args: impl Iterator<Item = String>) -> Result<(), Error> {
         std::fs::write(file_path, json)?;
     }
 
+    if let Some(ref bundle_path) = args.run_artifacts {
+        RunArtifacts::from_runner(&cairo_runner)?.write_zip_file(bundle_path)?;
+    }
+
+    if let Some(ref coverage_dir) = args.coverage {
+        let report = cairo_runner.get_coverage_report()?;
+        std::fs::create_dir_all(coverage_dir)?;
+        std::fs::write(coverage_dir.join("lcov.info"), report.to_lcov())?;
+        std::fs::write(
+            coverage_dir.join("index.html"),
+            report.to_html(|filename| std::fs::read_to_string(filename).ok()),
+        )?;
+    }
+
     #[cfg(feature = "with_tracer")]
     if args.tracer {
         start_tracer(&cairo_runner)?;
@@ -295,6 +451,11 @@ mod tests {
 
     #[rstest]
     #[case(["cairo-vm-cli", "--layout", "broken_layout", "../cairo_programs/fibonacci.json"].as_slice())]
+    #[case(["cairo-vm-cli", "--debug", "--run_from_cairo_pie", "../cairo_programs/fibonacci.json"].as_slice())]
+    #[case(["cairo-vm-cli", "--streaming", "--proof_mode", "--air_public_input", "/dev/null", "../cairo_programs/fibonacci.json"].as_slice())]
+    #[case(["cairo-vm-cli", "--run_artifacts", "/dev/null", "../cairo_programs/fibonacci.json"].as_slice())]
+    #[case(["cairo-vm-cli", "--reference_trace", "/dev/null", "../cairo_programs/fibonacci.json"].as_slice())]
+    #[case(["cairo-vm-cli", "--debug", "--reference_trace", "/dev/null", "--reference_memory", "/dev/null", "../cairo_programs/fibonacci.json"].as_slice())]
     fn test_run_invalid_args(#[case] args: &[&str]) {
         let args = args.iter().cloned().map(String::from);
         assert_matches!(run(args), Err(Error::Cli(_)));
@@ -386,6 +547,111 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_run_coverage() {
+        let coverage_dir = std::env::temp_dir().join("cairo-vm-cli-coverage");
+        let args = [
+            "cairo-vm-cli",
+            "../cairo_programs/manually_compiled/valid_program_b.json",
+            "--layout",
+            "small",
+            "--coverage",
+            coverage_dir.to_str().unwrap(),
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Ok(()));
+        let lcov = std::fs::read_to_string(coverage_dir.join("lcov.info")).unwrap();
+        assert!(lcov.contains("FNDA:1,__main__.main"));
+        assert!(coverage_dir.join("index.html").exists());
+    }
+
+    #[test]
+    fn test_run_streaming() {
+        let dir = std::env::temp_dir().join("cairo-vm-cli-streaming");
+        std::fs::create_dir_all(&dir).unwrap();
+        let run_with = |name: &str, extra_args: &[&str]| {
+            let (trace_file, memory_file) = (
+                dir.join(format!("{name}.trace")),
+                dir.join(format!("{name}.memory")),
+            );
+            let args = [
+                "cairo-vm-cli",
+                "../cairo_programs/manually_compiled/valid_program_b.json",
+                "--layout",
+                "small",
+                "--trace_file",
+                trace_file.to_str().unwrap(),
+                "--memory_file",
+                memory_file.to_str().unwrap(),
+            ]
+            .into_iter()
+            .chain(extra_args.iter().copied())
+            .map(String::from);
+            assert_matches!(run(args), Ok(()));
+            (
+                std::fs::read(trace_file).unwrap(),
+                std::fs::read(memory_file).unwrap(),
+            )
+        };
+        assert_eq!(
+            run_with("streamed", &["--streaming"]),
+            run_with("in_memory", &[])
+        );
+        assert!(!dir.join("streamed.raw").exists());
+    }
+
+    #[test]
+    fn test_run_reference() {
+        let dir = std::env::temp_dir().join("cairo-vm-cli-reference");
+        std::fs::create_dir_all(&dir).unwrap();
+        let (trace_file, memory_file) = (dir.join("trace"), dir.join("memory"));
+        let args = |extra_args: &[&str]| {
+            [
+                "cairo-vm-cli",
+                "../cairo_programs/manually_compiled/valid_program_b.json",
+                "--layout",
+                "small",
+            ]
+            .into_iter()
+            .chain(extra_args.iter().copied())
+            .map(String::from)
+            .collect::<Vec<_>>()
+        };
+        let reference_args = [
+            "--reference_trace",
+            trace_file.to_str().unwrap(),
+            "--reference_memory",
+            memory_file.to_str().unwrap(),
+        ];
+        assert_matches!(
+            run(args(&[
+                "--trace_file",
+                trace_file.to_str().unwrap(),
+                "--memory_file",
+                memory_file.to_str().unwrap(),
+            ])
+            .into_iter()),
+            Ok(())
+        );
+        assert_matches!(run(args(&reference_args).into_iter()), Ok(()));
+
+        // Drop the last step of the reference trace, each entry being 3 u64 registers
+        let trace = std::fs::read(&trace_file).unwrap();
+        std::fs::write(&trace_file, &trace[..trace.len() - 24]).unwrap();
+        let steps = trace.len() / 24 - 1;
+        assert_matches!(
+            run(args(&reference_args).into_iter()),
+            Err(Error::Divergence(step)) if step == steps
+        );
+
+        std::fs::write(&trace_file, &trace[..trace.len() - 1]).unwrap();
+        assert_matches!(
+            run(args(&reference_args).into_iter()),
+            Err(Error::TruncatedReference("trace"))
+        );
+    }
+
     #[test]
     fn test_run_missing_program() {
         let args = ["cairo-vm-cli", "../missing/program.json"]
@@ -394,6 +660,33 @@ mod tests {
         assert_matches!(run(args), Err(Error::IO(_)));
     }
 
+    #[test]
+    fn test_run_dynamic_layout_params() {
+        let args = [
+            "cairo-vm-cli",
+            "../cairo_programs/manually_compiled/valid_program_b.json",
+            "--layout",
+            "dynamic",
+            "--cairo_layout_params_file",
+            "../vm/src/tests/cairo_layout_params_file.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Ok(()));
+
+        let args = [
+            "cairo-vm-cli",
+            "../cairo_programs/manually_compiled/valid_program_b.json",
+            "--layout",
+            "dynamic",
+            "--cairo_layout_params_file",
+            "../missing/params.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Err(Error::IO(_)));
+    }
+
     #[rstest]
     #[case("../cairo_programs/manually_compiled/invalid_even_length_hex.json")]
     #[case("../cairo_programs/manually_compiled/invalid_memory.json")]
@@ -1,4 +1,9 @@
-use crate::error::Error;
+use crate::{
+    error::Error,
+    gas::{gas_report, out_of_gas_panic_data, GasReport, DEFAULT_INITIAL_GAS},
+    syscall_handler::{LocalSyscallHandler, StarknetHintProcessor, SyscallHandler},
+    values::{ArrayLayout, SierraTypes},
+};
 use cairo_lang_casm::{
     builder::{CasmBuilder, Var},
     casm, casm_build_extend,
@@ -22,26 +27,37 @@ use cairo_lang_sierra::{
         ConcreteType, NamedType,
     },
     ids::{ConcreteTypeId, GenericTypeId},
-    program::{Function, GenericArg, Program as SierraProgram},
+    program::{Function, GenericArg, Program as SierraProgram, StatementIdx},
     program_registry::ProgramRegistry,
 };
+use cairo_lang_sierra_generator::statements_code_locations::StatementsSourceCodeLocations;
 use cairo_lang_sierra_to_casm::{
     compiler::{CairoProgram, SierraToCasmConfig},
-    metadata::calc_metadata_ap_change_only,
+    metadata::{calc_metadata, calc_metadata_ap_change_only, MetadataComputationConfig},
 };
 use cairo_lang_sierra_type_size::get_type_size_map;
 use cairo_lang_utils::{
     bigint::BigIntAsHex, casts::IntoOrPanic, unordered_hash_map::UnorderedHashMap,
 };
 use cairo_vm::{
-    hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor,
+    hint_processor::{
+        cairo_1_hint_processor::hint_processor::Cairo1HintProcessor,
+        hint_processor_definition::HintProcessor,
+    },
     math_utils::signed_felt,
-    serde::deserialize_program::{ApTracking, FlowTrackingData, HintParams, ReferenceManager},
+    serde::deserialize_program::{
+        ApTracking, FlowTrackingData, HintParams, Identifier, InputFile, InstructionLocation,
+        Location, ReferenceManager,
+    },
     types::{
-        builtin_name::BuiltinName, layout_name::LayoutName, program::Program,
-        relocatable::MaybeRelocatable,
+        builtin_name::BuiltinName,
+        layout::CairoLayoutParams,
+        layout_name::LayoutName,
+        program::Program,
+        relocatable::{MaybeRelocatable, Relocatable},
     },
     vm::{
+        debugger::Debugger,
         errors::{runner_errors::RunnerError, vm_errors::VirtualMachineError},
         runners::cairo_runner::{CairoRunner, RunResources, RunnerMode},
         vm_core::VirtualMachine,
@@ -51,14 +67,21 @@ use cairo_vm::{
 use itertools::{chain, Itertools};
 use num_bigint::{BigInt, Sign};
 use num_traits::{cast::ToPrimitive, Zero};
-use std::{collections::HashMap, iter::Peekable};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    iter::Peekable,
+};
 
 /// Representation of a cairo argument
-/// Can consist of a single Felt or an array of Felts
+/// Can consist of a single Felt, an array of Felts, or a JSON value of the type of a parameter of `main`
 #[derive(Debug, Clone)]
 pub enum FuncArg {
     Array(Vec<Felt252>),
     Single(Felt252),
+    /// Value of the parameter it's matched with, which must start at the memory cell where the argument is loaded.
+    /// See the [values](crate::values) module for how each type is represented.
+    Json(serde_json::Value),
 }
 
 impl From<Felt252> for FuncArg {
@@ -73,6 +96,12 @@ impl From<Vec<Felt252>> for FuncArg {
     }
 }
 
+impl From<serde_json::Value> for FuncArg {
+    fn from(value: serde_json::Value) -> Self {
+        Self::Json(value)
+    }
+}
+
 /// Configuration parameters for a cairo run
 #[derive(Debug)]
 pub struct Cairo1RunConfig<'a> {
@@ -80,12 +109,16 @@ pub struct Cairo1RunConfig<'a> {
     pub args: &'a [FuncArg],
     /// Serialize program output into a user-friendly format
     pub serialize_output: bool,
+    /// Serialize program output as JSON, following the return type of `main` (requires `serialize_output`)
+    pub json_output: bool,
     /// Compute cairo trace during execution
     pub trace_enabled: bool,
     /// Relocate cairo memory at the end of the run
     pub relocate_mem: bool,
     /// Cairo layout chosen for the run
     pub layout: LayoutName,
+    /// Parameters of the `dynamic` layout
+    pub dynamic_layout_params: Option<CairoLayoutParams>,
     /// Run in proof_mode
     pub proof_mode: bool,
     /// Should be true if either air_public_input or cairo_pie_output are needed
@@ -93,6 +126,11 @@ pub struct Cairo1RunConfig<'a> {
     pub finalize_builtins: bool,
     /// Appends the return and input values to the output segment. This is performed by default when running in proof_mode
     pub append_return_values: bool,
+    /// Cairo source locations of the sierra statements, used to map the executed instructions back to the source code (e.g. for coverage reports)
+    pub code_locations: Option<&'a StatementsSourceCodeLocations>,
+    /// Gas available to the program. If set, the gas costs of the program are computed and charged,
+    /// and running out of gas fails with [Error::OutOfGas]. Otherwise the program doesn't consume gas
+    pub initial_gas: Option<usize>,
 }
 
 impl Default for Cairo1RunConfig<'_> {
@@ -100,12 +138,16 @@ impl Default for Cairo1RunConfig<'_> {
         Self {
             args: Default::default(),
             serialize_output: false,
+            json_output: false,
             trace_enabled: false,
             relocate_mem: false,
             layout: LayoutName::plain,
+            dynamic_layout_params: None,
             proof_mode: false,
             finalize_builtins: false,
             append_return_values: false,
+            code_locations: None,
+            initial_gas: None,
         }
     }
 }
@@ -126,22 +168,145 @@ pub fn cairo_run_program(
     sierra_program: &SierraProgram,
     cairo_run_config: Cairo1RunConfig,
 ) -> Result<(CairoRunner, Vec<MaybeRelocatable>, Option<String>), Error> {
-    let metadata = calc_metadata_ap_change_only(sierra_program)
-        .map_err(|_| VirtualMachineError::Unexpected)?;
+    cairo_run_program_with_syscall_handler(
+        sierra_program,
+        cairo_run_config,
+        &mut LocalSyscallHandler::default(),
+    )
+}
+
+/// Runs a Cairo 1 program like [cairo_run_program], executing its syscalls with `syscall_handler`
+pub fn cairo_run_program_with_syscall_handler(
+    sierra_program: &SierraProgram,
+    cairo_run_config: Cairo1RunConfig,
+    syscall_handler: &mut dyn SyscallHandler,
+) -> Result<(CairoRunner, Vec<MaybeRelocatable>, Option<String>), Error> {
+    let main_func = find_function(sierra_program, "::main")?;
+    let (runner, return_values, serialized_output, _) = run_program(
+        sierra_program,
+        main_func,
+        cairo_run_config,
+        syscall_handler,
+        false,
+        None,
+    )?;
+    Ok((runner, return_values, serialized_output))
+}
+
+/// Runs a Cairo 1 program like [cairo_run_program] through the interactive [Debugger], which takes
+/// control of the execution until the end of the program
+pub fn cairo_run_program_with_debugger<R: BufRead, W: Write>(
+    sierra_program: &SierraProgram,
+    cairo_run_config: Cairo1RunConfig,
+    debugger: &mut Debugger<R, W>,
+) -> Result<(CairoRunner, Vec<MaybeRelocatable>, Option<String>), Error> {
+    let main_func = find_function(sierra_program, "::main")?;
+    let (runner, return_values, serialized_output, _) = run_program(
+        sierra_program,
+        main_func,
+        cairo_run_config,
+        &mut LocalSyscallHandler::default(),
+        false,
+        Some(&mut |runner, end, hint_processor| debugger.run_until_pc(runner, end, hint_processor)),
+    )?;
+    Ok((runner, return_values, serialized_output))
+}
+
+/// Runs the function `func` of a Cairo 1 program like [cairo_run_program] runs `main`
+pub(crate) fn cairo_run_function(
+    sierra_program: &SierraProgram,
+    func: &Function,
+    cairo_run_config: Cairo1RunConfig,
+) -> Result<(CairoRunner, Vec<MaybeRelocatable>, Option<String>), Error> {
+    let (runner, return_values, serialized_output, _) = run_program(
+        sierra_program,
+        func,
+        cairo_run_config,
+        &mut LocalSyscallHandler::default(),
+        false,
+        None,
+    )?;
+    Ok((runner, return_values, serialized_output))
+}
+
+/// Runs a Cairo 1 program like [cairo_run_program], charging its gas costs, and reports the gas consumed by it.
+/// The run starts with `initial_gas` gas if set in the config, or [DEFAULT_INITIAL_GAS] otherwise.
+/// The trace is always enabled, as the gas consumed by each function is taken from it
+pub fn cairo_run_program_with_gas_report(
+    sierra_program: &SierraProgram,
+    cairo_run_config: Cairo1RunConfig,
+) -> Result<
+    (
+        CairoRunner,
+        Vec<MaybeRelocatable>,
+        Option<String>,
+        GasReport,
+    ),
+    Error,
+> {
+    let main_func = find_function(sierra_program, "::main")?;
+    let (runner, return_values, serialized_output, gas_report) = run_program(
+        sierra_program,
+        main_func,
+        cairo_run_config,
+        &mut LocalSyscallHandler::default(),
+        true,
+        None,
+    )?;
+    Ok((
+        runner,
+        return_values,
+        serialized_output,
+        gas_report.ok_or(Error::FailedToExtractReturnValues)?,
+    ))
+}
+
+/// Runs the program until the given pc through a [Debugger]
+type DebuggerRun<'a> = &'a mut dyn FnMut(
+    &mut CairoRunner,
+    Relocatable,
+    &mut dyn HintProcessor,
+) -> Result<(), VirtualMachineError>;
+
+#[allow(clippy::type_complexity)]
+fn run_program(
+    sierra_program: &SierraProgram,
+    main_func: &Function,
+    mut cairo_run_config: Cairo1RunConfig,
+    syscall_handler: &mut dyn SyscallHandler,
+    with_gas_report: bool,
+    debugger: Option<DebuggerRun>,
+) -> Result<
+    (
+        CairoRunner,
+        Vec<MaybeRelocatable>,
+        Option<String>,
+        Option<GasReport>,
+    ),
+    Error,
+> {
+    let charge_gas = cairo_run_config.initial_gas.is_some() || with_gas_report;
+    let initial_gas = cairo_run_config.initial_gas.unwrap_or(DEFAULT_INITIAL_GAS);
+    if with_gas_report {
+        cairo_run_config.trace_enabled = true;
+    }
+
+    let metadata = if charge_gas {
+        calc_metadata(sierra_program, MetadataComputationConfig::default())?
+    } else {
+        calc_metadata_ap_change_only(sierra_program).map_err(|_| VirtualMachineError::Unexpected)?
+    };
     let sierra_program_registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(sierra_program)?;
     let type_sizes =
         get_type_size_map(sierra_program, &sierra_program_registry).unwrap_or_default();
+    let types = SierraTypes::new(&sierra_program_registry, &type_sizes);
     let config = SierraToCasmConfig {
-        gas_usage_check: false,
+        gas_usage_check: charge_gas,
         max_bytecode_size: usize::MAX,
     };
     let casm_program =
         cairo_lang_sierra_to_casm::compiler::compile(sierra_program, &metadata, config)?;
 
-    let main_func = find_function(sierra_program, "::main")?;
-
-    let initial_gas = 9999999999999_usize;
-
     // Fetch return type data
     let return_type_id = match main_func.signature.ret_types.last() {
         // We need to check if the last return type is indeed the function's return value and not an implicit return value
@@ -155,15 +320,16 @@ pub fn cairo_run_program(
     };
 
     if cairo_run_config.copy_to_output()
-        && !check_only_array_felt_input_type(
+        && !check_serializable_input_types(
             &main_func.signature.param_types,
             &sierra_program_registry,
+            &types,
         )
     {
         return Err(Error::IlegalInputValue);
     };
     if cairo_run_config.copy_to_output()
-        && !check_only_array_felt_return_type(return_type_id, &sierra_program_registry)
+        && !check_serializable_return_type(return_type_id, &sierra_program_registry, &types)
     {
         return Err(Error::IlegalReturnValue);
     };
@@ -196,10 +362,13 @@ pub fn cairo_run_program(
 
     let (processor_hints, program_hints) = build_hints_vec(instructions.clone());
 
-    let mut hint_processor = Cairo1HintProcessor::new(
-        &processor_hints,
-        RunResources::default(),
-        cairo_run_config.copy_to_output(),
+    let mut hint_processor = StarknetHintProcessor::new(
+        Cairo1HintProcessor::new(
+            &processor_hints,
+            RunResources::default(),
+            cairo_run_config.copy_to_output(),
+        ),
+        syscall_handler,
     );
 
     let data: Vec<MaybeRelocatable> = instructions
@@ -208,6 +377,24 @@ pub fn cairo_run_program(
         .map(MaybeRelocatable::from)
         .collect();
 
+    // Function identifiers are only needed to set breakpoints on functions when debugging, and to report function coverage
+    let identifiers = if debugger.is_some() || cairo_run_config.code_locations.is_some() {
+        function_identifiers(
+            sierra_program,
+            &casm_program,
+            entry_code.current_code_offset,
+        )
+    } else {
+        HashMap::new()
+    };
+    let instruction_locations = cairo_run_config.code_locations.map(|code_locations| {
+        instruction_locations(
+            &casm_program,
+            code_locations,
+            entry_code.current_code_offset,
+        )
+    });
+
     let program = if cairo_run_config.proof_mode {
         Program::new_for_proof(
             builtins.clone(),
@@ -220,9 +407,9 @@ pub fn cairo_run_program(
             ReferenceManager {
                 references: Vec::new(),
             },
-            HashMap::new(),
+            identifiers,
             vec![],
-            None,
+            instruction_locations,
         )?
     } else {
         Program::new(
@@ -233,9 +420,9 @@ pub fn cairo_run_program(
             ReferenceManager {
                 references: Vec::new(),
             },
-            HashMap::new(),
+            identifiers,
             vec![],
-            None,
+            instruction_locations,
         )?
     };
 
@@ -248,14 +435,26 @@ pub fn cairo_run_program(
     let mut runner = CairoRunner::new_v2(
         &program,
         cairo_run_config.layout,
+        cairo_run_config.dynamic_layout_params.clone(),
         runner_mode,
         cairo_run_config.trace_enabled,
     )?;
     let end = runner.initialize(cairo_run_config.proof_mode)?;
-    load_arguments(&mut runner, &cairo_run_config, main_func, initial_gas)?;
+    load_arguments(
+        &mut runner,
+        &cairo_run_config,
+        main_func,
+        initial_gas,
+        &sierra_program_registry,
+        &type_sizes,
+    )?;
 
     // Run it until the end / infinite loop in proof_mode
-    runner.run_until_pc(end, &mut hint_processor)?;
+    if let Some(debugger) = debugger {
+        debugger(&mut runner, end, &mut hint_processor)?;
+    } else {
+        runner.run_until_pc(end, &mut hint_processor)?;
+    }
     if cairo_run_config.proof_mode {
         runner.run_for_steps(1, &mut hint_processor)?;
     }
@@ -264,6 +463,9 @@ pub fn cairo_run_program(
 
     let result_inner_type_size =
         result_inner_type_size(return_type_id, &sierra_program_registry, &type_sizes);
+    // The type of the return values after removing the PanicResult enum (if present)
+    let return_values_type_id =
+        result_inner_type(return_type_id, &sierra_program_registry).or(return_type_id);
     // Fetch return values
     let return_values = fetch_return_values(
         return_type_size,
@@ -271,14 +473,37 @@ pub fn cairo_run_program(
         &runner.vm,
         builtin_count,
         cairo_run_config.copy_to_output(),
-    )?;
+        return_values_type_id.is_some_and(|ty| types.array_element(ty).is_some()),
+    )
+    .map_err(|err| match err {
+        Error::RunPanic(panic_data) if charge_gas && panic_data == out_of_gas_panic_data() => {
+            Error::OutOfGas(initial_gas)
+        }
+        err => err,
+    })?;
 
     let serialized_output = if cairo_run_config.serialize_output {
-        if cairo_run_config.copy_to_output() {
+        if cairo_run_config.json_output {
+            let value = match return_values_type_id {
+                Some(ty) => {
+                    let arrays = if cairo_run_config.copy_to_output() {
+                        // The return value was copied into the output segment
+                        ArrayLayout::Inlined
+                    } else {
+                        ArrayLayout::Memory(&runner.vm)
+                    };
+                    types.read_value(arrays, ty, &mut return_values.iter())?
+                }
+                None => serde_json::Value::Null,
+            };
+            Some(value.to_string())
+        } else if cairo_run_config.copy_to_output() {
             // The return value is already serialized, so we can just print the array values
             let mut output_string = String::from("[");
             // Skip array_len
-            for elem in return_values[1..].iter() {
+            let skipped_len =
+                return_values_type_id.is_some_and(|ty| types.array_element(ty).is_some()) as usize;
+            for elem in return_values[skipped_len..].iter() {
                 maybe_add_whitespace(&mut output_string);
                 output_string.push_str(&elem.to_string());
             }
@@ -331,7 +556,22 @@ pub fn cairo_run_program(
 
     runner.relocate(true)?;
 
-    Ok((runner, return_values, serialized_output))
+    let gas_report = if with_gas_report {
+        Some(gas_report(
+            &runner,
+            sierra_program,
+            &sierra_program_registry,
+            &type_sizes,
+            &casm_program,
+            entry_code.current_code_offset,
+            main_func,
+            initial_gas,
+        )?)
+    } else {
+        None
+    };
+
+    Ok((runner, return_values, serialized_output, gas_report))
 }
 
 #[allow(clippy::type_complexity)]
@@ -453,6 +693,8 @@ fn load_arguments(
     cairo_run_config: &Cairo1RunConfig,
     main_func: &Function,
     initial_gas: usize,
+    sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    type_sizes: &UnorderedHashMap<ConcreteTypeId, i16>,
 ) -> Result<(), Error> {
     let got_gas_builtin = main_func
         .signature
@@ -492,7 +734,10 @@ fn load_arguments(
         )?;
         ap_offset += 1;
     }
-    for arg in cairo_run_config.args {
+    let types = SierraTypes::new(sierra_program_registry, type_sizes);
+    let param_types = user_param_types(&main_func.signature.param_types, sierra_program_registry);
+    let arg_types = json_arg_types(cairo_run_config.args, &param_types, &types)?;
+    for (arg, ty) in cairo_run_config.args.iter().zip(arg_types) {
         match arg {
             FuncArg::Array(args) => {
                 let array_start = runner.vm.add_memory_segment();
@@ -518,12 +763,94 @@ fn load_arguments(
                 )?;
                 ap_offset += 1;
             }
+            FuncArg::Json(value) => {
+                let ty = ty.expect("JSON arguments are matched with a parameter");
+                let mut cells = Vec::new();
+                types.write_value(&mut runner.vm, ty, value, &mut cells)?;
+                for cell in cells {
+                    runner.vm.insert_value(
+                        (runner.vm.get_ap() + ap_offset).map_err(VirtualMachineError::Math)?,
+                        cell,
+                    )?;
+                    ap_offset += 1;
+                }
+            }
         }
     }
 
     Ok(())
 }
 
+// Returns the types of the parameters of main that aren't implicit arguments (aka builtins, gas, or system)
+pub(crate) fn user_param_types<'a>(
+    params: &'a [ConcreteTypeId],
+    sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+) -> Vec<&'a ConcreteTypeId> {
+    params
+        .iter()
+        .filter(|ty| {
+            get_info(sierra_program_registry, ty)
+                .is_some_and(|info| !is_implicit_generic_id(&info.long_id.generic_id))
+        })
+        .collect()
+}
+
+// Matches each argument given as JSON with the parameter starting at the memory cell where the argument is loaded,
+// returning the type of each argument (None for felts and arrays of felts)
+fn json_arg_types<'a>(
+    args: &[FuncArg],
+    param_types: &[&'a ConcreteTypeId],
+    types: &SierraTypes,
+) -> Result<Vec<Option<&'a ConcreteTypeId>>, Error> {
+    let mut arg_types = Vec::new();
+    // Parameter where the next argument is loaded, and amount of its cells taken by previous arguments
+    let (mut param_index, mut param_offset) = (0, 0);
+    for (arg_index, arg) in args.iter().enumerate() {
+        match arg {
+            FuncArg::Json(_) => {
+                let ty = param_types
+                    .get(param_index)
+                    .filter(|_| param_offset == 0)
+                    .ok_or(Error::ArgumentUnaligned {
+                        param_index,
+                        arg_index,
+                    })?;
+                arg_types.push(Some(*ty));
+                param_index += 1;
+            }
+            FuncArg::Single(_) | FuncArg::Array(_) => {
+                arg_types.push(None);
+                param_offset += arg_size(arg, None, types)?;
+                // Move on to the next parameter once the current one is filled
+                while let Some(ty) = param_types.get(param_index) {
+                    let size = types.size(ty)?;
+                    if param_offset == 0 || param_offset < size {
+                        break;
+                    }
+                    param_offset -= size;
+                    param_index += 1;
+                }
+            }
+        }
+    }
+    Ok(arg_types)
+}
+
+// Returns the amount of memory cells taken by an argument, given its type if it's a JSON argument
+fn arg_size(
+    arg: &FuncArg,
+    ty: Option<&ConcreteTypeId>,
+    types: &SierraTypes,
+) -> Result<usize, Error> {
+    match ty {
+        Some(ty) => types.size(ty),
+        None => Ok(match arg {
+            FuncArg::Array(_) => 2,
+            _ => 1,
+        }),
+    }
+}
+
 /// Returns the instructions to add to the beginning of the code to successfully call the main
 /// function, as well as the builtins required to execute the program.
 fn create_entry_code(
@@ -534,7 +861,9 @@ fn create_entry_code(
     config: &Cairo1RunConfig,
 ) -> Result<(CasmContext, Vec<BuiltinName>), Error> {
     let copy_to_output_builtin = config.copy_to_output();
+    let types = SierraTypes::new(sierra_program_registry, type_sizes);
     let signature = &func.signature;
+    let param_types = user_param_types(&signature.param_types, sierra_program_registry);
     let got_segment_arena = signature.param_types.iter().any(|ty| {
         get_info(sierra_program_registry, ty)
             .map(|x| x.long_id.generic_id == SegmentArenaType::ID)
@@ -618,11 +947,10 @@ fn create_entry_code(
     let actual_args_size = config
         .args
         .iter()
-        .map(|arg| match arg {
-            FuncArg::Single(_) => 1,
-            FuncArg::Array(_) => 2,
-        })
-        .sum::<i16>();
+        .zip(json_arg_types(config.args, &param_types, &types)?)
+        .map(|(arg, ty)| arg_size(arg, ty, &types))
+        .sum::<Result<usize, Error>>()?
+        .into_or_panic::<i16>();
     if expected_arguments_size != actual_args_size {
         return Err(Error::ArgumentsSizeMismatch {
             expected: expected_arguments_size,
@@ -671,97 +999,85 @@ fn create_entry_code(
             casm_build_extend!(ctx, assert local = var;);
         }
         // Serialize return values into output segment
-        let output_ptr = output_ptr.unwrap();
+        let mut output_ptr = output_ptr.unwrap();
         let outputs = (1..(return_type_size + 1))
             .rev()
             .map(|i| ctx.add_var(CellExpression::Deref(deref!([ap - i]))))
             .collect_vec();
-        let (array_start_ptr, array_end_ptr) = if is_panic_result(signature.ret_types.last()) {
+        // Remove the PanicResult wrapper (if present)
+        let (panic_flag, return_values) = if is_panic_result(return_type_id) {
             // Write panic flag value
             let panic_flag = outputs[0];
             casm_build_extend! {ctx,
                 assert panic_flag = *(output_ptr++);
             };
-            // If the run did panic, these will point to the panic data
-            (outputs[1], outputs[2])
+            (Some(panic_flag), &outputs[1..])
         } else {
-            (outputs[0], outputs[1])
-        };
-        casm_build_extend! {ctx,
-            // Calculate size of array and write it into the output segment
-            tempvar array_size = array_end_ptr - array_start_ptr;
-            assert array_size = *(output_ptr++);
-            // Create loop variables
-            tempvar remaining_elements = array_size;
-            tempvar array_ptr = array_start_ptr;
-            tempvar write_ptr = output_ptr;
-            // Enter copying loop
-            rescope{remaining_elements = remaining_elements, array_ptr = array_ptr, write_ptr = write_ptr};
-            jump CopyOutputArray if remaining_elements != 0;
-            jump EndOutputCopy;
-
-            // Main Loop
-            CopyOutputArray:
-            #{steps = 0;}
-            // Write array value into output segment
-            tempvar val = *(array_ptr++);
-            assert val = *(write_ptr++);
-            const one = 1;
-            // Create loop variables
-            tempvar new_remaining_elements = remaining_elements - one;
-            tempvar new_array_ptr = array_ptr;
-            tempvar new_write_ptr = write_ptr;
-            // Continue the loop
-            rescope{remaining_elements = new_remaining_elements, array_ptr = new_array_ptr, write_ptr = new_write_ptr};
-            jump CopyOutputArray if remaining_elements != 0;
-
-            EndOutputCopy:
+            (None, &outputs[..])
         };
-        if !actual_args_size.is_zero() {
-            // Serialize the input values into the output segment
-            // We lost the output_ptr var after re-scoping, so we need to create it again
-            // The last instruction will write the last output ptr so we can find it in [ap - 1]
-            let output_ptr = ctx.add_var(CellExpression::Deref(deref!([ap - 1])));
-            // len(builtins - output) + len(builtins) + if segment_arena: segment_arena_ptr + info_ptr + 0 + (segment_arena_ptr + 3) + (gas_builtin)
-            let offset = (2 * builtins.len() - 1
-                + 4 * got_segment_arena as usize
-                + got_gas_builtin as usize) as i16;
-            let array_start_ptr = ctx.add_var(CellExpression::Deref(deref!([fp + offset])));
-            let array_end_ptr = ctx.add_var(CellExpression::Deref(deref!([fp + offset + 1])));
-            casm_build_extend! {ctx,
-                // Calculate size of array and write it into the output segment
-                tempvar array_size = array_end_ptr - array_start_ptr;
-                assert array_size = *(output_ptr++);
-                // Create loop variables
-                tempvar remaining_elements = array_size;
-                tempvar array_ptr = array_start_ptr;
-                tempvar write_ptr = output_ptr;
-                // Enter copying loop
-                rescope{remaining_elements = remaining_elements, array_ptr = array_ptr, write_ptr = write_ptr};
-                jump CopyInputArray if remaining_elements != 0;
-                jump EndInputCopy;
-
-                // Main Loop
-                CopyInputArray:
-                #{steps = 0;}
-                // Write array value into output segment
-                tempvar val = *(array_ptr++);
-                assert val = *(write_ptr++);
-                const one = 1;
-                // Create loop variables
-                tempvar new_remaining_elements = remaining_elements - one;
-                tempvar new_array_ptr = array_ptr;
-                tempvar new_write_ptr = write_ptr;
-                // Continue the loop
-                rescope{remaining_elements = new_remaining_elements, array_ptr = new_array_ptr, write_ptr = new_write_ptr};
-                jump CopyInputArray if remaining_elements != 0;
-
-                EndInputCopy:
-            };
+        let return_values_type_id = result_inner_type(return_type_id, sierra_program_registry)
+            .or(return_type_id)
+            .ok_or(Error::IlegalReturnValue)?;
+        if types.array_element(return_values_type_id).is_some() {
+            // If the run did panic, these will point to the panic data
+            output_ptr = copy_array_to_output(
+                &mut ctx,
+                return_values[0],
+                return_values[1],
+                output_ptr,
+                "Output",
+            );
+        } else {
+            // Values without pointers are copied as they are, from the Ok variant if the return value is a PanicResult
+            let size = types.size(return_values_type_id)?;
+            if let Some(panic_flag) = panic_flag {
+                casm_build_extend!(ctx, jump PanicOutput if panic_flag != 0;);
+            }
+            for value in &return_values[return_values.len() - size..] {
+                let value = *value;
+                casm_build_extend!(ctx, assert value = *(output_ptr++););
+            }
+            if panic_flag.is_some() {
+                // Copy the panic data, located at the end of the return values
+                casm_build_extend! {ctx,
+                    tempvar output_end = output_ptr;
+                    rescope{};
+                    jump EndReturnCopy;
+                    PanicOutput:
+                };
+                let panic_data = &return_values[return_values.len() - 2..];
+                copy_array_to_output(&mut ctx, panic_data[0], panic_data[1], output_ptr, "Panic");
+                casm_build_extend!(ctx, EndReturnCopy:);
+                // Both branches write the output pointer last, so we can find it in [ap - 1]
+                output_ptr = ctx.add_var(CellExpression::Deref(deref!([ap - 1])));
+            }
+        }
+        // Serialize the input values into the output segment
+        // len(builtins - output) + len(builtins) + if segment_arena: segment_arena_ptr + info_ptr + 0 + (segment_arena_ptr + 3) + (gas_builtin)
+        let mut offset = (2 * builtins.len() - 1
+            + 4 * got_segment_arena as usize
+            + got_gas_builtin as usize) as i16;
+        for (i, ty) in param_types.iter().enumerate() {
+            let size: i16 = types.size(ty)?.into_or_panic();
+            if types.array_element(ty).is_some() {
+                let array_start_ptr = ctx.add_var(CellExpression::Deref(deref!([fp + offset])));
+                let array_end_ptr = ctx.add_var(CellExpression::Deref(deref!([fp + offset + 1])));
+                output_ptr = copy_array_to_output(
+                    &mut ctx,
+                    array_start_ptr,
+                    array_end_ptr,
+                    output_ptr,
+                    &format!("Input{i}"),
+                );
+            } else {
+                for cell in offset..offset + size {
+                    let value = ctx.add_var(CellExpression::Deref(deref!([fp + cell])));
+                    casm_build_extend!(ctx, assert value = *(output_ptr++););
+                }
+            }
+            offset += size;
         }
         // After we are done writing into the output segment, we can write the final output_ptr into locals:
-        // The last instruction will write the final output ptr so we can find it in [ap - 1]
-        let output_ptr = ctx.add_var(CellExpression::Deref(deref!([ap - 1])));
         let local = ctx.add_var(CellExpression::Deref(deref!([fp])));
         casm_build_extend!(ctx, assert local = output_ptr;);
 
@@ -866,6 +1182,135 @@ fn create_entry_code(
     ))
 }
 
+/// Adds the instructions that copy the array `[array_start, array_end)` into the output segment, preceded by its length.
+/// Returns a variable holding the output pointer after the array.
+fn copy_array_to_output(
+    ctx: &mut CasmBuilder,
+    array_start_ptr: Var,
+    array_end_ptr: Var,
+    output_ptr: Var,
+    name: &str,
+) -> Var {
+    // The loop labels need to be unique within the entry code
+    let copy_label = format!("Copy{name}Array");
+    let end_label = format!("End{name}Copy");
+    casm_build_extend! {ctx,
+        // Calculate size of array and write it into the output segment
+        tempvar array_size = array_end_ptr - array_start_ptr;
+        assert array_size = *(output_ptr++);
+        // Create loop variables
+        tempvar remaining_elements = array_size;
+        tempvar array_ptr = array_start_ptr;
+        tempvar write_ptr = output_ptr;
+        // Enter copying loop
+        rescope{remaining_elements = remaining_elements, array_ptr = array_ptr, write_ptr = write_ptr};
+    };
+    ctx.jump_nz(remaining_elements, copy_label.clone());
+    ctx.jump(end_label.clone());
+
+    // Main Loop
+    ctx.label(copy_label.clone());
+    casm_build_extend! {ctx,
+        #{steps = 0;}
+        // Write array value into output segment
+        tempvar val = *(array_ptr++);
+        assert val = *(write_ptr++);
+        const one = 1;
+        // Create loop variables
+        tempvar new_remaining_elements = remaining_elements - one;
+        tempvar new_array_ptr = array_ptr;
+        tempvar new_write_ptr = write_ptr;
+        // Continue the loop
+        rescope{remaining_elements = new_remaining_elements, array_ptr = new_array_ptr, write_ptr = new_write_ptr};
+    };
+    ctx.jump_nz(remaining_elements, copy_label);
+
+    ctx.label(end_label);
+    // The last instruction wrote the output pointer, so we can find it in [ap - 1]
+    ctx.add_var(CellExpression::Deref(deref!([ap - 1])))
+}
+
+// Builds a function identifier for each sierra function, located at its offset within the program
+fn function_identifiers(
+    sierra_program: &SierraProgram,
+    casm_program: &CairoProgram,
+    code_offset: usize,
+) -> HashMap<String, Identifier> {
+    sierra_program
+        .funcs
+        .iter()
+        .filter_map(|func| {
+            let name = func.id.debug_name.as_ref()?.to_string();
+            let pc = code_offset
+                + casm_program.debug_info.sierra_statement_info[func.entry_point.0].start_offset;
+            let identifier = Identifier {
+                pc: Some(pc),
+                type_: Some("function".to_string()),
+                value: None,
+                full_name: Some(name.clone()),
+                members: None,
+                cairo_type: None,
+            };
+            Some((name, identifier))
+        })
+        .collect()
+}
+
+// Maps each instruction of the casm program to the first cairo source location of the sierra statement that generated it
+fn instruction_locations(
+    casm_program: &CairoProgram,
+    code_locations: &StatementsSourceCodeLocations,
+    code_offset: usize,
+) -> HashMap<usize, InstructionLocation> {
+    let instruction_offsets: Vec<usize> = casm_program
+        .instructions
+        .iter()
+        .scan(0, |offset, inst| {
+            let start = *offset;
+            *offset += inst.body.op_size();
+            Some(start)
+        })
+        .collect();
+    let mut instruction_locations = HashMap::new();
+    for (idx, statement) in casm_program
+        .debug_info
+        .sierra_statement_info
+        .iter()
+        .enumerate()
+    {
+        let Some((file, span)) = code_locations
+            .statements_to_code_location_map
+            .get(&StatementIdx(idx))
+            .and_then(|locations| locations.first())
+        else {
+            continue;
+        };
+        let location = InstructionLocation {
+            inst: Location {
+                end_line: span.end.line as u32 + 1,
+                end_col: span.end.col as u32 + 1,
+                input_file: InputFile {
+                    filename: file.0.clone(),
+                },
+                parent_location: None,
+                start_line: span.start.line as u32 + 1,
+                start_col: span.start.col as u32 + 1,
+            },
+            hints: vec![],
+            accessible_scopes: vec![],
+            flow_tracking_data: None,
+        };
+        let first = instruction_offsets.partition_point(|offset| *offset < statement.start_offset);
+        for offset in instruction_offsets[first..]
+            .iter()
+            .take_while(|offset| **offset < statement.end_offset)
+        {
+            instruction_locations.insert(code_offset + offset, location.clone());
+        }
+    }
+    instruction_locations
+}
+
 fn get_info<'a>(
     sierra_program_registry: &'a ProgramRegistry<CoreType, CoreLibfunc>,
     ty: &'a cairo_lang_sierra::ids::ConcreteTypeId,
@@ -910,31 +1355,17 @@ fn get_function_builtins(
     (builtins, builtin_offset)
 }
 
-// Checks that the program input (if present) is of type Array<Felt252>
-fn check_only_array_felt_input_type(
+// Checks that the program inputs (if present) can be copied into the output segment:
+// values without pointers (felts, integers, and structs or enums of them), or arrays of them
+fn check_serializable_input_types(
     params: &[ConcreteTypeId],
     sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    types: &SierraTypes,
 ) -> bool {
     // Filter implicit arguments (builtins, gas)
-    let arg_types = params
-        .iter()
-        .filter(|ty| {
-            let info = get_info(sierra_program_registry, ty).unwrap();
-            let generic_ty = &info.long_id.generic_id;
-            !is_implicit_generic_id(generic_ty)
-        })
-        .collect_vec();
-    if arg_types.is_empty() {
-        // No inputs
-        true
-    } else if arg_types.len() == 1 {
-        arg_types[0]
-            .debug_name
-            .as_ref()
-            .is_some_and(|name| name == "Array<felt252>")
-    } else {
-        false
-    }
+    user_param_types(params, sierra_program_registry)
+        .into_iter()
+        .all(|ty| types.is_serializable(ty))
 }
 
 // Returns true if the generic id corresponds to an implicit argument (aka a builtin, gas, or system type)
@@ -952,33 +1383,17 @@ fn is_implicit_generic_id(generic_ty: &GenericTypeId) -> bool {
     ]
     .contains(generic_ty)
 }
-// Checks that the return type is either an Array<Felt252> or a PanicResult<Array<Felt252>> type
-fn check_only_array_felt_return_type(
+// Checks that the return type (or T in PanicResult<T>) can be copied into the output segment:
+// a value without pointers (felt, integer, or struct or enum of them), or an array of them
+fn check_serializable_return_type(
     return_type_id: Option<&ConcreteTypeId>,
     sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    types: &SierraTypes,
 ) -> bool {
-    if return_type_id.is_none() {
-        return false;
-    };
     // Unwrap PanicResult (if appicable)
-    let return_type =
-        if let Some(return_type) = result_inner_type(return_type_id, sierra_program_registry) {
-            return_type
-        } else {
-            return_type_id.unwrap()
-        };
-    let return_type = sierra_program_registry.get_type(return_type).unwrap();
-    // Check that the resulting type is an Array<Felt252>
-    match return_type {
-        cairo_lang_sierra::extensions::core::CoreTypeConcrete::Array(info) => {
-            let inner_ty = sierra_program_registry.get_type(&info.ty).unwrap();
-            matches!(
-                inner_ty,
-                cairo_lang_sierra::extensions::core::CoreTypeConcrete::Felt252(_)
-            )
-        }
-        _ => false,
-    }
+    result_inner_type(return_type_id, sierra_program_registry)
+        .or(return_type_id)
+        .is_some_and(|return_type| types.is_serializable(return_type))
 }
 
 fn is_panic_result(return_type_id: Option<&ConcreteTypeId>) -> bool {
@@ -1038,10 +1453,13 @@ fn fetch_return_values(
     vm: &VirtualMachine,
     builtin_count: i16,
     fetch_from_output: bool,
+    array_return: bool,
 ) -> Result<Vec<MaybeRelocatable>, Error> {
     if fetch_from_output {
         // In this case we will find the serialized return value in the format:
         // [*panic_flag, array_len, array[0], array[1],..., array[array_len-1]]
+        // Or, if the return value (or panic data) is not an array, its memory cells as they are:
+        // [*panic_flag, value[0], value[1], ..., value[size-1]]
         // *: If the return value is a PanicResult
 
         // Output Builtin will always be on segment 2
@@ -1059,7 +1477,11 @@ fn fetch_return_values(
             )
         };
         // Take only the output (as the output segment will also contain the input)
-        let output_len = return_values[0].get_int().unwrap().to_usize().unwrap() + 1;
+        let output_len = if array_return || panic_flag {
+            return_values[0].get_int().unwrap().to_usize().unwrap() + 1
+        } else {
+            result_inner_type_size.unwrap_or(return_type_size) as usize
+        };
         let return_values = &return_values[0..output_len];
         // Return Ok or Err based on panic_flag
         if panic_flag {
@@ -1558,6 +1980,180 @@ mod tests {
             .program
     }
 
+    fn parse_sierra(code: &str) -> SierraProgram {
+        cairo_lang_sierra::ProgramParser::new().parse(code).unwrap()
+    }
+
+    // main(a: (u8, Span<felt252>), b: Option<u256>, c: Color, d: Result<felt252, felt252>) -> ((u8, Span<felt252>), Option<u256>, Color, Result<felt252, felt252>)
+    // Where Color is an enum with 3 variants, the second one holding a felt252
+    const TYPED_ARGS_PROGRAM: &str = "
+        type felt252 = felt252;
+        type u8 = u8;
+        type u128 = u128;
+        type u256 = Struct<ut@core::integer::u256, u128, u128>;
+        type Unit = Struct<ut@Tuple>;
+        type OptionU256 = Enum<ut@core::option::Option::<core::integer::u256>, u256, Unit>;
+        type ArrayFelt = Array<felt252>;
+        type SnapshotArrayFelt = Snapshot<ArrayFelt>;
+        type SpanFelt = Struct<ut@core::array::Span::<core::felt252>, SnapshotArrayFelt>;
+        type Pair = Struct<ut@Tuple, u8, SpanFelt>;
+        type Color = Enum<ut@test::Color, Unit, felt252, Unit>;
+        type ResultFelt = Enum<ut@core::result::Result::<core::felt252, core::felt252>, felt252, felt252>;
+        type Output = Struct<ut@Tuple, Pair, OptionU256, Color, ResultFelt>;
+
+        libfunc construct_output = struct_construct<Output>;
+        libfunc store_temp_output = store_temp<Output>;
+
+        construct_output(a, b, c, d) -> (r);
+        store_temp_output(r) -> (r);
+        return(r);
+
+        test::main@0(a: Pair, b: OptionU256, c: Color, d: ResultFelt) -> (Output);
+    ";
+
+    // main(a: u256, b: Array<felt252>, c: Color) -> PanicResult<((u256, Color),)>
+    // Panics with [7] if the color is the first variant
+    const TYPED_ARGS_PROOF_MODE_PROGRAM: &str = "
+        type felt252 = felt252;
+        type u128 = u128;
+        type u256 = Struct<ut@core::integer::u256, u128, u128>;
+        type Unit = Struct<ut@Tuple>;
+        type ArrayFelt = Array<felt252>;
+        type Color = Enum<ut@test::Color, Unit, felt252, Unit>;
+        type Output = Struct<ut@Tuple, u256, Color>;
+        type Inner = Struct<ut@Tuple, Output>;
+        type Panic = Struct<ut@core::panics::Panic>;
+        type PanicData = Struct<ut@Tuple, Panic, ArrayFelt>;
+        type core::panics::PanicResult::<(test::Output,)> = Enum<ut@core::panics::PanicResult::<(test::Output,)>, Inner, PanicData>;
+
+        libfunc match_color = enum_match<Color>;
+        libfunc init_first_color = enum_init<Color, 0>;
+        libfunc init_second_color = enum_init<Color, 1>;
+        libfunc init_third_color = enum_init<Color, 2>;
+        libfunc branch_align = branch_align;
+        libfunc drop_unit = drop<Unit>;
+        libfunc drop_u256 = drop<u256>;
+        libfunc drop_array = drop<ArrayFelt>;
+        libfunc construct_unit = struct_construct<Unit>;
+        libfunc construct_output = struct_construct<Output>;
+        libfunc construct_inner = struct_construct<Inner>;
+        libfunc construct_panic = struct_construct<Panic>;
+        libfunc construct_panic_data = struct_construct<PanicData>;
+        libfunc array_new = array_new<felt252>;
+        libfunc array_append = array_append<felt252>;
+        libfunc felt252_const_7 = felt252_const<7>;
+        libfunc store_temp_felt = store_temp<felt252>;
+        libfunc init_ok = enum_init<core::panics::PanicResult::<(test::Output,)>, 0>;
+        libfunc init_err = enum_init<core::panics::PanicResult::<(test::Output,)>, 1>;
+        libfunc store_temp_result = store_temp<core::panics::PanicResult::<(test::Output,)>>;
+
+        drop_array(b) -> ();
+        match_color(c) { fallthrough(unit) Second(x) Third(unit) };
+        branch_align() -> ();
+        drop_unit(unit) -> ();
+        drop_u256(a) -> ();
+        array_new() -> (data);
+        felt252_const_7() -> (seven);
+        store_temp_felt(seven) -> (seven);
+        array_append(data, seven) -> (data);
+        construct_panic() -> (panic);
+        construct_panic_data(panic, data) -> (err);
+        init_err(err) -> (r);
+        store_temp_result(r) -> (r);
+        return(r);
+        Second:
+        branch_align() -> ();
+        init_second_color(x) -> (c);
+        construct_output(a, c) -> (output);
+        construct_inner(output) -> (inner);
+        init_ok(inner) -> (r);
+        store_temp_result(r) -> (r);
+        return(r);
+        Third:
+        branch_align() -> ();
+        init_third_color(unit) -> (c);
+        construct_output(a, c) -> (output);
+        construct_inner(output) -> (inner);
+        init_ok(inner) -> (r);
+        store_temp_result(r) -> (r);
+        return(r);
+
+        test::main@0(a: u256, b: ArrayFelt, c: Color) -> (core::panics::PanicResult::<(test::Output,)>);
+    ";
+
+    // main(n: felt252) -> felt252, recursively counting down from n to 0 and withdrawing gas on each call
+    const GAS_PROGRAM: &str = "
+        type RangeCheck = RangeCheck;
+        type GasBuiltin = GasBuiltin;
+        type felt252 = felt252;
+        type NonZeroFelt = NonZero<felt252>;
+        type ArrayFelt = Array<felt252>;
+        type Inner = Struct<ut@Tuple, felt252>;
+        type Panic = Struct<ut@core::panics::Panic>;
+        type PanicData = Struct<ut@Tuple, Panic, ArrayFelt>;
+        type core::panics::PanicResult::<(core::felt252,)> = Enum<ut@core::panics::PanicResult::<(core::felt252,)>, Inner, PanicData>;
+
+        libfunc withdraw_gas = withdraw_gas;
+        libfunc branch_align = branch_align;
+        libfunc felt252_is_zero = felt252_is_zero;
+        libfunc unwrap_non_zero = unwrap_non_zero<felt252>;
+        libfunc drop_felt = drop<felt252>;
+        libfunc felt252_const_0 = felt252_const<0>;
+        libfunc felt252_const_out_of_gas = felt252_const<375233589013918064796019>;
+        libfunc felt252_sub_1 = felt252_sub_const<1>;
+        libfunc store_temp_rc = store_temp<RangeCheck>;
+        libfunc store_temp_gas = store_temp<GasBuiltin>;
+        libfunc store_temp_felt = store_temp<felt252>;
+        libfunc store_temp_result = store_temp<core::panics::PanicResult::<(core::felt252,)>>;
+        libfunc array_new = array_new<felt252>;
+        libfunc array_append = array_append<felt252>;
+        libfunc construct_inner = struct_construct<Inner>;
+        libfunc construct_panic = struct_construct<Panic>;
+        libfunc construct_panic_data = struct_construct<PanicData>;
+        libfunc init_ok = enum_init<core::panics::PanicResult::<(core::felt252,)>, 0>;
+        libfunc init_err = enum_init<core::panics::PanicResult::<(core::felt252,)>, 1>;
+        libfunc call_main = function_call<user@test::main>;
+        libfunc disable_ap_tracking = disable_ap_tracking;
+
+        disable_ap_tracking() -> ();
+        withdraw_gas(rc, gas) { fallthrough(rc, gas) OutOfGas(rc, gas) };
+        branch_align() -> ();
+        felt252_is_zero(n) { fallthrough() NonZero(n) };
+        branch_align() -> ();
+        felt252_const_0() -> (r);
+        construct_inner(r) -> (r);
+        init_ok(r) -> (r);
+        store_temp_rc(rc) -> (rc);
+        store_temp_gas(gas) -> (gas);
+        store_temp_result(r) -> (r);
+        return(rc, gas, r);
+        NonZero:
+        branch_align() -> ();
+        unwrap_non_zero(n) -> (n);
+        felt252_sub_1(n) -> (n);
+        store_temp_rc(rc) -> (rc);
+        store_temp_gas(gas) -> (gas);
+        store_temp_felt(n) -> (n);
+        call_main(rc, gas, n) -> (rc, gas, r);
+        return(rc, gas, r);
+        OutOfGas:
+        branch_align() -> ();
+        drop_felt(n) -> ();
+        array_new() -> (data);
+        felt252_const_out_of_gas() -> (msg);
+        store_temp_felt(msg) -> (msg);
+        array_append(data, msg) -> (data);
+        construct_panic() -> (panic);
+        construct_panic_data(panic, data) -> (err);
+        init_err(err) -> (r);
+        store_temp_rc(rc) -> (rc);
+        store_temp_gas(gas) -> (gas);
+        store_temp_result(r) -> (r);
+        return(rc, gas, r);
+
+        test::main@0(rc: RangeCheck, gas: GasBuiltin, n: felt252) -> (RangeCheck, GasBuiltin, core::panics::PanicResult::<(core::felt252,)>);
+    ";
+
     fn main_hash_panic_result(sierra_program: &SierraProgram) -> bool {
         let main_func = find_function(sierra_program, "::main").unwrap();
         main_func
@@ -1728,4 +2324,314 @@ mod tests {
             .collect_vec();
         assert_eq!(expected_output_segment, output_segment);
     }
+
+    #[test]
+    fn run_with_json_args_and_output() {
+        let sierra_program = parse_sierra(TYPED_ARGS_PROGRAM);
+        let args = serde_json::json!([
+            [7, [1, "2", "0x3"]],
+            "340282366920938463463374607431768211456",
+            {"variant": 1, "value": -1},
+            {"Err": 5}
+        ]);
+        let args = args
+            .as_array()
+            .unwrap()
+            .iter()
+            .cloned()
+            .map(FuncArg::Json)
+            .collect_vec();
+        let cairo_run_config = Cairo1RunConfig {
+            args: &args,
+            serialize_output: true,
+            json_output: true,
+            ..Default::default()
+        };
+        let (_, return_values, serialized_output) =
+            cairo_run_program(&sierra_program, cairo_run_config).unwrap();
+        // u8, span (2), option tag + u256 (2), color tag + felt252, result tag + felt252
+        assert_eq!(return_values.len(), 10);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&serialized_output.unwrap()).unwrap(),
+            serde_json::json!([
+                [7, [1, 2, 3]],
+                "340282366920938463463374607431768211456",
+                {"variant": 1, "value": "3618502788666131213697322783095070105623107215331596699973092056135872020480"},
+                {"Err": 5}
+            ])
+        );
+    }
+
+    #[test]
+    fn run_with_json_and_felt_args() {
+        let sierra_program = parse_sierra(TYPED_ARGS_PROGRAM);
+        // The u8 and the span of the first argument are given as felts
+        let args = [
+            FuncArg::Single(Felt252::from(7)),
+            FuncArg::Array(vec![Felt252::ONE]),
+            FuncArg::Json(serde_json::Value::Null),
+            FuncArg::Json(serde_json::json!({"variant": 2})),
+            FuncArg::Json(serde_json::json!({"Ok": 5})),
+        ];
+        let cairo_run_config = Cairo1RunConfig {
+            args: &args,
+            serialize_output: true,
+            json_output: true,
+            ..Default::default()
+        };
+        let (_, _, serialized_output) =
+            cairo_run_program(&sierra_program, cairo_run_config).unwrap();
+        assert_eq!(
+            serialized_output.unwrap(),
+            r#"[[7,[1]],null,{"variant":2},{"Ok":5}]"#
+        );
+    }
+
+    #[rstest]
+    #[case::unaligned(&[FuncArg::Single(Felt252::from(7)), FuncArg::Json(serde_json::json!([1]))], 0, 1)]
+    #[case::too_many(&[
+        FuncArg::Json(serde_json::json!([7, []])),
+        FuncArg::Json(serde_json::Value::Null),
+        FuncArg::Json(serde_json::json!({"variant": 0})),
+        FuncArg::Json(serde_json::json!({"Ok": 5})),
+        FuncArg::Json(serde_json::json!(1)),
+    ], 4, 4)]
+    fn run_with_unaligned_json_args(
+        #[case] args: &[FuncArg],
+        #[case] expected_param_index: usize,
+        #[case] expected_arg_index: usize,
+    ) {
+        let sierra_program = parse_sierra(TYPED_ARGS_PROGRAM);
+        let cairo_run_config = Cairo1RunConfig {
+            args,
+            ..Default::default()
+        };
+        assert_matches::assert_matches!(
+            cairo_run_program(&sierra_program, cairo_run_config).err(),
+            Some(Error::ArgumentUnaligned { param_index, arg_index })
+                if param_index == expected_param_index && arg_index == expected_arg_index
+        );
+    }
+
+    #[rstest]
+    #[case::u8_overflow(serde_json::json!([256, []]))]
+    #[case::not_a_struct(serde_json::json!(7))]
+    #[case::missing_member(serde_json::json!([7]))]
+    #[case::not_an_array(serde_json::json!([7, 1]))]
+    fn run_with_invalid_json_arg(#[case] arg: serde_json::Value) {
+        let sierra_program = parse_sierra(TYPED_ARGS_PROGRAM);
+        let args = [
+            FuncArg::Json(arg),
+            FuncArg::Json(serde_json::Value::Null),
+            FuncArg::Json(serde_json::json!({"variant": 0})),
+            FuncArg::Json(serde_json::json!({"Ok": 5})),
+        ];
+        let cairo_run_config = Cairo1RunConfig {
+            args: &args,
+            ..Default::default()
+        };
+        assert_matches::assert_matches!(
+            cairo_run_program(&sierra_program, cairo_run_config).err(),
+            Some(Error::InvalidArgumentValue { .. })
+        );
+    }
+
+    #[test]
+    fn run_with_json_args_unsupported_in_proof_mode() {
+        // The span is inside of a struct, so it can't be copied into the output segment
+        let sierra_program = parse_sierra(TYPED_ARGS_PROGRAM);
+        let cairo_run_config = Cairo1RunConfig {
+            proof_mode: true,
+            ..Default::default()
+        };
+        assert_matches::assert_matches!(
+            cairo_run_program(&sierra_program, cairo_run_config).err(),
+            Some(Error::IlegalInputValue)
+        );
+    }
+
+    #[rstest]
+    fn run_with_json_args_in_proof_mode(#[values(true, false)] proof_mode: bool) {
+        let sierra_program = parse_sierra(TYPED_ARGS_PROOF_MODE_PROGRAM);
+        let args = [
+            FuncArg::Json(serde_json::json!("0x100000000000000000000000000000002")),
+            FuncArg::Json(serde_json::json!([4, 5])),
+            FuncArg::Json(serde_json::json!({"variant": 1, "value": 6})),
+        ];
+        let cairo_run_config = Cairo1RunConfig {
+            args: &args,
+            proof_mode,
+            append_return_values: !proof_mode,
+            serialize_output: true,
+            json_output: true,
+            finalize_builtins: true,
+            layout: LayoutName::all_cairo,
+            ..Default::default()
+        };
+        let (runner, return_values, serialized_output) =
+            cairo_run_program(&sierra_program, cairo_run_config).unwrap();
+        let color_selector = Felt252::from(3);
+        let expected_output_segment = [
+            // panic_flag
+            Felt252::ZERO,
+            // output: u256 and color
+            Felt252::TWO,
+            Felt252::ONE,
+            color_selector,
+            Felt252::from(6),
+            // input: u256, array and color
+            Felt252::TWO,
+            Felt252::ONE,
+            Felt252::TWO,
+            Felt252::from(4),
+            Felt252::from(5),
+            color_selector,
+            Felt252::from(6),
+        ];
+        let output_segment = runner
+            .vm
+            .get_integer_range((2, 0).into(), runner.vm.get_segment_size(2).unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|f| f.into_owned())
+            .collect_vec();
+        assert_eq!(output_segment, expected_output_segment);
+        assert_eq!(
+            return_values,
+            expected_output_segment[1..5]
+                .iter()
+                .map(MaybeRelocatable::from)
+                .collect_vec()
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&serialized_output.unwrap()).unwrap(),
+            serde_json::json!(["340282366920938463463374607431768211458", {"variant": 1, "value": 6}])
+        );
+    }
+
+    #[rstest]
+    fn run_with_json_args_in_proof_mode_panic(#[values(true, false)] proof_mode: bool) {
+        let sierra_program = parse_sierra(TYPED_ARGS_PROOF_MODE_PROGRAM);
+        let args = [
+            FuncArg::Json(serde_json::json!(1)),
+            FuncArg::Json(serde_json::json!([])),
+            FuncArg::Json(serde_json::json!({"variant": 0})),
+        ];
+        let cairo_run_config = Cairo1RunConfig {
+            args: &args,
+            proof_mode,
+            append_return_values: !proof_mode,
+            layout: LayoutName::all_cairo,
+            ..Default::default()
+        };
+        assert_matches::assert_matches!(
+            cairo_run_program(&sierra_program, cairo_run_config).err(),
+            Some(Error::RunPanic(panic_data)) if panic_data == [Felt252::ONE, Felt252::from(7)]
+        );
+    }
+
+    #[test]
+    fn run_with_debugger_line_breakpoint() {
+        let compiler_config = CompilerConfig {
+            replace_ids: true,
+            ..CompilerConfig::default()
+        };
+        let mut db = RootDatabase::builder()
+            .detect_corelib()
+            .skip_auto_withdraw_gas()
+            .build()
+            .unwrap();
+        let main_crate_ids = setup_project(
+            &mut db,
+            Path::new("../cairo_programs/cairo-1-programs/fibonacci.cairo"),
+        )
+        .unwrap();
+        let sierra_program_with_dbg =
+            compile_prepared_db(&db, main_crate_ids, compiler_config).unwrap();
+        let code_locations = sierra_program_with_dbg
+            .debug_info
+            .statements_locations
+            .extract_statements_source_code_locations(&db);
+        let cairo_run_config = Cairo1RunConfig {
+            layout: LayoutName::all_cairo,
+            code_locations: Some(&code_locations),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let mut debugger = Debugger::new("b fibonacci.cairo:5\nc\nc\nc\n".as_bytes(), &mut output);
+        assert!(cairo_run_program_with_debugger(
+            &sierra_program_with_dbg.program,
+            cairo_run_config,
+            &mut debugger
+        )
+        .is_ok());
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("(cairo-debug) Breakpoint 1 set at fibonacci.cairo:5\n"));
+        // The execution stops at the call to fib in main before running to completion
+        assert!(output.contains(" in fibonacci::main at "));
+        assert!(output.contains("fibonacci.cairo:5\n"));
+        assert!(output.ends_with(" steps\n"));
+    }
+
+    #[test]
+    fn run_with_gas_report() {
+        let sierra_program = parse_sierra(GAS_PROGRAM);
+        let args = [FuncArg::Single(Felt252::from(3))];
+        let cairo_run_config = Cairo1RunConfig {
+            args: &args,
+            layout: LayoutName::all_cairo,
+            initial_gas: Some(100000),
+            ..Default::default()
+        };
+        let (_, return_values, _, gas_report) =
+            cairo_run_program_with_gas_report(&sierra_program, cairo_run_config).unwrap();
+        assert_eq!(return_values, vec![MaybeRelocatable::from(0)]);
+        assert_eq!(gas_report.initial_gas, 100000);
+        assert!(gas_report.consumed_gas > 0);
+        assert_eq!(
+            gas_report.remaining_gas + gas_report.consumed_gas,
+            gas_report.initial_gas
+        );
+        // main is called once by the entry code, and then recursively for n = 2, 1 and 0
+        assert_eq!(gas_report.functions.len(), 1);
+        let main_usage = &gas_report.functions[0];
+        assert_eq!(main_usage.name, "test::main");
+        assert_eq!(main_usage.calls, 4);
+        // Each call withdraws the same amount of gas
+        assert_eq!(main_usage.self_gas, gas_report.consumed_gas);
+        assert_eq!(main_usage.self_gas % 4, 0);
+        let call_gas = main_usage.self_gas / 4;
+        assert_eq!(main_usage.total_gas, (4 + 3 + 2 + 1) * call_gas);
+    }
+
+    #[test]
+    fn run_with_gas_report_default_initial_gas() {
+        let sierra_program = parse_sierra(GAS_PROGRAM);
+        let args = [FuncArg::Single(Felt252::from(3))];
+        let cairo_run_config = Cairo1RunConfig {
+            args: &args,
+            layout: LayoutName::all_cairo,
+            ..Default::default()
+        };
+        let (_, _, _, gas_report) =
+            cairo_run_program_with_gas_report(&sierra_program, cairo_run_config).unwrap();
+        assert_eq!(gas_report.initial_gas, DEFAULT_INITIAL_GAS);
+        assert!(gas_report.consumed_gas > 0);
+    }
+
+    #[test]
+    fn run_out_of_gas() {
+        let sierra_program = parse_sierra(GAS_PROGRAM);
+        let args = [FuncArg::Single(Felt252::from(100))];
+        let cairo_run_config = Cairo1RunConfig {
+            args: &args,
+            layout: LayoutName::all_cairo,
+            initial_gas: Some(1000),
+            ..Default::default()
+        };
+        assert_matches::assert_matches!(
+            cairo_run_program(&sierra_program, cairo_run_config).err(),
+            Some(Error::OutOfGas(1000))
+        );
+    }
 }
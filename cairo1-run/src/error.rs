@@ -5,8 +5,8 @@ use cairo_vm::{
     cairo_run::EncodeTraceError,
     types::errors::program_errors::ProgramError,
     vm::errors::{
-        memory_errors::MemoryError, runner_errors::RunnerError, trace_errors::TraceError,
-        vm_errors::VirtualMachineError,
+        cairo_run_errors::CairoRunError, memory_errors::MemoryError, runner_errors::RunnerError,
+        trace_errors::TraceError, vm_errors::VirtualMachineError,
     },
     Felt252,
 };
@@ -29,6 +29,8 @@ pub enum Error {
     #[error(transparent)]
     Runner(#[from] RunnerError),
     #[error(transparent)]
+    CairoRun(#[from] CairoRunError),
+    #[error(transparent)]
     ProgramRegistry(#[from] Box<ProgramRegistryError>),
     #[error(transparent)]
     Compilation(#[from] Box<CompilationError>),
@@ -42,6 +44,16 @@ pub enum Error {
     Memory(#[from] MemoryError),
     #[error("Program panicked with {0:?}")]
     RunPanic(Vec<Felt252>),
+    #[error("Program ran out of gas, with an initial gas of {0}")]
+    OutOfGas(usize),
+    #[error("{0} tests failed")]
+    TestsFailed(usize),
+    #[error("{0} fuzzed inputs failed")]
+    FuzzingFailed(usize),
+    #[error("Function {0} not found")]
+    FunctionNotFound(Box<str>),
+    #[error(transparent)]
+    Arbitrary(#[from] arbitrary::Error),
     #[error("Function signature has no return types")]
     NoRetTypesInSignature,
     #[error("No size for concrete type id: {0}")]
@@ -59,8 +71,14 @@ pub enum Error {
         param_index: usize,
         arg_index: usize,
     },
-    #[error("Only programs returning `Array<Felt252>` can be currently proven. Try serializing the final values before returning them")]
+    #[error("Only programs returning values without pointers (felts, integers, and structs or enums of them) or arrays of them can be currently proven. Try serializing the final values before returning them")]
     IlegalReturnValue,
-    #[error("Only programs with `Array<Felt252>` as an input can be currently proven. Try inputing the serialized version of the input and deserializing it on main")]
+    #[error("Only programs with inputs without pointers (felts, integers, and structs or enums of them) or arrays of them can be currently proven. Try inputing the serialized version of the input and deserializing it on main")]
     IlegalInputValue,
+    #[error("Invalid value {value} for an argument of type {ty}")]
+    InvalidArgumentValue { ty: ConcreteTypeId, value: Box<str> },
+    #[error("Type {0} is not supported as an argument or return value")]
+    UnsupportedType(ConcreteTypeId),
+    #[error("Unknown builtin: {0}")]
+    UnknownBuiltin(Box<str>),
 }
@@ -0,0 +1,375 @@
+//! Signature-driven fuzzing of Cairo 1 functions
+//!
+//! A [FunctionFuzzer] generates arguments for a function of a Sierra program from the types of its
+//! parameters, in the JSON representation of the [values](crate::values) module, and runs the
+//! function with them like [cairo_run_program](crate::cairo_run_program) runs `main`, charging
+//! its gas costs so that runaway loops end. Inputs are built from raw bytes, so they can either come
+//! from a coverage-guided fuzzer (see [FunctionFuzzer::run_input]), or from a seeded random
+//! generator (see [FunctionFuzzer::fuzz]).
+
+use crate::{
+    cairo_run::{cairo_run_function, user_param_types, Cairo1RunConfig, FuncArg},
+    error::Error,
+    gas::DEFAULT_INITIAL_GAS,
+    test_runner::format_panic_data,
+    values::SierraTypes,
+};
+use arbitrary::Unstructured;
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType},
+    ids::ConcreteTypeId,
+    program::{Function, Program as SierraProgram},
+    program_registry::ProgramRegistry,
+};
+use cairo_lang_sierra_type_size::get_type_size_map;
+use cairo_lang_utils::unordered_hash_map::UnorderedHashMap;
+use cairo_vm::{
+    types::layout_name::LayoutName,
+    vm::errors::{cairo_run_errors::CairoRunError, vm_errors::VirtualMachineError},
+};
+use rand::{rngs::SmallRng, RngCore, SeedableRng};
+use serde_json::Value;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct FuzzConfig {
+    pub layout: LayoutName,
+    /// Gas available to each run
+    pub initial_gas: usize,
+    /// Maximum number of elements of the arrays passed as arguments
+    pub max_array_len: usize,
+    /// Number of random bytes each input is generated from in [FunctionFuzzer::fuzz]
+    pub input_size: usize,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            layout: LayoutName::all_cairo,
+            initial_gas: DEFAULT_INITIAL_GAS,
+            max_array_len: 16,
+            input_size: 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The function panicked, e.g. because an `assert!` failed
+    Panic,
+    /// The function ran out of gas
+    OutOfGas,
+    /// A hint raised an error
+    Hint,
+    /// Any other error raised by the VM
+    Vm,
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FailureKind::Panic => "panic",
+            FailureKind::OutOfGas => "out of gas",
+            FailureKind::Hint => "hint error",
+            FailureKind::Vm => "vm error",
+        })
+    }
+}
+
+/// An input that made the fuzzed function fail
+#[derive(Debug, Clone)]
+pub struct FuzzFailure {
+    /// Bytes the input was generated from, which reproduce it when passed to [FunctionFuzzer::run_input]
+    pub data: Vec<u8>,
+    /// Arguments passed to the function, one per parameter
+    pub args: Vec<Value>,
+    pub kind: FailureKind,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FuzzReport {
+    pub runs: usize,
+    pub failures: Vec<FuzzFailure>,
+}
+
+impl fmt::Display for FuzzReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for failure in self.failures.iter() {
+            writeln!(
+                f,
+                "{} with args {}: {}",
+                failure.kind,
+                Value::Array(failure.args.clone()),
+                failure.error
+            )?;
+        }
+        writeln!(
+            f,
+            "fuzzing result: {} runs; {} failed",
+            self.runs,
+            self.failures.len()
+        )
+    }
+}
+
+pub struct FunctionFuzzer<'a> {
+    sierra_program: &'a SierraProgram,
+    function: &'a Function,
+    sierra_program_registry: ProgramRegistry<CoreType, CoreLibfunc>,
+    type_sizes: UnorderedHashMap<ConcreteTypeId, i16>,
+    param_types: Vec<ConcreteTypeId>,
+    config: FuzzConfig,
+}
+
+impl<'a> FunctionFuzzer<'a> {
+    /// Creates a fuzzer for the function of `sierra_program` named `function`, given either its
+    /// full path or the last segment of it
+    pub fn new(
+        sierra_program: &'a SierraProgram,
+        function: &str,
+        config: FuzzConfig,
+    ) -> Result<Self, Error> {
+        let suffix = format!("::{function}");
+        let func = sierra_program
+            .funcs
+            .iter()
+            .find(|func| {
+                func.id
+                    .debug_name
+                    .as_ref()
+                    .is_some_and(|name| name == function || name.ends_with(&suffix))
+            })
+            .ok_or_else(|| Error::FunctionNotFound(function.into()))?;
+        let sierra_program_registry =
+            ProgramRegistry::<CoreType, CoreLibfunc>::new(sierra_program)?;
+        let type_sizes =
+            get_type_size_map(sierra_program, &sierra_program_registry).unwrap_or_default();
+        let param_types = user_param_types(&func.signature.param_types, &sierra_program_registry)
+            .into_iter()
+            .cloned()
+            .collect();
+        let fuzzer = FunctionFuzzer {
+            sierra_program,
+            function: func,
+            sierra_program_registry,
+            type_sizes,
+            param_types,
+            config,
+        };
+        // Fail early on parameters of unsupported types
+        fuzzer.arbitrary_args(&mut Unstructured::new(&[]))?;
+        Ok(fuzzer)
+    }
+
+    /// Generates an input from `data` and runs the function with it.
+    /// Returns the failure it triggered, if any.
+    pub fn run_input(&self, data: &[u8]) -> Result<Option<FuzzFailure>, Error> {
+        let args = self.arbitrary_args(&mut Unstructured::new(data))?;
+        let func_args: Vec<_> = args.iter().cloned().map(FuncArg::Json).collect();
+        let config = Cairo1RunConfig {
+            args: &func_args,
+            layout: self.config.layout,
+            initial_gas: Some(self.config.initial_gas),
+            ..Default::default()
+        };
+        let error = match cairo_run_function(self.sierra_program, self.function, config) {
+            Ok(_) => return Ok(None),
+            Err(error) => error,
+        };
+        let kind = match &error {
+            Error::RunPanic(_) => FailureKind::Panic,
+            Error::OutOfGas(_) => FailureKind::OutOfGas,
+            Error::VirtualMachine(VirtualMachineError::Hint(_))
+            | Error::CairoRun(CairoRunError::VirtualMachine(VirtualMachineError::Hint(_))) => {
+                FailureKind::Hint
+            }
+            Error::VirtualMachine(_) | Error::CairoRun(_) | Error::Memory(_) => FailureKind::Vm,
+            // Errors raised before the function runs aren't caused by its input
+            _ => return Err(error),
+        };
+        let error = match error {
+            Error::RunPanic(panic_data) => {
+                format!("panicked with {}", format_panic_data(&panic_data))
+            }
+            error => error.to_string(),
+        };
+        Ok(Some(FuzzFailure {
+            data: data.to_vec(),
+            args,
+            kind,
+            error,
+        }))
+    }
+
+    /// Runs the function with `iterations` random inputs, generated from `seed`
+    pub fn fuzz(&self, iterations: usize, seed: u64) -> Result<FuzzReport, Error> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut data = vec![0; self.config.input_size];
+        let mut report = FuzzReport::default();
+        for _ in 0..iterations {
+            rng.fill_bytes(&mut data);
+            report.failures.extend(self.run_input(&data)?);
+            report.runs += 1;
+        }
+        Ok(report)
+    }
+
+    fn arbitrary_args(&self, u: &mut Unstructured) -> Result<Vec<Value>, Error> {
+        let types = SierraTypes::new(&self.sierra_program_registry, &self.type_sizes);
+        self.param_types
+            .iter()
+            .map(|ty| types.arbitrary_value(u, ty, self.config.max_array_len, 0))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    const FUZZED_FUNCTIONS_PROGRAM: &str = "
+        type felt252 = felt252;
+        type u8 = u8;
+        type u16 = u16;
+        type NonZeroFelt252 = NonZero<felt252>;
+        type Unit = Struct<ut@Tuple>;
+        type ArrayFelt = Array<felt252>;
+        type core::option::Option::<core::integer::u16> = Enum<ut@core::option::Option::<core::integer::u16>, u16, Unit>;
+        type Inner = Struct<ut@Tuple, Unit>;
+        type Panic = Struct<ut@core::panics::Panic>;
+        type PanicData = Struct<ut@Tuple, Panic, ArrayFelt>;
+        type core::panics::PanicResult::<((),)> = Enum<ut@core::panics::PanicResult::<((),)>, Inner, PanicData>;
+
+        libfunc felt252_is_zero = felt252_is_zero;
+        libfunc branch_align = branch_align;
+        libfunc drop_nz = drop<NonZeroFelt252>;
+        libfunc drop_u8 = drop<u8>;
+        libfunc drop_array = drop<ArrayFelt>;
+        libfunc drop_option = drop<core::option::Option::<core::integer::u16>>;
+        libfunc construct_unit = struct_construct<Unit>;
+        libfunc construct_inner = struct_construct<Inner>;
+        libfunc construct_panic = struct_construct<Panic>;
+        libfunc construct_panic_data = struct_construct<PanicData>;
+        libfunc array_new = array_new<felt252>;
+        libfunc array_append = array_append<felt252>;
+        libfunc felt252_const_boom = felt252_const<1651470189>;
+        libfunc store_temp_felt = store_temp<felt252>;
+        libfunc store_temp_unit = store_temp<Unit>;
+        libfunc init_ok = enum_init<core::panics::PanicResult::<((),)>, 0>;
+        libfunc init_err = enum_init<core::panics::PanicResult::<((),)>, 1>;
+        libfunc store_temp_result = store_temp<core::panics::PanicResult::<((),)>>;
+
+        felt252_is_zero(x) { fallthrough() 11(nz) };
+        branch_align() -> ();
+        array_new() -> (data);
+        felt252_const_boom() -> (msg);
+        store_temp_felt(msg) -> (msg);
+        array_append(data, msg) -> (data);
+        construct_panic() -> (panic);
+        construct_panic_data(panic, data) -> (err);
+        init_err(err) -> (r);
+        store_temp_result(r) -> (r);
+        return(r);
+        branch_align() -> ();
+        drop_nz(nz) -> ();
+        construct_unit() -> (unit);
+        construct_inner(unit) -> (inner);
+        init_ok(inner) -> (r);
+        store_temp_result(r) -> (r);
+        return(r);
+        drop_u8(a) -> ();
+        drop_array(b) -> ();
+        drop_option(c) -> ();
+        construct_unit() -> (unit);
+        store_temp_unit(unit) -> (unit);
+        return(unit);
+
+        fuzz::check_nonzero@0(x: felt252) -> (core::panics::PanicResult::<((),)>);
+        fuzz::takes_values@18(a: u8, b: ArrayFelt, c: core::option::Option::<core::integer::u16>) -> (Unit);
+    ";
+
+    fn parse_program() -> SierraProgram {
+        cairo_lang_sierra::ProgramParser::new()
+            .parse(FUZZED_FUNCTIONS_PROGRAM)
+            .unwrap()
+    }
+
+    #[test]
+    fn fuzz_reports_panics() {
+        let sierra_program = parse_program();
+        let fuzzer =
+            FunctionFuzzer::new(&sierra_program, "check_nonzero", FuzzConfig::default()).unwrap();
+        let report = fuzzer.fuzz(30, 0).unwrap();
+        assert_eq!(report.runs, 30);
+        assert!(!report.failures.is_empty());
+        for failure in report.failures.iter() {
+            assert_eq!(failure.kind, FailureKind::Panic);
+            assert_eq!(failure.args, vec![Value::from(0)]);
+            assert_eq!(failure.error, "panicked with (0x626f6f6d ('boom'))");
+        }
+    }
+
+    #[test]
+    fn run_input_reproduces_failures() {
+        let sierra_program = parse_program();
+        let fuzzer = FunctionFuzzer::new(
+            &sierra_program,
+            "fuzz::check_nonzero",
+            FuzzConfig::default(),
+        )
+        .unwrap();
+        let failure = fuzzer.fuzz(30, 1).unwrap().failures.remove(0);
+        let rerun = fuzzer.run_input(&failure.data).unwrap().unwrap();
+        assert_eq!(rerun.args, failure.args);
+        assert_eq!(rerun.kind, failure.kind);
+    }
+
+    #[test]
+    fn fuzz_typed_args() {
+        let sierra_program = parse_program();
+        let config = FuzzConfig {
+            max_array_len: 4,
+            ..Default::default()
+        };
+        let fuzzer = FunctionFuzzer::new(&sierra_program, "takes_values", config).unwrap();
+        assert!(fuzzer.fuzz(20, 0).unwrap().failures.is_empty());
+        for byte in 0..=u8::MAX {
+            let args = fuzzer
+                .arbitrary_args(&mut Unstructured::new(&[byte; 64]))
+                .unwrap();
+            assert_matches!(args.as_slice(), [a, Value::Array(b), c] => {
+                assert!(a.as_u64().is_some_and(|a| a <= u8::MAX as u64));
+                assert!(b.len() <= 4);
+                assert!(c.is_null() || c.as_u64().is_some_and(|c| c <= u16::MAX as u64));
+            });
+        }
+    }
+
+    #[test]
+    fn function_not_found() {
+        let sierra_program = parse_program();
+        assert_matches!(
+            FunctionFuzzer::new(&sierra_program, "missing", FuzzConfig::default()).err(),
+            Some(Error::FunctionNotFound(name)) if &*name == "missing"
+        );
+    }
+
+    #[test]
+    fn fuzz_report_display() {
+        let report = FuzzReport {
+            runs: 2,
+            failures: vec![FuzzFailure {
+                data: vec![],
+                args: vec![Value::from(0), Value::Null],
+                kind: FailureKind::Panic,
+                error: "panicked with (0x1)".to_string(),
+            }],
+        };
+        assert_eq!(
+            report.to_string(),
+            "panic with args [0,null]: panicked with (0x1)\nfuzzing result: 2 runs; 1 failed\n"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Gas accounting of Cairo 1 runs
+//!
+//! Sierra functions consuming gas receive the gas counter as a `GasBuiltin` argument, and return
+//! what is left of it. The [GasReport] of a run is built from its trace, reading the counter each
+//! function received when it was called, and the one it returned.
+
+use crate::error::Error;
+use cairo_lang_casm::instructions::InstructionBody;
+use cairo_lang_sierra::{
+    extensions::{
+        core::{CoreLibfunc, CoreType},
+        gas::GasBuiltinType,
+        ConcreteType, NamedType,
+    },
+    ids::ConcreteTypeId,
+    program::{Function, Program as SierraProgram},
+    program_registry::ProgramRegistry,
+};
+use cairo_lang_sierra_to_casm::compiler::CairoProgram;
+use cairo_lang_utils::unordered_hash_map::UnorderedHashMap;
+use cairo_vm::{
+    vm::{errors::trace_errors::TraceError, runners::cairo_runner::CairoRunner},
+    Felt252,
+};
+use num_traits::ToPrimitive;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+/// Initial gas of runs without a gas budget
+pub const DEFAULT_INITIAL_GAS: usize = 9999999999999;
+
+/// Panic data of a Cairo 1 program running out of gas ('Out of gas')
+pub(crate) fn out_of_gas_panic_data() -> [Felt252; 1] {
+    [Felt252::from_bytes_be_slice(b"Out of gas")]
+}
+
+/// Gas consumed by a run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasReport {
+    pub initial_gas: usize,
+    /// Gas returned by `main`
+    pub remaining_gas: usize,
+    pub consumed_gas: usize,
+    /// Gas consumed by each function called during the run, sorted by decreasing total gas
+    pub functions: Vec<FunctionGasUsage>,
+}
+
+/// Gas consumed by the calls to a function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionGasUsage {
+    pub name: String,
+    pub calls: usize,
+    /// Gas consumed by the function and the functions it called
+    pub total_gas: usize,
+    /// Gas consumed by the function itself
+    pub self_gas: usize,
+}
+
+impl fmt::Display for GasReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Initial gas: {}", self.initial_gas)?;
+        writeln!(f, "Consumed gas: {}", self.consumed_gas)?;
+        writeln!(f, "Remaining gas: {}", self.remaining_gas)?;
+        if self.functions.is_empty() {
+            return Ok(());
+        }
+        let name_width = self
+            .functions
+            .iter()
+            .map(|function| function.name.len())
+            .max()
+            .unwrap_or_default()
+            .max("Function".len());
+        writeln!(
+            f,
+            "{:<name_width$} {:>10} {:>14} {:>14}",
+            "Function", "Calls", "Total gas", "Self gas"
+        )?;
+        for function in self.functions.iter() {
+            writeln!(
+                f,
+                "{:<name_width$} {:>10} {:>14} {:>14}",
+                function.name, function.calls, function.total_gas, function.self_gas
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Position of the gas counter in the arguments and return values of a function
+struct GasCounterLocation {
+    name: String,
+    // Offset of the gas counter from the start of the arguments, and their total size
+    arg_offset: usize,
+    args_size: usize,
+    // Offset of the gas counter from the start of the return values, and their total size
+    ret_offset: usize,
+    rets_size: usize,
+}
+
+// A function call that hasn't returned yet
+struct ActiveCall {
+    function: usize,
+    fp: usize,
+    gas: usize,
+    callees_gas: usize,
+}
+
+/// Builds the gas report of a run from its relocated trace and memory.
+/// `code_offset` is the pc at which the casm program starts.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gas_report(
+    runner: &CairoRunner,
+    sierra_program: &SierraProgram,
+    sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    type_sizes: &UnorderedHashMap<ConcreteTypeId, i16>,
+    casm_program: &CairoProgram,
+    code_offset: usize,
+    main_func: &Function,
+    initial_gas: usize,
+) -> Result<GasReport, Error> {
+    let trace = runner
+        .relocated_trace
+        .as_ref()
+        .ok_or(TraceError::TraceNotRelocated)?;
+    let locations: Vec<_> = sierra_program
+        .funcs
+        .iter()
+        .map(|func| gas_counter_location(func, sierra_program_registry, type_sizes))
+        .collect();
+    let entry_points: HashMap<usize, usize> = sierra_program
+        .funcs
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| locations[*i].is_some())
+        .map(|(i, func)| {
+            (
+                code_offset
+                    + casm_program.debug_info.sierra_statement_info[func.entry_point.0]
+                        .start_offset,
+                i,
+            )
+        })
+        .collect();
+    let returns: HashSet<usize> = casm_program
+        .instructions
+        .iter()
+        .scan(code_offset, |pc, inst| {
+            let inst_pc = *pc;
+            *pc += inst.body.op_size();
+            Some((inst_pc, matches!(inst.body, InstructionBody::Ret(_))))
+        })
+        .filter_map(|(pc, is_ret)| is_ret.then_some(pc))
+        .collect();
+
+    let read_gas = |address: Option<usize>| -> Result<usize, Error> {
+        address
+            .and_then(|address| runner.relocated_memory.get(address)?.as_ref())
+            .and_then(|gas| gas.to_usize())
+            .ok_or(Error::FailedToExtractReturnValues)
+    };
+
+    let mut usages: Vec<Option<FunctionGasUsage>> = vec![None; sierra_program.funcs.len()];
+    let mut main_remaining_gas = None;
+    let mut stack: Vec<ActiveCall> = Vec::new();
+    for entry in trace.iter() {
+        // The program segment is relocated right after the first (unused) memory address
+        let pc = entry.pc - 1;
+        if let Some(&function) = entry_points.get(&pc) {
+            // Jumps back to the entry point of a function don't start a new call
+            if stack.last().map_or(true, |call| call.fp != entry.fp) {
+                let location = locations[function].as_ref().unwrap();
+                let gas =
+                    read_gas((entry.fp + location.arg_offset).checked_sub(location.args_size + 2))?;
+                stack.push(ActiveCall {
+                    function,
+                    fp: entry.fp,
+                    gas,
+                    callees_gas: 0,
+                });
+            }
+        }
+        if returns.contains(&pc) && stack.last().is_some_and(|call| call.fp == entry.fp) {
+            let call = stack.pop().unwrap();
+            let location = locations[call.function].as_ref().unwrap();
+            let remaining_gas =
+                read_gas((entry.ap + location.ret_offset).checked_sub(location.rets_size))?;
+            let total_gas = call.gas.saturating_sub(remaining_gas);
+            let usage = usages[call.function].get_or_insert_with(|| FunctionGasUsage {
+                name: location.name.clone(),
+                calls: 0,
+                total_gas: 0,
+                self_gas: 0,
+            });
+            usage.calls += 1;
+            usage.total_gas += total_gas;
+            usage.self_gas += total_gas.saturating_sub(call.callees_gas);
+            match stack.last_mut() {
+                Some(caller) => caller.callees_gas += total_gas,
+                None if sierra_program.funcs[call.function].id == main_func.id => {
+                    main_remaining_gas = Some(remaining_gas)
+                }
+                None => {}
+            }
+        }
+    }
+
+    let remaining_gas = main_remaining_gas.unwrap_or(initial_gas);
+    let mut functions: Vec<_> = usages.into_iter().flatten().collect();
+    functions.sort_by(|a, b| b.total_gas.cmp(&a.total_gas).then(a.name.cmp(&b.name)));
+    Ok(GasReport {
+        initial_gas,
+        remaining_gas,
+        consumed_gas: initial_gas.saturating_sub(remaining_gas),
+        functions,
+    })
+}
+
+// Returns where the gas counter is passed to and returned from `func`, or None if it doesn't use gas
+fn gas_counter_location(
+    func: &Function,
+    sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    type_sizes: &UnorderedHashMap<ConcreteTypeId, i16>,
+) -> Option<GasCounterLocation> {
+    let is_gas_builtin = |ty: &ConcreteTypeId| {
+        sierra_program_registry
+            .get_type(ty)
+            .is_ok_and(|info| info.info().long_id.generic_id == GasBuiltinType::ID)
+    };
+    let size = |ty: &ConcreteTypeId| type_sizes.get(ty).copied().unwrap_or_default() as usize;
+    let offset_of = |types: &[ConcreteTypeId]| -> Option<usize> {
+        let index = types.iter().position(is_gas_builtin)?;
+        Some(types[..index].iter().map(size).sum())
+    };
+    Some(GasCounterLocation {
+        name: func
+            .id
+            .debug_name
+            .as_ref()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| func.id.to_string()),
+        arg_offset: offset_of(&func.signature.param_types)?,
+        args_size: func.signature.param_types.iter().map(size).sum(),
+        ret_offset: offset_of(&func.signature.ret_types)?,
+        rets_size: func.signature.ret_types.iter().map(size).sum(),
+    })
+}
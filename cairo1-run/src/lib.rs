@@ -1,7 +1,17 @@
 pub mod cairo_run;
 pub mod error;
+pub mod fuzz;
+pub mod gas;
+pub mod syscall_handler;
+pub mod test_runner;
+pub mod values;
 // Re-export main struct and functions from crate for convenience
-pub use crate::cairo_run::{cairo_run_program, Cairo1RunConfig, FuncArg};
+pub use crate::cairo_run::{
+    cairo_run_program, cairo_run_program_with_debugger, cairo_run_program_with_gas_report,
+    cairo_run_program_with_syscall_handler, Cairo1RunConfig, FuncArg,
+};
+pub use crate::gas::{FunctionGasUsage, GasReport};
+pub use crate::syscall_handler::{LocalSyscallHandler, StarknetHintProcessor, SyscallHandler};
 // Re-export cairo_vm structs returned by this crate for ease of use
 pub use cairo_vm::{
     types::relocatable::{MaybeRelocatable, Relocatable},
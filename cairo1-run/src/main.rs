@@ -1,14 +1,23 @@
 use bincode::enc::write::Writer;
 use cairo1_run::error::Error;
-use cairo1_run::{cairo_run_program, Cairo1RunConfig, FuncArg};
+use cairo1_run::{
+    cairo_run_program, cairo_run_program_with_debugger, cairo_run_program_with_gas_report,
+    fuzz::{FunctionFuzzer, FuzzConfig},
+    test_runner::{compile_tests, run_tests, TestRunConfig},
+    Cairo1RunConfig, FuncArg,
+};
 use cairo_lang_compiler::{
-    compile_prepared_db, db::RootDatabase, project::setup_project, CompilerConfig,
+    compile_cairo_project_at_path, compile_prepared_db, db::RootDatabase, project::setup_project,
+    CompilerConfig,
 };
+use cairo_lang_sierra::program::Program as SierraProgram;
 use cairo_vm::{
-    air_public_input::PublicInputError, types::layout_name::LayoutName,
-    vm::errors::trace_errors::TraceError, Felt252,
+    air_public_input::PublicInputError,
+    types::{layout::CairoLayoutParams, layout_name::LayoutName},
+    vm::{debugger::Debugger, errors::trace_errors::TraceError},
+    Felt252,
 };
-use clap::{Parser, ValueHint};
+use clap::{Parser, Subcommand, ValueHint};
 use itertools::Itertools;
 use std::{
     io::{self, Write},
@@ -16,16 +25,28 @@ use std::{
 };
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
-    #[clap(value_parser, value_hint=ValueHint::FilePath)]
-    filename: PathBuf,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(value_parser, value_hint=ValueHint::FilePath, required = true)]
+    filename: Option<PathBuf>,
     #[clap(long = "trace_file", value_parser)]
     trace_file: Option<PathBuf>,
     #[structopt(long = "memory_file")]
     memory_file: Option<PathBuf>,
     #[clap(long = "layout", default_value = "plain", value_enum)]
     layout: LayoutName,
+    /// JSON file with the parameters of the dynamic layout
+    #[clap(long = "cairo_layout_params_file", value_parser)]
+    cairo_layout_params_file: Option<PathBuf>,
     #[clap(long = "proof_mode", value_parser)]
     proof_mode: bool,
     #[clap(long = "air_public_input", requires = "proof_mode")]
@@ -49,8 +70,15 @@ struct Args {
     // Same rules from `args` apply here
     #[clap(long = "args_file", value_parser, value_hint=ValueHint::FilePath, conflicts_with = "args")]
     args_file: Option<PathBuf>,
+    /// JSON array with the arguments of `main`, each one following the type of its parameter.
+    /// For example " --args_json '[[1, true], [1, 2, 3], null]'" for `main(a: (u8, bool), b: Span<felt252>, c: Option<u256>)`
+    #[clap(long = "args_json", value_parser=process_json_args, conflicts_with_all = ["args", "args_file"])]
+    args_json: Option<FuncArgs>,
     #[clap(long = "print_output", value_parser)]
     print_output: bool,
+    /// Print the output as JSON, following the return type of `main`
+    #[clap(long = "json_output", requires = "print_output")]
+    json_output: bool,
     #[clap(
         long = "append_return_values",
         // We need to add these air_private_input & air_public_input or else
@@ -58,6 +86,65 @@ struct Args {
         conflicts_with_all = ["proof_mode", "air_private_input", "air_public_input"]
     )]
     append_return_values: bool,
+    #[clap(long = "debug", value_parser, conflicts_with = "gas_report")]
+    debug: bool,
+    /// Directory where the lcov (lcov.info) and HTML (index.html) coverage reports are written.
+    /// Requires the program to be compiled from a cairo file
+    #[clap(long = "coverage", value_parser)]
+    coverage: Option<PathBuf>,
+    /// Gas available to the program. Running out of gas fails the run
+    #[clap(long = "initial_gas", value_parser)]
+    initial_gas: Option<usize>,
+    /// Print the gas consumed by the run, and by each function called during it
+    #[clap(long = "gas_report", value_parser)]
+    gas_report: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the functions marked with `#[test]` in a Cairo 1 crate
+    Test(TestArgs),
+    /// Run a function with random arguments generated from its signature, reporting the ones that make it fail
+    Fuzz(FuzzArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct TestArgs {
+    /// Cairo file, or directory with a `cairo_project.toml`
+    #[clap(value_parser, value_hint=ValueHint::AnyPath)]
+    path: PathBuf,
+    /// Only run the tests whose name contains this string
+    #[clap(long = "filter", value_parser)]
+    filter: Option<String>,
+    /// Also run the tests marked with `#[ignore]`
+    #[clap(long = "include_ignored", value_parser)]
+    include_ignored: bool,
+    #[clap(long = "layout", default_value = "all_cairo", value_enum)]
+    layout: LayoutName,
+}
+
+#[derive(clap::Args, Debug)]
+struct FuzzArgs {
+    /// Sierra file, or Cairo file or directory with a `cairo_project.toml`
+    #[clap(value_parser, value_hint=ValueHint::AnyPath)]
+    path: PathBuf,
+    /// Function to fuzz, given either its full path or its name
+    #[clap(long = "function", default_value = "main")]
+    function: String,
+    /// Number of inputs to run the function with
+    #[clap(long = "runs", default_value = "100")]
+    runs: usize,
+    /// Seed of the random generator of the inputs
+    #[clap(long = "seed", default_value = "0")]
+    seed: u64,
+    /// Gas available to each run
+    #[clap(long = "initial_gas")]
+    initial_gas: Option<usize>,
+    /// Maximum number of elements of the arrays passed as arguments
+    #[clap(long = "max_array_len")]
+    max_array_len: Option<usize>,
+    #[clap(long = "layout", default_value = "all_cairo", value_enum)]
+    layout: LayoutName,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -114,6 +201,16 @@ fn process_args(value: &str) -> Result<FuncArgs, String> {
     Ok(FuncArgs(args))
 }
 
+/// Parses a JSON array, with a JSON argument for each element
+fn process_json_args(value: &str) -> Result<FuncArgs, String> {
+    match serde_json::from_str(value).map_err(|err| err.to_string())? {
+        serde_json::Value::Array(values) => {
+            Ok(FuncArgs(values.into_iter().map(FuncArg::Json).collect()))
+        }
+        _ => Err("the arguments must be a JSON array".to_string()),
+    }
+}
+
 pub struct FileWriter {
     buf_writer: io::BufWriter<std::fs::File>,
     bytes_written: usize,
@@ -149,45 +246,92 @@ impl FileWriter {
 
 fn run(args: impl Iterator<Item = String>) -> Result<Option<String>, Error> {
     let mut args = Args::try_parse_from(args)?;
-    if let Some(filename) = args.args_file {
-        args.args = process_args(&std::fs::read_to_string(filename)?).unwrap();
+    match args.command {
+        Some(Command::Test(test_args)) => return run_test_command(test_args),
+        Some(Command::Fuzz(fuzz_args)) => return run_fuzz_command(fuzz_args),
+        None => {}
     }
-
-    let cairo_run_config = Cairo1RunConfig {
-        proof_mode: args.proof_mode,
-        serialize_output: args.print_output,
-        relocate_mem: args.memory_file.is_some() || args.air_public_input.is_some(),
-        layout: args.layout,
-        trace_enabled: args.trace_file.is_some() || args.air_public_input.is_some(),
-        args: &args.args.0,
-        finalize_builtins: args.air_public_input.is_some() || args.cairo_pie_output.is_some(),
-        append_return_values: args.append_return_values,
+    let Some(filename) = args.filename else {
+        unreachable!("clap requires the filename unless a subcommand is used")
     };
+    if let Some(args_file) = args.args_file {
+        args.args = process_args(&std::fs::read_to_string(args_file)?).unwrap();
+    }
+    if let Some(args_json) = args.args_json.take() {
+        args.args = args_json;
+    }
 
     // Try to parse the file as a sierra program
-    let file = std::fs::read(&args.filename)?;
-    let sierra_program = match serde_json::from_slice(&file) {
-        Ok(program) => program,
+    let file = std::fs::read(&filename)?;
+    let (sierra_program, code_locations) = match serde_json::from_slice(&file) {
+        Ok(program) => (program, None),
         Err(_) => {
             // If it fails, try to compile it as a cairo program
             let compiler_config = CompilerConfig {
                 replace_ids: true,
                 ..CompilerConfig::default()
             };
-            let mut db = RootDatabase::builder()
-                .detect_corelib()
-                .skip_auto_withdraw_gas()
-                .build()
-                .unwrap();
-            let main_crate_ids = setup_project(&mut db, &args.filename).unwrap();
+            let mut db_builder = RootDatabase::builder();
+            db_builder.detect_corelib();
+            // Gas is only withdrawn if it's going to be charged
+            if args.initial_gas.is_none() && !args.gas_report {
+                db_builder.skip_auto_withdraw_gas();
+            }
+            let mut db = db_builder.build().unwrap();
+            let main_crate_ids = setup_project(&mut db, &filename).unwrap();
             let sierra_program_with_dbg =
                 compile_prepared_db(&db, main_crate_ids, compiler_config).unwrap();
 
-            sierra_program_with_dbg.program
+            // Source locations are only available when compiling from a cairo file, and are used
+            // to report coverage and to set breakpoints on source lines
+            let code_locations = (args.debug || args.coverage.is_some()).then(|| {
+                sierra_program_with_dbg
+                    .debug_info
+                    .statements_locations
+                    .extract_statements_source_code_locations(&db)
+            });
+
+            (sierra_program_with_dbg.program, code_locations)
         }
     };
 
-    let (runner, _, serialized_output) = cairo_run_program(&sierra_program, cairo_run_config)?;
+    let dynamic_layout_params = args
+        .cairo_layout_params_file
+        .as_deref()
+        .map(CairoLayoutParams::from_file)
+        .transpose()?;
+
+    let cairo_run_config = Cairo1RunConfig {
+        proof_mode: args.proof_mode,
+        serialize_output: args.print_output,
+        json_output: args.json_output,
+        relocate_mem: args.memory_file.is_some() || args.air_public_input.is_some(),
+        layout: args.layout,
+        dynamic_layout_params,
+        trace_enabled: args.trace_file.is_some()
+            || args.air_public_input.is_some()
+            || args.coverage.is_some(),
+        args: &args.args.0,
+        finalize_builtins: args.air_public_input.is_some() || args.cairo_pie_output.is_some(),
+        append_return_values: args.append_return_values,
+        code_locations: code_locations.as_ref(),
+        initial_gas: args.initial_gas,
+    };
+
+    let (runner, serialized_output) = if args.gas_report {
+        let (runner, _, serialized_output, gas_report) =
+            cairo_run_program_with_gas_report(&sierra_program, cairo_run_config)?;
+        print!("{gas_report}");
+        (runner, serialized_output)
+    } else if args.debug {
+        let mut debugger = Debugger::new(io::stdin().lock(), io::stdout());
+        let (runner, _, serialized_output) =
+            cairo_run_program_with_debugger(&sierra_program, cairo_run_config, &mut debugger)?;
+        (runner, serialized_output)
+    } else {
+        let (runner, _, serialized_output) = cairo_run_program(&sierra_program, cairo_run_config)?;
+        (runner, serialized_output)
+    };
 
     if let Some(file_path) = args.air_public_input {
         let json = runner.get_air_public_input()?.serialize_json()?;
@@ -225,6 +369,16 @@ fn run(args: impl Iterator<Item = String>) -> Result<Option<String>, Error> {
         runner.get_cairo_pie()?.write_zip_file(file_path)?
     }
 
+    if let Some(ref coverage_dir) = args.coverage {
+        let report = runner.get_coverage_report()?;
+        std::fs::create_dir_all(coverage_dir)?;
+        std::fs::write(coverage_dir.join("lcov.info"), report.to_lcov())?;
+        std::fs::write(
+            coverage_dir.join("index.html"),
+            report.to_html(|filename| std::fs::read_to_string(filename).ok()),
+        )?;
+    }
+
     if let Some(trace_path) = args.trace_file {
         let relocated_trace = runner
             .relocated_trace
@@ -248,6 +402,55 @@ fn run(args: impl Iterator<Item = String>) -> Result<Option<String>, Error> {
     Ok(serialized_output)
 }
 
+fn run_test_command(test_args: TestArgs) -> Result<Option<String>, Error> {
+    let (sierra_program, tests) = compile_tests(&test_args.path)?;
+    let config = TestRunConfig {
+        filter: test_args.filter.as_deref(),
+        include_ignored: test_args.include_ignored,
+        layout: test_args.layout,
+    };
+    let summary = run_tests(&sierra_program, &tests, &config);
+    print!("{summary}");
+    match summary.failed() {
+        0 => Ok(None),
+        failed => Err(Error::TestsFailed(failed)),
+    }
+}
+
+fn run_fuzz_command(fuzz_args: FuzzArgs) -> Result<Option<String>, Error> {
+    // Try to parse the path as a sierra program, compiling it as a cairo project otherwise
+    let sierra_program: Option<SierraProgram> = std::fs::read(&fuzz_args.path)
+        .ok()
+        .and_then(|file| serde_json::from_slice(&file).ok());
+    let sierra_program = match sierra_program {
+        Some(program) => program,
+        None => compile_cairo_project_at_path(
+            &fuzz_args.path,
+            CompilerConfig {
+                replace_ids: true,
+                ..CompilerConfig::default()
+            },
+        )
+        .map_err(|err| Error::SierraCompilation(err.to_string()))?,
+    };
+    let default_config = FuzzConfig::default();
+    let config = FuzzConfig {
+        layout: fuzz_args.layout,
+        initial_gas: fuzz_args.initial_gas.unwrap_or(default_config.initial_gas),
+        max_array_len: fuzz_args
+            .max_array_len
+            .unwrap_or(default_config.max_array_len),
+        ..default_config
+    };
+    let fuzzer = FunctionFuzzer::new(&sierra_program, &fuzz_args.function, config)?;
+    let report = fuzzer.fuzz(fuzz_args.runs, fuzz_args.seed)?;
+    print!("{report}");
+    match report.failures.len() {
+        0 => Ok(None),
+        failed => Err(Error::FuzzingFailed(failed)),
+    }
+}
+
 fn main() -> Result<(), Error> {
     match run(std::env::args()) {
         Err(Error::Cli(err)) => err.exit(),
@@ -542,4 +745,115 @@ mod tests {
         let args = args.iter().cloned().map(String::from);
         assert_matches!(run(args), Err(Error::ArgumentsSizeMismatch { expected, actual }) if expected == 2 && actual == 3);
     }
+
+    #[test]
+    fn test_run_coverage() {
+        let coverage_dir = std::env::temp_dir().join("cairo1-run-coverage");
+        let args = [
+            "cairo1-run",
+            "../cairo_programs/cairo-1-programs/fibonacci.cairo",
+            "--coverage",
+            coverage_dir.to_str().unwrap(),
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Ok(_));
+        let lcov = std::fs::read_to_string(coverage_dir.join("lcov.info")).unwrap();
+        assert!(lcov.contains("fibonacci.cairo\n"));
+        assert!(lcov.contains("fibonacci::fib\n"));
+        assert!(coverage_dir.join("index.html").exists());
+    }
+
+    #[rstest]
+    #[case(&["--gas_report"])]
+    #[case(&["--initial_gas", "1000000", "--gas_report"])]
+    #[case(&["--initial_gas", "1000000"])]
+    fn test_run_with_gas(#[case] extra_args: &[&str]) {
+        let args = [
+            "cairo1-run",
+            "../cairo_programs/cairo-1-programs/fibonacci.cairo",
+        ]
+        .into_iter()
+        .chain(extra_args.iter().copied())
+        .map(String::from);
+        assert_matches!(run(args), Ok(_));
+    }
+
+    #[test]
+    fn test_run_out_of_gas() {
+        let args = [
+            "cairo1-run",
+            "../cairo_programs/cairo-1-programs/fibonacci.cairo",
+            "--initial_gas",
+            "100",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Err(Error::OutOfGas(100)));
+    }
+
+    #[test]
+    fn test_run_tests_command() {
+        let args = [
+            "cairo1-run",
+            "test",
+            "../cairo_programs/cairo-1-programs/tests/test_functions.cairo",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Ok(None));
+    }
+
+    #[test]
+    fn test_run_tests_command_include_ignored() {
+        let args = [
+            "cairo1-run",
+            "test",
+            "../cairo_programs/cairo-1-programs/tests/test_functions.cairo",
+            "--include_ignored",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Err(Error::TestsFailed(1)));
+    }
+
+    #[test]
+    fn test_run_fuzz_command_failures() {
+        let args = [
+            "cairo1-run",
+            "fuzz",
+            "../cairo_programs/cairo-1-programs/fuzzing/fuzz_targets.cairo",
+            "--function",
+            "divide",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Err(Error::FuzzingFailed(n)) if n > 0);
+    }
+
+    #[test]
+    fn test_run_fuzz_command() {
+        let args = [
+            "cairo1-run",
+            "fuzz",
+            "../cairo_programs/cairo-1-programs/fuzzing/fuzz_targets.cairo",
+            "--function",
+            "widening_sum",
+            "--runs",
+            "20",
+            "--seed",
+            "7",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Ok(None));
+    }
+
+    #[test]
+    fn test_run_requires_filename() {
+        let args = ["cairo1-run", "--print_output"]
+            .into_iter()
+            .map(String::from);
+        assert_matches!(run(args), Err(Error::Cli(_)));
+    }
 }
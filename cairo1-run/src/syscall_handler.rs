@@ -0,0 +1,1209 @@
+//! Standalone execution of Starknet syscalls
+//!
+//! Cairo 1 programs using the `System` implicit argument (e.g. Starknet contracts) request syscalls
+//! through the `SystemCall` hint, which the [Cairo1HintProcessor] doesn't support.
+//! The [StarknetHintProcessor] wraps it and executes the syscalls against a [SyscallHandler].
+//! The default handler, [LocalSyscallHandler], keeps the storage and the emitted events in memory,
+//! and runs the contracts called with `call_contract` from a set of locally registered classes,
+//! so that contracts can be executed and tested without a sequencer.
+//! The `keccak` and `sha256_process_block` syscalls don't depend on the state, and are computed
+//! by the [StarknetHintProcessor] itself.
+
+use crate::error::Error;
+use cairo_lang_casm::{
+    hints::{Hint, StarknetHint},
+    operand::{BinOpOperand, DerefOrImmediate, Operation, Register, ResOperand},
+};
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use cairo_vm::{
+    hint_processor::{
+        cairo_1_hint_processor::hint_processor::Cairo1HintProcessor,
+        hint_processor_definition::{HintProcessorLogic, HintReference},
+    },
+    serde::deserialize_program::ApTracking,
+    types::{
+        builtin_name::BuiltinName,
+        exec_scope::ExecutionScopes,
+        layout_name::LayoutName,
+        program::Program,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{
+        errors::{
+            hint_errors::HintError, runner_errors::RunnerError, vm_errors::VirtualMachineError,
+        },
+        runners::cairo_runner::{CairoArg, CairoRunner, ResourceTracker, RunResources},
+        vm_core::VirtualMachine,
+    },
+    Felt252,
+};
+use num_traits::ToPrimitive;
+use std::{any::Any, collections::HashMap};
+
+/// Result of a syscall, the error holds the revert reason
+pub type SyscallResult<T> = Result<T, Vec<Felt252>>;
+
+/// Gas cost of each syscall, as charged by the Starknet sequencer
+pub mod gas_costs {
+    const STEP: usize = 100;
+    const RANGE_CHECK: usize = 70;
+    const BITWISE: usize = 594;
+
+    pub const ENTRY_POINT_INITIAL_BUDGET: usize = 100 * STEP;
+    const ENTRY_POINT: usize = ENTRY_POINT_INITIAL_BUDGET + 500 * STEP;
+    pub const CALL_CONTRACT: usize = 10 * STEP + ENTRY_POINT;
+    pub const EMIT_EVENT: usize = 10 * STEP;
+    pub const GET_EXECUTION_INFO: usize = 10 * STEP;
+    pub const KECCAK: usize = 0;
+    pub const KECCAK_ROUND_COST: usize = 180000;
+    pub const SHA256_PROCESS_BLOCK: usize = 1852 * STEP + 65 * RANGE_CHECK + 1115 * BITWISE;
+    pub const STORAGE_READ: usize = 50 * STEP;
+    pub const STORAGE_WRITE: usize = 50 * STEP;
+}
+
+/// Block information returned by `get_execution_info`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockInfo {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub sequencer_address: Felt252,
+}
+
+/// Resource bounds of a transaction, as returned by `get_execution_info`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceBounds {
+    pub resource: Felt252,
+    pub max_amount: u64,
+    pub max_price_per_unit: u128,
+}
+
+/// Transaction information returned by `get_execution_info`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxInfo {
+    pub version: Felt252,
+    pub account_contract_address: Felt252,
+    pub max_fee: u128,
+    pub signature: Vec<Felt252>,
+    pub transaction_hash: Felt252,
+    pub chain_id: Felt252,
+    pub nonce: Felt252,
+    pub resource_bounds: Vec<ResourceBounds>,
+    pub tip: u128,
+    pub paymaster_data: Vec<Felt252>,
+    pub nonce_data_availability_mode: u32,
+    pub fee_data_availability_mode: u32,
+    pub account_deployment_data: Vec<Felt252>,
+}
+
+/// Execution information returned by `get_execution_info`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionInfo {
+    pub block_info: BlockInfo,
+    pub tx_info: TxInfo,
+    pub caller_address: Felt252,
+    pub contract_address: Felt252,
+    pub entry_point_selector: Felt252,
+}
+
+/// Event emitted with the `emit_event` syscall
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub from_address: Felt252,
+    pub keys: Vec<Felt252>,
+    pub data: Vec<Felt252>,
+}
+
+/// State accessed by the syscalls of a Cairo 1 program
+pub trait SyscallHandler {
+    fn storage_read(&mut self, address_domain: Felt252, key: Felt252) -> SyscallResult<Felt252>;
+
+    fn storage_write(
+        &mut self,
+        address_domain: Felt252,
+        key: Felt252,
+        value: Felt252,
+    ) -> SyscallResult<()>;
+
+    fn emit_event(&mut self, keys: Vec<Felt252>, data: Vec<Felt252>) -> SyscallResult<()>;
+
+    fn get_execution_info(&mut self) -> SyscallResult<ExecutionInfo>;
+
+    /// Calls the entrypoint `selector` of the contract at `contract_address`.
+    /// `gas` holds the gas available to the callee, and is updated with the gas left by it.
+    fn call_contract(
+        &mut self,
+        contract_address: Felt252,
+        selector: Felt252,
+        calldata: Vec<Felt252>,
+        gas: &mut usize,
+    ) -> Result<SyscallResult<Vec<Felt252>>, HintError>;
+}
+
+/// [SyscallHandler] keeping the state in memory, and running the called contracts from the
+/// classes registered with [LocalSyscallHandler::register_contract]
+#[derive(Clone, Debug, Default)]
+pub struct LocalSyscallHandler {
+    /// Storage of each contract, by contract address
+    pub storage: HashMap<Felt252, HashMap<Felt252, Felt252>>,
+    /// Events emitted so far, by every contract
+    pub events: Vec<Event>,
+    /// Execution information of the current call. The caller, contract address and selector are
+    /// updated on each `call_contract`
+    pub execution_info: ExecutionInfo,
+    contracts: HashMap<Felt252, CasmContractClass>,
+}
+
+impl LocalSyscallHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deploys `contract_class` at `contract_address`, so that it can be called
+    pub fn register_contract(
+        &mut self,
+        contract_address: Felt252,
+        contract_class: CasmContractClass,
+    ) {
+        self.contracts.insert(contract_address, contract_class);
+    }
+
+    /// Returns the value stored at `key` by the contract at `contract_address`
+    pub fn storage_at(&self, contract_address: Felt252, key: Felt252) -> Felt252 {
+        self.storage
+            .get(&contract_address)
+            .and_then(|storage| storage.get(&key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Calls the entrypoint `selector` of the contract registered at `contract_address`, with the
+    /// contract address of the execution info as the caller
+    pub fn call(
+        &mut self,
+        contract_address: Felt252,
+        selector: Felt252,
+        calldata: &[Felt252],
+    ) -> Result<SyscallResult<Vec<Felt252>>, Error> {
+        let Some(contract_class) = self.contracts.get(&contract_address).cloned() else {
+            return Ok(Err(vec![short_string(b"CONTRACT_NOT_DEPLOYED")]));
+        };
+        let caller = self.execution_info.contract_address;
+        let mut gas = usize::MAX;
+        self.enter_call(caller, contract_address, selector, |handler| {
+            run_entry_point(&contract_class, selector, calldata, &mut gas, handler)
+        })
+    }
+
+    // Runs `f` with the execution info of a call, restoring the current one afterwards
+    fn enter_call<T>(
+        &mut self,
+        caller_address: Felt252,
+        contract_address: Felt252,
+        selector: Felt252,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let prev_info = self.execution_info.clone();
+        self.execution_info.caller_address = caller_address;
+        self.execution_info.contract_address = contract_address;
+        self.execution_info.entry_point_selector = selector;
+        let result = f(self);
+        self.execution_info = prev_info;
+        result
+    }
+}
+
+impl SyscallHandler for LocalSyscallHandler {
+    fn storage_read(&mut self, address_domain: Felt252, key: Felt252) -> SyscallResult<Felt252> {
+        if address_domain != Felt252::ZERO {
+            return Err(vec![short_string(b"Unsupported address domain")]);
+        }
+        Ok(self.storage_at(self.execution_info.contract_address, key))
+    }
+
+    fn storage_write(
+        &mut self,
+        address_domain: Felt252,
+        key: Felt252,
+        value: Felt252,
+    ) -> SyscallResult<()> {
+        if address_domain != Felt252::ZERO {
+            return Err(vec![short_string(b"Unsupported address domain")]);
+        }
+        self.storage
+            .entry(self.execution_info.contract_address)
+            .or_default()
+            .insert(key, value);
+        Ok(())
+    }
+
+    fn emit_event(&mut self, keys: Vec<Felt252>, data: Vec<Felt252>) -> SyscallResult<()> {
+        self.events.push(Event {
+            from_address: self.execution_info.contract_address,
+            keys,
+            data,
+        });
+        Ok(())
+    }
+
+    fn get_execution_info(&mut self) -> SyscallResult<ExecutionInfo> {
+        Ok(self.execution_info.clone())
+    }
+
+    fn call_contract(
+        &mut self,
+        contract_address: Felt252,
+        selector: Felt252,
+        calldata: Vec<Felt252>,
+        gas: &mut usize,
+    ) -> Result<SyscallResult<Vec<Felt252>>, HintError> {
+        let Some(contract_class) = self.contracts.get(&contract_address).cloned() else {
+            return Ok(Err(vec![short_string(b"CONTRACT_NOT_DEPLOYED")]));
+        };
+        let caller = self.execution_info.contract_address;
+        self.enter_call(caller, contract_address, selector, |handler| {
+            run_entry_point(&contract_class, selector, &calldata, gas, handler)
+        })
+        .map_err(|err| HintError::CustomHint(err.to_string().into_boxed_str()))
+    }
+}
+
+/// Runs the external entrypoint `selector` of `contract_class`, with `syscall_handler` executing
+/// its syscalls.
+/// `gas` holds the initial gas of the run, and is updated with the gas left by it.
+/// Returns the data returned by the entrypoint, or its panic data if it panicked.
+pub fn run_entry_point(
+    contract_class: &CasmContractClass,
+    selector: Felt252,
+    calldata: &[Felt252],
+    gas: &mut usize,
+    syscall_handler: &mut dyn SyscallHandler,
+) -> Result<SyscallResult<Vec<Felt252>>, Error> {
+    let Some(entry_point) = contract_class
+        .entry_points_by_type
+        .external
+        .iter()
+        .find(|entry_point| Felt252::from(&entry_point.selector) == selector)
+    else {
+        return Ok(Err(vec![short_string(b"ENTRYPOINT_NOT_FOUND")]));
+    };
+    let builtins = entry_point
+        .builtins
+        .iter()
+        .map(|name| {
+            BuiltinName::from_str(name)
+                .ok_or_else(|| Error::UnknownBuiltin(name.clone().into_boxed_str()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let program = Program::try_from(contract_class.clone())?;
+    let mut runner = CairoRunner::new(&program, LayoutName::all_cairo, false, false)?;
+    runner.initialize_function_runner_cairo_1(&builtins)?;
+
+    // Implicit arguments: builtins, gas and system
+    let mut args: Vec<CairoArg> = runner
+        .vm
+        .get_builtin_runners()
+        .iter()
+        .filter(|builtin| builtins.contains(&builtin.name()))
+        .flat_map(|builtin| builtin.initial_stack())
+        .map(CairoArg::from)
+        .collect();
+    args.push(MaybeRelocatable::from(*gas).into());
+    args.push(MaybeRelocatable::from(runner.vm.add_memory_segment()).into());
+
+    // The libfuncs using gas read the builtin costs from the pointer after the program
+    let builtin_costs_ptr = runner.vm.add_memory_segment();
+    runner
+        .vm
+        .load_data(builtin_costs_ptr, &vec![MaybeRelocatable::from(0); 5])?;
+    let program_extra_data = [
+        MaybeRelocatable::from(Felt252::from(0x208B7FFF7FFF7FFE_u64)),
+        builtin_costs_ptr.into(),
+    ];
+    let program_end = (runner.program_base.ok_or(RunnerError::NoProgBase)?
+        + contract_class.bytecode.len())
+    .map_err(RunnerError::Math)?;
+    runner.vm.load_data(program_end, &program_extra_data)?;
+
+    let calldata_start = runner.vm.add_memory_segment();
+    let calldata_end = runner.vm.load_data(
+        calldata_start,
+        &calldata
+            .iter()
+            .map(MaybeRelocatable::from)
+            .collect::<Vec<_>>(),
+    )?;
+    args.push(MaybeRelocatable::from(calldata_start).into());
+    args.push(MaybeRelocatable::from(calldata_end).into());
+
+    let mut hint_processor = StarknetHintProcessor::new(
+        Cairo1HintProcessor::new(&contract_class.hints, RunResources::default(), false),
+        syscall_handler,
+    );
+    runner.run_from_entrypoint(
+        entry_point.offset,
+        &args.iter().collect::<Vec<_>>(),
+        true,
+        Some(contract_class.bytecode.len() + program_extra_data.len()),
+        &mut hint_processor,
+    )?;
+
+    // Return values: gas, system, panic flag, retdata start and end
+    let return_values = runner.vm.get_return_values(5)?;
+    *gas = return_values[0]
+        .get_int()
+        .and_then(|gas| gas.to_usize())
+        .ok_or(Error::FailedToExtractReturnValues)?;
+    let retdata_start = return_values[3]
+        .get_relocatable()
+        .ok_or(Error::FailedToExtractReturnValues)?;
+    let retdata_end = return_values[4]
+        .get_relocatable()
+        .ok_or(Error::FailedToExtractReturnValues)?;
+    let retdata = runner
+        .vm
+        .get_integer_range(
+            retdata_start,
+            (retdata_end - retdata_start).map_err(VirtualMachineError::Math)?,
+        )?
+        .into_iter()
+        .map(|value| *value)
+        .collect();
+    if return_values[2] == MaybeRelocatable::from(0) {
+        Ok(Ok(retdata))
+    } else {
+        Ok(Err(retdata))
+    }
+}
+
+/// Hint processor for Cairo 1 programs using syscalls.
+/// Executes the `SystemCall` hints with a [SyscallHandler], and every other hint with a
+/// [Cairo1HintProcessor]
+pub struct StarknetHintProcessor<'a> {
+    inner: Cairo1HintProcessor,
+    syscall_handler: &'a mut dyn SyscallHandler,
+}
+
+impl<'a> StarknetHintProcessor<'a> {
+    pub fn new(inner: Cairo1HintProcessor, syscall_handler: &'a mut dyn SyscallHandler) -> Self {
+        Self {
+            inner,
+            syscall_handler,
+        }
+    }
+
+    // Runs a single Hint
+    pub fn execute(
+        &mut self,
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        hint: &Hint,
+    ) -> Result<(), HintError> {
+        match hint {
+            Hint::Starknet(StarknetHint::SystemCall { system }) => {
+                let system_ptr = get_buffer_ptr(vm, system)?;
+                self.execute_syscall(vm, system_ptr)
+            }
+            _ => self.inner.execute(vm, exec_scopes, hint),
+        }
+    }
+
+    // Executes the syscall requested at `system_ptr`, and writes its response after the request.
+    // The request starts with the selector and the gas counter, the response with the gas counter
+    // and a failure flag, followed by the values returned by the syscall or the revert reason
+    fn execute_syscall(
+        &mut self,
+        vm: &mut VirtualMachine,
+        system_ptr: Relocatable,
+    ) -> Result<(), HintError> {
+        let mut buffer = SyscallBuffer { ptr: system_ptr };
+        let selector = buffer.next_felt(vm)?.to_bytes_be();
+        let selector = std::str::from_utf8(&selector)
+            .map_err(|_| HintError::CustomHint("Invalid syscall selector".into()))?
+            .trim_start_matches('\0');
+        let mut gas = buffer.next_usize(vm)?;
+
+        let result = match selector {
+            "StorageRead" => self.storage_read(vm, &mut buffer, &mut gas)?,
+            "StorageWrite" => self.storage_write(vm, &mut buffer, &mut gas)?,
+            "EmitEvent" => self.emit_event(vm, &mut buffer, &mut gas)?,
+            "GetExecutionInfo" => self.get_execution_info(vm, &mut gas)?,
+            "CallContract" => self.call_contract(vm, &mut buffer, &mut gas)?,
+            "Keccak" => keccak(vm, &mut buffer, &mut gas)?,
+            "Sha256ProcessBlock" => sha256_process_block(vm, &mut buffer, &mut gas)?,
+            _ => {
+                return Err(HintError::CustomHint(
+                    format!("Unsupported syscall: {selector}").into_boxed_str(),
+                ))
+            }
+        };
+
+        buffer.write(vm, gas)?;
+        match result {
+            Ok(values) => {
+                buffer.write(vm, 0)?;
+                for value in values {
+                    buffer.write(vm, value)?;
+                }
+            }
+            Err(revert_reason) => {
+                buffer.write(vm, 1)?;
+                buffer.write_arr(vm, &revert_reason)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn storage_read(
+        &mut self,
+        vm: &VirtualMachine,
+        buffer: &mut SyscallBuffer,
+        gas: &mut usize,
+    ) -> Result<SyscallResult<Vec<MaybeRelocatable>>, HintError> {
+        let address_domain = buffer.next_felt(vm)?;
+        let key = buffer.next_felt(vm)?;
+        if let Err(reason) = deduct_gas(gas, gas_costs::STORAGE_READ) {
+            return Ok(Err(reason));
+        }
+        Ok(self
+            .syscall_handler
+            .storage_read(address_domain, key)
+            .map(|value| vec![value.into()]))
+    }
+
+    fn storage_write(
+        &mut self,
+        vm: &VirtualMachine,
+        buffer: &mut SyscallBuffer,
+        gas: &mut usize,
+    ) -> Result<SyscallResult<Vec<MaybeRelocatable>>, HintError> {
+        let address_domain = buffer.next_felt(vm)?;
+        let key = buffer.next_felt(vm)?;
+        let value = buffer.next_felt(vm)?;
+        if let Err(reason) = deduct_gas(gas, gas_costs::STORAGE_WRITE) {
+            return Ok(Err(reason));
+        }
+        Ok(self
+            .syscall_handler
+            .storage_write(address_domain, key, value)
+            .map(|_| vec![]))
+    }
+
+    fn emit_event(
+        &mut self,
+        vm: &VirtualMachine,
+        buffer: &mut SyscallBuffer,
+        gas: &mut usize,
+    ) -> Result<SyscallResult<Vec<MaybeRelocatable>>, HintError> {
+        let keys = buffer.next_arr(vm)?;
+        let data = buffer.next_arr(vm)?;
+        if let Err(reason) = deduct_gas(gas, gas_costs::EMIT_EVENT) {
+            return Ok(Err(reason));
+        }
+        Ok(self.syscall_handler.emit_event(keys, data).map(|_| vec![]))
+    }
+
+    fn get_execution_info(
+        &mut self,
+        vm: &mut VirtualMachine,
+        gas: &mut usize,
+    ) -> Result<SyscallResult<Vec<MaybeRelocatable>>, HintError> {
+        if let Err(reason) = deduct_gas(gas, gas_costs::GET_EXECUTION_INFO) {
+            return Ok(Err(reason));
+        }
+        let info = match self.syscall_handler.get_execution_info() {
+            Ok(info) => info,
+            Err(reason) => return Ok(Err(reason)),
+        };
+        let block_info = alloc_data(
+            vm,
+            &[
+                Felt252::from(info.block_info.block_number).into(),
+                Felt252::from(info.block_info.block_timestamp).into(),
+                info.block_info.sequencer_address.into(),
+            ],
+        )?;
+        let tx = &info.tx_info;
+        let signature = alloc_arr(vm, &tx.signature)?;
+        let resource_bounds = alloc_arr(
+            vm,
+            &tx.resource_bounds
+                .iter()
+                .flat_map(|bounds| {
+                    [
+                        bounds.resource,
+                        bounds.max_amount.into(),
+                        bounds.max_price_per_unit.into(),
+                    ]
+                })
+                .collect::<Vec<_>>(),
+        )?;
+        let paymaster_data = alloc_arr(vm, &tx.paymaster_data)?;
+        let account_deployment_data = alloc_arr(vm, &tx.account_deployment_data)?;
+        let tx_info = alloc_data(
+            vm,
+            &[
+                tx.version.into(),
+                tx.account_contract_address.into(),
+                Felt252::from(tx.max_fee).into(),
+                signature.0.into(),
+                signature.1.into(),
+                tx.transaction_hash.into(),
+                tx.chain_id.into(),
+                tx.nonce.into(),
+                resource_bounds.0.into(),
+                resource_bounds.1.into(),
+                Felt252::from(tx.tip).into(),
+                paymaster_data.0.into(),
+                paymaster_data.1.into(),
+                Felt252::from(tx.nonce_data_availability_mode).into(),
+                Felt252::from(tx.fee_data_availability_mode).into(),
+                account_deployment_data.0.into(),
+                account_deployment_data.1.into(),
+            ],
+        )?;
+        let execution_info = alloc_data(
+            vm,
+            &[
+                block_info.into(),
+                tx_info.into(),
+                info.caller_address.into(),
+                info.contract_address.into(),
+                info.entry_point_selector.into(),
+            ],
+        )?;
+        Ok(Ok(vec![execution_info.into()]))
+    }
+
+    fn call_contract(
+        &mut self,
+        vm: &mut VirtualMachine,
+        buffer: &mut SyscallBuffer,
+        gas: &mut usize,
+    ) -> Result<SyscallResult<Vec<MaybeRelocatable>>, HintError> {
+        let contract_address = buffer.next_felt(vm)?;
+        let selector = buffer.next_felt(vm)?;
+        let calldata = buffer.next_arr(vm)?;
+        if let Err(reason) = deduct_gas(gas, gas_costs::CALL_CONTRACT) {
+            return Ok(Err(reason));
+        }
+        match self
+            .syscall_handler
+            .call_contract(contract_address, selector, calldata, gas)?
+        {
+            Ok(retdata) => {
+                let (start, end) = alloc_arr(vm, &retdata)?;
+                Ok(Ok(vec![start.into(), end.into()]))
+            }
+            Err(mut revert_reason) => {
+                revert_reason.push(short_string(b"ENTRYPOINT_FAILED"));
+                Ok(Err(revert_reason))
+            }
+        }
+    }
+}
+
+fn keccak(
+    vm: &VirtualMachine,
+    buffer: &mut SyscallBuffer,
+    gas: &mut usize,
+) -> Result<SyscallResult<Vec<MaybeRelocatable>>, HintError> {
+    let input = buffer.next_arr(vm)?;
+    if input.len() % 17 != 0 {
+        return Ok(Err(vec![short_string(b"Invalid keccak input size")]));
+    }
+    let n_rounds = input.len() / 17;
+    if let Err(reason) = deduct_gas(
+        gas,
+        gas_costs::KECCAK + n_rounds * gas_costs::KECCAK_ROUND_COST,
+    ) {
+        return Ok(Err(reason));
+    }
+    let mut state = [0u64; 25];
+    for chunk in input.chunks(17) {
+        for (word, value) in state.iter_mut().zip(chunk) {
+            *word ^= value
+                .to_u64()
+                .ok_or_else(|| HintError::CustomHint("Invalid keccak input word".into()))?;
+        }
+        keccak::f1600(&mut state);
+    }
+    let low = ((state[1] as u128) << 64) | state[0] as u128;
+    let high = ((state[3] as u128) << 64) | state[2] as u128;
+    Ok(Ok(vec![
+        Felt252::from(low).into(),
+        Felt252::from(high).into(),
+    ]))
+}
+
+fn sha256_process_block(
+    vm: &mut VirtualMachine,
+    buffer: &mut SyscallBuffer,
+    gas: &mut usize,
+) -> Result<SyscallResult<Vec<MaybeRelocatable>>, HintError> {
+    let state_ptr = buffer.next_ptr(vm)?;
+    let input_ptr = buffer.next_ptr(vm)?;
+    if let Err(reason) = deduct_gas(gas, gas_costs::SHA256_PROCESS_BLOCK) {
+        return Ok(Err(reason));
+    }
+    let to_u32 = |value: &Felt252| {
+        value
+            .to_u32()
+            .ok_or_else(|| HintError::CustomHint("Invalid sha256 word".into()))
+    };
+    let mut state = [0u32; 8];
+    for (word, value) in state.iter_mut().zip(vm.get_integer_range(state_ptr, 8)?) {
+        *word = to_u32(&value)?;
+    }
+    let mut block = [0u8; 64];
+    for (bytes, value) in block
+        .chunks_mut(4)
+        .zip(vm.get_integer_range(input_ptr, 16)?)
+    {
+        bytes.copy_from_slice(&to_u32(&value)?.to_be_bytes());
+    }
+    sha2::compress256(&mut state, &[block.into()]);
+    let new_state = alloc_data(vm, &state.map(|word| Felt252::from(word).into()))?;
+    Ok(Ok(vec![new_state.into()]))
+}
+
+// Reads the pointer held by a `[cell]` or `[cell] + offset` operand
+fn get_buffer_ptr(vm: &VirtualMachine, operand: &ResOperand) -> Result<Relocatable, HintError> {
+    let (cell, offset) = match operand {
+        ResOperand::Deref(cell) => (cell, 0),
+        ResOperand::BinOp(BinOpOperand {
+            op: Operation::Add,
+            a,
+            b: DerefOrImmediate::Immediate(offset),
+        }) => (
+            a,
+            offset.value.to_usize().ok_or(HintError::CustomHint(
+                "Illegal argument for a buffer".into(),
+            ))?,
+        ),
+        _ => {
+            return Err(HintError::CustomHint(
+                "Illegal argument for a buffer".into(),
+            ))
+        }
+    };
+    let base = match cell.register {
+        Register::AP => vm.get_ap(),
+        Register::FP => vm.get_fp(),
+    };
+    Ok((vm.get_relocatable((base + cell.offset as i32)?)? + offset)?)
+}
+
+// Deducts `amount` from `gas`, failing the syscall if there isn't enough gas left
+fn deduct_gas(gas: &mut usize, amount: usize) -> SyscallResult<()> {
+    *gas = gas
+        .checked_sub(amount)
+        .ok_or_else(|| vec![short_string(b"Syscall out of gas")])?;
+    Ok(())
+}
+
+// Writes `data` into a new segment, returning its start
+fn alloc_data(
+    vm: &mut VirtualMachine,
+    data: &[MaybeRelocatable],
+) -> Result<Relocatable, HintError> {
+    let start = vm.add_memory_segment();
+    vm.load_data(start, data)?;
+    Ok(start)
+}
+
+// Writes `values` into a new segment, returning its start and end
+fn alloc_arr(
+    vm: &mut VirtualMachine,
+    values: &[Felt252],
+) -> Result<(Relocatable, Relocatable), HintError> {
+    let start = vm.add_memory_segment();
+    let end = vm.load_data(
+        start,
+        &values
+            .iter()
+            .map(MaybeRelocatable::from)
+            .collect::<Vec<_>>(),
+    )?;
+    Ok((start, end))
+}
+
+fn short_string(value: &[u8]) -> Felt252 {
+    Felt252::from_bytes_be_slice(value)
+}
+
+// Reads a syscall request and writes its response
+struct SyscallBuffer {
+    ptr: Relocatable,
+}
+
+impl SyscallBuffer {
+    fn next(&mut self, vm: &VirtualMachine) -> Result<MaybeRelocatable, HintError> {
+        let value = vm
+            .get_maybe(&self.ptr)
+            .ok_or(HintError::CustomHint("Missing syscall argument".into()))?;
+        self.ptr = (self.ptr + 1)?;
+        Ok(value)
+    }
+
+    fn next_felt(&mut self, vm: &VirtualMachine) -> Result<Felt252, HintError> {
+        self.next(vm)?.get_int().ok_or(HintError::CustomHint(
+            "Expected a felt syscall argument".into(),
+        ))
+    }
+
+    fn next_usize(&mut self, vm: &VirtualMachine) -> Result<usize, HintError> {
+        self.next_felt(vm)?.to_usize().ok_or(HintError::CustomHint(
+            "Syscall argument out of range".into(),
+        ))
+    }
+
+    fn next_ptr(&mut self, vm: &VirtualMachine) -> Result<Relocatable, HintError> {
+        self.next(vm)?
+            .get_relocatable()
+            .ok_or(HintError::CustomHint(
+                "Expected a pointer syscall argument".into(),
+            ))
+    }
+
+    // Reads an array given by its start and end pointers
+    fn next_arr(&mut self, vm: &VirtualMachine) -> Result<Vec<Felt252>, HintError> {
+        let start = self.next_ptr(vm)?;
+        let end = self.next_ptr(vm)?;
+        Ok(vm
+            .get_integer_range(start, (end - start)?)?
+            .into_iter()
+            .map(|value| *value)
+            .collect())
+    }
+
+    fn write(
+        &mut self,
+        vm: &mut VirtualMachine,
+        value: impl Into<MaybeRelocatable>,
+    ) -> Result<(), HintError> {
+        vm.insert_value(self.ptr, value.into())?;
+        self.ptr = (self.ptr + 1)?;
+        Ok(())
+    }
+
+    // Writes `values` into a new segment, followed by its start and end pointers
+    fn write_arr(&mut self, vm: &mut VirtualMachine, values: &[Felt252]) -> Result<(), HintError> {
+        let (start, end) = alloc_arr(vm, values)?;
+        self.write(vm, start)?;
+        self.write(vm, end)
+    }
+}
+
+impl HintProcessorLogic for StarknetHintProcessor<'_> {
+    fn compile_hint(
+        &self,
+        hint_code: &str,
+        ap_tracking_data: &ApTracking,
+        reference_ids: &HashMap<String, usize>,
+        references: &[HintReference],
+    ) -> Result<Box<dyn Any>, VirtualMachineError> {
+        self.inner
+            .compile_hint(hint_code, ap_tracking_data, reference_ids, references)
+    }
+
+    fn execute_hint(
+        &mut self,
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        hint_data: &Box<dyn Any>,
+        _constants: &HashMap<String, Felt252>,
+    ) -> Result<(), HintError> {
+        let hints: &Vec<Hint> = hint_data.downcast_ref().ok_or(HintError::WrongHintData)?;
+        for hint in hints {
+            self.execute(vm, exec_scopes, hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl ResourceTracker for StarknetHintProcessor<'_> {
+    fn consumed(&self) -> bool {
+        self.inner.consumed()
+    }
+
+    fn consume_step(&mut self) {
+        self.inner.consume_step()
+    }
+
+    fn get_n_steps(&self) -> Option<usize> {
+        self.inner.get_n_steps()
+    }
+
+    fn run_resources(&self) -> &RunResources {
+        self.inner.run_resources()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cairo_lang_casm::{casm, operand::CellRef};
+    use cairo_lang_starknet_classes::casm_contract_class::{
+        CasmContractEntryPoint, CasmContractEntryPoints,
+    };
+    use cairo_lang_utils::bigint::BigUintAsHex;
+
+    const ECHO: &[u8] = b"echo";
+    const PANIC: &[u8] = b"panic";
+
+    // Contract returning its calldata, as retdata from `echo` and as panic data from `panic`
+    fn echo_contract() -> CasmContractClass {
+        let instructions = casm! {
+            [ap + 0] = [fp + -6], ap++;
+            [ap + 0] = [fp + -5], ap++;
+            [ap + 0] = 0, ap++;
+            [ap + 0] = [fp + -4], ap++;
+            [ap + 0] = [fp + -3], ap++;
+            ret;
+            [ap + 0] = [fp + -6], ap++;
+            [ap + 0] = [fp + -5], ap++;
+            [ap + 0] = 1, ap++;
+            [ap + 0] = [fp + -4], ap++;
+            [ap + 0] = [fp + -3], ap++;
+            ret;
+        }
+        .instructions;
+        let entry_point = |selector, offset| CasmContractEntryPoint {
+            selector: short_string(selector).to_biguint(),
+            offset,
+            builtins: vec![],
+        };
+        CasmContractClass {
+            prime: Felt252::prime(),
+            compiler_version: "2.8.0".to_string(),
+            bytecode: instructions
+                .iter()
+                .flat_map(|instruction| instruction.assemble().encode())
+                .map(|word| BigUintAsHex {
+                    value: Felt252::from(&word).to_biguint(),
+                })
+                .collect(),
+            bytecode_segment_lengths: None,
+            hints: vec![],
+            pythonic_hints: None,
+            entry_points_by_type: CasmContractEntryPoints {
+                external: vec![entry_point(ECHO, 0), entry_point(PANIC, 7)],
+                l1_handler: vec![],
+                constructor: vec![],
+            },
+        }
+    }
+
+    // Executes a syscall with the given request, returning its response
+    fn syscall(
+        vm: &mut VirtualMachine,
+        handler: &mut dyn SyscallHandler,
+        selector: &[u8],
+        gas: usize,
+        request: &[MaybeRelocatable],
+    ) -> Vec<MaybeRelocatable> {
+        let system_ptr = vm.add_memory_segment();
+        let mut data = vec![short_string(selector).into(), Felt252::from(gas).into()];
+        data.extend_from_slice(request);
+        vm.load_data(system_ptr, &data).unwrap();
+        // The hint reads the system pointer from [fp]
+        let fp = system_ptr.segment_index as usize;
+        vm.insert_value((1, fp).into(), system_ptr).unwrap();
+        vm.set_fp(fp);
+
+        let mut hint_processor = StarknetHintProcessor::new(
+            Cairo1HintProcessor::new(&[], RunResources::default(), false),
+            handler,
+        );
+        let hint = Hint::Starknet(StarknetHint::SystemCall {
+            system: ResOperand::Deref(CellRef {
+                register: Register::FP,
+                offset: 0,
+            }),
+        });
+        hint_processor
+            .execute(vm, &mut ExecutionScopes::new(), &hint)
+            .unwrap();
+
+        let mut response = vec![];
+        let mut ptr = (system_ptr + data.len()).unwrap();
+        while let Some(value) = vm.get_maybe(&ptr) {
+            response.push(value);
+            ptr = (ptr + 1_usize).unwrap();
+        }
+        response
+    }
+
+    fn new_vm() -> VirtualMachine {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm
+    }
+
+    fn felts(values: &[u64]) -> Vec<Felt252> {
+        values.iter().map(|value| Felt252::from(*value)).collect()
+    }
+
+    fn alloc(vm: &mut VirtualMachine, values: &[Felt252]) -> [MaybeRelocatable; 2] {
+        let (start, end) = alloc_arr(vm, values).unwrap();
+        [start.into(), end.into()]
+    }
+
+    fn read_arr(
+        vm: &VirtualMachine,
+        start: &MaybeRelocatable,
+        end: &MaybeRelocatable,
+    ) -> Vec<Felt252> {
+        let start = start.get_relocatable().unwrap();
+        let end = end.get_relocatable().unwrap();
+        vm.get_integer_range(start, (end - start).unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|value| *value)
+            .collect()
+    }
+
+    #[test]
+    fn storage_write_and_read() {
+        let mut vm = new_vm();
+        let mut handler = LocalSyscallHandler::new();
+        handler.execution_info.contract_address = Felt252::from(7);
+
+        let request = felts(&[0, 5, 42])
+            .iter()
+            .map(MaybeRelocatable::from)
+            .collect::<Vec<_>>();
+        let response = syscall(&mut vm, &mut handler, b"StorageWrite", 100000, &request);
+        let gas = 100000 - gas_costs::STORAGE_WRITE;
+        assert_eq!(response, vec![gas.into(), 0.into()]);
+        assert_eq!(
+            handler.storage_at(Felt252::from(7), Felt252::from(5)),
+            Felt252::from(42)
+        );
+
+        let response = syscall(
+            &mut vm,
+            &mut handler,
+            b"StorageRead",
+            gas,
+            &[0.into(), 5.into()],
+        );
+        let gas = gas - gas_costs::STORAGE_READ;
+        assert_eq!(response, vec![gas.into(), 0.into(), 42.into()]);
+
+        // Only the address domain 0 is supported
+        let response = syscall(
+            &mut vm,
+            &mut handler,
+            b"StorageRead",
+            gas,
+            &[1.into(), 5.into()],
+        );
+        assert_eq!(
+            response[..2],
+            [(gas - gas_costs::STORAGE_READ).into(), 1.into()]
+        );
+        assert_eq!(
+            read_arr(&vm, &response[2], &response[3]),
+            vec![short_string(b"Unsupported address domain")]
+        );
+    }
+
+    #[test]
+    fn syscall_out_of_gas() {
+        let mut vm = new_vm();
+        let mut handler = LocalSyscallHandler::new();
+        let gas = gas_costs::STORAGE_WRITE - 1;
+        let response = syscall(
+            &mut vm,
+            &mut handler,
+            b"StorageWrite",
+            gas,
+            &[0.into(), 5.into(), 42.into()],
+        );
+        assert_eq!(response[..2], [gas.into(), 1.into()]);
+        assert_eq!(
+            read_arr(&vm, &response[2], &response[3]),
+            vec![short_string(b"Syscall out of gas")]
+        );
+        assert!(handler.storage.is_empty());
+    }
+
+    #[test]
+    fn emit_event_and_get_execution_info() {
+        let mut vm = new_vm();
+        let mut handler = LocalSyscallHandler::new();
+        handler.execution_info = ExecutionInfo {
+            block_info: BlockInfo {
+                block_number: 10,
+                block_timestamp: 20,
+                sequencer_address: Felt252::from(30),
+            },
+            tx_info: TxInfo {
+                version: Felt252::from(3),
+                signature: felts(&[4, 5]),
+                ..Default::default()
+            },
+            caller_address: Felt252::from(1),
+            contract_address: Felt252::from(2),
+            entry_point_selector: short_string(ECHO),
+        };
+
+        let mut request = alloc(&mut vm, &felts(&[1])).to_vec();
+        request.extend(alloc(&mut vm, &felts(&[2, 3])));
+        let response = syscall(&mut vm, &mut handler, b"EmitEvent", 100000, &request);
+        assert_eq!(
+            response,
+            vec![(100000 - gas_costs::EMIT_EVENT).into(), 0.into()]
+        );
+        assert_eq!(
+            handler.events,
+            vec![Event {
+                from_address: Felt252::from(2),
+                keys: felts(&[1]),
+                data: felts(&[2, 3]),
+            }]
+        );
+
+        let response = syscall(&mut vm, &mut handler, b"GetExecutionInfo", 100000, &[]);
+        assert_eq!(
+            response[..2],
+            [(100000 - gas_costs::GET_EXECUTION_INFO).into(), 0.into()]
+        );
+        let info = vm
+            .get_continuous_range(response[2].get_relocatable().unwrap(), 5)
+            .unwrap();
+        assert_eq!(info[2..], [1.into(), 2.into(), short_string(ECHO).into()]);
+        let block_info = vm
+            .get_continuous_range(info[0].get_relocatable().unwrap(), 3)
+            .unwrap();
+        assert_eq!(block_info, vec![10.into(), 20.into(), 30.into()]);
+        let tx_info = vm
+            .get_continuous_range(info[1].get_relocatable().unwrap(), 17)
+            .unwrap();
+        assert_eq!(tx_info[0], 3.into());
+        assert_eq!(read_arr(&vm, &tx_info[3], &tx_info[4]), felts(&[4, 5]));
+        assert_eq!(read_arr(&vm, &tx_info[8], &tx_info[9]), vec![]);
+    }
+
+    #[test]
+    fn keccak_and_sha256_syscalls() {
+        let mut vm = new_vm();
+        let mut handler = LocalSyscallHandler::new();
+
+        // Keccak of the empty input, padded to a single block
+        let mut block = felts(&[1]);
+        block.resize(16, Felt252::ZERO);
+        block.push(Felt252::from(0x8000000000000000_u64));
+        let request = alloc(&mut vm, &block);
+        let gas = 1000000;
+        let response = syscall(&mut vm, &mut handler, b"Keccak", gas, &request);
+        assert_eq!(
+            response,
+            vec![
+                (gas - gas_costs::KECCAK_ROUND_COST).into(),
+                0.into(),
+                Felt252::from(0xc003c7dcb27d7e923c23f7860146d2c5_u128).into(),
+                Felt252::from(0x70a4855d04d8fa7b3b2782ca53b600e5_u128).into(),
+            ]
+        );
+
+        let request = alloc(&mut vm, &block[..16]);
+        let response = syscall(&mut vm, &mut handler, b"Keccak", gas, &request);
+        assert_eq!(response[..2], [gas.into(), 1.into()]);
+
+        // Sha256 of the empty input, padded to a single block
+        let state = alloc(
+            &mut vm,
+            &felts(&[
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ]),
+        );
+        let mut block = felts(&[0x80000000]);
+        block.resize(16, Felt252::ZERO);
+        let input = alloc(&mut vm, &block);
+        let response = syscall(
+            &mut vm,
+            &mut handler,
+            b"Sha256ProcessBlock",
+            gas,
+            &[state[0].clone(), input[0].clone()],
+        );
+        assert_eq!(
+            response[..2],
+            [(gas - gas_costs::SHA256_PROCESS_BLOCK).into(), 0.into()]
+        );
+        let digest = vm
+            .get_continuous_range(response[2].get_relocatable().unwrap(), 8)
+            .unwrap();
+        assert_eq!(
+            digest,
+            felts(&[
+                0xe3b0c442, 0x98fc1c14, 0x9afbf4c8, 0x996fb924, 0x27ae41e4, 0x649b934c, 0xa495991b,
+                0x7852b855,
+            ])
+            .iter()
+            .map(MaybeRelocatable::from)
+            .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn call_registered_contract() {
+        let mut vm = new_vm();
+        let mut handler = LocalSyscallHandler::new();
+        handler.register_contract(Felt252::from(0x1234), echo_contract());
+
+        let mut request = vec![0x1234.into(), short_string(ECHO).into()];
+        request.extend(alloc(&mut vm, &felts(&[1, 2, 3])));
+        let gas = 100000;
+        let response = syscall(&mut vm, &mut handler, b"CallContract", gas, &request);
+        assert_eq!(
+            response[..2],
+            [(gas - gas_costs::CALL_CONTRACT).into(), 0.into()]
+        );
+        assert_eq!(read_arr(&vm, &response[2], &response[3]), felts(&[1, 2, 3]));
+
+        // Calling a contract that isn't registered fails
+        request[0] = 0x5678.into();
+        let response = syscall(&mut vm, &mut handler, b"CallContract", gas, &request);
+        assert_eq!(response[1], 1.into());
+        assert_eq!(
+            read_arr(&vm, &response[2], &response[3]),
+            vec![
+                short_string(b"CONTRACT_NOT_DEPLOYED"),
+                short_string(b"ENTRYPOINT_FAILED")
+            ]
+        );
+    }
+
+    #[test]
+    fn call_contract_entry_points() {
+        let mut handler = LocalSyscallHandler::new();
+        handler.register_contract(Felt252::from(0x1234), echo_contract());
+        let calldata = felts(&[4, 5]);
+        assert_eq!(
+            handler
+                .call(Felt252::from(0x1234), short_string(ECHO), &calldata)
+                .unwrap(),
+            Ok(calldata.clone())
+        );
+        assert_eq!(
+            handler
+                .call(Felt252::from(0x1234), short_string(PANIC), &calldata)
+                .unwrap(),
+            Err(calldata.clone())
+        );
+        assert_eq!(
+            handler
+                .call(Felt252::from(0x1234), short_string(b"missing"), &calldata)
+                .unwrap(),
+            Err(vec![short_string(b"ENTRYPOINT_NOT_FOUND")])
+        );
+        // The execution info is restored after the call
+        assert_eq!(handler.execution_info, ExecutionInfo::default());
+    }
+}
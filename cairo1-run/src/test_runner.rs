@@ -0,0 +1,612 @@
+//! Runner of Cairo 1 test functions
+//!
+//! [compile_tests] compiles a Cairo 1 crate with the `test` configuration, and collects the
+//! functions marked with `#[test]`, along with their `#[should_panic]`, `#[available_gas]` and
+//! `#[ignore]` attributes. [run_tests] then runs each of them in a separate VM, through the same
+//! [Cairo1HintProcessor](cairo_vm::hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor)
+//! used by [cairo_run_program](crate::cairo_run_program).
+
+use crate::{
+    cairo_run::{cairo_run_function, Cairo1RunConfig},
+    error::Error,
+    gas::{out_of_gas_panic_data, DEFAULT_INITIAL_GAS},
+};
+use cairo_lang_compiler::{
+    compile_prepared_db, db::RootDatabase, project::setup_project, CompilerConfig,
+};
+use cairo_lang_defs::{
+    db::DefsGroup,
+    ids::TopLevelLanguageElementId,
+    plugin::{MacroPlugin, MacroPluginMetadata, PluginResult},
+};
+use cairo_lang_filesystem::cfg::{Cfg, CfgSet};
+use cairo_lang_semantic::{items::attribute::SemanticQueryAttrs, plugin::PluginSuite};
+use cairo_lang_sierra::program::Program as SierraProgram;
+use cairo_lang_syntax::{
+    attribute::structured::{Attribute, AttributeArgVariant},
+    node::{ast, db::SyntaxGroup},
+};
+use cairo_vm::{types::layout_name::LayoutName, Felt252};
+use num_traits::ToPrimitive;
+use std::{fmt, path::Path};
+
+const TEST_ATTR: &str = "test";
+const SHOULD_PANIC_ATTR: &str = "should_panic";
+const AVAILABLE_GAS_ATTR: &str = "available_gas";
+const IGNORE_ATTR: &str = "ignore";
+
+/// First felt of the panic data of a `ByteArray` (e.g. the message of `panic!`)
+const BYTE_ARRAY_MAGIC: &str = "46a6158a16a947e5916b2a2ca68501a45e93d7110e81aa2d6438b1c57c879a3";
+const BYTES_IN_WORD: usize = 31;
+
+/// A function marked with `#[test]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    /// Full path of the function
+    pub name: String,
+    /// Gas available to the test, set with `#[available_gas(amount)]`
+    pub available_gas: Option<usize>,
+    pub expectation: TestExpectation,
+    /// Marked with `#[ignore]`
+    pub ignored: bool,
+}
+
+/// Expected outcome of a test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestExpectation {
+    Success,
+    /// Marked with `#[should_panic]`, with the expected panic data if set with `#[should_panic(expected: ...)]`
+    Panics(Option<Vec<Felt252>>),
+}
+
+/// Outcome of a test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestStatus {
+    Passed,
+    /// Holds the reason of the failure
+    Failed(String),
+    Ignored,
+}
+
+/// Configuration parameters for a test run
+#[derive(Debug)]
+pub struct TestRunConfig<'a> {
+    /// Only run the tests whose name contains this string
+    pub filter: Option<&'a str>,
+    /// Also run the tests marked with `#[ignore]`
+    pub include_ignored: bool,
+    /// Cairo layout used to run each test
+    pub layout: LayoutName,
+}
+
+impl Default for TestRunConfig<'_> {
+    fn default() -> Self {
+        Self {
+            filter: None,
+            include_ignored: false,
+            layout: LayoutName::all_cairo,
+        }
+    }
+}
+
+/// Outcome of each test of a test run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestSummary {
+    pub results: Vec<(String, TestStatus)>,
+}
+
+impl TestSummary {
+    pub fn passed(&self) -> usize {
+        self.count(|status| matches!(status, TestStatus::Passed))
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|status| matches!(status, TestStatus::Failed(_)))
+    }
+
+    pub fn ignored(&self) -> usize {
+        self.count(|status| matches!(status, TestStatus::Ignored))
+    }
+
+    fn count(&self, f: impl Fn(&TestStatus) -> bool) -> usize {
+        self.results.iter().filter(|(_, status)| f(status)).count()
+    }
+}
+
+impl fmt::Display for TestSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "running {} tests", self.results.len())?;
+        for (name, status) in self.results.iter() {
+            let status = match status {
+                TestStatus::Passed => "ok",
+                TestStatus::Failed(_) => "fail",
+                TestStatus::Ignored => "ignored",
+            };
+            writeln!(f, "test {name} ... {status}")?;
+        }
+        if self.failed() > 0 {
+            writeln!(f, "failures:")?;
+            for (name, status) in self.results.iter() {
+                if let TestStatus::Failed(reason) = status {
+                    writeln!(f, "   {name} - {reason}")?;
+                }
+            }
+        }
+        writeln!(
+            f,
+            "test result: {}. {} passed; {} failed; {} ignored",
+            if self.failed() > 0 { "FAILED" } else { "ok" },
+            self.passed(),
+            self.failed(),
+            self.ignored()
+        )
+    }
+}
+
+/// Declares the attributes of test functions, so that they compile without the test plugin of the Cairo compiler
+#[derive(Debug, Default)]
+struct TestAttributesPlugin;
+
+impl MacroPlugin for TestAttributesPlugin {
+    fn generate_code(
+        &self,
+        _db: &dyn SyntaxGroup,
+        _item_ast: ast::ModuleItem,
+        _metadata: &MacroPluginMetadata<'_>,
+    ) -> PluginResult {
+        PluginResult::default()
+    }
+
+    fn declared_attributes(&self) -> Vec<String> {
+        [
+            TEST_ATTR,
+            SHOULD_PANIC_ATTR,
+            AVAILABLE_GAS_ATTR,
+            IGNORE_ATTR,
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+}
+
+/// Compiles the Cairo 1 crate at `path` (a cairo file, or a directory with a `cairo_project.toml`)
+/// with the `test` configuration, and returns the compiled program and its test functions
+pub fn compile_tests(path: &Path) -> Result<(SierraProgram, Vec<TestCase>), Error> {
+    let mut plugin_suite = PluginSuite::default();
+    plugin_suite.add_plugin::<TestAttributesPlugin>();
+    let mut db = RootDatabase::builder()
+        .detect_corelib()
+        .with_cfg(CfgSet::from_iter([Cfg::name("test")]))
+        .with_plugin_suite(plugin_suite)
+        .build()
+        .map_err(|err| Error::SierraCompilation(err.to_string()))?;
+    let main_crate_ids =
+        setup_project(&mut db, path).map_err(|err| Error::SierraCompilation(err.to_string()))?;
+    let compiler_config = CompilerConfig {
+        replace_ids: true,
+        ..CompilerConfig::default()
+    };
+    let sierra_program = compile_prepared_db(&db, main_crate_ids.clone(), compiler_config)
+        .map_err(|err| Error::SierraCompilation(err.to_string()))?
+        .program;
+
+    let mut tests = Vec::new();
+    for crate_id in main_crate_ids {
+        for module_id in db.crate_modules(crate_id).iter() {
+            let Ok(free_functions) = db.module_free_functions_ids(*module_id) else {
+                continue;
+            };
+            for free_function_id in free_functions.iter() {
+                let attr = |name| free_function_id.find_attr(&db, name).ok().flatten();
+                if attr(TEST_ATTR).is_none() {
+                    continue;
+                }
+                let name = free_function_id.full_path(&db);
+                let invalid_attr = |attr_name: &str| {
+                    Error::SierraCompilation(format!(
+                        "Invalid #[{attr_name}] attribute of test {name}"
+                    ))
+                };
+                let available_gas = attr(AVAILABLE_GAS_ATTR)
+                    .map(|attr| {
+                        available_gas(&db, &attr).ok_or_else(|| invalid_attr(AVAILABLE_GAS_ATTR))
+                    })
+                    .transpose()?;
+                let expectation = match attr(SHOULD_PANIC_ATTR) {
+                    Some(attr) => TestExpectation::Panics(
+                        expected_panic_data(&db, &attr)
+                            .ok_or_else(|| invalid_attr(SHOULD_PANIC_ATTR))?,
+                    ),
+                    None => TestExpectation::Success,
+                };
+                tests.push(TestCase {
+                    name,
+                    available_gas,
+                    expectation,
+                    ignored: attr(IGNORE_ATTR).is_some(),
+                });
+            }
+        }
+    }
+    Ok((sierra_program, tests))
+}
+
+// Parses `#[available_gas(amount)]`
+fn available_gas(db: &dyn SyntaxGroup, attr: &Attribute) -> Option<usize> {
+    match &attr.args[..] {
+        [arg] => match &arg.variant {
+            AttributeArgVariant::Unnamed(ast::Expr::Literal(literal)) => {
+                literal.numeric_value(db)?.to_usize()
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Parses `#[should_panic]` and `#[should_panic(expected: data)]`, where data is a felt, a short
+// string, a tuple of them, or a string
+fn expected_panic_data(db: &dyn SyntaxGroup, attr: &Attribute) -> Option<Option<Vec<Felt252>>> {
+    match &attr.args[..] {
+        [] => Some(None),
+        [arg] => match &arg.variant {
+            AttributeArgVariant::Named { value, name } if name.text == "expected" => {
+                let data = match value {
+                    ast::Expr::Tuple(tuple) => tuple
+                        .expressions(db)
+                        .elements(db)
+                        .iter()
+                        .map(|expr| felt_value(db, expr))
+                        .collect::<Option<Vec<_>>>()?,
+                    ast::Expr::String(string) => byte_array_panic_data(&string.string_value(db)?),
+                    expr => vec![felt_value(db, expr)?],
+                };
+                Some(Some(data))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn felt_value(db: &dyn SyntaxGroup, expr: &ast::Expr) -> Option<Felt252> {
+    let value = match expr {
+        ast::Expr::Literal(literal) => literal.numeric_value(db)?,
+        ast::Expr::ShortString(short_string) => short_string.numeric_value(db)?,
+        _ => return None,
+    };
+    Some(Felt252::from(value))
+}
+
+// Serializes a string as a `ByteArray` panic
+fn byte_array_panic_data(string: &str) -> Vec<Felt252> {
+    let chunks: Vec<_> = string.as_bytes().chunks(BYTES_IN_WORD).collect();
+    let (full_words, pending_word) = match chunks.last() {
+        Some(last) if last.len() < BYTES_IN_WORD => (&chunks[..chunks.len() - 1], *last),
+        _ => (&chunks[..], &[][..]),
+    };
+    let mut data = vec![
+        Felt252::from_hex_unchecked(BYTE_ARRAY_MAGIC),
+        Felt252::from(full_words.len()),
+    ];
+    data.extend(
+        full_words
+            .iter()
+            .map(|word| Felt252::from_bytes_be_slice(word)),
+    );
+    data.push(Felt252::from_bytes_be_slice(pending_word));
+    data.push(Felt252::from(pending_word.len()));
+    data
+}
+
+/// Runs each test of `tests` in a separate VM
+pub fn run_tests(
+    sierra_program: &SierraProgram,
+    tests: &[TestCase],
+    config: &TestRunConfig,
+) -> TestSummary {
+    let results = tests
+        .iter()
+        .filter(|test| {
+            config
+                .filter
+                .map_or(true, |filter| test.name.contains(filter))
+        })
+        .map(|test| {
+            let status = if test.ignored && !config.include_ignored {
+                TestStatus::Ignored
+            } else {
+                run_test(sierra_program, test, config.layout)
+            };
+            (test.name.clone(), status)
+        })
+        .collect();
+    TestSummary { results }
+}
+
+/// Runs a single test, checking its outcome against its expectation
+pub fn run_test(sierra_program: &SierraProgram, test: &TestCase, layout: LayoutName) -> TestStatus {
+    let Some(func) = sierra_program
+        .funcs
+        .iter()
+        .find(|func| func.id.debug_name.as_deref() == Some(test.name.as_str()))
+    else {
+        return TestStatus::Failed("Test function not found in the program".to_string());
+    };
+    let cairo_run_config = Cairo1RunConfig {
+        layout,
+        initial_gas: Some(test.available_gas.unwrap_or(DEFAULT_INITIAL_GAS)),
+        ..Default::default()
+    };
+    let panic_data = match cairo_run_function(sierra_program, func, cairo_run_config) {
+        Ok(_) => None,
+        Err(Error::RunPanic(panic_data)) => Some(panic_data),
+        Err(Error::OutOfGas(_)) => Some(out_of_gas_panic_data().to_vec()),
+        Err(err) => return TestStatus::Failed(err.to_string()),
+    };
+    match (&test.expectation, panic_data) {
+        (TestExpectation::Success, None) | (TestExpectation::Panics(None), Some(_)) => {
+            TestStatus::Passed
+        }
+        (TestExpectation::Success, Some(panic_data)) => {
+            TestStatus::Failed(format!("Panicked with {}", format_panic_data(&panic_data)))
+        }
+        (TestExpectation::Panics(_), None) => {
+            TestStatus::Failed("Expected panic, but the test didn't panic".to_string())
+        }
+        (TestExpectation::Panics(Some(expected)), Some(panic_data)) => {
+            if *expected == panic_data {
+                TestStatus::Passed
+            } else {
+                TestStatus::Failed(format!(
+                    "Panicked with {} instead of the expected {}",
+                    format_panic_data(&panic_data),
+                    format_panic_data(expected)
+                ))
+            }
+        }
+    }
+}
+
+/// Formats panic data as a tuple of hex felts, each followed by its decoding as a short string
+/// (if it is one). `ByteArray` panics are formatted as the string they hold
+pub fn format_panic_data(panic_data: &[Felt252]) -> String {
+    if let Some(string) = byte_array_string(panic_data) {
+        return format!("{string:?}");
+    }
+    let felts: Vec<_> = panic_data
+        .iter()
+        .map(|felt| match short_string(felt) {
+            Some(string) => format!("{felt:#x} ('{string}')"),
+            None => format!("{felt:#x}"),
+        })
+        .collect();
+    format!("({})", felts.join(", "))
+}
+
+// Decodes a felt as a short string, if it's made of printable ascii characters
+fn short_string(felt: &Felt252) -> Option<String> {
+    let bytes = felt.to_bytes_be();
+    let start = bytes.iter().position(|byte| *byte != 0)?;
+    bytes[start..]
+        .iter()
+        .all(|byte| byte.is_ascii_graphic() || *byte == b' ')
+        .then(|| String::from_utf8_lossy(&bytes[start..]).into_owned())
+}
+
+// Decodes the panic data of a `ByteArray` panic
+fn byte_array_string(panic_data: &[Felt252]) -> Option<String> {
+    let (magic, data) = panic_data.split_first()?;
+    if *magic != Felt252::from_hex_unchecked(BYTE_ARRAY_MAGIC) {
+        return None;
+    }
+    let (full_words_len, data) = data.split_first()?;
+    let full_words_len = full_words_len.to_usize()?;
+    let [full_words @ .., pending_word, pending_word_len] = data else {
+        return None;
+    };
+    if full_words.len() != full_words_len {
+        return None;
+    }
+    let pending_word_len = pending_word_len
+        .to_usize()
+        .filter(|len| *len < BYTES_IN_WORD)?;
+    let mut bytes = Vec::new();
+    for word in full_words {
+        bytes.extend_from_slice(&word.to_bytes_be()[32 - BYTES_IN_WORD..]);
+    }
+    bytes.extend_from_slice(&pending_word.to_bytes_be()[32 - pending_word_len..]);
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("", 0)]
+    #[case("short", 0)]
+    #[case("exactly thirty one bytes long!!", 1)]
+    #[case("a string that is longer than thirty one bytes", 1)]
+    fn byte_array_panic_data_roundtrip(#[case] string: &str, #[case] full_words: usize) {
+        let panic_data = byte_array_panic_data(string);
+        assert_eq!(panic_data[1], Felt252::from(full_words));
+        assert_eq!(panic_data.len(), full_words + 4);
+        assert_eq!(byte_array_string(&panic_data).as_deref(), Some(string));
+        assert_eq!(format_panic_data(&panic_data), format!("{string:?}"));
+    }
+
+    #[test]
+    fn format_short_strings() {
+        let panic_data = [
+            Felt252::from_bytes_be_slice(b"Out of gas"),
+            Felt252::from(2),
+        ];
+        assert_eq!(
+            format_panic_data(&panic_data),
+            "(0x4f7574206f6620676173 ('Out of gas'), 0x2)"
+        );
+        assert_eq!(format_panic_data(&[]), "()");
+    }
+
+    #[test]
+    fn test_summary_counts() {
+        let summary = TestSummary {
+            results: vec![
+                ("a::passes".to_string(), TestStatus::Passed),
+                (
+                    "a::fails".to_string(),
+                    TestStatus::Failed("reason".to_string()),
+                ),
+                ("a::ignored".to_string(), TestStatus::Ignored),
+            ],
+        };
+        assert_eq!(
+            (summary.passed(), summary.failed(), summary.ignored()),
+            (1, 1, 1)
+        );
+        assert_eq!(
+            summary.to_string(),
+            "running 3 tests\n\
+             test a::passes ... ok\n\
+             test a::fails ... fail\n\
+             test a::ignored ... ignored\n\
+             failures:\n   a::fails - reason\n\
+             test result: FAILED. 1 passed; 1 failed; 1 ignored\n"
+        );
+    }
+
+    // Test functions test::passes, which returns (), and test::panics, which panics with 'boom'
+    const TEST_FUNCTIONS_PROGRAM: &str = "
+        type felt252 = felt252;
+        type Unit = Struct<ut@Tuple>;
+        type ArrayFelt = Array<felt252>;
+        type Inner = Struct<ut@Tuple, Unit>;
+        type Panic = Struct<ut@core::panics::Panic>;
+        type PanicData = Struct<ut@Tuple, Panic, ArrayFelt>;
+        type core::panics::PanicResult::<((),)> = Enum<ut@core::panics::PanicResult::<((),)>, Inner, PanicData>;
+
+        libfunc construct_unit = struct_construct<Unit>;
+        libfunc construct_inner = struct_construct<Inner>;
+        libfunc construct_panic = struct_construct<Panic>;
+        libfunc construct_panic_data = struct_construct<PanicData>;
+        libfunc array_new = array_new<felt252>;
+        libfunc array_append = array_append<felt252>;
+        libfunc felt252_const_boom = felt252_const<1651470189>;
+        libfunc store_temp_felt = store_temp<felt252>;
+        libfunc init_ok = enum_init<core::panics::PanicResult::<((),)>, 0>;
+        libfunc init_err = enum_init<core::panics::PanicResult::<((),)>, 1>;
+        libfunc store_temp_result = store_temp<core::panics::PanicResult::<((),)>>;
+
+        construct_unit() -> (unit);
+        construct_inner(unit) -> (inner);
+        init_ok(inner) -> (r);
+        store_temp_result(r) -> (r);
+        return(r);
+        array_new() -> (data);
+        felt252_const_boom() -> (msg);
+        store_temp_felt(msg) -> (msg);
+        array_append(data, msg) -> (data);
+        construct_panic() -> (panic);
+        construct_panic_data(panic, data) -> (err);
+        init_err(err) -> (r);
+        store_temp_result(r) -> (r);
+        return(r);
+
+        test::passes@0() -> (core::panics::PanicResult::<((),)>);
+        test::panics@5() -> (core::panics::PanicResult::<((),)>);
+    ";
+
+    fn boom() -> Vec<Felt252> {
+        vec![Felt252::from_bytes_be_slice(b"boom")]
+    }
+
+    #[rstest]
+    #[case("test::passes", TestExpectation::Success, TestStatus::Passed)]
+    #[case("test::passes", TestExpectation::Panics(None), TestStatus::Failed("Expected panic, but the test didn't panic".to_string()))]
+    #[case("test::panics", TestExpectation::Panics(None), TestStatus::Passed)]
+    #[case(
+        "test::panics",
+        TestExpectation::Panics(Some(boom())),
+        TestStatus::Passed
+    )]
+    #[case("test::panics", TestExpectation::Success, TestStatus::Failed("Panicked with (0x626f6f6d ('boom'))".to_string()))]
+    #[case(
+        "test::panics",
+        TestExpectation::Panics(Some(vec![Felt252::ONE])),
+        TestStatus::Failed("Panicked with (0x626f6f6d ('boom')) instead of the expected (0x1)".to_string())
+    )]
+    #[case("test::missing", TestExpectation::Success, TestStatus::Failed("Test function not found in the program".to_string()))]
+    fn run_test_checks_expectation(
+        #[case] name: &str,
+        #[case] expectation: TestExpectation,
+        #[case] expected_status: TestStatus,
+    ) {
+        let sierra_program = cairo_lang_sierra::ProgramParser::new()
+            .parse(TEST_FUNCTIONS_PROGRAM)
+            .unwrap();
+        let test = TestCase {
+            name: name.to_string(),
+            available_gas: None,
+            expectation,
+            ignored: false,
+        };
+        assert_eq!(
+            run_test(&sierra_program, &test, LayoutName::all_cairo),
+            expected_status
+        );
+    }
+
+    #[test]
+    fn run_tests_filters_and_ignores() {
+        let sierra_program = cairo_lang_sierra::ProgramParser::new()
+            .parse(TEST_FUNCTIONS_PROGRAM)
+            .unwrap();
+        let test = |name: &str, ignored| TestCase {
+            name: name.to_string(),
+            available_gas: None,
+            expectation: TestExpectation::Success,
+            ignored,
+        };
+        let tests = [
+            test("test::passes", false),
+            test("test::panics", true),
+            test("other::passes", false),
+        ];
+        let config = TestRunConfig {
+            filter: Some("test::"),
+            ..Default::default()
+        };
+        let summary = run_tests(&sierra_program, &tests, &config);
+        assert_eq!(
+            summary.results,
+            vec![
+                ("test::passes".to_string(), TestStatus::Passed),
+                ("test::panics".to_string(), TestStatus::Ignored),
+            ]
+        );
+        let config = TestRunConfig {
+            include_ignored: true,
+            ..Default::default()
+        };
+        let summary = run_tests(&sierra_program, &tests, &config);
+        assert_eq!(
+            (summary.passed(), summary.failed(), summary.ignored()),
+            (1, 2, 0)
+        );
+    }
+
+    #[test]
+    fn compile_and_run_tests() {
+        let (sierra_program, tests) = compile_tests(Path::new(
+            "../cairo_programs/cairo-1-programs/tests/test_functions.cairo",
+        ))
+        .unwrap();
+        assert_eq!(tests.len(), 5);
+        let summary = run_tests(&sierra_program, &tests, &TestRunConfig::default());
+        assert_eq!(summary.passed(), 4);
+        assert_eq!(summary.failed(), 0);
+        assert_eq!(summary.ignored(), 1);
+    }
+}
@@ -0,0 +1,558 @@
+//! Conversion between JSON values and the memory representation of the Sierra types of the
+//! arguments and return values of a program
+//!
+//! Values are represented as follows:
+//! * Felts and integers (including `u256` and bounded integers) are JSON numbers, or strings
+//!   holding decimal or `0x` prefixed hexadecimal numbers. Negative values are accepted for
+//!   signed integers and felts.
+//! * `bool` values are JSON booleans.
+//! * Structs and tuples are arrays with the values of their members.
+//! * Arrays and spans are arrays with the values of their elements.
+//! * `Option` values are `null` for `None`, and the inner value for `Some`.
+//! * `Result` values are `{"Ok": value}` or `{"Err": value}`.
+//! * Other enums are `{"variant": index, "value": value}`, where `value` can be left out for
+//!   variants without data.
+//! * Boxes, snapshots and `NonZero` values are their inner value, as are `Nullable` values, which
+//!   can also be `null`.
+
+use crate::error::Error;
+use arbitrary::{Arbitrary, Unstructured};
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+    extensions::types::TypeInfo,
+    ids::ConcreteTypeId,
+    program::GenericArg,
+    program_registry::ProgramRegistry,
+};
+use cairo_lang_utils::unordered_hash_map::UnorderedHashMap;
+use cairo_vm::{
+    math_utils::signed_felt,
+    types::relocatable::{MaybeRelocatable, Relocatable},
+    utils::CAIRO_PRIME,
+    vm::vm_core::VirtualMachine,
+    Felt252,
+};
+use num_bigint::{BigInt, Sign};
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use serde_json::{json, Map, Value};
+
+// Maximum nesting of the values generated by [SierraTypes::arbitrary_value]
+const MAX_ARBITRARY_VALUE_DEPTH: usize = 32;
+
+/// Where the elements of the arrays are found when reading a value
+#[derive(Clone, Copy)]
+pub(crate) enum ArrayLayout<'a> {
+    /// In the memory of the vm, with the array being a pair of `(start, end)` pointers
+    Memory(&'a VirtualMachine),
+    /// Right after the length of the array, as they are copied into the output segment
+    Inlined,
+}
+
+/// The types of a Sierra program, together with their sizes
+pub(crate) struct SierraTypes<'a> {
+    registry: &'a ProgramRegistry<CoreType, CoreLibfunc>,
+    type_sizes: &'a UnorderedHashMap<ConcreteTypeId, i16>,
+}
+
+impl<'a> SierraTypes<'a> {
+    pub(crate) fn new(
+        registry: &'a ProgramRegistry<CoreType, CoreLibfunc>,
+        type_sizes: &'a UnorderedHashMap<ConcreteTypeId, i16>,
+    ) -> Self {
+        SierraTypes {
+            registry,
+            type_sizes,
+        }
+    }
+
+    fn get(&self, ty: &ConcreteTypeId) -> Result<&'a CoreTypeConcrete, Error> {
+        Ok(self.registry.get_type(ty)?)
+    }
+
+    /// Amount of memory cells taken by a value of type `ty`
+    pub(crate) fn size(&self, ty: &ConcreteTypeId) -> Result<usize, Error> {
+        self.type_sizes
+            .get(ty)
+            .and_then(|size| size.to_usize())
+            .ok_or_else(|| Error::NoTypeSizeForId(ty.clone()))
+    }
+
+    /// Returns true if the values of type `ty` don't contain pointers, so that they can be copied
+    /// as they are into the output segment
+    pub(crate) fn is_flat(&self, ty: &ConcreteTypeId) -> bool {
+        match self.get(ty) {
+            Ok(
+                CoreTypeConcrete::Felt252(_)
+                | CoreTypeConcrete::Uint8(_)
+                | CoreTypeConcrete::Uint16(_)
+                | CoreTypeConcrete::Uint32(_)
+                | CoreTypeConcrete::Uint64(_)
+                | CoreTypeConcrete::Uint128(_)
+                | CoreTypeConcrete::Sint8(_)
+                | CoreTypeConcrete::Sint16(_)
+                | CoreTypeConcrete::Sint32(_)
+                | CoreTypeConcrete::Sint64(_)
+                | CoreTypeConcrete::Sint128(_)
+                | CoreTypeConcrete::Bytes31(_)
+                | CoreTypeConcrete::BoundedInt(_),
+            ) => true,
+            Ok(CoreTypeConcrete::Struct(info)) => {
+                info.members.iter().all(|member| self.is_flat(member))
+            }
+            Ok(CoreTypeConcrete::Enum(info)) => {
+                info.variants.iter().all(|variant| self.is_flat(variant))
+            }
+            Ok(CoreTypeConcrete::Snapshot(info)) => self.is_flat(&info.ty),
+            Ok(CoreTypeConcrete::NonZero(info)) => self.is_flat(&info.ty),
+            _ => false,
+        }
+    }
+
+    /// Returns the type of the elements if `ty` is an array, a snapshot of an array or a span
+    pub(crate) fn array_element(&self, ty: &ConcreteTypeId) -> Option<&'a ConcreteTypeId> {
+        match self.get(ty).ok()? {
+            CoreTypeConcrete::Array(info) => Some(&info.ty),
+            CoreTypeConcrete::Snapshot(info) => self.array_element(&info.ty),
+            CoreTypeConcrete::Struct(info) if is_span(&info.info) => {
+                self.array_element(&info.members[0])
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if the values of type `ty` can be copied into the output segment, that is, if
+    /// they are flat or arrays of flat values
+    pub(crate) fn is_serializable(&self, ty: &ConcreteTypeId) -> bool {
+        self.is_flat(ty)
+            || self
+                .array_element(ty)
+                .is_some_and(|element| self.is_flat(element))
+    }
+
+    /// Appends the memory representation of `value` as a value of type `ty` to `cells`.
+    /// The contents of arrays and boxes are loaded into new segments of the vm.
+    pub(crate) fn write_value(
+        &self,
+        vm: &mut VirtualMachine,
+        ty: &ConcreteTypeId,
+        value: &Value,
+        cells: &mut Vec<MaybeRelocatable>,
+    ) -> Result<(), Error> {
+        let invalid_value = || Error::InvalidArgumentValue {
+            ty: ty.clone(),
+            value: value.to_string().into_boxed_str(),
+        };
+        match self.get(ty)? {
+            CoreTypeConcrete::Struct(info) if is_u256(&info.info) => {
+                let value = parse_integer(value)
+                    .filter(|n| !n.is_negative() && n.bits() <= 256)
+                    .ok_or_else(invalid_value)?;
+                let mask = (BigInt::one() << 128) - 1;
+                cells.push(Felt252::from(&value & &mask).into());
+                cells.push(Felt252::from(value >> 128).into());
+            }
+            CoreTypeConcrete::Struct(info) if is_span(&info.info) => {
+                self.write_value(vm, &info.members[0], value, cells)?
+            }
+            CoreTypeConcrete::Struct(info) => match value {
+                Value::Array(values) if values.len() == info.members.len() => {
+                    for (member, value) in info.members.iter().zip(values) {
+                        self.write_value(vm, member, value, cells)?;
+                    }
+                }
+                Value::Null if info.members.is_empty() => {}
+                _ => return Err(invalid_value()),
+            },
+            CoreTypeConcrete::Enum(info) => {
+                let (index, inner) = enum_variant(&info.info, value, info.variants.len())
+                    .ok_or_else(invalid_value)?;
+                let variant = &info.variants[index];
+                let max_variant_size = info
+                    .variants
+                    .iter()
+                    .map(|variant| self.size(variant))
+                    .try_fold(0, |max, size| size.map(|size| max.max(size)))?;
+                cells.push(Felt252::from(variant_selector(info.variants.len(), index)).into());
+                cells.extend((self.size(variant)?..max_variant_size).map(|_| Felt252::ZERO.into()));
+                self.write_value(vm, variant, &inner, cells)?;
+            }
+            CoreTypeConcrete::Array(info) => {
+                let Value::Array(values) = value else {
+                    return Err(invalid_value());
+                };
+                let mut data = Vec::new();
+                for value in values {
+                    self.write_value(vm, &info.ty, value, &mut data)?;
+                }
+                let start = vm.add_memory_segment();
+                let end = vm.load_data(start, &data)?;
+                cells.push(start.into());
+                cells.push(end.into());
+            }
+            CoreTypeConcrete::Snapshot(info) | CoreTypeConcrete::NonZero(info) => {
+                self.write_value(vm, &info.ty, value, cells)?
+            }
+            CoreTypeConcrete::Nullable(_) if value.is_null() => cells.push(Felt252::ZERO.into()),
+            CoreTypeConcrete::Box(info) | CoreTypeConcrete::Nullable(info) => {
+                let mut data = Vec::new();
+                self.write_value(vm, &info.ty, value, &mut data)?;
+                let ptr = vm.add_memory_segment();
+                vm.load_data(ptr, &data)?;
+                cells.push(ptr.into());
+            }
+            concrete => {
+                let (lower, upper) =
+                    integer_range(concrete).ok_or_else(|| Error::UnsupportedType(ty.clone()))?;
+                let value = parse_integer(value)
+                    .filter(|n| *n >= lower && *n < upper)
+                    .ok_or_else(invalid_value)?;
+                cells.push(Felt252::from(value).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a value of type `ty` from its memory representation in `cells`
+    pub(crate) fn read_value<'b>(
+        &self,
+        arrays: ArrayLayout,
+        ty: &ConcreteTypeId,
+        cells: &mut impl Iterator<Item = &'b MaybeRelocatable>,
+    ) -> Result<Value, Error> {
+        let value = match self.get(ty)? {
+            CoreTypeConcrete::Struct(info) if is_u256(&info.info) => {
+                let low = next_int(cells)?.to_bigint();
+                let high = next_int(cells)?.to_bigint();
+                integer_to_json(low + (high << 128))
+            }
+            CoreTypeConcrete::Struct(info) if is_span(&info.info) => {
+                self.read_value(arrays, &info.members[0], cells)?
+            }
+            CoreTypeConcrete::Struct(info) => Value::Array(
+                info.members
+                    .iter()
+                    .map(|member| self.read_value(arrays, member, cells))
+                    .collect::<Result<_, _>>()?,
+            ),
+            CoreTypeConcrete::Enum(info) => {
+                let selector = next_int(cells)?
+                    .to_usize()
+                    .ok_or(Error::FailedToExtractReturnValues)?;
+                let index = variant_index(info.variants.len(), selector)
+                    .ok_or(Error::FailedToExtractReturnValues)?;
+                let variant = &info.variants[index];
+                let max_variant_size = info
+                    .variants
+                    .iter()
+                    .map(|variant| self.size(variant))
+                    .try_fold(0, |max, size| size.map(|size| max.max(size)))?;
+                // Skip the padding in front of the smaller variants
+                for _ in self.size(variant)?..max_variant_size {
+                    next_int(cells)?;
+                }
+                let inner = self.read_value(arrays, variant, cells)?;
+                enum_to_json(&info.info, index, inner)
+            }
+            CoreTypeConcrete::Array(info) => {
+                let data = match arrays {
+                    ArrayLayout::Memory(vm) => {
+                        let start = next_relocatable(cells)?;
+                        let end = next_relocatable(cells)?;
+                        let len = (end - start).map_err(|_| Error::FailedToExtractReturnValues)?;
+                        vm.get_continuous_range(start, len)?
+                    }
+                    ArrayLayout::Inlined => {
+                        let len = next_int(cells)?
+                            .to_usize()
+                            .ok_or(Error::FailedToExtractReturnValues)?;
+                        cells.take(len).cloned().collect()
+                    }
+                };
+                let mut data = data.iter().peekable();
+                let mut values = Vec::new();
+                while data.peek().is_some() {
+                    values.push(self.read_value(arrays, &info.ty, &mut data)?);
+                }
+                Value::Array(values)
+            }
+            CoreTypeConcrete::Snapshot(info) | CoreTypeConcrete::NonZero(info) => {
+                self.read_value(arrays, &info.ty, cells)?
+            }
+            CoreTypeConcrete::Box(info) | CoreTypeConcrete::Nullable(info) => {
+                let ArrayLayout::Memory(vm) = arrays else {
+                    return Err(Error::UnsupportedType(ty.clone()));
+                };
+                match cells.next() {
+                    Some(MaybeRelocatable::RelocatableValue(ptr)) => {
+                        let data = vm.get_continuous_range(*ptr, self.size(&info.ty)?)?;
+                        self.read_value(arrays, &info.ty, &mut data.iter())?
+                    }
+                    Some(MaybeRelocatable::Int(felt))
+                        if felt.is_zero()
+                            && matches!(self.get(ty)?, CoreTypeConcrete::Nullable(_)) =>
+                    {
+                        Value::Null
+                    }
+                    _ => return Err(Error::FailedToExtractReturnValues),
+                }
+            }
+            concrete => {
+                let (lower, _) =
+                    integer_range(concrete).ok_or_else(|| Error::UnsupportedType(ty.clone()))?;
+                let felt = next_int(cells)?;
+                // Felts are read as unsigned values, like the other non negative integers
+                if lower.is_negative() && !matches!(concrete, CoreTypeConcrete::Felt252(_)) {
+                    integer_to_json(signed_felt(felt))
+                } else {
+                    integer_to_json(felt.to_bigint())
+                }
+            }
+        };
+        Ok(value)
+    }
+
+    /// Generates an arbitrary value of type `ty`, in the JSON representation accepted by
+    /// [SierraTypes::write_value]. Integers are biased towards the bounds of their range, and
+    /// arrays have at most `max_array_len` elements
+    pub(crate) fn arbitrary_value(
+        &self,
+        u: &mut Unstructured,
+        ty: &ConcreteTypeId,
+        max_array_len: usize,
+        depth: usize,
+    ) -> Result<Value, Error> {
+        // Values of recursive types nest boxes or arrays until they pick a variant that ends them
+        if depth > MAX_ARBITRARY_VALUE_DEPTH {
+            return Err(Error::UnsupportedType(ty.clone()));
+        }
+        let value = match self.get(ty)? {
+            CoreTypeConcrete::Struct(info) if is_u256(&info.info) => {
+                arbitrary_integer(u, &BigInt::zero(), &(BigInt::one() << 256))?
+            }
+            CoreTypeConcrete::Struct(info) if is_span(&info.info) => {
+                self.arbitrary_value(u, &info.members[0], max_array_len, depth + 1)?
+            }
+            CoreTypeConcrete::Struct(info) => Value::Array(
+                info.members
+                    .iter()
+                    .map(|member| self.arbitrary_value(u, member, max_array_len, depth + 1))
+                    .collect::<Result<_, _>>()?,
+            ),
+            CoreTypeConcrete::Enum(info) => {
+                let index = u.choose_index(info.variants.len())?;
+                let inner =
+                    self.arbitrary_value(u, &info.variants[index], max_array_len, depth + 1)?;
+                enum_to_json(&info.info, index, inner)
+            }
+            CoreTypeConcrete::Array(info) => {
+                let len = u.int_in_range(0..=max_array_len)?;
+                Value::Array(
+                    (0..len)
+                        .map(|_| self.arbitrary_value(u, &info.ty, max_array_len, depth + 1))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            CoreTypeConcrete::NonZero(info) => {
+                let value = self.arbitrary_value(u, &info.ty, max_array_len, depth + 1)?;
+                if parse_integer(&value).is_some_and(|n| n.is_zero()) {
+                    Value::from(1)
+                } else {
+                    value
+                }
+            }
+            CoreTypeConcrete::Nullable(_) if u.ratio(1, 4)? => Value::Null,
+            CoreTypeConcrete::Snapshot(info)
+            | CoreTypeConcrete::Box(info)
+            | CoreTypeConcrete::Nullable(info) => {
+                self.arbitrary_value(u, &info.ty, max_array_len, depth + 1)?
+            }
+            concrete => {
+                let (lower, upper) =
+                    integer_range(concrete).ok_or_else(|| Error::UnsupportedType(ty.clone()))?;
+                arbitrary_integer(u, &lower, &upper)?
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// Returns an arbitrary integer in the range `[lower, upper)`, as a JSON value
+fn arbitrary_integer(
+    u: &mut Unstructured,
+    lower: &BigInt,
+    upper: &BigInt,
+) -> arbitrary::Result<Value> {
+    let n = match u.int_in_range(0..=6u8)? {
+        0 => lower.clone(),
+        1 => upper - 1,
+        2 => BigInt::zero(),
+        3 => BigInt::one(),
+        4 => BigInt::from(u8::arbitrary(u)?),
+        5 => -BigInt::from(u8::arbitrary(u)?),
+        _ => BigInt::from_bytes_be(Sign::Plus, &<[u8; 32]>::arbitrary(u)?),
+    };
+    let n = if n >= *lower && n < *upper {
+        n
+    } else {
+        let range = upper - lower;
+        lower + ((n % &range) + &range) % &range
+    };
+    Ok(integer_to_json(n))
+}
+
+/// Returns the debug name of the user type of a struct or enum
+fn user_type_name(info: &TypeInfo) -> Option<&str> {
+    match info.long_id.generic_args.first()? {
+        GenericArg::UserType(user_type) => user_type.debug_name.as_deref(),
+        _ => None,
+    }
+}
+
+fn is_u256(info: &TypeInfo) -> bool {
+    user_type_name(info) == Some("core::integer::u256")
+}
+
+fn is_span(info: &TypeInfo) -> bool {
+    user_type_name(info).is_some_and(|name| name.starts_with("core::array::Span::"))
+}
+
+/// Returns the range of values `[lower, upper)` of an integer type
+fn integer_range(concrete: &CoreTypeConcrete) -> Option<(BigInt, BigInt)> {
+    let bits = |bits: u32| (BigInt::zero(), BigInt::one() << bits);
+    let signed_bits = |bits: u32| (-(BigInt::one() << (bits - 1)), BigInt::one() << (bits - 1));
+    Some(match concrete {
+        CoreTypeConcrete::Felt252(_) => {
+            let prime = BigInt::from_biguint(Sign::Plus, CAIRO_PRIME.clone());
+            (-&prime + 1, prime)
+        }
+        CoreTypeConcrete::Uint8(_) => bits(8),
+        CoreTypeConcrete::Uint16(_) => bits(16),
+        CoreTypeConcrete::Uint32(_) => bits(32),
+        CoreTypeConcrete::Uint64(_) => bits(64),
+        CoreTypeConcrete::Uint128(_) => bits(128),
+        CoreTypeConcrete::Bytes31(_) => bits(248),
+        CoreTypeConcrete::Sint8(_) => signed_bits(8),
+        CoreTypeConcrete::Sint16(_) => signed_bits(16),
+        CoreTypeConcrete::Sint32(_) => signed_bits(32),
+        CoreTypeConcrete::Sint64(_) => signed_bits(64),
+        CoreTypeConcrete::Sint128(_) => signed_bits(128),
+        CoreTypeConcrete::BoundedInt(info) => (info.range.lower.clone(), info.range.upper.clone()),
+        _ => return None,
+    })
+}
+
+/// Returns the value stored in the first memory cell of a value of the enum variant `index`
+fn variant_selector(n_variants: usize, index: usize) -> usize {
+    // Enums with more than two variants are matched with a jump table, so the selector is the
+    // relative jump to the branch of the variant
+    if n_variants <= 2 {
+        index
+    } else {
+        2 * (n_variants - index) - 1
+    }
+}
+
+/// Returns the index of the enum variant with the given selector
+fn variant_index(n_variants: usize, selector: usize) -> Option<usize> {
+    let index = if n_variants <= 2 {
+        selector
+    } else {
+        n_variants.checked_sub((selector + 1) / 2)?
+    };
+    (index < n_variants).then_some(index)
+}
+
+/// Returns the index of the variant and the inner value of an enum given as JSON
+fn enum_variant(info: &TypeInfo, value: &Value, n_variants: usize) -> Option<(usize, Value)> {
+    let unit = || Value::Array(Vec::new());
+    let name = user_type_name(info).unwrap_or_default();
+    let (index, inner) = if name == "core::bool" {
+        (value.as_bool()? as usize, unit())
+    } else if name.starts_with("core::option::Option::") {
+        match value {
+            Value::Null => (1, unit()),
+            value => (0, value.clone()),
+        }
+    } else if name.starts_with("core::result::Result::") {
+        match value.as_object()?.iter().collect::<Vec<_>>().as_slice() {
+            [(key, value)] if *key == "Ok" => (0, (*value).clone()),
+            [(key, value)] if *key == "Err" => (1, (*value).clone()),
+            _ => return None,
+        }
+    } else {
+        let object = value.as_object()?;
+        let index = object.get("variant")?.as_u64()?.to_usize()?;
+        (index, object.get("value").cloned().unwrap_or_else(unit))
+    };
+    (index < n_variants).then_some((index, inner))
+}
+
+/// Returns the JSON representation of a value of the enum variant `index`
+fn enum_to_json(info: &TypeInfo, index: usize, inner: Value) -> Value {
+    let name = user_type_name(info).unwrap_or_default();
+    if name == "core::bool" {
+        Value::Bool(index != 0)
+    } else if name.starts_with("core::option::Option::") {
+        if index == 0 {
+            inner
+        } else {
+            Value::Null
+        }
+    } else if name.starts_with("core::result::Result::") {
+        let key = if index == 0 { "Ok" } else { "Err" };
+        Value::Object(Map::from_iter([(key.to_string(), inner)]))
+    } else if inner == Value::Array(Vec::new()) {
+        json!({ "variant": index })
+    } else {
+        json!({ "variant": index, "value": inner })
+    }
+}
+
+/// Parses a JSON number, or a string holding a decimal or hexadecimal number
+fn parse_integer(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Number(number) => number
+            .as_i64()
+            .map(BigInt::from)
+            .or_else(|| number.as_u64().map(BigInt::from)),
+        Value::String(string) => {
+            let (negative, digits) = match string.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, string.as_str()),
+            };
+            let number = match digits.strip_prefix("0x") {
+                Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16)?,
+                None => BigInt::parse_bytes(digits.as_bytes(), 10)?,
+            };
+            Some(if negative { -number } else { number })
+        }
+        _ => None,
+    }
+}
+
+/// Returns the integer as a JSON number if it fits in 64 bits, or as a decimal string otherwise
+fn integer_to_json(value: BigInt) -> Value {
+    if let Some(value) = value.to_i64() {
+        value.into()
+    } else if let Some(value) = value.to_u64() {
+        value.into()
+    } else {
+        Value::String(value.to_string())
+    }
+}
+
+fn next_int<'b>(cells: &mut impl Iterator<Item = &'b MaybeRelocatable>) -> Result<Felt252, Error> {
+    cells
+        .next()
+        .and_then(|cell| cell.get_int())
+        .ok_or(Error::FailedToExtractReturnValues)
+}
+
+fn next_relocatable<'b>(
+    cells: &mut impl Iterator<Item = &'b MaybeRelocatable>,
+) -> Result<Relocatable, Error> {
+    cells
+        .next()
+        .and_then(|cell| cell.get_relocatable())
+        .ok_or(Error::FailedToExtractReturnValues)
+}
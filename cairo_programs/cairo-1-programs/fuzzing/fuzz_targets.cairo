@@ -0,0 +1,14 @@
+fn divide(a: u32, b: u32) -> u32 {
+    a / b
+}
+
+fn widening_sum(values: Array<u8>) -> u64 {
+    let mut sum: u64 = 0;
+    let mut values = values.span();
+    while let Option::Some(value) = values.pop_front() {
+        sum += (*value).into();
+    };
+    sum
+}
+
+fn main() {}
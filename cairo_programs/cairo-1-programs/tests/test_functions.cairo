@@ -0,0 +1,40 @@
+fn fib(a: felt252, b: felt252, n: felt252) -> felt252 {
+    match n {
+        0 => a,
+        _ => fib(b, a + b, n - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fib;
+
+    #[test]
+    fn test_fib() {
+        assert(fib(1, 1, 10) == 144, 'wrong fib');
+    }
+
+    #[test]
+    #[available_gas(1000000)]
+    fn test_fib_with_gas() {
+        assert(fib(1, 1, 10) == 144, 'wrong fib');
+    }
+
+    #[test]
+    #[should_panic(expected: ('wrong fib',))]
+    fn test_assert_fails() {
+        assert(fib(1, 1, 10) == 0, 'wrong fib');
+    }
+
+    #[test]
+    #[should_panic(expected: "fib overflow")]
+    fn test_panic_with_message() {
+        panic!("fib overflow");
+    }
+
+    #[test]
+    #[ignore]
+    fn test_ignored() {
+        assert(fib(1, 1, 10) == 0, 'wrong fib');
+    }
+}
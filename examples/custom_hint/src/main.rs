@@ -7,13 +7,13 @@ use cairo_vm::hint_processor::hint_processor_definition::HintReference;
 use cairo_vm::serde::deserialize_program::ApTracking;
 use cairo_vm::types::exec_scope::ExecutionScopes;
 use cairo_vm::types::layout_name::LayoutName;
+use cairo_vm::types::shared::Shared;
 use cairo_vm::vm::{errors::hint_errors::HintError, vm_core::VirtualMachine};
 use cairo_vm::Felt252;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::Path;
-use std::rc::Rc;
 
 // Create the function that implements the custom hint
 fn print_a_hint(
@@ -36,7 +36,7 @@ fn main() {
     let mut hint_processor = BuiltinHintProcessor::new_empty();
 
     //Add the custom hint, together with the Python code
-    hint_processor.add_hint(String::from("print(ids.a)"), Rc::new(hint));
+    hint_processor.add_hint(String::from("print(ids.a)"), Shared::new(hint));
 
     let file = File::open(Path::new("custom_hint.json")).expect("Couldn't load file");
     let mut reader = BufReader::new(file);
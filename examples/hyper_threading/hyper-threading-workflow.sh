@@ -1,6 +1,7 @@
 #!/bin/bash
 
-# Define a list of RAYON_NUM_THREADS
+# Define a list of thread counts
+# Both variables are set, as binaries built before `run_batch` read RAYON_NUM_THREADS
 thread_counts=(1 2 4 6 8 16 )
 
 # Define binary names
@@ -16,7 +17,7 @@ for threads in "${thread_counts[@]}"; do
     
     # Add each binary to the command with the current threads value
     for binary in "${binaries[@]}"; do
-        cmd+=" -n \"${binary} threads: ${threads}\" 'RAYON_NUM_THREADS=${threads} ./${binary}'"
+        cmd+=" -n \"${binary} threads: ${threads}\" 'RAYON_NUM_THREADS=${threads} N_THREADS=${threads} ./${binary}'"
     done
     
     # Execute 
@@ -9,7 +9,7 @@ cmd="hyperfine -r 1"
 # Build the command string with all thread counts
 for threads in "${thread_counts[@]}"; do
     # For hyperfine, wrap each command in 'sh -c' to correctly handle the environment variable
-    cmd+=" -n \"threads: ${threads}\" 'sh -c \"RAYON_NUM_THREADS=${threads} ${binary}\"'"
+    cmd+=" -n \"threads: ${threads}\" 'sh -c \"N_THREADS=${threads} ${binary}\"'"
 done
 
 # Execute the hyperfine command
@@ -1,9 +1,9 @@
 use cairo_vm::{
-    cairo_run::{cairo_run_program, CairoRunConfig},
+    cairo_run::{run_batch, BatchRunConfig, CairoRunConfig},
     hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
-    types::{layout_name::LayoutName, program::Program},
+    types::{exec_scope::ExecutionScopes, layout_name::LayoutName, program::Program},
+    vm::runners::program_cache::ProgramCache,
 };
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::path::Path;
 
 // Define build_filename macro to prepend a relative path to the file names
@@ -40,14 +40,16 @@ fn main() {
     let n_programs = &program_filenames.len();
 
     for filename in program_filenames {
-        programs.push(Program::from_file(Path::new(&filename), Some("main")).unwrap())
+        let program = Program::from_file(Path::new(&filename), Some("main")).unwrap();
+        programs.push((ProgramCache::new(program), ()))
     }
 
-    let start_time = std::time::Instant::now();
-
-    // Parallel execution of the program processing
-    programs.into_par_iter().for_each(|program| {
-        let cairo_run_config = CairoRunConfig {
+    // The amount of threads can be set with N_THREADS, it defaults to the available parallelism
+    let n_threads = std::env::var("N_THREADS")
+        .ok()
+        .map(|n_threads| n_threads.parse().expect("N_THREADS should be a number"));
+    let batch_config = BatchRunConfig {
+        run_config: CairoRunConfig {
             entrypoint: "main",
             trace_enabled: false,
             relocate_mem: false,
@@ -55,13 +57,21 @@ fn main() {
             proof_mode: true,
             secure_run: Some(false),
             ..Default::default()
-        };
-        let mut hint_executor = BuiltinHintProcessor::new_empty();
+        },
+        n_threads,
+    };
 
-        // Execute each program in parallel
-        let _result = cairo_run_program(&program, &cairo_run_config, &mut hint_executor)
-            .expect("Couldn't run program");
-    });
+    let start_time = std::time::Instant::now();
+
+    // Parallel execution of the program processing
+    run_batch(
+        programs,
+        &batch_config,
+        |()| (BuiltinHintProcessor::new_empty(), ExecutionScopes::new()),
+        |result| {
+            result.expect("Couldn't run program");
+        },
+    );
     let elapsed = start_time.elapsed();
 
     tracing::info!(%n_programs, ?elapsed, "Finished");
@@ -0,0 +1,27 @@
+#![no_main]
+use cairo_vm::{
+    fuzzing::{FunctionFuzzer, FuzzConfig},
+    types::program::Program,
+};
+use libfuzzer_sys::fuzz_target;
+use std::{env, path::Path, sync::OnceLock};
+
+// Compiled program and function to fuzz, read from the environment on the first iteration
+static PROGRAM: OnceLock<(Program, String)> = OnceLock::new();
+
+fuzz_target!(|data: &[u8]| {
+    let (program, function) = PROGRAM.get_or_init(|| {
+        let path = env::var("CAIRO_FUZZ_PROGRAM").expect("CAIRO_FUZZ_PROGRAM is not set");
+        let function = env::var("CAIRO_FUZZ_FUNCTION").unwrap_or_else(|_| "main".to_string());
+        let program = Program::from_file(Path::new(&path), None).unwrap();
+        (program, function)
+    });
+    let fuzzer = FunctionFuzzer::new(program, function, FuzzConfig::default()).unwrap();
+    // Report the inputs that make the function fail as crashes
+    if let Some(failure) = fuzzer.run_input(data).unwrap() {
+        panic!(
+            "{:?} with args {:?}: {}",
+            failure.kind, failure.args, failure.error
+        );
+    }
+});
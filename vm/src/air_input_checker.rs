@@ -0,0 +1,489 @@
+//! Independent re-check of the artifacts of a proof mode run, so that a corrupt trace, memory or
+//! AIR input is detected before sending them to a prover.
+
+// The `(*.0).0` syntax of thiserror falsely triggers this clippy warning
+#![allow(clippy::explicit_auto_deref)]
+
+use num_traits::{ToPrimitive, Zero};
+use thiserror_no_std::Error;
+
+use crate::{
+    air_private_input::{AirPrivateInput, PrivateInput},
+    air_public_input::PublicInput,
+    stdlib::{boxed::Box, prelude::*},
+    types::{
+        builtin_name::BuiltinName,
+        instruction::{ApUpdate, FpUpdate, Instruction, Op1Addr, Opcode, PcUpdate, Register, Res},
+    },
+    vm::{decoding::decoder::decode_instruction, trace::trace_entry::RelocatedTraceEntry},
+    Felt252,
+};
+
+/// Bias added to the instruction offsets to make them non negative, as done for the range check
+/// limits of the run
+const OFFSET_BIAS: isize = 1 << 15;
+
+#[derive(Debug, PartialEq, Error)]
+pub enum AirInputCheckError {
+    #[error("The trace is empty")]
+    EmptyTrace,
+    #[error("Step {0}: the value at pc is not a valid instruction")]
+    InvalidInstruction(usize),
+    #[error("Step {}: memory address {} is not set", (*.0).0, (*.0).1)]
+    MissingMemory(Box<(usize, usize)>),
+    #[error("Step {}: the {} operand is not a valid address", (*.0).0, (*.0).1)]
+    InvalidAddress(Box<(usize, &'static str)>),
+    #[error("Step {0}: the assertion of the instruction doesn't hold")]
+    AssertEqFailed(usize),
+    #[error("Step {0}: the call instruction didn't store fp and the return pc")]
+    InvalidCall(usize),
+    #[error("Step {}: {} of the next step doesn't follow from the instruction", (*.0).0, (*.0).1)]
+    RegisterMismatch(Box<(usize, &'static str)>),
+    #[error("The public input has {} steps but the trace has {}", (*.0).0, (*.0).1)]
+    StepsMismatch(Box<(usize, usize)>),
+    #[error("The public memory entry at address {0} doesn't match the memory")]
+    PublicMemoryMismatch(usize),
+    #[error("The public input range check limits {:?} don't match the ones of the run {:?}", (*.0).0, (*.0).1)]
+    RangeCheckLimitsMismatch(Box<((isize, isize), (isize, isize))>),
+    #[error("The public input has no memory segment for the {0} builtin")]
+    MissingBuiltinSegment(BuiltinName),
+    #[error("The private input of instance {} of the {} builtin doesn't match the memory", (*.0).1, (*.0).0)]
+    PrivateInputMismatch(Box<(BuiltinName, usize)>),
+}
+
+/// Checks that the artifacts of a proof mode run are consistent with each other:
+/// * Every step of the trace executes a valid instruction whose operands are in the memory, its
+///   assertions hold, and the registers of the next step follow from it
+/// * The public memory and the amount of steps of the public input match the memory and the trace
+/// * The range check limits of the public input are the ones used by the instructions and the
+///   range check builtins
+/// * The builtin private inputs match the cells of their builtin segments
+pub fn check_air_inputs(
+    trace: &[RelocatedTraceEntry],
+    memory: &[Option<Felt252>],
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+) -> Result<(), AirInputCheckError> {
+    if trace.is_empty() {
+        return Err(AirInputCheckError::EmptyTrace);
+    }
+    if public_input.n_steps != trace.len() {
+        return Err(AirInputCheckError::StepsMismatch(Box::new((
+            public_input.n_steps,
+            trace.len(),
+        ))));
+    }
+
+    let mut offset_limits: Option<(isize, isize)> = None;
+    for (step, entry) in trace.iter().enumerate() {
+        let instruction = check_step(step, entry, trace.get(step + 1), memory)?;
+        for offset in [instruction.off0, instruction.off1, instruction.off2] {
+            let offset = offset + OFFSET_BIAS;
+            let (min, max) = offset_limits.unwrap_or((offset, offset));
+            offset_limits = Some((min.min(offset), max.max(offset)));
+        }
+    }
+
+    for entry in public_input.public_memory.iter() {
+        if memory.get(entry.address).copied().flatten() != entry.value {
+            return Err(AirInputCheckError::PublicMemoryMismatch(entry.address));
+        }
+    }
+
+    for (builtin, inputs) in private_input.0.iter() {
+        if inputs.is_empty() {
+            continue;
+        }
+        let base = public_input
+            .memory_segments
+            .get(builtin.to_str())
+            .ok_or(AirInputCheckError::MissingBuiltinSegment(*builtin))?
+            .begin_addr;
+        for input in inputs {
+            check_private_input(*builtin, base, input, memory)?;
+        }
+    }
+
+    let rc_limits = [BuiltinName::range_check, BuiltinName::range_check96]
+        .into_iter()
+        .filter_map(|builtin| range_check_usage(builtin, memory, public_input))
+        .chain(offset_limits)
+        .reduce(|(min1, max1), (min2, max2)| (min1.min(min2), max1.max(max2)));
+    let public_rc_limits = (public_input.rc_min, public_input.rc_max);
+    if let Some(rc_limits) = rc_limits.filter(|limits| *limits != public_rc_limits) {
+        return Err(AirInputCheckError::RangeCheckLimitsMismatch(Box::new((
+            public_rc_limits,
+            rc_limits,
+        ))));
+    }
+    Ok(())
+}
+
+/// Checks a step of the trace against the memory and the next step, returning its instruction
+fn check_step(
+    step: usize,
+    entry: &RelocatedTraceEntry,
+    next: Option<&RelocatedTraceEntry>,
+    memory: &[Option<Felt252>],
+) -> Result<Instruction, AirInputCheckError> {
+    let get = |address: usize| {
+        memory
+            .get(address)
+            .copied()
+            .flatten()
+            .ok_or_else(|| AirInputCheckError::MissingMemory(Box::new((step, address))))
+    };
+    let address = |base: usize, offset: isize, operand: &'static str| {
+        base.checked_add_signed(offset)
+            .ok_or_else(|| AirInputCheckError::InvalidAddress(Box::new((step, operand))))
+    };
+    let to_address = |value: Felt252, operand: &'static str| {
+        value
+            .to_usize()
+            .ok_or_else(|| AirInputCheckError::InvalidAddress(Box::new((step, operand))))
+    };
+    let register = |register: Register| match register {
+        Register::AP => entry.ap,
+        Register::FP => entry.fp,
+    };
+
+    let instruction = get(entry.pc)?
+        .to_u64()
+        .and_then(|encoded| decode_instruction(encoded).ok())
+        .ok_or(AirInputCheckError::InvalidInstruction(step))?;
+    let size = instruction.size();
+
+    let dst = get(address(
+        register(instruction.dst_register),
+        instruction.off0,
+        "dst",
+    )?)?;
+    let op0 = get(address(
+        register(instruction.op0_register),
+        instruction.off1,
+        "op0",
+    )?)?;
+    let op1_base = match instruction.op1_addr {
+        Op1Addr::Imm => entry.pc,
+        Op1Addr::AP => entry.ap,
+        Op1Addr::FP => entry.fp,
+        Op1Addr::Op0 => to_address(op0, "op0")?,
+    };
+    let op1 = get(address(op1_base, instruction.off2, "op1")?)?;
+    let res = match instruction.res {
+        Res::Op1 => Some(op1),
+        Res::Add => Some(op0 + op1),
+        Res::Mul => Some(op0 * op1),
+        Res::Unconstrained => None,
+    };
+
+    match instruction.opcode {
+        Opcode::AssertEq if res != Some(dst) => {
+            return Err(AirInputCheckError::AssertEqFailed(step))
+        }
+        Opcode::Call if dst != Felt252::from(entry.fp) || op0 != Felt252::from(entry.pc + size) => {
+            return Err(AirInputCheckError::InvalidCall(step))
+        }
+        _ => {}
+    }
+
+    let Some(next) = next else {
+        return Ok(instruction);
+    };
+    let pc = Felt252::from(entry.pc);
+    let next_pc = match instruction.pc_update {
+        PcUpdate::Regular => Some(Felt252::from(entry.pc + size)),
+        PcUpdate::Jump => res,
+        PcUpdate::JumpRel => res.map(|res| pc + res),
+        PcUpdate::Jnz if dst.is_zero() => Some(Felt252::from(entry.pc + size)),
+        PcUpdate::Jnz => Some(pc + op1),
+    };
+    let ap = Felt252::from(entry.ap);
+    let next_ap = match instruction.ap_update {
+        ApUpdate::Regular => Some(ap),
+        ApUpdate::Add => res.map(|res| ap + res),
+        ApUpdate::Add1 => Some(ap + 1),
+        ApUpdate::Add2 => Some(ap + 2),
+    };
+    let next_fp = match instruction.fp_update {
+        FpUpdate::Regular => Felt252::from(entry.fp),
+        FpUpdate::APPlus2 => ap + 2,
+        FpUpdate::Dst => dst,
+    };
+    for (register, expected, value) in [
+        ("pc", next_pc, next.pc),
+        ("ap", next_ap, next.ap),
+        ("fp", Some(next_fp), next.fp),
+    ] {
+        if expected != Some(Felt252::from(value)) {
+            return Err(AirInputCheckError::RegisterMismatch(Box::new((
+                step, register,
+            ))));
+        }
+    }
+    Ok(instruction)
+}
+
+/// Returns the minimum and maximum 16 bit parts of the values of a range check builtin, computed
+/// in the same way as the runner does for the range check limits of the run
+fn range_check_usage(
+    builtin: BuiltinName,
+    memory: &[Option<Felt252>],
+    public_input: &PublicInput,
+) -> Option<(isize, isize)> {
+    let n_parts = match builtin {
+        BuiltinName::range_check96 => 6,
+        _ => 8,
+    };
+    let segment = public_input.memory_segments.get(builtin.to_str())?;
+    let cells = memory.get(segment.begin_addr..segment.stop_ptr)?;
+    let mut limits = (!cells.is_empty()).then_some((isize::MAX, isize::MIN))?;
+    for value in cells {
+        limits = (*value)?
+            .to_le_digits()
+            .into_iter()
+            .flat_map(|digit| {
+                (0..=3)
+                    .rev()
+                    .map(move |i| ((digit >> (i * 16)) & 0xffff) as isize)
+            })
+            .take(n_parts)
+            .fold(limits, |(min, max), part| (min.min(part), max.max(part)));
+    }
+    Some(limits)
+}
+
+/// Checks that the input cells of a builtin instance hold the values of its private input
+fn check_private_input(
+    builtin: BuiltinName,
+    base: usize,
+    input: &PrivateInput,
+    memory: &[Option<Felt252>],
+) -> Result<(), AirInputCheckError> {
+    let mismatch =
+        |index: usize| AirInputCheckError::PrivateInputMismatch(Box::new((builtin, index)));
+    let check_cells = |index: usize, cells_per_instance: usize, values: &[Felt252]| {
+        let instance_base = base + index * cells_per_instance;
+        values
+            .iter()
+            .enumerate()
+            .all(|(offset, value)| memory.get(instance_base + offset) == Some(&Some(*value)))
+            .then_some(())
+            .ok_or_else(|| mismatch(index))
+    };
+    match input {
+        PrivateInput::Value(input) => check_cells(input.index, 1, &[input.value]),
+        PrivateInput::Pair(input) => {
+            let cells_per_instance = match builtin {
+                BuiltinName::bitwise => 5,
+                _ => 3,
+            };
+            check_cells(input.index, cells_per_instance, &[input.x, input.y])
+        }
+        PrivateInput::EcOp(input) => check_cells(
+            input.index,
+            7,
+            &[input.p_x, input.p_y, input.q_x, input.q_y, input.m],
+        ),
+        PrivateInput::PoseidonState(input) => check_cells(
+            input.index,
+            6,
+            &[input.input_s0, input.input_s1, input.input_s2],
+        ),
+        PrivateInput::KeccakState(input) => check_cells(
+            input.index,
+            16,
+            &[
+                input.input_s0,
+                input.input_s1,
+                input.input_s2,
+                input.input_s3,
+                input.input_s4,
+                input.input_s5,
+                input.input_s6,
+                input.input_s7,
+            ],
+        ),
+        PrivateInput::Signature(input) => check_cells(input.index, 2, &[input.pubkey, input.msg]),
+        PrivateInput::Mod(input) => input.instances.iter().try_for_each(|instance| {
+            check_cells(
+                instance.index,
+                7,
+                &[
+                    instance.p0,
+                    instance.p1,
+                    instance.p2,
+                    instance.p3,
+                    Felt252::from(instance.values_ptr),
+                    Felt252::from(instance.offsets_ptr),
+                    Felt252::from(instance.n),
+                ],
+            )
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        air_private_input::PrivateInputValue, air_public_input::PublicMemoryEntry,
+        utils::test_utils::run_program_b,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_inputs_of_run() {
+        let runner = run_program_b(true, true);
+        assert_matches!(
+            check_air_inputs(
+                runner.relocated_trace.as_ref().unwrap(),
+                &runner.relocated_memory,
+                &runner.get_air_public_input().unwrap(),
+                &runner.get_air_private_input(),
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_inputs_with_missing_step() {
+        let runner = run_program_b(true, true);
+        let mut trace = runner.relocated_trace.clone().unwrap();
+        trace.pop();
+        assert_matches!(
+            check_air_inputs(
+                &trace,
+                &runner.relocated_memory,
+                &runner.get_air_public_input().unwrap(),
+                &runner.get_air_private_input(),
+            ),
+            Err(AirInputCheckError::StepsMismatch(bx)) if *bx == (trace.len() + 1, trace.len())
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_inputs_with_wrong_register() {
+        let runner = run_program_b(true, true);
+        let mut trace = runner.relocated_trace.clone().unwrap();
+        trace[1].ap += 1;
+        assert_matches!(
+            check_air_inputs(
+                &trace,
+                &runner.relocated_memory,
+                &runner.get_air_public_input().unwrap(),
+                &runner.get_air_private_input(),
+            ),
+            Err(AirInputCheckError::RegisterMismatch(bx)) if *bx == (0, "ap")
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_inputs_with_missing_instruction() {
+        let runner = run_program_b(true, true);
+        let trace = runner.relocated_trace.as_ref().unwrap();
+        let mut memory = runner.relocated_memory.clone();
+        memory[trace[0].pc] = None;
+        assert_matches!(
+            check_air_inputs(
+                trace,
+                &memory,
+                &runner.get_air_public_input().unwrap(),
+                &runner.get_air_private_input(),
+            ),
+            Err(AirInputCheckError::MissingMemory(bx)) if *bx == (0, trace[0].pc)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_inputs_with_wrong_public_memory() {
+        let runner = run_program_b(true, true);
+        let mut public_input = runner.get_air_public_input().unwrap();
+        // Runs outside of proof mode only make the program segment public
+        let address = runner.relocated_trace.as_ref().unwrap()[0].pc;
+        public_input.public_memory.push(PublicMemoryEntry {
+            address,
+            value: Some(runner.relocated_memory[address].unwrap() + Felt252::ONE),
+            page: 0,
+        });
+        assert_matches!(
+            check_air_inputs(
+                runner.relocated_trace.as_ref().unwrap(),
+                &runner.relocated_memory,
+                &public_input,
+                &runner.get_air_private_input(),
+            ),
+            Err(AirInputCheckError::PublicMemoryMismatch(a)) if a == address
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_inputs_with_wrong_rc_limits() {
+        let runner = run_program_b(true, true);
+        let mut public_input = runner.get_air_public_input().unwrap();
+        let rc_limits = (public_input.rc_min, public_input.rc_max);
+        public_input.rc_max += 1;
+        assert_matches!(
+            check_air_inputs(
+                runner.relocated_trace.as_ref().unwrap(),
+                &runner.relocated_memory,
+                &public_input,
+                &runner.get_air_private_input(),
+            ),
+            Err(AirInputCheckError::RangeCheckLimitsMismatch(bx))
+                if *bx == ((rc_limits.0, rc_limits.1 + 1), rc_limits)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_inputs_with_wrong_private_input() {
+        let runner = run_program_b(true, true);
+        let mut private_input = runner.get_air_private_input();
+        let range_check_inputs = private_input.0.get_mut(&BuiltinName::range_check).unwrap();
+        let Some(PrivateInput::Value(PrivateInputValue { index: 0, value })) =
+            range_check_inputs.first_mut()
+        else {
+            panic!("expected a range check value");
+        };
+        *value += Felt252::ONE;
+        assert_matches!(
+            check_air_inputs(
+                runner.relocated_trace.as_ref().unwrap(),
+                &runner.relocated_memory,
+                &runner.get_air_public_input().unwrap(),
+                &private_input,
+            ),
+            Err(AirInputCheckError::PrivateInputMismatch(bx))
+                if *bx == (BuiltinName::range_check, 0)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_inputs_without_builtin_segment() {
+        let runner = run_program_b(true, true);
+        let mut public_input = runner.get_air_public_input().unwrap();
+        public_input.memory_segments.remove("range_check");
+        assert_matches!(
+            check_air_inputs(
+                runner.relocated_trace.as_ref().unwrap(),
+                &runner.relocated_memory,
+                &public_input,
+                &runner.get_air_private_input(),
+            ),
+            Err(AirInputCheckError::MissingBuiltinSegment(
+                BuiltinName::range_check
+            ))
+        );
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     },
     types::builtin_name::BuiltinName,
 };
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 
 use crate::Felt252;
 
@@ -46,8 +46,10 @@ pub enum PrivateInput {
     Value(PrivateInputValue),
     Pair(PrivateInputPair),
     EcOp(PrivateInputEcOp),
-    PoseidonState(PrivateInputPoseidonState),
+    // Untagged variants are tried in order, so KeccakState has to come before PoseidonState, whose
+    // fields are a subset of its own
     KeccakState(PrivateInputKeccakState),
+    PoseidonState(PrivateInputPoseidonState),
     Signature(PrivateInputSignature),
     Mod(ModInput),
 }
@@ -126,9 +128,25 @@ pub struct ModInputInstance {
     pub values_ptr: usize,
     pub offsets_ptr: usize,
     pub n: usize,
+    #[serde(deserialize_with = "deserialize_batch")]
     pub batch: BTreeMap<usize, ModInputMemoryVars>,
 }
 
+// JSON object keys are strings, and the untagged PrivateInput doesn't parse them back into integers
+fn deserialize_batch<'de, D: Deserializer<'de>>(
+    d: D,
+) -> Result<BTreeMap<usize, ModInputMemoryVars>, D::Error> {
+    BTreeMap::<String, ModInputMemoryVars>::deserialize(d)?
+        .into_iter()
+        .map(|(index, vars)| {
+            index
+                .parse()
+                .map(|index| (index, vars))
+                .map_err(de::Error::custom)
+        })
+        .collect()
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct ModInputMemoryVars {
     pub a_offset: usize,
@@ -189,11 +207,20 @@ impl From<AirPrivateInputSerializable> for AirPrivateInput {
         };
         insert_input(BuiltinName::pedersen, private_input.pedersen);
         insert_input(BuiltinName::range_check, private_input.range_check);
+        insert_input(BuiltinName::range_check96, private_input.range_check96);
         insert_input(BuiltinName::ecdsa, private_input.ecdsa);
         insert_input(BuiltinName::bitwise, private_input.bitwise);
         insert_input(BuiltinName::ec_op, private_input.ec_op);
         insert_input(BuiltinName::keccak, private_input.keccak);
         insert_input(BuiltinName::poseidon, private_input.poseidon);
+        insert_input(
+            BuiltinName::add_mod,
+            private_input.add_mod.map(|input| vec![input]),
+        );
+        insert_input(
+            BuiltinName::mul_mod,
+            private_input.mul_mod.map(|input| vec![input]),
+        );
 
         Self(inputs)
     }
@@ -203,6 +230,18 @@ impl AirPrivateInputSerializable {
     pub fn serialize_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string_pretty(&self)
     }
+
+    pub fn deserialize_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn trace_path(&self) -> &str {
+        &self.trace_path
+    }
+
+    pub fn memory_path(&self) -> &str {
+        &self.memory_path
+    }
 }
 
 #[cfg(test)]
@@ -219,9 +258,8 @@ mod tests {
     use crate::alloc::string::ToString;
 
     #[cfg(feature = "std")]
-    #[test]
-    fn test_from_serializable() {
-        let serializable_private_input = AirPrivateInputSerializable {
+    fn serializable_private_input() -> AirPrivateInputSerializable {
+        AirPrivateInputSerializable {
             trace_path: "trace.bin".to_string(),
             memory_path: "memory.bin".to_string(),
             pedersen: Some(vec![PrivateInput::Pair(PrivateInputPair {
@@ -278,10 +316,47 @@ mod tests {
                     input_s2: Felt252::from(3),
                 },
             )]),
-            add_mod: None,
+            add_mod: Some(PrivateInput::Mod(ModInput {
+                instances: vec![ModInputInstance {
+                    index: 0,
+                    p0: Felt252::from(7),
+                    p1: Felt252::ZERO,
+                    p2: Felt252::ZERO,
+                    p3: Felt252::ZERO,
+                    values_ptr: 120,
+                    offsets_ptr: 140,
+                    n: 1,
+                    batch: BTreeMap::from([(
+                        0,
+                        ModInputMemoryVars {
+                            a_offset: 0,
+                            a0: Felt252::from(3),
+                            a1: Felt252::ZERO,
+                            a2: Felt252::ZERO,
+                            a3: Felt252::ZERO,
+                            b_offset: 4,
+                            b0: Felt252::from(5),
+                            b1: Felt252::ZERO,
+                            b2: Felt252::ZERO,
+                            b3: Felt252::ZERO,
+                            c_offset: 8,
+                            c0: Felt252::from(1),
+                            c1: Felt252::ZERO,
+                            c2: Felt252::ZERO,
+                            c3: Felt252::ZERO,
+                        },
+                    )]),
+                }],
+                zero_value_address: 100,
+            })),
             mul_mod: None,
-        };
+        }
+    }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_serializable() {
+        let serializable_private_input = serializable_private_input();
         let private_input = AirPrivateInput::from(serializable_private_input.clone());
 
         assert_matches!(private_input.0.get(&BuiltinName::pedersen), data if data == serializable_private_input.pedersen.as_ref());
@@ -291,6 +366,26 @@ mod tests {
         assert_matches!(private_input.0.get(&BuiltinName::ec_op), data if data == serializable_private_input.ec_op.as_ref());
         assert_matches!(private_input.0.get(&BuiltinName::keccak), data if data == serializable_private_input.keccak.as_ref());
         assert_matches!(private_input.0.get(&BuiltinName::poseidon), data if data == serializable_private_input.poseidon.as_ref());
+        assert_matches!(private_input.0.get(&BuiltinName::range_check96), data if data == serializable_private_input.range_check96.as_ref());
+        assert_matches!(private_input.0.get(&BuiltinName::add_mod).and_then(|data| data.first()), data if data == serializable_private_input.add_mod.as_ref());
+        assert!(!private_input.0.contains_key(&BuiltinName::mul_mod));
+        assert_eq!(
+            private_input.to_serializable("trace.bin".to_string(), "memory.bin".to_string()),
+            serializable_private_input
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn serialize_and_deserialize_air_private_input() {
+        let serializable_private_input = serializable_private_input();
+        let deserialized_private_input = AirPrivateInputSerializable::deserialize_json(
+            &serializable_private_input.serialize_json().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(deserialized_private_input, serializable_private_input);
+        assert_eq!(deserialized_private_input.trace_path(), "trace.bin");
+        assert_eq!(deserialized_private_input.memory_path(), "memory.bin");
     }
 
     #[test]
@@ -7,13 +7,14 @@ use crate::{
         collections::HashMap,
         prelude::{String, Vec},
     },
+    types::layout::CairoLayoutParams,
     vm::{
         errors::{trace_errors::TraceError, vm_errors::VirtualMachineError},
         trace::trace_entry::RelocatedTraceEntry,
     },
 };
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct PublicMemoryEntry {
     pub address: usize,
     #[serde(serialize_with = "mem_value_serde::serialize")]
@@ -43,7 +44,7 @@ mod mem_value_serde {
     pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
         d: D,
     ) -> Result<Option<Felt252>, D::Error> {
-        d.deserialize_str(Felt252OptionVisitor)
+        d.deserialize_option(Felt252OptionVisitor)
     }
 
     struct Felt252OptionVisitor;
@@ -62,6 +63,13 @@ mod mem_value_serde {
             Ok(None)
         }
 
+        fn visit_some<D>(self, d: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            d.deserialize_str(self)
+        }
+
         fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
         where
             E: de::Error,
@@ -73,7 +81,7 @@ mod mem_value_serde {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct MemorySegmentAddresses {
     pub begin_addr: usize,
     pub stop_ptr: usize,
@@ -89,8 +97,7 @@ impl From<(usize, usize)> for MemorySegmentAddresses {
     }
 }
 
-#[allow(clippy::manual_non_exhaustive)]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct PublicInput<'a> {
     pub layout: &'a str,
     pub rc_min: isize,
@@ -98,14 +105,15 @@ pub struct PublicInput<'a> {
     pub n_steps: usize,
     pub memory_segments: HashMap<&'a str, MemorySegmentAddresses>,
     pub public_memory: Vec<PublicMemoryEntry>,
-    #[serde(skip_deserializing)] // This is set to None by default so we can skip it
-    dynamic_params: (),
+    /// Parameters of the `dynamic` layout, `None` for the other layouts
+    pub dynamic_params: Option<CairoLayoutParams>,
 }
 
 impl<'a> PublicInput<'a> {
     pub fn new(
         memory: &[Option<Felt252>],
         layout: &'a str,
+        dynamic_params: Option<CairoLayoutParams>,
         public_memory_addresses: &[(usize, usize)],
         memory_segment_addresses: HashMap<&'static str, (usize, usize)>,
         trace: &[RelocatedTraceEntry],
@@ -134,7 +142,7 @@ impl<'a> PublicInput<'a> {
 
         Ok(PublicInput {
             layout,
-            dynamic_params: (),
+            dynamic_params,
             rc_min,
             rc_max,
             n_steps: trace.len(),
@@ -158,6 +166,65 @@ impl<'a> PublicInput<'a> {
     }
 }
 
+/// [PublicInput] owning its strings, so it can be deserialized from any reader and outlive the
+/// JSON it was read from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OwnedPublicInput {
+    pub layout: String,
+    pub rc_min: isize,
+    pub rc_max: isize,
+    pub n_steps: usize,
+    pub memory_segments: HashMap<String, MemorySegmentAddresses>,
+    pub public_memory: Vec<PublicMemoryEntry>,
+    /// Parameters of the `dynamic` layout, `None` for the other layouts
+    pub dynamic_params: Option<CairoLayoutParams>,
+}
+
+impl OwnedPublicInput {
+    pub fn serialize_json(&self) -> Result<String, PublicInputError> {
+        serde_json::to_string_pretty(&self).map_err(PublicInputError::from)
+    }
+
+    pub fn deserialize_json(json: &str) -> Result<Self, PublicInputError> {
+        serde_json::from_str(json).map_err(PublicInputError::from)
+    }
+
+    /// Returns the public input borrowing its strings from `self`
+    pub fn as_public_input(&self) -> PublicInput<'_> {
+        PublicInput {
+            layout: &self.layout,
+            rc_min: self.rc_min,
+            rc_max: self.rc_max,
+            n_steps: self.n_steps,
+            memory_segments: self
+                .memory_segments
+                .iter()
+                .map(|(name, addresses)| (name.as_str(), addresses.clone()))
+                .collect(),
+            public_memory: self.public_memory.clone(),
+            dynamic_params: self.dynamic_params.clone(),
+        }
+    }
+}
+
+impl From<PublicInput<'_>> for OwnedPublicInput {
+    fn from(public_input: PublicInput<'_>) -> Self {
+        OwnedPublicInput {
+            layout: public_input.layout.into(),
+            rc_min: public_input.rc_min,
+            rc_max: public_input.rc_max,
+            n_steps: public_input.n_steps,
+            memory_segments: public_input
+                .memory_segments
+                .into_iter()
+                .map(|(name, addresses)| (name.into(), addresses))
+                .collect(),
+            public_memory: public_input.public_memory,
+            dynamic_params: public_input.dynamic_params,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum PublicInputError {
     #[error("The trace slice provided is empty")]
@@ -219,4 +286,48 @@ mod tests {
             deserialized_public_input.public_memory
         );
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn serialize_and_deserialize_owned_public_input() {
+        let public_input = OwnedPublicInput {
+            layout: "dynamic".to_string(),
+            rc_min: 32762,
+            rc_max: 32769,
+            n_steps: 16,
+            memory_segments: HashMap::from([
+                ("program".to_string(), (1, 5).into()),
+                ("execution".to_string(), (25, 40).into()),
+            ]),
+            public_memory: vec![
+                PublicMemoryEntry {
+                    address: 1,
+                    value: Some(Felt252::from(0x40780017fff7fff_u64)),
+                    page: 0,
+                },
+                PublicMemoryEntry {
+                    address: 2,
+                    value: None,
+                    page: 1,
+                },
+            ],
+            dynamic_params: Some(
+                serde_json::from_str(include_str!("tests/cairo_layout_params_file.json")).unwrap(),
+            ),
+        };
+        let deserialized_public_input =
+            OwnedPublicInput::deserialize_json(&public_input.serialize_json().unwrap()).unwrap();
+        assert_eq!(deserialized_public_input, public_input);
+
+        let borrowed_public_input = public_input.as_public_input();
+        assert_eq!(
+            OwnedPublicInput::from(borrowed_public_input.clone()),
+            public_input
+        );
+        let borrowed_json = borrowed_public_input.serialize_json().unwrap();
+        assert_eq!(
+            OwnedPublicInput::deserialize_json(&borrowed_json).unwrap(),
+            public_input
+        );
+    }
 }
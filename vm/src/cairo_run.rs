@@ -1,15 +1,37 @@
+#[cfg(feature = "std")]
+use crate::vm::{debugger::Debugger, differential::DifferentialChecker};
 use crate::{
     hint_processor::hint_processor_definition::HintProcessor,
-    types::{builtin_name::BuiltinName, layout_name::LayoutName, program::Program},
+    types::{
+        builtin_name::BuiltinName,
+        layout::CairoLayoutParams,
+        layout_name::LayoutName,
+        program::Program,
+        relocatable::{relocate_address, relocate_value, Relocatable},
+    },
     vm::{
         errors::{
-            cairo_run_errors::CairoRunError, runner_errors::RunnerError, vm_exception::VmException,
+            cairo_run_errors::CairoRunError, runner_errors::RunnerError, trace_errors::TraceError,
+            vm_errors::VirtualMachineError, vm_exception::VmException,
+        },
+        runners::{
+            cairo_pie::CairoPie,
+            cairo_runner::{CairoRunner, RunnerMode},
+            program_cache::ProgramCache,
         },
-        runners::{cairo_pie::CairoPie, cairo_runner::CairoRunner},
         security::verify_secure_runner,
+        trace::{trace_entry::RelocatedTraceEntry, trace_sink::TraceSink},
+        vm_core::VirtualMachine,
     },
 };
+#[cfg(feature = "std")]
+use std::{
+    io::{BufRead, Write},
+    num::NonZeroUsize,
+    sync::Mutex,
+};
 
+use crate::stdlib::prelude::*;
 use crate::Felt252;
 use bincode::enc::write::Writer;
 
@@ -26,6 +48,9 @@ pub struct CairoRunConfig<'a> {
     pub trace_enabled: bool,
     pub relocate_mem: bool,
     pub layout: LayoutName,
+    /// Parameters of the `dynamic` layout, see [CairoLayoutParams]
+    #[cfg_attr(feature = "test_utils", arbitrary(value = None))]
+    pub dynamic_layout_params: Option<CairoLayoutParams>,
     pub proof_mode: bool,
     pub secure_run: Option<bool>,
     pub disable_trace_padding: bool,
@@ -39,6 +64,7 @@ impl<'a> Default for CairoRunConfig<'a> {
             trace_enabled: false,
             relocate_mem: false,
             layout: LayoutName::plain,
+            dynamic_layout_params: None,
             proof_mode: false,
             secure_run: None,
             disable_trace_padding: false,
@@ -47,6 +73,26 @@ impl<'a> Default for CairoRunConfig<'a> {
     }
 }
 
+impl<'a> CairoRunConfig<'a> {
+    fn runner_mode(&self) -> RunnerMode {
+        if self.proof_mode {
+            RunnerMode::ProofModeCanonical
+        } else {
+            RunnerMode::ExecutionMode
+        }
+    }
+}
+
+/// Configuration of a [run_batch]
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub struct BatchRunConfig<'a> {
+    /// Configuration shared by every run of the batch
+    pub run_config: CairoRunConfig<'a>,
+    /// Amount of threads running the batch, defaults to the available parallelism
+    pub n_threads: Option<usize>,
+}
+
 /// Runs a program with a customized execution scope.
 pub fn cairo_run_program_with_initial_scope(
     program: &Program,
@@ -54,6 +100,199 @@ pub fn cairo_run_program_with_initial_scope(
     hint_processor: &mut dyn HintProcessor,
     exec_scopes: ExecutionScopes,
 ) -> Result<CairoRunner, CairoRunError> {
+    run_program(
+        CairoRunner::new_v2(
+            program,
+            cairo_run_config.layout,
+            cairo_run_config.dynamic_layout_params.clone(),
+            cairo_run_config.runner_mode(),
+            cairo_run_config.trace_enabled,
+        )?,
+        cairo_run_config,
+        hint_processor,
+        exec_scopes,
+        |cairo_runner, end, hint_processor| cairo_runner.run_until_pc(end, hint_processor),
+    )
+}
+
+/// Runs a program with a customized execution scope, sharing the program and its decoded
+/// instructions with the other runs of the [ProgramCache].
+pub fn cairo_run_program_with_cache(
+    cache: &ProgramCache,
+    cairo_run_config: &CairoRunConfig,
+    hint_processor: &mut dyn HintProcessor,
+    exec_scopes: ExecutionScopes,
+) -> Result<CairoRunner, CairoRunError> {
+    run_program(
+        CairoRunner::new_with_cache(
+            cache,
+            cairo_run_config.layout,
+            cairo_run_config.dynamic_layout_params.clone(),
+            cairo_run_config.runner_mode(),
+            cairo_run_config.trace_enabled,
+        )?,
+        cairo_run_config,
+        hint_processor,
+        exec_scopes,
+        |cairo_runner, end, hint_processor| cairo_runner.run_until_pc(end, hint_processor),
+    )
+}
+
+/// Runs many independent executions across threads, returning the results in the order of
+/// `programs_and_inputs`.
+///
+/// The runs of the same [ProgramCache] share the parsed program and its decoded instructions.
+/// Each thread creates the hint processor and the initial execution scopes of a run from its
+/// input with `prepare`, and reduces the finished runner to the value returned for the run with
+/// `finish`, so that neither of them has to be sent between threads.
+#[cfg(feature = "std")]
+pub fn run_batch<I, H, R>(
+    programs_and_inputs: Vec<(ProgramCache, I)>,
+    config: &BatchRunConfig,
+    prepare: impl Fn(I) -> (H, ExecutionScopes) + Sync,
+    finish: impl Fn(Result<CairoRunner, CairoRunError>) -> R + Sync,
+) -> Vec<R>
+where
+    I: Send,
+    H: HintProcessor,
+    R: Send,
+{
+    let n_runs = programs_and_inputs.len();
+    let n_threads = config
+        .n_threads
+        .or_else(|| {
+            std::thread::available_parallelism()
+                .ok()
+                .map(NonZeroUsize::get)
+        })
+        .unwrap_or(1)
+        .clamp(1, n_runs.max(1));
+
+    let pending = Mutex::new(programs_and_inputs.into_iter().enumerate());
+    let results = Mutex::new((0..n_runs).map(|_| None).collect::<Vec<Option<R>>>());
+    std::thread::scope(|scope| {
+        for _ in 0..n_threads {
+            scope.spawn(|| loop {
+                let Some((index, (cache, input))) = pending.lock().unwrap().next() else {
+                    break;
+                };
+                let (mut hint_processor, exec_scopes) = prepare(input);
+                let result = finish(cairo_run_program_with_cache(
+                    &cache,
+                    &config.run_config,
+                    &mut hint_processor,
+                    exec_scopes,
+                ));
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every run of the batch is finished"))
+        .collect()
+}
+
+/// Runs a program writing its trace entries to `trace_sink` while it is executed, instead of
+/// keeping the whole trace in memory, see [VirtualMachine::set_trace_sink].
+/// The trace must be enabled in the `cairo_run_config`.
+pub fn cairo_run_program_with_trace_sink(
+    program: &Program,
+    cairo_run_config: &CairoRunConfig,
+    hint_processor: &mut dyn HintProcessor,
+    trace_sink: Box<dyn TraceSink>,
+) -> Result<CairoRunner, CairoRunError> {
+    let mut cairo_runner = CairoRunner::new_v2(
+        program,
+        cairo_run_config.layout,
+        cairo_run_config.dynamic_layout_params.clone(),
+        cairo_run_config.runner_mode(),
+        cairo_run_config.trace_enabled,
+    )?;
+    cairo_runner.vm.set_trace_sink(trace_sink)?;
+    run_program(
+        cairo_runner,
+        cairo_run_config,
+        hint_processor,
+        ExecutionScopes::new(),
+        |cairo_runner, end, hint_processor| cairo_runner.run_until_pc(end, hint_processor),
+    )
+}
+
+/// Runs a program through the interactive [Debugger], which takes control of the execution until the end of the program.
+#[cfg(feature = "std")]
+pub fn cairo_run_program_with_debugger<R: BufRead, W: Write>(
+    program: &Program,
+    cairo_run_config: &CairoRunConfig,
+    hint_processor: &mut dyn HintProcessor,
+    debugger: &mut Debugger<R, W>,
+) -> Result<CairoRunner, CairoRunError> {
+    run_program(
+        CairoRunner::new_v2(
+            program,
+            cairo_run_config.layout,
+            cairo_run_config.dynamic_layout_params.clone(),
+            cairo_run_config.runner_mode(),
+            cairo_run_config.trace_enabled,
+        )?,
+        cairo_run_config,
+        hint_processor,
+        ExecutionScopes::new(),
+        |cairo_runner, end, hint_processor| {
+            debugger.run_until_pc(cairo_runner, end, hint_processor)
+        },
+    )
+}
+
+/// Runs the program comparing it step by step with a reference execution, see
+/// [DifferentialChecker]. The trace is enabled and the memory relocated regardless of
+/// `cairo_run_config`, as they are compared with the reference at the end of the run.
+#[cfg(feature = "std")]
+pub fn cairo_run_program_with_reference(
+    program: &Program,
+    cairo_run_config: &CairoRunConfig,
+    hint_processor: &mut dyn HintProcessor,
+    checker: &mut DifferentialChecker,
+) -> Result<CairoRunner, CairoRunError> {
+    let cairo_run_config = CairoRunConfig {
+        trace_enabled: true,
+        relocate_mem: true,
+        dynamic_layout_params: cairo_run_config.dynamic_layout_params.clone(),
+        ..*cairo_run_config
+    };
+    let cairo_runner = run_program(
+        CairoRunner::new_v2(
+            program,
+            cairo_run_config.layout,
+            cairo_run_config.dynamic_layout_params.clone(),
+            cairo_run_config.runner_mode(),
+            cairo_run_config.trace_enabled,
+        )?,
+        &cairo_run_config,
+        hint_processor,
+        ExecutionScopes::new(),
+        |cairo_runner, end, hint_processor| checker.run_until_pc(cairo_runner, end, hint_processor),
+    )?;
+    checker.check_relocated(&cairo_runner)?;
+    Ok(cairo_runner)
+}
+
+fn run_program<F>(
+    mut cairo_runner: CairoRunner,
+    cairo_run_config: &CairoRunConfig,
+    hint_processor: &mut dyn HintProcessor,
+    exec_scopes: ExecutionScopes,
+    run_until_pc: F,
+) -> Result<CairoRunner, CairoRunError>
+where
+    F: FnOnce(
+        &mut CairoRunner,
+        Relocatable,
+        &mut dyn HintProcessor,
+    ) -> Result<(), VirtualMachineError>,
+{
     let secure_run = cairo_run_config
         .secure_run
         .unwrap_or(!cairo_run_config.proof_mode);
@@ -62,20 +301,12 @@ pub fn cairo_run_program_with_initial_scope(
         .allow_missing_builtins
         .unwrap_or(cairo_run_config.proof_mode);
 
-    let mut cairo_runner = CairoRunner::new(
-        program,
-        cairo_run_config.layout,
-        cairo_run_config.proof_mode,
-        cairo_run_config.trace_enabled,
-    )?;
-
     cairo_runner.exec_scopes = exec_scopes;
 
     let end = cairo_runner.initialize(allow_missing_builtins)?;
     // check step calculation
 
-    cairo_runner
-        .run_until_pc(end, hint_processor)
+    run_until_pc(&mut cairo_runner, end, hint_processor)
         .map_err(|err| VmException::from_vm_error(&cairo_runner, err))?;
 
     if cairo_run_config.proof_mode {
@@ -148,10 +379,11 @@ pub fn cairo_run_pie(
     let allow_missing_builtins = cairo_run_config.allow_missing_builtins.unwrap_or_default();
 
     let program = Program::from_stripped_program(&pie.metadata.program);
-    let mut cairo_runner = CairoRunner::new(
+    let mut cairo_runner = CairoRunner::new_v2(
         &program,
         cairo_run_config.layout,
-        false,
+        cairo_run_config.dynamic_layout_params.clone(),
+        RunnerMode::ExecutionMode,
         cairo_run_config.trace_enabled,
     )?;
 
@@ -220,10 +452,11 @@ pub fn cairo_run_fuzzed_program(
         .allow_missing_builtins
         .unwrap_or(cairo_run_config.proof_mode);
 
-    let mut cairo_runner = CairoRunner::new(
+    let mut cairo_runner = CairoRunner::new_v2(
         &program,
         cairo_run_config.layout,
-        cairo_run_config.proof_mode,
+        cairo_run_config.dynamic_layout_params.clone(),
+        cairo_run_config.runner_mode(),
         cairo_run_config.trace_enabled,
     )?;
 
@@ -299,11 +532,81 @@ pub fn write_encoded_memory(
     Ok(())
 }
 
+/// Reads a trace written by [write_encoded_trace].
+/// Returns `None` if the length of `bytes` isn't a multiple of the size of an entry.
+pub fn read_encoded_trace(bytes: &[u8]) -> Option<Vec<RelocatedTraceEntry>> {
+    const ENTRY_SIZE: usize = 24;
+    if bytes.len() % ENTRY_SIZE != 0 {
+        return None;
+    }
+    let read_u64 = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().unwrap_or_default());
+    Some(
+        bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|entry| RelocatedTraceEntry {
+                ap: read_u64(&entry[0..8]) as usize,
+                fp: read_u64(&entry[8..16]) as usize,
+                pc: read_u64(&entry[16..24]) as usize,
+            })
+            .collect(),
+    )
+}
+
+/// Reads a memory written by [write_encoded_memory].
+/// Returns `None` if the length of `bytes` isn't a multiple of the size of a cell.
+pub fn read_encoded_memory(bytes: &[u8]) -> Option<Vec<Option<Felt252>>> {
+    const CELL_SIZE: usize = 40;
+    if bytes.len() % CELL_SIZE != 0 {
+        return None;
+    }
+    // Relocated addresses start at 1
+    let mut memory = vec![None];
+    for cell in bytes.chunks_exact(CELL_SIZE) {
+        let address = u64::from_le_bytes(cell[0..8].try_into().unwrap_or_default()) as usize;
+        if memory.len() <= address {
+            memory.resize(address + 1, None);
+        }
+        memory[address] = Some(Felt252::from_bytes_le_slice(&cell[8..40]));
+    }
+    Some(memory)
+}
+
+/// Writes the binary representation of the memory of a finished run, in the same format as
+/// [write_encoded_memory], relocating each cell as it is written instead of building the whole
+/// relocated memory first.
+/// The `relocation_table` can be obtained with [VirtualMachine::relocate_segments] once the
+/// segment sizes are computed.
+pub fn write_relocated_memory(
+    vm: &VirtualMachine,
+    relocation_table: &[usize],
+    dest: &mut impl Writer,
+) -> Result<(), TraceError> {
+    let memory = &vm.segments.memory.data;
+    for index in 0..memory.num_segments() {
+        for (offset, cell) in memory.written_cells(index) {
+            let Some(value) = cell.get_value() else {
+                continue;
+            };
+            let address = relocate_address(
+                Relocatable::from((index as isize, offset)),
+                relocation_table,
+            )?;
+            let value = relocate_value(value, relocation_table)?;
+            dest.write(&(address as u64).to_le_bytes())
+                .and_then(|_| dest.write(&value.to_bytes_le()))
+                .map_err(|e| TraceError::Stream(e.to_string().into_boxed_str()))?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::stdlib::prelude::*;
+    use crate::stdlib::collections::HashMap;
     use crate::vm::runners::cairo_runner::RunResources;
+    #[cfg(feature = "std")]
+    use crate::vm::trace::trace_sink::{write_relocated_raw_trace, RawTraceWriter};
     use crate::Felt252;
     use crate::{
         hint_processor::{
@@ -447,6 +750,109 @@ mod tests {
         assert_eq!(*expected_encoded_memory, buffer);
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_encoded_trace_and_memory() {
+        let trace = [
+            RelocatedTraceEntry {
+                pc: 1,
+                ap: 5,
+                fp: 5,
+            },
+            RelocatedTraceEntry {
+                pc: 3,
+                ap: 6,
+                fp: 5,
+            },
+        ];
+        let mut buffer = [0; 48];
+        write_encoded_trace(&trace, &mut SliceWriter::new(&mut buffer)).unwrap();
+        assert_eq!(read_encoded_trace(&buffer), Some(trace.to_vec()));
+        assert_eq!(read_encoded_trace(&buffer[..47]), None);
+
+        let memory = [None, Some(Felt252::from(7)), None, Some(Felt252::from(-1))];
+        let mut buffer = [0; 80];
+        write_encoded_memory(&memory, &mut SliceWriter::new(&mut buffer)).unwrap();
+        assert_eq!(read_encoded_memory(&buffer), Some(memory.to_vec()));
+        assert_eq!(read_encoded_memory(&buffer[..41]), None);
+        assert_eq!(read_encoded_memory(&[]), Some(vec![None]));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn streamed_run_matches_in_memory_run() {
+        #[derive(Default)]
+        struct VecWriter(Vec<u8>);
+
+        impl Writer for VecWriter {
+            fn write(&mut self, bytes: &[u8]) -> Result<(), bincode::error::EncodeError> {
+                self.0.extend_from_slice(bytes);
+                Ok(())
+            }
+        }
+
+        /// Raw trace output that can be read after moving it into the trace sink
+        struct SharedRawTrace(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl std::io::Write for SharedRawTrace {
+            fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(bytes)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let program = program_b();
+        let cairo_run_config = CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            layout: LayoutName::small,
+            ..Default::default()
+        };
+        let runner = cairo_run_program(
+            &program,
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let (mut expected_trace, mut expected_memory) =
+            (VecWriter::default(), VecWriter::default());
+        write_encoded_trace(
+            runner.relocated_trace.as_ref().unwrap(),
+            &mut expected_trace,
+        )
+        .unwrap();
+        write_encoded_memory(&runner.relocated_memory, &mut expected_memory).unwrap();
+
+        let raw_trace = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let streamed_runner = cairo_run_program_with_trace_sink(
+            &program,
+            &CairoRunConfig {
+                relocate_mem: false,
+                ..cairo_run_config
+            },
+            &mut BuiltinHintProcessor::new_empty(),
+            Box::new(RawTraceWriter::new(SharedRawTrace(raw_trace.clone()))),
+        )
+        .unwrap();
+        assert!(streamed_runner.relocated_trace.is_none());
+        assert!(streamed_runner.relocated_memory.is_empty());
+        let relocation_table = streamed_runner.vm.segments.relocate_segments().unwrap();
+        let (mut trace, mut memory) = (VecWriter::default(), VecWriter::default());
+        let raw_trace = raw_trace.lock().unwrap().clone();
+        write_relocated_raw_trace(&mut raw_trace.as_slice(), &relocation_table, &mut trace)
+            .unwrap();
+        write_relocated_memory(&streamed_runner.vm, &relocation_table, &mut memory).unwrap();
+        assert_eq!(trace.0, expected_trace.0);
+        assert_eq!(memory.0, expected_memory.0);
+        assert_eq!(
+            streamed_runner.get_execution_resources().unwrap(),
+            runner.get_execution_resources().unwrap()
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn run_with_no_trace() {
@@ -518,4 +924,50 @@ mod tests {
             CairoRunError::Runner(RunnerError::PieNStepsVsRunResourcesNStepsMismatch)
         )));
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn run_batch_shares_program_cache() {
+        let program = program_b();
+        let cache = ProgramCache::new(program);
+        // Runs limited to 5 steps fail, the program needs 18
+        let programs_and_inputs = [100, 5, 100, 100, 5, 100]
+            .into_iter()
+            .map(|n_steps| (cache.clone(), n_steps))
+            .collect();
+        let config = BatchRunConfig {
+            run_config: CairoRunConfig {
+                layout: LayoutName::small,
+                ..Default::default()
+            },
+            n_threads: Some(3),
+        };
+        let results = run_batch(
+            programs_and_inputs,
+            &config,
+            |n_steps| {
+                (
+                    BuiltinHintProcessor::new(HashMap::new(), RunResources::new(n_steps)),
+                    ExecutionScopes::new(),
+                )
+            },
+            |result| result.map(|runner| runner.vm.current_step).ok(),
+        );
+        assert_eq!(
+            results,
+            [Some(18), None, Some(18), Some(18), None, Some(18)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn run_batch_empty() {
+        let results = run_batch(
+            Vec::<(ProgramCache, ())>::new(),
+            &BatchRunConfig::default(),
+            |_| (BuiltinHintProcessor::new_empty(), ExecutionScopes::new()),
+            |result| result.is_ok(),
+        );
+        assert!(results.is_empty());
+    }
 }
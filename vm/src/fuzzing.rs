@@ -0,0 +1,611 @@
+//! Signature-driven fuzzing of Cairo 0 functions
+//!
+//! A [FunctionFuzzer] reads the signature of a function from the `Args` and `ImplicitArgs`
+//! identifiers of its [Program], and generates inputs matching the `cairo_type` of each argument.
+//! Inputs are built from raw bytes, so they can either come from a coverage-guided fuzzer (see
+//! [FunctionFuzzer::run_input]), or from a seeded random generator (see [FunctionFuzzer::fuzz]).
+//! Each input is executed with [CairoRunner::run_from_entrypoint], and the ones that make the run
+//! fail are reported as [FuzzFailure]s.
+
+use crate::stdlib::{collections::HashMap, prelude::*};
+
+use arbitrary::{Arbitrary, Unstructured};
+use rand::{rngs::SmallRng, RngCore, SeedableRng};
+use thiserror_no_std::Error;
+
+use crate::{
+    hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    types::{
+        builtin_name::BuiltinName, layout_name::LayoutName, program::Program,
+        relocatable::MaybeRelocatable,
+    },
+    vm::{
+        errors::{
+            cairo_run_errors::CairoRunError, runner_errors::RunnerError,
+            vm_errors::VirtualMachineError,
+        },
+        runners::cairo_runner::{CairoArg, CairoRunner, RunResources},
+    },
+    Felt252,
+};
+
+// Maximum nesting of pointers and structs in generated arguments, reached by recursive types
+const MAX_TYPE_DEPTH: usize = 4;
+
+#[derive(Debug, Error)]
+pub enum FuzzError {
+    #[error("Function {0} not found")]
+    FunctionNotFound(String),
+    #[error("Missing {0} struct of the fuzzed function")]
+    MissingSignature(String),
+    #[error("Unsupported argument type: {0}")]
+    UnsupportedType(String),
+    #[error(transparent)]
+    Runner(#[from] RunnerError),
+    #[error(transparent)]
+    Arbitrary(#[from] arbitrary::Error),
+}
+
+#[derive(Clone, Debug)]
+pub struct FuzzConfig {
+    pub layout: LayoutName,
+    /// Steps after which a run is reported as [FailureKind::StepLimitExceeded]
+    pub max_steps: usize,
+    /// Maximum number of elements of the arrays passed as pointer arguments
+    pub max_array_len: usize,
+    /// Number of random bytes each input is generated from in [FunctionFuzzer::fuzz]
+    pub input_size: usize,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        FuzzConfig {
+            layout: LayoutName::all_cairo,
+            max_steps: 100_000,
+            max_array_len: 16,
+            input_size: 1024,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureKind {
+    /// A hint raised an error
+    Hint,
+    /// An assert instruction failed
+    Assert,
+    /// The run exceeded [FuzzConfig::max_steps]
+    StepLimitExceeded,
+    /// Any other error raised by the VM
+    Vm,
+}
+
+/// An input that made the fuzzed function fail
+#[derive(Clone, Debug)]
+pub struct FuzzFailure {
+    /// Bytes the input was generated from, which reproduce it when passed to [FunctionFuzzer::run_input]
+    pub data: Vec<u8>,
+    /// Implicit arguments followed by the explicit ones, as passed to the function
+    pub args: Vec<CairoArg>,
+    pub kind: FailureKind,
+    pub error: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FuzzReport {
+    pub runs: usize,
+    pub failures: Vec<FuzzFailure>,
+}
+
+// Layout of a function argument, resolved from its cairo_type
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ArgType {
+    Felt,
+    // Pointer to an array of elements of the given type, or to an empty segment if the type nests
+    // too deep
+    Pointer(Option<Box<ArgType>>),
+    // Members of a struct or tuple, by increasing offset
+    Struct(Vec<ArgType>),
+    // Implicit argument holding the base of a builtin segment
+    Builtin(BuiltinName),
+}
+
+#[derive(Clone, Debug)]
+struct Param {
+    name: String,
+    ty: ArgType,
+}
+
+pub struct FunctionFuzzer<'a> {
+    program: &'a Program,
+    config: FuzzConfig,
+    entrypoint: usize,
+    implicit_args: Vec<Param>,
+    args: Vec<Param>,
+    builtins: Vec<BuiltinName>,
+}
+
+impl<'a> FunctionFuzzer<'a> {
+    /// Creates a fuzzer for the function `__main__.<function>` of `program`
+    pub fn new(
+        program: &'a Program,
+        function: &str,
+        config: FuzzConfig,
+    ) -> Result<Self, FuzzError> {
+        let full_name = format!("__main__.{function}");
+        let entrypoint = program
+            .get_identifier(&full_name)
+            .and_then(|identifier| identifier.pc)
+            .ok_or_else(|| FuzzError::FunctionNotFound(function.to_string()))?;
+        let implicit_args = signature_params(program, &format!("{full_name}.ImplicitArgs"), true)?;
+        let args = signature_params(program, &format!("{full_name}.Args"), false)?;
+        let builtins = implicit_args
+            .iter()
+            .filter_map(|param| match param.ty {
+                ArgType::Builtin(builtin) => Some(builtin),
+                _ => None,
+            })
+            .collect();
+        Ok(FunctionFuzzer {
+            program,
+            config,
+            entrypoint,
+            implicit_args,
+            args,
+            builtins,
+        })
+    }
+
+    /// Generates an input from `data` and runs the function with it.
+    /// Returns the failure it triggered, if any.
+    pub fn run_input(&self, data: &[u8]) -> Result<Option<FuzzFailure>, FuzzError> {
+        let mut runner = CairoRunner::new(self.program, self.config.layout, false, false)?;
+        runner.initialize_function_runner_cairo_1(&self.builtins)?;
+        let args = self.arbitrary_args(&mut Unstructured::new(data), &runner)?;
+        let mut hint_processor =
+            BuiltinHintProcessor::new(HashMap::new(), RunResources::new(self.config.max_steps));
+        let result = runner.run_from_entrypoint(
+            self.entrypoint,
+            &args.iter().collect::<Vec<_>>(),
+            false,
+            None,
+            &mut hint_processor,
+        );
+        Ok(result.err().map(|error| FuzzFailure {
+            data: data.to_vec(),
+            args,
+            kind: failure_kind(&error),
+            error: error.to_string(),
+        }))
+    }
+
+    /// Runs the function with `iterations` random inputs, generated from `seed`
+    pub fn fuzz(&self, iterations: usize, seed: u64) -> Result<FuzzReport, FuzzError> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut data = vec![0; self.config.input_size];
+        let mut report = FuzzReport::default();
+        for _ in 0..iterations {
+            rng.fill_bytes(&mut data);
+            report.failures.extend(self.run_input(&data)?);
+            report.runs += 1;
+        }
+        Ok(report)
+    }
+
+    fn arbitrary_args(
+        &self,
+        u: &mut Unstructured,
+        runner: &CairoRunner,
+    ) -> Result<Vec<CairoArg>, FuzzError> {
+        // Arrays are generated first, so that their `<name>_len` or `n_<name>` arguments can hold
+        // their actual length
+        let mut arrays = HashMap::new();
+        let mut lengths = HashMap::new();
+        for param in self.args.iter() {
+            if let ArgType::Pointer(_) = param.ty {
+                let mut cells = Vec::new();
+                let len = self.arbitrary_cells(u, runner, &param.ty, 0, &mut cells)?;
+                arrays.insert(param.name.as_str(), cells);
+                lengths.insert(format!("{}_len", param.name), len);
+                lengths.insert(format!("n_{}", param.name), len);
+            }
+        }
+
+        let mut args = Vec::new();
+        for param in self.implicit_args.iter() {
+            self.arbitrary_cells(u, runner, &param.ty, 0, &mut args)?;
+        }
+        for param in self.args.iter() {
+            match (&param.ty, lengths.get(&param.name)) {
+                (ArgType::Pointer(_), _) => {
+                    args.extend(arrays.remove(param.name.as_str()).unwrap_or_default())
+                }
+                (ArgType::Felt, Some(len)) => args.push(MaybeRelocatable::from(*len).into()),
+                (ty, _) => {
+                    self.arbitrary_cells(u, runner, ty, 0, &mut args)?;
+                }
+            }
+        }
+        Ok(args)
+    }
+
+    // Appends the cells of an arbitrary value of type `ty` to `cells`.
+    // Returns the number of elements of the array if `ty` is a pointer.
+    fn arbitrary_cells(
+        &self,
+        u: &mut Unstructured,
+        runner: &CairoRunner,
+        ty: &ArgType,
+        depth: usize,
+        cells: &mut Vec<CairoArg>,
+    ) -> Result<usize, FuzzError> {
+        match ty {
+            ArgType::Felt => cells.push(MaybeRelocatable::from(arbitrary_felt(u)?).into()),
+            ArgType::Builtin(builtin) => {
+                let base = runner
+                    .vm
+                    .builtin_runners
+                    .iter()
+                    .find(|runner| runner.name() == *builtin)
+                    .map(|runner| runner.base())
+                    .ok_or(RunnerError::MissingBuiltin(*builtin))?;
+                cells.push(MaybeRelocatable::from((base as isize, 0)).into())
+            }
+            ArgType::Struct(members) => {
+                for member in members.iter() {
+                    self.arbitrary_cells(u, runner, member, depth + 1, cells)?;
+                }
+            }
+            ArgType::Pointer(element) => {
+                let mut elements = Vec::new();
+                let mut len = 0;
+                if let Some(element) = element.as_ref().filter(|_| depth < MAX_TYPE_DEPTH) {
+                    len = u.int_in_range(0..=self.config.max_array_len)?;
+                    for _ in 0..len {
+                        self.arbitrary_cells(u, runner, element, depth + 1, &mut elements)?;
+                    }
+                }
+                cells.push(CairoArg::Composed(elements));
+                return Ok(len);
+            }
+        }
+        Ok(0)
+    }
+}
+
+// Felts biased towards the edge values of the field and of the common integer ranges
+fn arbitrary_felt(u: &mut Unstructured) -> arbitrary::Result<Felt252> {
+    Ok(match u.int_in_range(0..=7u8)? {
+        0 => Felt252::ZERO,
+        1 => Felt252::ONE,
+        2 => Felt252::MAX,
+        3 => Felt252::from(u128::MAX) + Felt252::ONE,
+        4 => Felt252::from(u8::arbitrary(u)?),
+        5 => Felt252::from(u64::arbitrary(u)?),
+        6 => -Felt252::from(u64::arbitrary(u)?),
+        _ => Felt252::arbitrary(u)?,
+    })
+}
+
+fn failure_kind(error: &CairoRunError) -> FailureKind {
+    let error = match error {
+        CairoRunError::VmException(exception) => &exception.inner_exc,
+        CairoRunError::VirtualMachine(error) => error,
+        _ => return FailureKind::Vm,
+    };
+    match error {
+        VirtualMachineError::Hint(_) => FailureKind::Hint,
+        VirtualMachineError::DiffAssertValues(_) => FailureKind::Assert,
+        VirtualMachineError::UnfinishedExecution => FailureKind::StepLimitExceeded,
+        _ => FailureKind::Vm,
+    }
+}
+
+// Reads the members of the Args or ImplicitArgs struct of a function, by increasing offset
+fn signature_params(
+    program: &Program,
+    struct_name: &str,
+    implicit: bool,
+) -> Result<Vec<Param>, FuzzError> {
+    let members = program
+        .get_identifier(struct_name)
+        .and_then(|identifier| identifier.members.as_ref())
+        .ok_or_else(|| FuzzError::MissingSignature(struct_name.to_string()))?;
+    let mut members: Vec<_> = members.iter().collect();
+    members.sort_by_key(|(_, member)| member.offset);
+    members
+        .into_iter()
+        .map(|(name, member)| {
+            let builtin = name
+                .strip_suffix("_ptr")
+                .and_then(BuiltinName::from_str)
+                .filter(|_| implicit);
+            let ty = match builtin {
+                Some(builtin) => ArgType::Builtin(builtin),
+                None => resolve_type(program, &member.cairo_type, 0)?,
+            };
+            Ok(Param {
+                name: name.clone(),
+                ty,
+            })
+        })
+        .collect()
+}
+
+fn resolve_type(program: &Program, cairo_type: &str, depth: usize) -> Result<ArgType, FuzzError> {
+    let cairo_type = cairo_type.trim();
+    if let Some(pointee) = cairo_type.strip_suffix('*') {
+        if depth >= MAX_TYPE_DEPTH {
+            return Ok(ArgType::Pointer(None));
+        }
+        return Ok(ArgType::Pointer(Some(Box::new(resolve_type(
+            program,
+            pointee,
+            depth + 1,
+        )?))));
+    }
+    if cairo_type == "felt" || cairo_type == "codeoffset" {
+        return Ok(ArgType::Felt);
+    }
+    if depth > MAX_TYPE_DEPTH {
+        return Err(FuzzError::UnsupportedType(cairo_type.to_string()));
+    }
+    if let Some(members) = cairo_type
+        .strip_prefix('(')
+        .and_then(|tuple| tuple.strip_suffix(')'))
+    {
+        return split_tuple_members(members)
+            .into_iter()
+            .map(|member| {
+                // Named tuple members are written as `name: type`
+                let member_type = match member.split_once(':') {
+                    Some((name, ty)) if !name.contains('(') => ty,
+                    _ => member,
+                };
+                resolve_type(program, member_type, depth + 1)
+            })
+            .collect::<Result<_, _>>()
+            .map(ArgType::Struct);
+    }
+    let members = program
+        .get_identifier(cairo_type)
+        .filter(|identifier| identifier.type_.as_deref() == Some("struct"))
+        .and_then(|identifier| identifier.members.as_ref())
+        .ok_or_else(|| FuzzError::UnsupportedType(cairo_type.to_string()))?;
+    let mut members: Vec<_> = members.values().collect();
+    members.sort_by_key(|member| member.offset);
+    members
+        .into_iter()
+        .map(|member| resolve_type(program, &member.cairo_type, depth + 1))
+        .collect::<Result<_, _>>()
+        .map(ArgType::Struct)
+}
+
+// Splits the members of a tuple type on the commas that aren't nested in inner tuples
+fn split_tuple_members(members: &str) -> Vec<&str> {
+    let mut split = Vec::new();
+    let mut nesting = 0;
+    let mut start = 0;
+    for (i, c) in members.char_indices() {
+        match c {
+            '(' => nesting += 1,
+            ')' => nesting -= 1,
+            ',' if nesting == 0 => {
+                split.push(&members[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !members[start..].trim().is_empty() {
+        split.push(&members[start..]);
+    }
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn function(pc: usize) -> serde_json::Value {
+        serde_json::json!({ "decorators": [], "pc": pc, "type": "function" })
+    }
+
+    fn signature_struct(full_name: &str, members: &[(&str, &str)]) -> serde_json::Value {
+        let members: serde_json::Map<_, _> = members
+            .iter()
+            .enumerate()
+            .map(|(offset, (name, cairo_type))| {
+                (
+                    name.to_string(),
+                    serde_json::json!({ "cairo_type": cairo_type, "offset": offset }),
+                )
+            })
+            .collect();
+        serde_json::json!({
+            "full_name": full_name,
+            "members": members,
+            "size": members.len(),
+            "type": "struct",
+        })
+    }
+
+    // func assert_seven(x) { assert x = 7; return (); }
+    // func first_is_five{range_check_ptr}(arr: felt*, arr_len) { assert [arr] = 5; return (); }
+    // func takes_structs(p: Point, t: (x: felt, y: felt*)) { return (); }
+    // func loop_forever() { jmp rel 0; }
+    fn test_program() -> Program {
+        let mut identifiers = serde_json::Map::new();
+        let mut add_function =
+            |name: &str, pc: usize, implicit_args: &[(&str, &str)], args: &[(&str, &str)]| {
+                let full_name = format!("__main__.{name}");
+                identifiers.insert(full_name.clone(), function(pc));
+                for (suffix, members) in [("ImplicitArgs", implicit_args), ("Args", args)] {
+                    let struct_name = format!("{full_name}.{suffix}");
+                    identifiers
+                        .insert(struct_name.clone(), signature_struct(&struct_name, members));
+                }
+            };
+        add_function("assert_seven", 0, &[], &[("x", "felt")]);
+        add_function(
+            "first_is_five",
+            3,
+            &[("range_check_ptr", "felt")],
+            &[("arr", "felt*"), ("arr_len", "felt")],
+        );
+        add_function(
+            "takes_structs",
+            10,
+            &[],
+            &[("p", "__main__.Point"), ("t", "(x: felt, y: felt*)")],
+        );
+        add_function("loop_forever", 11, &[], &[]);
+        add_function(
+            "takes_unknown_struct",
+            10,
+            &[],
+            &[("u", "__main__.Unknown")],
+        );
+        identifiers.insert(
+            "__main__.Point".to_string(),
+            signature_struct("__main__.Point", &[("x", "felt"), ("y", "felt")]),
+        );
+        let program = serde_json::json!({
+            "attributes": [],
+            "builtins": [],
+            "data": [
+                "0x400780017fff7ffd", "0x7", "0x208b7fff7fff7ffe",
+                "0x480280007ffc8000", "0x400680017fff7fff", "0x5", "0x480a7ffb7fff8000",
+                "0x208b7fff7fff7ffe", "0x0", "0x0",
+                "0x208b7fff7fff7ffe",
+                "0x10780017fff7fff", "0x0",
+            ],
+            "debug_info": null,
+            "hints": {},
+            "identifiers": identifiers,
+            "main_scope": "__main__",
+            "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+            "reference_manager": { "references": [] },
+        });
+        Program::from_bytes(&serde_json::to_vec(&program).unwrap(), None).unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn fuzz_reports_failed_asserts() {
+        let program = test_program();
+        let fuzzer = FunctionFuzzer::new(&program, "assert_seven", FuzzConfig::default()).unwrap();
+        let report = fuzzer.fuzz(50, 0).unwrap();
+        assert_eq!(report.runs, 50);
+        assert!(!report.failures.is_empty());
+        for failure in report.failures.iter() {
+            assert_eq!(failure.kind, FailureKind::Assert);
+            assert_eq!(failure.args.len(), 1);
+            assert_ne!(failure.args[0], MaybeRelocatable::from(7).into());
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_input_reproduces_failures() {
+        let program = test_program();
+        let fuzzer = FunctionFuzzer::new(&program, "assert_seven", FuzzConfig::default()).unwrap();
+        let failure = fuzzer.fuzz(10, 1).unwrap().failures.remove(0);
+        let rerun = fuzzer.run_input(&failure.data).unwrap().unwrap();
+        assert_eq!(rerun.args, failure.args);
+        assert_eq!(rerun.error, failure.error);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_input_passing() {
+        let program = test_program();
+        let fuzzer = FunctionFuzzer::new(&program, "takes_structs", FuzzConfig::default()).unwrap();
+        assert_matches!(fuzzer.run_input(&[]), Ok(None));
+        assert!(fuzzer.fuzz(20, 0).unwrap().failures.is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn arrays_and_builtins_args() {
+        let program = test_program();
+        let fuzzer = FunctionFuzzer::new(&program, "first_is_five", FuzzConfig::default()).unwrap();
+        assert_eq!(fuzzer.builtins, vec![BuiltinName::range_check]);
+        let report = fuzzer.fuzz(50, 0).unwrap();
+        assert!(!report.failures.is_empty());
+        for failure in report.failures.iter() {
+            // Segments 0 and 1 hold the program and the execution
+            assert_eq!(failure.args[0], MaybeRelocatable::from((2, 0)).into());
+            let elements =
+                assert_matches!(&failure.args[1], CairoArg::Composed(elements) => elements);
+            assert_eq!(
+                failure.args[2],
+                MaybeRelocatable::from(elements.len()).into()
+            );
+            let expected_kind = if elements.is_empty() {
+                FailureKind::Vm
+            } else {
+                FailureKind::Assert
+            };
+            assert_eq!(failure.kind, expected_kind);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn step_limit_exceeded() {
+        let program = test_program();
+        let config = FuzzConfig {
+            max_steps: 100,
+            ..Default::default()
+        };
+        let fuzzer = FunctionFuzzer::new(&program, "loop_forever", config).unwrap();
+        let failure = fuzzer.run_input(&[]).unwrap().unwrap();
+        assert_eq!(failure.kind, FailureKind::StepLimitExceeded);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn new_errors() {
+        let program = test_program();
+        assert_matches!(
+            FunctionFuzzer::new(&program, "missing", FuzzConfig::default()).err(),
+            Some(FuzzError::FunctionNotFound(name)) if name == "missing"
+        );
+        assert_matches!(
+            FunctionFuzzer::new(&program, "takes_unknown_struct", FuzzConfig::default()).err(),
+            Some(FuzzError::UnsupportedType(ty)) if ty == "__main__.Unknown"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn resolve_nested_types() {
+        let program = test_program();
+        assert_eq!(
+            resolve_type(&program, "(a: felt, b: (x: felt, y: __main__.Point*))", 0).unwrap(),
+            ArgType::Struct(vec![
+                ArgType::Felt,
+                ArgType::Struct(vec![
+                    ArgType::Felt,
+                    ArgType::Pointer(Some(Box::new(ArgType::Struct(vec![
+                        ArgType::Felt,
+                        ArgType::Felt
+                    ]))))
+                ])
+            ])
+        );
+        assert_eq!(
+            resolve_type(&program, "felt*****", 0).unwrap(),
+            ArgType::Pointer(Some(Box::new(ArgType::Pointer(Some(Box::new(
+                ArgType::Pointer(Some(Box::new(ArgType::Pointer(Some(Box::new(
+                    ArgType::Pointer(None)
+                ))))))
+            ))))))
+        );
+    }
+}
@@ -0,0 +1,764 @@
+use crate::stdlib::{
+    collections::{BTreeMap, HashMap},
+    prelude::*,
+};
+
+use crate::{
+    hint_processor::{
+        builtin_hint_processor::hint_utils::{
+            get_ptr_from_var_name, get_reference_from_var_name, get_relocatable_from_var_name,
+            insert_value_from_var_name,
+        },
+        hint_processor_definition::{HintExtension, HintProcessorLogic, HintReference},
+    },
+    program_fact::{get_fact_topology_from_additional_data, FactTopology, FactTopologyError},
+    program_hash::compute_program_hash_chain,
+    serde::deserialize_program::{ApTracking, HintParams},
+    types::{
+        builtin_name::BuiltinName,
+        exec_scope::ExecutionScopes,
+        program::Program,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{
+        errors::{hint_errors::HintError, memory_errors::MemoryError},
+        runners::{
+            builtin_runner::OutputBuiltinState,
+            cairo_pie::{
+                BuiltinAdditionalData, CairoPie, OutputBuiltinAdditionalData, PublicMemoryPage,
+            },
+        },
+        vm_core::VirtualMachine,
+    },
+    Felt252,
+};
+
+use super::{
+    program_loader::{load_cairo_pie, load_program},
+    types::{Task, ALL_BUILTINS},
+    vars,
+};
+
+/// Version of the bootloader hashed along with the program of each task
+const BOOTLOADER_VERSION: usize = 0;
+
+/// Implements hint:
+/// %{ ids.program_data_ptr = program_data_base = segments.add() %}
+pub fn allocate_program_data_segment(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let program_data_base = vm.add_memory_segment();
+    insert_value_from_var_name(
+        "program_data_ptr",
+        program_data_base,
+        vm,
+        ids_data,
+        ap_tracking,
+    )?;
+    exec_scopes.insert_value(vars::PROGRAM_DATA_BASE, program_data_base);
+    Ok(())
+}
+
+/// Implements hint:
+/// %{
+///     from starkware.cairo.bootloaders.simple_bootloader.utils import load_program
+///
+///     # Call load_program to load the program header and code to memory.
+///     program_address, program_data_size = load_program(
+///         task=task, memory=memory, program_header=ids.program_header,
+///         builtins_offset=ids.ProgramHeader.builtin_list)
+///     segments.finalize(program_data_base.segment_index, program_data_size)
+/// %}
+pub fn load_task_program(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let program = exec_scopes
+        .get_ref::<Task>(vars::TASK)?
+        .get_program()
+        .map_err(|err| HintError::InvalidTaskProgram(err.to_string().into_boxed_str()))?;
+    let program_header = get_ptr_from_var_name("program_header", vm, ids_data, ap_tracking)?;
+    let (program_address, program_data_size) = load_program(vm, &program, program_header)?;
+
+    let program_data_base: Relocatable = exec_scopes.get(vars::PROGRAM_DATA_BASE)?;
+    vm.segments.finalize(
+        Some(program_data_size),
+        program_data_base.segment_index as usize,
+        None,
+    );
+    exec_scopes.insert_value(vars::PROGRAM_ADDRESS, program_address);
+    Ok(())
+}
+
+/// Implements hint:
+/// %{
+///     # Validate hash.
+///     from starkware.cairo.bootloaders.hash_program import compute_program_hash_chain
+///
+///     assert memory[ids.output_ptr + 1] == compute_program_hash_chain(task.get_program()), \
+///       'Computed hash does not match input.'
+/// %}
+pub fn validate_hash(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let program = exec_scopes
+        .get_ref::<Task>(vars::TASK)?
+        .get_program()
+        .map_err(|err| HintError::InvalidTaskProgram(err.to_string().into_boxed_str()))?;
+    let computed_hash = compute_program_hash_chain(&program, BOOTLOADER_VERSION)
+        .map_err(|err| HintError::InvalidTaskProgram(err.to_string().into_boxed_str()))?;
+    let computed_hash = Felt252::from_bytes_be(&computed_hash.to_bytes_be());
+
+    let output_ptr = get_ptr_from_var_name("output_ptr", vm, ids_data, ap_tracking)?;
+    let program_hash = *vm.get_integer((output_ptr + 1)?)?;
+    if program_hash != computed_hash {
+        return Err(HintError::ProgramHashMismatch(Box::new((
+            computed_hash,
+            program_hash,
+        ))));
+    }
+    Ok(())
+}
+
+/// Implements hint:
+/// %{
+///     # Sanity check.
+///     assert ids.program_address == program_address
+/// %}
+pub fn assert_program_address(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let ids_program_address = get_ptr_from_var_name("program_address", vm, ids_data, ap_tracking)?;
+    let program_address: Relocatable = exec_scopes.get(vars::PROGRAM_ADDRESS)?;
+    if ids_program_address != program_address {
+        return Err(HintError::ProgramAddressMismatch(Box::new((
+            ids_program_address,
+            program_address,
+        ))));
+    }
+    Ok(())
+}
+
+/// Implements the hint calling the task (see [EXECUTE_TASK_CALL_TASK](crate::hint_processor::builtin_hint_processor::hint_code::EXECUTE_TASK_CALL_TASK)).
+///
+/// A Cairo PIE is loaded into memory so that it returns to the instruction after the call.
+/// For a program, the output builtin is given a fresh state to collect the pages of the task, and
+/// the hints of the program are compiled with `hint_processor` and returned, to be executed when
+/// the program is run from `program_address`.
+pub fn call_task(
+    hint_processor: &dyn HintProcessorLogic,
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+) -> Result<HintExtension, HintError> {
+    let program_address: Relocatable = exec_scopes.get(vars::PROGRAM_ADDRESS)?;
+    let (hint_extension, output_runner_data) = match exec_scopes.get_ref::<Task>(vars::TASK)? {
+        Task::Program(program) => {
+            let hint_extension = compile_task_hints(hint_processor, program, program_address)?;
+            let output_builtin = vm.get_output_builtin_mut()?;
+            let output_state = output_builtin.get_state();
+            output_builtin.new_state(output_state.base, output_builtin.included);
+            (hint_extension, Some(output_state))
+        }
+        Task::Pie(pie) => {
+            let n_builtins = pie.metadata.program.builtins.len();
+            let execution_segment_address = (vm.get_ap() - n_builtins)?;
+            let ret_fp = vm.get_fp();
+            // The PIE returns to the instruction following the call
+            let ret_pc = (vm.get_pc() + vm.decode_current_instruction()?.size())?;
+            load_cairo_pie(
+                vm,
+                pie,
+                program_address,
+                execution_segment_address,
+                ret_fp,
+                ret_pc,
+            )?;
+            (HintExtension::default(), None)
+        }
+    };
+
+    exec_scopes.insert_value(vars::OUTPUT_RUNNER_DATA, output_runner_data);
+    exec_scopes.enter_scope(HashMap::new());
+    Ok(hint_extension)
+}
+
+/// Compiles the hints of `program`, placing them at their pc relative to `program_address`
+fn compile_task_hints(
+    hint_processor: &dyn HintProcessorLogic,
+    program: &Program,
+    program_address: Relocatable,
+) -> Result<HintExtension, HintError> {
+    let references = &program.shared_program_data.reference_manager;
+    let mut hint_extension = HintExtension::default();
+    for (pc, hints) in
+        BTreeMap::<usize, Vec<HintParams>>::from(&program.shared_program_data.hints_collection)
+    {
+        let compiled_hints = hints
+            .iter()
+            .map(|hint| {
+                hint_processor.compile_hint(
+                    &hint.code,
+                    &hint.flow_tracking_data.ap_tracking,
+                    &hint.flow_tracking_data.reference_ids,
+                    references,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        hint_extension.insert((program_address + pc)?, compiled_hints);
+    }
+    Ok(hint_extension)
+}
+
+/// Implements hint:
+/// %{
+///     from starkware.cairo.bootloaders.simple_bootloader.utils import write_return_builtins
+///
+///     # Fill the values of all builtin pointers after executing the task.
+///     builtins = task.get_program().builtins
+///     write_return_builtins(
+///         memory=memory, return_builtins_addr=ids.return_builtin_ptrs.address_,
+///         used_builtins=builtins, used_builtins_addr=ids.used_builtins_addr,
+///         pre_execution_builtins_addr=ids.pre_execution_builtin_ptrs.address_, task=task)
+///
+///     vm_exit_scope()
+/// %}
+pub fn write_return_builtins(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    // The task is stored in the scope enclosing the one entered to run it
+    exec_scopes.exit_scope()?;
+    let task = exec_scopes.get_ref::<Task>(vars::TASK)?;
+    let used_builtins = task
+        .get_program()
+        .map_err(|err| HintError::InvalidTaskProgram(err.to_string().into_boxed_str()))?
+        .builtins;
+
+    let return_builtins_addr =
+        get_struct_address("return_builtin_ptrs", vm, ids_data, ap_tracking)?;
+    let pre_execution_builtins_addr =
+        get_struct_address("pre_execution_builtin_ptrs", vm, ids_data, ap_tracking)?;
+    let used_builtins_addr =
+        get_ptr_from_var_name("used_builtins_addr", vm, ids_data, ap_tracking)?;
+
+    let mut used_builtin_offset = 0;
+    for (index, builtin) in ALL_BUILTINS.iter().enumerate() {
+        let pre_execution_ptr = get_value(vm, (pre_execution_builtins_addr + index)?)?;
+        let return_ptr = if used_builtins.contains(builtin) {
+            let return_ptr = get_value(vm, (used_builtins_addr + used_builtin_offset)?)?;
+            used_builtin_offset += 1;
+            if let Task::Pie(pie) = task {
+                check_builtin_usage(pie, *builtin, &pre_execution_ptr, &return_ptr)?;
+            }
+            return_ptr
+        } else {
+            // The builtin is unused, hence its value is the same as before calling the program
+            pre_execution_ptr
+        };
+        vm.insert_value((return_builtins_addr + index)?, return_ptr)?;
+    }
+    Ok(())
+}
+
+/// Checks that the builtin pointers moved by the size of the builtin segment of the PIE
+fn check_builtin_usage(
+    pie: &CairoPie,
+    builtin: BuiltinName,
+    pre_execution_ptr: &MaybeRelocatable,
+    return_ptr: &MaybeRelocatable,
+) -> Result<(), HintError> {
+    let size = pie
+        .metadata
+        .builtin_segments
+        .get(&builtin)
+        .map(|segment| segment.size);
+    let used = match (pre_execution_ptr, return_ptr) {
+        (MaybeRelocatable::RelocatableValue(start), MaybeRelocatable::RelocatableValue(end)) => {
+            (*end - *start).ok()
+        }
+        _ => None,
+    };
+    if size.is_none() || size != used {
+        return Err(HintError::InconsistentBuiltinUsage(builtin));
+    }
+    Ok(())
+}
+
+/// Implements hint:
+/// %{
+///     # Append fact topologies from the task.
+///     fact_topologies.append(get_task_fact_topology(
+///         output_size=ids.return_builtin_ptrs.output - ids.pre_execution_builtin_ptrs.output,
+///         task=task,
+///         output_builtin=output_builtin,
+///         output_runner_data=output_runner_data,
+///     ))
+/// %}
+pub fn append_fact_topology(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    // The output pointer is the first member of the BuiltinData struct
+    let output_start = vm.get_relocatable(get_struct_address(
+        "pre_execution_builtin_ptrs",
+        vm,
+        ids_data,
+        ap_tracking,
+    )?)?;
+    let output_end = vm.get_relocatable(get_struct_address(
+        "return_builtin_ptrs",
+        vm,
+        ids_data,
+        ap_tracking,
+    )?)?;
+    let output_size = (output_end - output_start)?;
+
+    let additional_data = match exec_scopes.get_ref::<Task>(vars::TASK)? {
+        Task::Program(_) => {
+            let output_runner_data = exec_scopes
+                .get_ref::<Option<OutputBuiltinState>>(vars::OUTPUT_RUNNER_DATA)?
+                .clone()
+                .ok_or_else(|| {
+                    HintError::VariableNotInScopeError(
+                        vars::OUTPUT_RUNNER_DATA.to_string().into_boxed_str(),
+                    )
+                })?;
+            // Restore the state of the bootloader, keeping the pages added by the task
+            let output_builtin = vm.get_output_builtin_mut()?;
+            let task_state = output_builtin.get_state();
+            output_builtin.set_state(output_runner_data);
+            task_output_additional_data(task_state, output_start)?
+        }
+        Task::Pie(pie) => match pie.additional_data.0.get(&BuiltinName::output) {
+            Some(BuiltinAdditionalData::Output(additional_data)) => additional_data.clone(),
+            _ => {
+                return Err(HintError::InvalidFactTopology(FactTopologyError(
+                    "The Cairo PIE has no output builtin additional data".into(),
+                )))
+            }
+        },
+    };
+
+    let fact_topology = get_fact_topology_from_additional_data(output_size, &additional_data)?;
+    exec_scopes
+        .get_mut_list_ref::<FactTopology>(vars::FACT_TOPOLOGIES)?
+        .push(fact_topology);
+    Ok(())
+}
+
+/// Returns the additional data of the output builtin state of a task, with the page starts made
+/// relative to the start of its output
+fn task_output_additional_data(
+    task_state: OutputBuiltinState,
+    output_start: Relocatable,
+) -> Result<OutputBuiltinAdditionalData, HintError> {
+    let pages = task_state
+        .pages
+        .into_iter()
+        .map(|(page_id, page)| {
+            let start = page.start.checked_sub(output_start.offset).ok_or_else(|| {
+                HintError::InvalidFactTopology(FactTopologyError(
+                    format!("Page {page_id} starts before the output of the task").into_boxed_str(),
+                ))
+            })?;
+            Ok((
+                page_id,
+                PublicMemoryPage {
+                    start,
+                    size: page.size,
+                },
+            ))
+        })
+        .collect::<Result<_, HintError>>()?;
+    Ok(OutputBuiltinAdditionalData {
+        pages,
+        attributes: task_state.attributes,
+    })
+}
+
+/// Returns the address of a struct variable, or the address it points to if it's a pointer to
+/// the struct, which is what `ids.<var_name>.address_` evaluates to in both cases
+fn get_struct_address(
+    var_name: &str,
+    vm: &VirtualMachine,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<Relocatable, HintError> {
+    let reference = get_reference_from_var_name(var_name, ids_data)?;
+    if reference
+        .cairo_type
+        .as_ref()
+        .is_some_and(|cairo_type| cairo_type.ends_with('*'))
+    {
+        get_ptr_from_var_name(var_name, vm, ids_data, ap_tracking)
+    } else {
+        get_relocatable_from_var_name(var_name, vm, ids_data, ap_tracking)
+    }
+}
+
+fn get_value(vm: &VirtualMachine, address: Relocatable) -> Result<MaybeRelocatable, HintError> {
+    vm.get_maybe(&address)
+        .ok_or_else(|| MemoryError::UnknownMemoryCell(Box::new(address)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        any_box,
+        hint_processor::builtin_hint_processor::{
+            builtin_hint_processor_definition::{BuiltinHintProcessor, HintProcessorData},
+            hint_code,
+        },
+        relocatable,
+        utils::test_utils::*,
+        vm::runners::builtin_runner::OutputBuiltinRunner,
+    };
+    use assert_matches::assert_matches;
+
+    fn scopes_with_task(task: Task) -> ExecutionScopes {
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::TASK, task);
+        exec_scopes
+    }
+
+    #[test]
+    fn run_allocate_program_data_segment() {
+        let mut vm = vm!();
+        add_segments!(vm, 2);
+        vm.run_context.fp = 1;
+        let mut exec_scopes = ExecutionScopes::new();
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["program_data_ptr"],
+                hint_code::EXECUTE_TASK_ALLOCATE_PROGRAM_DATA_SEGMENT,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![vm.segments.memory, ((1, 0), (2, 0))];
+        assert_matches!(
+            exec_scopes.get::<Relocatable>(vars::PROGRAM_DATA_BASE),
+            Ok(base) if base == relocatable!(2, 0)
+        );
+    }
+
+    #[test]
+    fn run_load_task_program() {
+        let mut vm = vm!();
+        vm.run_context.fp = 1;
+        vm.segments = segments![((1, 0), (2, 0))];
+        vm.segments.add();
+        let program = program_b();
+        let program_len = program.shared_program_data.data.len();
+        let mut exec_scopes = scopes_with_task(Task::Program(program));
+        exec_scopes.insert_value(vars::PROGRAM_DATA_BASE, relocatable!(2, 0));
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["program_header"],
+                hint_code::EXECUTE_TASK_LOAD_PROGRAM,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        // The header holds the output and range check builtins
+        assert_matches!(
+            exec_scopes.get::<Relocatable>(vars::PROGRAM_ADDRESS),
+            Ok(address) if address == relocatable!(2, 6)
+        );
+        check_memory![vm.segments.memory, ((2, 3), 2)];
+        assert_eq!(vm.segments.get_segment_size(2), Some(6 + program_len));
+    }
+
+    #[test]
+    fn run_validate_hash() {
+        let program = program_b();
+        let hash = compute_program_hash_chain(&program.get_stripped_program().unwrap(), 0).unwrap();
+        let hash = Felt252::from_bytes_be(&hash.to_bytes_be());
+        let mut exec_scopes = scopes_with_task(Task::Program(program));
+
+        let mut vm = vm!();
+        vm.run_context.fp = 1;
+        vm.segments = segments![((1, 0), (2, 0)), ((2, 0), 1)];
+        vm.insert_value(relocatable!(2, 1), hash).unwrap();
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["output_ptr"],
+                hint_code::EXECUTE_TASK_VALIDATE_HASH,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+
+        let mut vm = vm!();
+        vm.run_context.fp = 1;
+        vm.segments = segments![((1, 0), (2, 0)), ((2, 0), 1), ((2, 1), 42)];
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["output_ptr"],
+                hint_code::EXECUTE_TASK_VALIDATE_HASH,
+                &mut exec_scopes
+            ),
+            Err(HintError::ProgramHashMismatch(bx)) if *bx == (hash, Felt252::from(42))
+        );
+    }
+
+    #[test]
+    fn run_assert_program_address() {
+        let mut vm = vm!();
+        vm.run_context.fp = 1;
+        vm.segments = segments![((1, 0), (2, 5))];
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::PROGRAM_ADDRESS, relocatable!(2, 5));
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["program_address"],
+                hint_code::EXECUTE_TASK_ASSERT_PROGRAM_ADDRESS,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        exec_scopes.insert_value(vars::PROGRAM_ADDRESS, relocatable!(2, 6));
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["program_address"],
+                hint_code::EXECUTE_TASK_ASSERT_PROGRAM_ADDRESS,
+                &mut exec_scopes
+            ),
+            Err(HintError::ProgramAddressMismatch(bx))
+                if *bx == (relocatable!(2, 5), relocatable!(2, 6))
+        );
+    }
+
+    #[test]
+    fn call_task_program_compiles_hints() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../../cairo_programs/manually_compiled/valid_program_a.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let hint_pcs: Vec<usize> =
+            BTreeMap::<usize, Vec<HintParams>>::from(&program.shared_program_data.hints_collection)
+                .into_keys()
+                .collect();
+        let mut vm = vm!();
+        vm.builtin_runners = vec![OutputBuiltinRunner::new(true).into()];
+        vm.get_output_builtin_mut().unwrap().new_state(2, true);
+        vm.get_output_builtin_mut()
+            .unwrap()
+            .add_attribute("bootloader".to_string(), vec![1]);
+        let mut exec_scopes = scopes_with_task(Task::Program(program));
+        exec_scopes.insert_value(vars::PROGRAM_ADDRESS, relocatable!(3, 5));
+
+        let hint_extension = call_task(
+            &BuiltinHintProcessor::new_empty(),
+            &mut vm,
+            &mut exec_scopes,
+        )
+        .unwrap();
+        let mut extension_pcs: Vec<Relocatable> = hint_extension.into_keys().collect();
+        extension_pcs.sort();
+        assert!(!hint_pcs.is_empty());
+        assert_eq!(
+            extension_pcs,
+            hint_pcs
+                .iter()
+                .map(|pc| relocatable!(3, 5 + pc))
+                .collect::<Vec<_>>()
+        );
+        // The task starts with an empty output state, and the previous one is saved
+        assert!(vm
+            .get_output_builtin_mut()
+            .unwrap()
+            .get_state()
+            .attributes
+            .is_empty());
+        exec_scopes.exit_scope().unwrap();
+        assert_matches!(
+            exec_scopes.get_ref::<Option<OutputBuiltinState>>(vars::OUTPUT_RUNNER_DATA),
+            Ok(Some(state)) if state.attributes.contains_key("bootloader")
+        );
+
+        // Without hint extensions the hints of the task can't be loaded
+        assert_matches!(
+            run_hint!(
+                vm,
+                HashMap::new(),
+                hint_code::EXECUTE_TASK_CALL_TASK,
+                &mut exec_scopes
+            ),
+            Err(HintError::TaskHintsNotSupported)
+        );
+    }
+
+    #[test]
+    fn run_call_task_pie() {
+        let pie = run_program_b(false, false).get_cairo_pie().unwrap();
+        let program_data = pie.metadata.program.data.clone();
+        let mut vm = vm!();
+        // call rel 3
+        vm.segments = segments![
+            ((0, 0), 0x1104800180018000_u64),
+            ((1, 0), (2, 0)),
+            ((1, 1), (4, 0))
+        ];
+        add_segments!(vm, 3);
+        vm.run_context.ap = 2;
+        vm.run_context.fp = 2;
+        let mut exec_scopes = scopes_with_task(Task::Pie(Box::new(pie)));
+        exec_scopes.insert_value(vars::PROGRAM_ADDRESS, relocatable!(3, 0));
+        assert_matches!(
+            run_hint!(
+                vm,
+                HashMap::new(),
+                hint_code::EXECUTE_TASK_CALL_TASK,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        for (index, value) in program_data.iter().enumerate() {
+            assert_eq!(vm.get_maybe(&relocatable!(3, index)).as_ref(), Some(value));
+        }
+        // The PIE returns after the call instruction, with the frame of the bootloader
+        check_memory![vm.segments.memory, ((1, 2), (1, 2)), ((1, 3), (0, 2))];
+        exec_scopes.exit_scope().unwrap();
+        assert_matches!(
+            exec_scopes.get_ref::<Option<OutputBuiltinState>>(vars::OUTPUT_RUNNER_DATA),
+            Ok(None)
+        );
+    }
+
+    fn builtin_ptrs_vm(output_end: Relocatable) -> VirtualMachine {
+        let mut vm = vm!();
+        vm.run_context.fp = 20;
+        // return_builtin_ptrs at (1, 0), pre_execution_builtin_ptrs at (1, 10) and
+        // used_builtins_addr at (1, 19)
+        vm.segments = segments![
+            ((1, 10), (2, 0)),
+            ((1, 11), (3, 0)),
+            ((1, 12), (3, 1)),
+            ((1, 13), (3, 2)),
+            ((1, 14), (3, 3)),
+            ((1, 15), (3, 4)),
+            ((1, 16), (3, 5)),
+            ((1, 17), (3, 6)),
+            ((1, 19), (4, 0))
+        ];
+        add_segments!(vm, 3);
+        vm.insert_value(relocatable!(4, 0), output_end).unwrap();
+        vm.insert_value(relocatable!(4, 1), relocatable!(3, 9))
+            .unwrap();
+        vm
+    }
+
+    fn builtin_ptrs_ids_data() -> HashMap<String, HintReference> {
+        non_continuous_ids_data![
+            ("return_builtin_ptrs", -20),
+            ("pre_execution_builtin_ptrs", -10),
+            ("used_builtins_addr", -1)
+        ]
+    }
+
+    #[test]
+    fn run_write_return_builtins() {
+        let mut vm = builtin_ptrs_vm(relocatable!(2, 3));
+        let mut exec_scopes = scopes_with_task(Task::Program(program_b()));
+        exec_scopes.enter_scope(HashMap::new());
+        assert_matches!(
+            run_hint!(
+                vm,
+                builtin_ptrs_ids_data(),
+                hint_code::EXECUTE_TASK_WRITE_RETURN_BUILTINS,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![
+            vm.segments.memory,
+            ((1, 0), (2, 3)),
+            ((1, 1), (3, 0)),
+            ((1, 2), (3, 9)),
+            ((1, 7), (3, 6))
+        ];
+        assert_eq!(exec_scopes.data.len(), 1);
+    }
+
+    #[test]
+    fn run_write_return_builtins_inconsistent_pie() {
+        let mut vm = builtin_ptrs_vm(relocatable!(2, 7));
+        let pie = run_program_b(false, false).get_cairo_pie().unwrap();
+        let mut exec_scopes = scopes_with_task(Task::Pie(Box::new(pie)));
+        exec_scopes.enter_scope(HashMap::new());
+        assert_matches!(
+            run_hint!(
+                vm,
+                builtin_ptrs_ids_data(),
+                hint_code::EXECUTE_TASK_WRITE_RETURN_BUILTINS,
+                &mut exec_scopes
+            ),
+            Err(HintError::InconsistentBuiltinUsage(BuiltinName::output))
+        );
+    }
+
+    #[test]
+    fn run_append_fact_topology() {
+        let mut vm = vm!();
+        vm.builtin_runners = vec![OutputBuiltinRunner::new(true).into()];
+        vm.run_context.fp = 20;
+        vm.segments = segments![((1, 0), (2, 8)), ((1, 10), (2, 3))];
+        let output_builtin = vm.get_output_builtin_mut().unwrap();
+        output_builtin.new_state(2, true);
+        let bootloader_state = output_builtin.get_state();
+        output_builtin.add_page(1, relocatable!(2, 6), 2).unwrap();
+        output_builtin.add_attribute(
+            crate::program_fact::GPS_FACT_TOPOLOGY.to_string(),
+            vec![2, 1, 0, 2],
+        );
+        let mut exec_scopes = scopes_with_task(Task::Program(program_b()));
+        exec_scopes.insert_value(vars::OUTPUT_RUNNER_DATA, Some(bootloader_state.clone()));
+        exec_scopes.insert_value(vars::FACT_TOPOLOGIES, Vec::<FactTopology>::new());
+        assert_matches!(
+            run_hint!(
+                vm,
+                builtin_ptrs_ids_data(),
+                hint_code::EXECUTE_TASK_APPEND_FACT_TOPOLOGIES,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            exec_scopes
+                .get_list_ref::<FactTopology>(vars::FACT_TOPOLOGIES)
+                .unwrap(),
+            &vec![FactTopology {
+                tree_structure: vec![2, 1, 0, 2],
+                page_sizes: vec![3, 2],
+            }]
+        );
+        assert_eq!(
+            vm.get_output_builtin_mut().unwrap().get_state(),
+            bootloader_state
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{
+    program_fact::FactTopology,
+    types::relocatable::Relocatable,
+    vm::{
+        errors::{hint_errors::HintError, vm_errors::VirtualMachineError},
+        runners::builtin_runner::OutputBuiltinRunner,
+    },
+};
+
+/// Adds the pages of every task to the output builtin, starting from page 1 as page 0 holds the
+/// output of the bootloader and the output size and program hash of each task.
+/// (cairo-lang reference: `configure_fact_topologies` in `simple_bootloader/utils.py`)
+pub fn configure_fact_topologies(
+    fact_topologies: &[FactTopology],
+    mut output_start: Relocatable,
+    output_builtin: &mut OutputBuiltinRunner,
+) -> Result<(), HintError> {
+    let mut page_id = 1;
+    for fact_topology in fact_topologies {
+        // Skip the output size and program hash written by the bootloader
+        output_start = (output_start + 2)?;
+        for page_size in fact_topology.page_sizes.iter() {
+            output_builtin
+                .add_page(page_id, output_start, *page_size)
+                .map_err(VirtualMachineError::from)?;
+            page_id += 1;
+            output_start = (output_start + *page_size)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the fact topologies of the tasks to `path` as JSON
+pub fn write_to_fact_topologies_file(
+    path: &Path,
+    fact_topologies: &[FactTopology],
+) -> Result<(), HintError> {
+    #[derive(Serialize)]
+    struct FactTopologiesFile<'a> {
+        fact_topologies: &'a [FactTopology],
+    }
+
+    let json = serde_json::to_string_pretty(&FactTopologiesFile { fact_topologies })
+        .map_err(|err| HintError::CustomHint(err.to_string().into_boxed_str()))?;
+    std::fs::write(path, json).map_err(|err| {
+        HintError::CustomHint(
+            format!(
+                "Failed to write the fact topologies to {}: {err}",
+                path.display()
+            )
+            .into_boxed_str(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        relocatable, stdlib::collections::HashMap, vm::runners::cairo_pie::PublicMemoryPage,
+    };
+
+    #[test]
+    fn configure_fact_topologies_adds_pages() {
+        let mut output_builtin = OutputBuiltinRunner::new(true);
+        output_builtin.new_state(2, true);
+        let fact_topologies = [
+            FactTopology {
+                tree_structure: vec![1, 0],
+                page_sizes: vec![3],
+            },
+            FactTopology {
+                tree_structure: vec![2, 1, 0, 2],
+                page_sizes: vec![1, 2],
+            },
+        ];
+        configure_fact_topologies(&fact_topologies, relocatable!(2, 1), &mut output_builtin)
+            .unwrap();
+        assert_eq!(
+            output_builtin.get_state().pages,
+            HashMap::from([
+                (1, PublicMemoryPage { start: 3, size: 3 }),
+                (2, PublicMemoryPage { start: 8, size: 1 }),
+                (3, PublicMemoryPage { start: 9, size: 2 }),
+            ])
+        );
+    }
+
+    #[test]
+    fn write_fact_topologies_file() {
+        let path = std::env::temp_dir().join("write_fact_topologies_file.json");
+        let fact_topologies = [FactTopology {
+            tree_structure: vec![1, 0],
+            page_sizes: vec![3],
+        }];
+        write_to_fact_topologies_file(&path, &fact_topologies).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"fact_topologies": [{"tree_structure": [1, 0], "page_sizes": [3]}]})
+        );
+    }
+}
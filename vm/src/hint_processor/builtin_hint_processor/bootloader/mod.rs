@@ -0,0 +1,30 @@
+//! Native implementation of the hints of the simple bootloader
+//!
+//! The simple bootloader runs a list of tasks, either programs or Cairo PIEs, writing the output
+//! size and program hash of each task to its output followed by the task output, and reporting the
+//! fact topology of every task through the pages of the output builtin.
+//! The bootloader program itself is not part of this crate, it has to be compiled from
+//! [cairo-lang](https://github.com/starkware-libs/cairo-lang/tree/v0.12.3/src/starkware/cairo/bootloaders/simple_bootloader),
+//! as done for the tests with `cairo_programs/bootloaders/simple_bootloader.cairo`.
+//! Its input is passed in the `program_input` scope variable as a [SimpleBootloaderInput](types::SimpleBootloaderInput).
+//!
+//! Programs with hints can only be run as tasks with the `extensive_hints` feature, as their hints
+//! are loaded along with the program during the run.
+
+pub mod execute_task_hints;
+pub mod fact_topologies;
+pub mod program_loader;
+pub mod simple_bootloader_hints;
+pub mod types;
+
+/// Names of the variables stored in the execution scopes by the bootloader hints
+pub(crate) mod vars {
+    pub const PROGRAM_INPUT: &str = "program_input";
+    pub const SIMPLE_BOOTLOADER_INPUT: &str = "simple_bootloader_input";
+    pub const FACT_TOPOLOGIES: &str = "fact_topologies";
+    pub const TASK: &str = "task";
+    pub const PROGRAM_DATA_BASE: &str = "program_data_base";
+    pub const PROGRAM_ADDRESS: &str = "program_address";
+    pub const OUTPUT_RUNNER_DATA: &str = "output_runner_data";
+    pub const N_SELECTED_BUILTINS: &str = "n_selected_builtins";
+}
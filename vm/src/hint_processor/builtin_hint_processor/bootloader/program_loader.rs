@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use crate::{
+    types::{
+        builtin_name::BuiltinName,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{
+        errors::hint_errors::HintError,
+        runners::cairo_pie::{BuiltinAdditionalData, CairoPie, StrippedProgram},
+        vm_core::VirtualMachine,
+    },
+    Felt252,
+};
+
+/// Offset of the builtin list in the `ProgramHeader` struct of the bootloader
+pub const BUILTINS_OFFSET: usize = 4;
+
+/// Writes the header of `program` at `header_address`, followed by its bytecode.
+/// Returns the address of the bytecode and the size of the program data.
+/// (cairo-lang reference: `load_program` in `simple_bootloader/utils.py`)
+pub fn load_program(
+    vm: &mut VirtualMachine,
+    program: &StrippedProgram,
+    header_address: Relocatable,
+) -> Result<(Relocatable, usize), HintError> {
+    let n_builtins = program.builtins.len();
+    // The header ends with the list of builtins used by the program
+    let header_size = BUILTINS_OFFSET + n_builtins;
+
+    // data_length does not include the data_length field itself
+    vm.insert_value(
+        header_address,
+        Felt252::from(header_size - 1 + program.data.len()),
+    )?;
+    vm.insert_value((header_address + 2)?, Felt252::from(program.main))?;
+    vm.insert_value((header_address + 3)?, Felt252::from(n_builtins))?;
+    for (index, builtin) in program.builtins.iter().enumerate() {
+        vm.insert_value(
+            (header_address + (BUILTINS_OFFSET + index))?,
+            Felt252::from_bytes_be_slice(builtin.to_str().as_bytes()),
+        )?;
+    }
+
+    let program_address = (header_address + header_size)?;
+    vm.load_data(program_address, &program.data)?;
+    Ok((program_address, header_size + program.data.len()))
+}
+
+/// Loads the memory of `pie` so that it can be run as a task: its program segment is placed at
+/// `program_address`, its execution segment at `execution_segment_address` and its builtin
+/// segments at the builtin pointers found at the start of the execution segment.
+/// The return fp and pc of the PIE are replaced by `ret_fp` and `ret_pc`, and its extra
+/// segments by new segments.
+/// (cairo-lang reference: `load_cairo_pie` in `simple_bootloader/utils.py`)
+pub fn load_cairo_pie(
+    vm: &mut VirtualMachine,
+    pie: &CairoPie,
+    program_address: Relocatable,
+    execution_segment_address: Relocatable,
+    ret_fp: Relocatable,
+    ret_pc: Relocatable,
+) -> Result<(), HintError> {
+    let metadata = &pie.metadata;
+    let mut segment_offsets = HashMap::from([
+        (metadata.program_segment.index, program_address),
+        (metadata.execution_segment.index, execution_segment_address),
+        (metadata.ret_fp_segment.index, ret_fp),
+        (metadata.ret_pc_segment.index, ret_pc),
+    ]);
+    for (index, builtin) in metadata.program.builtins.iter().enumerate() {
+        let segment = metadata
+            .builtin_segments
+            .get(builtin)
+            .ok_or(HintError::InconsistentBuiltinUsage(*builtin))?;
+        segment_offsets.insert(
+            segment.index,
+            vm.get_relocatable((execution_segment_address + index)?)?,
+        );
+    }
+    for segment in metadata.extra_segments.iter() {
+        segment_offsets.insert(segment.index, vm.add_memory_segment());
+    }
+
+    let relocate_address = |address: Relocatable| -> Result<Relocatable, HintError> {
+        let base = segment_offsets
+            .get(&address.segment_index)
+            .ok_or(HintError::UnknownPieSegment(address.segment_index))?;
+        Ok((*base + address.offset)?)
+    };
+
+    // The signatures have to be added before the public keys and messages are written to memory
+    if let Some(BuiltinAdditionalData::Signature(signatures)) =
+        pie.additional_data.0.get(&BuiltinName::ecdsa)
+    {
+        let signature_builtin = vm.get_signature_builtin()?;
+        for (address, signature) in signatures {
+            signature_builtin.add_signature(relocate_address(*address)?, signature)?;
+        }
+    }
+
+    for ((segment_index, offset), value) in pie.memory.0.iter() {
+        let address = relocate_address(Relocatable::from((*segment_index as isize, *offset)))?;
+        let value = match value {
+            MaybeRelocatable::RelocatableValue(value) => relocate_address(*value)?.into(),
+            MaybeRelocatable::Int(_) => value.clone(),
+        };
+        vm.insert_value(address, value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{relocatable, utils::test_utils::*};
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn load_program_writes_header_and_data() {
+        let mut vm = vm!();
+        add_segments!(vm, 2);
+        let program = StrippedProgram {
+            data: vec![Felt252::from(10).into(), Felt252::from(20).into()],
+            builtins: vec![BuiltinName::output, BuiltinName::pedersen],
+            main: 1,
+            prime: (),
+        };
+
+        let (program_address, size) = load_program(&mut vm, &program, relocatable!(1, 0)).unwrap();
+        assert_eq!(program_address, relocatable!(1, 6));
+        assert_eq!(size, 8);
+        check_memory![
+            vm.segments.memory,
+            ((1, 0), 7),
+            ((1, 2), 1),
+            ((1, 3), 2),
+            ((1, 4), 0x6f7574707574),
+            ((1, 5), 0x706564657273656e),
+            ((1, 6), 10),
+            ((1, 7), 20)
+        ];
+        // The bootloader version is left to the bootloader
+        assert_eq!(vm.get_maybe(&relocatable!(1, 1)), None);
+    }
+
+    #[test]
+    fn load_cairo_pie_relocates_segments() {
+        let pie = run_program_b(false, false).get_cairo_pie().unwrap();
+        let mut vm = vm!();
+        add_segments!(vm, 5);
+        // The builtin pointers passed to the task
+        vm.insert_value(relocatable!(2, 0), relocatable!(3, 5))
+            .unwrap();
+        vm.insert_value(relocatable!(2, 1), relocatable!(4, 0))
+            .unwrap();
+
+        load_cairo_pie(
+            &mut vm,
+            &pie,
+            relocatable!(1, 0),
+            relocatable!(2, 0),
+            relocatable!(2, 10),
+            relocatable!(0, 7),
+        )
+        .unwrap();
+
+        for (index, value) in pie.metadata.program.data.iter().enumerate() {
+            assert_eq!(vm.get_maybe(&relocatable!(1, index)).as_ref(), Some(value));
+        }
+        // The return fp and pc at the start of the execution segment point to the bootloader
+        let n_builtins = pie.metadata.program.builtins.len();
+        let (ret_fp_index, ret_pc_index) = (n_builtins, n_builtins + 1);
+        assert_eq!(
+            vm.get_relocatable(relocatable!(2, ret_fp_index)).unwrap(),
+            relocatable!(2, 10)
+        );
+        assert_eq!(
+            vm.get_relocatable(relocatable!(2, ret_pc_index)).unwrap(),
+            relocatable!(0, 7)
+        );
+        // The output of the PIE is written where the task output starts
+        let output_segment = pie.metadata.builtin_segments[&BuiltinName::output].index as usize;
+        let (_, output) = pie
+            .memory
+            .0
+            .iter()
+            .find(|(address, _)| *address == (output_segment, 0))
+            .unwrap();
+        assert_eq!(vm.get_maybe(&relocatable!(3, 5)).as_ref(), Some(output));
+    }
+
+    #[test]
+    fn load_cairo_pie_unknown_segment() {
+        let mut pie = run_program_b(false, false).get_cairo_pie().unwrap();
+        pie.memory.0.push(((42, 0), Felt252::ONE.into()));
+        let mut vm = vm!();
+        add_segments!(vm, 5);
+        vm.insert_value(relocatable!(2, 0), relocatable!(3, 0))
+            .unwrap();
+        vm.insert_value(relocatable!(2, 1), relocatable!(4, 0))
+            .unwrap();
+        assert_matches!(
+            load_cairo_pie(
+                &mut vm,
+                &pie,
+                relocatable!(1, 0),
+                relocatable!(2, 0),
+                relocatable!(2, 10),
+                relocatable!(0, 7),
+            ),
+            Err(HintError::UnknownPieSegment(42))
+        );
+    }
+}
@@ -0,0 +1,405 @@
+use crate::stdlib::{collections::HashMap, prelude::*};
+
+use num_traits::ToPrimitive;
+
+use crate::{
+    hint_processor::{
+        builtin_hint_processor::hint_utils::{
+            get_integer_from_var_name, get_ptr_from_var_name, insert_value_from_var_name,
+        },
+        hint_processor_definition::HintReference,
+    },
+    program_fact::FactTopology,
+    serde::deserialize_program::ApTracking,
+    types::{exec_scope::ExecutionScopes, shared::AnyBox},
+    vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
+    Felt252,
+};
+
+use super::{
+    fact_topologies::{configure_fact_topologies, write_to_fact_topologies_file},
+    types::{SimpleBootloaderInput, Task, ALL_BUILTINS},
+    vars,
+};
+
+/// Implements hint:
+/// %{
+///     from starkware.cairo.bootloaders.simple_bootloader.objects import SimpleBootloaderInput
+///     simple_bootloader_input = SimpleBootloaderInput.Schema().load(program_input)
+/// %}
+pub fn load_simple_bootloader_input(exec_scopes: &mut ExecutionScopes) -> Result<(), HintError> {
+    let input: SimpleBootloaderInput = exec_scopes.get(vars::PROGRAM_INPUT)?;
+    exec_scopes.insert_value(vars::SIMPLE_BOOTLOADER_INPUT, input);
+    Ok(())
+}
+
+/// Implements hint:
+/// %{
+///     n_tasks = len(simple_bootloader_input.tasks)
+///     memory[ids.output_ptr] = n_tasks
+///
+///     # Task range checks are located right after simple bootloader validation range checks, and
+///     # this is validated later in this function.
+///     ids.task_range_check_ptr = ids.range_check_ptr + ids.BuiltinData.SIZE * n_tasks
+///
+///     # A list of fact_toplogies that instruct how to generate the fact from the program output
+///     # for each task.
+///     fact_topologies = []
+/// %}
+pub fn prepare_task_range_checks(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let n_tasks = exec_scopes
+        .get_ref::<SimpleBootloaderInput>(vars::SIMPLE_BOOTLOADER_INPUT)?
+        .tasks
+        .len();
+    let output_ptr = get_ptr_from_var_name("output_ptr", vm, ids_data, ap_tracking)?;
+    vm.insert_value(output_ptr, Felt252::from(n_tasks))?;
+
+    // BuiltinData holds a pointer for each of the builtins
+    let range_check_ptr = get_ptr_from_var_name("range_check_ptr", vm, ids_data, ap_tracking)?;
+    insert_value_from_var_name(
+        "task_range_check_ptr",
+        (range_check_ptr + ALL_BUILTINS.len() * n_tasks)?,
+        vm,
+        ids_data,
+        ap_tracking,
+    )?;
+
+    exec_scopes.insert_value(vars::FACT_TOPOLOGIES, Vec::<FactTopology>::new());
+    Ok(())
+}
+
+/// Implements hint:
+/// %{
+///     from starkware.cairo.bootloaders.simple_bootloader.objects import Task
+///
+///     # Pass current task to execute_task.
+///     task_id = len(simple_bootloader_input.tasks) - ids.n_tasks
+///     task = simple_bootloader_input.tasks[task_id].load_task()
+/// %}
+pub fn set_current_task(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let input = exec_scopes.get_ref::<SimpleBootloaderInput>(vars::SIMPLE_BOOTLOADER_INPUT)?;
+    let n_tasks = get_integer_from_var_name("n_tasks", vm, ids_data, ap_tracking)?;
+    let task_id = n_tasks
+        .to_usize()
+        .and_then(|n_tasks| input.tasks.len().checked_sub(n_tasks))
+        .ok_or_else(|| HintError::InvalidValue(Box::new(("n_tasks", n_tasks, Felt252::ZERO))))?;
+    let task: Task = input.tasks[task_id].clone();
+    exec_scopes.insert_value(vars::TASK, task);
+    Ok(())
+}
+
+/// Implements hint:
+/// %{
+///     # Dump fact topologies to a json file.
+///     from starkware.cairo.bootloaders.simple_bootloader.utils import (
+///         configure_fact_topologies,
+///         write_to_fact_topologies_file,
+///     )
+///
+///     # The task-related output is prefixed by a single word that contains the number of tasks.
+///     output_start = ids.output_ptr + 1
+///
+///     if not simple_bootloader_input.single_page:
+///         # Configure the memory pages in the output builtin, based on fact_topologies.
+///         configure_fact_topologies(
+///             fact_topologies=fact_topologies, output_start=output_start,
+///             output_builtin=output_builtin,
+///         )
+///
+///     if simple_bootloader_input.fact_topologies_path is not None:
+///         write_to_fact_topologies_file(
+///             fact_topologies_path=simple_bootloader_input.fact_topologies_path,
+///             fact_topologies=fact_topologies,
+///         )
+/// %}
+pub fn dump_fact_topologies(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let input = exec_scopes.get_ref::<SimpleBootloaderInput>(vars::SIMPLE_BOOTLOADER_INPUT)?;
+    let fact_topologies = exec_scopes.get_ref::<Vec<FactTopology>>(vars::FACT_TOPOLOGIES)?;
+    let output_start = (get_ptr_from_var_name("output_ptr", vm, ids_data, ap_tracking)? + 1)?;
+
+    if !input.single_page {
+        configure_fact_topologies(fact_topologies, output_start, vm.get_output_builtin_mut()?)?;
+    }
+    if let Some(path) = &input.fact_topologies_path {
+        write_to_fact_topologies_file(path, fact_topologies)?;
+    }
+    Ok(())
+}
+
+/// Implements hint:
+/// %{ vm_enter_scope({'n_selected_builtins': ids.n_selected_builtins}) %}
+pub fn select_builtins_enter_scope(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let n_selected_builtins: AnyBox = Box::new(get_integer_from_var_name(
+        "n_selected_builtins",
+        vm,
+        ids_data,
+        ap_tracking,
+    )?);
+    exec_scopes.enter_scope(HashMap::from([(
+        String::from(vars::N_SELECTED_BUILTINS),
+        n_selected_builtins,
+    )]));
+    Ok(())
+}
+
+/// Implements hint:
+/// %{
+///     # A builtin should be selected iff its encoding appears in the selected encodings list
+///     # and the list wasn't exhausted.
+///     # Note that testing inclusion by a single comparison is possible since the lists are sorted.
+///     ids.select_builtin = int(
+///       n_selected_builtins > 0 and memory[ids.selected_encodings] == memory[ids.all_encodings])
+///     if ids.select_builtin:
+///       n_selected_builtins = n_selected_builtins - 1
+/// %}
+pub fn select_builtin(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let n_selected_builtins: Felt252 = exec_scopes.get(vars::N_SELECTED_BUILTINS)?;
+    let select_builtin = n_selected_builtins != Felt252::ZERO && {
+        let selected_encodings =
+            get_ptr_from_var_name("selected_encodings", vm, ids_data, ap_tracking)?;
+        let all_encodings = get_ptr_from_var_name("all_encodings", vm, ids_data, ap_tracking)?;
+        vm.get_integer(selected_encodings)? == vm.get_integer(all_encodings)?
+    };
+    insert_value_from_var_name(
+        "select_builtin",
+        Felt252::from(select_builtin as u8),
+        vm,
+        ids_data,
+        ap_tracking,
+    )?;
+    if select_builtin {
+        exec_scopes.insert_value(
+            vars::N_SELECTED_BUILTINS,
+            n_selected_builtins - Felt252::ONE,
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        any_box,
+        hint_processor::{
+            builtin_hint_processor::{
+                builtin_hint_processor_definition::{BuiltinHintProcessor, HintProcessorData},
+                hint_code,
+            },
+            hint_processor_definition::HintProcessorLogic,
+        },
+        utils::test_utils::*,
+        vm::runners::builtin_runner::OutputBuiltinRunner,
+    };
+    use assert_matches::assert_matches;
+
+    fn bootloader_input(n_tasks: usize) -> SimpleBootloaderInput {
+        SimpleBootloaderInput {
+            tasks: vec![Task::Program(program_b()); n_tasks],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn run_load_simple_bootloader_input() {
+        let mut vm = vm!();
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::PROGRAM_INPUT, bootloader_input(2));
+        assert_matches!(
+            run_hint!(
+                vm,
+                HashMap::new(),
+                hint_code::SIMPLE_BOOTLOADER_LOAD_INPUT,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        let input = exec_scopes
+            .get_ref::<SimpleBootloaderInput>(vars::SIMPLE_BOOTLOADER_INPUT)
+            .unwrap();
+        assert_eq!(input.tasks.len(), 2);
+    }
+
+    #[test]
+    fn run_prepare_task_range_checks() {
+        let mut vm = vm!();
+        vm.run_context.fp = 3;
+        vm.segments = segments![((1, 0), (2, 0)), ((1, 1), (3, 4))];
+        add_segments!(vm, 2);
+        let ids_data = ids_data!["output_ptr", "range_check_ptr", "task_range_check_ptr"];
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::SIMPLE_BOOTLOADER_INPUT, bootloader_input(3));
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data,
+                hint_code::SIMPLE_BOOTLOADER_PREPARE_TASK_RANGE_CHECKS,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![vm.segments.memory, ((2, 0), 3), ((1, 2), (3, 28))];
+        assert_matches!(
+            exec_scopes.get_ref::<Vec<FactTopology>>(vars::FACT_TOPOLOGIES),
+            Ok(topologies) if topologies.is_empty()
+        );
+    }
+
+    #[test]
+    fn run_set_current_task() {
+        let mut vm = vm!();
+        vm.run_context.fp = 1;
+        vm.segments = segments![((1, 0), 2)];
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::SIMPLE_BOOTLOADER_INPUT, bootloader_input(3));
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["n_tasks"],
+                hint_code::SIMPLE_BOOTLOADER_SET_CURRENT_TASK,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        assert_matches!(
+            exec_scopes.get_ref::<Task>(vars::TASK),
+            Ok(Task::Program(_))
+        );
+
+        vm.segments = segments![((1, 0), 4)];
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["n_tasks"],
+                hint_code::SIMPLE_BOOTLOADER_SET_CURRENT_TASK,
+                &mut exec_scopes
+            ),
+            Err(HintError::InvalidValue(_))
+        );
+    }
+
+    #[test]
+    fn run_dump_fact_topologies() {
+        let mut vm = vm!();
+        vm.builtin_runners = vec![OutputBuiltinRunner::new(true).into()];
+        vm.run_context.fp = 1;
+        vm.segments = segments![((1, 0), (2, 0))];
+        vm.get_output_builtin_mut().unwrap().new_state(2, true);
+        let path = std::env::temp_dir().join("run_dump_fact_topologies.json");
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(
+            vars::SIMPLE_BOOTLOADER_INPUT,
+            SimpleBootloaderInput {
+                fact_topologies_path: Some(path.clone()),
+                ..bootloader_input(1)
+            },
+        );
+        exec_scopes.insert_value(
+            vars::FACT_TOPOLOGIES,
+            vec![FactTopology {
+                tree_structure: vec![1, 0],
+                page_sizes: vec![4],
+            }],
+        );
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["output_ptr"],
+                hint_code::SIMPLE_BOOTLOADER_DUMP_FACT_TOPOLOGIES,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        let pages = vm.get_output_builtin_mut().unwrap().get_state().pages;
+        assert_eq!(pages.len(), 1);
+        assert_eq!((pages[&1].start, pages[&1].size), (3, 4));
+        assert!(std::fs::read_to_string(path)
+            .unwrap()
+            .contains("fact_topologies"));
+    }
+
+    #[test]
+    fn run_select_builtins() {
+        let mut vm = vm!();
+        vm.run_context.fp = 4;
+        // selected_encodings, all_encodings, select_builtin, n_selected_builtins
+        vm.segments = segments![
+            ((1, 0), (2, 0)),
+            ((1, 1), (3, 0)),
+            ((1, 3), 1),
+            ((2, 0), 0x6f7574707574),
+            ((3, 0), 0x6f7574707574),
+            ((3, 1), 0x706564657273656e)
+        ];
+        let ids_data = ids_data![
+            "selected_encodings",
+            "all_encodings",
+            "select_builtin",
+            "n_selected_builtins"
+        ];
+        let mut exec_scopes = ExecutionScopes::new();
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data.clone(),
+                hint_code::SELECT_BUILTINS_ENTER_SCOPE,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data.clone(),
+                hint_code::INNER_SELECT_BUILTINS_SELECT_BUILTIN,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![vm.segments.memory, ((1, 2), 1)];
+        assert_matches!(
+            exec_scopes.get::<Felt252>(vars::N_SELECTED_BUILTINS),
+            Ok(n) if n == Felt252::ZERO
+        );
+
+        // Once all the builtins are selected the remaining ones are skipped
+        let mut vm = vm!();
+        vm.run_context.fp = 4;
+        vm.segments = segments![((1, 0), (2, 1)), ((1, 1), (3, 1))];
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data,
+                hint_code::INNER_SELECT_BUILTINS_SELECT_BUILTIN,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![vm.segments.memory, ((1, 2), 0)];
+    }
+}
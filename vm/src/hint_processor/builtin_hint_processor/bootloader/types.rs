@@ -0,0 +1,145 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use thiserror_no_std::Error;
+
+use crate::{
+    types::{builtin_name::BuiltinName, errors::program_errors::ProgramError, program::Program},
+    vm::runners::cairo_pie::{CairoPie, StrippedProgram},
+};
+
+/// Builtins the bootloader passes to every task, in the order of its `BuiltinData` struct
+pub const ALL_BUILTINS: [BuiltinName; 8] = [
+    BuiltinName::output,
+    BuiltinName::pedersen,
+    BuiltinName::range_check,
+    BuiltinName::ecdsa,
+    BuiltinName::bitwise,
+    BuiltinName::ec_op,
+    BuiltinName::keccak,
+    BuiltinName::poseidon,
+];
+
+/// Task run by the simple bootloader
+#[derive(Clone, Debug)]
+pub enum Task {
+    /// Program executed along with its hints
+    Program(Program),
+    /// Previous execution, loaded into memory without running any hint
+    Pie(Box<CairoPie>),
+}
+
+impl Task {
+    /// Returns the program of the task, as loaded by the bootloader to compute its hash
+    pub fn get_program(&self) -> Result<StrippedProgram, ProgramError> {
+        match self {
+            Task::Program(program) => program.get_stripped_program(),
+            Task::Pie(pie) => Ok(pie.metadata.program.clone()),
+        }
+    }
+}
+
+/// Input of the simple bootloader
+#[derive(Clone, Debug, Default)]
+pub struct SimpleBootloaderInput {
+    pub tasks: Vec<Task>,
+    /// File the fact topologies of the tasks are written to at the end of the run
+    pub fact_topologies_path: Option<PathBuf>,
+    /// Leaves the whole output in page 0 instead of adding the pages of each task
+    pub single_page: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum SimpleBootloaderInputError {
+    #[error("Failed to parse the simple bootloader input: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Failed to load the program of task {0}: {1}")]
+    Program(usize, ProgramError),
+    #[error("Failed to read the Cairo PIE of task {0}: {1}")]
+    Pie(usize, std::io::Error),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum TaskJson {
+    RunProgramTask { program: serde_json::Value },
+    CairoPiePath { path: PathBuf },
+}
+
+#[derive(Deserialize)]
+struct SimpleBootloaderInputJson {
+    tasks: Vec<TaskJson>,
+    fact_topologies_path: Option<PathBuf>,
+    #[serde(default)]
+    single_page: bool,
+}
+
+impl SimpleBootloaderInput {
+    /// Parses the input in the format of the Python VM, where tasks are either a
+    /// `RunProgramTask` with a compiled program or a `CairoPiePath` with the path of a zipped PIE
+    pub fn from_json(json: &str) -> Result<Self, SimpleBootloaderInputError> {
+        let input: SimpleBootloaderInputJson = serde_json::from_str(json)?;
+        let tasks = input
+            .tasks
+            .into_iter()
+            .enumerate()
+            .map(|(index, task)| match task {
+                TaskJson::RunProgramTask { program } => {
+                    Program::from_bytes(&serde_json::to_vec(&program)?, Some("main"))
+                        .map(Task::Program)
+                        .map_err(|err| SimpleBootloaderInputError::Program(index, err))
+                }
+                TaskJson::CairoPiePath { path } => CairoPie::read_zip_file(&path)
+                    .map(|pie| Task::Pie(Box::new(pie)))
+                    .map_err(|err| SimpleBootloaderInputError::Pie(index, err)),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(SimpleBootloaderInput {
+            tasks,
+            fact_topologies_path: input.fact_topologies_path,
+            single_page: input.single_page,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn simple_bootloader_input_from_json() {
+        let program: serde_json::Value = serde_json::from_slice(include_bytes!(
+            "../../../../../cairo_programs/manually_compiled/valid_program_b.json"
+        ))
+        .unwrap();
+        let json = serde_json::json!({
+            "tasks": [{"type": "RunProgramTask", "program": program}],
+            "fact_topologies_path": "fact_topologies.json",
+        });
+        let input = SimpleBootloaderInput::from_json(&json.to_string()).unwrap();
+        assert_eq!(input.tasks.len(), 1);
+        assert_matches!(
+            &input.tasks[0],
+            Task::Program(p) if p.builtins == vec![BuiltinName::output, BuiltinName::range_check]
+        );
+        assert_eq!(
+            input.fact_topologies_path,
+            Some(PathBuf::from("fact_topologies.json"))
+        );
+        assert!(!input.single_page);
+    }
+
+    #[test]
+    fn simple_bootloader_input_from_json_missing_pie() {
+        let json = r#"{"tasks": [{"type": "CairoPiePath", "path": "missing_pie.zip"}]}"#;
+        assert_matches!(
+            SimpleBootloaderInput::from_json(json),
+            Err(SimpleBootloaderInputError::Pie(0, _))
+        );
+        assert_matches!(
+            SimpleBootloaderInput::from_json(r#"{"tasks": [{"type": "Unknown"}]}"#),
+            Err(SimpleBootloaderInputError::Json(_))
+        );
+    }
+}
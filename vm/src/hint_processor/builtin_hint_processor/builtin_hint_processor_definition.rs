@@ -23,12 +23,13 @@ use super::{
     },
 };
 use crate::Felt252;
+use crate::{any_box, vm::errors::vm_errors::VirtualMachineError};
 use crate::{
     hint_processor::{
         builtin_hint_processor::secp::ec_utils::{
             ec_double_assign_new_x, ec_double_assign_new_x_v2,
         },
-        hint_processor_definition::HintProcessorLogic,
+        hint_processor_definition::{get_resolved_ids_data, HintProcessorLogic},
     },
     vm::runners::cairo_runner::{ResourceTracker, RunResources},
 };
@@ -110,14 +111,27 @@ use crate::{
         hint_processor_definition::HintReference,
     },
     serde::deserialize_program::ApTracking,
-    stdlib::{any::Any, collections::HashMap, prelude::*, rc::Rc},
-    types::exec_scope::ExecutionScopes,
+    stdlib::{any::Any, collections::HashMap, prelude::*},
+    types::{exec_scope::ExecutionScopes, shared::Shared},
     vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
 };
 
 #[cfg(feature = "test_utils")]
 use crate::hint_processor::builtin_hint_processor::skip_next_instruction::skip_next_instruction;
 
+#[cfg(feature = "std")]
+use crate::hint_processor::builtin_hint_processor::bootloader::{
+    execute_task_hints::{
+        allocate_program_data_segment, append_fact_topology, assert_program_address, call_task,
+        load_task_program, validate_hash, write_return_builtins,
+    },
+    simple_bootloader_hints::{
+        dump_fact_topologies, load_simple_bootloader_input, prepare_task_range_checks,
+        select_builtin, select_builtins_enter_scope, set_current_task,
+    },
+};
+use crate::hint_processor::hint_processor_definition::HintExtension;
+
 #[cfg(feature = "test_utils")]
 use crate::hint_processor::builtin_hint_processor::print::{print_array, print_dict, print_felt};
 use crate::hint_processor::builtin_hint_processor::secp::secp_utils::{
@@ -130,6 +144,9 @@ pub struct HintProcessorData {
     pub code: String,
     pub ap_tracking: ApTracking,
     pub ids_data: HashMap<String, HintReference>,
+    /// Implementation of the hint, resolved from its code by [BuiltinHintProcessor::compile_hint].
+    /// Hints without it are looked up by code when they are executed.
+    pub compiled_hint: Option<CompiledHint>,
 }
 
 impl HintProcessorData {
@@ -138,25 +155,79 @@ impl HintProcessorData {
             code,
             ap_tracking: ApTracking::default(),
             ids_data,
+            compiled_hint: None,
+        }
+    }
+}
+
+/// Implementation of a hint run by a [BuiltinHintProcessor]
+#[derive(Clone)]
+pub enum CompiledHint {
+    /// One of the hints of the [BuiltinHintProcessor], see [get_builtin_hint_fn]
+    Builtin(BuiltinHintFn),
+    /// One of the hints of the [BuiltinHintProcessor] which extend the hints of the run, see
+    /// [get_builtin_extensive_hint_fn]
+    BuiltinExtensive(BuiltinExtensiveHintFn),
+    /// A hint added to the [BuiltinHintProcessor] with [BuiltinHintProcessor::add_hint]
+    Extra(Shared<HintFunc>),
+}
+
+// NOTE: implemented manually as the hint functions can't be formatted
+impl core::fmt::Debug for CompiledHint {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CompiledHint::Builtin(_) => f.write_str("Builtin"),
+            CompiledHint::BuiltinExtensive(_) => f.write_str("BuiltinExtensive"),
+            CompiledHint::Extra(_) => f.write_str("Extra"),
         }
     }
 }
 
-#[allow(clippy::type_complexity)]
-pub struct HintFunc(
-    pub  Box<
-        dyn Fn(
-                &mut VirtualMachine,
-                &mut ExecutionScopes,
-                &HashMap<String, HintReference>,
-                &ApTracking,
-                &HashMap<String, Felt252>,
-            ) -> Result<(), HintError>
-            + Sync,
-    >,
-);
+/// Implementation of a hint of the [BuiltinHintProcessor], see [get_builtin_hint_fn]
+pub type BuiltinHintFn = fn(
+    &mut BuiltinHintProcessor,
+    &mut VirtualMachine,
+    &mut ExecutionScopes,
+    &HintProcessorData,
+    &HashMap<String, Felt252>,
+) -> Result<(), HintError>;
+
+/// Implementation of a hint of the [BuiltinHintProcessor] which extends the hints of the run, see
+/// [get_builtin_extensive_hint_fn]
+pub type BuiltinExtensiveHintFn = fn(
+    &mut BuiltinHintProcessor,
+    &mut VirtualMachine,
+    &mut ExecutionScopes,
+    &HintProcessorData,
+    &HashMap<String, Felt252>,
+) -> Result<HintExtension, HintError>;
+
+#[cfg(not(feature = "thread_safe"))]
+type HintFn = dyn Fn(
+        &mut VirtualMachine,
+        &mut ExecutionScopes,
+        &HashMap<String, HintReference>,
+        &ApTracking,
+        &HashMap<String, Felt252>,
+    ) -> Result<(), HintError>
+    + Sync;
+#[cfg(feature = "thread_safe")]
+type HintFn = dyn Fn(
+        &mut VirtualMachine,
+        &mut ExecutionScopes,
+        &HashMap<String, HintReference>,
+        &ApTracking,
+        &HashMap<String, Felt252>,
+    ) -> Result<(), HintError>
+    + Sync
+    + Send;
+
+/// Implementation of a hint added to a [BuiltinHintProcessor].
+/// It has to be `Send` as well as `Sync` with the `thread_safe` feature.
+pub struct HintFunc(pub Box<HintFn>);
+
 pub struct BuiltinHintProcessor {
-    pub extra_hints: HashMap<String, Rc<HintFunc>>,
+    pub extra_hints: HashMap<String, Shared<HintFunc>>,
     run_resources: RunResources,
 }
 impl BuiltinHintProcessor {
@@ -167,16 +238,70 @@ impl BuiltinHintProcessor {
         }
     }
 
-    pub fn new(extra_hints: HashMap<String, Rc<HintFunc>>, run_resources: RunResources) -> Self {
+    pub fn new(
+        extra_hints: HashMap<String, Shared<HintFunc>>,
+        run_resources: RunResources,
+    ) -> Self {
         BuiltinHintProcessor {
             extra_hints,
             run_resources,
         }
     }
 
-    pub fn add_hint(&mut self, hint_code: String, hint_func: Rc<HintFunc>) {
+    pub fn add_hint(&mut self, hint_code: String, hint_func: Shared<HintFunc>) {
         self.extra_hints.insert(hint_code, hint_func);
     }
+
+    // Extra hints take precedence over the builtin hints with the same code
+    fn resolve_hint(&self, code: &str) -> Option<CompiledHint> {
+        match self.extra_hints.get(code) {
+            Some(hint_func) => Some(CompiledHint::Extra(hint_func.clone())),
+            None => get_builtin_hint_fn(code)
+                .map(CompiledHint::Builtin)
+                .or_else(|| {
+                    get_builtin_extensive_hint_fn(code).map(CompiledHint::BuiltinExtensive)
+                }),
+        }
+    }
+
+    // Runs a hint, returning the hints it extends the run with
+    fn run_hint(
+        &mut self,
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        hint_data: &HintProcessorData,
+        constants: &HashMap<String, Felt252>,
+    ) -> Result<HintExtension, HintError> {
+        let resolved_hint;
+        let compiled_hint = match &hint_data.compiled_hint {
+            Some(compiled_hint) => compiled_hint,
+            None => {
+                resolved_hint = self.resolve_hint(&hint_data.code).ok_or_else(|| {
+                    HintError::UnknownHint(hint_data.code.clone().into_boxed_str())
+                })?;
+                &resolved_hint
+            }
+        };
+        match compiled_hint {
+            CompiledHint::Builtin(hint_fn) => {
+                hint_fn(self, vm, exec_scopes, hint_data, constants)?;
+                Ok(HintExtension::default())
+            }
+            CompiledHint::BuiltinExtensive(hint_fn) => {
+                hint_fn(self, vm, exec_scopes, hint_data, constants)
+            }
+            CompiledHint::Extra(hint_func) => {
+                hint_func.0(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    constants,
+                )?;
+                Ok(HintExtension::default())
+            }
+        }
+    }
 }
 
 impl HintProcessorLogic for BuiltinHintProcessor {
@@ -190,187 +315,330 @@ impl HintProcessorLogic for BuiltinHintProcessor {
         let hint_data = hint_data
             .downcast_ref::<HintProcessorData>()
             .ok_or(HintError::WrongHintData)?;
+        // The hints of a program task can only be loaded by execute_hint_extensive
+        let hint_extension = self.run_hint(vm, exec_scopes, hint_data, constants)?;
+        if hint_extension.is_empty() {
+            Ok(())
+        } else {
+            Err(HintError::TaskHintsNotSupported)
+        }
+    }
 
-        if let Some(hint_func) = self.extra_hints.get(&hint_data.code) {
-            return hint_func.0(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                constants,
-            );
+    /// Resolves the code of the hint to one of the extra hints of the processor or to a builtin
+    /// hint, and its ids to their references with the ap tracking of the hint applied.
+    /// Extra hints added after a hint is compiled don't replace the hint it was resolved to.
+    fn compile_hint(
+        &self,
+        hint_code: &str,
+        ap_tracking_data: &ApTracking,
+        reference_ids: &HashMap<String, usize>,
+        references: &[HintReference],
+    ) -> Result<Box<dyn Any>, VirtualMachineError> {
+        Ok(any_box!(HintProcessorData {
+            code: hint_code.to_string(),
+            ap_tracking: ap_tracking_data.clone(),
+            ids_data: get_resolved_ids_data(reference_ids, references, ap_tracking_data)?,
+            compiled_hint: self.resolve_hint(hint_code),
+        }))
+    }
+
+    #[cfg(feature = "extensive_hints")]
+    fn execute_hint_extensive(
+        &mut self,
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        hint_data: &Box<dyn Any>,
+        constants: &HashMap<String, Felt252>,
+    ) -> Result<HintExtension, HintError> {
+        let hint_data = hint_data
+            .downcast_ref::<HintProcessorData>()
+            .ok_or(HintError::WrongHintData)?;
+        self.run_hint(vm, exec_scopes, hint_data, constants)
+    }
+}
+
+/// Resolves the code of a hint to its implementation among the hints of the [BuiltinHintProcessor]
+/// which extend the hints of the run
+#[allow(unused_variables)]
+pub fn get_builtin_extensive_hint_fn(code: &str) -> Option<BuiltinExtensiveHintFn> {
+    // Calling a program task loads its hints
+    #[cfg(feature = "std")]
+    if code == hint_code::EXECUTE_TASK_CALL_TASK {
+        let hint_fn: BuiltinExtensiveHintFn =
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                call_task(hint_processor, vm, exec_scopes)
+            };
+        return Some(hint_fn);
+    }
+    None
+}
+
+/// Resolves the code of a hint to its implementation among the hints of the [BuiltinHintProcessor]
+// Non-capturing closures coerce to function pointers, their unused arguments are allowed here
+#[allow(unused_variables)]
+pub fn get_builtin_hint_fn(code: &str) -> Option<BuiltinHintFn> {
+    let hint_fn: BuiltinHintFn = match code {
+        hint_code::ADD_SEGMENT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| add_segment(vm)
         }
-        match &*hint_data.code {
-            hint_code::ADD_SEGMENT => add_segment(vm),
-            hint_code::IS_NN => is_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::IS_NN_OUT_OF_RANGE => {
-                is_nn_out_of_range(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::ASSERT_LE_FELT => assert_le_felt(
+        hint_code::IS_NN => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            is_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::IS_NN_OUT_OF_RANGE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            is_nn_out_of_range(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_LE_FELT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            assert_le_felt(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
                 &hint_data.ap_tracking,
                 constants,
-            ),
-            hint_code::ASSERT_LE_FELT_EXCLUDED_2 => assert_le_felt_excluded_2(exec_scopes),
-            hint_code::ASSERT_LE_FELT_EXCLUDED_1 => assert_le_felt_excluded_1(vm, exec_scopes),
-            hint_code::ASSERT_LE_FELT_EXCLUDED_0 => assert_le_felt_excluded_0(vm, exec_scopes),
-            hint_code::IS_LE_FELT => is_le_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::ASSERT_250_BITS => {
-                assert_250_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::IS_250_BITS => is_250_bits(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::IS_ADDR_BOUNDED => {
-                is_addr_bounded(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::IS_POSITIVE => is_positive(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::SPLIT_INT_ASSERT_RANGE => {
-                split_int_assert_range(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::SPLIT_INT => split_int(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::ASSERT_NOT_EQUAL => {
-                assert_not_equal(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::ASSERT_NN => assert_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::SQRT => sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::ASSERT_NOT_ZERO => {
-                assert_not_zero(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::IS_QUAD_RESIDUE => {
-                is_quad_residue(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::VM_EXIT_SCOPE => exit_scope(exec_scopes),
-            hint_code::MEMCPY_ENTER_SCOPE => {
-                memcpy_enter_scope(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::MEMSET_ENTER_SCOPE => {
-                memset_enter_scope(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+            )
+        },
+        hint_code::ASSERT_LE_FELT_EXCLUDED_2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                assert_le_felt_excluded_2(exec_scopes)
             }
-            hint_code::MEMCPY_CONTINUE_COPYING => memset_step_loop(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "continue_copying",
-            ),
-            hint_code::MEMSET_CONTINUE_LOOP => memset_step_loop(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "continue_loop",
-            ),
-            hint_code::SPLIT_FELT => {
-                split_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        }
+        hint_code::ASSERT_LE_FELT_EXCLUDED_1 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                assert_le_felt_excluded_1(vm, exec_scopes)
             }
-            hint_code::UNSIGNED_DIV_REM => {
-                unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::ASSERT_LE_FELT_EXCLUDED_0 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                assert_le_felt_excluded_0(vm, exec_scopes)
             }
-            hint_code::SIGNED_DIV_REM => {
-                signed_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::IS_LE_FELT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            is_le_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_250_BITS => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            assert_250_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::IS_250_BITS => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            is_250_bits(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::IS_ADDR_BOUNDED => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            is_addr_bounded(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::IS_POSITIVE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            is_positive(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SPLIT_INT_ASSERT_RANGE => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                split_int_assert_range(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::ASSERT_LT_FELT => {
-                assert_lt_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::SPLIT_INT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_int(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_NOT_EQUAL => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            assert_not_equal(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_NN => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            assert_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SQRT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_NOT_ZERO => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            assert_not_zero(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::IS_QUAD_RESIDUE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            is_quad_residue(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::VM_EXIT_SCOPE => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| exit_scope(exec_scopes)
+        }
+        hint_code::MEMCPY_ENTER_SCOPE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            memcpy_enter_scope(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::MEMSET_ENTER_SCOPE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            memset_enter_scope(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::MEMCPY_CONTINUE_COPYING => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                memset_step_loop(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    "continue_copying",
+                )
             }
-            hint_code::FIND_ELEMENT => {
-                find_element(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::MEMSET_CONTINUE_LOOP => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                memset_step_loop(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    "continue_loop",
+                )
             }
-            hint_code::SEARCH_SORTED_LOWER => {
+        }
+        hint_code::SPLIT_FELT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::UNSIGNED_DIV_REM => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SIGNED_DIV_REM => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            signed_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_LT_FELT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            assert_lt_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::FIND_ELEMENT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            find_element(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SEARCH_SORTED_LOWER => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 search_sorted_lower(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::POW => pow(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::SET_ADD => set_add(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::DICT_NEW => dict_new(vm, exec_scopes),
-            hint_code::DICT_READ => {
-                dict_read(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::DICT_WRITE => {
-                dict_write(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::DEFAULT_DICT_NEW => {
-                default_dict_new(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::POW => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            pow(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SET_ADD => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            set_add(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DICT_NEW => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| dict_new(vm, exec_scopes)
+        }
+        hint_code::DICT_READ => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            dict_read(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DICT_WRITE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            dict_write(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DEFAULT_DICT_NEW => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            default_dict_new(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SQUASH_DICT_INNER_FIRST_ITERATION => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                squash_dict_inner_first_iteration(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
             }
-            hint_code::SQUASH_DICT_INNER_FIRST_ITERATION => squash_dict_inner_first_iteration(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::USORT_ENTER_SCOPE => usort_enter_scope(exec_scopes),
-            hint_code::USORT_BODY => {
-                usort_body(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::USORT_ENTER_SCOPE => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| usort_enter_scope(exec_scopes)
+        }
+        hint_code::USORT_BODY => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            usort_body(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::USORT_VERIFY => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            verify_usort(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::USORT_VERIFY_MULTIPLICITY_ASSERT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                verify_multiplicity_assert(exec_scopes)
             }
-            hint_code::USORT_VERIFY => {
-                verify_usort(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::USORT_VERIFY_MULTIPLICITY_BODY => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                verify_multiplicity_body(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
             }
-            hint_code::USORT_VERIFY_MULTIPLICITY_ASSERT => verify_multiplicity_assert(exec_scopes),
-            hint_code::USORT_VERIFY_MULTIPLICITY_BODY => verify_multiplicity_body(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::BLAKE2S_COMPUTE => {
-                compute_blake2s(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::BLAKE2S_COMPUTE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            compute_blake2s(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::VERIFY_ZERO_V1 | hint_code::VERIFY_ZERO_V2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                verify_zero(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    &SECP_P,
+                )
             }
-            hint_code::VERIFY_ZERO_V1 | hint_code::VERIFY_ZERO_V2 => verify_zero(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P,
-            ),
-            hint_code::VERIFY_ZERO_V3 => verify_zero(
+        }
+        hint_code::VERIFY_ZERO_V3 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            verify_zero(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
                 &hint_data.ap_tracking,
                 &SECP_P_V2,
-            ),
-            hint_code::VERIFY_ZERO_EXTERNAL_SECP => verify_zero_with_external_const(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::NONDET_BIGINT3_V1 | hint_code::NONDET_BIGINT3_V2 => {
-                nondet_bigint3(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::REDUCE_V1 => {
-                reduce_v1(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::REDUCE_V2 => {
-                reduce_v2(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+            )
+        },
+        hint_code::VERIFY_ZERO_EXTERNAL_SECP => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                verify_zero_with_external_const(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
             }
-            hint_code::REDUCE_ED25519 => {
-                ed25519_reduce(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::NONDET_BIGINT3_V1 | hint_code::NONDET_BIGINT3_V2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                nondet_bigint3(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::BLAKE2S_FINALIZE | hint_code::BLAKE2S_FINALIZE_V2 => {
+        }
+        hint_code::REDUCE_V1 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            reduce_v1(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::REDUCE_V2 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            reduce_v2(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::REDUCE_ED25519 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            ed25519_reduce(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::BLAKE2S_FINALIZE | hint_code::BLAKE2S_FINALIZE_V2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 finalize_blake2s(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::BLAKE2S_FINALIZE_V3 => {
+        }
+        hint_code::BLAKE2S_FINALIZE_V3 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 finalize_blake2s_v3(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::BLAKE2S_ADD_UINT256 => {
+        }
+        hint_code::BLAKE2S_ADD_UINT256 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 blake2s_add_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::BLAKE2S_ADD_UINT256_BIGEND => {
+        }
+        hint_code::BLAKE2S_ADD_UINT256_BIGEND => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 blake2s_add_uint256_bigend(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::UNSAFE_KECCAK => {
-                unsafe_keccak(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UNSAFE_KECCAK_FINALIZE => {
+        }
+        hint_code::UNSAFE_KECCAK => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            unsafe_keccak(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UNSAFE_KECCAK_FINALIZE => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 unsafe_keccak_finalize(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::SQUASH_DICT_INNER_SKIP_LOOP => squash_dict_inner_skip_loop(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::SQUASH_DICT_INNER_CHECK_ACCESS_INDEX => {
+        }
+        hint_code::SQUASH_DICT_INNER_SKIP_LOOP => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                squash_dict_inner_skip_loop(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
+            }
+        }
+        hint_code::SQUASH_DICT_INNER_CHECK_ACCESS_INDEX => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 squash_dict_inner_check_access_index(
                     vm,
                     exec_scopes,
@@ -378,17 +646,29 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                     &hint_data.ap_tracking,
                 )
             }
-            hint_code::SQUASH_DICT_INNER_CONTINUE_LOOP => squash_dict_inner_continue_loop(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::SQUASH_DICT_INNER_ASSERT_LEN_KEYS => {
+        }
+        hint_code::SQUASH_DICT_INNER_CONTINUE_LOOP => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                squash_dict_inner_continue_loop(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
+            }
+        }
+        hint_code::SQUASH_DICT_INNER_ASSERT_LEN_KEYS => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 squash_dict_inner_assert_len_keys(exec_scopes)
             }
-            hint_code::SQUASH_DICT_INNER_LEN_ASSERT => squash_dict_inner_len_assert(exec_scopes),
-            hint_code::SQUASH_DICT_INNER_USED_ACCESSES_ASSERT => {
+        }
+        hint_code::SQUASH_DICT_INNER_LEN_ASSERT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                squash_dict_inner_len_assert(exec_scopes)
+            }
+        }
+        hint_code::SQUASH_DICT_INNER_USED_ACCESSES_ASSERT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 squash_dict_inner_used_accesses_assert(
                     vm,
                     exec_scopes,
@@ -396,57 +676,83 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                     &hint_data.ap_tracking,
                 )
             }
-            hint_code::SQUASH_DICT_INNER_NEXT_KEY => squash_dict_inner_next_key(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::SQUASH_DICT => {
-                squash_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::VM_ENTER_SCOPE => enter_scope(exec_scopes),
-            hint_code::DICT_UPDATE => {
-                dict_update(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::SQUASH_DICT_INNER_NEXT_KEY => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                squash_dict_inner_next_key(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
             }
-            hint_code::DICT_SQUASH_COPY_DICT => {
+        }
+        hint_code::SQUASH_DICT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            squash_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::VM_ENTER_SCOPE => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| enter_scope(exec_scopes)
+        }
+        hint_code::DICT_UPDATE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            dict_update(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DICT_SQUASH_COPY_DICT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 dict_squash_copy_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::DICT_SQUASH_UPDATE_PTR => {
+        }
+        hint_code::DICT_SQUASH_UPDATE_PTR => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 dict_squash_update_ptr(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::UINT256_ADD => {
-                uint256_add(vm, &hint_data.ids_data, &hint_data.ap_tracking, false)
-            }
-            hint_code::UINT256_ADD_LOW => {
-                uint256_add(vm, &hint_data.ids_data, &hint_data.ap_tracking, true)
-            }
-            hint_code::UINT128_ADD => uint128_add(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::UINT256_SUB => uint256_sub(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::SPLIT_64 => split_64(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::UINT256_SQRT => {
-                uint256_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking, false)
-            }
-            hint_code::UINT256_SQRT_FELT => {
-                uint256_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking, true)
-            }
-            hint_code::UINT256_SIGNED_NN => {
-                uint256_signed_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UINT256_UNSIGNED_DIV_REM => {
+        }
+        hint_code::UINT256_ADD => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint256_add(vm, &hint_data.ids_data, &hint_data.ap_tracking, false)
+        },
+        hint_code::UINT256_ADD_LOW => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint256_add(vm, &hint_data.ids_data, &hint_data.ap_tracking, true)
+        },
+        hint_code::UINT128_ADD => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint128_add(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT256_SUB => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint256_sub(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SPLIT_64 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_64(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT256_SQRT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint256_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking, false)
+        },
+        hint_code::UINT256_SQRT_FELT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint256_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking, true)
+        },
+        hint_code::UINT256_SIGNED_NN => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint256_signed_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT256_UNSIGNED_DIV_REM => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 uint256_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::UINT256_EXPANDED_UNSIGNED_DIV_REM => {
+        }
+        hint_code::UINT256_EXPANDED_UNSIGNED_DIV_REM => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 uint256_expanded_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::BIGINT_TO_UINT256 => {
-                bigint_to_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::IS_ZERO_PACK_V1 | hint_code::IS_ZERO_PACK_V2 => {
+        }
+        hint_code::BIGINT_TO_UINT256 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            bigint_to_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::IS_ZERO_PACK_V1 | hint_code::IS_ZERO_PACK_V2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 is_zero_pack(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::IS_ZERO_NONDET | hint_code::IS_ZERO_INT => is_zero_nondet(vm, exec_scopes),
-            hint_code::IS_ZERO_PACK_EXTERNAL_SECP_V1 | hint_code::IS_ZERO_PACK_EXTERNAL_SECP_V2 => {
+        }
+        hint_code::IS_ZERO_NONDET | hint_code::IS_ZERO_INT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| is_zero_nondet(vm, exec_scopes)
+        }
+        hint_code::IS_ZERO_PACK_EXTERNAL_SECP_V1 | hint_code::IS_ZERO_PACK_EXTERNAL_SECP_V2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 is_zero_pack_external_secp(
                     vm,
                     exec_scopes,
@@ -454,62 +760,94 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                     &hint_data.ap_tracking,
                 )
             }
-            hint_code::IS_ZERO_PACK_ED25519 => {
+        }
+        hint_code::IS_ZERO_PACK_ED25519 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 ed25519_is_zero_pack(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::IS_ZERO_ASSIGN_SCOPE_VARS => is_zero_assign_scope_variables(exec_scopes),
-            hint_code::IS_ZERO_ASSIGN_SCOPE_VARS_EXTERNAL_SECP => {
+        }
+        hint_code::IS_ZERO_ASSIGN_SCOPE_VARS => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                is_zero_assign_scope_variables(exec_scopes)
+            }
+        }
+        hint_code::IS_ZERO_ASSIGN_SCOPE_VARS_EXTERNAL_SECP => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 is_zero_assign_scope_variables_external_const(exec_scopes)
             }
-            hint_code::IS_ZERO_ASSIGN_SCOPE_VARS_ED25519 => {
+        }
+        hint_code::IS_ZERO_ASSIGN_SCOPE_VARS_ED25519 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 ed25519_is_zero_assign_scope_vars(exec_scopes)
             }
-            hint_code::DIV_MOD_N_PACKED_DIVMOD_V1 => div_mod_n_packed_divmod(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::GET_FELT_BIT_LENGTH => {
+        }
+        hint_code::DIV_MOD_N_PACKED_DIVMOD_V1 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                div_mod_n_packed_divmod(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
+            }
+        }
+        hint_code::GET_FELT_BIT_LENGTH => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 get_felt_bitlenght(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::BIGINT_PACK_DIV_MOD => bigint_pack_div_mod_hint(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::BIGINT_SAFE_DIV => {
-                bigint_safe_div_hint(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::BIGINT_PACK_DIV_MOD => |hint_processor,
+                                           vm,
+                                           exec_scopes,
+                                           hint_data,
+                                           constants| {
+            bigint_pack_div_mod_hint(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::BIGINT_SAFE_DIV => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            bigint_safe_div_hint(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DIV_MOD_N_PACKED_DIVMOD_EXTERNAL_N => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                div_mod_n_packed_external_n(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
             }
-            hint_code::DIV_MOD_N_PACKED_DIVMOD_EXTERNAL_N => div_mod_n_packed_external_n(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::DIV_MOD_N_SAFE_DIV => div_mod_n_safe_div(exec_scopes, "a", "b", 0),
-            hint_code::DIV_MOD_N_SAFE_DIV_PLUS_ONE => div_mod_n_safe_div(exec_scopes, "a", "b", 1),
-            hint_code::GET_POINT_FROM_X => get_point_from_x(
+        }
+        hint_code::DIV_MOD_N_SAFE_DIV => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            div_mod_n_safe_div(exec_scopes, "a", "b", 0)
+        },
+        hint_code::DIV_MOD_N_SAFE_DIV_PLUS_ONE => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                div_mod_n_safe_div(exec_scopes, "a", "b", 1)
+            }
+        }
+        hint_code::GET_POINT_FROM_X => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            get_point_from_x(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
                 &hint_data.ap_tracking,
                 constants,
-            ),
-            hint_code::EC_NEGATE => ec_negate_import_secp_p(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::EC_NEGATE_EMBEDDED_SECP => ec_negate_embedded_secp_p(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::EC_DOUBLE_SLOPE_V1 => compute_doubling_slope(
+            )
+        },
+        hint_code::EC_NEGATE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            ec_negate_import_secp_p(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EC_NEGATE_EMBEDDED_SECP => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                ec_negate_embedded_secp_p(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
+            }
+        }
+        hint_code::EC_DOUBLE_SLOPE_V1 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            compute_doubling_slope(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
@@ -517,8 +855,10 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                 "point",
                 &SECP_P,
                 &ALPHA,
-            ),
-            hint_code::EC_DOUBLE_SLOPE_V2 => compute_doubling_slope(
+            )
+        },
+        hint_code::EC_DOUBLE_SLOPE_V2 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            compute_doubling_slope(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
@@ -526,8 +866,10 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                 "point",
                 &SECP_P_V2,
                 &ALPHA_V2,
-            ),
-            hint_code::EC_DOUBLE_SLOPE_V3 => compute_doubling_slope(
+            )
+        },
+        hint_code::EC_DOUBLE_SLOPE_V3 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            compute_doubling_slope(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
@@ -535,8 +877,10 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                 "pt",
                 &SECP_P,
                 &ALPHA,
-            ),
-            hint_code::EC_DOUBLE_SLOPE_V4 => compute_doubling_slope(
+            )
+        },
+        hint_code::EC_DOUBLE_SLOPE_V4 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            compute_doubling_slope(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
@@ -544,14 +888,20 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                 "point",
                 &SECP256R1_P,
                 &SECP256R1_ALPHA,
-            ),
-            hint_code::EC_DOUBLE_SLOPE_EXTERNAL_CONSTS => compute_doubling_slope_external_consts(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::COMPUTE_SLOPE_V1 => compute_slope_and_assing_secp_p(
+            )
+        },
+        hint_code::EC_DOUBLE_SLOPE_EXTERNAL_CONSTS => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                compute_doubling_slope_external_consts(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
+            }
+        }
+        hint_code::COMPUTE_SLOPE_V1 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            compute_slope_and_assing_secp_p(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
@@ -559,11 +909,15 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                 "point0",
                 "point1",
                 &SECP_P,
-            ),
-            hint_code::SQUARE_SLOPE_X_MOD_P => {
+            )
+        },
+        hint_code::SQUARE_SLOPE_X_MOD_P => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 square_slope_minus_xs(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::COMPUTE_SLOPE_V2 => compute_slope_and_assing_secp_p(
+        }
+        hint_code::COMPUTE_SLOPE_V2 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            compute_slope_and_assing_secp_p(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
@@ -571,98 +925,148 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                 "point0",
                 "point1",
                 &SECP_P_V2,
-            ),
-            hint_code::COMPUTE_SLOPE_SECP256R1_V1 => compute_slope(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "point0",
-                "point1",
-                "SECP_P",
-            ),
-            hint_code::COMPUTE_SLOPE_SECP256R1_V2 => compute_slope(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "point0",
-                "point1",
-                "SECP256R1_P",
-            ),
-            hint_code::IMPORT_SECP256R1_P => import_secp256r1_p(exec_scopes),
-            hint_code::COMPUTE_SLOPE_WHITELIST => compute_slope_and_assing_secp_p(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "pt0",
-                "pt1",
-                &SECP_P,
-            ),
-            hint_code::EC_DOUBLE_ASSIGN_NEW_X_V1 => ec_double_assign_new_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P,
-                "point",
-            ),
-            hint_code::EC_DOUBLE_ASSIGN_NEW_X_V2 => ec_double_assign_new_x_v2(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "point",
-            ),
-            hint_code::EC_DOUBLE_ASSIGN_NEW_X_V3 => ec_double_assign_new_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P_V2,
-                "point",
-            ),
-            hint_code::EC_DOUBLE_ASSIGN_NEW_X_V4 => ec_double_assign_new_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P,
-                "pt",
-            ),
-            hint_code::EC_DOUBLE_ASSIGN_NEW_Y => ec_double_assign_new_y(exec_scopes),
-            hint_code::KECCAK_WRITE_ARGS => {
-                keccak_write_args(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+            )
+        },
+        hint_code::COMPUTE_SLOPE_SECP256R1_V1 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                compute_slope(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    "point0",
+                    "point1",
+                    "SECP_P",
+                )
+            }
+        }
+        hint_code::COMPUTE_SLOPE_SECP256R1_V2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                compute_slope(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    "point0",
+                    "point1",
+                    "SECP256R1_P",
+                )
+            }
+        }
+        hint_code::IMPORT_SECP256R1_P => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| import_secp256r1_p(exec_scopes)
+        }
+        hint_code::COMPUTE_SLOPE_WHITELIST => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                compute_slope_and_assing_secp_p(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    "pt0",
+                    "pt1",
+                    &SECP_P,
+                )
+            }
+        }
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V1 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                ec_double_assign_new_x(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    &SECP_P,
+                    "point",
+                )
+            }
+        }
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                ec_double_assign_new_x_v2(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    "point",
+                )
+            }
+        }
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V3 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                ec_double_assign_new_x(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    &SECP_P_V2,
+                    "point",
+                )
+            }
+        }
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V4 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                ec_double_assign_new_x(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    &SECP_P,
+                    "pt",
+                )
+            }
+        }
+        hint_code::EC_DOUBLE_ASSIGN_NEW_Y => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                ec_double_assign_new_y(exec_scopes)
+            }
+        }
+        hint_code::KECCAK_WRITE_ARGS => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            keccak_write_args(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::COMPARE_BYTES_IN_WORD_NONDET => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                compare_bytes_in_word_nondet(
+                    vm,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    constants,
+                )
             }
-            hint_code::COMPARE_BYTES_IN_WORD_NONDET => compare_bytes_in_word_nondet(
-                vm,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                constants,
-            ),
-            hint_code::SHA256_MAIN_CONSTANT_INPUT_LENGTH => sha256_main_constant_input_length(
-                vm,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                constants,
-            ),
-            hint_code::SHA256_MAIN_ARBITRARY_INPUT_LENGTH => sha256_main_arbitrary_input_length(
-                vm,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                constants,
-            ),
-            hint_code::SHA256_INPUT => {
-                sha256_input(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::SHA256_MAIN_CONSTANT_INPUT_LENGTH => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                sha256_main_constant_input_length(
+                    vm,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    constants,
+                )
             }
-            hint_code::SHA256_FINALIZE => {
-                sha256_finalize(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::SHA256_MAIN_ARBITRARY_INPUT_LENGTH => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                sha256_main_arbitrary_input_length(
+                    vm,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    constants,
+                )
             }
-            hint_code::CAIRO_KECCAK_INPUT_IS_FULL_WORD => {
+        }
+        hint_code::SHA256_INPUT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            sha256_input(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SHA256_FINALIZE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            sha256_finalize(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::CAIRO_KECCAK_INPUT_IS_FULL_WORD => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 cairo_keccak_is_full_word(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::COMPARE_KECCAK_FULL_RATE_IN_BYTES_NONDET => {
+        }
+        hint_code::COMPARE_KECCAK_FULL_RATE_IN_BYTES_NONDET => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 compare_keccak_full_rate_in_bytes_nondet(
                     vm,
                     &hint_data.ids_data,
@@ -670,186 +1074,276 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                     constants,
                 )
             }
-            hint_code::BLOCK_PERMUTATION | hint_code::BLOCK_PERMUTATION_WHITELIST_V1 => {
+        }
+        hint_code::BLOCK_PERMUTATION | hint_code::BLOCK_PERMUTATION_WHITELIST_V1 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 block_permutation_v1(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
             }
-            hint_code::BLOCK_PERMUTATION_WHITELIST_V2 => {
+        }
+        hint_code::BLOCK_PERMUTATION_WHITELIST_V2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 block_permutation_v2(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
             }
-            hint_code::CAIRO_KECCAK_FINALIZE_V1 => {
+        }
+        hint_code::CAIRO_KECCAK_FINALIZE_V1 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 cairo_keccak_finalize_v1(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
             }
-            hint_code::CAIRO_KECCAK_FINALIZE_V2 => {
+        }
+        hint_code::CAIRO_KECCAK_FINALIZE_V2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 cairo_keccak_finalize_v2(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
             }
-            hint_code::FAST_EC_ADD_ASSIGN_NEW_X => fast_ec_add_assign_new_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P,
-                "point0",
-                "point1",
-            ),
-            hint_code::FAST_EC_ADD_ASSIGN_NEW_X_V2 => fast_ec_add_assign_new_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P_V2,
-                "point0",
-                "point1",
-            ),
-            hint_code::FAST_EC_ADD_ASSIGN_NEW_X_V3 => fast_ec_add_assign_new_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P,
-                "pt0",
-                "pt1",
-            ),
-            hint_code::FAST_EC_ADD_ASSIGN_NEW_Y => fast_ec_add_assign_new_y(exec_scopes),
-            hint_code::EC_MUL_INNER => {
-                ec_mul_inner(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::RELOCATE_SEGMENT => {
-                relocate_segment(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::TEMPORARY_ARRAY => {
-                temporary_array(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::VERIFY_ECDSA_SIGNATURE => {
-                verify_ecdsa_signature(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::SPLIT_OUTPUT_0 => {
-                split_output(vm, &hint_data.ids_data, &hint_data.ap_tracking, 0)
-            }
-            hint_code::SPLIT_OUTPUT_1 => {
-                split_output(vm, &hint_data.ids_data, &hint_data.ap_tracking, 1)
-            }
-            hint_code::SPLIT_INPUT_3 => {
-                split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 3, 1)
-            }
-            hint_code::SPLIT_INPUT_6 => {
-                split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 6, 2)
+        }
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_X => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                fast_ec_add_assign_new_x(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    &SECP_P,
+                    "point0",
+                    "point1",
+                )
             }
-            hint_code::SPLIT_INPUT_9 => {
-                split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 9, 3)
+        }
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_X_V2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                fast_ec_add_assign_new_x(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    &SECP_P_V2,
+                    "point0",
+                    "point1",
+                )
             }
-            hint_code::SPLIT_INPUT_12 => {
-                split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 12, 4)
+        }
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_X_V3 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                fast_ec_add_assign_new_x(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                    &SECP_P,
+                    "pt0",
+                    "pt1",
+                )
             }
-            hint_code::SPLIT_INPUT_15 => {
-                split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 15, 5)
+        }
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_Y => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                fast_ec_add_assign_new_y(exec_scopes)
             }
-            hint_code::SPLIT_N_BYTES => {
-                split_n_bytes(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        }
+        hint_code::EC_MUL_INNER => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            ec_mul_inner(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::RELOCATE_SEGMENT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            relocate_segment(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::TEMPORARY_ARRAY => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            temporary_array(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::VERIFY_ECDSA_SIGNATURE => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                verify_ecdsa_signature(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::SPLIT_OUTPUT_MID_LOW_HIGH => {
+        }
+        hint_code::SPLIT_OUTPUT_0 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_output(vm, &hint_data.ids_data, &hint_data.ap_tracking, 0)
+        },
+        hint_code::SPLIT_OUTPUT_1 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_output(vm, &hint_data.ids_data, &hint_data.ap_tracking, 1)
+        },
+        hint_code::SPLIT_INPUT_3 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 3, 1)
+        },
+        hint_code::SPLIT_INPUT_6 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 6, 2)
+        },
+        hint_code::SPLIT_INPUT_9 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 9, 3)
+        },
+        hint_code::SPLIT_INPUT_12 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 12, 4)
+        },
+        hint_code::SPLIT_INPUT_15 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 15, 5)
+        },
+        hint_code::SPLIT_N_BYTES => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_n_bytes(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::SPLIT_OUTPUT_MID_LOW_HIGH => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 split_output_mid_low_high(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::NONDET_N_GREATER_THAN_10 => {
+        }
+        hint_code::NONDET_N_GREATER_THAN_10 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 n_greater_than_10(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::NONDET_N_GREATER_THAN_2 => {
+        }
+        hint_code::NONDET_N_GREATER_THAN_2 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 n_greater_than_2(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::NONDET_ELEMENTS_OVER_TEN => {
+        }
+        hint_code::NONDET_ELEMENTS_OVER_TEN => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 elements_over_x(vm, &hint_data.ids_data, &hint_data.ap_tracking, 10)
             }
-            hint_code::NONDET_ELEMENTS_OVER_TWO => {
+        }
+        hint_code::NONDET_ELEMENTS_OVER_TWO => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 elements_over_x(vm, &hint_data.ids_data, &hint_data.ap_tracking, 2)
             }
-            hint_code::RANDOM_EC_POINT => {
-                random_ec_point_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::CHAINED_EC_OP_RANDOM_EC_POINT => {
+        }
+        hint_code::RANDOM_EC_POINT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            random_ec_point_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::CHAINED_EC_OP_RANDOM_EC_POINT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 chained_ec_op_random_ec_point_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::RECOVER_Y => recover_y_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::PACK_MODN_DIV_MODN => {
-                pack_modn_div_modn(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::XS_SAFE_DIV => div_mod_n_safe_div(exec_scopes, "x", "s", 0),
-            hint_code::UINT384_UNSIGNED_DIV_REM => {
+        }
+        hint_code::RECOVER_Y => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            recover_y_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::PACK_MODN_DIV_MODN => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            pack_modn_div_modn(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::XS_SAFE_DIV => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            div_mod_n_safe_div(exec_scopes, "x", "s", 0)
+        },
+        hint_code::UINT384_UNSIGNED_DIV_REM => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 uint384_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::UINT384_SPLIT_128 => {
-                uint384_split_128(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::ADD_NO_UINT384_CHECK => {
+        }
+        hint_code::UINT384_SPLIT_128 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint384_split_128(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ADD_NO_UINT384_CHECK => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 add_no_uint384_check(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
             }
-            hint_code::UINT384_SQRT => {
-                uint384_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UNSIGNED_DIV_REM_UINT768_BY_UINT384
-            | hint_code::UNSIGNED_DIV_REM_UINT768_BY_UINT384_STRIPPED => {
+        }
+        hint_code::UINT384_SQRT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint384_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UNSIGNED_DIV_REM_UINT768_BY_UINT384
+        | hint_code::UNSIGNED_DIV_REM_UINT768_BY_UINT384_STRIPPED => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 unsigned_div_rem_uint768_by_uint384(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::SUB_REDUCED_A_AND_REDUCED_B => {
+        }
+        hint_code::SUB_REDUCED_A_AND_REDUCED_B => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 sub_reduced_a_and_reduced_b(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::UINT384_GET_SQUARE_ROOT => {
+        }
+        hint_code::UINT384_GET_SQUARE_ROOT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 u384_get_square_root(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::UINT256_GET_SQUARE_ROOT => {
+        }
+        hint_code::UINT256_GET_SQUARE_ROOT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 u256_get_square_root(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::UINT384_SIGNED_NN => {
-                uint384_signed_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UINT384_DIV => uint384_div(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::UINT256_MUL_DIV_MOD => {
+        }
+        hint_code::UINT384_SIGNED_NN => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint384_signed_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT384_DIV => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            uint384_div(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT256_MUL_DIV_MOD => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 uint256_mul_div_mod(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::IMPORT_SECP256R1_ALPHA => import_secp256r1_alpha(exec_scopes),
-            hint_code::IMPORT_SECP256R1_N => import_secp256r1_n(exec_scopes),
-            hint_code::UINT512_UNSIGNED_DIV_REM => {
-                uint512_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::HI_MAX_BITLEN => {
-                hi_max_bitlen(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::QUAD_BIT => quad_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::INV_MOD_P_UINT256 => {
-                inv_mod_p_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::IMPORT_SECP256R1_ALPHA => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                import_secp256r1_alpha(exec_scopes)
             }
-            hint_code::INV_MOD_P_UINT512 => {
-                inv_mod_p_uint512(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::IMPORT_SECP256R1_N => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| import_secp256r1_n(exec_scopes)
+        }
+        hint_code::UINT512_UNSIGNED_DIV_REM => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                uint512_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::DI_BIT => di_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::EXAMPLE_BLAKE2S_COMPRESS => {
+        }
+        hint_code::HI_MAX_BITLEN => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            hi_max_bitlen(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::QUAD_BIT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            quad_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::INV_MOD_P_UINT256 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            inv_mod_p_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::INV_MOD_P_UINT512 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            inv_mod_p_uint512(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DI_BIT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            di_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EXAMPLE_BLAKE2S_COMPRESS => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 example_blake2s_compress(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::EC_RECOVER_DIV_MOD_N_PACKED => ec_recover_divmod_n_packed(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::EC_RECOVER_SUB_A_B => {
-                ec_recover_sub_a_b(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::A_B_BITAND_1 => {
-                a_b_bitand_1(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::EC_RECOVER_DIV_MOD_N_PACKED => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                ec_recover_divmod_n_packed(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
             }
-            hint_code::ASSERT_LE_FELT_V_0_6 => {
+        }
+        hint_code::EC_RECOVER_SUB_A_B => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            ec_recover_sub_a_b(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::A_B_BITAND_1 => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            a_b_bitand_1(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_LE_FELT_V_0_6 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 assert_le_felt_v_0_6(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::ASSERT_LE_FELT_V_0_8 => {
+        }
+        hint_code::ASSERT_LE_FELT_V_0_8 => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 assert_le_felt_v_0_8(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::EC_RECOVER_PRODUCT_MOD => {
+        }
+        hint_code::EC_RECOVER_PRODUCT_MOD => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 ec_recover_product_mod(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::EC_RECOVER_PRODUCT_DIV_M => ec_recover_product_div_m(exec_scopes),
-            hint_code::SPLIT_XX => split_xx(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::RUN_P_CIRCUIT => {
-                run_p_mod_circuit(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        }
+        hint_code::EC_RECOVER_PRODUCT_DIV_M => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                ec_recover_product_div_m(exec_scopes)
             }
-            hint_code::RUN_P_CIRCUIT_WITH_LARGE_BATCH_SIZE => {
+        }
+        hint_code::SPLIT_XX => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            split_xx(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::RUN_P_CIRCUIT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            run_p_mod_circuit(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::RUN_P_CIRCUIT_WITH_LARGE_BATCH_SIZE => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
                 run_p_mod_circuit_with_large_batch_size(
                     vm,
                     &hint_data.ids_data,
@@ -857,26 +1351,122 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                     constants,
                 )
             }
-            #[cfg(feature = "test_utils")]
-            hint_code::SKIP_NEXT_INSTRUCTION => skip_next_instruction(vm),
-            #[cfg(feature = "test_utils")]
-            hint_code::PRINT_FELT => print_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            #[cfg(feature = "test_utils")]
-            hint_code::PRINT_ARR => print_array(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            #[cfg(feature = "test_utils")]
-            hint_code::PRINT_DICT => {
-                print_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::EXCESS_BALANCE => excess_balance_hint(
+        }
+        #[cfg(feature = "test_utils")]
+        hint_code::SKIP_NEXT_INSTRUCTION => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| skip_next_instruction(vm)
+        }
+        #[cfg(feature = "test_utils")]
+        hint_code::PRINT_FELT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            print_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        #[cfg(feature = "test_utils")]
+        hint_code::PRINT_ARR => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            print_array(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        #[cfg(feature = "test_utils")]
+        hint_code::PRINT_DICT => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            print_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EXCESS_BALANCE => |hint_processor, vm, exec_scopes, hint_data, constants| {
+            excess_balance_hint(
                 vm,
                 &hint_data.ids_data,
                 &hint_data.ap_tracking,
                 constants,
                 exec_scopes,
-            ),
-            code => Err(HintError::UnknownHint(code.to_string().into_boxed_str())),
+            )
+        },
+        #[cfg(feature = "std")]
+        hint_code::SIMPLE_BOOTLOADER_LOAD_INPUT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                load_simple_bootloader_input(exec_scopes)
+            }
         }
-    }
+        #[cfg(feature = "std")]
+        hint_code::SIMPLE_BOOTLOADER_PREPARE_TASK_RANGE_CHECKS => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                prepare_task_range_checks(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
+            }
+        }
+        #[cfg(feature = "std")]
+        hint_code::SIMPLE_BOOTLOADER_SET_CURRENT_TASK => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                set_current_task(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+            }
+        }
+        #[cfg(feature = "std")]
+        hint_code::SIMPLE_BOOTLOADER_DUMP_FACT_TOPOLOGIES => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                dump_fact_topologies(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+            }
+        }
+        #[cfg(feature = "std")]
+        hint_code::EXECUTE_TASK_ALLOCATE_PROGRAM_DATA_SEGMENT => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                allocate_program_data_segment(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
+            }
+        }
+        #[cfg(feature = "std")]
+        hint_code::EXECUTE_TASK_LOAD_PROGRAM => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                load_task_program(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+            }
+        }
+        #[cfg(feature = "std")]
+        hint_code::EXECUTE_TASK_VALIDATE_HASH => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                validate_hash(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+            }
+        }
+        #[cfg(feature = "std")]
+        hint_code::EXECUTE_TASK_ASSERT_PROGRAM_ADDRESS => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                assert_program_address(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+            }
+        }
+        #[cfg(feature = "std")]
+        hint_code::EXECUTE_TASK_WRITE_RETURN_BUILTINS => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                write_return_builtins(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+            }
+        }
+        #[cfg(feature = "std")]
+        hint_code::EXECUTE_TASK_APPEND_FACT_TOPOLOGIES => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                append_fact_topology(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+            }
+        }
+        #[cfg(feature = "std")]
+        hint_code::SELECT_BUILTINS_ENTER_SCOPE => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                select_builtins_enter_scope(
+                    vm,
+                    exec_scopes,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                )
+            }
+        }
+        #[cfg(feature = "std")]
+        hint_code::INNER_SELECT_BUILTINS_SELECT_BUILTIN => {
+            |hint_processor, vm, exec_scopes, hint_data, constants| {
+                select_builtin(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+            }
+        }
+        _ => return None,
+    };
+    Some(hint_fn)
 }
 
 impl ResourceTracker for BuiltinHintProcessor {
@@ -900,12 +1490,16 @@ impl ResourceTracker for BuiltinHintProcessor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::stdlib::any::Any;
     use crate::types::relocatable::Relocatable;
+    use crate::types::shared::AnyBox;
 
     use crate::{
         any_box,
-        types::{exec_scope::ExecutionScopes, relocatable::MaybeRelocatable},
+        hint_processor::hint_processor_utils::compute_addr_from_reference,
+        serde::deserialize_program::OffsetValue,
+        types::{
+            exec_scope::ExecutionScopes, instruction::Register, relocatable::MaybeRelocatable,
+        },
         utils::test_utils::*,
         vm::{
             errors::{exec_scope_errors::ExecScopeError, memory_errors::MemoryError},
@@ -979,6 +1573,139 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn compile_hint_resolves_builtin_hints() {
+        let hint_processor = BuiltinHintProcessor::new_empty();
+        let compile = |code| {
+            hint_processor
+                .compile_hint(code, &ApTracking::default(), &HashMap::new(), &[])
+                .unwrap()
+        };
+        let hint_data = compile(hint_code::ADD_SEGMENT);
+        let hint_data = hint_data.downcast_ref::<HintProcessorData>().unwrap();
+        assert_matches!(hint_data.compiled_hint, Some(CompiledHint::Builtin(_)));
+
+        let hint_data = compile("random_invalid_code");
+        let hint_data = hint_data.downcast_ref::<HintProcessorData>().unwrap();
+        assert!(hint_data.compiled_hint.is_none());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn compile_hint_rebases_ap_based_references() {
+        let ap_tracking = |offset| ApTracking { group: 1, offset };
+        let references = vec![
+            HintReference {
+                offset1: OffsetValue::Reference(Register::AP, -1, false),
+                ap_tracking_data: Some(ap_tracking(2)),
+                ..HintReference::new_simple(0)
+            },
+            HintReference {
+                offset1: OffsetValue::Reference(Register::AP, -1, false),
+                ap_tracking_data: Some(ApTracking {
+                    group: 0,
+                    offset: 2,
+                }),
+                ..HintReference::new_simple(0)
+            },
+            HintReference::new_simple(-3),
+        ];
+        let reference_ids = HashMap::from([
+            ("main.a".to_string(), 0),
+            ("main.b".to_string(), 1),
+            ("main.c".to_string(), 2),
+        ]);
+        let hint_data = BuiltinHintProcessor::new_empty()
+            .compile_hint(
+                hint_code::ADD_SEGMENT,
+                &ap_tracking(5),
+                &reference_ids,
+                &references,
+            )
+            .unwrap();
+        let ids_data = &hint_data
+            .downcast_ref::<HintProcessorData>()
+            .unwrap()
+            .ids_data;
+        assert_eq!(
+            ids_data["a"],
+            HintReference {
+                offset1: OffsetValue::Reference(Register::AP, -4, false),
+                ap_tracking_data: Some(ap_tracking(5)),
+                ..references[0].clone()
+            }
+        );
+        // References of another group are left to fail when they are used
+        assert_eq!(ids_data["b"], references[1]);
+        assert_eq!(ids_data["c"], references[2]);
+
+        // The rebased reference points to the same address
+        let mut vm = vm!();
+        vm.run_context.ap = 10;
+        assert_eq!(
+            compute_addr_from_reference(&ids_data["a"], &vm, &ap_tracking(5)),
+            compute_addr_from_reference(&references[0], &vm, &ap_tracking(5))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_unresolved_builtin_hint() {
+        let mut vm = vm!();
+        add_segments!(vm, 1);
+        let hint_data =
+            HintProcessorData::new_default(hint_code::ADD_SEGMENT.to_string(), HashMap::new());
+        assert!(hint_data.compiled_hint.is_none());
+        assert_matches!(
+            BuiltinHintProcessor::new_empty().execute_hint(
+                &mut vm,
+                &mut ExecutionScopes::new(),
+                &any_box!(hint_data),
+                &HashMap::new(),
+            ),
+            Ok(())
+        );
+        assert_eq!(vm.segments.num_segments(), 2);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn extra_hint_overrides_builtin_hint() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_hint(
+            hint_code::ADD_SEGMENT.to_string(),
+            Shared::new(HintFunc(Box::new(enter_scope))),
+        );
+        let compiled = hint_processor
+            .compile_hint(
+                hint_code::ADD_SEGMENT,
+                &ApTracking::default(),
+                &HashMap::new(),
+                &[],
+            )
+            .unwrap();
+        assert_matches!(
+            compiled
+                .downcast_ref::<HintProcessorData>()
+                .unwrap()
+                .compiled_hint,
+            Some(CompiledHint::Extra(_))
+        );
+        let unresolved =
+            HintProcessorData::new_default(hint_code::ADD_SEGMENT.to_string(), HashMap::new());
+        let mut vm = vm!();
+        let exec_scopes = exec_scopes_ref!();
+        for hint_data in [compiled, any_box!(unresolved)] {
+            assert_matches!(
+                hint_processor.execute_hint(&mut vm, exec_scopes, &hint_data, &HashMap::new()),
+                Ok(())
+            );
+        }
+        assert_eq!(exec_scopes.data.len(), 3);
+        assert_eq!(vm.segments.num_segments(), 0);
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn memcpy_enter_scope_valid() {
@@ -1086,7 +1813,7 @@ mod tests {
         let mut vm = vm!();
         // Create new vm scope with dummy variable
         let mut exec_scopes = ExecutionScopes::new();
-        let a_value: Box<dyn Any> = Box::new(Felt252::ONE);
+        let a_value: AnyBox = Box::new(Felt252::ONE);
         exec_scopes.enter_scope(HashMap::from([(String::from("a"), a_value)]));
         // Initialize memory segments
         add_segments!(vm, 1);
@@ -1303,8 +2030,11 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn add_hint_add_same_hint_twice() {
         let mut hint_processor = BuiltinHintProcessor::new_empty();
-        let hint_func = Rc::new(HintFunc(Box::new(enter_scope)));
-        hint_processor.add_hint(String::from("enter_scope_custom_a"), Rc::clone(&hint_func));
+        let hint_func = Shared::new(HintFunc(Box::new(enter_scope)));
+        hint_processor.add_hint(
+            String::from("enter_scope_custom_a"),
+            Shared::clone(&hint_func),
+        );
         hint_processor.add_hint(String::from("enter_scope_custom_b"), hint_func);
         let mut vm = vm!();
         let exec_scopes = exec_scopes_ref!();
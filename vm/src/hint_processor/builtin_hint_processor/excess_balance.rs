@@ -441,7 +441,7 @@ pub fn excess_balance_hint(
 
 #[cfg(test)]
 mod tests {
-    use crate::stdlib::{cell::RefCell, rc::Rc};
+    use crate::types::shared::{Shared, SharedCell};
     use core::str::FromStr;
 
     use super::*;
@@ -819,7 +819,7 @@ mod tests {
                 ]),
             )
             .unwrap();
-        exec_scopes.insert_value("dict_manager", Rc::new(RefCell::new(dict_manager)));
+        exec_scopes.insert_value("dict_manager", Shared::new(SharedCell::new(dict_manager)));
 
         // EXECUTION
         assert!(excess_balance_hint(
@@ -1185,7 +1185,7 @@ mod tests {
                 ]),
             )
             .unwrap();
-        exec_scopes.insert_value("dict_manager", Rc::new(RefCell::new(dict_manager)));
+        exec_scopes.insert_value("dict_manager", Shared::new(SharedCell::new(dict_manager)));
 
         // EXECUTION
         assert!(excess_balance_hint(
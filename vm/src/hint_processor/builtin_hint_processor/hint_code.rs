@@ -1462,3 +1462,148 @@ ids.check_account_value = res["account_value"]
 ids.check_excess_balance = res["excess_balance"]
 ids.check_margin_requirement_d = res["margin_requirement"]
 ids.check_unrealized_pnl_d = res["unrealized_pnl"]"#;
+
+#[cfg(feature = "std")]
+pub const SIMPLE_BOOTLOADER_LOAD_INPUT: &str =
+    "from starkware.cairo.bootloaders.simple_bootloader.objects import SimpleBootloaderInput
+simple_bootloader_input = SimpleBootloaderInput.Schema().load(program_input)";
+
+#[cfg(feature = "std")]
+pub const SIMPLE_BOOTLOADER_PREPARE_TASK_RANGE_CHECKS: &str =
+    "n_tasks = len(simple_bootloader_input.tasks)
+memory[ids.output_ptr] = n_tasks
+
+# Task range checks are located right after simple bootloader validation range checks, and
+# this is validated later in this function.
+ids.task_range_check_ptr = ids.range_check_ptr + ids.BuiltinData.SIZE * n_tasks
+
+# A list of fact_toplogies that instruct how to generate the fact from the program output
+# for each task.
+fact_topologies = []";
+
+#[cfg(feature = "std")]
+pub const SIMPLE_BOOTLOADER_SET_CURRENT_TASK: &str =
+    "from starkware.cairo.bootloaders.simple_bootloader.objects import Task
+
+# Pass current task to execute_task.
+task_id = len(simple_bootloader_input.tasks) - ids.n_tasks
+task = simple_bootloader_input.tasks[task_id].load_task()";
+
+#[cfg(feature = "std")]
+pub const SIMPLE_BOOTLOADER_DUMP_FACT_TOPOLOGIES: &str = "# Dump fact topologies to a json file.
+from starkware.cairo.bootloaders.simple_bootloader.utils import (
+    configure_fact_topologies,
+    write_to_fact_topologies_file,
+)
+
+# The task-related output is prefixed by a single word that contains the number of tasks.
+output_start = ids.output_ptr + 1
+
+if not simple_bootloader_input.single_page:
+    # Configure the memory pages in the output builtin, based on fact_topologies.
+    configure_fact_topologies(
+        fact_topologies=fact_topologies, output_start=output_start,
+        output_builtin=output_builtin,
+    )
+
+if simple_bootloader_input.fact_topologies_path is not None:
+    write_to_fact_topologies_file(
+        fact_topologies_path=simple_bootloader_input.fact_topologies_path,
+        fact_topologies=fact_topologies,
+    )";
+
+#[cfg(feature = "std")]
+pub const EXECUTE_TASK_ALLOCATE_PROGRAM_DATA_SEGMENT: &str =
+    "ids.program_data_ptr = program_data_base = segments.add()";
+
+#[cfg(feature = "std")]
+pub const EXECUTE_TASK_LOAD_PROGRAM: &str =
+    "from starkware.cairo.bootloaders.simple_bootloader.utils import load_program
+
+# Call load_program to load the program header and code to memory.
+program_address, program_data_size = load_program(
+    task=task, memory=memory, program_header=ids.program_header,
+    builtins_offset=ids.ProgramHeader.builtin_list)
+segments.finalize(program_data_base.segment_index, program_data_size)";
+
+#[cfg(feature = "std")]
+pub const EXECUTE_TASK_VALIDATE_HASH: &str = "# Validate hash.
+from starkware.cairo.bootloaders.hash_program import compute_program_hash_chain
+
+assert memory[ids.output_ptr + 1] == compute_program_hash_chain(task.get_program()), \\
+  'Computed hash does not match input.'";
+
+#[cfg(feature = "std")]
+pub const EXECUTE_TASK_ASSERT_PROGRAM_ADDRESS: &str = "# Sanity check.
+assert ids.program_address == program_address";
+
+#[cfg(feature = "std")]
+pub const EXECUTE_TASK_CALL_TASK: &str =
+    "from starkware.cairo.bootloaders.simple_bootloader.objects import (
+    CairoPieTask,
+    RunProgramTask,
+    Task,
+)
+from starkware.cairo.bootloaders.simple_bootloader.utils import (
+    load_cairo_pie,
+    prepare_output_runner,
+)
+
+assert isinstance(task, Task)
+n_builtins = len(task.get_program().builtins)
+new_task_locals = {}
+if isinstance(task, RunProgramTask):
+    new_task_locals['program_input'] = task.program_input
+    new_task_locals['WITH_BOOTLOADER'] = True
+
+    vm_load_program(task.program, program_address)
+elif isinstance(task, CairoPieTask):
+    ret_pc = ids.ret_pc_label.instruction_offset_ - ids.call_task.instruction_offset_ + pc
+    load_cairo_pie(
+        task=task.cairo_pie, memory=memory, segments=segments,
+        program_address=program_address, execution_segment_address= ap - n_builtins,
+        builtin_runners=builtin_runners, ret_fp=fp, ret_pc=ret_pc)
+else:
+    raise NotImplementedError(f'Unexpected task type: {type(task).__name__}.')
+
+output_runner_data = prepare_output_runner(
+    task=task,
+    output_builtin=output_builtin,
+    output_ptr=ids.pre_execution_builtin_ptrs.output)
+vm_enter_scope(new_task_locals)";
+
+#[cfg(feature = "std")]
+pub const EXECUTE_TASK_WRITE_RETURN_BUILTINS: &str =
+    "from starkware.cairo.bootloaders.simple_bootloader.utils import write_return_builtins
+
+# Fill the values of all builtin pointers after executing the task.
+builtins = task.get_program().builtins
+write_return_builtins(
+    memory=memory, return_builtins_addr=ids.return_builtin_ptrs.address_,
+    used_builtins=builtins, used_builtins_addr=ids.used_builtins_addr,
+    pre_execution_builtins_addr=ids.pre_execution_builtin_ptrs.address_, task=task)
+
+vm_exit_scope()";
+
+#[cfg(feature = "std")]
+pub const EXECUTE_TASK_APPEND_FACT_TOPOLOGIES: &str = "# Append fact topologies from the task.
+fact_topologies.append(get_task_fact_topology(
+    output_size=ids.return_builtin_ptrs.output - ids.pre_execution_builtin_ptrs.output,
+    task=task,
+    output_builtin=output_builtin,
+    output_runner_data=output_runner_data,
+))";
+
+#[cfg(feature = "std")]
+pub const SELECT_BUILTINS_ENTER_SCOPE: &str =
+    "vm_enter_scope({'n_selected_builtins': ids.n_selected_builtins})";
+
+#[cfg(feature = "std")]
+pub const INNER_SELECT_BUILTINS_SELECT_BUILTIN: &str =
+    "# A builtin should be selected iff its encoding appears in the selected encodings list
+# and the list wasn't exhausted.
+# Note that testing inclusion by a single comparison is possible since the lists are sorted.
+ids.select_builtin = int(
+  n_selected_builtins > 0 and memory[ids.selected_encodings] == memory[ids.all_encodings])
+if ids.select_builtin:
+  n_selected_builtins = n_selected_builtins - 1";
@@ -11,7 +11,6 @@ use crate::utils::CAIRO_PRIME;
 
 use crate::Felt252;
 use crate::{
-    any_box,
     hint_processor::{
         builtin_hint_processor::hint_utils::{
             get_integer_from_var_name, get_ptr_from_var_name, insert_value_from_var_name,
@@ -131,7 +130,7 @@ pub fn assert_le_felt(
     }
 
     let excluded = lengths_and_indices[2].1;
-    exec_scopes.assign_or_update_variable("excluded", any_box!(Felt252::from(excluded)));
+    exec_scopes.assign_or_update_variable("excluded", Box::new(Felt252::from(excluded)));
 
     let (q_0, r_0) = (lengths_and_indices[0].0).div_mod_floor(&prime_over_3_high.to_biguint());
     let (q_1, r_1) = (lengths_and_indices[1].0).div_mod_floor(&prime_over_2_high.to_biguint());
@@ -1,6 +1,8 @@
 pub mod bigint;
 pub mod blake2s_hash;
 pub mod blake2s_utils;
+#[cfg(feature = "std")]
+pub mod bootloader;
 pub mod builtin_hint_processor_definition;
 pub mod cairo_keccak;
 pub mod dict_hint_utils;
@@ -1031,7 +1031,7 @@ mod tests {
             ("slope".to_string(), HintReference::new_simple(-4)),
         ]);
         let mut exec_scopes = ExecutionScopes::new();
-        exec_scopes.assign_or_update_variable("SECP_P", any_box!(SECP_P.clone()));
+        exec_scopes.assign_or_update_variable("SECP_P", Box::new(SECP_P.clone()));
 
         //Execute the hint
         assert_matches!(run_hint!(vm, ids_data, hint_code, &mut exec_scopes), Ok(()));
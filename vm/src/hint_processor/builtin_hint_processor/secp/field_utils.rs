@@ -293,7 +293,7 @@ mod tests {
         let new_secp_p = 55;
 
         let mut exec_scopes = ExecutionScopes::new();
-        exec_scopes.assign_or_update_variable("SECP_P", any_box!(bigint!(new_secp_p)));
+        exec_scopes.assign_or_update_variable("SECP_P", Box::new(bigint!(new_secp_p)));
 
         //Execute the hint
         assert!(run_hint!(vm, ids_data, hint_code, &mut exec_scopes).is_ok());
@@ -553,7 +553,7 @@ mod tests {
 
         let mut exec_scopes = ExecutionScopes::new();
         //Initialize vm scope with variable `x`
-        exec_scopes.assign_or_update_variable("x", any_box!(BigInt::zero()));
+        exec_scopes.assign_or_update_variable("x", Box::new(BigInt::zero()));
         //Create hint data
         //Execute the hint
         assert_matches!(
@@ -581,7 +581,7 @@ mod tests {
 
         //Initialize vm scope with variable `x`
         let mut exec_scopes = ExecutionScopes::new();
-        exec_scopes.assign_or_update_variable("x", any_box!(bigint!(123890i32)));
+        exec_scopes.assign_or_update_variable("x", Box::new(bigint!(123890i32)));
 
         //Execute the hint
         assert_matches!(
@@ -631,7 +631,7 @@ mod tests {
 
         //Initialize vm scope with variable `x`
         let mut exec_scopes = ExecutionScopes::new();
-        exec_scopes.assign_or_update_variable("x", any_box!(BigInt::zero()));
+        exec_scopes.assign_or_update_variable("x", Box::new(BigInt::zero()));
         //Execute the hint
         assert_matches!(
             run_hint!(vm, HashMap::new(), hint_code, &mut exec_scopes),
@@ -659,7 +659,7 @@ mod tests {
             //Initialize vm scope with variable `x`
             exec_scopes.assign_or_update_variable(
                 "x",
-                any_box!(bigint_str!(
+                Box::new(bigint_str!(
                     "52621538839140286024584685587354966255185961783273479086367"
                 )),
             );
@@ -1,6 +1,5 @@
 use crate::Felt252;
 use crate::{
-    any_box,
     hint_processor::{
         builtin_hint_processor::{hint_utils::get_integer_from_var_name, secp::secp_utils::BETA},
         hint_processor_definition::HintReference,
@@ -52,7 +51,7 @@ pub fn div_mod_n_packed_divmod(
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
 ) -> Result<(), HintError> {
-    exec_scopes.assign_or_update_variable("N", any_box!(N.clone()));
+    exec_scopes.assign_or_update_variable("N", Box::new(N.clone()));
     div_mod_n_packed(vm, exec_scopes, ids_data, ap_tracking, &N)
 }
 
@@ -188,7 +187,7 @@ mod tests {
     fn safe_div_ok() {
         // "import N"
         let mut exec_scopes = ExecutionScopes::new();
-        exec_scopes.assign_or_update_variable("N", any_box!(N.clone()));
+        exec_scopes.assign_or_update_variable("N", Box::new(N.clone()));
 
         let hint_codes = vec![
             hint_code::DIV_MOD_N_PACKED_DIVMOD_V1,
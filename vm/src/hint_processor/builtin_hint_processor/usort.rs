@@ -1,4 +1,4 @@
-use crate::stdlib::{any::Any, boxed::Box, collections::HashMap, prelude::*};
+use crate::stdlib::{boxed::Box, collections::HashMap, prelude::*};
 
 use crate::Felt252;
 use crate::{
@@ -9,7 +9,7 @@ use crate::{
         hint_processor_definition::HintReference,
     },
     serde::deserialize_program::ApTracking,
-    types::exec_scope::ExecutionScopes,
+    types::{exec_scope::ExecutionScopes, shared::AnyBox},
     vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
 };
 
@@ -17,7 +17,7 @@ use num_traits::ToPrimitive;
 
 pub fn usort_enter_scope(exec_scopes: &mut ExecutionScopes) -> Result<(), HintError> {
     if let Ok(usort_max_size) = exec_scopes.get::<Felt252>("usort_max_size") {
-        let boxed_max_size: Box<dyn Any> = Box::new(usort_max_size);
+        let boxed_max_size: AnyBox = Box::new(usort_max_size);
         exec_scopes.enter_scope(HashMap::from([(
             "usort_max_size".to_string(),
             boxed_max_size,
@@ -39,6 +39,30 @@ impl DictTrackerExecScope {
             end: None,
         }
     }
+
+    /// Creates a tracker from its contents, as returned by `data`, `start` and `end`.
+    pub(crate) fn from_parts(
+        data: HashMap<Felt252, MaybeRelocatable>,
+        start: Relocatable,
+        end: Option<Relocatable>,
+    ) -> Self {
+        Self { data, start, end }
+    }
+
+    /// The data of the dictionary.
+    pub fn data(&self) -> &HashMap<Felt252, MaybeRelocatable> {
+        &self.data
+    }
+
+    /// The start of the segment of the dictionary.
+    pub fn start(&self) -> Relocatable {
+        self.start
+    }
+
+    /// The end of the segment of the dictionary, if finalized.
+    pub fn end(&self) -> Option<Relocatable> {
+        self.end
+    }
 }
 
 impl DictManagerExecScope {
@@ -52,6 +76,35 @@ impl DictManagerExecScope {
         }
     }
 
+    /// Creates a manager from its contents, as returned by `segment_to_tracker`, `trackers` and
+    /// `use_temporary_segments`.
+    pub(crate) fn from_parts(
+        segment_to_tracker: HashMap<isize, usize>,
+        trackers: Vec<DictTrackerExecScope>,
+        use_temporary_segments: bool,
+    ) -> Self {
+        Self {
+            segment_to_tracker,
+            trackers,
+            use_temporary_segments,
+        }
+    }
+
+    /// Maps between a segment index and the position of its tracker in `trackers`.
+    pub fn segment_to_tracker(&self) -> &HashMap<isize, usize> {
+        &self.segment_to_tracker
+    }
+
+    /// The trackers of the dictionaries, in the order of allocation.
+    pub fn trackers(&self) -> &[DictTrackerExecScope] {
+        &self.trackers
+    }
+
+    /// Whether dictionaries are created on temporary segments.
+    pub fn use_temporary_segments(&self) -> bool {
+        self.use_temporary_segments
+    }
+
     /// Allocates a new segment for a new dictionary and return the start of the segment.
     pub fn new_default_dict(&mut self, vm: &mut VirtualMachine) -> Result<Relocatable, HintError> {
         let dict_segment = if self.use_temporary_segments {
@@ -48,6 +48,7 @@ pub trait HintProcessorLogic {
             code: hint_code.to_string(),
             ap_tracking: ap_tracking_data.clone(),
             ids_data: get_ids_data(reference_ids, references)?,
+            compiled_hint: None,
         }))
     }
 
@@ -77,7 +78,7 @@ pub type HintExtension = HashMap<Relocatable, Vec<Box<dyn Any>>>;
 pub trait HintProcessor: HintProcessorLogic + ResourceTracker {}
 impl<T> HintProcessor for T where T: HintProcessorLogic + ResourceTracker {}
 
-fn get_ids_data(
+pub(crate) fn get_ids_data(
     reference_ids: &HashMap<String, usize>,
     references: &[HintReference],
 ) -> Result<HashMap<String, HintReference>, VirtualMachineError> {
@@ -98,6 +99,23 @@ fn get_ids_data(
     Ok(ids_data)
 }
 
+/// Version of [get_ids_data] which applies the ap tracking corrections of the references up
+/// front, rebasing their `ap` based offsets to the ap tracking of the hint.
+/// References of a different ap tracking group are kept as they are, to fail when they are used.
+pub(crate) fn get_resolved_ids_data(
+    reference_ids: &HashMap<String, usize>,
+    references: &[HintReference],
+    ap_tracking: &ApTracking,
+) -> Result<HashMap<String, HintReference>, VirtualMachineError> {
+    let mut ids_data = get_ids_data(reference_ids, references)?;
+    for reference in ids_data.values_mut() {
+        if let Some(resolved) = reference.rebase_ap_tracking(ap_tracking) {
+            *reference = resolved;
+        }
+    }
+    Ok(ids_data)
+}
+
 #[cfg_attr(feature = "test_utils", derive(Arbitrary))]
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct HintReference {
@@ -131,6 +149,30 @@ impl HintReference {
             cairo_type: None,
         }
     }
+
+    // Returns the reference with its ap based offsets relative to the given ap tracking, or None
+    // if it isn't ap based or belongs to another group
+    fn rebase_ap_tracking(&self, ap_tracking: &ApTracking) -> Option<HintReference> {
+        let reference_ap_tracking = self.ap_tracking_data.as_ref()?;
+        if reference_ap_tracking.group != ap_tracking.group {
+            return None;
+        }
+        let ap_diff =
+            i32::try_from(ap_tracking.offset as i64 - reference_ap_tracking.offset as i64).ok()?;
+        let rebase =
+            |offset_value: &OffsetValue| match offset_value {
+                OffsetValue::Reference(Register::AP, offset, deref) => Some(
+                    OffsetValue::Reference(Register::AP, offset.checked_sub(ap_diff)?, *deref),
+                ),
+                _ => Some(offset_value.clone()),
+            };
+        Some(HintReference {
+            offset1: rebase(&self.offset1)?,
+            offset2: rebase(&self.offset2)?,
+            ap_tracking_data: Some(ap_tracking.clone()),
+            ..self.clone()
+        })
+    }
 }
 
 impl From<Reference> for HintReference {
@@ -2,12 +2,15 @@
 //!
 //! ## Feature Flags
 //! - `std`: Enables usage of the [`std`] standard library. Enabled by default.
+//! - `hooks`: Enables [`Hooks`](crate::vm::hooks::Hooks) support for the [VirtualMachine](vm::vm_core::VirtualMachine). Not enabled by default.
 //! - `test_utils`: Enables the following to help with tests (not enabled by default):
-//!    - [`Hooks`](crate::vm::hooks::Hooks) support for the [VirtualMachine](vm::vm_core::VirtualMachine);
+//!    - the `hooks` feature;
 //!    - the `print_*` family of hints;
 //!    - the `skip_next_instruction()` hints;
-//!    - implementations of [`arbitrary::Arbitrary`](https://docs.rs/arbitrary/latest/arbitrary/) for some structs.
+//!    - implementations of [`arbitrary::Arbitrary`](https://docs.rs/arbitrary/latest/arbitrary/) for some structs;
+//!    - the [`fuzzing`] module, to fuzz Cairo 0 functions from their signatures.
 //! - `cairo-1-hints`: Enable hints that were introduced in Cairo 1. Not enabled by default.
+//! - `thread_safe`: Makes [CairoRunner](vm::runners::cairo_runner::CairoRunner), [VirtualMachine](vm::vm_core::VirtualMachine) and the hint processors `Send + Sync`, see [`types::shared`]. Requires `std`, not enabled by default.
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![deny(warnings)]
@@ -52,12 +55,18 @@ pub mod stdlib {
     pub use crate::without_std::*;
 }
 
+pub mod air_input_checker;
 pub mod air_private_input;
 pub mod air_public_input;
 pub mod cairo_run;
+#[cfg(feature = "test_utils")]
+pub mod fuzzing;
 pub mod hint_processor;
 pub mod math_utils;
+pub mod program_fact;
 pub mod program_hash;
+#[cfg(feature = "std")]
+pub mod run_artifacts;
 pub mod serde;
 pub mod types;
 pub mod utils;
@@ -0,0 +1,530 @@
+//! Computation of the fact registered on-chain for a program run: the keccak hash of its program
+//! hash and the root of its output, split into pages according to its fact topology.
+//! (cairo-lang reference: `starkware/cairo/bootloaders/compute_fact.py`)
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use thiserror_no_std::Error;
+
+use crate::{
+    program_hash::{compute_program_hash_chain, ProgramHashError},
+    stdlib::{boxed::Box, collections::HashMap, prelude::*},
+    types::{builtin_name::BuiltinName, errors::program_errors::ProgramError},
+    vm::{
+        errors::memory_errors::MemoryError,
+        runners::{
+            builtin_runner::BuiltinRunner,
+            cairo_pie::{
+                BuiltinAdditionalData, CairoPie, OutputBuiltinAdditionalData, Pages,
+                StrippedProgram,
+            },
+            cairo_runner::CairoRunner,
+        },
+    },
+    Felt252,
+};
+
+/// Attribute of the output builtin holding the tree structure of the fact of a program
+pub const GPS_FACT_TOPOLOGY: &str = "gps_fact_topology";
+
+/// Describes how the fact of a program is computed from its output: `page_sizes` splits the output
+/// into pages, and `tree_structure` how they are merged into the fact
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FactTopology {
+    pub tree_structure: Vec<usize>,
+    pub page_sizes: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Error)]
+#[error("Invalid fact topology: {0}")]
+pub struct FactTopologyError(pub Box<str>);
+
+#[derive(Debug, Error)]
+pub enum ProgramFactError {
+    #[error(transparent)]
+    FactTopology(#[from] FactTopologyError),
+    #[error("Failed to compute the program hash: {0}")]
+    ProgramHash(#[from] ProgramHashError),
+    #[error(transparent)]
+    Program(#[from] ProgramError),
+    #[error(transparent)]
+    Memory(#[from] MemoryError),
+    #[error("The output builtin cell at offset {0} is missing or is not an integer")]
+    InvalidOutputCell(usize),
+}
+
+/// Fact of a program run, along with the values it is computed from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramFact {
+    pub program_hash: Felt252,
+    /// Root of the tree of output pages described by `fact_topology`. When the output is a single
+    /// page, this is the keccak hash of the output.
+    pub output_hash: [u8; 32],
+    /// `keccak(program_hash, output_hash)`
+    pub fact: [u8; 32],
+    pub fact_topology: FactTopology,
+}
+
+/// Returns the fact topology of a program from the additional data of its output builtin, whose
+/// page starts are relative to the start of the output.
+/// Programs using pages must set the [GPS_FACT_TOPOLOGY] attribute, otherwise their whole output
+/// is a single page.
+/// (cairo-lang reference: `get_fact_topology_from_additional_data` in `simple_bootloader/utils.py`)
+pub fn get_fact_topology_from_additional_data(
+    output_size: usize,
+    additional_data: &OutputBuiltinAdditionalData,
+) -> Result<FactTopology, FactTopologyError> {
+    let tree_structure = match additional_data.attributes.get(GPS_FACT_TOPOLOGY) {
+        Some(tree_structure) => {
+            if tree_structure.is_empty()
+                || tree_structure.len() % 2 != 0
+                || tree_structure.len() > 10
+            {
+                return Err(invalid_fact_topology(format!(
+                    "Invalid tree structure specified in the {GPS_FACT_TOPOLOGY} attribute"
+                )));
+            }
+            if tree_structure.iter().any(|value| *value >= 1 << 30) {
+                return Err(invalid_fact_topology(format!(
+                    "Values in the {GPS_FACT_TOPOLOGY} attribute must be below 2**30"
+                )));
+            }
+            tree_structure.clone()
+        }
+        None => {
+            if !additional_data.pages.is_empty() {
+                return Err(invalid_fact_topology(format!(
+                    "Programs without the {GPS_FACT_TOPOLOGY} attribute must not use pages"
+                )));
+            }
+            vec![1, 0]
+        }
+    };
+
+    Ok(FactTopology {
+        tree_structure,
+        page_sizes: get_page_sizes(output_size, &additional_data.pages)?,
+    })
+}
+
+/// Returns the sizes of the pages of an output, page 0 being the output before page 1 or the
+/// whole output if there are no pages. The pages must be consecutive and cover the rest of it.
+fn get_page_sizes(output_size: usize, pages: &Pages) -> Result<Vec<usize>, FactTopologyError> {
+    let mut pages: Vec<_> = pages.iter().collect();
+    pages.sort_by_key(|(page_id, _)| **page_id);
+
+    let mut page0_size = output_size;
+    let mut expected_page_start = None;
+    let mut page_sizes = vec![];
+    for (expected_page_id, (page_id, page)) in (1..).zip(pages) {
+        if *page_id != expected_page_id {
+            return Err(invalid_fact_topology(format!(
+                "Expected page id {expected_page_id}, found {page_id}"
+            )));
+        }
+        if *page_id == 1 {
+            if page.start == 0 || page.start > output_size {
+                return Err(invalid_fact_topology(format!(
+                    "Invalid page start {}",
+                    page.start
+                )));
+            }
+            page0_size = page.start;
+        } else if expected_page_start != Some(page.start) {
+            return Err(invalid_fact_topology(format!(
+                "Page {page_id} starts at {}, expected {}",
+                page.start,
+                expected_page_start.unwrap_or_default()
+            )));
+        }
+        if page.size == 0 || page.size > output_size {
+            return Err(invalid_fact_topology(format!(
+                "Invalid page size {}",
+                page.size
+            )));
+        }
+        expected_page_start = Some(page.start + page.size);
+        page_sizes.push(page.size);
+    }
+
+    if let Some(pages_end) = expected_page_start {
+        if pages_end != output_size {
+            return Err(invalid_fact_topology(format!(
+                "Pages must cover the entire program output. Expected size of {output_size}, found {pages_end}"
+            )));
+        }
+    }
+    page_sizes.insert(0, page0_size);
+    Ok(page_sizes)
+}
+
+/// Node of the tree of output pages
+struct FactNode {
+    hash: [u8; 32],
+    end_offset: usize,
+}
+
+/// Computes the root of the tree of output pages. `tree_structure` is a list of pairs
+/// `(n_pages, n_nodes)`: `n_pages` pages are pushed to a stack as leaves, then the last `n_nodes`
+/// nodes of the stack are replaced by their parent, whose hash is
+/// `1 + keccak(hash_0, end_offset_0, ..., hash_n, end_offset_n)`.
+/// (cairo-lang reference: `generate_output_root` in `compute_fact.py`)
+pub fn compute_output_hash(
+    output: &[Felt252],
+    fact_topology: &FactTopology,
+) -> Result<[u8; 32], FactTopologyError> {
+    let mut page_sizes = fact_topology.page_sizes.iter();
+    let mut offset = 0;
+    let mut node_stack: Vec<FactNode> = vec![];
+    for pair in fact_topology.tree_structure.chunks(2) {
+        let (n_pages, n_nodes) = match pair {
+            [n_pages, n_nodes] => (*n_pages, *n_nodes),
+            _ => {
+                return Err(invalid_fact_topology(
+                    "The tree structure must have an even length".to_string(),
+                ))
+            }
+        };
+
+        for _ in 0..n_pages {
+            let page_size = *page_sizes.next().ok_or_else(|| {
+                invalid_fact_topology("The tree structure has more pages than the output".into())
+            })?;
+            let page = output.get(offset..offset + page_size).ok_or_else(|| {
+                invalid_fact_topology("The pages exceed the size of the output".into())
+            })?;
+            offset += page_size;
+            node_stack.push(FactNode {
+                hash: keccak_words(page.iter().map(Felt252::to_bytes_be)),
+                end_offset: offset,
+            });
+        }
+
+        if n_nodes > 0 {
+            let children_start = node_stack.len().checked_sub(n_nodes).ok_or_else(|| {
+                invalid_fact_topology(format!("Not enough nodes to merge {n_nodes} of them"))
+            })?;
+            let children = node_stack.split_off(children_start);
+            let hash = keccak_words(
+                children
+                    .iter()
+                    .flat_map(|child| [child.hash, Felt252::from(child.end_offset).to_bytes_be()]),
+            );
+            node_stack.push(FactNode {
+                hash: increment(hash),
+                end_offset: offset,
+            });
+        }
+    }
+
+    if page_sizes.next().is_some() || offset != output.len() {
+        return Err(invalid_fact_topology(
+            "The pages of the fact topology must cover the entire output".into(),
+        ));
+    }
+    match node_stack.as_slice() {
+        [root] => Ok(root.hash),
+        _ => Err(invalid_fact_topology(format!(
+            "The tree structure must merge the pages into a single root, found {} nodes",
+            node_stack.len()
+        ))),
+    }
+}
+
+/// Computes the fact of a program from its hash and the root of its output tree
+pub fn compute_fact(program_hash: &Felt252, output_hash: &[u8; 32]) -> [u8; 32] {
+    keccak_words([program_hash.to_bytes_be(), *output_hash])
+}
+
+/// Computes the fact of a run of `program`, given its output and the additional data of its
+/// output builtin. The program hash is computed as done by the bootloader.
+pub fn compute_program_fact(
+    program: &StrippedProgram,
+    output: &[Felt252],
+    output_additional_data: &OutputBuiltinAdditionalData,
+) -> Result<ProgramFact, ProgramFactError> {
+    let program_hash = compute_program_hash_chain(program, 0)?;
+    let program_hash = Felt252::from_bytes_be(&program_hash.to_bytes_be());
+    let fact_topology =
+        get_fact_topology_from_additional_data(output.len(), output_additional_data)?;
+    let output_hash = compute_output_hash(output, &fact_topology)?;
+    Ok(ProgramFact {
+        fact: compute_fact(&program_hash, &output_hash),
+        program_hash,
+        output_hash,
+        fact_topology,
+    })
+}
+
+/// Computes the fact of the execution of a Cairo PIE
+pub fn compute_cairo_pie_fact(pie: &CairoPie) -> Result<ProgramFact, ProgramFactError> {
+    let mut additional_data = empty_additional_data();
+    let mut output = vec![];
+    if let Some(segment) = pie.metadata.builtin_segments.get(&BuiltinName::output) {
+        let segment_index = segment.index as usize;
+        let cells: HashMap<_, _> = pie
+            .memory
+            .0
+            .iter()
+            .filter(|((index, _), _)| *index == segment_index)
+            .map(|((_, offset), value)| (*offset, value))
+            .collect();
+        output = (0..segment.size)
+            .map(|offset| {
+                cells
+                    .get(&offset)
+                    .and_then(|value| value.get_int())
+                    .ok_or(ProgramFactError::InvalidOutputCell(offset))
+            })
+            .collect::<Result<_, _>>()?;
+        if let Some(BuiltinAdditionalData::Output(data)) =
+            pie.additional_data.0.get(&BuiltinName::output)
+        {
+            additional_data = data.clone();
+        }
+    }
+    compute_program_fact(&pie.metadata.program, &output, &additional_data)
+}
+
+/// Computes the fact of a finished run. The segments of the run must have their used sizes
+/// computed, which is done by [CairoRunner::end_run].
+pub fn compute_runner_fact(runner: &CairoRunner) -> Result<ProgramFact, ProgramFactError> {
+    let mut additional_data = empty_additional_data();
+    let mut output = vec![];
+    let output_builtin = runner
+        .vm
+        .builtin_runners
+        .iter()
+        .find_map(|builtin| match builtin {
+            BuiltinRunner::Output(output_builtin) => Some(output_builtin),
+            _ => None,
+        });
+    if let Some(output_builtin) = output_builtin {
+        let size = output_builtin.get_used_cells(&runner.vm.segments)?;
+        output = runner
+            .vm
+            .get_integer_range((output_builtin.base() as isize, 0).into(), size)?
+            .into_iter()
+            .map(|value| value.into_owned())
+            .collect();
+        if let BuiltinAdditionalData::Output(data) = output_builtin.get_additional_data() {
+            additional_data = data;
+        }
+    }
+    compute_program_fact(
+        &runner.get_program().get_stripped_program()?,
+        &output,
+        &additional_data,
+    )
+}
+
+fn empty_additional_data() -> OutputBuiltinAdditionalData {
+    OutputBuiltinAdditionalData {
+        pages: HashMap::new(),
+        attributes: HashMap::new(),
+    }
+}
+
+/// Hashes the concatenation of 32-byte big-endian words
+fn keccak_words(words: impl IntoIterator<Item = [u8; 32]>) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for word in words {
+        hasher.update(word);
+    }
+    hasher.finalize().into()
+}
+
+/// Adds one to a big-endian 256-bit hash. Python fails if the hash is `2**256 - 1`, which keccak
+/// doesn't output in practice, here it wraps around.
+fn increment(mut hash: [u8; 32]) -> [u8; 32] {
+    for byte in hash.iter_mut().rev() {
+        let (value, overflow) = byte.overflowing_add(1);
+        *byte = value;
+        if !overflow {
+            break;
+        }
+    }
+    hash
+}
+
+fn invalid_fact_topology(message: String) -> FactTopologyError {
+    FactTopologyError(message.into_boxed_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        utils::test_utils::{program_b, run_program_b},
+        vm::runners::cairo_pie::PublicMemoryPage,
+    };
+    use assert_matches::assert_matches;
+    use num_bigint::BigUint;
+
+    fn additional_data(pages: &[(usize, usize, usize)]) -> OutputBuiltinAdditionalData {
+        OutputBuiltinAdditionalData {
+            pages: pages
+                .iter()
+                .map(|(id, start, size)| {
+                    (
+                        *id,
+                        PublicMemoryPage {
+                            start: *start,
+                            size: *size,
+                        },
+                    )
+                })
+                .collect(),
+            attributes: HashMap::from([(GPS_FACT_TOPOLOGY.to_string(), vec![2, 1, 0, 2])]),
+        }
+    }
+
+    fn output() -> Vec<Felt252> {
+        (1..=7).map(Felt252::from).collect()
+    }
+
+    fn hex(hash: &[u8; 32]) -> String {
+        BigUint::from_bytes_be(hash).to_str_radix(16)
+    }
+
+    #[test]
+    fn fact_topology_without_pages() {
+        assert_eq!(
+            get_fact_topology_from_additional_data(5, &empty_additional_data()).unwrap(),
+            FactTopology {
+                tree_structure: vec![1, 0],
+                page_sizes: vec![5],
+            }
+        );
+    }
+
+    #[test]
+    fn fact_topology_with_pages() {
+        let data = additional_data(&[(2, 4, 3), (1, 1, 3)]);
+        assert_eq!(
+            get_fact_topology_from_additional_data(7, &data).unwrap(),
+            FactTopology {
+                tree_structure: vec![2, 1, 0, 2],
+                page_sizes: vec![1, 3, 3],
+            }
+        );
+    }
+
+    #[test]
+    fn fact_topology_invalid_pages() {
+        let gap = additional_data(&[(1, 1, 2), (2, 4, 3)]);
+        assert_matches!(get_fact_topology_from_additional_data(7, &gap), Err(_));
+        let uncovered = additional_data(&[(1, 1, 2)]);
+        assert_matches!(
+            get_fact_topology_from_additional_data(7, &uncovered),
+            Err(_)
+        );
+        let mut without_attribute = additional_data(&[(1, 1, 6)]);
+        without_attribute.attributes.clear();
+        assert_matches!(
+            get_fact_topology_from_additional_data(7, &without_attribute),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn output_hash_single_page() {
+        let fact_topology = FactTopology {
+            tree_structure: vec![1, 0],
+            page_sizes: vec![7],
+        };
+        let output_hash = compute_output_hash(&output(), &fact_topology).unwrap();
+        assert_eq!(
+            hex(&output_hash),
+            "dc0f375e737e67a9da47e3f3d3caafe3435f5bcf1bb0980453eedbd45cd33547"
+        );
+        assert_eq!(
+            hex(&compute_fact(&Felt252::from(0x1234), &output_hash)),
+            "425c137ccaa4403b6766e5ba6e17cf73f546a45f4e204e43b2e2fe4bef5e9bb2"
+        );
+    }
+
+    #[test]
+    fn output_hash_empty_output() {
+        let fact_topology = FactTopology {
+            tree_structure: vec![1, 0],
+            page_sizes: vec![0],
+        };
+        assert_eq!(
+            hex(&compute_output_hash(&[], &fact_topology).unwrap()),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+
+    #[test]
+    fn output_hash_with_pages() {
+        let fact_topology = FactTopology {
+            tree_structure: vec![2, 1, 1, 3],
+            page_sizes: vec![1, 3, 3],
+        };
+        let output_hash = compute_output_hash(&output(), &fact_topology).unwrap();
+        assert_eq!(
+            hex(&output_hash),
+            "25b068b8dd0042f1602a358c4a532ce1a3ff1b25506d44ea73ce8b2e007d9045"
+        );
+        assert_eq!(
+            hex(&compute_fact(&Felt252::from(0x1234), &output_hash)),
+            "90c8b301f98b22e3836bb9bfd252743efd2d17d13fc3002e38d03a86489b0813"
+        );
+    }
+
+    #[test]
+    fn output_hash_invalid_tree_structure() {
+        // Leaves two nodes in the stack
+        let unmerged = FactTopology {
+            tree_structure: vec![2, 0],
+            page_sizes: vec![1, 6],
+        };
+        assert_matches!(compute_output_hash(&output(), &unmerged), Err(_));
+        // Doesn't use the last page
+        let unused_page = FactTopology {
+            tree_structure: vec![2, 1, 0, 2],
+            page_sizes: vec![1, 3, 3],
+        };
+        assert_matches!(compute_output_hash(&output(), &unused_page), Err(_));
+        let too_many_pages = FactTopology {
+            tree_structure: vec![2, 2],
+            page_sizes: vec![7],
+        };
+        assert_matches!(compute_output_hash(&output(), &too_many_pages), Err(_));
+        let too_large_page = FactTopology {
+            tree_structure: vec![1, 0],
+            page_sizes: vec![8],
+        };
+        assert_matches!(compute_output_hash(&output(), &too_large_page), Err(_));
+    }
+
+    #[test]
+    fn runner_and_cairo_pie_facts_match() {
+        let runner = run_program_b(false, false);
+
+        let fact = compute_runner_fact(&runner).unwrap();
+        let output_size = fact.fact_topology.page_sizes[0];
+        assert_eq!(
+            fact.fact_topology,
+            FactTopology {
+                tree_structure: vec![1, 0],
+                page_sizes: vec![output_size],
+            }
+        );
+        let program_hash =
+            compute_program_hash_chain(&program_b().get_stripped_program().unwrap(), 0).unwrap();
+        assert_eq!(
+            fact.program_hash,
+            Felt252::from_bytes_be(&program_hash.to_bytes_be())
+        );
+        assert_eq!(
+            fact.fact,
+            compute_fact(&fact.program_hash, &fact.output_hash)
+        );
+        assert_eq!(
+            compute_cairo_pie_fact(&runner.get_cairo_pie().unwrap()).unwrap(),
+            fact
+        );
+    }
+}
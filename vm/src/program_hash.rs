@@ -1,7 +1,8 @@
-use starknet_crypto::{pedersen_hash, FieldElement};
+use starknet_crypto::{pedersen_hash, poseidon_hash_many, FieldElement};
 
 use crate::Felt252;
 
+use crate::hint_processor::builtin_hint_processor::blake2s_hash::{blake2s_compress, IV};
 use crate::stdlib::vec::Vec;
 use crate::types::builtin_name::BuiltinName;
 use crate::types::relocatable::MaybeRelocatable;
@@ -84,49 +85,130 @@ fn maybe_relocatable_to_field_element(
     felt_to_field_element(felt)
 }
 
+/// Hash function used to compute the hash of a program
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(all(feature = "clap", feature = "std"), derive(clap::ValueEnum))]
+pub enum ProgramHashFunction {
+    /// Pedersen hash chain over the program, prefixed by its length
+    #[default]
+    Pedersen,
+    /// Poseidon hash of the program
+    Poseidon,
+    /// Blake2s hash of the program encoded as u32 words, as done for Starknet compiled classes
+    Blake2s,
+}
+
+/// Returns the values hashed to compute the program hash: the program header, without the length
+/// of the data, followed by the builtins and the bytecode of the program
+fn program_data_chain(
+    program: &StrippedProgram,
+    bootloader_version: usize,
+) -> Result<Vec<FieldElement>, ProgramHashError> {
+    let program_header = [
+        FieldElement::from(bootloader_version),
+        FieldElement::from(program.main),
+        FieldElement::from(program.builtins.len()),
+    ];
+
+    let mut data_chain = program_header.to_vec();
+    for builtin in program.builtins.iter() {
+        data_chain.push(builtin_name_to_field_element(builtin)?);
+    }
+    for value in program.data.iter() {
+        data_chain.push(maybe_relocatable_to_field_element(value)?);
+    }
+    Ok(data_chain)
+}
+
 /// Computes the Pedersen hash of a program.
 /// [(cairo_lang reference)](https://github.com/starkware-libs/cairo-lang/blob/efa9648f57568aad8f8a13fbf027d2de7c63c2c0/src/starkware/cairo/bootloaders/hash_program.py#L11)
 pub fn compute_program_hash_chain(
     program: &StrippedProgram,
     bootloader_version: usize,
 ) -> Result<FieldElement, ProgramHashError> {
-    let program_main = program.main;
-    let program_main = FieldElement::from(program_main);
-
-    // Convert builtin names to field elements
-    let builtin_list: Result<Vec<FieldElement>, _> = program
-        .builtins
-        .iter()
-        .map(builtin_name_to_field_element)
-        .collect();
-    let builtin_list = builtin_list?;
-
-    let program_header = vec![
-        FieldElement::from(bootloader_version),
-        program_main,
-        FieldElement::from(program.builtins.len()),
-    ];
+    compute_program_hash(program, ProgramHashFunction::Pedersen, bootloader_version)
+}
 
-    let program_data: Result<Vec<_>, _> = program
-        .data
-        .iter()
-        .map(maybe_relocatable_to_field_element)
-        .collect();
-    let program_data = program_data?;
-
-    let data_chain_len = program_header.len() + builtin_list.len() + program_data.len();
-    let data_chain_len_vec = vec![FieldElement::from(data_chain_len)];
-
-    // Prepare a chain of iterators to feed to the hash function
-    let data_chain = [
-        &data_chain_len_vec,
-        &program_header,
-        &builtin_list,
-        &program_data,
-    ];
+/// Computes the hash of a program with the given hash function.
+/// [(cairo_lang reference)](https://github.com/starkware-libs/cairo-lang/blob/v0.13.2/src/starkware/cairo/bootloaders/hash_program.py#L11)
+pub fn compute_program_hash(
+    program: &StrippedProgram,
+    hash_function: ProgramHashFunction,
+    bootloader_version: usize,
+) -> Result<FieldElement, ProgramHashError> {
+    let data_chain = program_data_chain(program, bootloader_version)?;
+    match hash_function {
+        ProgramHashFunction::Pedersen => {
+            let data_chain_len = [FieldElement::from(data_chain.len())];
+            Ok(compute_hash_chain(
+                data_chain_len.iter().chain(data_chain.iter()),
+                pedersen_hash,
+            )?)
+        }
+        ProgramHashFunction::Poseidon => Ok(poseidon_hash_many(&data_chain)),
+        ProgramHashFunction::Blake2s => {
+            let hash = blake2s_digest(&encode_felts_to_u32s(&data_chain));
+            // The hash is read as a little-endian integer, reduced modulo the prime
+            felt_to_field_element(&Felt252::from_bytes_le(&hash))
+        }
+    }
+}
+
+/// Encodes field elements into u32 words: values below 2**63 take 2 words, and bigger ones take
+/// 8 words with the most significant bit of the first one set.
+/// [(cairo_lang reference)](https://github.com/starkware-libs/cairo-lang/blob/v0.14.0/src/starkware/cairo/common/cairo_blake2s/blake2s_utils.py)
+fn encode_felts_to_u32s(felts: &[FieldElement]) -> Vec<u32> {
+    let mut words = Vec::new();
+    for felt in felts {
+        let bytes = felt.to_bytes_be();
+        let limbs = bytes
+            .chunks(4)
+            .map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+        if bytes[..24].iter().all(|byte| *byte == 0) && bytes[24] < 0x80 {
+            words.extend(limbs.skip(6));
+        } else {
+            let start = words.len();
+            words.extend(limbs);
+            words[start] |= 1 << 31;
+        }
+    }
+    words
+}
+
+/// Computes the Blake2s-256 hash of the little-endian encoding of `words`
+fn blake2s_digest(words: &[u32]) -> [u8; 32] {
+    // Parameter block of an unkeyed hash with a 32 byte output
+    let mut state = IV;
+    state[0] ^= 0x01010020;
+
+    // The last block is compressed with the finalization flag, even if the input is empty
+    let blocks: Vec<&[u32]> = if words.is_empty() {
+        vec![&[]]
+    } else {
+        words.chunks(16).collect()
+    };
+    let mut counter = 0_u64;
+    for (index, block) in blocks.iter().enumerate() {
+        let mut message = [0; 16];
+        message[..block.len()].copy_from_slice(block);
+        counter += block.len() as u64 * 4;
+        let is_last_block = index == blocks.len() - 1;
+        let new_state = blake2s_compress(
+            &state,
+            &message,
+            counter as u32,
+            (counter >> 32) as u32,
+            if is_last_block { u32::MAX } else { 0 },
+            0,
+        );
+        state.copy_from_slice(&new_state);
+    }
 
-    let hash = compute_hash_chain(data_chain.iter().flat_map(|&v| v.iter()), pedersen_hash)?;
-    Ok(hash)
+    let mut digest = [0; 32];
+    for (bytes, word) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
 }
 
 #[cfg(test)]
@@ -137,6 +219,8 @@ mod tests {
     use starknet_crypto::pedersen_hash;
 
     use super::*;
+    use crate::stdlib::prelude::*;
+    use assert_matches::assert_matches;
 
     #[test]
     fn test_compute_hash_chain() {
@@ -155,6 +239,129 @@ mod tests {
         assert_eq!(computed_hash, expected_hash);
     }
 
+    fn stripped_program() -> StrippedProgram {
+        StrippedProgram {
+            data: [
+                Felt252::from(10),
+                Felt252::from(20),
+                Felt252::from(1_u64 << 63),
+                Felt252::MAX,
+            ]
+            .into_iter()
+            .map(MaybeRelocatable::from)
+            .collect(),
+            builtins: vec![BuiltinName::output, BuiltinName::pedersen],
+            main: 1,
+            prime: (),
+        }
+    }
+
+    #[test]
+    fn test_blake2s_digest() {
+        // Expected digest of the bytes "abc\0" computed with `hashlib.blake2s`
+        assert_eq!(
+            blake2s_digest(&[0x636261]),
+            [
+                0x04, 0xe0, 0x58, 0x8b, 0x83, 0xbf, 0x60, 0x65, 0xda, 0x68, 0x9f, 0x76, 0xde, 0xfe,
+                0x21, 0x4c, 0xf3, 0x99, 0x7f, 0xd7, 0x58, 0x4e, 0xca, 0xa5, 0xe3, 0x0d, 0xc0, 0xeb,
+                0x34, 0x52, 0x77, 0x73
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encode_felts_to_u32s() {
+        let felts = [
+            FieldElement::from(0x1_0000_0002_u64),
+            FieldElement::from((1_u64 << 63) + 3),
+        ];
+        assert_eq!(
+            encode_felts_to_u32s(&felts),
+            vec![1, 2, 1 << 31, 0, 0, 0, 0, 0, 1 << 31, 3]
+        );
+    }
+
+    #[test]
+    fn test_compute_program_hash_pedersen() {
+        let program = stripped_program();
+        let data_chain = program_data_chain(&program, 0).unwrap();
+        let expected_hash = compute_hash_chain(
+            [FieldElement::from(9_u64)].iter().chain(data_chain.iter()),
+            pedersen_hash,
+        )
+        .unwrap();
+        assert_eq!(
+            compute_program_hash(&program, ProgramHashFunction::Pedersen, 0).unwrap(),
+            expected_hash
+        );
+        assert_eq!(
+            compute_program_hash_chain(&program, 0).unwrap(),
+            expected_hash
+        );
+    }
+
+    #[test]
+    fn test_compute_program_hash_poseidon() {
+        // Expected hashes computed with a Python port of cairo-lang's `poseidon_hash_many`, checked
+        // against the hashes of cairo_programs/poseidon_hash.cairo
+        let program = stripped_program();
+        assert_eq!(
+            format!(
+                "{:#x}",
+                compute_program_hash(&program, ProgramHashFunction::Poseidon, 0).unwrap()
+            ),
+            "0x4d55ab06ab67d94f2a509063743f5337c905715351a964e46648f28d1acb746"
+        );
+        // The bootloader version is part of the header
+        assert_eq!(
+            format!(
+                "{:#x}",
+                compute_program_hash(&program, ProgramHashFunction::Poseidon, 1).unwrap()
+            ),
+            "0x256fe47e66208161fdbeea60aa2206e3ac1e23279ec9bb84dfc4b97d3c40c93"
+        );
+    }
+
+    #[test]
+    fn test_compute_program_hash_blake2s() {
+        // Expected hashes computed with `hashlib.blake2s` over the encoding of cairo-lang
+        let program_hash =
+            compute_program_hash(&stripped_program(), ProgramHashFunction::Blake2s, 0).unwrap();
+        assert_eq!(
+            format!("{:#x}", program_hash),
+            "0x6b2984f79b656e34d42fbe76bf37d4a5122d2aa65b9bc42c3b3526afeb265e3"
+        );
+
+        // The encoding of the program takes several blocks
+        let program = StrippedProgram {
+            data: (0..100).map(|value| Felt252::from(value).into()).collect(),
+            builtins: vec![],
+            main: 1,
+            prime: (),
+        };
+        let program_hash = compute_program_hash(&program, ProgramHashFunction::Blake2s, 0).unwrap();
+        assert_eq!(
+            format!("{:#x}", program_hash),
+            "0x9eebb590afecc5d7b095a06c5b571ffc09ab1ab917164a01d6fecd1e281a0c"
+        );
+    }
+
+    #[test]
+    fn test_compute_program_hash_relocatable_data() {
+        let mut program = stripped_program();
+        program.data.push(MaybeRelocatable::from((1, 0)));
+        for hash_function in [
+            ProgramHashFunction::Pedersen,
+            ProgramHashFunction::Poseidon,
+            ProgramHashFunction::Blake2s,
+        ] {
+            assert_matches!(
+                compute_program_hash(&program, hash_function, 0),
+                Err(ProgramHashError::InvalidProgramData)
+            );
+        }
+    }
+
     #[cfg(feature = "std")]
     #[rstest]
     // Expected hashes generated with `cairo-hash-program`
@@ -187,4 +394,41 @@ mod tests {
 
         assert_eq!(program_hash_hex, expected_program_hash);
     }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    // Expected hashes computed with a Python port of cairo-lang's `compute_program_hash_chain`, using
+    // `poseidon_hash_many` and `encode_felt252_data_and_calc_blake_hash` (`hashlib.blake2s`)
+    #[case::valid_program_a_poseidon(
+        "../cairo_programs/manually_compiled/valid_program_a.json",
+        ProgramHashFunction::Poseidon,
+        "0x70a4f1f4e885daedffdd6afa5e5010fc34be2d59fbedb6c80c1eb9a15ad30c3"
+    )]
+    #[case::valid_program_a_blake2s(
+        "../cairo_programs/manually_compiled/valid_program_a.json",
+        ProgramHashFunction::Blake2s,
+        "0xace3fad79aa9ec8491b9327e895b41e4c357af15ed5a966fad6304fad25fd3"
+    )]
+    #[case::valid_program_b_poseidon(
+        "../cairo_programs/manually_compiled/valid_program_b.json",
+        ProgramHashFunction::Poseidon,
+        "0x14104d0125ad308d6310986012e5c9b119a4d360a3d124ab18539fe96b1724f"
+    )]
+    #[case::valid_program_b_blake2s(
+        "../cairo_programs/manually_compiled/valid_program_b.json",
+        ProgramHashFunction::Blake2s,
+        "0x4b8f1d6b6e9308073480eca3fd37dbf5bd9d55e6e6f16c0d1c9eee50865613c"
+    )]
+    fn test_compute_program_hash_of_program(
+        #[case] program_path: PathBuf,
+        #[case] hash_function: ProgramHashFunction,
+        #[case] expected_program_hash: &str,
+    ) {
+        let program = Program::from_file(program_path.as_path(), Some("main")).unwrap();
+        let stripped_program = program.get_stripped_program().unwrap();
+
+        let program_hash = compute_program_hash(&stripped_program, hash_function, 0).unwrap();
+
+        assert_eq!(format!("{:#x}", program_hash), expected_program_hash);
+    }
 }
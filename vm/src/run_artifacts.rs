@@ -0,0 +1,278 @@
+//! Single compressed container for the artifacts of a proof mode run, replacing the separate
+//! trace, memory and AIR input files.
+//!
+//! The bundle is a zip archive with the following entries, all of them deflated:
+//! * `version.json`: version of the bundle format, see [RUN_ARTIFACTS_VERSION]
+//! * `trace.bin`: relocated trace, in the format of [write_encoded_trace]
+//! * `memory.bin`: relocated memory, in the format of [write_encoded_memory]
+//! * `air_public_input.json`: AIR public input
+//! * `air_private_input.json`: AIR private input, with `trace.bin` and `memory.bin` as its paths
+//! * `layout_params.json`: parameters of the `dynamic` layout, only present for that layout
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek, Write},
+    path::Path,
+};
+
+use bincode::enc::write::Writer;
+use serde::{Deserialize, Serialize};
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    air_private_input::AirPrivateInputSerializable,
+    air_public_input::{PublicInput, PublicInputError},
+    cairo_run::{
+        read_encoded_memory, read_encoded_trace, write_encoded_memory, write_encoded_trace,
+    },
+    types::layout::CairoLayoutParams,
+    vm::{runners::cairo_runner::CairoRunner, trace::trace_entry::RelocatedTraceEntry},
+    Felt252,
+};
+
+/// Version of the bundle format written by [RunArtifacts::write_zip]
+pub const RUN_ARTIFACTS_VERSION: u32 = 1;
+
+const VERSION_ENTRY: &str = "version.json";
+const TRACE_ENTRY: &str = "trace.bin";
+const MEMORY_ENTRY: &str = "memory.bin";
+const AIR_PUBLIC_INPUT_ENTRY: &str = "air_public_input.json";
+const AIR_PRIVATE_INPUT_ENTRY: &str = "air_private_input.json";
+const LAYOUT_PARAMS_ENTRY: &str = "layout_params.json";
+
+#[derive(Serialize, Deserialize)]
+struct BundleVersion {
+    version: u32,
+}
+
+/// Artifacts of a proof mode run, stored together in a single compressed bundle
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunArtifacts {
+    pub relocated_trace: Vec<RelocatedTraceEntry>,
+    pub relocated_memory: Vec<Option<Felt252>>,
+    /// AIR public input serialized as JSON, see [RunArtifacts::air_public_input]
+    air_public_input: String,
+    pub air_private_input: AirPrivateInputSerializable,
+    /// Parameters of the `dynamic` layout, `None` for the other layouts
+    pub layout_params: Option<CairoLayoutParams>,
+}
+
+impl RunArtifacts {
+    /// Collects the artifacts of a finished run.
+    /// The run must have been performed with the trace enabled, and its trace and memory must
+    /// have been relocated.
+    pub fn from_runner(runner: &CairoRunner) -> Result<Self, PublicInputError> {
+        let air_public_input = serde_json::to_string(&runner.get_air_public_input()?)?;
+        Ok(RunArtifacts {
+            relocated_trace: runner
+                .relocated_trace
+                .clone()
+                .ok_or(PublicInputError::EmptyTrace)?,
+            relocated_memory: {
+                // Trailing empty cells aren't stored in the bundle
+                let len = runner.relocated_memory.iter().rposition(Option::is_some);
+                runner.relocated_memory[..len.map_or(1, |i| i + 1)].to_vec()
+            },
+            air_public_input,
+            air_private_input: runner
+                .get_air_private_input()
+                .to_serializable(TRACE_ENTRY.to_string(), MEMORY_ENTRY.to_string()),
+            layout_params: runner.layout.dynamic_layout_params.clone(),
+        })
+    }
+
+    /// Returns the AIR public input of the run, borrowing its strings from the bundle
+    pub fn air_public_input(&self) -> Result<PublicInput<'_>, PublicInputError> {
+        Ok(serde_json::from_str(&self.air_public_input)?)
+    }
+
+    /// Writes the bundle as a zip archive
+    pub fn write_zip<W: Write + Seek>(&self, writer: W) -> Result<(), io::Error> {
+        let mut zip_writer = ZipWriter::new(writer);
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        zip_writer.start_file(VERSION_ENTRY, options)?;
+        serde_json::to_writer(
+            &mut zip_writer,
+            &BundleVersion {
+                version: RUN_ARTIFACTS_VERSION,
+            },
+        )?;
+        zip_writer.start_file(TRACE_ENTRY, options)?;
+        write_encoded_trace(&self.relocated_trace, &mut EntryWriter(&mut zip_writer))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        zip_writer.start_file(MEMORY_ENTRY, options)?;
+        write_encoded_memory(&self.relocated_memory, &mut EntryWriter(&mut zip_writer))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        zip_writer.start_file(AIR_PUBLIC_INPUT_ENTRY, options)?;
+        zip_writer.write_all(self.air_public_input.as_bytes())?;
+        zip_writer.start_file(AIR_PRIVATE_INPUT_ENTRY, options)?;
+        serde_json::to_writer(&mut zip_writer, &self.air_private_input)?;
+        if let Some(layout_params) = &self.layout_params {
+            zip_writer.start_file(LAYOUT_PARAMS_ENTRY, options)?;
+            serde_json::to_writer(&mut zip_writer, layout_params)?;
+        }
+        zip_writer.finish()?;
+        Ok(())
+    }
+
+    pub fn write_zip_file(&self, file_path: &Path) -> Result<(), io::Error> {
+        self.write_zip(File::create(file_path)?)
+    }
+
+    /// Reads a bundle written by [RunArtifacts::write_zip].
+    /// Fails if the bundle was written with a different version of the format.
+    pub fn from_zip_archive<R: Read + Seek>(
+        mut zip_reader: ZipArchive<R>,
+    ) -> Result<Self, io::Error> {
+        let version: BundleVersion =
+            serde_json::from_reader(io::BufReader::new(zip_reader.by_name(VERSION_ENTRY)?))?;
+        if version.version != RUN_ARTIFACTS_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported run artifacts version {}, expected {RUN_ARTIFACTS_VERSION}",
+                version.version
+            )));
+        }
+
+        let mut trace = Vec::new();
+        zip_reader.by_name(TRACE_ENTRY)?.read_to_end(&mut trace)?;
+        let relocated_trace = decode_trace(&trace)?;
+
+        let mut memory = Vec::new();
+        zip_reader.by_name(MEMORY_ENTRY)?.read_to_end(&mut memory)?;
+        let relocated_memory = decode_memory(&memory)?;
+
+        let mut air_public_input = String::new();
+        zip_reader
+            .by_name(AIR_PUBLIC_INPUT_ENTRY)?
+            .read_to_string(&mut air_public_input)?;
+
+        let air_private_input = serde_json::from_reader(io::BufReader::new(
+            zip_reader.by_name(AIR_PRIVATE_INPUT_ENTRY)?,
+        ))?;
+
+        let layout_params = match zip_reader.by_name(LAYOUT_PARAMS_ENTRY) {
+            Ok(entry) => Some(serde_json::from_reader(io::BufReader::new(entry))?),
+            Err(zip::result::ZipError::FileNotFound) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(RunArtifacts {
+            relocated_trace,
+            relocated_memory,
+            air_public_input,
+            air_private_input,
+            layout_params,
+        })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, io::Error> {
+        Self::from_zip_archive(ZipArchive::new(io::Cursor::new(bytes))?)
+    }
+
+    pub fn read_zip_file(file_path: &Path) -> Result<Self, io::Error> {
+        Self::from_zip_archive(ZipArchive::new(File::open(file_path)?)?)
+    }
+}
+
+/// Adapts a zip entry to the [Writer] used by the trace and memory encoders
+struct EntryWriter<'a, W: Write + Seek>(&'a mut ZipWriter<W>);
+
+impl<'a, W: Write + Seek> Writer for EntryWriter<'a, W> {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), bincode::error::EncodeError> {
+        self.0
+            .write_all(bytes)
+            .map_err(|inner| bincode::error::EncodeError::Io { inner, index: 0 })
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn decode_trace(bytes: &[u8]) -> Result<Vec<RelocatedTraceEntry>, io::Error> {
+    read_encoded_trace(bytes).ok_or_else(|| invalid_data(format!("truncated {TRACE_ENTRY}")))
+}
+
+fn decode_memory(bytes: &[u8]) -> Result<Vec<Option<Felt252>>, io::Error> {
+    read_encoded_memory(bytes).ok_or_else(|| invalid_data(format!("truncated {MEMORY_ENTRY}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cairo_run::{cairo_run_program, CairoRunConfig},
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::layout_name::LayoutName,
+        utils::test_utils::program_b,
+    };
+    use assert_matches::assert_matches;
+
+    fn run_artifacts(layout: LayoutName, layout_params: Option<CairoLayoutParams>) -> RunArtifacts {
+        let config = CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            layout,
+            dynamic_layout_params: layout_params,
+            ..Default::default()
+        };
+        let runner = cairo_run_program(
+            &program_b(),
+            &config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        RunArtifacts::from_runner(&runner).unwrap()
+    }
+
+    #[test]
+    fn write_and_read_bundle() {
+        let artifacts = run_artifacts(LayoutName::small, None);
+        let mut bundle = io::Cursor::new(Vec::new());
+        artifacts.write_zip(&mut bundle).unwrap();
+        let read_artifacts = RunArtifacts::from_bytes(bundle.get_ref()).unwrap();
+        assert_eq!(read_artifacts, artifacts);
+
+        let public_input = read_artifacts.air_public_input().unwrap();
+        assert_eq!(public_input.layout, "small");
+        assert_eq!(public_input.n_steps, artifacts.relocated_trace.len());
+        assert!(public_input.dynamic_params.is_none());
+    }
+
+    #[test]
+    fn write_and_read_bundle_with_layout_params() {
+        let layout_params =
+            CairoLayoutParams::from_file(Path::new("src/tests/cairo_layout_params_file.json"))
+                .unwrap();
+        let artifacts = run_artifacts(LayoutName::dynamic, Some(layout_params.clone()));
+        let file_path = std::env::temp_dir().join("run_artifacts_dynamic.zip");
+        artifacts.write_zip_file(&file_path).unwrap();
+        let read_artifacts = RunArtifacts::read_zip_file(&file_path).unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+        assert_eq!(read_artifacts.layout_params, Some(layout_params));
+        assert_eq!(read_artifacts, artifacts);
+    }
+
+    #[test]
+    fn read_bundle_with_other_version() {
+        let mut bundle = io::Cursor::new(Vec::new());
+        let mut zip_writer = ZipWriter::new(&mut bundle);
+        zip_writer
+            .start_file(VERSION_ENTRY, FileOptions::default())
+            .unwrap();
+        zip_writer.write_all(br#"{"version": 2}"#).unwrap();
+        zip_writer.finish().unwrap();
+        drop(zip_writer);
+        assert_matches!(
+            RunArtifacts::from_bytes(bundle.get_ref()),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn decode_truncated_entries() {
+        assert!(decode_trace(&[0; 25]).is_err());
+        assert!(decode_memory(&[0; 39]).is_err());
+        assert_eq!(decode_memory(&[]).unwrap(), [None]);
+    }
+}
@@ -181,6 +181,10 @@ impl DebugInfo {
 pub struct InstructionLocation {
     pub inst: Location,
     pub hints: Vec<HintLocation>,
+    #[serde(default)]
+    pub accessible_scopes: Vec<String>,
+    #[serde(default)]
+    pub flow_tracking_data: Option<FlowTrackingData>,
 }
 
 #[cfg_attr(feature = "test_utils", derive(Arbitrary))]
@@ -1316,6 +1320,14 @@ mod tests {
                             start_col: 5,
                         },
                         hints: vec![],
+                        accessible_scopes: vec![
+                            String::from("starkware.cairo.lang.compiler.lib.registers"),
+                            String::from("starkware.cairo.lang.compiler.lib.registers.get_fp_and_pc"),
+                        ],
+                        flow_tracking_data: Some(FlowTrackingData {
+                            ap_tracking: ApTracking { group: 0, offset: 0 },
+                            reference_ids: HashMap::new(),
+                        }),
                     },
                 ),
                 (
@@ -1330,6 +1342,14 @@ mod tests {
                             start_col: 5,
                         },
                         hints: vec![],
+                        accessible_scopes: vec![
+                            String::from("starkware.cairo.common.alloc"),
+                            String::from("starkware.cairo.common.alloc.alloc"),
+                        ],
+                        flow_tracking_data: Some(FlowTrackingData {
+                            ap_tracking: ApTracking { group: 1, offset: 1 },
+                            reference_ids: HashMap::new(),
+                        }),
                     },
                 ),
             ]),
@@ -1432,6 +1452,12 @@ mod tests {
                         }), String::from( "While expanding the reference 'syscall_ptr' in:"))
                     ), start_line: 9, start_col: 18 },
                     hints: vec![],
+                    accessible_scopes: vec![
+                        String::from("__main__"),
+                        String::from("__main__"),
+                        String::from("__main__.constructor"),
+                    ],
+                    flow_tracking_data: None,
                 }),
             ]
         ) };
@@ -39,6 +39,9 @@ mod pedersen_test;
 mod struct_test;
 
 mod cairo_pie_test;
+// Programs with hints can only be run as tasks with the `extensive_hints` feature
+#[cfg(all(feature = "std", feature = "extensive_hints"))]
+mod simple_bootloader_test;
 #[cfg(feature = "test_utils")]
 mod skip_instruction_test;
 
@@ -0,0 +1,131 @@
+use crate::{
+    cairo_run::{cairo_run, cairo_run_program_with_initial_scope, CairoRunConfig},
+    felt_hex,
+    hint_processor::builtin_hint_processor::{
+        bootloader::types::{SimpleBootloaderInput, Task},
+        builtin_hint_processor_definition::BuiltinHintProcessor,
+    },
+    program_fact::FactTopology,
+    program_hash::compute_program_hash_chain,
+    stdlib::collections::HashMap,
+    types::{
+        exec_scope::ExecutionScopes, layout_name::LayoutName, program::Program,
+        relocatable::Relocatable,
+    },
+    vm::runners::cairo_pie::PublicMemoryPage,
+    Felt252,
+};
+
+#[test]
+fn simple_bootloader_runs_pie_and_program_tasks() {
+    // The Cairo PIE task is loaded into memory without running its hints
+    let pie = cairo_run(
+        include_bytes!("../../../cairo_programs/pedersen_test.json"),
+        &CairoRunConfig {
+            layout: LayoutName::all_cairo,
+            ..Default::default()
+        },
+        &mut BuiltinHintProcessor::new_empty(),
+    )
+    .unwrap()
+    .get_cairo_pie()
+    .unwrap();
+    // The hints of the program task are loaded along with the program when it is called
+    let program = Program::from_bytes(
+        include_bytes!("../../../cairo_programs/signed_div_rem.json"),
+        Some("main"),
+    )
+    .unwrap();
+    let tasks = vec![Task::Pie(Box::new(pie)), Task::Program(program)];
+    let program_hashes: Vec<Felt252> = tasks
+        .iter()
+        .map(|task| {
+            let program_hash = compute_program_hash_chain(&task.get_program().unwrap(), 0).unwrap();
+            Felt252::from_bytes_be(&program_hash.to_bytes_be())
+        })
+        .collect();
+
+    let fact_topologies_path = std::env::temp_dir().join("simple_bootloader_fact_topologies.json");
+    let mut exec_scopes = ExecutionScopes::new();
+    exec_scopes.insert_value(
+        "program_input",
+        SimpleBootloaderInput {
+            tasks,
+            fact_topologies_path: Some(fact_topologies_path.clone()),
+            single_page: false,
+        },
+    );
+    // The bootloader JSON isn't checked in, it must be compiled first with
+    // `make cairo_bootloader_programs` (or `make test-extensive_hints`)
+    let bootloader = Program::from_bytes(
+        include_bytes!("../../../cairo_programs/bootloaders/simple_bootloader.json"),
+        Some("main"),
+    )
+    .unwrap();
+    let mut runner = cairo_run_program_with_initial_scope(
+        &bootloader,
+        &CairoRunConfig {
+            layout: LayoutName::starknet_with_keccak,
+            ..Default::default()
+        },
+        &mut BuiltinHintProcessor::new_empty(),
+        exec_scopes,
+    )
+    .unwrap();
+
+    // The number of tasks, followed by the output size (including this header) and program hash
+    // of each task and its output
+    let output_builtin = runner.vm.get_output_builtin_mut().unwrap();
+    let output_base = output_builtin.base();
+    let pages = output_builtin.get_state().pages;
+    let output: Vec<Felt252> = runner
+        .vm
+        .get_integer_range(Relocatable::from((output_base as isize, 0)), 10)
+        .unwrap()
+        .into_iter()
+        .map(|value| value.into_owned())
+        .collect();
+    assert_eq!(
+        output,
+        [
+            Felt252::from(2),
+            Felt252::from(3),
+            program_hashes[0],
+            felt_hex!("0x49ee3eba8c1600700ee1b87eb599f16716b0b1022947733551fde4050ca6804"),
+            Felt252::from(6),
+            program_hashes[1],
+            Felt252::from(-4),
+            Felt252::from(-4),
+            Felt252::from(2),
+            Felt252::from(2),
+        ]
+    );
+
+    // Each task output is a page, as done by `configure_fact_topologies` in cairo-lang
+    assert_eq!(
+        pages,
+        HashMap::from([
+            (1, PublicMemoryPage { start: 3, size: 1 }),
+            (2, PublicMemoryPage { start: 6, size: 4 }),
+        ])
+    );
+
+    // Tasks without pages have a single page holding their whole output
+    let fact_topologies: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(fact_topologies_path).unwrap()).unwrap();
+    let fact_topologies: Vec<FactTopology> =
+        serde_json::from_value(fact_topologies["fact_topologies"].clone()).unwrap();
+    assert_eq!(
+        fact_topologies,
+        [
+            FactTopology {
+                tree_structure: vec![1, 0],
+                page_sizes: vec![1],
+            },
+            FactTopology {
+                tree_structure: vec![1, 0],
+                page_sizes: vec![4],
+            },
+        ]
+    );
+}
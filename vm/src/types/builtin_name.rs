@@ -1,7 +1,10 @@
-use serde::{Deserialize, Serialize};
+use crate::stdlib::{borrow::Cow, prelude::*};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 
 #[cfg(feature = "test_utils")]
 use arbitrary::{self, Arbitrary};
+#[cfg(feature = "std")]
+use std::sync::{PoisonError, RwLock};
 
 // Internal constants
 const OUTPUT_BUILTIN_NAME: &str = "output";
@@ -31,8 +34,7 @@ const ADD_MOD_BUILTIN_NAME_WITH_SUFFIX: &str = "add_mod_builtin";
 const MUL_MOD_BUILTIN_NAME_WITH_SUFFIX: &str = "mul_mod_builtin";
 
 /// Enum representing the name of a cairo builtin
-#[cfg_attr(feature = "test_utils", derive(Arbitrary))]
-#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash, PartialOrd, Ord)]
 #[allow(non_camel_case_types)]
 pub enum BuiltinName {
     output,
@@ -47,8 +49,64 @@ pub enum BuiltinName {
     range_check96,
     add_mod,
     mul_mod,
+    /// A builtin implemented outside of the VM, see
+    /// [CustomBuiltin](crate::vm::runners::builtin_runner::CustomBuiltin)
+    custom(&'static CustomBuiltinName),
 }
 
+/// Name of a builtin implemented outside of the VM
+///
+/// Names of custom builtins have to be registered with [BuiltinName::register_custom], which
+/// [CairoRunner::add_custom_builtin](crate::vm::runners::cairo_runner::CairoRunner::add_custom_builtin)
+/// also does, before loading programs or Cairo PIEs which use them.
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash, PartialOrd, Ord)]
+pub struct CustomBuiltinName {
+    name: &'static str,
+    name_with_suffix: &'static str,
+}
+
+impl CustomBuiltinName {
+    /// Creates the name of a custom builtin from its string representation, with and without the
+    /// "_builtin" suffix
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use cairo_vm::types::builtin_name::{BuiltinName, CustomBuiltinName};
+    ///
+    /// static SQUARE: CustomBuiltinName = CustomBuiltinName::new("square", "square_builtin");
+    /// assert_eq!(BuiltinName::custom(&SQUARE).to_str(), "square");
+    ///
+    /// ```
+    pub const fn new(name: &'static str, name_with_suffix: &'static str) -> Self {
+        CustomBuiltinName {
+            name,
+            name_with_suffix,
+        }
+    }
+}
+
+/// Names of the custom builtins that can be parsed from their string representation.
+/// The registry is shared by the whole process, and names are never removed from it.
+#[cfg(feature = "std")]
+static CUSTOM_BUILTIN_NAMES: RwLock<Vec<&'static CustomBuiltinName>> = RwLock::new(Vec::new());
+
+/// String representations of every builtin name known by the VM
+const BUILTIN_NAMES: &[&str] = &[
+    OUTPUT_BUILTIN_NAME,
+    RANGE_CHECK_BUILTIN_NAME,
+    HASH_BUILTIN_NAME,
+    SIGNATURE_BUILTIN_NAME,
+    KECCAK_BUILTIN_NAME,
+    BITWISE_BUILTIN_NAME,
+    EC_OP_BUILTIN_NAME,
+    POSEIDON_BUILTIN_NAME,
+    SEGMENT_ARENA_BUILTIN_NAME,
+    RANGE_CHECK_96_BUILTIN_NAME,
+    ADD_MOD_BUILTIN_NAME,
+    MUL_MOD_BUILTIN_NAME,
+];
+
 impl BuiltinName {
     /// Converts a [`BuiltinName`] to its string representation adding the "_builtin" suffix
     ///
@@ -75,6 +133,7 @@ impl BuiltinName {
             BuiltinName::range_check96 => RANGE_CHECK_96_BUILTIN_NAME_WITH_SUFFIX,
             BuiltinName::add_mod => ADD_MOD_BUILTIN_NAME_WITH_SUFFIX,
             BuiltinName::mul_mod => MUL_MOD_BUILTIN_NAME_WITH_SUFFIX,
+            BuiltinName::custom(name) => name.name_with_suffix,
         }
     }
 
@@ -103,6 +162,7 @@ impl BuiltinName {
             BuiltinName::range_check96 => RANGE_CHECK_96_BUILTIN_NAME,
             BuiltinName::add_mod => ADD_MOD_BUILTIN_NAME,
             BuiltinName::mul_mod => MUL_MOD_BUILTIN_NAME,
+            BuiltinName::custom(name) => name.name,
         }
     }
 
@@ -132,7 +192,7 @@ impl BuiltinName {
             RANGE_CHECK_96_BUILTIN_NAME_WITH_SUFFIX => Some(BuiltinName::range_check96),
             ADD_MOD_BUILTIN_NAME_WITH_SUFFIX => Some(BuiltinName::add_mod),
             MUL_MOD_BUILTIN_NAME_WITH_SUFFIX => Some(BuiltinName::mul_mod),
-            _ => None,
+            _ => Self::find_custom(|name| name.name_with_suffix == suffixed_str),
         }
     }
 
@@ -164,9 +224,73 @@ impl BuiltinName {
             RANGE_CHECK_96_BUILTIN_NAME => Some(BuiltinName::range_check96),
             ADD_MOD_BUILTIN_NAME => Some(BuiltinName::add_mod),
             MUL_MOD_BUILTIN_NAME => Some(BuiltinName::mul_mod),
-            _ => None,
+            _ => Self::find_custom(|name| name.name == str),
+        }
+    }
+
+    /// Registers the name of a custom builtin, so that it can be parsed by
+    /// [BuiltinName::from_str] and [BuiltinName::from_str_with_suffix], and used in the builtins
+    /// of deserialized programs and Cairo PIEs. Registering a name twice has no effect.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use cairo_vm::types::builtin_name::{BuiltinName, CustomBuiltinName};
+    ///
+    /// static SQUARE: CustomBuiltinName = CustomBuiltinName::new("square", "square_builtin");
+    /// assert_eq!(BuiltinName::from_str("square"), None);
+    ///
+    /// BuiltinName::register_custom(&SQUARE);
+    /// assert_eq!(BuiltinName::from_str("square"), Some(BuiltinName::custom(&SQUARE)));
+    /// assert_eq!(BuiltinName::from_str_with_suffix("square_builtin"), Some(BuiltinName::custom(&SQUARE)));
+    ///
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn register_custom(name: &'static CustomBuiltinName) {
+        let mut names = CUSTOM_BUILTIN_NAMES
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if !names.contains(&name) {
+            names.push(name);
         }
     }
+
+    #[cfg(feature = "std")]
+    fn find_custom(predicate: impl Fn(&CustomBuiltinName) -> bool) -> Option<Self> {
+        CUSTOM_BUILTIN_NAMES
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .find(|name| predicate(name))
+            .map(|name| BuiltinName::custom(name))
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn find_custom(_predicate: impl Fn(&CustomBuiltinName) -> bool) -> Option<Self> {
+        None
+    }
+}
+
+impl Serialize for BuiltinName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.to_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for BuiltinName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = Cow::<str>::deserialize(deserializer)?;
+        BuiltinName::from_str(&name).ok_or_else(|| D::Error::unknown_variant(&name, BUILTIN_NAMES))
+    }
+}
+
+// Custom builtins can't be generated, as their names have to be registered beforehand
+#[cfg(feature = "test_utils")]
+impl<'a> Arbitrary<'a> for BuiltinName {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let name = u.choose(BUILTIN_NAMES)?;
+        Ok(BuiltinName::from_str(name).expect("Known builtin name"))
+    }
 }
 
 /// NOTE: Adds "_builtin" suffix
@@ -1,13 +1,13 @@
-use crate::stdlib::{any::Any, cell::RefCell, collections::HashMap, prelude::*, rc::Rc};
+use crate::stdlib::{any::Any, collections::HashMap, prelude::*};
 use crate::{
-    any_box,
     hint_processor::builtin_hint_processor::dict_manager::DictManager,
+    types::shared::{AnyBox, MaybeSendSync, Shared, SharedCell},
     vm::errors::{exec_scope_errors::ExecScopeError, hint_errors::HintError},
 };
 
 #[derive(Debug)]
 pub struct ExecutionScopes {
-    pub data: Vec<HashMap<String, Box<dyn Any>>>,
+    pub data: Vec<HashMap<String, AnyBox>>,
 }
 
 impl ExecutionScopes {
@@ -17,7 +17,7 @@ impl ExecutionScopes {
         }
     }
 
-    pub fn enter_scope(&mut self, new_scope_locals: HashMap<String, Box<dyn Any>>) {
+    pub fn enter_scope(&mut self, new_scope_locals: HashMap<String, AnyBox>) {
         self.data.push(new_scope_locals);
     }
 
@@ -31,16 +31,14 @@ impl ExecutionScopes {
     }
 
     ///Returns a mutable reference to the dictionary containing the variables present in the current scope
-    pub fn get_local_variables_mut(
-        &mut self,
-    ) -> Result<&mut HashMap<String, Box<dyn Any>>, HintError> {
+    pub fn get_local_variables_mut(&mut self) -> Result<&mut HashMap<String, AnyBox>, HintError> {
         self.data
             .last_mut()
             .ok_or(HintError::FromScopeError(ExecScopeError::NoScopeError))
     }
 
     ///Returns a dictionary containing the variables present in the current scope
-    pub fn get_local_variables(&self) -> Result<&HashMap<String, Box<dyn Any>>, HintError> {
+    pub fn get_local_variables(&self) -> Result<&HashMap<String, AnyBox>, HintError> {
         self.data
             .last()
             .ok_or(HintError::FromScopeError(ExecScopeError::NoScopeError))
@@ -54,7 +52,7 @@ impl ExecutionScopes {
     }
 
     ///Creates or updates an existing variable given its name and boxed value
-    pub fn assign_or_update_variable(&mut self, var_name: &str, var_value: Box<dyn Any>) {
+    pub fn assign_or_update_variable(&mut self, var_name: &str, var_value: AnyBox) {
         if let Ok(local_variables) = self.get_local_variables_mut() {
             local_variables.insert(var_name.to_string(), var_value);
         }
@@ -94,7 +92,7 @@ impl ExecutionScopes {
     }
 
     ///Returns the value in the current execution scope that matches the name
-    pub fn get_any_boxed_ref(&self, name: &str) -> Result<&Box<dyn Any>, HintError> {
+    pub fn get_any_boxed_ref(&self, name: &str) -> Result<&AnyBox, HintError> {
         if let Some(variable) = self.get_local_variables()?.get(name) {
             return Ok(variable);
         }
@@ -104,7 +102,7 @@ impl ExecutionScopes {
     }
 
     ///Returns the value in the current execution scope that matches the name
-    pub fn get_any_boxed_mut(&mut self, name: &str) -> Result<&mut Box<dyn Any>, HintError> {
+    pub fn get_any_boxed_mut(&mut self, name: &str) -> Result<&mut AnyBox, HintError> {
         if let Some(variable) = self.get_local_variables_mut()?.get_mut(name) {
             return Ok(variable);
         }
@@ -147,10 +145,10 @@ impl ExecutionScopes {
     }
 
     ///Returns the value in the dict manager
-    pub fn get_dict_manager(&self) -> Result<Rc<RefCell<DictManager>>, HintError> {
-        let mut val: Option<Rc<RefCell<DictManager>>> = None;
+    pub fn get_dict_manager(&self) -> Result<Shared<SharedCell<DictManager>>, HintError> {
+        let mut val: Option<Shared<SharedCell<DictManager>>> = None;
         if let Some(variable) = self.get_local_variables()?.get("dict_manager") {
-            if let Some(dict_manager) = variable.downcast_ref::<Rc<RefCell<DictManager>>>() {
+            if let Some(dict_manager) = variable.downcast_ref::<Shared<SharedCell<DictManager>>>() {
                 val = Some(dict_manager.clone());
             }
         }
@@ -174,13 +172,13 @@ impl ExecutionScopes {
     }
 
     ///Inserts the boxed value into the current scope
-    pub fn insert_box(&mut self, name: &str, value: Box<dyn Any>) {
+    pub fn insert_box(&mut self, name: &str, value: AnyBox) {
         self.assign_or_update_variable(name, value);
     }
 
     ///Inserts the value into the current scope
-    pub fn insert_value<T: 'static>(&mut self, name: &str, value: T) {
-        self.assign_or_update_variable(name, any_box!(value));
+    pub fn insert_value<T: 'static + MaybeSendSync>(&mut self, name: &str, value: T) {
+        self.assign_or_update_variable(name, Box::new(value));
     }
 }
 
@@ -210,7 +208,7 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_local_variables_test() {
         let var_name = String::from("a");
-        let var_value: Box<dyn Any> = Box::new(Felt252::from(2));
+        let var_value: AnyBox = Box::new(Felt252::from(2));
 
         let scope = HashMap::from([(var_name, var_value)]);
 
@@ -231,14 +229,14 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn enter_new_scope_test() {
         let var_name = String::from("a");
-        let var_value: Box<dyn Any> = Box::new(Felt252::from(2_i32));
+        let var_value: AnyBox = Box::new(Felt252::from(2_i32));
 
         let new_scope = HashMap::from([(var_name, var_value)]);
 
         let mut scopes = ExecutionScopes {
             data: vec![HashMap::from([(
                 String::from("b"),
-                (Box::new(Felt252::ONE) as Box<dyn Any>),
+                (Box::new(Felt252::ONE) as AnyBox),
             )])],
         };
 
@@ -274,7 +272,7 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn exit_scope_test() {
         let var_name = String::from("a");
-        let var_value: Box<dyn Any> = Box::new(Felt252::from(2));
+        let var_value: AnyBox = Box::new(Felt252::from(2));
 
         let new_scope = HashMap::from([(var_name, var_value)]);
 
@@ -310,7 +308,7 @@ mod tests {
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn assign_local_variable_test() {
-        let var_value: Box<dyn Any> = Box::new(Felt252::from(2));
+        let var_value: AnyBox = Box::new(Felt252::from(2));
 
         let mut scopes = ExecutionScopes::new();
 
@@ -332,13 +330,13 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn re_assign_local_variable_test() {
         let var_name = String::from("a");
-        let var_value: Box<dyn Any> = Box::new(Felt252::from(2));
+        let var_value: AnyBox = Box::new(Felt252::from(2));
 
         let scope = HashMap::from([(var_name, var_value)]);
 
         let mut scopes = ExecutionScopes { data: vec![scope] };
 
-        let var_value_new: Box<dyn Any> = Box::new(Felt252::from(3));
+        let var_value_new: AnyBox = Box::new(Felt252::from(3));
 
         scopes.assign_or_update_variable("a", var_value_new);
 
@@ -358,7 +356,7 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn delete_local_variable_test() {
         let var_name = String::from("a");
-        let var_value: Box<dyn Any> = Box::new(Felt252::from(2));
+        let var_value: AnyBox = Box::new(Felt252::from(2));
 
         let scope = HashMap::from([(var_name, var_value)]);
 
@@ -388,7 +386,7 @@ mod tests {
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_listu64_test() {
-        let list_u64: Box<dyn Any> = Box::new(vec![20_u64, 18_u64]);
+        let list_u64: AnyBox = Box::new(vec![20_u64, 18_u64]);
 
         let mut scopes = ExecutionScopes::default();
 
@@ -410,7 +408,7 @@ mod tests {
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_u64_test() {
-        let u64: Box<dyn Any> = Box::new(9_u64);
+        let u64: AnyBox = Box::new(9_u64);
 
         let mut scopes = ExecutionScopes::new();
 
@@ -436,7 +434,7 @@ mod tests {
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_mut_int_ref_test() {
-        let bigint: Box<dyn Any> = Box::new(Felt252::from(12));
+        let bigint: AnyBox = Box::new(Felt252::from(12));
 
         let mut scopes = ExecutionScopes::new();
         scopes.assign_or_update_variable("bigint", bigint);
@@ -450,7 +448,7 @@ mod tests {
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_any_boxed_test() {
-        let list_u64: Box<dyn Any> = Box::new(vec![20_u64, 18_u64]);
+        let list_u64: AnyBox = Box::new(vec![20_u64, 18_u64]);
 
         let mut scopes = ExecutionScopes::default();
 
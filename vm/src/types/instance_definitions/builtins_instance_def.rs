@@ -5,6 +5,7 @@ use super::{
     pedersen_instance_def::PedersenInstanceDef, poseidon_instance_def::PoseidonInstanceDef,
     range_check_instance_def::RangeCheckInstanceDef,
 };
+use crate::types::layout::CairoLayoutParams;
 
 pub(crate) const BUILTIN_INSTANCES_PER_COMPONENT: u32 = 1;
 
@@ -213,6 +214,34 @@ impl BuiltinsInstanceDef {
             mul_mod: None,
         }
     }
+
+    pub(crate) fn dynamic_with_params(params: &CairoLayoutParams) -> BuiltinsInstanceDef {
+        let ratio = |uses_builtin: bool, ratio: u32| uses_builtin.then_some(Some(ratio));
+        BuiltinsInstanceDef {
+            output: true,
+            pedersen: ratio(params.uses_pedersen_builtin, params.pedersen_ratio)
+                .map(PedersenInstanceDef::new),
+            range_check: ratio(params.uses_range_check_builtin, params.range_check_ratio)
+                .map(RangeCheckInstanceDef::new),
+            ecdsa: ratio(params.uses_ecdsa_builtin, params.ecdsa_ratio).map(EcdsaInstanceDef::new),
+            bitwise: ratio(params.uses_bitwise_builtin, params.bitwise_ratio)
+                .map(BitwiseInstanceDef::new),
+            ec_op: ratio(params.uses_ec_op_builtin, params.ec_op_ratio).map(EcOpInstanceDef::new),
+            keccak: ratio(params.uses_keccak_builtin, params.keccak_ratio)
+                .map(KeccakInstanceDef::new),
+            poseidon: ratio(params.uses_poseidon_builtin, params.poseidon_ratio)
+                .map(PoseidonInstanceDef::new),
+            range_check96: ratio(
+                params.uses_range_check96_builtin,
+                params.range_check96_ratio,
+            )
+            .map(RangeCheckInstanceDef::new),
+            add_mod: ratio(params.uses_add_mod_builtin, params.add_mod_ratio)
+                .map(|ratio| ModInstanceDef::new(ratio, 1, 96)),
+            mul_mod: ratio(params.uses_mul_mod_builtin, params.mul_mod_ratio)
+                .map(|ratio| ModInstanceDef::new(ratio, 1, 96)),
+        }
+    }
 }
 
 #[cfg(test)]
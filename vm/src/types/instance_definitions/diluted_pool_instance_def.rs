@@ -3,6 +3,8 @@ use serde::Serialize;
 #[derive(Serialize, Debug, PartialEq)]
 pub(crate) struct DilutedPoolInstanceDef {
     pub(crate) units_per_step: u32, // 2 ^ log_units_per_step (for cairo_lang comparison)
+    // If true, there is one unit every `units_per_step` steps instead
+    pub(crate) fractional_units_per_step: bool,
     pub(crate) spacing: u32,
     pub(crate) n_bits: u32,
 }
@@ -11,6 +13,7 @@ impl DilutedPoolInstanceDef {
     pub(crate) fn default() -> Self {
         DilutedPoolInstanceDef {
             units_per_step: 16,
+            fractional_units_per_step: false,
             spacing: 4,
             n_bits: 16,
         }
@@ -19,10 +22,20 @@ impl DilutedPoolInstanceDef {
     pub(crate) fn new(units_per_step: u32, spacing: u32, n_bits: u32) -> Self {
         DilutedPoolInstanceDef {
             units_per_step,
+            fractional_units_per_step: false,
             spacing,
             n_bits,
         }
     }
+
+    pub(crate) fn from_log_units_per_step(log_units_per_step: i32) -> Self {
+        DilutedPoolInstanceDef {
+            units_per_step: 2_u32.saturating_pow(log_units_per_step.unsigned_abs()),
+            fractional_units_per_step: log_units_per_step < 0,
+            spacing: 4,
+            n_bits: 16,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -49,4 +62,17 @@ mod tests {
         assert_eq!(diluted_pool.spacing, 1);
         assert_eq!(diluted_pool.n_bits, 1);
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_from_log_units_per_step() {
+        let diluted_pool = DilutedPoolInstanceDef::from_log_units_per_step(3);
+        assert_eq!(diluted_pool.units_per_step, 8);
+        assert!(!diluted_pool.fractional_units_per_step);
+        let diluted_pool = DilutedPoolInstanceDef::from_log_units_per_step(-2);
+        assert_eq!(diluted_pool.units_per_step, 4);
+        assert!(diluted_pool.fractional_units_per_step);
+        assert_eq!(diluted_pool.spacing, 4);
+        assert_eq!(diluted_pool.n_bits, 16);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::stdlib::fmt;
 use crate::types::layout_name::LayoutName;
 
 use super::instance_definitions::{
@@ -6,7 +7,7 @@ use super::instance_definitions::{
 
 pub(crate) const MEMORY_UNITS_PER_STEP: u32 = 8;
 
-use serde::Serialize;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Serialize, Debug)]
 pub struct CairoLayout {
@@ -15,6 +16,98 @@ pub struct CairoLayout {
     pub(crate) builtins: BuiltinsInstanceDef,
     pub(crate) public_memory_fraction: u32,
     pub(crate) diluted_pool_instance_def: Option<DilutedPoolInstanceDef>,
+    pub(crate) memory_units_per_step: u32,
+    pub(crate) dynamic_layout_params: Option<CairoLayoutParams>,
+}
+
+/// Parameters of the `dynamic` layout, in the format of the prover's dynamic params file.
+/// The builtins not used by the layout are left out of it, the ones used by it get an instance
+/// every `<builtin>_ratio` steps.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CairoLayoutParams {
+    pub rc_units: u32,
+    pub cpu_component_step: u32,
+    pub memory_units_per_step: u32,
+    /// Log2 of the diluted units available per step, negative if there are less units than steps
+    pub log_diluted_units_per_step: i32,
+    #[serde(with = "bool_as_int")]
+    pub uses_pedersen_builtin: bool,
+    pub pedersen_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_range_check_builtin: bool,
+    pub range_check_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_ecdsa_builtin: bool,
+    pub ecdsa_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_bitwise_builtin: bool,
+    pub bitwise_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_ec_op_builtin: bool,
+    pub ec_op_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_keccak_builtin: bool,
+    pub keccak_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_poseidon_builtin: bool,
+    pub poseidon_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_range_check96_builtin: bool,
+    pub range_check96_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_add_mod_builtin: bool,
+    pub add_mod_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_mul_mod_builtin: bool,
+    pub mul_mod_ratio: u32,
+}
+
+impl CairoLayoutParams {
+    /// Reads the parameters from a JSON file
+    #[cfg(feature = "std")]
+    pub fn from_file(params_path: &std::path::Path) -> std::io::Result<Self> {
+        let params_file = std::fs::File::open(params_path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(
+            params_file,
+        ))?)
+    }
+}
+
+// The prover encodes the flags of the dynamic params as 0 or 1
+mod bool_as_int {
+    use super::*;
+
+    pub(super) fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(*value as u32)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<bool, D::Error> {
+        struct BoolVisitor;
+
+        impl de::Visitor<'_> for BoolVisitor {
+            type Value = bool;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a boolean, 0 or 1")
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<bool, E> {
+                Ok(value)
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<bool, E> {
+                match value {
+                    0 => Ok(false),
+                    1 => Ok(true),
+                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(value), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(BoolVisitor)
+    }
 }
 
 impl CairoLayout {
@@ -25,6 +118,8 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::plain(),
             public_memory_fraction: 4,
             diluted_pool_instance_def: None,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
         }
     }
 
@@ -35,6 +130,8 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::small(),
             public_memory_fraction: 4,
             diluted_pool_instance_def: None,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
         }
     }
 
@@ -45,6 +142,8 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::dex(),
             public_memory_fraction: 4,
             diluted_pool_instance_def: None,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
         }
     }
 
@@ -55,6 +154,8 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::recursive(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
         }
     }
 
@@ -65,6 +166,8 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::starknet(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::new(2, 4, 16)),
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
         }
     }
 
@@ -75,6 +178,8 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::starknet_with_keccak(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
         }
     }
 
@@ -85,6 +190,8 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::recursive_large_output(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
         }
     }
     pub(crate) fn recursive_with_poseidon() -> CairoLayout {
@@ -94,6 +201,8 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::recursive_with_poseidon(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::new(8, 4, 16)),
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
         }
     }
 
@@ -104,6 +213,8 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::all_cairo(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
         }
     }
 
@@ -114,6 +225,8 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::all_solidity(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
         }
     }
 
@@ -124,6 +237,22 @@ impl CairoLayout {
             builtins: BuiltinsInstanceDef::dynamic(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
+            dynamic_layout_params: None,
+        }
+    }
+
+    pub(crate) fn dynamic_instance_with_params(params: CairoLayoutParams) -> CairoLayout {
+        CairoLayout {
+            name: LayoutName::dynamic,
+            rc_units: params.rc_units,
+            builtins: BuiltinsInstanceDef::dynamic_with_params(&params),
+            public_memory_fraction: 8,
+            diluted_pool_instance_def: Some(DilutedPoolInstanceDef::from_log_units_per_step(
+                params.log_diluted_units_per_step,
+            )),
+            memory_units_per_step: params.memory_units_per_step,
+            dynamic_layout_params: Some(params),
         }
     }
 }
@@ -255,6 +384,93 @@ mod tests {
         );
     }
 
+    fn layout_params() -> CairoLayoutParams {
+        serde_json::from_str(include_str!("../tests/cairo_layout_params_file.json")).unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deserialize_layout_params() {
+        let params = layout_params();
+        assert_eq!(params.rc_units, 4);
+        assert_eq!(params.log_diluted_units_per_step, 4);
+        assert!(params.uses_pedersen_builtin);
+        assert_eq!(params.pedersen_ratio, 256);
+        assert!(!params.uses_mul_mod_builtin);
+
+        // The flags are serialized as integers, but booleans are accepted too
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(value["uses_pedersen_builtin"], 1);
+        assert_eq!(value["uses_mul_mod_builtin"], 0);
+        let mut value = value;
+        value["uses_mul_mod_builtin"] = true.into();
+        let params: CairoLayoutParams = serde_json::from_value(value.clone()).unwrap();
+        assert!(params.uses_mul_mod_builtin);
+        value["uses_mul_mod_builtin"] = 2.into();
+        assert!(serde_json::from_value::<CairoLayoutParams>(value).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_dynamic_instance_with_params() {
+        let mut params = layout_params();
+        params.log_diluted_units_per_step = -1;
+        params.memory_units_per_step = 16;
+        let layout = CairoLayout::dynamic_instance_with_params(params.clone());
+        assert_eq!(layout.name, LayoutName::dynamic);
+        assert_eq!(layout.rc_units, 4);
+        assert_eq!(layout.memory_units_per_step, 16);
+        assert_eq!(layout.builtins.pedersen.unwrap().ratio, Some(256));
+        assert_eq!(layout.builtins.range_check.unwrap().ratio, Some(8));
+        assert_eq!(layout.builtins.poseidon.unwrap().ratio, Some(256));
+        assert!(layout.builtins.range_check96.is_none());
+        assert!(layout.builtins.add_mod.is_none());
+        let diluted_pool = layout.diluted_pool_instance_def.unwrap();
+        assert_eq!(diluted_pool.units_per_step, 2);
+        assert!(diluted_pool.fractional_units_per_step);
+        assert_eq!(layout.dynamic_layout_params, Some(params));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn dynamic_layout_params_in_air_public_input() {
+        use crate::{
+            hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+            utils::test_utils::program_b,
+            vm::runners::cairo_runner::{CairoRunner, RunnerMode},
+        };
+
+        let params = CairoLayoutParams::from_file(std::path::Path::new(
+            "src/tests/cairo_layout_params_file.json",
+        ))
+        .unwrap();
+        assert_eq!(params, layout_params());
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runner = CairoRunner::new_v2(
+            &program_b(),
+            LayoutName::dynamic,
+            Some(params.clone()),
+            RunnerMode::ExecutionMode,
+            true,
+        )
+        .unwrap();
+        let end = runner.initialize(false).unwrap();
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        runner.end_run(false, false, &mut hint_processor).unwrap();
+        // The program doesn't return the builtin pointers, so the builtin segments are left empty
+        for builtin in runner.vm.builtin_runners.iter_mut() {
+            builtin.set_stop_ptr(0);
+        }
+        runner.relocate(true).unwrap();
+
+        let public_input = runner.get_air_public_input().unwrap();
+        assert_eq!(public_input.layout, "dynamic");
+        assert_eq!(public_input.dynamic_params, Some(params));
+        let public_input = serde_json::to_value(&public_input).unwrap();
+        assert_eq!(public_input["dynamic_params"]["uses_pedersen_builtin"], 1);
+        assert_eq!(public_input["dynamic_params"]["range_check_ratio"], 8);
+    }
+
     #[test]
     fn get_dynamic_instance() {
         let layout = CairoLayout::dynamic_instance();
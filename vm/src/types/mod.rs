@@ -7,3 +7,4 @@ pub mod layout;
 pub mod layout_name;
 pub mod program;
 pub mod relocatable;
+pub mod shared;
@@ -991,6 +991,8 @@ mod tests {
                     start_col: 0,
                 },
                 hints: vec![],
+                accessible_scopes: vec![],
+                flow_tracking_data: None,
             }
         }
 
@@ -0,0 +1,165 @@
+//! Shared ownership and interior mutability for the state of a run
+//!
+//! By default the state shared during a run uses [Rc](crate::stdlib::rc::Rc) and
+//! [RefCell](crate::stdlib::cell::RefCell), which can't cross thread boundaries.
+//! With the `thread_safe` feature (which requires `std`), [Shared] is an [Arc](std::sync::Arc) and
+//! [SharedCell] is backed by a [RwLock], and the values stored in the execution scopes, the memory
+//! validation rules, the memory backends and the extra hints of the
+//! [BuiltinHintProcessor](crate::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor)
+//! have to be `Send + Sync`. This makes the
+//! [CairoRunner](crate::vm::runners::cairo_runner::CairoRunner), its
+//! [VirtualMachine](crate::vm::vm_core::VirtualMachine) and the hint processors `Send + Sync`,
+//! so that a paused run can be moved to another thread.
+
+use crate::stdlib::{any::Any, prelude::*};
+
+#[cfg(not(feature = "thread_safe"))]
+pub use crate::stdlib::{cell::RefCell as SharedCell, rc::Rc as Shared};
+#[cfg(feature = "thread_safe")]
+pub use std::sync::Arc as Shared;
+
+#[cfg(feature = "thread_safe")]
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Boxed value stored in the execution scopes
+#[cfg(not(feature = "thread_safe"))]
+pub type AnyBox = Box<dyn Any>;
+/// Boxed value stored in the execution scopes
+#[cfg(feature = "thread_safe")]
+pub type AnyBox = Box<dyn Any + Send + Sync>;
+
+/// Implemented by every type, or only by `Send + Sync` types with the `thread_safe` feature
+#[cfg(not(feature = "thread_safe"))]
+pub trait MaybeSendSync {}
+#[cfg(not(feature = "thread_safe"))]
+impl<T: ?Sized> MaybeSendSync for T {}
+
+/// Implemented by every type, or only by `Send + Sync` types with the `thread_safe` feature
+#[cfg(feature = "thread_safe")]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(feature = "thread_safe")]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+/// Thread safe replacement of [RefCell](std::cell::RefCell), with the same borrowing methods.
+///
+/// Unlike [RefCell](std::cell::RefCell), conflicting borrows aren't detected: they wait for the
+/// other borrow to be released. Borrowing a cell which is already borrowed mutably by the same
+/// thread, or calling [SharedCell::borrow_mut] on a cell already borrowed by the same thread,
+/// deadlocks (or panics, depending on the platform) where a [RefCell](std::cell::RefCell) panicked
+/// with a `BorrowMutError`. Borrows must therefore be dropped before calling code that may borrow
+/// the same cell, such as hints borrowing the dict manager.
+#[cfg(feature = "thread_safe")]
+#[derive(Debug, Default)]
+pub struct SharedCell<T>(RwLock<T>);
+
+#[cfg(feature = "thread_safe")]
+impl<T> SharedCell<T> {
+    pub fn new(value: T) -> Self {
+        SharedCell(RwLock::new(value))
+    }
+
+    pub fn borrow(&self) -> RwLockReadGuard<'_, T> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Mutably borrows the value, waiting for the other borrows to be released.
+    /// Deadlocks if the current thread already holds a borrow of this cell.
+    pub fn borrow_mut(&self) -> RwLockWriteGuard<'_, T> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "thread_safe")]
+impl<T: Clone> Clone for SharedCell<T> {
+    fn clone(&self) -> Self {
+        SharedCell::new(self.borrow().clone())
+    }
+}
+
+#[cfg(feature = "thread_safe")]
+impl<T: PartialEq> PartialEq for SharedCell<T> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.borrow() == *other.borrow()
+    }
+}
+
+#[cfg(feature = "thread_safe")]
+impl<T: Eq> Eq for SharedCell<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn shared_cell_borrow() {
+        let cell = Shared::new(SharedCell::new(vec![1, 2]));
+        let other = Shared::clone(&cell);
+        other.borrow_mut().push(3);
+        assert_eq!(*cell.borrow(), vec![1, 2, 3]);
+        assert_eq!(cell.as_ref().clone(), SharedCell::new(vec![1, 2, 3]));
+        drop(other);
+        assert_eq!(
+            Shared::try_unwrap(cell).unwrap().into_inner(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn any_box_downcast() {
+        let mut value: AnyBox = Box::new(Shared::new(SharedCell::new(5_u32)));
+        *value
+            .downcast_mut::<Shared<SharedCell<u32>>>()
+            .unwrap()
+            .borrow_mut() += 1;
+        assert_eq!(
+            *value
+                .downcast_ref::<Shared<SharedCell<u32>>>()
+                .unwrap()
+                .borrow(),
+            6
+        );
+        assert!(value.downcast_ref::<u32>().is_none());
+    }
+
+    #[cfg(feature = "thread_safe")]
+    #[test]
+    fn runner_is_send_and_sync() {
+        use crate::{
+            hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+            types::layout_name::LayoutName,
+            utils::test_utils::program_b,
+            vm::{runners::cairo_runner::CairoRunner, vm_core::VirtualMachine},
+        };
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CairoRunner>();
+        assert_send_sync::<VirtualMachine>();
+        assert_send_sync::<BuiltinHintProcessor>();
+        #[cfg(feature = "cairo-1-hints")]
+        assert_send_sync::<
+            crate::hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor,
+        >();
+
+        // Pause a run, and finish it in another thread
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runner = CairoRunner::new(&program_b(), LayoutName::small, false, false).unwrap();
+        let end = runner.initialize(false).unwrap();
+        runner.run_for_steps(5, &mut hint_processor).unwrap();
+        let runner = std::thread::spawn(move || {
+            runner.run_until_pc(end, &mut hint_processor).unwrap();
+            runner
+        })
+        .join()
+        .unwrap();
+        assert_eq!(runner.vm.current_step, 18);
+    }
+}
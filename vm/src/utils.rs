@@ -52,8 +52,12 @@ pub fn from_relocatable_to_indexes(relocatable: Relocatable) -> (usize, usize) {
 #[cfg(test)]
 #[macro_use]
 pub mod test_utils {
+    use crate::cairo_run::{cairo_run_program, CairoRunConfig};
+    use crate::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
     use crate::types::exec_scope::ExecutionScopes;
+    use crate::types::layout_name::LayoutName;
     use crate::types::relocatable::MaybeRelocatable;
+    use crate::vm::runners::cairo_runner::CairoRunner;
     use crate::vm::trace::trace_entry::TraceEntry;
 
     #[macro_export]
@@ -163,7 +167,7 @@ pub mod test_utils {
                 if $si < 0 {
                     $mem.temp_data.push($crate::stdlib::vec::Vec::new())
                 } else {
-                    $mem.data.push($crate::stdlib::vec::Vec::new());
+                    $mem.data.add_segment();
                 }
                 res = $mem.insert(k, v);
             }
@@ -178,7 +182,7 @@ pub mod test_utils {
                 if $si < 0 {
                     $mem.temp_data.push($crate::stdlib::vec::Vec::new())
                 } else {
-                    $mem.data.push($crate::stdlib::vec::Vec::new());
+                    $mem.data.add_segment();
                 }
                 res = $mem.insert(k, v);
             }
@@ -525,7 +529,7 @@ pub mod test_utils {
                 $(
                     exec_scopes.assign_or_update_variable(
                         $name,
-                        any_box!($val),
+                        Box::new($val),
                     );
                 )*
                 exec_scopes
@@ -578,13 +582,13 @@ pub mod test_utils {
             )*
             let mut dict_manager = DictManager::new();
             dict_manager.trackers.insert(2, tracker);
-            $exec_scopes.insert_value("dict_manager", crate::stdlib::rc::Rc::new(core::cell::RefCell::new(dict_manager)))
+            $exec_scopes.insert_value("dict_manager", crate::types::shared::Shared::new(crate::types::shared::SharedCell::new(dict_manager)))
         };
         ($exec_scopes:expr, $tracker_num:expr) => {
             let  tracker = DictTracker::new_empty(relocatable!($tracker_num, 0));
             let mut dict_manager = DictManager::new();
             dict_manager.trackers.insert(2, tracker);
-            $exec_scopes.insert_value("dict_manager", crate::stdlib::rc::Rc::new(core::cell::RefCell::new(dict_manager)))
+            $exec_scopes.insert_value("dict_manager", crate::types::shared::Shared::new(crate::types::shared::SharedCell::new(dict_manager)))
         };
 
     }
@@ -598,13 +602,13 @@ pub mod test_utils {
             )*
             let mut dict_manager = DictManager::new();
             dict_manager.trackers.insert(2, tracker);
-            $exec_scopes.insert_value("dict_manager", crate::stdlib::rc::Rc::new(core::cell::RefCell::new(dict_manager)))
+            $exec_scopes.insert_value("dict_manager", crate::types::shared::Shared::new(crate::types::shared::SharedCell::new(dict_manager)))
         };
         ($exec_scopes:expr, $tracker_num:expr,$default:expr) => {
             let tracker = DictTracker::new_default_dict(relocatable!($tracker_num, 0), &MaybeRelocatable::from($default), None);
             let mut dict_manager = DictManager::new();
             dict_manager.trackers.insert(2, tracker);
-            $exec_scopes.insert_value("dict_manager", crate::stdlib::rc::Rc::new(core::cell::RefCell::new(dict_manager)))
+            $exec_scopes.insert_value("dict_manager", crate::types::shared::Shared::new(crate::types::shared::SharedCell::new(dict_manager)))
         };
     }
     pub(crate) use dict_manager_default;
@@ -634,14 +638,40 @@ pub mod test_utils {
         let scope_value = scopes.get_any_boxed_ref(name).unwrap();
         assert_eq!(scope_value.downcast_ref::<T>(), Some(&value));
     }
+
+    /// Loads `valid_program_b`, a small program using the output and range_check builtins
+    pub(crate) fn program_b() -> Program {
+        Program::from_bytes(
+            include_bytes!("../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap()
+    }
+
+    /// Runs `valid_program_b` with the small layout
+    pub(crate) fn run_program_b(trace_enabled: bool, relocate_mem: bool) -> CairoRunner {
+        let config = CairoRunConfig {
+            trace_enabled,
+            relocate_mem,
+            layout: LayoutName::small,
+            ..Default::default()
+        };
+        cairo_run_program(
+            &program_b(),
+            &config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::hint_processor::hint_processor_definition::HintProcessorLogic;
-    use crate::stdlib::{cell::RefCell, collections::HashMap, rc::Rc, string::String, vec::Vec};
+    use crate::stdlib::{collections::HashMap, string::String, vec::Vec};
     use crate::types::builtin_name::BuiltinName;
     use crate::types::program::HintsCollection;
+    use crate::types::shared::{Shared, SharedCell};
     use crate::{
         hint_processor::{
             builtin_hint_processor::{
@@ -666,7 +696,7 @@ mod test {
     fn memory_macro_test() {
         let mut memory = Memory::new();
         for _ in 0..2 {
-            memory.data.push(Vec::new());
+            memory.data.add_segment();
         }
         memory
             .insert(
@@ -678,7 +708,7 @@ mod test {
             .insert(Relocatable::from((1, 1)), &MaybeRelocatable::from((1, 0)))
             .unwrap();
         let mem = memory![((1, 2), 1), ((1, 1), (1, 0))];
-        assert_eq!(memory.data, mem.data);
+        assert_eq!(memory.data.cells(), mem.data.cells());
     }
 
     #[test]
@@ -686,7 +716,7 @@ mod test {
     fn check_memory_macro_test() {
         let mut memory = Memory::new();
         for _ in 0..2 {
-            memory.data.push(Vec::new());
+            memory.data.add_segment();
         }
         memory
             .insert(Relocatable::from((1, 1)), &MaybeRelocatable::from((1, 0)))
@@ -707,7 +737,7 @@ mod test {
     fn check_memory_address_macro_test() {
         let mut memory = Memory::new();
         for _ in 0..2 {
-            memory.data.push(Vec::new());
+            memory.data.add_segment();
         }
         memory
             .insert(Relocatable::from((1, 1)), &MaybeRelocatable::from((1, 0)))
@@ -784,26 +814,28 @@ mod test {
         add_segments!(vm, 1);
         assert_matches::assert_matches!(run_hint!(vm, HashMap::new(), hint_code), Ok(()));
         //A segment is added
-        assert_eq!(vm.segments.memory.data.len(), 2);
+        assert_eq!(vm.segments.memory.data.num_segments(), 2);
     }
 
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn check_scope_test_pass() {
         let mut exec_scopes = ExecutionScopes::new();
-        exec_scopes.assign_or_update_variable("a", any_box!(String::from("Hello")));
+        exec_scopes.assign_or_update_variable("a", Box::new(String::from("Hello")));
         exec_scopes.assign_or_update_variable(
             "",
-            any_box!(Rc::new(RefCell::new(HashMap::<usize, Vec<usize>>::new()))),
+            Box::new(Shared::new(SharedCell::new(
+                HashMap::<usize, Vec<usize>>::new(),
+            ))),
         );
-        exec_scopes.assign_or_update_variable("c", any_box!(vec![1, 2, 3, 4]));
+        exec_scopes.assign_or_update_variable("c", Box::new(vec![1, 2, 3, 4]));
         check_scope!(
             &exec_scopes,
             [
                 ("a", String::from("Hello")),
                 (
                     "",
-                    Rc::new(RefCell::new(HashMap::<usize, Vec<usize>>::new()))
+                    Shared::new(SharedCell::new(HashMap::<usize, Vec<usize>>::new()))
                 ),
                 ("c", vec![1, 2, 3, 4])
             ]
@@ -814,19 +846,21 @@ mod test {
     #[should_panic]
     fn check_scope_test_fail() {
         let mut exec_scopes = ExecutionScopes::new();
-        exec_scopes.assign_or_update_variable("a", any_box!(String::from("Hello")));
+        exec_scopes.assign_or_update_variable("a", Box::new(String::from("Hello")));
         exec_scopes.assign_or_update_variable(
             "",
-            any_box!(Rc::new(RefCell::new(HashMap::<usize, Vec<usize>>::new()))),
+            Box::new(Shared::new(SharedCell::new(
+                HashMap::<usize, Vec<usize>>::new(),
+            ))),
         );
-        exec_scopes.assign_or_update_variable("c", any_box!(vec![1, 2, 3, 4]));
+        exec_scopes.assign_or_update_variable("c", Box::new(vec![1, 2, 3, 4]));
         check_scope!(
             &exec_scopes,
             [
                 ("a", String::from("Hello")),
                 (
                     "",
-                    Rc::new(RefCell::new(HashMap::<usize, Vec<usize>>::new()))
+                    Shared::new(SharedCell::new(HashMap::<usize, Vec<usize>>::new()))
                 ),
                 ("c", vec![1, 2, 3, 5])
             ]
@@ -838,7 +872,7 @@ mod test {
     fn scope_macro_test() {
         let scope_from_macro = scope![("a", crate::Felt252::ONE)];
         let mut scope_verbose = ExecutionScopes::new();
-        scope_verbose.assign_or_update_variable("a", any_box!(crate::Felt252::ONE));
+        scope_verbose.assign_or_update_variable("a", Box::new(crate::Felt252::ONE));
         assert_eq!(scope_from_macro.data.len(), scope_verbose.data.len());
         assert_eq!(scope_from_macro.data[0].len(), scope_verbose.data[0].len());
         assert_eq!(
@@ -860,7 +894,7 @@ mod test {
         let mut exec_scopes = ExecutionScopes::new();
         exec_scopes.assign_or_update_variable(
             "dict_manager",
-            any_box!(Rc::new(RefCell::new(dict_manager))),
+            Box::new(Shared::new(SharedCell::new(dict_manager))),
         );
         check_dictionary!(&exec_scopes, 2, (5, 10));
     }
@@ -875,7 +909,7 @@ mod test {
         let mut exec_scopes = ExecutionScopes::new();
         exec_scopes.assign_or_update_variable(
             "dict_manager",
-            any_box!(Rc::new(RefCell::new(dict_manager))),
+            Box::new(Shared::new(SharedCell::new(dict_manager))),
         );
         check_dictionary!(&exec_scopes, 2, (5, 11));
     }
@@ -889,7 +923,7 @@ mod test {
         let mut exec_scopes = ExecutionScopes::new();
         exec_scopes.assign_or_update_variable(
             "dict_manager",
-            any_box!(Rc::new(RefCell::new(dict_manager))),
+            Box::new(Shared::new(SharedCell::new(dict_manager))),
         );
         check_dict_ptr!(&exec_scopes, 2, (2, 0));
     }
@@ -903,7 +937,7 @@ mod test {
         let mut exec_scopes = ExecutionScopes::new();
         exec_scopes.assign_or_update_variable(
             "dict_manager",
-            any_box!(Rc::new(RefCell::new(dict_manager))),
+            Box::new(Shared::new(SharedCell::new(dict_manager))),
         );
         check_dict_ptr!(&exec_scopes, 2, (3, 0));
     }
@@ -918,7 +952,7 @@ mod test {
         dict_manager!(exec_scopes, 2);
         assert_matches::assert_matches!(
             exec_scopes.get_dict_manager(),
-            Ok(x) if x == Rc::new(RefCell::new(dict_manager))
+            Ok(x) if x == Shared::new(SharedCell::new(dict_manager))
         );
     }
 
@@ -936,7 +970,7 @@ mod test {
         dict_manager_default!(exec_scopes, 2, 17);
         assert_matches::assert_matches!(
             exec_scopes.get_dict_manager(),
-            Ok(x) if x == Rc::new(RefCell::new(dict_manager))
+            Ok(x) if x == Shared::new(SharedCell::new(dict_manager))
         );
     }
 
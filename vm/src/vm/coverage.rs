@@ -0,0 +1,410 @@
+//! Execution coverage
+//!
+//! Maps the execution trace of a finished run back to the Cairo source code using the program's
+//! instruction locations, and computes per-file line, function and branch coverage.
+//! The resulting [CoverageReport] can be exported in the lcov format or as an HTML report.
+//!
+//! - Lines are the start lines of the instructions of the program. A line is hit as many times
+//!   as its most executed instruction.
+//! - Functions are the program's function identifiers. A function is hit once per execution of
+//!   its first instruction.
+//! - Branches are the two outcomes of each conditional jump (`jmp rel <n> if <cond> != 0`).
+
+use crate::stdlib::{collections::BTreeMap, prelude::*};
+use core::fmt::Write;
+
+use num_traits::ToPrimitive;
+
+use crate::{
+    serde::deserialize_program::InstructionLocation,
+    types::{instruction::PcUpdate, relocatable::Relocatable},
+    vm::{
+        decoding::decoder::decode_instruction, errors::trace_errors::TraceError,
+        runners::cairo_runner::CairoRunner,
+    },
+};
+
+/// Coverage of a whole run, indexed by source file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub files: BTreeMap<String, FileCoverage>,
+}
+
+/// Coverage of a single source file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileCoverage {
+    /// Hit count of each line containing instructions
+    pub lines: BTreeMap<u32, usize>,
+    /// Coverage of the functions defined in the file, indexed by full name
+    pub functions: BTreeMap<String, FunctionCoverage>,
+    /// Coverage of the conditional jumps in the file, sorted by pc
+    pub branches: Vec<BranchCoverage>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionCoverage {
+    /// Line of the first instruction of the function
+    pub line: u32,
+    /// Number of times the function was called
+    pub hits: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BranchCoverage {
+    pub line: u32,
+    /// Offset of the conditional jump within the program segment
+    pub pc: usize,
+    /// Number of times the jump was performed
+    pub taken: usize,
+    /// Number of times the execution fell through to the next instruction
+    pub not_taken: usize,
+}
+
+impl FileCoverage {
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|hits| **hits > 0).count()
+    }
+
+    pub fn functions_hit(&self) -> usize {
+        self.functions.values().filter(|f| f.hits > 0).count()
+    }
+
+    /// Number of branch outcomes (two per conditional jump) that were exercised
+    pub fn branches_hit(&self) -> usize {
+        self.branches
+            .iter()
+            .map(|b| (b.taken > 0) as usize + (b.not_taken > 0) as usize)
+            .sum()
+    }
+}
+
+impl CoverageReport {
+    /// Computes the coverage of a finished run.
+    /// The run must have been performed with the trace enabled, and the program must contain
+    /// debug information (instruction locations) for the report to contain any file.
+    pub fn new(runner: &CairoRunner) -> Result<Self, TraceError> {
+        let trace = runner.vm.get_trace()?;
+        let program_base = runner.program_base.unwrap_or_default();
+        let program_offset = |pc: Relocatable| {
+            (pc.segment_index == program_base.segment_index)
+                .then(|| pc.offset.checked_sub(program_base.offset))
+                .flatten()
+        };
+
+        // Count the executions of each instruction, and the jumps performed by each conditional jump
+        let mut pc_hits = BTreeMap::<usize, usize>::new();
+        let mut jumps = BTreeMap::<usize, usize>::new();
+        let mut sizes = BTreeMap::<usize, Option<usize>>::new();
+        for (i, entry) in trace.iter().enumerate() {
+            let Some(offset) = program_offset(entry.pc) else {
+                continue;
+            };
+            *pc_hits.entry(offset).or_default() += 1;
+            let size = *sizes
+                .entry(offset)
+                .or_insert_with(|| conditional_jump_size(runner, entry.pc));
+            let next_pc = trace.get(i + 1).map(|next| next.pc);
+            if let (Some(size), Some(next_pc)) = (size, next_pc) {
+                if (entry.pc + size).ok() != Some(next_pc) {
+                    *jumps.entry(offset).or_default() += 1;
+                }
+            }
+        }
+
+        let Some(locations) = runner
+            .program
+            .shared_program_data
+            .instruction_locations
+            .as_ref()
+        else {
+            return Ok(CoverageReport::default());
+        };
+        let mut files = BTreeMap::<String, FileCoverage>::new();
+        for (offset, location) in locations {
+            let hits = pc_hits.get(offset).copied().unwrap_or_default();
+            let file = file_coverage(&mut files, location);
+            let line_hits = file.lines.entry(location.inst.start_line).or_default();
+            *line_hits = (*line_hits).max(hits);
+
+            let is_conditional_jump = (program_base + *offset)
+                .ok()
+                .and_then(|pc| conditional_jump_size(runner, pc))
+                .is_some();
+            if is_conditional_jump {
+                let taken = jumps.get(offset).copied().unwrap_or_default();
+                // The last instruction of the trace has no successor, so it isn't counted as a branch outcome
+                let outcomes = if trace.last().and_then(|e| program_offset(e.pc)) == Some(*offset) {
+                    hits.saturating_sub(1)
+                } else {
+                    hits
+                };
+                file.branches.push(BranchCoverage {
+                    line: location.inst.start_line,
+                    pc: *offset,
+                    taken,
+                    not_taken: outcomes - taken,
+                });
+            }
+        }
+
+        for (name, identifier) in runner.program.iter_identifiers() {
+            if identifier.type_.as_deref() != Some("function") {
+                continue;
+            }
+            let Some((pc, location)) = identifier.pc.and_then(|pc| Some((pc, locations.get(&pc)?)))
+            else {
+                continue;
+            };
+            file_coverage(&mut files, location).functions.insert(
+                name.to_string(),
+                FunctionCoverage {
+                    line: location.inst.start_line,
+                    hits: pc_hits.get(&pc).copied().unwrap_or_default(),
+                },
+            );
+        }
+
+        for file in files.values_mut() {
+            file.branches.sort_by_key(|branch| branch.pc);
+        }
+        Ok(CoverageReport { files })
+    }
+
+    /// Serializes the report in the lcov tracefile format
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::new();
+        for (filename, file) in &self.files {
+            // Writing to a String can't fail
+            let _ = writeln!(lcov, "TN:\nSF:{filename}");
+            for (name, function) in &file.functions {
+                let _ = writeln!(lcov, "FN:{},{name}", function.line);
+            }
+            for (name, function) in &file.functions {
+                let _ = writeln!(lcov, "FNDA:{},{name}", function.hits);
+            }
+            let _ = writeln!(lcov, "FNF:{}", file.functions.len());
+            let _ = writeln!(lcov, "FNH:{}", file.functions_hit());
+            for branch in &file.branches {
+                let executed = branch.taken + branch.not_taken > 0;
+                for (id, count) in [(0, branch.not_taken), (1, branch.taken)] {
+                    let count = if executed {
+                        count.to_string()
+                    } else {
+                        "-".to_string()
+                    };
+                    let _ = writeln!(lcov, "BRDA:{},{},{id},{count}", branch.line, branch.pc);
+                }
+            }
+            let _ = writeln!(lcov, "BRF:{}", file.branches.len() * 2);
+            let _ = writeln!(lcov, "BRH:{}", file.branches_hit());
+            for (line, hits) in &file.lines {
+                let _ = writeln!(lcov, "DA:{line},{hits}");
+            }
+            let _ = writeln!(lcov, "LF:{}", file.lines.len());
+            let _ = writeln!(lcov, "LH:{}", file.lines_hit());
+            lcov.push_str("end_of_record\n");
+        }
+        lcov
+    }
+
+    /// Renders the report as a standalone HTML page, with a summary table and the annotated
+    /// source of each file. `source` returns the source text of a file given its name, files
+    /// without it only list their covered lines.
+    pub fn to_html<F>(&self, mut source: F) -> String
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let mut html = String::from(HTML_HEADER);
+        html.push_str("<h1>Coverage report</h1>\n<table>\n<tr><th>File</th><th>Lines</th><th>Functions</th><th>Branches</th></tr>\n");
+        for (i, (filename, file)) in self.files.iter().enumerate() {
+            let _ = writeln!(
+                html,
+                "<tr><td><a href=\"#file{i}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(filename),
+                ratio(file.lines_hit(), file.lines.len()),
+                ratio(file.functions_hit(), file.functions.len()),
+                ratio(file.branches_hit(), file.branches.len() * 2),
+            );
+        }
+        html.push_str("</table>\n");
+
+        for (i, (filename, file)) in self.files.iter().enumerate() {
+            let _ = writeln!(
+                html,
+                "<h2 id=\"file{i}\">{}</h2>\n<table class=\"source\">",
+                escape_html(filename)
+            );
+            let source = source(filename);
+            let lines: Vec<(u32, &str)> = match &source {
+                Some(source) => (1..).zip(source.lines()).collect(),
+                None => file.lines.keys().map(|line| (*line, "")).collect(),
+            };
+            for (line, text) in lines {
+                let (class, hits) = match file.lines.get(&line) {
+                    Some(0) => ("miss", "0".to_string()),
+                    Some(hits) => {
+                        let partial = file
+                            .branches
+                            .iter()
+                            .any(|b| b.line == line && (b.taken == 0 || b.not_taken == 0));
+                        (if partial { "partial" } else { "hit" }, hits.to_string())
+                    }
+                    None => ("", String::new()),
+                };
+                let _ = writeln!(
+                    html,
+                    "<tr class=\"{class}\"><td class=\"line\">{line}</td><td class=\"hits\">{hits}</td><td><pre>{}</pre></td></tr>",
+                    escape_html(text)
+                );
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn file_coverage<'a>(
+    files: &'a mut BTreeMap<String, FileCoverage>,
+    location: &InstructionLocation,
+) -> &'a mut FileCoverage {
+    files
+        .entry(location.inst.input_file.filename.clone())
+        .or_default()
+}
+
+// Returns the size of the instruction at pc if it is a conditional jump
+fn conditional_jump_size(runner: &CairoRunner, pc: Relocatable) -> Option<usize> {
+    let encoding = runner.vm.get_integer(pc).ok()?.to_u64()?;
+    let instruction = decode_instruction(encoding).ok()?;
+    (instruction.pc_update == PcUpdate::Jnz).then(|| instruction.size())
+}
+
+fn ratio(hit: usize, total: usize) -> String {
+    if total == 0 {
+        return "-".to_string();
+    }
+    format!("{hit}/{total} ({:.1}%)", hit as f64 * 100.0 / total as f64)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_HEADER: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Coverage report</title>
+<style>
+body { font-family: sans-serif; }
+table { border-collapse: collapse; }
+td, th { padding: 0 8px; text-align: left; }
+pre { margin: 0; }
+.source td { font-family: monospace; }
+.line, .hits { color: #777; text-align: right; }
+.hit { background: #dfd; }
+.partial { background: #ffd; }
+.miss { background: #fdd; }
+</style>
+</head>
+<body>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::run_program_b;
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn coverage_without_trace() {
+        let runner = run_program_b(false, false);
+        assert_matches!(
+            CoverageReport::new(&runner),
+            Err(TraceError::TraceNotEnabled)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn coverage_report() {
+        let runner = run_program_b(true, false);
+        let report = CoverageReport::new(&runner).unwrap();
+        assert!(!report.files.is_empty());
+        let (_, file) = report
+            .files
+            .iter()
+            .find(|(_, file)| file.functions.contains_key("__main__.main"))
+            .unwrap();
+        assert_eq!(file.functions["__main__.main"].hits, 1);
+        assert!(file.lines_hit() > 0);
+
+        let lcov = report.to_lcov();
+        assert!(lcov.starts_with("TN:\nSF:"));
+        assert!(lcov.contains("FNDA:1,__main__.main\n"));
+        assert_eq!(lcov.matches("end_of_record").count(), report.files.len());
+
+        let html = report.to_html(|_| None);
+        assert!(html.contains("<h1>Coverage report</h1>"));
+        assert!(html.contains("class=\"hit\""));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn html_source() {
+        let mut file = FileCoverage::default();
+        file.lines.insert(2, 1);
+        file.lines.insert(3, 0);
+        let report = CoverageReport {
+            files: BTreeMap::from([("a.cairo".to_string(), file)]),
+        };
+        let html = report.to_html(|filename| {
+            (filename == "a.cairo")
+                .then(|| "func main() {\n    let x = 1 < 2;\n    ret;\n}".to_string())
+        });
+        assert!(html.contains("<tr class=\"\"><td class=\"line\">1</td><td class=\"hits\"></td><td><pre>func main() {</pre></td></tr>"));
+        assert!(html.contains("<tr class=\"hit\"><td class=\"line\">2</td><td class=\"hits\">1</td><td><pre>    let x = 1 &lt; 2;</pre></td></tr>"));
+        assert!(html.contains("<tr class=\"miss\"><td class=\"line\">3</td><td class=\"hits\">0</td><td><pre>    ret;</pre></td></tr>"));
+        assert!(html.contains("<td class=\"line\">4</td>"));
+
+        // Without the source only the lines with instructions are listed
+        let html = report.to_html(|_| None);
+        assert!(!html.contains("<td class=\"line\">1</td>"));
+        assert!(html.contains("<td class=\"line\">2</td>"));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn lcov_branches() {
+        let mut file = FileCoverage::default();
+        file.lines.insert(3, 2);
+        file.lines.insert(4, 0);
+        file.branches.push(BranchCoverage {
+            line: 3,
+            pc: 5,
+            taken: 2,
+            not_taken: 0,
+        });
+        file.branches.push(BranchCoverage {
+            line: 4,
+            pc: 9,
+            taken: 0,
+            not_taken: 0,
+        });
+        let report = CoverageReport {
+            files: BTreeMap::from([("a.cairo".to_string(), file)]),
+        };
+        assert_eq!(
+            report.to_lcov(),
+            "TN:\nSF:a.cairo\nFNF:0\nFNH:0\nBRDA:3,5,0,0\nBRDA:3,5,1,2\nBRDA:4,9,0,-\nBRDA:4,9,1,-\nBRF:4\nBRH:1\nDA:3,2\nDA:4,0\nLF:2\nLH:1\nend_of_record\n"
+        );
+    }
+}
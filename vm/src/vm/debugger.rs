@@ -0,0 +1,668 @@
+//! Interactive debugger
+//!
+//! Runs a program step by step, stopping before the first instruction and at every breakpoint to
+//! read commands from an input stream. The following commands are available:
+//! - `break <pc|file:line|function>` (`b`): stops the execution before the given pc, the first instruction of a Cairo source line or the first instruction of a function
+//! - `delete <n>` (`d`): removes the n-th breakpoint
+//! - `breakpoints` (`bl`): lists the current breakpoints
+//! - `step [n]` (`s`): executes n instructions (1 by default)
+//! - `continue` (`c`): runs until the next breakpoint or the end of the program
+//! - `back [n]` (`bk`): undoes the last n instructions (1 by default), restoring the registers and memory
+//! - `registers` (`r`): prints the pc, ap and fp registers
+//! - `memory <addr> [n]` (`x`): prints n memory cells starting at addr, which can be `ap`, `fp` or `pc` with an optional offset (e.g. `fp-3`) or a `segment:offset` pair
+//! - `print ids.<name>` (`p`): prints the value of a Cairo 0 variable in scope, including struct members (e.g. `ids.point.x`)
+//! - `quit` (`q`): aborts the execution
+//!
+//! Hints at the current pc are executed together with its instruction, so the state shown when
+//! the debugger stops doesn't include the memory written by them yet.
+//! Stepping back doesn't undo the changes made by hints to the execution scopes.
+
+use crate::stdlib::{collections::HashMap, prelude::*};
+use std::io::{BufRead, Write};
+
+use crate::{
+    hint_processor::{
+        hint_processor_definition::HintProcessor,
+        hint_processor_utils::{compute_addr_from_reference, get_maybe_relocatable_from_reference},
+    },
+    serde::deserialize_program::{InstructionLocation, Member},
+    types::relocatable::{MaybeRelocatable, Relocatable},
+    vm::{
+        errors::vm_errors::VirtualMachineError, runners::cairo_runner::CairoRunner,
+        vm_core::VirtualMachine,
+    },
+};
+
+const HELP: &str = "\
+break <pc|file:line|function>  (b)   Set a breakpoint
+delete <n>                     (d)   Delete the n-th breakpoint
+breakpoints                    (bl)  List breakpoints
+step [n]                       (s)   Execute n instructions (1 by default)
+continue                       (c)   Run until the next breakpoint or the end of the program
+back [n]                       (bk)  Undo the last n instructions (1 by default)
+registers                      (r)   Print the pc, ap and fp registers
+memory <addr> [n]              (x)   Print n memory cells starting at addr (e.g. ap, fp-3, 1:4)
+print ids.<name>               (p)   Print the value of a variable in scope
+help                           (h)   Print this message
+quit                           (q)   Abort the execution";
+
+struct Breakpoint {
+    description: String,
+    pcs: Vec<Relocatable>,
+}
+
+enum Resume {
+    Step(usize),
+    Continue,
+    Quit,
+}
+
+pub struct Debugger<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Debugger {
+            input,
+            output,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Runs the program until `address` is reached, handing control over to the user before the
+    /// first step and every time a breakpoint is hit.
+    /// Returns [VirtualMachineError::UnfinishedExecution] if the user quits the debugger.
+    pub fn run_until_pc(
+        &mut self,
+        runner: &mut CairoRunner,
+        address: Relocatable,
+        hint_processor: &mut dyn HintProcessor,
+    ) -> Result<(), VirtualMachineError> {
+        if runner.vm.get_pc() == address {
+            return Ok(());
+        }
+        runner.vm.enable_journal();
+        let mut hints = runner.compile_hints(hint_processor)?;
+        self.print_location(runner)?;
+        loop {
+            let stopped = match self.read_commands(runner)? {
+                Resume::Step(n) => {
+                    let mut steps = 0;
+                    runner.run_until_pc_or_breakpoint(
+                        address,
+                        hint_processor,
+                        &mut hints,
+                        |_| {
+                            steps += 1;
+                            steps > n
+                        },
+                    )?
+                }
+                Resume::Continue => {
+                    let pcs: Vec<Relocatable> = self
+                        .breakpoints
+                        .iter()
+                        .flat_map(|b| b.pcs.iter().copied())
+                        .collect();
+                    let mut first_step = true;
+                    runner.run_until_pc_or_breakpoint(
+                        address,
+                        hint_processor,
+                        &mut hints,
+                        |vm| !core::mem::take(&mut first_step) && pcs.contains(&vm.get_pc()),
+                    )?
+                }
+                Resume::Quit => return Err(VirtualMachineError::UnfinishedExecution),
+            };
+            if !stopped {
+                writeln!(
+                    self.output,
+                    "Program finished after {} steps",
+                    runner.vm.current_step
+                )
+                .map_err(io_error)?;
+                return Ok(());
+            }
+            self.print_location(runner)?;
+        }
+    }
+
+    // Reads and executes commands until one of them resumes the execution.
+    // Reaching the end of the input is handled as a `quit` command.
+    fn read_commands(&mut self, runner: &mut CairoRunner) -> Result<Resume, VirtualMachineError> {
+        loop {
+            write!(self.output, "(cairo-debug) ").map_err(io_error)?;
+            self.output.flush().map_err(io_error)?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line).map_err(io_error)? == 0 {
+                return Ok(Resume::Quit);
+            }
+            let mut words = line.split_whitespace();
+            let Some(command) = words.next() else {
+                continue;
+            };
+            let args: Vec<&str> = words.collect();
+            let message = match (command, args.as_slice()) {
+                ("step" | "s", []) => return Ok(Resume::Step(1)),
+                ("step" | "s", [n]) => match n.parse::<usize>() {
+                    Ok(n) => return Ok(Resume::Step(n)),
+                    Err(_) => format!("Invalid number of steps: {n}"),
+                },
+                ("continue" | "c", []) => return Ok(Resume::Continue),
+                ("back" | "bk", []) => step_back(runner, "1"),
+                ("back" | "bk", [n]) => step_back(runner, n),
+                ("quit" | "q", []) => return Ok(Resume::Quit),
+                ("break" | "b", [target]) => self.add_breakpoint(runner, target),
+                ("delete" | "d", [n]) => self.delete_breakpoint(n),
+                ("breakpoints" | "bl", []) => self.list_breakpoints(),
+                ("registers" | "r", []) => format_registers(&runner.vm),
+                ("memory" | "x", [addr]) => format_memory(&runner.vm, addr, "1"),
+                ("memory" | "x", [addr, len]) => format_memory(&runner.vm, addr, len),
+                ("print" | "p", [expr]) => format_ids(runner, expr),
+                ("help" | "h", []) => HELP.to_string(),
+                _ => format!(
+                    "Unknown command: {}. Type `help` for a list of commands",
+                    line.trim()
+                ),
+            };
+            writeln!(self.output, "{message}").map_err(io_error)?;
+        }
+    }
+
+    fn print_location(&mut self, runner: &CairoRunner) -> Result<(), VirtualMachineError> {
+        writeln!(self.output, "{}", format_location(runner)).map_err(io_error)
+    }
+
+    fn add_breakpoint(&mut self, runner: &CairoRunner, target: &str) -> String {
+        let pcs = if let Some(pc) = parse_pc(runner, target) {
+            vec![pc]
+        } else if let Some((file, line)) = target
+            .rsplit_once(':')
+            .and_then(|(file, line)| Some((file, line.parse::<u32>().ok()?)))
+        {
+            line_pcs(runner, file, line)
+        } else {
+            function_pcs(runner, target)
+        };
+        if pcs.is_empty() {
+            return format!("No instruction found for {target}");
+        }
+        self.breakpoints.push(Breakpoint {
+            description: target.to_string(),
+            pcs,
+        });
+        format!("Breakpoint {} set at {target}", self.breakpoints.len())
+    }
+
+    fn delete_breakpoint(&mut self, n: &str) -> String {
+        match n.parse::<usize>() {
+            Ok(n) if n > 0 && n <= self.breakpoints.len() => {
+                self.breakpoints.remove(n - 1);
+                format!("Breakpoint {n} deleted")
+            }
+            _ => format!("No breakpoint number {n}"),
+        }
+    }
+
+    fn list_breakpoints(&self) -> String {
+        if self.breakpoints.is_empty() {
+            return "No breakpoints".to_string();
+        }
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let pcs: Vec<String> = b.pcs.iter().map(|pc| pc.to_string()).collect();
+                format!("{}: {} (pc {})", i + 1, b.description, pcs.join(", "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn format_location(runner: &CairoRunner) -> String {
+    let mut message = format_registers(&runner.vm);
+    if let Some(function) = function_at(runner, runner.vm.get_pc()) {
+        message.push_str(&format!(" in {function}"));
+    }
+    if let Some(location) = instruction_location(runner, runner.vm.get_pc()) {
+        message.push_str(&format!(
+            " at {}:{}",
+            location.inst.input_file.filename, location.inst.start_line
+        ));
+    }
+    message
+}
+
+fn step_back(runner: &mut CairoRunner, n: &str) -> String {
+    let Ok(n) = n.parse::<usize>() else {
+        return format!("Invalid number of steps: {n}");
+    };
+    match runner.vm.step_back(n) {
+        Ok(()) => format_location(runner),
+        Err(error) => error.to_string(),
+    }
+}
+
+fn io_error(error: std::io::Error) -> VirtualMachineError {
+    VirtualMachineError::Other(error.into())
+}
+
+fn program_base(runner: &CairoRunner) -> Relocatable {
+    runner.program_base.unwrap_or_default()
+}
+
+// Returns the offset of pc within the program segment
+fn program_offset(runner: &CairoRunner, pc: Relocatable) -> Option<usize> {
+    let base = program_base(runner);
+    if pc.segment_index != base.segment_index {
+        return None;
+    }
+    pc.offset.checked_sub(base.offset)
+}
+
+fn instruction_location(runner: &CairoRunner, pc: Relocatable) -> Option<&InstructionLocation> {
+    runner
+        .program
+        .shared_program_data
+        .instruction_locations
+        .as_ref()?
+        .get(&program_offset(runner, pc)?)
+}
+
+// Returns the name of the function with the closest start pc before the given pc
+fn function_at(runner: &CairoRunner, pc: Relocatable) -> Option<&str> {
+    let offset = program_offset(runner, pc)?;
+    runner
+        .program
+        .iter_identifiers()
+        .filter(|(_, identifier)| identifier.type_.as_deref() == Some("function"))
+        .filter_map(|(name, identifier)| Some((name, identifier.pc?)))
+        .filter(|(_, function_pc)| *function_pc <= offset)
+        .max_by_key(|(_, function_pc)| *function_pc)
+        .map(|(name, _)| name)
+}
+
+// Parses either an offset within the program segment or a `segment:offset` pair
+fn parse_pc(runner: &CairoRunner, target: &str) -> Option<Relocatable> {
+    if let Ok(offset) = target.parse::<usize>() {
+        return (program_base(runner) + offset).ok();
+    }
+    parse_relocatable(target)
+}
+
+fn parse_relocatable(value: &str) -> Option<Relocatable> {
+    let (segment, offset) = value.split_once(':')?;
+    Some(Relocatable::from((
+        segment.parse::<isize>().ok()?,
+        offset.parse::<usize>().ok()?,
+    )))
+}
+
+// Returns the first pc of each block of consecutive instructions located at the given source line
+fn line_pcs(runner: &CairoRunner, file: &str, line: u32) -> Vec<Relocatable> {
+    let Some(locations) = &runner.program.shared_program_data.instruction_locations else {
+        return Vec::new();
+    };
+    let mut offsets: Vec<(&usize, &InstructionLocation)> = locations.iter().collect();
+    offsets.sort_by_key(|(offset, _)| **offset);
+    let mut previous_matched = false;
+    let mut pcs = Vec::new();
+    for (offset, location) in offsets {
+        let matches =
+            location.inst.start_line == line && location.inst.input_file.filename.ends_with(file);
+        if matches && !previous_matched {
+            if let Ok(pc) = program_base(runner) + *offset {
+                pcs.push(pc);
+            }
+        }
+        previous_matched = matches;
+    }
+    pcs
+}
+
+// Matches either the full name of the function or its last component
+fn function_pcs(runner: &CairoRunner, name: &str) -> Vec<Relocatable> {
+    // Cairo 0 paths are separated by `.`, Cairo 1 paths by `::`
+    let suffixes = [format!(".{name}"), format!("::{name}")];
+    runner
+        .program
+        .iter_identifiers()
+        .filter(|(full_name, identifier)| {
+            identifier.type_.as_deref() == Some("function")
+                && (*full_name == name || suffixes.iter().any(|s| full_name.ends_with(s)))
+        })
+        .filter_map(|(_, identifier)| (program_base(runner) + identifier.pc?).ok())
+        .collect()
+}
+
+fn format_registers(vm: &VirtualMachine) -> String {
+    format!("pc={} ap={} fp={}", vm.get_pc(), vm.get_ap(), vm.get_fp())
+}
+
+// Parses `ap`, `fp` or `pc` with an optional offset (`fp-3`) or a `segment:offset` pair
+fn parse_address(vm: &VirtualMachine, addr: &str) -> Option<Relocatable> {
+    if let Some(addr) = parse_relocatable(addr) {
+        return Some(addr);
+    }
+    let split = addr.find(['+', '-']).unwrap_or(addr.len());
+    let (register, offset) = addr.split_at(split);
+    let base = match register {
+        "ap" => vm.get_ap(),
+        "fp" => vm.get_fp(),
+        "pc" => vm.get_pc(),
+        _ => return None,
+    };
+    match offset.strip_prefix('+') {
+        _ if offset.is_empty() => Some(base),
+        Some(offset) => (base + offset.parse::<usize>().ok()?).ok(),
+        None => (base - offset.strip_prefix('-')?.parse::<usize>().ok()?).ok(),
+    }
+}
+
+fn format_memory(vm: &VirtualMachine, addr: &str, len: &str) -> String {
+    let Some(addr) = parse_address(vm, addr) else {
+        return format!("Invalid address: {addr}");
+    };
+    let Ok(len) = len.parse::<usize>() else {
+        return format!("Invalid number of cells: {len}");
+    };
+    (0..len)
+        .filter_map(|i| (addr + i).ok())
+        .map(|addr| match vm.get_maybe(&addr) {
+            Some(value) => format!("[{addr}] = {value}"),
+            None => format!("[{addr}] = <unknown>"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Resolves an `ids.<name>[.<member>]*` expression through the references in scope at the current pc
+fn format_ids(runner: &CairoRunner, expr: &str) -> String {
+    let Some(path) = expr.strip_prefix("ids.") else {
+        return format!("Invalid expression: {expr}. Expected ids.<name>");
+    };
+    let vm = &runner.vm;
+    let Some((location, flow_tracking_data)) = instruction_location(runner, vm.get_pc())
+        .and_then(|location| Some((location, location.flow_tracking_data.as_ref()?)))
+    else {
+        return "No reference information available for the current pc".to_string();
+    };
+    let mut members = path.split('.');
+    let name = members.next().unwrap_or_default();
+    let Some(reference) = location
+        .accessible_scopes
+        .iter()
+        .rev()
+        .find_map(|scope| {
+            flow_tracking_data
+                .reference_ids
+                .get(&format!("{scope}.{name}"))
+        })
+        .and_then(|id| {
+            runner
+                .program
+                .shared_program_data
+                .reference_manager
+                .get(*id)
+        })
+    else {
+        return format!("Unknown variable: {name}");
+    };
+    let ap_tracking = &flow_tracking_data.ap_tracking;
+    let mut addr = compute_addr_from_reference(reference, vm, ap_tracking);
+    let mut value = get_maybe_relocatable_from_reference(vm, reference, ap_tracking);
+    let mut cairo_type = reference.cairo_type.clone().unwrap_or_default();
+
+    for member_name in members {
+        // Members of struct pointers are accessed through the pointer value
+        let (base, struct_name) = match cairo_type.strip_suffix('*') {
+            Some(struct_name) => (value.and_then(|v| v.get_relocatable()), struct_name),
+            None => (addr, cairo_type.as_str()),
+        };
+        let Some(member) = struct_members(runner, struct_name)
+            .and_then(|members| members.get(member_name).cloned())
+        else {
+            return format!("Unknown member {member_name} of type {cairo_type}");
+        };
+        addr = base.and_then(|base| (base + member.offset).ok());
+        value = addr.and_then(|addr| vm.get_maybe(&addr));
+        cairo_type = member.cairo_type;
+    }
+
+    match struct_members(runner, &cairo_type) {
+        Some(members) => {
+            let mut members: Vec<(&String, &Member)> = members.iter().collect();
+            members.sort_by_key(|(_, member)| member.offset);
+            let fields: Vec<String> = members
+                .into_iter()
+                .map(|(name, member)| {
+                    let value = addr
+                        .and_then(|addr| (addr + member.offset).ok())
+                        .and_then(|addr| vm.get_maybe(&addr));
+                    format!("{name}: {}", format_value(value))
+                })
+                .collect();
+            format!("{cairo_type}({})", fields.join(", "))
+        }
+        None => format_value(value),
+    }
+}
+
+fn struct_members<'a>(runner: &'a CairoRunner, name: &str) -> Option<&'a HashMap<String, Member>> {
+    runner
+        .program
+        .get_identifier(name)
+        .filter(|identifier| identifier.type_.as_deref() == Some("struct"))?
+        .members
+        .as_ref()
+}
+
+fn format_value(value: Option<MaybeRelocatable>) -> String {
+    value.map_or_else(|| "<unknown>".to_string(), |value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::{layout_name::LayoutName, program::Program},
+        utils::test_utils::{cairo_runner, program_b},
+    };
+
+    fn run_with_commands(commands: &str) -> (String, bool) {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runner = cairo_runner!(program_b(), LayoutName::small);
+        let end = runner.initialize(false).unwrap();
+        let mut debugger = Debugger::new(commands.as_bytes(), Vec::new());
+        let finished = debugger
+            .run_until_pc(&mut runner, end, &mut hint_processor)
+            .is_ok();
+        (String::from_utf8(debugger.output).unwrap(), finished)
+    }
+
+    #[test]
+    fn continue_without_breakpoints_runs_to_completion() {
+        let (output, finished) = run_with_commands("c\n");
+        assert!(finished);
+        assert_eq!(
+            output,
+            "pc=0:13 ap=1:4 fp=1:4 in __main__.main at main1.cairo:14\n\
+             (cairo-debug) Program finished after 18 steps\n"
+        );
+    }
+
+    #[test]
+    fn quit_aborts_execution() {
+        let (output, finished) = run_with_commands("s\nq\n");
+        assert!(!finished);
+        assert_eq!(
+            output,
+            "pc=0:13 ap=1:4 fp=1:4 in __main__.main at main1.cairo:14\n\
+             (cairo-debug) pc=0:14 ap=1:5 fp=1:4 in __main__.main at main1.cairo:15\n\
+             (cairo-debug) "
+        );
+    }
+
+    #[test]
+    fn end_of_input_aborts_execution() {
+        let (_, finished) = run_with_commands("s 3\n");
+        assert!(!finished);
+    }
+
+    #[test]
+    fn step_and_line_breakpoints() {
+        let (output, finished) = run_with_commands(
+            "b main1.cairo:15\nb main1.cairo:9\nbl\ns 2\nc\nr\nx fp-4 4\nc\nd 2\nc\n",
+        );
+        assert!(finished);
+        assert_eq!(
+            output,
+            "pc=0:13 ap=1:4 fp=1:4 in __main__.main at main1.cairo:14\n\
+             (cairo-debug) Breakpoint 1 set at main1.cairo:15\n\
+             (cairo-debug) Breakpoint 2 set at main1.cairo:9\n\
+             (cairo-debug) 1: main1.cairo:15 (pc 0:14, 0:19)\n\
+             2: main1.cairo:9 (pc 0:5)\n\
+             (cairo-debug) pc=0:16 ap=1:6 fp=1:4 in __main__.main at main1.cairo:15\n\
+             (cairo-debug) pc=0:5 ap=1:8 fp=1:8 in __main__.check_range at main1.cairo:9\n\
+             (cairo-debug) pc=0:5 ap=1:8 fp=1:8\n\
+             (cairo-debug) [1:4] = 3:0\n\
+             [1:5] = 7\n\
+             [1:6] = 1:4\n\
+             [1:7] = 0:18\n\
+             (cairo-debug) pc=0:19 ap=1:13 fp=1:4 in __main__.main at main1.cairo:15\n\
+             (cairo-debug) Breakpoint 2 deleted\n\
+             (cairo-debug) Program finished after 18 steps\n"
+        );
+    }
+
+    #[test]
+    fn function_breakpoint_and_ids() {
+        let (output, finished) =
+            run_with_commands("b serialize_word\nc\np ids.word\np ids.output_ptr\nc\n");
+        assert!(finished);
+        assert!(output.contains(
+            "(cairo-debug) Breakpoint 1 set at serialize_word\n\
+             (cairo-debug) pc=0:0 ap=1:16 fp=1:16 in starkware.cairo.common.serialize.serialize_word"
+        ));
+        assert!(output.contains("(cairo-debug) 7\n(cairo-debug) 2:0\n"));
+    }
+
+    #[test]
+    fn pc_breakpoint() {
+        let (output, finished) = run_with_commands("b 4\nb 0:5\nbl\nc\nc\nc\n");
+        assert!(finished);
+        assert!(output.contains("1: 4 (pc 0:4)\n2: 0:5 (pc 0:5)\n"));
+        assert!(output.contains(
+            "(cairo-debug) pc=0:4 ap=1:8 fp=1:8 in __main__.check_range at main1.cairo:8\n\
+             (cairo-debug) pc=0:5 ap=1:8 fp=1:8 in __main__.check_range at main1.cairo:9\n\
+             (cairo-debug) Program finished after 18 steps\n"
+        ));
+    }
+
+    #[test]
+    fn step_back() {
+        let (output, finished) = run_with_commands("s 2\nbk\nbk 5\nbk x\nc\n");
+        assert!(finished);
+        assert_eq!(
+            output,
+            "pc=0:13 ap=1:4 fp=1:4 in __main__.main at main1.cairo:14\n\
+             (cairo-debug) pc=0:16 ap=1:6 fp=1:4 in __main__.main at main1.cairo:15\n\
+             (cairo-debug) pc=0:14 ap=1:5 fp=1:4 in __main__.main at main1.cairo:15\n\
+             (cairo-debug) Can't step back 5 steps, only 1 steps were journaled\n\
+             (cairo-debug) Invalid number of steps: x\n\
+             (cairo-debug) Program finished after 18 steps\n"
+        );
+    }
+
+    #[test]
+    fn parse_address_registers_and_relocatables() {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_ap(5);
+        vm.set_fp(3);
+        assert_eq!(parse_address(&vm, "ap"), Some(Relocatable::from((1, 5))));
+        assert_eq!(parse_address(&vm, "ap+2"), Some(Relocatable::from((1, 7))));
+        assert_eq!(parse_address(&vm, "fp-3"), Some(Relocatable::from((1, 0))));
+        assert_eq!(parse_address(&vm, "fp-4"), None);
+        assert_eq!(parse_address(&vm, "2:4"), Some(Relocatable::from((2, 4))));
+        assert_eq!(parse_address(&vm, "sp"), None);
+    }
+
+    #[test]
+    fn unknown_command_and_invalid_arguments() {
+        let (output, finished) =
+            run_with_commands("foo\ns abc\nd 3\nx sp\nb not_a_function\np x\np ids.y\nbl\n");
+        assert!(!finished);
+        assert!(output.contains("Unknown command: foo"));
+        assert!(output.contains("Invalid number of steps: abc"));
+        assert!(output.contains("No breakpoint number 3"));
+        assert!(output.contains("Invalid address: sp"));
+        assert!(output.contains("No instruction found for not_a_function"));
+        assert!(output.contains("Invalid expression: x"));
+        assert!(output.contains("Unknown variable: y"));
+        assert!(output.contains("No breakpoints"));
+    }
+
+    #[test]
+    fn hints_are_compiled_once_across_resumes() {
+        use crate::{
+            any_box,
+            hint_processor::hint_processor_definition::HintProcessorLogic,
+            serde::deserialize_program::ApTracking,
+            types::exec_scope::ExecutionScopes,
+            vm::{errors::hint_errors::HintError, runners::cairo_runner::ResourceTracker},
+            Felt252,
+        };
+        use core::{any::Any, cell::Cell};
+
+        // Counts the compiled hints, ignoring their execution
+        #[derive(Default)]
+        struct CountingHintProcessor {
+            compiled: Cell<usize>,
+        }
+
+        impl HintProcessorLogic for CountingHintProcessor {
+            fn execute_hint(
+                &mut self,
+                _vm: &mut VirtualMachine,
+                _exec_scopes: &mut ExecutionScopes,
+                _hint_data: &Box<dyn Any>,
+                _constants: &HashMap<String, Felt252>,
+            ) -> Result<(), HintError> {
+                Ok(())
+            }
+
+            fn compile_hint(
+                &self,
+                _hint_code: &str,
+                _ap_tracking_data: &ApTracking,
+                _reference_ids: &HashMap<String, usize>,
+                _references: &[crate::hint_processor::hint_processor_definition::HintReference],
+            ) -> Result<Box<dyn Any>, VirtualMachineError> {
+                self.compiled.set(self.compiled.get() + 1);
+                Ok(any_box!(()))
+            }
+        }
+
+        impl ResourceTracker for CountingHintProcessor {}
+
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/manually_compiled/valid_program_a.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut hint_processor = CountingHintProcessor::default();
+        let mut runner = cairo_runner!(program);
+        let end = runner.initialize(false).unwrap();
+        let mut debugger = Debugger::new("s\ns\ns 2\nc\n".as_bytes(), Vec::new());
+        debugger
+            .run_until_pc(&mut runner, end, &mut hint_processor)
+            .unwrap();
+        // valid_program_a has two hints
+        assert_eq!(hint_processor.compiled.get(), 2);
+    }
+}
@@ -0,0 +1,539 @@
+//! Differential testing against a reference execution
+//!
+//! Runs a program comparing it with the relocated trace and memory of a reference run of the same
+//! program, for example one made with the Python VM of cairo-lang, and stops at the first
+//! difference:
+//! - The registers are compared before every step.
+//! - The cells of the program and execution segments are compared as soon as they are written,
+//!   as their relocated addresses are already known during the run.
+//! - The rest of the memory and the steps executed after reaching the end of the program (proof
+//!   mode and trace padding) are compared once the run is relocated.
+//!
+//! A [Divergence] points to the instruction that caused the difference, together with its hints
+//! and source location.
+
+use crate::stdlib::{collections::BTreeMap, prelude::*};
+use core::{cmp::Ordering, fmt};
+
+use crate::{
+    hint_processor::hint_processor_definition::HintProcessor,
+    serde::deserialize_program::HintParams,
+    types::relocatable::{MaybeRelocatable, Relocatable},
+    vm::{
+        errors::{
+            cairo_run_errors::CairoRunError, trace_errors::TraceError,
+            vm_errors::VirtualMachineError, vm_exception::get_location,
+        },
+        journal::MemoryJournalEntry,
+        runners::cairo_runner::CairoRunner,
+        trace::trace_entry::RelocatedTraceEntry,
+        vm_core::VirtualMachine,
+    },
+    Felt252,
+};
+
+/// Relocated addresses of the program and execution segments
+type SegmentBases = [usize; 2];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// A register holds a different relocated address
+    Register {
+        register: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// A memory cell holds a different value, `None` if the cell isn't set
+    Memory {
+        address: usize,
+        expected: Option<Felt252>,
+        actual: Option<Felt252>,
+    },
+    /// The run kept executing after the end of the reference trace
+    ExtraSteps,
+    /// The run finished before the end of the reference trace
+    MissingSteps { reference_steps: usize },
+}
+
+impl fmt::Display for DivergenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format_cell = |value: &Option<Felt252>| match value {
+            Some(value) => value.to_string(),
+            None => "<unset>".to_string(),
+        };
+        match self {
+            DivergenceKind::Register {
+                register,
+                expected,
+                actual,
+            } => write!(f, "{register} is {actual} instead of {expected}"),
+            DivergenceKind::Memory {
+                address,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "memory cell {address} is {} instead of {}",
+                format_cell(actual),
+                format_cell(expected)
+            ),
+            DivergenceKind::ExtraSteps => write!(f, "the reference trace ended"),
+            DivergenceKind::MissingSteps { reference_steps } => write!(
+                f,
+                "the run finished but the reference trace has {reference_steps} steps"
+            ),
+        }
+    }
+}
+
+/// First difference between a run and the reference execution
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// Number of steps executed when the difference appeared
+    pub step: usize,
+    pub kind: DivergenceKind,
+    /// Pc of the instruction that caused the difference, `None` if it doesn't come from an
+    /// instruction (e.g. the initial state of the run)
+    pub pc: Option<Relocatable>,
+    /// Encoded instruction at `pc`
+    pub instruction: Option<Felt252>,
+    /// Code of the hints executed before the instruction
+    pub hints: Vec<String>,
+    /// Source location of the instruction, including the source code if it can be read
+    pub location: Option<String>,
+}
+
+impl Divergence {
+    fn new(
+        runner: &CairoRunner,
+        step: usize,
+        kind: DivergenceKind,
+        pc: Option<Relocatable>,
+    ) -> Self {
+        let program_offset = pc.filter(|pc| pc.segment_index == 0).map(|pc| pc.offset);
+        let hints = program_offset
+            .and_then(|offset| {
+                BTreeMap::<usize, Vec<HintParams>>::from(
+                    &runner.program.shared_program_data.hints_collection,
+                )
+                .remove(&offset)
+            })
+            .unwrap_or_default();
+        Divergence {
+            step,
+            kind,
+            pc,
+            instruction: pc.and_then(|pc| runner.vm.get_integer(pc).ok().map(|value| *value)),
+            hints: hints.into_iter().map(|hint| hint.code).collect(),
+            location: pc.zip(program_offset).and_then(|(pc, offset)| {
+                get_location(offset, runner, None)
+                    .map(|location| location.to_string_with_content(&format!("(pc={pc})")))
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Diverged from the reference after {} steps: {}",
+            self.step, self.kind
+        )?;
+        match (self.pc, self.instruction) {
+            (Some(pc), Some(instruction)) => {
+                writeln!(f, "Instruction at pc={pc}: {}", instruction.to_hex_string())?
+            }
+            (Some(pc), None) => writeln!(f, "Instruction at pc={pc}")?,
+            (None, _) => writeln!(f, "The difference doesn't come from an instruction")?,
+        }
+        for hint in self.hints.iter() {
+            writeln!(f, "Hint:\n%{{\n{hint}\n%}}")?;
+        }
+        if let Some(location) = &self.location {
+            writeln!(f, "{location}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares a run with the relocated trace and memory of a reference execution
+pub struct DifferentialChecker {
+    trace: Vec<RelocatedTraceEntry>,
+    memory: Vec<Option<Felt252>>,
+    divergence: Option<Divergence>,
+}
+
+impl DifferentialChecker {
+    pub fn new(trace: Vec<RelocatedTraceEntry>, memory: Vec<Option<Felt252>>) -> Self {
+        DifferentialChecker {
+            trace,
+            memory,
+            divergence: None,
+        }
+    }
+
+    /// Returns the first difference found with the reference execution
+    pub fn divergence(&self) -> Option<&Divergence> {
+        self.divergence.as_ref()
+    }
+
+    /// Runs the program until `address` is reached, comparing the registers and the cells
+    /// written by every step with the reference execution.
+    /// Returns [VirtualMachineError::DivergedFromReference] at the first difference, which is
+    /// then available through [DifferentialChecker::divergence].
+    pub fn run_until_pc(
+        &mut self,
+        runner: &mut CairoRunner,
+        address: Relocatable,
+        hint_processor: &mut dyn HintProcessor,
+    ) -> Result<(), VirtualMachineError> {
+        // The journal records the cells written by each step
+        runner.vm.enable_journal();
+        let program_len = runner
+            .vm
+            .segments
+            .memory
+            .data
+            .segment_len(0)
+            .unwrap_or_default();
+        // Memory is relocated from address 1, starting with the program and execution segments
+        let bases = [1, 1 + program_len];
+
+        let mut found = self
+            .check_initial_memory(&runner.vm, &bases)
+            .map(|kind| (0, kind, None));
+        let mut journal_len = 0;
+        let mut last_pc = None;
+        if found.is_none() {
+            let mut hints = runner.compile_hints(hint_processor)?;
+            runner.run_until_pc_or_breakpoint(address, hint_processor, &mut hints, |vm| {
+                found = self
+                    .check_step(vm, &bases, &mut journal_len)
+                    .map(|kind| (vm.current_step, kind, last_pc));
+                last_pc = Some(vm.get_pc());
+                found.is_some()
+            })?;
+        }
+        if found.is_none() {
+            found = self
+                .check_written_memory(&runner.vm, &bases, &mut journal_len)
+                .map(|kind| (runner.vm.current_step, kind, last_pc));
+        }
+
+        match found {
+            Some((step, kind, pc)) => {
+                self.divergence = Some(Divergence::new(runner, step, kind, pc));
+                Err(VirtualMachineError::DivergedFromReference(step))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Compares the relocated trace and memory of a finished run with the reference execution.
+    /// Returns [VirtualMachineError::DivergedFromReference] at the first difference, which is
+    /// then available through [DifferentialChecker::divergence].
+    pub fn check_relocated(&mut self, runner: &CairoRunner) -> Result<(), CairoRunError> {
+        let trace = runner
+            .relocated_trace
+            .as_ref()
+            .ok_or(TraceError::TraceNotRelocated)?;
+        let step_pc = |step: usize| Some(runner.vm.journal.as_ref()?.get(step)?.pc);
+        let previous_step_pc = |step: usize| step.checked_sub(1).and_then(step_pc);
+
+        let found = self
+            .compare_trace(trace)
+            .map(|(step, kind)| (step, kind, previous_step_pc(step)))
+            .or_else(|| {
+                let kind = self.compare_memory(&runner.relocated_memory)?;
+                let DivergenceKind::Memory { address, .. } = kind else {
+                    return None;
+                };
+                match find_write_step(runner, address) {
+                    Some(step) => Some((step + 1, kind, step_pc(step))),
+                    None => Some((runner.vm.current_step, kind, None)),
+                }
+            });
+
+        match found {
+            Some((step, kind, pc)) => {
+                self.divergence = Some(Divergence::new(runner, step, kind, pc));
+                Err(VirtualMachineError::DivergedFromReference(step).into())
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Compares the initial cells of the program and execution segments
+    fn check_initial_memory(
+        &self,
+        vm: &VirtualMachine,
+        bases: &SegmentBases,
+    ) -> Option<DivergenceKind> {
+        (0..bases.len()).find_map(|segment| {
+            let len = vm.segments.memory.data.segment_len(segment)?;
+            (0..len)
+                .find_map(|offset| self.check_cell(vm, bases, (segment as isize, offset).into()))
+        })
+    }
+
+    // Compares the cells written by the previous step and the registers before the next one
+    fn check_step(
+        &self,
+        vm: &VirtualMachine,
+        bases: &SegmentBases,
+        journal_len: &mut usize,
+    ) -> Option<DivergenceKind> {
+        self.check_written_memory(vm, bases, journal_len)
+            .or_else(|| {
+                let Some(expected) = self.trace.get(vm.current_step) else {
+                    return Some(DivergenceKind::ExtraSteps);
+                };
+                [
+                    ("pc", vm.get_pc(), expected.pc),
+                    ("ap", vm.get_ap(), expected.ap),
+                    ("fp", vm.get_fp(), expected.fp),
+                ]
+                .into_iter()
+                .find_map(|(register, value, expected)| {
+                    let actual = relocate(bases, value)?;
+                    (actual != expected).then_some(DivergenceKind::Register {
+                        register,
+                        expected,
+                        actual,
+                    })
+                })
+            })
+    }
+
+    // Compares the cells written since the last call
+    fn check_written_memory(
+        &self,
+        vm: &VirtualMachine,
+        bases: &SegmentBases,
+        journal_len: &mut usize,
+    ) -> Option<DivergenceKind> {
+        let journal = vm.segments.memory.journal.as_ref()?;
+        let entries = journal.get(*journal_len..).unwrap_or_default();
+        *journal_len = journal.len();
+        entries.iter().find_map(|entry| match entry {
+            MemoryJournalEntry::Cell { address, .. } => self.check_cell(vm, bases, *address),
+            _ => None,
+        })
+    }
+
+    // Compares a cell of the program or execution segments, skipping the ones that aren't set yet
+    // or point to segments whose relocated address isn't known until the end of the run
+    fn check_cell(
+        &self,
+        vm: &VirtualMachine,
+        bases: &SegmentBases,
+        address: Relocatable,
+    ) -> Option<DivergenceKind> {
+        let relocated_address = relocate(bases, address)?;
+        let actual = match vm.get_maybe(&address)? {
+            MaybeRelocatable::Int(value) => value,
+            MaybeRelocatable::RelocatableValue(value) => relocate(bases, value)?.into(),
+        };
+        let expected = self.memory.get(relocated_address).copied().flatten();
+        (expected != Some(actual)).then_some(DivergenceKind::Memory {
+            address: relocated_address,
+            expected,
+            actual: Some(actual),
+        })
+    }
+
+    fn compare_trace(&self, trace: &[RelocatedTraceEntry]) -> Option<(usize, DivergenceKind)> {
+        for (step, (entry, expected)) in trace.iter().zip(self.trace.iter()).enumerate() {
+            for (register, actual, expected) in [
+                ("pc", entry.pc, expected.pc),
+                ("ap", entry.ap, expected.ap),
+                ("fp", entry.fp, expected.fp),
+            ] {
+                if actual != expected {
+                    let kind = DivergenceKind::Register {
+                        register,
+                        expected,
+                        actual,
+                    };
+                    return Some((step, kind));
+                }
+            }
+        }
+        match trace.len().cmp(&self.trace.len()) {
+            Ordering::Greater => Some((self.trace.len(), DivergenceKind::ExtraSteps)),
+            Ordering::Less => {
+                let kind = DivergenceKind::MissingSteps {
+                    reference_steps: self.trace.len(),
+                };
+                Some((trace.len(), kind))
+            }
+            Ordering::Equal => None,
+        }
+    }
+
+    fn compare_memory(&self, memory: &[Option<Felt252>]) -> Option<DivergenceKind> {
+        (0..memory.len().max(self.memory.len())).find_map(|address| {
+            let actual = memory.get(address).copied().flatten();
+            let expected = self.memory.get(address).copied().flatten();
+            (actual != expected).then_some(DivergenceKind::Memory {
+                address,
+                expected,
+                actual,
+            })
+        })
+    }
+}
+
+fn relocate(bases: &SegmentBases, address: Relocatable) -> Option<usize> {
+    let base = bases.get(usize::try_from(address.segment_index).ok()?)?;
+    Some(base + address.offset)
+}
+
+// Returns the step that wrote the cell at the given relocated address
+fn find_write_step(runner: &CairoRunner, address: usize) -> Option<usize> {
+    let relocation_table = runner.vm.segments.relocate_segments().ok()?;
+    let segment = relocation_table.iter().rposition(|base| *base <= address)?;
+    let address = Relocatable::from((segment as isize, address - relocation_table[segment]));
+    runner.vm.find_write_step(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cairo_run::{cairo_run_program, cairo_run_program_with_reference, CairoRunConfig},
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::layout_name::LayoutName,
+        utils::test_utils::program_b,
+    };
+    use assert_matches::assert_matches;
+
+    fn config() -> CairoRunConfig<'static> {
+        CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            layout: LayoutName::small,
+            ..Default::default()
+        }
+    }
+
+    // Runs the program to produce a reference trace and memory
+    fn reference() -> (Vec<RelocatedTraceEntry>, Vec<Option<Felt252>>) {
+        let runner = cairo_run_program(
+            &program_b(),
+            &config(),
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        (runner.relocated_trace.unwrap(), runner.relocated_memory)
+    }
+
+    fn run_against(
+        trace: Vec<RelocatedTraceEntry>,
+        memory: Vec<Option<Felt252>>,
+    ) -> (Result<CairoRunner, CairoRunError>, DifferentialChecker) {
+        let mut checker = DifferentialChecker::new(trace, memory);
+        let result = cairo_run_program_with_reference(
+            &program_b(),
+            &config(),
+            &mut BuiltinHintProcessor::new_empty(),
+            &mut checker,
+        );
+        (result, checker)
+    }
+
+    #[test]
+    fn same_execution() {
+        let (trace, memory) = reference();
+        let (result, checker) = run_against(trace, memory);
+        assert!(result.is_ok());
+        assert_eq!(checker.divergence(), None);
+    }
+
+    #[test]
+    fn diverging_register() {
+        let (mut trace, memory) = reference();
+        trace[3].ap += 1;
+        let (result, checker) = run_against(trace.clone(), memory);
+        assert_matches!(result.err(), Some(CairoRunError::VmException(_)));
+        let divergence = checker.divergence().unwrap();
+        assert_eq!(divergence.step, 3);
+        assert_eq!(
+            divergence.kind,
+            DivergenceKind::Register {
+                register: "ap",
+                expected: trace[3].ap,
+                actual: trace[3].ap - 1,
+            }
+        );
+        assert_eq!(divergence.pc, Some(Relocatable::from((0, trace[2].pc - 1))));
+        assert!(divergence.instruction.is_some());
+        assert!(divergence
+            .to_string()
+            .starts_with("Diverged from the reference after 3 steps: ap is"));
+    }
+
+    #[test]
+    fn diverging_execution_cell() {
+        let (trace, mut memory) = reference();
+        // The last cell written by the first step
+        let address = trace[1].ap - 1;
+        memory[address] = Some(Felt252::from(42));
+        let (result, checker) = run_against(trace.clone(), memory);
+        assert!(result.is_err());
+        let divergence = checker.divergence().unwrap();
+        assert_eq!(divergence.step, 1);
+        assert_matches!(
+            divergence.kind,
+            DivergenceKind::Memory { address: a, expected, .. } if a == address && expected == Some(Felt252::from(42))
+        );
+        assert_eq!(divergence.pc, Some(Relocatable::from((0, trace[0].pc - 1))));
+    }
+
+    #[test]
+    fn diverging_builtin_cell() {
+        let (trace, mut memory) = reference();
+        // The output builtin segment is relocated after the program and execution segments
+        let address = memory.len() - 1;
+        memory[address] = Some(Felt252::from(42));
+        let (result, checker) = run_against(trace, memory);
+        assert_matches!(
+            result.err(),
+            Some(CairoRunError::VirtualMachine(
+                VirtualMachineError::DivergedFromReference(_)
+            ))
+        );
+        let divergence = checker.divergence().unwrap();
+        assert_matches!(divergence.kind, DivergenceKind::Memory { address: a, .. } if a == address);
+        assert!(divergence.pc.is_some());
+    }
+
+    #[test]
+    fn shorter_reference() {
+        let (mut trace, memory) = reference();
+        trace.truncate(5);
+        let (result, checker) = run_against(trace, memory);
+        assert!(result.is_err());
+        let divergence = checker.divergence().unwrap();
+        assert_eq!(divergence.step, 5);
+        assert_eq!(divergence.kind, DivergenceKind::ExtraSteps);
+    }
+
+    #[test]
+    fn longer_reference() {
+        let (mut trace, memory) = reference();
+        let steps = trace.len();
+        trace.push(trace[0].clone());
+        let (result, checker) = run_against(trace, memory);
+        assert!(result.is_err());
+        let divergence = checker.divergence().unwrap();
+        assert_eq!(divergence.step, steps);
+        assert_eq!(
+            divergence.kind,
+            DivergenceKind::MissingSteps {
+                reference_steps: steps + 1
+            }
+        );
+    }
+}
@@ -8,7 +8,9 @@ use thiserror_no_std::Error;
 use crate::Felt252;
 use num_bigint::{BigInt, BigUint};
 
+use crate::program_fact::FactTopologyError;
 use crate::types::{
+    builtin_name::BuiltinName,
     errors::math_errors::MathError,
     relocatable::{MaybeRelocatable, Relocatable},
 };
@@ -190,6 +192,20 @@ pub enum HintError {
     ExcessBalanceKeyError(Box<str>),
     #[error("excess_balance_func: Failed to calculate {0}")]
     ExcessBalanceCalculationFailed(Box<str>),
+    #[error("Failed to load the program of the task: {0}")]
+    InvalidTaskProgram(Box<str>),
+    #[error("Computed hash {} does not match the input {}", (*.0).0, (*.0).1)]
+    ProgramHashMismatch(Box<(Felt252, Felt252)>),
+    #[error("Sanity check failed: ids.program_address is {}, expected {}", (*.0).0, (*.0).1)]
+    ProgramAddressMismatch(Box<(Relocatable, Relocatable)>),
+    #[error("Segment {0} of the Cairo PIE is not described by its metadata")]
+    UnknownPieSegment(isize),
+    #[error("Builtin usage of {0} is inconsistent with the Cairo PIE")]
+    InconsistentBuiltinUsage(BuiltinName),
+    #[error(transparent)]
+    InvalidFactTopology(#[from] FactTopologyError),
+    #[error("Running programs with hints in the bootloader requires the extensive_hints feature")]
+    TaskHintsNotSupported,
 }
 
 #[cfg(test)]
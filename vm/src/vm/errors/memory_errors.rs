@@ -101,6 +101,11 @@ pub enum MemoryError {
     UnrelocatedMemory,
     #[error("Malformed public memory")]
     MalformedPublicMemory,
+    #[error("Memory backends can only be replaced before adding any segment")]
+    NonEmptyMemoryBackend,
+    #[cfg(feature = "hooks")]
+    #[error("Memory write hook failed: {0}")]
+    MemoryWriteHook(Box<str>),
 }
 
 #[derive(Debug, PartialEq, Eq, Error)]
@@ -132,6 +132,10 @@ pub enum RunnerError {
     CairoPieProofMode,
     #[error("{0}: Invalid additional data")]
     InvalidAdditionalData(BuiltinName),
+    #[error("The snapshot doesn't match the runner: {0}")]
+    SnapshotMismatch(Box<String>),
+    #[error("Execution scope variable {0} has a type that can't be included in a snapshot")]
+    SnapshotUnsupportedScopeVariable(Box<String>),
 }
 
 #[cfg(test)]
@@ -1,5 +1,7 @@
 use thiserror_no_std::Error;
 
+use crate::stdlib::prelude::*;
+
 use crate::vm::errors::memory_errors::MemoryError;
 
 #[derive(Debug, PartialEq, Error)]
@@ -14,6 +16,10 @@ pub enum TraceError {
     MemoryError(#[from] MemoryError),
     #[error("Trace not relocated")]
     TraceNotRelocated,
+    #[error("The trace was written to a trace sink and isn't kept in memory")]
+    TraceStreamed,
+    #[error("Failed to stream the trace: {0}")]
+    Stream(Box<str>),
 }
 
 #[cfg(test)]
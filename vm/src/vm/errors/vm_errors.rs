@@ -136,6 +136,12 @@ pub enum VirtualMachineError {
     RelocationNotFound(usize),
     #[error("{} batch size is not {}", (*.0).0, (*.0).1)]
     ModBuiltinBatchSize(Box<(BuiltinName, usize)>),
+    #[error("The journal is not enabled")]
+    JournalNotEnabled,
+    #[error("Can't step back {} steps, only {} steps were journaled", (*.0).0, (*.0).1)]
+    StepBackOutOfJournal(Box<(usize, usize)>),
+    #[error("The execution diverged from the reference after {0} steps")]
+    DivergedFromReference(usize),
 }
 
 #[cfg(test)]
@@ -328,6 +328,8 @@ mod test {
         let instruction_location = InstructionLocation {
             inst: location.clone(),
             hints: vec![],
+            accessible_scopes: vec![],
+            flow_tracking_data: None,
         };
         let program = program!(
             instruction_locations = Some(HashMap::from([(pc.offset, instruction_location)])),
@@ -563,6 +565,8 @@ mod test {
         let instruction_location = InstructionLocation {
             inst: location.clone(),
             hints: vec![],
+            accessible_scopes: vec![],
+            flow_tracking_data: None,
         };
         let program =
             program!(instruction_locations = Some(HashMap::from([(2, instruction_location)])),);
@@ -586,6 +590,8 @@ mod test {
         let instruction_location = InstructionLocation {
             inst: location,
             hints: vec![],
+            accessible_scopes: vec![],
+            flow_tracking_data: None,
         };
         let program =
             program!(instruction_locations = Some(HashMap::from([(2, instruction_location)])),);
@@ -623,6 +629,8 @@ mod test {
         let instruction_location = InstructionLocation {
             inst: location_a,
             hints: vec![hint_location],
+            accessible_scopes: vec![],
+            flow_tracking_data: None,
         };
         let program =
             program!(instruction_locations = Some(HashMap::from([(2, instruction_location)])),);
@@ -1141,6 +1149,8 @@ cairo_programs/bad_programs/uint256_sub_b_gt_256.cairo:10:2: (pc=0:12)
         let instruction_location = InstructionLocation {
             inst: location,
             hints: vec![],
+            accessible_scopes: vec![],
+            flow_tracking_data: None,
         };
         let program =
             program!(instruction_locations = Some(HashMap::from([(5, instruction_location)])),);
@@ -5,25 +5,51 @@
 //! If added to the VM, hooks function will be called during the VM execution at specific stages.
 //!
 //! Available hooks:
-//! - before_first_step, executed before entering the execution loop in [run_until_pc](CairoRunner::run_until_pc)
+//! - before_first_step, executed before entering the execution loop in [run_until_pc](crate::vm::runners::cairo_runner::CairoRunner::run_until_pc)
 //! - pre_step_instruction, executed before each instruction_step in [step](VirtualMachine::step)
 //! - post_step_instruction, executed after each instruction_step in [step](VirtualMachine::step)
+//! - pre_hint_execution, executed before each hint is run in [step_hint](VirtualMachine::step_hint)
+//! - post_hint_execution, executed after each hint is run in [step_hint](VirtualMachine::step_hint)
+//! - memory_write, executed for each value written into an empty memory cell through
+//!   [Memory::insert](crate::vm::vm_memory::memory::Memory::insert), which includes the writes of
+//!   [MemorySegmentManager::load_data](crate::vm::vm_memory::memory_segments::MemorySegmentManager::load_data)
+//!   and [write_arg](crate::vm::vm_memory::memory_segments::MemorySegmentManager::write_arg).
+//!   The writes are recorded by the memory while the hook is set, from the moment it is set with
+//!   [set_hooks](VirtualMachine::set_hooks) or the [VirtualMachineBuilder](crate::vm::vm_core::VirtualMachineBuilder),
+//!   or from the next step otherwise, and the hook runs for them after each instruction writes
+//!   its deduced operands, after each hint, and after each call to
+//!   [insert_value](VirtualMachine::insert_value) or [load_data](VirtualMachine::load_data).
+//!   The writes of a failed hint or instruction are not reported. Values the VM writes directly into its memory
+//!   backend, such as the zeros of the zero segment of the mod builtins, and the relocation of
+//!   temporary segments, are not reported
+//! - builtin_deduction, executed after a builtin runner deduces the value of a memory cell during an instruction
+//!
+//! The signature of each hook is part of the public API and is tracked by [HOOKS_API_VERSION].
 
 use crate::stdlib::{any::Any, collections::HashMap, prelude::*, sync::Arc};
 
+use crate::types::relocatable::{MaybeRelocatable, Relocatable};
 use crate::Felt252;
 
 use crate::{
     hint_processor::hint_processor_definition::HintProcessor, types::exec_scope::ExecutionScopes,
 };
 
-use super::{errors::vm_errors::VirtualMachineError, vm_core::VirtualMachine};
+use super::{
+    errors::{memory_errors::MemoryError, vm_errors::VirtualMachineError},
+    vm_core::VirtualMachine,
+};
+
+/// Version of the hooks API.
+///
+/// It is increased every time a hook is added or the signature of an existing one changes.
+pub const HOOKS_API_VERSION: u32 = 2;
 
-type BeforeFirstStepHookFunc = Arc<
+pub type BeforeFirstStepHookFunc = Arc<
     dyn Fn(&mut VirtualMachine, &[Box<dyn Any>]) -> Result<(), VirtualMachineError> + Sync + Send,
 >;
 
-type StepHookFunc = Arc<
+pub type StepHookFunc = Arc<
     dyn Fn(
             &mut VirtualMachine,
             &mut dyn HintProcessor,
@@ -35,6 +61,25 @@ type StepHookFunc = Arc<
         + Send,
 >;
 
+/// Receives the hint data of the hint being executed, as returned by [HintProcessor::compile_hint]
+pub type HintHookFunc = Arc<
+    dyn Fn(
+            &mut VirtualMachine,
+            &mut ExecutionScopes,
+            &dyn Any,
+            &HashMap<String, Felt252>,
+        ) -> Result<(), VirtualMachineError>
+        + Sync
+        + Send,
+>;
+
+/// Receives the address of the memory cell and the value written to it
+pub type MemoryHookFunc = Arc<
+    dyn Fn(&VirtualMachine, Relocatable, &MaybeRelocatable) -> Result<(), VirtualMachineError>
+        + Sync
+        + Send,
+>;
+
 /// The hooks to be executed during the VM run
 ///
 /// They can be individually ignored by setting them to [None]
@@ -43,6 +88,10 @@ pub struct Hooks {
     before_first_step: Option<BeforeFirstStepHookFunc>,
     pre_step_instruction: Option<StepHookFunc>,
     post_step_instruction: Option<StepHookFunc>,
+    pre_hint_execution: Option<HintHookFunc>,
+    post_hint_execution: Option<HintHookFunc>,
+    memory_write: Option<MemoryHookFunc>,
+    builtin_deduction: Option<MemoryHookFunc>,
 }
 
 impl Hooks {
@@ -55,11 +104,57 @@ impl Hooks {
             before_first_step,
             pre_step_instruction,
             post_step_instruction,
+            ..Default::default()
         }
     }
+
+    pub fn pre_hint_execution(mut self, hook: Option<HintHookFunc>) -> Self {
+        self.pre_hint_execution = hook;
+        self
+    }
+
+    pub fn post_hint_execution(mut self, hook: Option<HintHookFunc>) -> Self {
+        self.post_hint_execution = hook;
+        self
+    }
+
+    pub fn memory_write(mut self, hook: Option<MemoryHookFunc>) -> Self {
+        self.memory_write = hook;
+        self
+    }
+
+    pub fn builtin_deduction(mut self, hook: Option<MemoryHookFunc>) -> Self {
+        self.builtin_deduction = hook;
+        self
+    }
 }
 
 impl VirtualMachine {
+    /// Replaces the hooks executed by the VM, which can also be set with
+    /// [VirtualMachineBuilder::hooks](crate::vm::vm_core::VirtualMachineBuilder::hooks)
+    pub fn set_hooks(&mut self, hooks: Hooks) {
+        self.hooks = hooks;
+        self.record_memory_writes();
+    }
+
+    /// Makes the memory record the cells written while the memory_write hook is set, which is
+    /// also done before each step for hooks assigned without [set_hooks](Self::set_hooks)
+    pub(crate) fn record_memory_writes(&mut self) {
+        let record = self.hooks.memory_write.is_some();
+        let memory = &mut self.segments.memory;
+        if record != memory.hook_writes.is_some() {
+            memory.hook_writes = record.then(Vec::new);
+        }
+    }
+
+    /// Drops the cells written by a failed hint or instruction, which are not reported to the
+    /// memory_write hook
+    pub(crate) fn discard_memory_writes(&mut self) {
+        if let Some(hook_writes) = &mut self.segments.memory.hook_writes {
+            hook_writes.clear();
+        }
+    }
+
     pub fn execute_before_first_step(
         &mut self,
         hint_data: &[Box<dyn Any>],
@@ -98,6 +193,78 @@ impl VirtualMachine {
 
         Ok(())
     }
+
+    pub fn execute_pre_hint_execution(
+        &mut self,
+        exec_scope: &mut ExecutionScopes,
+        hint_data: &dyn Any,
+        constants: &HashMap<String, Felt252>,
+    ) -> Result<(), VirtualMachineError> {
+        if let Some(hook_func) = self.hooks.clone().pre_hint_execution {
+            (hook_func)(self, exec_scope, hint_data, constants)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn execute_post_hint_execution(
+        &mut self,
+        exec_scope: &mut ExecutionScopes,
+        hint_data: &dyn Any,
+        constants: &HashMap<String, Felt252>,
+    ) -> Result<(), VirtualMachineError> {
+        if let Some(hook_func) = self.hooks.clone().post_hint_execution {
+            (hook_func)(self, exec_scope, hint_data, constants)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn execute_memory_write(
+        &self,
+        addr: Relocatable,
+        value: &MaybeRelocatable,
+    ) -> Result<(), VirtualMachineError> {
+        if let Some(hook_func) = &self.hooks.memory_write {
+            (hook_func)(self, addr, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the memory_write hook for each cell written to memory since it last ran, in the order
+    /// they were written
+    pub(crate) fn execute_memory_writes(&mut self) -> Result<(), VirtualMachineError> {
+        let Some(hook_writes) = &self.segments.memory.hook_writes else {
+            return Ok(());
+        };
+        let result = hook_writes
+            .iter()
+            .try_for_each(|(addr, value)| self.execute_memory_write(*addr, value));
+        if let Some(hook_writes) = &mut self.segments.memory.hook_writes {
+            hook_writes.clear();
+        }
+        result
+    }
+
+    /// Version of [execute_memory_writes](Self::execute_memory_writes) for values written through
+    /// the public memory API of the VM, whose errors are [MemoryError]s
+    pub(crate) fn execute_memory_writes_from_api(&mut self) -> Result<(), MemoryError> {
+        self.execute_memory_writes()
+            .map_err(|e| MemoryError::MemoryWriteHook(e.to_string().into_boxed_str()))
+    }
+
+    pub fn execute_builtin_deduction(
+        &self,
+        addr: Relocatable,
+        value: &MaybeRelocatable,
+    ) -> Result<(), VirtualMachineError> {
+        if let Some(hook_func) = &self.hooks.builtin_deduction {
+            (hook_func)(self, addr, value)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -229,4 +396,315 @@ mod tests {
         let end = cairo_runner.initialize(false).unwrap();
         assert!(cairo_runner.run_until_pc(end, &mut hint_processor).is_ok());
     }
+
+    #[test]
+    fn hint_and_memory_hooks_failure() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/sqrt.json"),
+            Some("main"),
+        )
+        .expect("Call to `Program::from_file()` failed.");
+
+        fn hint_hook(
+            _vm: &mut VirtualMachine,
+            _exec_scope: &mut ExecutionScopes,
+            _hint_data: &dyn Any,
+            _constants: &HashMap<String, Felt252>,
+        ) -> Result<(), VirtualMachineError> {
+            Err(VirtualMachineError::Unexpected)
+        }
+
+        fn memory_hook(
+            _vm: &VirtualMachine,
+            _addr: Relocatable,
+            _value: &MaybeRelocatable,
+        ) -> Result<(), VirtualMachineError> {
+            Err(VirtualMachineError::Unexpected)
+        }
+
+        // Pre hint fail
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut cairo_runner = cairo_runner!(program);
+        cairo_runner.vm.hooks = Hooks::default().pre_hint_execution(Some(Arc::new(hint_hook)));
+
+        let end = cairo_runner.initialize(false).unwrap();
+        assert!(cairo_runner.run_until_pc(end, &mut hint_processor).is_err());
+
+        // Post hint fail
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut cairo_runner = cairo_runner!(program);
+        cairo_runner.vm.hooks = Hooks::default().post_hint_execution(Some(Arc::new(hint_hook)));
+
+        let end = cairo_runner.initialize(false).unwrap();
+        assert!(cairo_runner.run_until_pc(end, &mut hint_processor).is_err());
+
+        // Memory write fail
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut cairo_runner = cairo_runner!(program);
+        cairo_runner
+            .vm
+            .set_hooks(Hooks::default().memory_write(Some(Arc::new(memory_hook))));
+
+        let end = cairo_runner.initialize(false).unwrap();
+        assert!(cairo_runner.run_until_pc(end, &mut hint_processor).is_err());
+    }
+
+    #[test]
+    fn hint_and_memory_hooks_success() {
+        use crate::stdlib::sync::atomic::{AtomicUsize, Ordering};
+
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/bitwise_builtin_test.json"),
+            Some("main"),
+        )
+        .expect("Call to `Program::from_file()` failed.");
+
+        static MEMORY_WRITES: AtomicUsize = AtomicUsize::new(0);
+        static BUILTIN_DEDUCTIONS: AtomicUsize = AtomicUsize::new(0);
+
+        fn memory_write_hook(
+            vm: &VirtualMachine,
+            addr: Relocatable,
+            value: &MaybeRelocatable,
+        ) -> Result<(), VirtualMachineError> {
+            assert_eq!(vm.get_maybe(&addr).as_ref(), Some(value));
+            MEMORY_WRITES.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn builtin_deduction_hook(
+            _vm: &VirtualMachine,
+            _addr: Relocatable,
+            _value: &MaybeRelocatable,
+        ) -> Result<(), VirtualMachineError> {
+            BUILTIN_DEDUCTIONS.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut cairo_runner = cairo_runner!(program);
+        cairo_runner.vm.set_hooks(
+            Hooks::default()
+                .memory_write(Some(Arc::new(memory_write_hook)))
+                .builtin_deduction(Some(Arc::new(builtin_deduction_hook))),
+        );
+
+        let end = cairo_runner.initialize(false).unwrap();
+        assert!(cairo_runner.run_until_pc(end, &mut hint_processor).is_ok());
+        assert!(MEMORY_WRITES.load(Ordering::Relaxed) > 0);
+        assert!(BUILTIN_DEDUCTIONS.load(Ordering::Relaxed) > 0);
+    }
+
+    #[test]
+    fn memory_write_hook_on_api_writes() {
+        use crate::stdlib::sync::atomic::{AtomicUsize, Ordering};
+        use crate::{relocatable, utils::test_utils::*};
+
+        static MEMORY_WRITES: AtomicUsize = AtomicUsize::new(0);
+
+        fn memory_write_hook(
+            vm: &VirtualMachine,
+            addr: Relocatable,
+            value: &MaybeRelocatable,
+        ) -> Result<(), VirtualMachineError> {
+            assert_eq!(vm.get_maybe(&addr).as_ref(), Some(value));
+            MEMORY_WRITES.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn failing_hook(
+            _vm: &VirtualMachine,
+            _addr: Relocatable,
+            _value: &MaybeRelocatable,
+        ) -> Result<(), VirtualMachineError> {
+            Err(VirtualMachineError::Unexpected)
+        }
+
+        let mut vm = vm!();
+        vm.set_hooks(Hooks::default().memory_write(Some(Arc::new(memory_write_hook))));
+        add_segments!(vm, 2);
+        vm.insert_value(relocatable!(1, 0), 5).unwrap();
+        assert_eq!(MEMORY_WRITES.load(Ordering::Relaxed), 1);
+        vm.load_data(
+            relocatable!(1, 1),
+            &[mayberelocatable!(1), mayberelocatable!(0, 3)],
+        )
+        .unwrap();
+        assert_eq!(MEMORY_WRITES.load(Ordering::Relaxed), 3);
+
+        vm.set_hooks(Hooks::default().memory_write(Some(Arc::new(failing_hook))));
+        assert_matches::assert_matches!(
+            vm.insert_value(relocatable!(1, 3), 7),
+            Err(MemoryError::MemoryWriteHook(_))
+        );
+    }
+
+    #[test]
+    fn memory_write_hook_on_hint_writes_through_segments() {
+        use crate::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::HintFunc;
+        use crate::hint_processor::hint_processor_definition::{HintProcessorLogic, HintReference};
+        use crate::serde::deserialize_program::ApTracking;
+        use crate::stdlib::sync::atomic::{AtomicUsize, Ordering};
+        use crate::types::shared::Shared;
+        use crate::utils::test_utils::*;
+        use crate::vm::errors::hint_errors::HintError;
+
+        static MEMORY_WRITES: AtomicUsize = AtomicUsize::new(0);
+
+        fn memory_write_hook(
+            vm: &VirtualMachine,
+            addr: Relocatable,
+            value: &MaybeRelocatable,
+        ) -> Result<(), VirtualMachineError> {
+            assert_eq!(vm.get_maybe(&addr).as_ref(), Some(value));
+            MEMORY_WRITES.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        // Writes without going through the memory API of the VM
+        fn write_through_segments(
+            vm: &mut VirtualMachine,
+            _exec_scopes: &mut ExecutionScopes,
+            _ids_data: &HashMap<String, HintReference>,
+            _ap_tracking: &ApTracking,
+            _constants: &HashMap<String, Felt252>,
+        ) -> Result<(), HintError> {
+            let base = vm.segments.add();
+            vm.segments
+                .write_arg(base, &vec![MaybeRelocatable::from(1), base.into()])?;
+            vm.segments.memory.insert((base + 2)?, Felt252::from(3))?;
+            Ok(())
+        }
+
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_hint(
+            "write_through_segments".to_string(),
+            Shared::new(HintFunc(Box::new(write_through_segments))),
+        );
+        let hint_data = hint_processor
+            .compile_hint(
+                "write_through_segments",
+                &ApTracking::default(),
+                &HashMap::new(),
+                &[],
+            )
+            .unwrap();
+
+        let mut vm = vm!();
+        vm.set_hooks(Hooks::default().memory_write(Some(Arc::new(memory_write_hook))));
+        #[cfg(not(feature = "extensive_hints"))]
+        vm.step_hint(
+            &mut hint_processor,
+            exec_scopes_ref!(),
+            &[hint_data],
+            &HashMap::new(),
+        )
+        .unwrap();
+        #[cfg(feature = "extensive_hints")]
+        vm.step_hint(
+            &mut hint_processor,
+            exec_scopes_ref!(),
+            &mut vec![hint_data],
+            &mut HashMap::from([(vm.get_pc(), (0, core::num::NonZeroUsize::new(1).unwrap()))]),
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(MEMORY_WRITES.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn memory_write_hook_assigned_without_set_hooks() {
+        use crate::stdlib::sync::atomic::{AtomicUsize, Ordering};
+
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/sqrt.json"),
+            Some("main"),
+        )
+        .expect("Call to `Program::from_file()` failed.");
+
+        static MEMORY_WRITES: AtomicUsize = AtomicUsize::new(0);
+
+        fn memory_write_hook(
+            _vm: &VirtualMachine,
+            _addr: Relocatable,
+            _value: &MaybeRelocatable,
+        ) -> Result<(), VirtualMachineError> {
+            MEMORY_WRITES.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut cairo_runner = cairo_runner!(program);
+        cairo_runner.vm.hooks = Hooks::default().memory_write(Some(Arc::new(memory_write_hook)));
+
+        let end = cairo_runner.initialize(false).unwrap();
+        assert!(cairo_runner.run_until_pc(end, &mut hint_processor).is_ok());
+        assert!(MEMORY_WRITES.load(Ordering::Relaxed) > 0);
+    }
+
+    #[test]
+    fn memory_write_hook_skips_failed_hint_writes() {
+        use crate::hint_processor::builtin_hint_processor::{
+            builtin_hint_processor_definition::HintFunc, hint_code,
+        };
+        use crate::hint_processor::hint_processor_definition::HintReference;
+        use crate::serde::deserialize_program::ApTracking;
+        use crate::stdlib::sync::atomic::{AtomicUsize, Ordering};
+        use crate::types::shared::Shared;
+        use crate::vm::errors::hint_errors::HintError;
+
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/sqrt.json"),
+            Some("main"),
+        )
+        .expect("Call to `Program::from_file()` failed.");
+
+        static FAILED_HINT_WRITES: AtomicUsize = AtomicUsize::new(0);
+
+        // Value written by the failing hint, which the program doesn't write
+        const FAILED_HINT_VALUE: usize = 0xdeadbeef;
+
+        fn memory_write_hook(
+            _vm: &VirtualMachine,
+            _addr: Relocatable,
+            value: &MaybeRelocatable,
+        ) -> Result<(), VirtualMachineError> {
+            if value == &MaybeRelocatable::from(FAILED_HINT_VALUE) {
+                FAILED_HINT_WRITES.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(())
+        }
+
+        fn write_and_fail(
+            vm: &mut VirtualMachine,
+            _exec_scopes: &mut ExecutionScopes,
+            _ids_data: &HashMap<String, HintReference>,
+            _ap_tracking: &ApTracking,
+            _constants: &HashMap<String, Felt252>,
+        ) -> Result<(), HintError> {
+            let base = vm.segments.add();
+            vm.segments
+                .memory
+                .insert(base, Felt252::from(FAILED_HINT_VALUE))?;
+            Err(HintError::WrongHintData)
+        }
+
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_hint(
+            hint_code::SQRT.to_string(),
+            Shared::new(HintFunc(Box::new(write_and_fail))),
+        );
+        let mut cairo_runner = cairo_runner!(program);
+        cairo_runner
+            .vm
+            .set_hooks(Hooks::default().memory_write(Some(Arc::new(memory_write_hook))));
+
+        let end = cairo_runner.initialize(false).unwrap();
+        assert!(cairo_runner.run_until_pc(end, &mut hint_processor).is_err());
+        assert_eq!(
+            cairo_runner.vm.segments.memory.hook_writes,
+            Some(Vec::new())
+        );
+        assert_eq!(FAILED_HINT_WRITES.load(Ordering::Relaxed), 0);
+    }
 }
@@ -0,0 +1,130 @@
+//! Journal of the changes made by each step, used to step backwards through a run
+//!
+//! When enabled with [VirtualMachine::enable_journal](super::vm_core::VirtualMachine::enable_journal),
+//! the vm records the registers before every step, and the memory records the previous state of
+//! every cell written or marked as accessed, so that
+//! [VirtualMachine::step_back](super::vm_core::VirtualMachine::step_back) can undo them.
+//!
+//! Only the state of the vm is journaled: execution scopes and the internal state of builtin
+//! runners modified by hints are not restored when stepping back.
+
+use crate::types::relocatable::Relocatable;
+
+use super::vm_memory::memory::MemoryCell;
+
+/// Change made to the memory, holding what is needed to undo it
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum MemoryJournalEntry {
+    /// A cell was written or marked as accessed.
+    /// `previous` is the cell before the change and `segment_len` the length of its segment.
+    Cell {
+        address: Relocatable,
+        previous: MemoryCell,
+        segment_len: usize,
+    },
+    /// An address was added to the validated addresses
+    Validated(Relocatable),
+    /// A relocation rule was added for the temporary segment with the given key
+    RelocationRule(usize),
+}
+
+/// State of the vm before a step
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct StepJournalEntry {
+    pub(crate) pc: Relocatable,
+    pub(crate) ap: usize,
+    pub(crate) fp: usize,
+    pub(crate) current_step: usize,
+    pub(crate) rc_limits: Option<(isize, isize)>,
+    pub(crate) skip_instruction_execution: bool,
+    pub(crate) trace_len: Option<usize>,
+    pub(crate) num_segments: usize,
+    pub(crate) num_temp_segments: usize,
+    /// Length of the memory journal before the step
+    pub(crate) memory_journal_len: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::{layout_name::LayoutName, relocatable::Relocatable},
+        utils::test_utils::program_b,
+        vm::{errors::vm_errors::VirtualMachineError, runners::cairo_runner::CairoRunner},
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn initialized_runner() -> (CairoRunner, Relocatable) {
+        let mut runner = CairoRunner::new(&program_b(), LayoutName::small, false, true).unwrap();
+        let end = runner.initialize(false).unwrap();
+        (runner, end)
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn step_back_restores_previous_state() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let (mut runner, end) = initialized_runner();
+        runner.vm.enable_journal();
+        runner.run_for_steps(5, &mut hint_processor).unwrap();
+        let paused = runner.take_snapshot().unwrap();
+
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        let finished = runner.take_snapshot().unwrap();
+        assert_eq!(runner.vm.journaled_steps(), 18);
+
+        runner.vm.step_back(13).unwrap();
+        assert_eq!(runner.vm.journaled_steps(), 5);
+        assert_eq!(runner.take_snapshot().unwrap(), paused);
+
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        assert_eq!(runner.take_snapshot().unwrap(), finished);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn step_back_errors() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let (mut runner, _) = initialized_runner();
+        assert_matches!(
+            runner.vm.step_back(1),
+            Err(VirtualMachineError::JournalNotEnabled)
+        );
+        runner.run_for_steps(2, &mut hint_processor).unwrap();
+        runner.vm.enable_journal();
+        runner.run_for_steps(2, &mut hint_processor).unwrap();
+        assert_matches!(
+            runner.vm.step_back(3),
+            Err(VirtualMachineError::StepBackOutOfJournal(bx)) if *bx == (3, 2)
+        );
+        assert_matches!(runner.vm.step_back(0), Ok(()));
+        assert_eq!(runner.vm.current_step, 4);
+        runner.vm.disable_journal();
+        assert_eq!(runner.vm.journaled_steps(), 0);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn find_write_step() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let (mut runner, end) = initialized_runner();
+        runner.vm.enable_journal();
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+
+        // The output builtin cell is written by serialize_word
+        let output = Relocatable::from((2, 0));
+        let step = runner.vm.find_write_step(output).unwrap();
+        runner
+            .vm
+            .step_back(runner.vm.current_step - step - 1)
+            .unwrap();
+        assert!(runner.vm.segments.memory.get(&output).is_some());
+        runner.vm.step_back(1).unwrap();
+        assert!(runner.vm.segments.memory.get(&output).is_none());
+        // Written before the journal was enabled
+        assert_eq!(runner.vm.find_write_step((1, 0).into()), None);
+    }
+}
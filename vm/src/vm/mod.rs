@@ -1,12 +1,20 @@
 pub mod context;
+#[cfg(feature = "std")]
+pub mod coverage;
+#[cfg(feature = "std")]
+pub mod debugger;
 pub mod decoding;
+#[cfg(feature = "std")]
+pub mod differential;
 pub mod errors;
+pub(crate) mod journal;
+pub mod profiler;
 pub mod runners;
 pub mod security;
 pub mod trace;
 pub mod vm_core;
 pub mod vm_memory;
 
-#[cfg(feature = "test_utils")]
-#[cfg_attr(docsrs, doc(cfg(feature = "test_utils")))]
+#[cfg(feature = "hooks")]
+#[cfg_attr(docsrs, doc(cfg(feature = "hooks")))]
 pub mod hooks;
@@ -0,0 +1,561 @@
+//! Execution profiler
+//!
+//! Walks the execution trace of a finished run, reconstructing the call stack from the `call` and
+//! `ret` instructions, and attributes the resources used by each step to the stack of functions
+//! that was active when it was executed:
+//! - Steps: each executed instruction.
+//! - Memory holes: unaccessed cells of the execution segment, attributed to the function that
+//!   advanced ap past them.
+//! - Builtin instances: each instance is attributed to the function that first accessed one of
+//!   its cells through an instruction operand.
+//!
+//! The resulting [Profile] can be exported in the folded stacks format used by flamegraph tools,
+//! or as an uncompressed pprof protobuf.
+
+use crate::stdlib::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    prelude::*,
+};
+use core::fmt::Write;
+
+use num_traits::ToPrimitive;
+
+use crate::{
+    types::{
+        builtin_name::BuiltinName,
+        instruction::{Instruction, Op1Addr, Opcode, Register},
+        relocatable::Relocatable,
+    },
+    vm::{
+        decoding::decoder::decode_instruction, errors::trace_errors::TraceError,
+        runners::cairo_runner::CairoRunner, trace::trace_entry::TraceEntry,
+    },
+};
+
+/// Name given to the frames whose pc doesn't belong to any function identifier
+pub const UNKNOWN_FUNCTION: &str = "<unknown>";
+
+// The ap and fp registers of the trace are offsets within the execution segment
+const EXECUTION_SEGMENT: isize = 1;
+
+/// Resources used by a run, indexed by call stack (outermost function first)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub stacks: BTreeMap<Vec<String>, ProfileSample>,
+}
+
+/// Resources used while a call stack was active
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileSample {
+    pub steps: usize,
+    pub memory_holes: usize,
+    pub builtin_instances: BTreeMap<BuiltinName, usize>,
+}
+
+impl ProfileSample {
+    fn merge(&mut self, other: &ProfileSample) {
+        self.steps += other.steps;
+        self.memory_holes += other.memory_holes;
+        for (builtin, instances) in &other.builtin_instances {
+            *self.builtin_instances.entry(*builtin).or_default() += instances;
+        }
+    }
+}
+
+// A frame of the reconstructed call stack
+struct Frame {
+    function: usize,
+    fp: usize,
+}
+
+impl Profile {
+    /// Profiles a finished run. The run must have been performed with the trace enabled.
+    pub fn new(runner: &CairoRunner) -> Result<Self, TraceError> {
+        let trace = runner.vm.get_trace()?;
+        let program_base = runner.program_base.unwrap_or_default();
+
+        // Function identifiers sorted by pc, so that each pc can be mapped to its function
+        let mut functions: Vec<(usize, &str)> = runner
+            .program
+            .iter_identifiers()
+            .filter(|(_, identifier)| identifier.type_.as_deref() == Some("function"))
+            .filter_map(|(name, identifier)| Some((identifier.pc?, name)))
+            .collect();
+        functions.sort();
+        let mut names: Vec<String> = functions.iter().map(|(_, name)| name.to_string()).collect();
+        names.push(UNKNOWN_FUNCTION.to_string());
+        let function_at = |pc: Relocatable| {
+            let index = (pc.segment_index == program_base.segment_index)
+                .then(|| pc.offset.checked_sub(program_base.offset))
+                .flatten()
+                .map(|offset| functions.partition_point(|(function_pc, _)| *function_pc <= offset))
+                .unwrap_or_default();
+            index.checked_sub(1).unwrap_or(functions.len())
+        };
+
+        // Builtin segments, along with the size of their instances
+        let builtins: HashMap<isize, (BuiltinName, usize)> = runner
+            .vm
+            .builtin_runners
+            .iter()
+            .filter(|builtin| builtin.cells_per_instance() > 0)
+            .map(|builtin| {
+                (
+                    builtin.base() as isize,
+                    (builtin.name(), builtin.cells_per_instance() as usize),
+                )
+            })
+            .collect();
+        let mut used_instances = HashSet::<(isize, usize)>::new();
+
+        let mut instructions = HashMap::<Relocatable, Option<Instruction>>::new();
+        let mut samples = HashMap::<Vec<usize>, ProfileSample>::new();
+        let mut stack = Vec::<Frame>::new();
+        if let Some(first) = trace.first() {
+            stack.push(Frame {
+                function: function_at(first.pc),
+                fp: first.fp,
+            });
+        }
+        for (i, entry) in trace.iter().enumerate() {
+            let next = trace.get(i + 1);
+            let instruction = *instructions
+                .entry(entry.pc)
+                .or_insert_with(|| decode(runner, entry.pc));
+            let key: Vec<usize> = stack.iter().map(|frame| frame.function).collect();
+            let sample = samples.entry(key).or_default();
+            sample.steps += 1;
+
+            let Some(instruction) = instruction else {
+                continue;
+            };
+            for addr in operand_addresses(runner, entry, &instruction) {
+                let Some((name, cells_per_instance)) = builtins.get(&addr.segment_index) else {
+                    continue;
+                };
+                if used_instances.insert((addr.segment_index, addr.offset / cells_per_instance)) {
+                    *sample.builtin_instances.entry(*name).or_default() += 1;
+                }
+            }
+            if let Some(next) = next {
+                sample.memory_holes += (entry.ap..next.ap)
+                    .filter(|offset| !is_accessed(runner, EXECUTION_SEGMENT, *offset))
+                    .count();
+            }
+
+            match (instruction.opcode, next) {
+                (Opcode::Call, Some(next)) => stack.push(Frame {
+                    function: function_at(next.pc),
+                    fp: next.fp,
+                }),
+                (Opcode::Ret, Some(next)) => {
+                    // Pop frames until the caller's fp is restored, keeping the outermost one
+                    while stack.len() > 1 {
+                        stack.pop();
+                        if stack.last().is_some_and(|frame| frame.fp == next.fp) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut profile = Profile::default();
+        for (key, sample) in samples {
+            let stack = key.into_iter().map(|i| names[i].clone()).collect();
+            profile.stacks.entry(stack).or_default().merge(&sample);
+        }
+        Ok(profile)
+    }
+
+    /// Resources used by each function, including the functions it called
+    pub fn total_by_function(&self) -> BTreeMap<String, ProfileSample> {
+        let mut totals = BTreeMap::<String, ProfileSample>::new();
+        for (stack, sample) in &self.stacks {
+            // Recursive functions are only counted once per stack
+            let functions: HashSet<&String> = stack.iter().collect();
+            for function in functions {
+                totals.entry(function.clone()).or_default().merge(sample);
+            }
+        }
+        totals
+    }
+
+    /// Resources used by each function, excluding the functions it called
+    pub fn self_by_function(&self) -> BTreeMap<String, ProfileSample> {
+        let mut totals = BTreeMap::<String, ProfileSample>::new();
+        for (stack, sample) in &self.stacks {
+            if let Some(function) = stack.last() {
+                totals.entry(function.clone()).or_default().merge(sample);
+            }
+        }
+        totals
+    }
+
+    /// Serializes the steps of each call stack in the folded stacks format (`main;foo;bar 42`),
+    /// which can be rendered by flamegraph tools
+    pub fn to_folded_stacks(&self) -> String {
+        self.folded_stacks(|sample| sample.steps)
+    }
+
+    /// Serializes the instances of the given builtin used by each call stack in the folded stacks format
+    pub fn to_folded_stacks_for_builtin(&self, builtin: BuiltinName) -> String {
+        self.folded_stacks(|sample| {
+            sample
+                .builtin_instances
+                .get(&builtin)
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+
+    fn folded_stacks(&self, value: impl Fn(&ProfileSample) -> usize) -> String {
+        let mut folded = String::new();
+        for (stack, sample) in &self.stacks {
+            let value = value(sample);
+            if value > 0 {
+                // Writing to a String can't fail
+                let _ = writeln!(folded, "{} {value}", stack.join(";"));
+            }
+        }
+        folded
+    }
+
+    /// Serializes the profile as an (uncompressed) pprof protobuf, with the steps, memory holes
+    /// and the instances of each builtin as sample values
+    pub fn to_pprof(&self) -> Vec<u8> {
+        let builtins: BTreeSet<BuiltinName> = self
+            .stacks
+            .values()
+            .flat_map(|sample| sample.builtin_instances.keys().copied())
+            .collect();
+        let mut strings = StringTable::default();
+        let mut profile = Vec::new();
+
+        let mut sample_types = vec![("steps", "count"), ("memory_holes", "count")];
+        sample_types.extend(builtins.iter().map(|builtin| (builtin.to_str(), "count")));
+        for (type_, unit) in sample_types {
+            let mut value_type = Vec::new();
+            encode_varint_field(&mut value_type, 1, strings.index(type_));
+            encode_varint_field(&mut value_type, 2, strings.index(unit));
+            encode_bytes_field(&mut profile, 1, &value_type);
+        }
+
+        // Each function has a single location, sharing its id
+        let mut function_ids = BTreeMap::<&str, u64>::new();
+        for stack in self.stacks.keys() {
+            for function in stack {
+                let next_id = function_ids.len() as u64 + 1;
+                function_ids.entry(function).or_insert(next_id);
+            }
+        }
+
+        for (stack, sample) in &self.stacks {
+            let mut encoded_sample = Vec::new();
+            // Locations are ordered from the innermost frame to the outermost one
+            let location_ids: Vec<u64> = stack
+                .iter()
+                .rev()
+                .map(|f| function_ids[f.as_str()])
+                .collect();
+            encode_packed_field(&mut encoded_sample, 1, &location_ids);
+            let mut values = vec![sample.steps as u64, sample.memory_holes as u64];
+            values.extend(builtins.iter().map(|builtin| {
+                sample
+                    .builtin_instances
+                    .get(builtin)
+                    .copied()
+                    .unwrap_or_default() as u64
+            }));
+            encode_packed_field(&mut encoded_sample, 2, &values);
+            encode_bytes_field(&mut profile, 2, &encoded_sample);
+        }
+
+        for id in function_ids.values() {
+            let mut line = Vec::new();
+            encode_varint_field(&mut line, 1, *id);
+            let mut location = Vec::new();
+            encode_varint_field(&mut location, 1, *id);
+            encode_bytes_field(&mut location, 4, &line);
+            encode_bytes_field(&mut profile, 4, &location);
+        }
+
+        for (name, id) in &function_ids {
+            let mut function = Vec::new();
+            encode_varint_field(&mut function, 1, *id);
+            encode_varint_field(&mut function, 2, strings.index(name));
+            encode_bytes_field(&mut profile, 5, &function);
+        }
+
+        for string in strings.strings {
+            encode_bytes_field(&mut profile, 6, string.as_bytes());
+        }
+        profile
+    }
+}
+
+fn decode(runner: &CairoRunner, pc: Relocatable) -> Option<Instruction> {
+    let encoding = runner.vm.get_integer(pc).ok()?.to_u64()?;
+    decode_instruction(encoding).ok()
+}
+
+fn is_accessed(runner: &CairoRunner, segment_index: isize, offset: usize) -> bool {
+    runner
+        .vm
+        .segments
+        .memory
+        .data
+        .get(segment_index as usize, offset)
+        .is_some_and(|cell| cell.is_accessed())
+}
+
+// Returns the addresses of the dst, op0 and op1 operands of an executed instruction
+fn operand_addresses(
+    runner: &CairoRunner,
+    entry: &TraceEntry,
+    instruction: &Instruction,
+) -> Vec<Relocatable> {
+    let register = |register: Register, offset: isize| {
+        let base = match register {
+            Register::AP => entry.ap,
+            Register::FP => entry.fp,
+        };
+        Some(Relocatable::from((
+            EXECUTION_SEGMENT,
+            base.checked_add_signed(offset)?,
+        )))
+    };
+    let dst = register(instruction.dst_register, instruction.off0);
+    let op0 = register(instruction.op0_register, instruction.off1);
+    let op1 = match instruction.op1_addr {
+        Op1Addr::AP => register(Register::AP, instruction.off2),
+        Op1Addr::FP => register(Register::FP, instruction.off2),
+        Op1Addr::Op0 => op0
+            .and_then(|op0| runner.vm.get_relocatable(op0).ok())
+            .and_then(|base| {
+                Some(Relocatable::from((
+                    base.segment_index,
+                    base.offset.checked_add_signed(instruction.off2)?,
+                )))
+            }),
+        Op1Addr::Imm => None,
+    };
+    [dst, op0, op1].into_iter().flatten().collect()
+}
+
+// The string table of a pprof profile, where the first string must be empty
+struct StringTable {
+    strings: Vec<String>,
+    indexes: HashMap<String, u64>,
+}
+
+impl Default for StringTable {
+    fn default() -> Self {
+        Self {
+            strings: vec![String::new()],
+            indexes: HashMap::from([(String::new(), 0)]),
+        }
+    }
+}
+
+impl StringTable {
+    fn index(&mut self, string: &str) -> u64 {
+        if let Some(index) = self.indexes.get(string) {
+            return *index;
+        }
+        let index = self.strings.len() as u64;
+        self.strings.push(string.to_string());
+        self.indexes.insert(string.to_string(), index);
+        index
+    }
+}
+
+fn encode_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn encode_varint_field(buffer: &mut Vec<u8>, field: u64, value: u64) {
+    encode_varint(buffer, field << 3);
+    encode_varint(buffer, value);
+}
+
+fn encode_bytes_field(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    encode_varint(buffer, (field << 3) | 2);
+    encode_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+fn encode_packed_field(buffer: &mut Vec<u8>, field: u64, values: &[u64]) {
+    let mut packed = Vec::new();
+    for value in values {
+        encode_varint(&mut packed, *value);
+    }
+    encode_bytes_field(buffer, field, &packed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cairo_run::{cairo_run_program, CairoRunConfig};
+    use crate::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+    use crate::types::{layout_name::LayoutName, program::Program};
+    use crate::utils::test_utils::run_program_b;
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn profile_without_trace() {
+        let runner = run_program_b(false, false);
+        assert_matches!(Profile::new(&runner), Err(TraceError::TraceNotEnabled));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn profile_steps() {
+        let runner = run_program_b(true, false);
+        let profile = Profile::new(&runner).unwrap();
+        let total_steps: usize = profile.stacks.values().map(|sample| sample.steps).sum();
+        assert_eq!(
+            total_steps,
+            runner.get_execution_resources().unwrap().n_steps
+        );
+        assert_eq!(
+            profile.total_by_function()["__main__.main"].steps,
+            total_steps
+        );
+        assert!(profile.to_folded_stacks().starts_with("__main__.main"));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn profile_nested_calls() {
+        // main calls foo, which calls bar, and then calls bar directly
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/manually_compiled/nested_calls.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let config = CairoRunConfig {
+            trace_enabled: true,
+            layout: LayoutName::plain,
+            ..Default::default()
+        };
+        let runner =
+            cairo_run_program(&program, &config, &mut BuiltinHintProcessor::new_empty()).unwrap();
+        let profile = Profile::new(&runner).unwrap();
+
+        let steps: Vec<(String, usize)> = profile
+            .stacks
+            .iter()
+            .map(|(stack, sample)| (stack.join(";"), sample.steps))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                ("__main__.main".to_string(), 3),
+                ("__main__.main;__main__.bar".to_string(), 1),
+                ("__main__.main;__main__.foo".to_string(), 2),
+                ("__main__.main;__main__.foo;__main__.bar".to_string(), 1),
+            ]
+        );
+        let totals = profile.total_by_function();
+        assert_eq!(totals["__main__.main"].steps, 7);
+        assert_eq!(totals["__main__.foo"].steps, 3);
+        assert_eq!(totals["__main__.bar"].steps, 2);
+        let self_totals = profile.self_by_function();
+        assert_eq!(self_totals["__main__.main"].steps, 3);
+        assert_eq!(self_totals["__main__.foo"].steps, 2);
+        assert_eq!(self_totals["__main__.bar"].steps, 2);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn folded_stacks_and_totals() {
+        let stack = |functions: &[&str]| functions.iter().map(|f| f.to_string()).collect();
+        let profile = Profile {
+            stacks: BTreeMap::from([
+                (
+                    stack(&["main"]),
+                    ProfileSample {
+                        steps: 3,
+                        ..Default::default()
+                    },
+                ),
+                (
+                    stack(&["main", "fib"]),
+                    ProfileSample {
+                        steps: 5,
+                        memory_holes: 1,
+                        builtin_instances: BTreeMap::from([(BuiltinName::range_check, 2)]),
+                    },
+                ),
+                (
+                    stack(&["main", "fib", "fib"]),
+                    ProfileSample {
+                        steps: 4,
+                        ..Default::default()
+                    },
+                ),
+            ]),
+        };
+        assert_eq!(
+            profile.to_folded_stacks(),
+            "main 3\nmain;fib 5\nmain;fib;fib 4\n"
+        );
+        assert_eq!(
+            profile.to_folded_stacks_for_builtin(BuiltinName::range_check),
+            "main;fib 2\n"
+        );
+        let totals = profile.total_by_function();
+        assert_eq!(totals["main"].steps, 12);
+        assert_eq!(totals["fib"].steps, 9);
+        assert_eq!(totals["fib"].memory_holes, 1);
+        let self_totals = profile.self_by_function();
+        assert_eq!(self_totals["main"].steps, 3);
+        assert_eq!(self_totals["fib"].steps, 9);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn pprof_encoding() {
+        let profile = Profile {
+            stacks: BTreeMap::from([(
+                vec!["main".to_string()],
+                ProfileSample {
+                    steps: 300,
+                    ..Default::default()
+                },
+            )]),
+        };
+        assert_eq!(
+            profile.to_pprof(),
+            [
+                // sample_type: steps, count
+                0x0a, 0x04, 0x08, 0x01, 0x10, 0x02, //
+                // sample_type: memory_holes, count
+                0x0a, 0x04, 0x08, 0x03, 0x10, 0x02, //
+                // sample: location [1], values [300, 0]
+                0x12, 0x08, 0x0a, 0x01, 0x01, 0x12, 0x03, 0xac, 0x02, 0x00, //
+                // location: id 1, line { function 1 }
+                0x22, 0x06, 0x08, 0x01, 0x22, 0x02, 0x08, 0x01, //
+                // function: id 1, name "main"
+                0x2a, 0x04, 0x08, 0x01, 0x10, 0x04, //
+                // string_table: "", "steps", "count", "memory_holes", "main"
+                0x32, 0x00, //
+                0x32, 0x05, b's', b't', b'e', b'p', b's', //
+                0x32, 0x05, b'c', b'o', b'u', b'n', b't', //
+                0x32, 0x0c, b'm', b'e', b'm', b'o', b'r', b'y', b'_', b'h', b'o', b'l', b'e',
+                b's', //
+                0x32, 0x04, b'm', b'a', b'i', b'n',
+            ]
+        );
+    }
+}
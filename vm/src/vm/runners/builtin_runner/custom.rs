@@ -0,0 +1,403 @@
+use crate::air_private_input::PrivateInput;
+use crate::stdlib::{boxed::Box, fmt::Debug, vec::Vec};
+use crate::types::builtin_name::CustomBuiltinName;
+use crate::types::instance_definitions::builtins_instance_def::BUILTIN_INSTANCES_PER_COMPONENT;
+use crate::types::relocatable::{MaybeRelocatable, Relocatable};
+use crate::types::shared::MaybeSendSync;
+use crate::vm::errors::{
+    memory_errors::MemoryError, runner_errors::RunnerError, vm_errors::VirtualMachineError,
+};
+use crate::vm::runners::cairo_pie::BuiltinAdditionalData;
+use crate::vm::vm_core::VirtualMachine;
+use crate::vm::vm_memory::{memory::Memory, memory_segments::MemorySegmentManager};
+use num_integer::div_ceil;
+
+/// A builtin implemented outside of the VM, registered with
+/// [CairoRunner::add_custom_builtin](crate::vm::runners::cairo_runner::CairoRunner::add_custom_builtin).
+///
+/// The VM handles its segment, its initial and final stack and its used cells accounting, as it
+/// does for the builtins it implements, and relies on this trait for the builtin's semantics.
+/// Its name is registered with
+/// [BuiltinName::register_custom](crate::types::builtin_name::BuiltinName::register_custom) when
+/// the builtin is added to a runner. Programs and Cairo PIEs using it which are loaded before that
+/// require registering the name beforehand. Without the `std` feature names can't be registered, and
+/// programs using custom builtins have to be built with [BuiltinName::custom](crate::types::builtin_name::BuiltinName::custom).
+pub trait CustomBuiltin: CustomBuiltinClone + Debug + MaybeSendSync {
+    fn name(&self) -> &'static CustomBuiltinName;
+
+    /// Number of steps per builtin instance, or None if the layout is dynamic
+    fn ratio(&self) -> Option<u32>;
+
+    fn cells_per_instance(&self) -> u32;
+
+    fn n_input_cells(&self) -> u32;
+
+    fn instances_per_component(&self) -> u32 {
+        BUILTIN_INSTANCES_PER_COMPONENT
+    }
+
+    /// Deduces the value of an output cell of the builtin's segment from its input cells
+    fn deduce_memory_cell(
+        &self,
+        _address: Relocatable,
+        _memory: &Memory,
+    ) -> Result<Option<MaybeRelocatable>, RunnerError> {
+        Ok(None)
+    }
+
+    /// Adds the rules validating the values written to the builtin's segment
+    fn add_validation_rule(&self, _segment_index: usize, _memory: &mut Memory) {}
+
+    /// Runs checks on the builtin's segment on top of the ones shared by every builtin
+    fn run_additional_security_checks(
+        &self,
+        _segment_index: usize,
+        _vm: &VirtualMachine,
+    ) -> Result<(), VirtualMachineError> {
+        Ok(())
+    }
+
+    /// Returns the information about the builtin's segment that should be added to the AIR
+    /// private input
+    fn air_private_input(&self, _segment_index: usize, _memory: &Memory) -> Vec<PrivateInput> {
+        Vec::new()
+    }
+
+    /// Returns the data stored by the builtin needed to re-execute from a cairo pie
+    fn get_additional_data(&self) -> BuiltinAdditionalData {
+        BuiltinAdditionalData::None
+    }
+
+    /// Extends the builtin's data with the data obtained from a previous cairo execution
+    fn extend_additional_data(
+        &mut self,
+        _additional_data: &BuiltinAdditionalData,
+    ) -> Result<(), RunnerError> {
+        Ok(())
+    }
+
+    /// Clears the builtin's data, such as when restoring a snapshot of an earlier point of the run
+    fn clear_additional_data(&mut self) {}
+}
+
+/// Allows cloning boxed [CustomBuiltin]s, implemented by every [CustomBuiltin] which is [Clone]
+pub trait CustomBuiltinClone {
+    fn clone_box(&self) -> Box<dyn CustomBuiltin>;
+}
+
+impl<T: CustomBuiltin + Clone + 'static> CustomBuiltinClone for T {
+    fn clone_box(&self) -> Box<dyn CustomBuiltin> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn CustomBuiltin> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomBuiltinRunner {
+    builtin: Box<dyn CustomBuiltin>,
+    pub base: usize,
+    pub(crate) stop_ptr: Option<usize>,
+    pub(crate) included: bool,
+}
+
+impl CustomBuiltinRunner {
+    pub fn new(builtin: Box<dyn CustomBuiltin>, included: bool) -> Self {
+        CustomBuiltinRunner {
+            builtin,
+            base: 0,
+            stop_ptr: None,
+            included,
+        }
+    }
+
+    pub fn builtin(&self) -> &dyn CustomBuiltin {
+        self.builtin.as_ref()
+    }
+
+    pub fn builtin_mut(&mut self) -> &mut dyn CustomBuiltin {
+        self.builtin.as_mut()
+    }
+
+    pub fn initialize_segments(&mut self, segments: &mut MemorySegmentManager) {
+        self.base = segments.add().segment_index as usize // segments.add() always returns a positive index
+    }
+
+    pub fn initial_stack(&self) -> Vec<MaybeRelocatable> {
+        if self.included {
+            vec![MaybeRelocatable::from((self.base as isize, 0))]
+        } else {
+            vec![]
+        }
+    }
+
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    pub fn ratio(&self) -> Option<u32> {
+        self.builtin.ratio()
+    }
+
+    pub fn add_validation_rule(&self, memory: &mut Memory) {
+        self.builtin.add_validation_rule(self.base, memory)
+    }
+
+    pub fn deduce_memory_cell(
+        &self,
+        address: Relocatable,
+        memory: &Memory,
+    ) -> Result<Option<MaybeRelocatable>, RunnerError> {
+        self.builtin.deduce_memory_cell(address, memory)
+    }
+
+    pub fn get_used_cells(&self, segments: &MemorySegmentManager) -> Result<usize, MemoryError> {
+        segments
+            .get_segment_used_size(self.base)
+            .ok_or(MemoryError::MissingSegmentUsedSizes)
+    }
+
+    pub fn get_used_instances(
+        &self,
+        segments: &MemorySegmentManager,
+    ) -> Result<usize, MemoryError> {
+        let used_cells = self.get_used_cells(segments)?;
+        Ok(div_ceil(
+            used_cells,
+            self.builtin.cells_per_instance() as usize,
+        ))
+    }
+
+    pub fn run_additional_security_checks(
+        &self,
+        vm: &VirtualMachine,
+    ) -> Result<(), VirtualMachineError> {
+        self.builtin.run_additional_security_checks(self.base, vm)
+    }
+
+    pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
+        self.builtin.air_private_input(self.base, memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::air_private_input::PrivateInputValue;
+    use crate::stdlib::collections::HashMap;
+    use crate::types::builtin_name::BuiltinName;
+    use crate::vm::runners::builtin_runner::BuiltinRunner;
+    use crate::vm::vm_memory::memory::ValidationRule;
+    use crate::Felt252;
+    use crate::{relocatable, utils::test_utils::*};
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    static SQUARE: CustomBuiltinName = CustomBuiltinName::new("square", "square_builtin");
+
+    /// Squares its input cell, and only accepts inputs smaller than 2**64
+    #[derive(Debug, Clone, Default)]
+    struct SquareBuiltin {
+        verified_addresses: Vec<Relocatable>,
+    }
+
+    impl CustomBuiltin for SquareBuiltin {
+        fn name(&self) -> &'static CustomBuiltinName {
+            &SQUARE
+        }
+
+        fn ratio(&self) -> Option<u32> {
+            Some(8)
+        }
+
+        fn cells_per_instance(&self) -> u32 {
+            2
+        }
+
+        fn n_input_cells(&self) -> u32 {
+            1
+        }
+
+        fn deduce_memory_cell(
+            &self,
+            address: Relocatable,
+            memory: &Memory,
+        ) -> Result<Option<MaybeRelocatable>, RunnerError> {
+            if address.offset % 2 == 0 {
+                return Ok(None);
+            }
+            Ok(memory
+                .get_integer((address - 1)?)
+                .ok()
+                .map(|x| MaybeRelocatable::from(x.as_ref() * x.as_ref())))
+        }
+
+        fn add_validation_rule(&self, segment_index: usize, memory: &mut Memory) {
+            let rule = ValidationRule(Box::new(
+                |memory: &Memory, address: Relocatable| -> Result<Vec<Relocatable>, MemoryError> {
+                    if address.offset % 2 != 0 {
+                        return Ok(vec![]);
+                    }
+                    let value = memory.get_integer(address)?;
+                    if value.bits() > 64 {
+                        return Err(MemoryError::RangeCheckNumOutOfBounds(Box::new((
+                            value.into_owned(),
+                            Felt252::TWO.pow(64_u128),
+                        ))));
+                    }
+                    Ok(vec![address])
+                },
+            ));
+            memory.add_validation_rule(segment_index, rule);
+        }
+
+        fn air_private_input(&self, segment_index: usize, memory: &Memory) -> Vec<PrivateInput> {
+            memory
+                .get_integer_range((segment_index as isize, 0).into(), 1)
+                .unwrap_or_default()
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| {
+                    PrivateInput::Value(PrivateInputValue {
+                        index,
+                        value: value.into_owned(),
+                    })
+                })
+                .collect()
+        }
+
+        fn get_additional_data(&self) -> BuiltinAdditionalData {
+            BuiltinAdditionalData::Hash(self.verified_addresses.clone())
+        }
+
+        fn extend_additional_data(
+            &mut self,
+            additional_data: &BuiltinAdditionalData,
+        ) -> Result<(), RunnerError> {
+            let BuiltinAdditionalData::Hash(addresses) = additional_data else {
+                return Err(RunnerError::InvalidAdditionalData(BuiltinName::custom(
+                    &SQUARE,
+                )));
+            };
+            self.verified_addresses.extend(addresses);
+            Ok(())
+        }
+    }
+
+    fn square_builtin(included: bool) -> BuiltinRunner {
+        CustomBuiltinRunner::new(Box::new(SquareBuiltin::default()), included).into()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn initial_stack_included_and_not_included() {
+        let mut vm = vm!();
+        vm.segments = segments![((0, 0), 0)];
+        let mut builtin = square_builtin(true);
+        builtin.initialize_segments(&mut vm.segments);
+        assert_eq!(builtin.base(), 1);
+        assert_eq!(builtin.initial_stack(), vec![mayberelocatable!(1, 0)]);
+        assert!(square_builtin(false).initial_stack().is_empty());
+        assert_eq!(builtin.name(), BuiltinName::custom(&SQUARE));
+        assert_eq!(builtin.name().to_str_with_suffix(), "square_builtin");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deduce_memory_cell() {
+        let builtin = square_builtin(true);
+        let memory = memory![((0, 0), 12), ((0, 2), 3)];
+        assert_eq!(
+            builtin.deduce_memory_cell(relocatable!(0, 1), &memory),
+            Ok(Some(MaybeRelocatable::from(144)))
+        );
+        assert_eq!(
+            builtin.deduce_memory_cell(relocatable!(0, 2), &memory),
+            Ok(None)
+        );
+        assert_eq!(
+            builtin.deduce_memory_cell(relocatable!(0, 3), &memory),
+            Ok(Some(MaybeRelocatable::from(9)))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validation_rule_rejects_big_inputs() {
+        let mut vm = vm!();
+        let mut builtin = square_builtin(true);
+        builtin.initialize_segments(&mut vm.segments);
+        builtin.add_validation_rule(&mut vm.segments.memory);
+        vm.segments
+            .memory
+            .insert(relocatable!(0, 0), Felt252::from(u64::MAX))
+            .unwrap();
+        assert_matches!(
+            vm.segments
+                .memory
+                .insert(relocatable!(0, 2), Felt252::TWO.pow(64_u128)),
+            Err(MemoryError::RangeCheckNumOutOfBounds(bx)) if bx.0 == Felt252::TWO.pow(64_u128)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn final_stack_and_used_cells() {
+        let mut vm = vm!();
+        vm.current_step = 16;
+        vm.segments = segments![((0, 0), 3), ((0, 1), 9), ((1, 0), (0, 2))];
+        vm.segments.segment_used_sizes = Some(vec![2, 1]);
+        let mut builtin = square_builtin(true);
+        assert_eq!(
+            builtin.final_stack(&vm.segments, relocatable!(1, 1)),
+            Ok(relocatable!(1, 0))
+        );
+        assert_eq!(builtin.get_used_instances(&vm.segments), Ok(1));
+        assert_eq!(builtin.get_allocated_memory_units(&vm), Ok(4));
+        assert_eq!(builtin.get_used_cells_and_allocated_size(&vm), Ok((2, 4)));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_security_checks_missing_input_cell() {
+        let mut vm = vm!();
+        vm.segments = segments![((0, 1), 9), ((0, 2), 3), ((0, 3), 9)];
+        let builtin = square_builtin(true);
+        assert_matches!(
+            builtin.run_security_checks(&vm),
+            Err(VirtualMachineError::Memory(
+                MemoryError::MissingMemoryCellsWithOffsets(bx)
+            )) if *bx == (BuiltinName::custom(&SQUARE), vec![0])
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn air_private_input_and_additional_data() {
+        let mut vm = vm!();
+        vm.segments = segments![((0, 0), 3), ((0, 1), 9)];
+        let mut builtin = square_builtin(true);
+        assert_eq!(
+            builtin.air_private_input(&vm.segments),
+            vec![PrivateInput::Value(PrivateInputValue {
+                index: 0,
+                value: Felt252::from(3)
+            })]
+        );
+        let additional_data = BuiltinAdditionalData::Hash(vec![relocatable!(0, 0)]);
+        builtin.extend_additional_data(&additional_data).unwrap();
+        assert_eq!(builtin.get_additional_data(), additional_data);
+        assert_eq!(
+            builtin.extend_additional_data(&BuiltinAdditionalData::Signature(HashMap::new())),
+            Err(RunnerError::InvalidAdditionalData(BuiltinName::custom(
+                &SQUARE
+            )))
+        );
+    }
+}
@@ -1,10 +1,11 @@
 use crate::air_private_input::{PrivateInput, PrivateInputEcOp};
+use crate::stdlib::collections::HashMap;
 use crate::stdlib::prelude::*;
-use crate::stdlib::{cell::RefCell, collections::HashMap};
 use crate::types::instance_definitions::ec_op_instance_def::{
     CELLS_PER_EC_OP, INPUT_CELLS_PER_EC_OP, SCALAR_HEIGHT,
 };
 use crate::types::relocatable::{MaybeRelocatable, Relocatable};
+use crate::types::shared::SharedCell;
 use crate::vm::errors::memory_errors::MemoryError;
 use crate::vm::errors::runner_errors::RunnerError;
 use crate::vm::vm_memory::memory::Memory;
@@ -19,7 +20,7 @@ pub struct EcOpBuiltinRunner {
     pub base: usize,
     pub(crate) stop_ptr: Option<usize>,
     pub(crate) included: bool,
-    cache: RefCell<HashMap<Relocatable, Felt252>>,
+    cache: SharedCell<HashMap<Relocatable, Felt252>>,
 }
 
 impl EcOpBuiltinRunner {
@@ -29,7 +30,7 @@ impl EcOpBuiltinRunner {
             ratio,
             stop_ptr: None,
             included,
-            cache: RefCell::new(HashMap::new()),
+            cache: SharedCell::new(HashMap::new()),
         }
     }
     ///Returns True if the point (x, y) is on the elliptic curve defined as
@@ -208,8 +209,7 @@ impl EcOpBuiltinRunner {
 
     pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
         let mut private_inputs = vec![];
-        if let Some(segment) = memory.data.get(self.base) {
-            let segment_len = segment.len();
+        if let Some(segment_len) = memory.data.segment_len(self.base) {
             for (index, off) in (0..segment_len)
                 .step_by(CELLS_PER_EC_OP as usize)
                 .enumerate()
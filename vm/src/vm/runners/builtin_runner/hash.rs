@@ -1,8 +1,9 @@
 use crate::air_private_input::{PrivateInput, PrivateInputPair};
-use crate::stdlib::{cell::RefCell, prelude::*};
+use crate::stdlib::prelude::*;
 use crate::types::builtin_name::BuiltinName;
 use crate::types::instance_definitions::pedersen_instance_def::CELLS_PER_HASH;
 use crate::types::relocatable::{MaybeRelocatable, Relocatable};
+use crate::types::shared::SharedCell;
 use crate::vm::errors::memory_errors::MemoryError;
 use crate::vm::errors::runner_errors::RunnerError;
 use crate::vm::runners::cairo_pie::BuiltinAdditionalData;
@@ -21,7 +22,7 @@ pub struct HashBuiltinRunner {
     // Therefore need interior mutability
     // 1 at position 'n' means offset 'n' relative to base pointer
     // has been verified
-    pub(self) verified_addresses: RefCell<Vec<bool>>,
+    pub(self) verified_addresses: SharedCell<Vec<bool>>,
 }
 
 impl HashBuiltinRunner {
@@ -30,7 +31,7 @@ impl HashBuiltinRunner {
             base: 0,
             ratio,
             stop_ptr: None,
-            verified_addresses: RefCell::new(Vec::new()),
+            verified_addresses: SharedCell::new(Vec::new()),
             included,
         }
     }
@@ -142,10 +143,13 @@ impl HashBuiltinRunner {
         Ok(())
     }
 
+    pub(crate) fn clear_additional_data(&mut self) {
+        self.verified_addresses.borrow_mut().clear();
+    }
+
     pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
         let mut private_inputs = vec![];
-        if let Some(segment) = memory.data.get(self.base) {
-            let segment_len = segment.len();
+        if let Some(segment_len) = memory.data.segment_len(self.base) {
             for (index, off) in (0..segment_len)
                 .step_by(CELLS_PER_HASH as usize)
                 .enumerate()
@@ -423,7 +427,7 @@ mod tests {
     fn deduce_memory_cell_pedersen_for_preset_memory_already_computed() {
         let memory = memory![((0, 3), 32), ((0, 4), 72), ((0, 5), 0)];
         let mut builtin = HashBuiltinRunner::new(Some(8), true);
-        builtin.verified_addresses = RefCell::new(vec![false, false, false, false, false, true]);
+        builtin.verified_addresses = SharedCell::new(vec![false, false, false, false, false, true]);
         let result = builtin.deduce_memory_cell(Relocatable::from((0, 5)), &memory);
         assert_eq!(result, Ok(None));
     }
@@ -465,7 +469,7 @@ mod tests {
         let mut builtin = HashBuiltinRunner::new(Some(1), true);
         let verified_addresses = vec![Relocatable::from((0, 3)), Relocatable::from((0, 6))];
         builtin.verified_addresses =
-            RefCell::new(vec![false, false, false, true, false, false, true]);
+            SharedCell::new(vec![false, false, false, true, false, false, true]);
         assert_eq!(
             builtin.get_additional_data(),
             BuiltinAdditionalData::Hash(verified_addresses)
@@ -476,7 +480,7 @@ mod tests {
     fn get_and_extend_additional_data() {
         let mut builtin_a = HashBuiltinRunner::new(Some(1), true);
         builtin_a.verified_addresses =
-            RefCell::new(vec![false, false, false, true, false, false, true]);
+            SharedCell::new(vec![false, false, false, true, false, false, true]);
         let additional_data = builtin_a.get_additional_data();
         let mut builtin_b = HashBuiltinRunner::new(Some(1), true);
         builtin_b.extend_additional_data(&additional_data).unwrap();
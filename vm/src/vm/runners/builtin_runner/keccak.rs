@@ -1,11 +1,12 @@
 use crate::air_private_input::{PrivateInput, PrivateInputKeccakState};
 use crate::math_utils::safe_div_usize;
-use crate::stdlib::{cell::RefCell, collections::HashMap, prelude::*};
+use crate::stdlib::{collections::HashMap, prelude::*};
 use crate::types::builtin_name::BuiltinName;
 use crate::types::instance_definitions::keccak_instance_def::{
     CELLS_PER_KECCAK, INPUT_CELLS_PER_KECCAK,
 };
 use crate::types::relocatable::{MaybeRelocatable, Relocatable};
+use crate::types::shared::SharedCell;
 use crate::vm::errors::memory_errors::MemoryError;
 use crate::vm::errors::runner_errors::RunnerError;
 use crate::vm::vm_memory::memory::Memory;
@@ -27,7 +28,7 @@ pub struct KeccakBuiltinRunner {
     pub base: usize,
     pub(crate) stop_ptr: Option<usize>,
     pub(crate) included: bool,
-    cache: RefCell<HashMap<Relocatable, Felt252>>,
+    cache: SharedCell<HashMap<Relocatable, Felt252>>,
 }
 
 impl KeccakBuiltinRunner {
@@ -37,7 +38,7 @@ impl KeccakBuiltinRunner {
             ratio,
             stop_ptr: None,
             included,
-            cache: RefCell::new(HashMap::new()),
+            cache: SharedCell::new(HashMap::new()),
         }
     }
 
@@ -164,8 +165,7 @@ impl KeccakBuiltinRunner {
 
     pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
         let mut private_inputs = vec![];
-        if let Some(segment) = memory.data.get(self.base) {
-            let segment_len = segment.len();
+        if let Some(segment_len) = memory.data.segment_len(self.base) {
             for (index, off) in (0..segment_len)
                 .step_by(CELLS_PER_KECCAK as usize)
                 .enumerate()
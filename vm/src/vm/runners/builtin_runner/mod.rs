@@ -30,6 +30,7 @@ use crate::vm::vm_memory::memory::Memory;
 use crate::vm::vm_memory::memory_segments::MemorySegmentManager;
 
 mod bitwise;
+mod custom;
 mod ec_op;
 mod hash;
 mod keccak;
@@ -44,6 +45,7 @@ pub use self::keccak::KeccakBuiltinRunner;
 pub(crate) use self::range_check::{RC_N_PARTS_96, RC_N_PARTS_STANDARD};
 use self::segment_arena::ARENA_BUILTIN_SIZE;
 pub use bitwise::BitwiseBuiltinRunner;
+pub use custom::{CustomBuiltin, CustomBuiltinClone, CustomBuiltinRunner};
 pub use ec_op::EcOpBuiltinRunner;
 pub use hash::HashBuiltinRunner;
 pub use modulo::ModBuiltinRunner;
@@ -61,8 +63,8 @@ use super::cairo_pie::BuiltinAdditionalData;
  * are either storing a `dyn Trait` inside an `Arc<Mutex<&dyn Trait>>` or
  * making the type itself `Send`. We opted for not complicating the user nor
  * moving the guarantees to runtime by using an `enum` rather than a `Trait`.
- * This works under the assumption that we don't expect downstream users to
- * extend Cairo by adding new builtin runners.
+ * Downstream users extending Cairo with new builtins implement the
+ * `CustomBuiltin` trait instead, which is wrapped by the `Custom` variant.
  */
 #[derive(Debug, Clone)]
 pub enum BuiltinRunner {
@@ -77,6 +79,7 @@ pub enum BuiltinRunner {
     Poseidon(PoseidonBuiltinRunner),
     SegmentArena(SegmentArenaBuiltinRunner),
     Mod(ModBuiltinRunner),
+    Custom(CustomBuiltinRunner),
 }
 
 impl BuiltinRunner {
@@ -100,6 +103,7 @@ impl BuiltinRunner {
                 segment_arena.initialize_segments(segments)
             }
             BuiltinRunner::Mod(ref mut modulo) => modulo.initialize_segments(segments),
+            BuiltinRunner::Custom(ref mut custom) => custom.initialize_segments(segments),
         }
     }
 
@@ -116,6 +120,7 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(ref poseidon) => poseidon.initial_stack(),
             BuiltinRunner::SegmentArena(ref segment_arena) => segment_arena.initial_stack(),
             BuiltinRunner::Mod(ref modulo) => modulo.initial_stack(),
+            BuiltinRunner::Custom(ref custom) => custom.initial_stack(),
         }
     }
 
@@ -216,6 +221,7 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(ref poseidon) => poseidon.included,
             BuiltinRunner::SegmentArena(ref segment_arena) => segment_arena.included,
             BuiltinRunner::Mod(ref modulo) => modulo.included,
+            BuiltinRunner::Custom(ref custom) => custom.included,
         }
     }
 
@@ -234,6 +240,7 @@ impl BuiltinRunner {
             //Warning, returns only the segment index, base offset will be 3
             BuiltinRunner::SegmentArena(ref segment_arena) => segment_arena.base(),
             BuiltinRunner::Mod(ref modulo) => modulo.base(),
+            BuiltinRunner::Custom(ref custom) => custom.base(),
         }
     }
 
@@ -249,6 +256,7 @@ impl BuiltinRunner {
             BuiltinRunner::Signature(ref signature) => signature.ratio(),
             BuiltinRunner::Poseidon(poseidon) => poseidon.ratio(),
             BuiltinRunner::Mod(ref modulo) => modulo.ratio(),
+            BuiltinRunner::Custom(ref custom) => custom.ratio(),
         }
     }
 
@@ -258,6 +266,7 @@ impl BuiltinRunner {
             BuiltinRunner::RangeCheck96(ref range_check) => range_check.add_validation_rule(memory),
             BuiltinRunner::Signature(ref signature) => signature.add_validation_rule(memory),
             BuiltinRunner::Poseidon(ref poseidon) => poseidon.add_validation_rule(memory),
+            BuiltinRunner::Custom(ref custom) => custom.add_validation_rule(memory),
             _ => {}
         }
     }
@@ -273,6 +282,7 @@ impl BuiltinRunner {
             BuiltinRunner::Hash(ref hash) => hash.deduce_memory_cell(address, memory),
             BuiltinRunner::Keccak(ref keccak) => keccak.deduce_memory_cell(address, memory),
             BuiltinRunner::Poseidon(ref poseidon) => poseidon.deduce_memory_cell(address, memory),
+            BuiltinRunner::Custom(ref custom) => custom.deduce_memory_cell(address, memory),
             _ => Ok(None),
         }
     }
@@ -296,6 +306,7 @@ impl BuiltinRunner {
                 segment_arena.get_used_cells(segments)
             }
             BuiltinRunner::Mod(ref modulo) => modulo.get_used_cells(segments),
+            BuiltinRunner::Custom(ref custom) => custom.get_used_cells(segments),
         }
     }
 
@@ -319,6 +330,7 @@ impl BuiltinRunner {
                 segment_arena.get_used_instances(segments)
             }
             BuiltinRunner::Mod(modulo) => modulo.get_used_instances(segments),
+            BuiltinRunner::Custom(custom) => custom.get_used_instances(segments),
         }
     }
 
@@ -362,7 +374,7 @@ impl BuiltinRunner {
         }
     }
 
-    fn cells_per_instance(&self) -> u32 {
+    pub(crate) fn cells_per_instance(&self) -> u32 {
         match self {
             BuiltinRunner::Bitwise(_) => CELLS_PER_BITWISE,
             BuiltinRunner::EcOp(_) => CELLS_PER_EC_OP,
@@ -374,6 +386,7 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(_) => CELLS_PER_POSEIDON,
             BuiltinRunner::SegmentArena(_) => ARENA_BUILTIN_SIZE,
             BuiltinRunner::Mod(_) => CELLS_PER_MOD,
+            BuiltinRunner::Custom(custom) => custom.builtin().cells_per_instance(),
         }
     }
 
@@ -389,12 +402,14 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(_) => INPUT_CELLS_PER_POSEIDON,
             BuiltinRunner::SegmentArena(_) => ARENA_BUILTIN_SIZE,
             BuiltinRunner::Mod(_) => CELLS_PER_MOD,
+            BuiltinRunner::Custom(custom) => custom.builtin().n_input_cells(),
         }
     }
 
     fn instances_per_component(&self) -> u32 {
         match self {
             BuiltinRunner::Keccak(_) => KECCAK_INSTANCES_PER_COMPONENT,
+            BuiltinRunner::Custom(custom) => custom.builtin().instances_per_component(),
             _ => BUILTIN_INSTANCES_PER_COMPONENT,
         }
     }
@@ -412,6 +427,7 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(_) => BuiltinName::poseidon,
             BuiltinRunner::SegmentArena(_) => BuiltinName::segment_arena,
             BuiltinRunner::Mod(b) => b.name(),
+            BuiltinRunner::Custom(custom) => BuiltinName::custom(custom.builtin().name()),
         }
     }
 
@@ -422,21 +438,30 @@ impl BuiltinRunner {
         if let BuiltinRunner::Mod(modulo) = self {
             modulo.run_additional_security_checks(vm)?;
         }
+        if let BuiltinRunner::Custom(custom) = self {
+            custom.run_additional_security_checks(vm)?;
+        }
         let cells_per_instance = self.cells_per_instance() as usize;
         let n_input_cells = self.n_input_cells() as usize;
         let builtin_segment_index = self.base();
+        let memory = &vm.segments.memory.data;
         // If the builtin's segment is empty, there are no security checks to run
-        let builtin_segment = match vm.segments.memory.data.get(builtin_segment_index) {
-            Some(segment) if !segment.is_empty() => segment,
+        let segment_len = match memory.segment_len(builtin_segment_index) {
+            Some(len) if len > 0 => len,
             _ => return Ok(()),
         };
+        let is_written = |offset| {
+            memory
+                .get(builtin_segment_index, offset)
+                .is_some_and(|cell| cell.is_some())
+        };
         // The builtin segment's size - 1 is the maximum offset within the segment's addresses
         // Assumption: The last element is not a None value
         // It is safe to asume this for normal program execution
         // If there are trailing None values at the end, the following security checks will fail
-        let offset_max = builtin_segment.len().saturating_sub(1);
+        let offset_max = segment_len - 1;
         // offset_len is the amount of non-None values in the segment
-        let offset_len = builtin_segment.iter().filter(|x| x.is_some()).count();
+        let offset_len = memory.written_cells(builtin_segment_index).count();
         let n = match offset_len {
             0 => 0,
             _ => div_floor(offset_max, cells_per_instance) + 1,
@@ -452,11 +477,7 @@ impl BuiltinRunner {
         for i in 0..n {
             for j in 0..n_input_cells {
                 let offset = cells_per_instance * i + j;
-                if builtin_segment
-                    .get(offset)
-                    .filter(|x| x.is_some())
-                    .is_none()
-                {
+                if !is_written(offset) {
                     missing_offsets.push(offset)
                 }
             }
@@ -473,11 +494,7 @@ impl BuiltinRunner {
         for i in 0..n {
             for j in n_input_cells..cells_per_instance {
                 let offset = cells_per_instance * i + j;
-                if builtin_segment
-                    .get(offset)
-                    .filter(|x| x.is_some())
-                    .is_none()
-                {
+                if !is_written(offset) {
                     vm.verify_auto_deductions_for_addr(
                         Relocatable::from((builtin_segment_index as isize, offset)),
                         self,
@@ -519,6 +536,7 @@ impl BuiltinRunner {
             BuiltinRunner::Hash(builtin) => builtin.get_additional_data(),
             BuiltinRunner::Output(builtin) => builtin.get_additional_data(),
             BuiltinRunner::Signature(builtin) => builtin.get_additional_data(),
+            BuiltinRunner::Custom(builtin) => builtin.builtin().get_additional_data(),
             _ => BuiltinAdditionalData::None,
         }
     }
@@ -533,10 +551,24 @@ impl BuiltinRunner {
             BuiltinRunner::Hash(builtin) => builtin.extend_additional_data(additional_data),
             BuiltinRunner::Output(builtin) => builtin.extend_additional_data(additional_data),
             BuiltinRunner::Signature(builtin) => builtin.extend_additional_data(additional_data),
+            BuiltinRunner::Custom(builtin) => builtin
+                .builtin_mut()
+                .extend_additional_data(additional_data),
             _ => Ok(()),
         }
     }
 
+    /// Clears the builtin's internal data, see [Self::get_additional_data]
+    pub(crate) fn clear_additional_data(&mut self) {
+        match self {
+            BuiltinRunner::Hash(builtin) => builtin.clear_additional_data(),
+            BuiltinRunner::Output(builtin) => builtin.clear_additional_data(),
+            BuiltinRunner::Signature(builtin) => builtin.clear_additional_data(),
+            BuiltinRunner::Custom(builtin) => builtin.builtin_mut().clear_additional_data(),
+            _ => {}
+        }
+    }
+
     // Returns information about the builtin that should be added to the AIR private input.
     pub fn air_private_input(&self, segments: &MemorySegmentManager) -> Vec<PrivateInput> {
         match self {
@@ -549,6 +581,7 @@ impl BuiltinRunner {
             BuiltinRunner::Signature(builtin) => builtin.air_private_input(&segments.memory),
             BuiltinRunner::Keccak(builtin) => builtin.air_private_input(&segments.memory),
             BuiltinRunner::Mod(builtin) => builtin.air_private_input(segments),
+            BuiltinRunner::Custom(builtin) => builtin.air_private_input(&segments.memory),
             _ => vec![],
         }
     }
@@ -570,6 +603,24 @@ impl BuiltinRunner {
                 segment_arena.stop_ptr = Some(stop_ptr)
             }
             BuiltinRunner::Mod(modulo) => modulo.stop_ptr = Some(stop_ptr),
+            BuiltinRunner::Custom(custom) => custom.stop_ptr = Some(stop_ptr),
+        }
+    }
+
+    pub(crate) fn clear_stop_ptr(&mut self) {
+        match self {
+            BuiltinRunner::Bitwise(ref mut bitwise) => bitwise.stop_ptr = None,
+            BuiltinRunner::EcOp(ref mut ec) => ec.stop_ptr = None,
+            BuiltinRunner::Hash(ref mut hash) => hash.stop_ptr = None,
+            BuiltinRunner::Output(ref mut output) => output.stop_ptr = None,
+            BuiltinRunner::RangeCheck(ref mut range_check) => range_check.stop_ptr = None,
+            BuiltinRunner::RangeCheck96(ref mut range_check) => range_check.stop_ptr = None,
+            BuiltinRunner::Keccak(ref mut keccak) => keccak.stop_ptr = None,
+            BuiltinRunner::Signature(ref mut signature) => signature.stop_ptr = None,
+            BuiltinRunner::Poseidon(ref mut poseidon) => poseidon.stop_ptr = None,
+            BuiltinRunner::SegmentArena(ref mut segment_arena) => segment_arena.stop_ptr = None,
+            BuiltinRunner::Mod(modulo) => modulo.stop_ptr = None,
+            BuiltinRunner::Custom(custom) => custom.stop_ptr = None,
         }
     }
 
@@ -586,6 +637,7 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(ref poseidon) => poseidon.stop_ptr,
             BuiltinRunner::SegmentArena(ref segment_arena) => segment_arena.stop_ptr,
             BuiltinRunner::Mod(ref modulo) => modulo.stop_ptr,
+            BuiltinRunner::Custom(ref custom) => custom.stop_ptr,
         }
     }
 }
@@ -656,6 +708,12 @@ impl From<ModBuiltinRunner> for BuiltinRunner {
     }
 }
 
+impl From<CustomBuiltinRunner> for BuiltinRunner {
+    fn from(runner: CustomBuiltinRunner) -> Self {
+        BuiltinRunner::Custom(runner)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -666,6 +724,7 @@ mod tests {
     use crate::utils::test_utils::*;
     use crate::vm::errors::memory_errors::InsufficientAllocatedCellsError;
     use crate::vm::vm_memory::memory::MemoryCell;
+    use crate::vm::vm_memory::memory_backend::MemoryStorage;
     use assert_matches::assert_matches;
 
     #[cfg(target_arch = "wasm32")]
@@ -1190,7 +1249,7 @@ mod tests {
         let builtin = BuiltinRunner::Bitwise(BitwiseBuiltinRunner::new(Some(256), true));
         let mut vm = vm!();
 
-        vm.segments.memory.data = vec![vec![]];
+        vm.segments.memory.data = MemoryStorage::from(vec![vec![]]);
 
         assert_matches!(builtin.run_security_checks(&vm), Ok(()));
     }
@@ -1327,7 +1386,11 @@ mod tests {
 
         let mut vm = vm!();
 
-        vm.segments.memory.data = vec![vec![MemoryCell::NONE, MemoryCell::NONE, MemoryCell::NONE]];
+        vm.segments.memory.data = MemoryStorage::from(vec![vec![
+            MemoryCell::NONE,
+            MemoryCell::NONE,
+            MemoryCell::NONE,
+        ]]);
 
         assert_matches!(builtin.run_security_checks(&vm), Ok(()));
     }
@@ -1363,7 +1426,7 @@ mod tests {
 
         let mut vm = vm!();
         // The values stored in memory are not relevant for this test
-        vm.segments.memory.data = vec![vec![]];
+        vm.segments.memory.data = MemoryStorage::from(vec![vec![]]);
 
         assert_matches!(builtin.run_security_checks(&vm), Ok(()));
     }
@@ -120,8 +120,12 @@ impl ModBuiltinRunner {
         self.base = segments.add().segment_index as usize; // segments.add() always returns a positive index
     }
 
-    pub fn initialize_zero_segment(&mut self, segments: &mut MemorySegmentManager) {
-        self.zero_segment_index = segments.add_zero_segment(self.zero_segment_size);
+    pub fn initialize_zero_segment(
+        &mut self,
+        segments: &mut MemorySegmentManager,
+    ) -> Result<(), MemoryError> {
+        self.zero_segment_index = segments.add_zero_segment(self.zero_segment_size)?;
+        Ok(())
     }
 
     pub fn initial_stack(&self) -> Vec<MaybeRelocatable> {
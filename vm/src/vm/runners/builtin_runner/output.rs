@@ -137,6 +137,11 @@ impl OutputBuiltinRunner {
         Ok(())
     }
 
+    pub(crate) fn clear_additional_data(&mut self) {
+        self.pages.clear();
+        self.attributes.clear();
+    }
+
     pub(crate) fn set_stop_ptr_offset(&mut self, offset: usize) {
         self.stop_ptr = Some(offset)
     }
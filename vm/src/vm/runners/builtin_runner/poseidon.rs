@@ -1,10 +1,11 @@
 use crate::air_private_input::{PrivateInput, PrivateInputPoseidonState};
-use crate::stdlib::{cell::RefCell, collections::HashMap, prelude::*};
+use crate::stdlib::{collections::HashMap, prelude::*};
 use crate::types::builtin_name::BuiltinName;
 use crate::types::instance_definitions::poseidon_instance_def::{
     CELLS_PER_POSEIDON, INPUT_CELLS_PER_POSEIDON,
 };
 use crate::types::relocatable::{MaybeRelocatable, Relocatable};
+use crate::types::shared::SharedCell;
 use crate::vm::errors::memory_errors::MemoryError;
 use crate::vm::errors::runner_errors::RunnerError;
 use crate::vm::vm_memory::memory::Memory;
@@ -19,7 +20,7 @@ pub struct PoseidonBuiltinRunner {
     ratio: Option<u32>,
     pub(crate) stop_ptr: Option<usize>,
     pub(crate) included: bool,
-    cache: RefCell<HashMap<Relocatable, Felt252>>,
+    cache: SharedCell<HashMap<Relocatable, Felt252>>,
 }
 
 impl PoseidonBuiltinRunner {
@@ -29,7 +30,7 @@ impl PoseidonBuiltinRunner {
             ratio,
             stop_ptr: None,
             included,
-            cache: RefCell::new(HashMap::new()),
+            cache: SharedCell::new(HashMap::new()),
         }
     }
 
@@ -113,8 +114,7 @@ impl PoseidonBuiltinRunner {
 
     pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
         let mut private_inputs = vec![];
-        if let Some(segment) = memory.data.get(self.base) {
-            let segment_len = segment.len();
+        if let Some(segment_len) = memory.data.segment_len(self.base) {
             for (index, off) in (0..segment_len)
                 .step_by(CELLS_PER_POSEIDON as usize)
                 .enumerate()
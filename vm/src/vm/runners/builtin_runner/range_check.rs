@@ -115,12 +115,11 @@ impl<const N_PARTS: u64> RangeCheckBuiltinRunner<N_PARTS> {
     }
 
     pub fn get_range_check_usage(&self, memory: &Memory) -> Option<(usize, usize)> {
-        let range_check_segment = memory.data.get(self.base)?;
         let mut rc_bounds =
-            (!range_check_segment.is_empty()).then_some((usize::MAX, usize::MIN))?;
+            (memory.data.segment_len(self.base)? > 0).then_some((usize::MAX, usize::MIN))?;
 
         // Split value into n_parts parts of less than _INNER_RC_BOUND size.
-        for value in range_check_segment {
+        for (_, value) in memory.data.written_cells(self.base) {
             rc_bounds = value
                 .get_value()?
                 .get_int_ref()?
@@ -149,11 +148,9 @@ impl<const N_PARTS: u64> RangeCheckBuiltinRunner<N_PARTS> {
 
     pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
         let mut private_inputs = vec![];
-        if let Some(segment) = memory.data.get(self.base) {
-            for (index, cell) in segment.iter().enumerate() {
-                if let Some(value) = cell.get_value().and_then(|value| value.get_int()) {
-                    private_inputs.push(PrivateInput::Value(PrivateInputValue { index, value }))
-                }
+        for (index, cell) in memory.data.written_cells(self.base) {
+            if let Some(value) = cell.get_value().and_then(|value| value.get_int()) {
+                private_inputs.push(PrivateInput::Value(PrivateInputValue { index, value }))
             }
         }
         private_inputs
@@ -1,10 +1,11 @@
 use crate::air_private_input::{PrivateInput, PrivateInputSignature, SignatureInput};
 use crate::math_utils::div_mod;
-use crate::stdlib::{cell::RefCell, collections::HashMap, prelude::*, rc::Rc};
+use crate::stdlib::{collections::HashMap, prelude::*};
 
 use crate::types::builtin_name::BuiltinName;
 use crate::types::errors::math_errors::MathError;
 use crate::types::instance_definitions::ecdsa_instance_def::CELLS_PER_SIGNATURE;
+use crate::types::shared::{Shared, SharedCell};
 use crate::vm::errors::runner_errors::RunnerError;
 use crate::vm::runners::cairo_pie::BuiltinAdditionalData;
 use crate::Felt252;
@@ -38,7 +39,7 @@ pub struct SignatureBuiltinRunner {
     ratio: Option<u32>,
     base: usize,
     pub(crate) stop_ptr: Option<usize>,
-    signatures: Rc<RefCell<HashMap<Relocatable, Signature>>>,
+    signatures: Shared<SharedCell<HashMap<Relocatable, Signature>>>,
 }
 
 impl SignatureBuiltinRunner {
@@ -48,7 +49,7 @@ impl SignatureBuiltinRunner {
             included,
             ratio,
             stop_ptr: None,
-            signatures: Rc::new(RefCell::new(HashMap::new())),
+            signatures: Shared::new(SharedCell::new(HashMap::new())),
         }
     }
 
@@ -96,7 +97,7 @@ impl SignatureBuiltinRunner {
     }
     pub fn add_validation_rule(&self, memory: &mut Memory) {
         let cells_per_instance = CELLS_PER_SIGNATURE;
-        let signatures = Rc::clone(&self.signatures);
+        let signatures = Shared::clone(&self.signatures);
         let rule: ValidationRule = ValidationRule(Box::new(
             move |memory: &Memory, addr: Relocatable| -> Result<Vec<Relocatable>, MemoryError> {
                 let cell_index = addr.offset % cells_per_instance as usize;
@@ -208,6 +209,10 @@ impl SignatureBuiltinRunner {
         Ok(())
     }
 
+    pub(crate) fn clear_additional_data(&mut self) {
+        self.signatures.borrow_mut().clear();
+    }
+
     pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
         let mut private_inputs = vec![];
         for (addr, signature) in self.signatures.borrow().iter() {
@@ -517,7 +522,7 @@ mod tests {
                 s: FieldElement::from_dec_str("1239").unwrap(),
             },
         )]);
-        builtin.signatures = Rc::new(RefCell::new(signatures));
+        builtin.signatures = Shared::new(SharedCell::new(signatures));
         let signatures = HashMap::from([(
             Relocatable::from((4, 0)),
             (felt_str!("45678"), felt_str!("1239")),
@@ -538,7 +543,7 @@ mod tests {
                 s: FieldElement::from_dec_str("1239").unwrap(),
             },
         )]);
-        builtin_a.signatures = Rc::new(RefCell::new(signatures));
+        builtin_a.signatures = Shared::new(SharedCell::new(signatures));
         let additional_data = builtin_a.get_additional_data();
         let mut builtin_b = SignatureBuiltinRunner::new(Some(512), true);
         builtin_b.extend_additional_data(&additional_data).unwrap();
@@ -6,8 +6,9 @@ use crate::{
         collections::{HashMap, HashSet},
         ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign},
         prelude::*,
+        sync::Arc,
     },
-    types::{builtin_name::BuiltinName, layout::MEMORY_UNITS_PER_STEP, layout_name::LayoutName},
+    types::{builtin_name::BuiltinName, layout::CairoLayoutParams, layout_name::LayoutName},
     vm::{
         runners::builtin_runner::SegmentArenaBuiltinRunner,
         trace::trace_entry::{relocate_trace_register, RelocatedTraceEntry},
@@ -21,6 +22,7 @@ use crate::{
     types::{
         errors::{math_errors::MathError, program_errors::ProgramError},
         exec_scope::ExecutionScopes,
+        instruction::Instruction,
         layout::CairoLayout,
         program::Program,
         relocatable::{relocate_address, relocate_value, MaybeRelocatable, Relocatable},
@@ -38,8 +40,9 @@ use crate::{
         security::verify_secure_runner,
         {
             runners::builtin_runner::{
-                BitwiseBuiltinRunner, BuiltinRunner, EcOpBuiltinRunner, HashBuiltinRunner,
-                OutputBuiltinRunner, RangeCheckBuiltinRunner, SignatureBuiltinRunner,
+                BitwiseBuiltinRunner, BuiltinRunner, CustomBuiltin, CustomBuiltinRunner,
+                EcOpBuiltinRunner, HashBuiltinRunner, OutputBuiltinRunner, RangeCheckBuiltinRunner,
+                SignatureBuiltinRunner,
             },
             vm_core::VirtualMachine,
         },
@@ -57,6 +60,12 @@ use super::{
     cairo_pie::{self, CairoPie, CairoPieMetadata, CairoPieVersion},
 };
 use crate::types::instance_definitions::mod_instance_def::ModInstanceDef;
+#[cfg(feature = "extensive_hints")]
+use crate::types::program::HintRange;
+#[cfg(feature = "std")]
+use crate::vm::coverage::CoverageReport;
+use crate::vm::profiler::Profile;
+use crate::vm::runners::snapshot::{ExecScopesSnapshot, RunnerSnapshot, VmSnapshot};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum CairoArg {
@@ -140,10 +149,22 @@ impl ResourceTracker for RunResources {
     }
 }
 
+/// Hint data of a program compiled by a [HintProcessor], returned by
+/// [CairoRunner::compile_hints]. Resuming a run with the same instance avoids compiling the hints
+/// again, and keeps the hints added by [HintExtension](crate::hint_processor::hint_processor_definition::HintExtension)s
+/// under the `extensive_hints` feature.
+pub struct CompiledHints {
+    hint_data: Vec<Box<dyn Any>>,
+    #[cfg(feature = "extensive_hints")]
+    hint_ranges: HashMap<Relocatable, HintRange>,
+}
+
 pub struct CairoRunner {
     pub vm: VirtualMachine,
-    pub(crate) program: Program,
-    layout: CairoLayout,
+    pub(crate) program: Arc<Program>,
+    /// Instructions of the program decoded ahead of time, see [ProgramCache](super::program_cache::ProgramCache)
+    pub(crate) instruction_cache: Option<Arc<Vec<Option<Instruction>>>>,
+    pub(crate) layout: CairoLayout,
     final_pc: Option<Relocatable>,
     pub program_base: Option<Relocatable>,
     execution_base: Option<Relocatable>,
@@ -158,6 +179,8 @@ pub struct CairoRunner {
     pub relocated_memory: Vec<Option<Felt252>>,
     pub exec_scopes: ExecutionScopes,
     pub relocated_trace: Option<Vec<RelocatedTraceEntry>>,
+    /// Builtins implemented outside of the VM, see [CairoRunner::add_custom_builtin]
+    custom_builtins: Vec<Box<dyn CustomBuiltin>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -168,9 +191,29 @@ pub enum RunnerMode {
 }
 
 impl CairoRunner {
+    /// Creates a runner for `program`.
+    /// `dynamic_layout_params` sets the builtin ratios and the trace cells of the `dynamic` layout,
+    /// which uses default parameters if they are not provided. They are ignored by the other layouts.
     pub fn new_v2(
         program: &Program,
         layout: LayoutName,
+        dynamic_layout_params: Option<CairoLayoutParams>,
+        mode: RunnerMode,
+        trace_enabled: bool,
+    ) -> Result<CairoRunner, RunnerError> {
+        Self::new_from_shared(
+            Arc::new(program.clone()),
+            layout,
+            dynamic_layout_params,
+            mode,
+            trace_enabled,
+        )
+    }
+
+    pub(crate) fn new_from_shared(
+        program: Arc<Program>,
+        layout: LayoutName,
+        dynamic_layout_params: Option<CairoLayoutParams>,
         mode: RunnerMode,
         trace_enabled: bool,
     ) -> Result<CairoRunner, RunnerError> {
@@ -185,16 +228,20 @@ impl CairoRunner {
             LayoutName::recursive_with_poseidon => CairoLayout::recursive_with_poseidon(),
             LayoutName::all_cairo => CairoLayout::all_cairo_instance(),
             LayoutName::all_solidity => CairoLayout::all_solidity_instance(),
-            LayoutName::dynamic => CairoLayout::dynamic_instance(),
+            LayoutName::dynamic => match dynamic_layout_params {
+                Some(params) => CairoLayout::dynamic_instance_with_params(params),
+                None => CairoLayout::dynamic_instance(),
+            },
         };
         Ok(CairoRunner {
-            program: program.clone(),
+            entrypoint: program.shared_program_data.main,
+            program,
+            instruction_cache: None,
             vm: VirtualMachine::new(trace_enabled),
             layout: cairo_layout,
             final_pc: None,
             program_base: None,
             execution_base: None,
-            entrypoint: program.shared_program_data.main,
             initial_ap: None,
             initial_fp: None,
             initial_pc: None,
@@ -209,6 +256,7 @@ impl CairoRunner {
                 None
             },
             relocated_trace: None,
+            custom_builtins: Vec::new(),
         })
     }
 
@@ -222,11 +270,18 @@ impl CairoRunner {
             Self::new_v2(
                 program,
                 layout,
+                None,
                 RunnerMode::ProofModeCanonical,
                 trace_enabled,
             )
         } else {
-            Self::new_v2(program, layout, RunnerMode::ExecutionMode, trace_enabled)
+            Self::new_v2(
+                program,
+                layout,
+                None,
+                RunnerMode::ExecutionMode,
+                trace_enabled,
+            )
         }
     }
 
@@ -236,7 +291,9 @@ impl CairoRunner {
         let end = self.initialize_main_entrypoint()?;
         for builtin_runner in self.vm.builtin_runners.iter_mut() {
             if let BuiltinRunner::Mod(runner) = builtin_runner {
-                runner.initialize_zero_segment(&mut self.vm.segments);
+                runner
+                    .initialize_zero_segment(&mut self.vm.segments)
+                    .map_err(RunnerError::MemoryInitializationError)?;
             }
         }
         self.initialize_vm()?;
@@ -261,6 +318,15 @@ impl CairoRunner {
             BuiltinName::add_mod,
             BuiltinName::mul_mod,
         ];
+        // Custom builtins come after the ones of the VM, in their registration order
+        let builtin_ordered_list: Vec<_> = builtin_ordered_list
+            .into_iter()
+            .chain(
+                self.custom_builtins
+                    .iter()
+                    .map(|builtin| BuiltinName::custom(builtin.name())),
+            )
+            .collect();
         if !is_subsequence(&self.program.builtins, &builtin_ordered_list) {
             return Err(RunnerError::DisorderedBuiltins);
         };
@@ -367,6 +433,14 @@ impl CairoRunner {
                     .push(ModBuiltinRunner::new_mul_mod(instance_def, included).into());
             }
         }
+        for builtin in self.custom_builtins.iter() {
+            let included = program_builtins.remove(&BuiltinName::custom(builtin.name()));
+            if included || self.is_proof_mode() {
+                self.vm
+                    .builtin_runners
+                    .push(CustomBuiltinRunner::new(builtin.clone(), included).into());
+            }
+        }
         if !program_builtins.is_empty() && !allow_missing_builtins {
             return Err(RunnerError::NoBuiltinForInstance(Box::new((
                 program_builtins.iter().map(|n| **n).collect(),
@@ -377,6 +451,18 @@ impl CairoRunner {
         Ok(())
     }
 
+    /// Registers a builtin implemented outside of the VM, which is created by
+    /// [CairoRunner::initialize_builtins] along with the builtins of the layout.
+    /// Custom builtins have to be registered before initializing the runner, and be used by the
+    /// program after the builtins of the VM, in their registration order.
+    /// Its name is registered with [BuiltinName::register_custom], so that programs and Cairo PIEs
+    /// loaded afterwards can use it [`std` feature].
+    pub fn add_custom_builtin(&mut self, builtin: Box<dyn CustomBuiltin>) {
+        #[cfg(feature = "std")]
+        BuiltinName::register_custom(builtin.name());
+        self.custom_builtins.push(builtin);
+    }
+
     fn is_proof_mode(&self) -> bool {
         self.runner_mode == RunnerMode::ProofModeCanonical
             || self.runner_mode == RunnerMode::ProofModeCairo1
@@ -385,7 +471,12 @@ impl CairoRunner {
     // Initialize all program builtins. Values used are the original one from the CairoFunctionRunner
     // Values extracted from here: https://github.com/starkware-libs/cairo-lang/blob/4fb83010ab77aa7ead0c9df4b0c05e030bc70b87/src/starkware/cairo/common/cairo_function_runner.py#L28
     pub fn initialize_program_builtins(&mut self) -> Result<(), RunnerError> {
-        fn initialize_builtin(name: BuiltinName, vm: &mut VirtualMachine) {
+        fn initialize_builtin(
+            name: BuiltinName,
+            vm: &mut VirtualMachine,
+            custom_builtins: &[Box<dyn CustomBuiltin>],
+            layout: LayoutName,
+        ) -> Result<(), RunnerError> {
             match name {
                 BuiltinName::pedersen => vm
                     .builtin_runners
@@ -425,11 +516,30 @@ impl CairoRunner {
                     ModBuiltinRunner::new_mul_mod(&ModInstanceDef::new(Some(1), 1, 96), true)
                         .into(),
                 ),
+                BuiltinName::custom(custom_name) => {
+                    let builtin = custom_builtins
+                        .iter()
+                        .find(|builtin| builtin.name() == custom_name)
+                        .ok_or_else(|| {
+                            RunnerError::NoBuiltinForInstance(Box::new((
+                                HashSet::from([name]),
+                                layout,
+                            )))
+                        })?;
+                    vm.builtin_runners
+                        .push(CustomBuiltinRunner::new(builtin.clone(), true).into())
+                }
             }
+            Ok(())
         }
 
         for builtin_name in &self.program.builtins {
-            initialize_builtin(*builtin_name, &mut self.vm);
+            initialize_builtin(
+                *builtin_name,
+                &mut self.vm,
+                &self.custom_builtins,
+                self.layout.name,
+            )?;
         }
         Ok(())
     }
@@ -457,6 +567,11 @@ impl CairoRunner {
         self.vm
             .load_data(prog_base, &self.program.shared_program_data.data)
             .map_err(RunnerError::MemoryInitializationError)?;
+        if prog_base == Relocatable::from((0, 0)) {
+            if let Some(instruction_cache) = &self.instruction_cache {
+                self.vm.set_instruction_cache(instruction_cache.clone());
+            }
+        }
 
         // Mark all addresses from the program segment as accessed
         for i in 0..self.program.shared_program_data.data.len() {
@@ -642,37 +757,74 @@ impl CairoRunner {
         address: Relocatable,
         hint_processor: &mut dyn HintProcessor,
     ) -> Result<(), VirtualMachineError> {
+        let mut hints = self.compile_hints(hint_processor)?;
+        self.run_until_pc_or_breakpoint(address, hint_processor, &mut hints, |_| false)?;
+        Ok(())
+    }
+
+    /// Compiles the hints of the program with `hint_processor` and runs the `before_first_step`
+    /// hook. The returned [CompiledHints] are used by [CairoRunner::run_until_pc_or_breakpoint],
+    /// and must be kept between the calls made to resume a run.
+    pub fn compile_hints(
+        &mut self,
+        hint_processor: &mut dyn HintProcessor,
+    ) -> Result<CompiledHints, VirtualMachineError> {
         let references = &self.program.shared_program_data.reference_manager;
-        #[cfg(not(feature = "extensive_hints"))]
         let hint_data = self.get_hint_data(references, hint_processor)?;
-        #[cfg(feature = "extensive_hints")]
-        let mut hint_data = self.get_hint_data(references, hint_processor)?;
-        #[cfg(feature = "extensive_hints")]
-        let mut hint_ranges = self
-            .program
-            .shared_program_data
-            .hints_collection
-            .hints_ranges
-            .clone();
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         self.vm.execute_before_first_step(&hint_data)?;
+        Ok(CompiledHints {
+            hint_data,
+            #[cfg(feature = "extensive_hints")]
+            hint_ranges: self
+                .program
+                .shared_program_data
+                .hints_collection
+                .hints_ranges
+                .clone(),
+        })
+    }
+
+    /// Runs the program until `address` is reached or `breakpoint` returns true for the current
+    /// state of the VM, in which case the step at the current pc is not executed.
+    /// Returns true if the execution was stopped by `breakpoint`.
+    pub fn run_until_pc_or_breakpoint<F>(
+        &mut self,
+        address: Relocatable,
+        hint_processor: &mut dyn HintProcessor,
+        hints: &mut CompiledHints,
+        mut breakpoint: F,
+    ) -> Result<bool, VirtualMachineError>
+    where
+        F: FnMut(&VirtualMachine) -> bool,
+    {
         while self.vm.get_pc() != address && !hint_processor.consumed() {
+            if breakpoint(&self.vm) {
+                return Ok(true);
+            }
             self.vm.step(
                 hint_processor,
                 &mut self.exec_scopes,
                 #[cfg(feature = "extensive_hints")]
-                &mut hint_data,
+                &mut hints.hint_data,
                 #[cfg(not(feature = "extensive_hints"))]
                 self.program
                     .shared_program_data
                     .hints_collection
                     .get_hint_range_for_pc(self.vm.get_pc().offset)
+                    // Code loaded in other segments, such as bootloader tasks, has no hints
+                    .filter(|_| {
+                        self.program_base.map(|base| base.segment_index)
+                            == Some(self.vm.get_pc().segment_index)
+                    })
                     .and_then(|range| {
-                        range.and_then(|(start, length)| hint_data.get(start..start + length.get()))
+                        range.and_then(|(start, length)| {
+                            hints.hint_data.get(start..start + length.get())
+                        })
                     })
                     .unwrap_or(&[]),
                 #[cfg(feature = "extensive_hints")]
-                &mut hint_ranges,
+                &mut hints.hint_ranges,
                 &self.program.constants,
             )?;
 
@@ -683,7 +835,7 @@ impl CairoRunner {
             return Err(VirtualMachineError::UnfinishedExecution);
         }
 
-        Ok(())
+        Ok(false)
     }
 
     /// Execute an exact number of steps on the program from the actual position.
@@ -710,6 +862,10 @@ impl CairoRunner {
             .shared_program_data
             .hints_collection
             .get_hint_range_for_pc(self.vm.get_pc().offset)
+            .filter(|_| {
+                self.program_base.map(|base| base.segment_index)
+                    == Some(self.vm.get_pc().segment_index)
+            })
             .and_then(|range| {
                 range.and_then(|(start, length)| hint_data.get(start..start + length.get()))
             })
@@ -829,7 +985,14 @@ impl CairoRunner {
             used_units_by_builtins += used_units * multiplier;
         }
 
-        let diluted_units = diluted_pool_instance.units_per_step as usize * self.vm.current_step;
+        let diluted_units = if diluted_pool_instance.fractional_units_per_step {
+            safe_div_usize(
+                self.vm.current_step,
+                diluted_pool_instance.units_per_step as usize,
+            )?
+        } else {
+            diluted_pool_instance.units_per_step as usize * self.vm.current_step
+        };
         let unused_diluted_units = diluted_units.saturating_sub(used_units_by_builtins);
 
         let diluted_usage_upper_bound = 1usize << diluted_pool_instance.n_bits;
@@ -891,12 +1054,7 @@ impl CairoRunner {
             return Err(TraceError::AlreadyRelocated);
         }
 
-        let trace = self
-            .vm
-            .trace
-            .as_ref()
-            .ok_or(TraceError::TraceNotEnabled)?
-            .iter();
+        let trace = self.vm.get_trace()?.iter();
         let mut relocated_trace = Vec::<RelocatedTraceEntry>::with_capacity(trace.len());
         let segment_1_base = relocation_table
             .get(1)
@@ -922,22 +1080,30 @@ impl CairoRunner {
         }
         //Relocated addresses start at 1
         self.relocated_memory.push(None);
-        for (index, segment) in self.vm.segments.memory.data.iter().enumerate() {
-            for (seg_offset, cell) in segment.iter().enumerate() {
-                match cell.get_value() {
-                    Some(cell) => {
-                        let relocated_addr = relocate_address(
-                            Relocatable::from((index as isize, seg_offset)),
-                            relocation_table,
-                        )?;
-                        let value = relocate_value(cell, relocation_table)?;
-                        if self.relocated_memory.len() <= relocated_addr {
-                            self.relocated_memory.resize(relocated_addr + 1, None);
-                        }
-                        self.relocated_memory[relocated_addr] = Some(value);
-                    }
-                    None => self.relocated_memory.push(None),
+        let memory = &self.vm.segments.memory.data;
+        for index in 0..memory.num_segments() {
+            for (seg_offset, cell) in memory.written_cells(index) {
+                let Some(cell) = cell.get_value() else {
+                    continue;
+                };
+                let relocated_addr = relocate_address(
+                    Relocatable::from((index as isize, seg_offset)),
+                    relocation_table,
+                )?;
+                let value = relocate_value(cell, relocation_table)?;
+                if self.relocated_memory.len() <= relocated_addr {
+                    self.relocated_memory.resize(relocated_addr + 1, None);
                 }
+                self.relocated_memory[relocated_addr] = Some(value);
+            }
+            // Unset cells at the end of the segment are kept as gaps
+            let segment_len = memory.segment_len(index).unwrap_or_default();
+            if let Some(segment_end) = relocation_table
+                .get(index)
+                .map(|base| base + segment_len)
+                .filter(|end| self.relocated_memory.len() < *end)
+            {
+                self.relocated_memory.resize(segment_end, None);
             }
         }
         Ok(())
@@ -961,7 +1127,9 @@ impl CairoRunner {
                 return Err(TraceError::MemoryError(memory_error));
             }
         }
-        if self.vm.trace.is_some() {
+        if self.vm.trace_sink.is_some() {
+            self.vm.flush_trace()?;
+        } else if self.vm.trace.is_some() {
             self.relocate_trace(&relocation_table)?;
         }
         self.vm.relocation_table = Some(relocation_table);
@@ -1009,12 +1177,7 @@ impl CairoRunner {
     }
 
     pub fn get_execution_resources(&self) -> Result<ExecutionResources, RunnerError> {
-        let n_steps = self
-            .vm
-            .trace
-            .as_ref()
-            .map(|x| x.len())
-            .unwrap_or(self.vm.current_step);
+        let n_steps = self.vm.trace_len().unwrap_or(self.vm.current_step);
         let n_memory_holes = self.get_memory_holes()?;
 
         let mut builtin_instance_counter = HashMap::new();
@@ -1157,7 +1320,7 @@ impl CairoRunner {
 
         // Out of the memory units available per step, a fraction is used for public memory, and
         // four are used for the instruction.
-        let total_memory_units = MEMORY_UNITS_PER_STEP * vm_current_step_u32;
+        let total_memory_units = instance.memory_units_per_step * vm_current_step_u32;
         let (public_memory_units, rem) =
             div_rem(total_memory_units, instance.public_memory_fraction);
         if rem != 0 {
@@ -1191,7 +1354,7 @@ impl CairoRunner {
         &mut self,
         program_builtins: &[BuiltinName],
     ) -> Result<(), RunnerError> {
-        self.program.builtins = program_builtins.to_vec();
+        Arc::make_mut(&mut self.program).builtins = program_builtins.to_vec();
         self.initialize_program_builtins()?;
         self.initialize_segments(self.program_base);
         Ok(())
@@ -1398,6 +1561,7 @@ impl CairoRunner {
         PublicInput::new(
             &self.relocated_memory,
             self.layout.name.to_str(),
+            self.layout.dynamic_layout_params.clone(),
             &self.vm.get_public_memory_addresses()?,
             self.get_memory_segment_addresses()?,
             self.relocated_trace
@@ -1408,6 +1572,61 @@ impl CairoRunner {
         )
     }
 
+    /// Captures the state of a paused run, which can be restored with [CairoRunner::restore_snapshot]
+    /// to resume the execution later.
+    /// Fails if the execution scopes contain variables of types that aren't supported by the snapshot.
+    pub fn take_snapshot(&self) -> Result<RunnerSnapshot, RunnerError> {
+        Ok(RunnerSnapshot {
+            program_base: self.program_base,
+            execution_base: self.execution_base,
+            execution_public_memory: self.execution_public_memory.clone(),
+            run_ended: self.run_ended,
+            segments_finalized: self.segments_finalized,
+            vm: VmSnapshot::new(&self.vm),
+            exec_scopes: ExecScopesSnapshot::new(&self.exec_scopes)?,
+        })
+    }
+
+    /// Restores the state of a paused run from a snapshot taken with [CairoRunner::take_snapshot].
+    /// The runner must have been created and initialized in the same way as the one the snapshot was
+    /// taken from, after which the execution can be resumed with [CairoRunner::run_until_pc].
+    pub fn restore_snapshot(&mut self, snapshot: &RunnerSnapshot) -> Result<(), RunnerError> {
+        if self.program_base != snapshot.program_base
+            || self.execution_base != snapshot.execution_base
+        {
+            return Err(RunnerError::SnapshotMismatch(Box::new(
+                "program and execution bases don't match".to_string(),
+            )));
+        }
+        let exec_scopes = snapshot.exec_scopes.restore()?;
+        // Steps journaled before the restore can't be undone afterwards
+        let journal_enabled = self.vm.journal.is_some();
+        self.vm.disable_journal();
+        snapshot.vm.restore(&mut self.vm)?;
+        if journal_enabled {
+            self.vm.enable_journal();
+        }
+        self.exec_scopes = exec_scopes;
+        self.execution_public_memory
+            .clone_from(&snapshot.execution_public_memory);
+        self.run_ended = snapshot.run_ended;
+        self.segments_finalized = snapshot.segments_finalized;
+        Ok(())
+    }
+
+    /// Computes the steps, memory holes and builtin instances used by each call stack of the run.
+    /// Requires the run to have been performed with the trace enabled.
+    pub fn get_profile(&self) -> Result<Profile, TraceError> {
+        Profile::new(self)
+    }
+
+    /// Computes the line, function and branch coverage of the run, mapped to the Cairo source.
+    /// Requires the run to have been performed with the trace enabled.
+    #[cfg(feature = "std")]
+    pub fn get_coverage_report(&self) -> Result<CoverageReport, TraceError> {
+        CoverageReport::new(self)
+    }
+
     pub fn get_air_private_input(&self) -> AirPrivateInput {
         let mut private_inputs = HashMap::new();
         for builtin in self.vm.builtin_runners.iter() {
@@ -1554,7 +1773,9 @@ mod tests {
     use crate::air_private_input::{PrivateInput, PrivateInputSignature, SignatureInput};
     use crate::cairo_run::{cairo_run, CairoRunConfig};
     use crate::stdlib::collections::{HashMap, HashSet};
+    use crate::types::builtin_name::CustomBuiltinName;
     use crate::vm::vm_memory::memory::MemoryCell;
+    use crate::vm::vm_memory::memory_backend::MemoryStorage;
 
     use crate::felt_hex;
     use crate::{
@@ -1633,6 +1854,129 @@ mod tests {
         assert!(cairo_runner.initialize_builtins(true).is_ok())
     }
 
+    static SQUARE: CustomBuiltinName = CustomBuiltinName::new("square", "square_builtin");
+
+    #[derive(Debug, Clone)]
+    struct SquareBuiltin;
+
+    impl CustomBuiltin for SquareBuiltin {
+        fn name(&self) -> &'static CustomBuiltinName {
+            &SQUARE
+        }
+
+        fn ratio(&self) -> Option<u32> {
+            Some(8)
+        }
+
+        fn cells_per_instance(&self) -> u32 {
+            2
+        }
+
+        fn n_input_cells(&self) -> u32 {
+            1
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn initialize_builtins_with_custom_builtin() {
+        let program = program![BuiltinName::output, BuiltinName::custom(&SQUARE)];
+        let mut cairo_runner = cairo_runner!(program, LayoutName::small);
+        cairo_runner.add_custom_builtin(Box::new(SquareBuiltin));
+        cairo_runner.initialize_builtins(false).unwrap();
+        let names: Vec<_> = cairo_runner
+            .vm
+            .builtin_runners
+            .iter()
+            .map(BuiltinRunner::name)
+            .collect();
+        assert_eq!(names, [BuiltinName::output, BuiltinName::custom(&SQUARE)]);
+
+        cairo_runner.initialize_segments(None);
+        assert_eq!(
+            cairo_runner.vm.builtin_runners[1].initial_stack(),
+            vec![mayberelocatable!(3, 0)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn add_custom_builtin_registers_its_name() {
+        static CUBE: CustomBuiltinName = CustomBuiltinName::new("cube", "cube_builtin");
+
+        #[derive(Debug, Clone)]
+        struct CubeBuiltin;
+
+        impl CustomBuiltin for CubeBuiltin {
+            fn name(&self) -> &'static CustomBuiltinName {
+                &CUBE
+            }
+
+            fn ratio(&self) -> Option<u32> {
+                Some(8)
+            }
+
+            fn cells_per_instance(&self) -> u32 {
+                2
+            }
+
+            fn n_input_cells(&self) -> u32 {
+                1
+            }
+        }
+
+        assert_eq!(BuiltinName::from_str("cube"), None);
+        let mut cairo_runner = cairo_runner!(program!(), LayoutName::small);
+        cairo_runner.add_custom_builtin(Box::new(CubeBuiltin));
+        assert_eq!(
+            BuiltinName::from_str("cube"),
+            Some(BuiltinName::custom(&CUBE))
+        );
+        assert_eq!(
+            BuiltinName::from_str_with_suffix("cube_builtin"),
+            Some(BuiltinName::custom(&CUBE))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn initialize_builtins_with_custom_builtin_proof_mode() {
+        let program = program!();
+        let mut cairo_runner = cairo_runner!(program, LayoutName::plain, true);
+        cairo_runner.add_custom_builtin(Box::new(SquareBuiltin));
+        cairo_runner.initialize_builtins(false).unwrap();
+        let custom_builtin = &cairo_runner.vm.builtin_runners[0];
+        assert_eq!(custom_builtin.name(), BuiltinName::custom(&SQUARE));
+        assert!(custom_builtin.initial_stack().is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn initialize_builtins_with_custom_builtin_disordered() {
+        let program = program![BuiltinName::custom(&SQUARE), BuiltinName::output];
+        let mut cairo_runner = cairo_runner!(program, LayoutName::small);
+        cairo_runner.add_custom_builtin(Box::new(SquareBuiltin));
+        assert_matches!(
+            cairo_runner.initialize_builtins(false),
+            Err(RunnerError::DisorderedBuiltins)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn initialize_builtins_with_unregistered_custom_builtin() {
+        let program = program![BuiltinName::custom(&SQUARE)];
+        let mut cairo_runner = cairo_runner!(program, LayoutName::small);
+        assert_matches!(
+            cairo_runner.initialize_builtins(false),
+            Err(RunnerError::DisorderedBuiltins)
+        );
+        assert_matches!(
+            cairo_runner.initialize_program_builtins(),
+            Err(RunnerError::NoBuiltinForInstance(_))
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn initialize_segments_with_base() {
@@ -3252,7 +3596,7 @@ mod tests {
 
         // Swap the first and second builtins (first should be `output`).
         cairo_runner.vm.builtin_runners.swap(0, 1);
-        cairo_runner.program.builtins.swap(0, 1);
+        Arc::make_mut(&mut cairo_runner.program).builtins.swap(0, 1);
 
         cairo_runner.initialize_segments(None);
 
@@ -4047,14 +4391,14 @@ mod tests {
 
         let mut cairo_runner = cairo_runner!(program);
 
-        cairo_runner.vm.segments.memory.data = vec![
+        cairo_runner.vm.segments.memory.data = MemoryStorage::from(vec![
             vec![
                 MemoryCell::new(Felt252::from(0x8000_8023_8012u64).into()),
                 MemoryCell::new(Felt252::from(0xBFFF_8000_0620u64).into()),
                 MemoryCell::new(Felt252::from(0x8FFF_8000_0750u64).into()),
             ],
             vec![MemoryCell::new((0isize, 0usize).into()); 128 * 1024],
-        ];
+        ]);
 
         cairo_runner.run_for_steps(1, &mut hint_processor).unwrap();
 
@@ -4073,9 +4417,9 @@ mod tests {
 
         let mut cairo_runner = cairo_runner!(program);
 
-        cairo_runner.vm.segments.memory.data = vec![vec![MemoryCell::new(mayberelocatable!(
-            0x80FF_8000_0530u64
-        ))]];
+        cairo_runner.vm.segments.memory.data = MemoryStorage::from(vec![vec![MemoryCell::new(
+            mayberelocatable!(0x80FF_8000_0530u64),
+        )]]);
         cairo_runner.vm.builtin_runners =
             vec![RangeCheckBuiltinRunner::<RC_N_PARTS_STANDARD>::new(Some(12), true).into()];
 
@@ -4105,9 +4449,9 @@ mod tests {
         let mut cairo_runner = cairo_runner!(program, LayoutName::plain);
         cairo_runner.vm.builtin_runners = vec![];
         cairo_runner.vm.current_step = 10000;
-        cairo_runner.vm.segments.memory.data = vec![vec![MemoryCell::new(mayberelocatable!(
-            0x80FF_8000_0530u64
-        ))]];
+        cairo_runner.vm.segments.memory.data = MemoryStorage::from(vec![vec![MemoryCell::new(
+            mayberelocatable!(0x80FF_8000_0530u64),
+        )]]);
         cairo_runner.vm.trace = Some(vec![TraceEntry {
             pc: (0, 0).into(),
             ap: 0,
@@ -4127,9 +4471,9 @@ mod tests {
         let mut cairo_runner = cairo_runner!(program);
         cairo_runner.vm.builtin_runners =
             vec![RangeCheckBuiltinRunner::<RC_N_PARTS_STANDARD>::new(Some(8), true).into()];
-        cairo_runner.vm.segments.memory.data = vec![vec![MemoryCell::new(mayberelocatable!(
-            0x80FF_8000_0530u64
-        ))]];
+        cairo_runner.vm.segments.memory.data = MemoryStorage::from(vec![vec![MemoryCell::new(
+            mayberelocatable!(0x80FF_8000_0530u64),
+        )]]);
         cairo_runner.vm.trace = Some(vec![TraceEntry {
             pc: (0, 0).into(),
             ap: 0,
@@ -4192,9 +4536,9 @@ mod tests {
         let mut cairo_runner = cairo_runner!(program);
         cairo_runner.vm.builtin_runners =
             vec![RangeCheckBuiltinRunner::<RC_N_PARTS_STANDARD>::new(Some(8), true).into()];
-        cairo_runner.vm.segments.memory.data = vec![vec![MemoryCell::new(mayberelocatable!(
-            0x80FF_8000_0530u64
-        ))]];
+        cairo_runner.vm.segments.memory.data = MemoryStorage::from(vec![vec![MemoryCell::new(
+            mayberelocatable!(0x80FF_8000_0530u64),
+        )]]);
         cairo_runner.vm.trace = Some(vec![TraceEntry {
             pc: (0, 0).into(),
             ap: 0,
@@ -4618,11 +4962,11 @@ mod tests {
         cairo_runner.segments_finalized = false;
         let output_builtin = OutputBuiltinRunner::new(true);
         cairo_runner.vm.builtin_runners.push(output_builtin.into());
-        cairo_runner.vm.segments.memory.data = vec![
+        cairo_runner.vm.segments.memory.data = MemoryStorage::from(vec![
             vec![],
             vec![MemoryCell::new(MaybeRelocatable::from((0, 0)))],
             vec![],
-        ];
+        ]);
         cairo_runner.vm.set_ap(1);
         cairo_runner.vm.segments.segment_used_sizes = Some(vec![0, 1, 0]);
         //Check values written by first call to segments.finalize()
@@ -4648,11 +4992,11 @@ mod tests {
         cairo_runner.segments_finalized = false;
         let output_builtin = OutputBuiltinRunner::new(true);
         cairo_runner.vm.builtin_runners.push(output_builtin.into());
-        cairo_runner.vm.segments.memory.data = vec![
+        cairo_runner.vm.segments.memory.data = MemoryStorage::from(vec![
             vec![MemoryCell::new(MaybeRelocatable::from((0, 0)))],
             vec![MemoryCell::new(MaybeRelocatable::from((0, 1)))],
             vec![],
-        ];
+        ]);
         cairo_runner.vm.set_ap(1);
         cairo_runner.vm.segments.segment_used_sizes = Some(vec![1, 1, 0]);
         //Check values written by first call to segments.finalize()
@@ -4681,14 +5025,14 @@ mod tests {
         cairo_runner.vm.builtin_runners.push(output_builtin.into());
         cairo_runner.vm.builtin_runners.push(bitwise_builtin.into());
         cairo_runner.initialize_segments(None);
-        cairo_runner.vm.segments.memory.data = vec![
+        cairo_runner.vm.segments.memory.data = MemoryStorage::from(vec![
             vec![MemoryCell::new(MaybeRelocatable::from((0, 0)))],
             vec![
                 MemoryCell::new(MaybeRelocatable::from((2, 0))),
                 MemoryCell::new(MaybeRelocatable::from((3, 5))),
             ],
             vec![],
-        ];
+        ]);
         cairo_runner.vm.set_ap(2);
         // We use 5 as bitwise builtin's segment size as a bitwise instance is 5 cells
         cairo_runner.vm.segments.segment_used_sizes = Some(vec![0, 2, 0, 5]);
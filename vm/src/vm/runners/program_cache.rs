@@ -0,0 +1,181 @@
+//! Programs parsed and decoded once, to be shared by many runs
+//!
+//! Creating a [CairoRunner] with [CairoRunner::new] clones the [Program], and each run decodes
+//! the instructions of the program segment again as they are executed.
+//! A [ProgramCache] holds the program behind an [Arc] together with its instructions decoded
+//! ahead of time, so that the runners created with [CairoRunner::new_with_cache] share both of
+//! them, including runners running in different threads (see `cairo_run::run_batch`).
+
+use crate::stdlib::{prelude::*, sync::Arc};
+
+use num_traits::ToPrimitive;
+
+use crate::{
+    types::{
+        instruction::Instruction, layout::CairoLayoutParams, layout_name::LayoutName,
+        program::Program, relocatable::MaybeRelocatable,
+    },
+    vm::{
+        decoding::decoder::decode_instruction,
+        errors::runner_errors::RunnerError,
+        runners::cairo_runner::{CairoRunner, RunnerMode},
+    },
+};
+
+/// A parsed program with its instructions decoded ahead of time
+#[derive(Clone, Debug)]
+pub struct ProgramCache {
+    program: Arc<Program>,
+    instructions: Arc<Vec<Option<Instruction>>>,
+}
+
+impl ProgramCache {
+    /// Decodes every word of the program data that is a valid instruction.
+    /// Words that aren't, such as immediate values, are left out of the cache.
+    pub fn new(program: impl Into<Arc<Program>>) -> ProgramCache {
+        let program = program.into();
+        let instructions = program
+            .iter_data()
+            .map(|word| match word {
+                MaybeRelocatable::Int(felt) => {
+                    felt.to_u64().and_then(|word| decode_instruction(word).ok())
+                }
+                MaybeRelocatable::RelocatableValue(_) => None,
+            })
+            .collect();
+        ProgramCache {
+            program,
+            instructions: Arc::new(instructions),
+        }
+    }
+
+    pub fn program(&self) -> &Arc<Program> {
+        &self.program
+    }
+
+    /// Amount of program words decoded as instructions
+    pub fn decoded_instructions(&self) -> usize {
+        self.instructions.iter().flatten().count()
+    }
+
+    pub(crate) fn instructions(&self) -> &Arc<Vec<Option<Instruction>>> {
+        &self.instructions
+    }
+}
+
+impl From<Program> for ProgramCache {
+    fn from(program: Program) -> Self {
+        ProgramCache::new(program)
+    }
+}
+
+impl CairoRunner {
+    /// Creates a runner sharing the program and the decoded instructions of `cache`.
+    /// The decoded instructions are used when the program is loaded at the start of segment 0,
+    /// which is the case for runners initialized with [CairoRunner::initialize].
+    pub fn new_with_cache(
+        cache: &ProgramCache,
+        layout: LayoutName,
+        dynamic_layout_params: Option<CairoLayoutParams>,
+        mode: RunnerMode,
+        trace_enabled: bool,
+    ) -> Result<CairoRunner, RunnerError> {
+        let mut runner = CairoRunner::new_from_shared(
+            cache.program().clone(),
+            layout,
+            dynamic_layout_params,
+            mode,
+            trace_enabled,
+        )?;
+        runner.instruction_cache = Some(cache.instructions().clone());
+        Ok(runner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::relocatable::Relocatable, utils::test_utils::program_b, Felt252,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn program_cache() -> ProgramCache {
+        program_b().into()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decodes_program_instructions() {
+        let cache = program_cache();
+        assert_eq!(cache.instructions().len(), cache.program().data_len());
+        assert!(cache.decoded_instructions() > 0);
+        assert!(cache.decoded_instructions() <= cache.program().data_len());
+        for (word, instruction) in cache.program().iter_data().zip(cache.instructions().iter()) {
+            if let Some(instruction) = instruction {
+                let word = word.get_int().unwrap().to_u64().unwrap();
+                assert_eq!(decode_instruction(word).as_ref().ok(), Some(instruction));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn runners_share_program_and_instructions() {
+        let cache = program_cache();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let mut runner = CairoRunner::new_with_cache(
+                &cache,
+                LayoutName::small,
+                None,
+                RunnerMode::ExecutionMode,
+                true,
+            )
+            .unwrap();
+            let end = runner.initialize(false).unwrap();
+            runner.run_until_pc(end, &mut hint_processor).unwrap();
+            assert!(Arc::ptr_eq(&runner.program, cache.program()));
+            // Every executed instruction was already decoded, so the cache wasn't cloned
+            assert!(Arc::ptr_eq(
+                &runner.vm.instruction_cache,
+                cache.instructions()
+            ));
+            runs.push(runner.take_snapshot().unwrap());
+        }
+        assert_eq!(runs[0], runs[1]);
+
+        let mut runner = CairoRunner::new(cache.program(), LayoutName::small, false, true).unwrap();
+        let end = runner.initialize(false).unwrap();
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        assert_eq!(runner.take_snapshot().unwrap(), runs[0]);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cache_not_used_outside_segment_zero() {
+        let cache = program_cache();
+        let mut runner = CairoRunner::new_with_cache(
+            &cache,
+            LayoutName::small,
+            None,
+            RunnerMode::ExecutionMode,
+            false,
+        )
+        .unwrap();
+        runner.initialize_builtins(false).unwrap();
+        runner.vm.add_memory_segment();
+        runner.initialize_segments(Some(Relocatable::from((0, 5))));
+        runner
+            .initialize_function_entrypoint(0, Vec::new(), Felt252::ZERO.into())
+            .unwrap();
+        assert!(!Arc::ptr_eq(
+            &runner.vm.instruction_cache,
+            cache.instructions()
+        ));
+    }
+}
@@ -0,0 +1,850 @@
+//! Snapshots of paused runs
+//!
+//! A [RunnerSnapshot] holds the state of a [CairoRunner](super::cairo_runner::CairoRunner) that
+//! changes during its execution, so that a paused run can be serialized, restored later (possibly
+//! on another machine) and resumed with `run_until_pc`.
+//! Snapshots are restored into a runner created and initialized in the same way as the
+//! original one (same program, layout, mode and entrypoint), which provides the parts of the
+//! state that don't change during the execution, such as the memory validation rules.
+//!
+//! Execution scope variables are only supported for the types used by the builtin hint
+//! processors, taking a snapshot of a run that stored any other type in its scopes will fail.
+
+use crate::stdlib::{any::Any, collections::HashMap, prelude::*};
+
+use num_bigint::{BigInt, BigUint};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "cairo-1-hints")]
+use crate::hint_processor::cairo_1_hint_processor::dict_manager::{
+    DictManagerExecScope, DictSquashExecScope, DictTrackerExecScope,
+};
+use crate::{
+    hint_processor::builtin_hint_processor::dict_manager::{DictManager, DictTracker, Dictionary},
+    types::{
+        builtin_name::BuiltinName,
+        exec_scope::ExecutionScopes,
+        relocatable::{MaybeRelocatable, Relocatable},
+        shared::{AnyBox, Shared, SharedCell},
+    },
+    vm::{
+        errors::{memory_errors::MemoryError, runner_errors::RunnerError},
+        runners::{builtin_runner::BuiltinRunner, cairo_pie::BuiltinAdditionalData},
+        trace::trace_entry::TraceEntry,
+        vm_core::VirtualMachine,
+        vm_memory::{memory_backend::MemoryBackend, memory_segments::MemorySegmentManager},
+    },
+    Felt252,
+};
+
+/// State of a paused run
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RunnerSnapshot {
+    pub program_base: Option<Relocatable>,
+    pub execution_base: Option<Relocatable>,
+    pub execution_public_memory: Option<Vec<usize>>,
+    pub run_ended: bool,
+    pub segments_finalized: bool,
+    pub vm: VmSnapshot,
+    pub exec_scopes: ExecScopesSnapshot,
+}
+
+/// State of the [VirtualMachine], including its memory and builtins
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VmSnapshot {
+    pub pc: Relocatable,
+    pub ap: usize,
+    pub fp: usize,
+    pub current_step: usize,
+    pub rc_limits: Option<(isize, isize)>,
+    pub run_finished: bool,
+    pub trace: Option<Vec<TraceEntry>>,
+    pub segments: SegmentsSnapshot,
+    pub builtins: Vec<BuiltinSnapshot>,
+}
+
+/// Contents of the memory and segment information.
+/// Cells are stored along with whether they were accessed, holes are stored as `None`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SegmentsSnapshot {
+    pub data: Vec<Vec<Option<(MaybeRelocatable, bool)>>>,
+    pub temp_data: Vec<Vec<Option<(MaybeRelocatable, bool)>>>,
+    pub relocation_rules: HashMap<usize, Relocatable>,
+    pub segment_sizes: HashMap<usize, usize>,
+    pub segment_used_sizes: Option<Vec<usize>>,
+    pub public_memory_offsets: HashMap<usize, Vec<(usize, usize)>>,
+    pub zero_segment_index: usize,
+    pub zero_segment_size: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BuiltinSnapshot {
+    pub name: BuiltinName,
+    pub base: usize,
+    pub stop_ptr: Option<usize>,
+    pub additional_data: BuiltinAdditionalData,
+}
+
+/// Contents of the execution scopes.
+/// Dict managers shared between scopes are stored once and referenced by their index.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ExecScopesSnapshot {
+    pub scopes: Vec<Vec<(String, ScopeValue)>>,
+    pub dict_managers: Vec<DictManagerSnapshot>,
+}
+
+/// Execution scope variable of one of the supported types
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ScopeValue {
+    Felt(Felt252),
+    BigInt(BigInt),
+    BigUint(BigUint),
+    U64(u64),
+    Usize(usize),
+    Bool(bool),
+    Relocatable(Relocatable),
+    FeltVec(Vec<Felt252>),
+    UsizeVec(Vec<usize>),
+    U64Vec(Vec<u64>),
+    BigIntVec(Vec<BigInt>),
+    MaybeRelocatableVec(Vec<MaybeRelocatable>),
+    /// `HashMap<Felt252, Vec<Felt252>>` sorted by key, such as the `access_indices` of `squash_dict`
+    FeltVecMap(Vec<(Felt252, Vec<Felt252>)>),
+    /// `HashMap<Felt252, Vec<usize>>` sorted by key
+    UsizeVecMap(Vec<(Felt252, Vec<usize>)>),
+    /// `HashMap<Felt252, Vec<u64>>` sorted by key, such as the `positions_dict` of `usort`
+    U64VecMap(Vec<(Felt252, Vec<u64>)>),
+    /// Index of a shared `Shared<SharedCell<DictManager>>`
+    DictManager(usize),
+    #[cfg(feature = "cairo-1-hints")]
+    Cairo1DictManager(Cairo1DictManagerSnapshot),
+    #[cfg(feature = "cairo-1-hints")]
+    Cairo1DictSquash(Cairo1DictSquashSnapshot),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DictManagerSnapshot {
+    pub trackers: Vec<(isize, DictTrackerSnapshot)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DictTrackerSnapshot {
+    pub data: Vec<(MaybeRelocatable, MaybeRelocatable)>,
+    pub default_value: Option<MaybeRelocatable>,
+    pub current_ptr: Relocatable,
+}
+
+#[cfg(feature = "cairo-1-hints")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cairo1DictManagerSnapshot {
+    pub segment_to_tracker: Vec<(isize, usize)>,
+    pub trackers: Vec<Cairo1DictTrackerSnapshot>,
+    pub use_temporary_segments: bool,
+}
+
+#[cfg(feature = "cairo-1-hints")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cairo1DictTrackerSnapshot {
+    pub data: Vec<(Felt252, MaybeRelocatable)>,
+    pub start: Relocatable,
+    pub end: Option<Relocatable>,
+}
+
+#[cfg(feature = "cairo-1-hints")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cairo1DictSquashSnapshot {
+    pub access_indices: Vec<(Felt252, Vec<Felt252>)>,
+    pub keys: Vec<Felt252>,
+}
+
+impl RunnerSnapshot {
+    /// Serializes the snapshot into a JSON byte array
+    pub fn to_bytes(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(self)
+    }
+
+    /// Deserializes a snapshot from a JSON byte array
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(bytes)
+    }
+}
+
+impl VmSnapshot {
+    pub(crate) fn new(vm: &VirtualMachine) -> Self {
+        VmSnapshot {
+            pc: vm.run_context.pc,
+            ap: vm.run_context.ap,
+            fp: vm.run_context.fp,
+            current_step: vm.current_step,
+            rc_limits: vm.rc_limits,
+            run_finished: vm.is_run_finished(),
+            trace: vm.trace.clone(),
+            segments: SegmentsSnapshot::new(&vm.segments),
+            builtins: vm
+                .builtin_runners
+                .iter()
+                .map(|builtin| BuiltinSnapshot {
+                    name: builtin.name(),
+                    base: builtin.base(),
+                    stop_ptr: builtin.stop_ptr(),
+                    additional_data: builtin.get_additional_data(),
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn restore(&self, vm: &mut VirtualMachine) -> Result<(), RunnerError> {
+        let builtins_match =
+            vm.builtin_runners.len() == self.builtins.len()
+                && vm.builtin_runners.iter().zip(self.builtins.iter()).all(
+                    |(builtin, snapshot)| {
+                        builtin.name() == snapshot.name && builtin.base() == snapshot.base
+                    },
+                );
+        if !builtins_match {
+            return Err(RunnerError::SnapshotMismatch(Box::new(
+                "builtin runners don't match".to_string(),
+            )));
+        }
+        // The data recorded after the snapshot was taken is dropped
+        for (builtin, snapshot) in vm.builtin_runners.iter_mut().zip(self.builtins.iter()) {
+            match snapshot.stop_ptr {
+                Some(stop_ptr) => builtin.set_stop_ptr(stop_ptr),
+                None => builtin.clear_stop_ptr(),
+            }
+            builtin.clear_additional_data();
+            restore_additional_data(builtin, &snapshot.additional_data)?;
+        }
+        self.segments.restore(&mut vm.segments)?;
+        vm.run_context.pc = self.pc;
+        vm.run_context.ap = self.ap;
+        vm.run_context.fp = self.fp;
+        vm.current_step = self.current_step;
+        vm.rc_limits = self.rc_limits;
+        vm.set_run_finished(self.run_finished);
+        vm.trace.clone_from(&self.trace);
+        Ok(())
+    }
+}
+
+fn restore_additional_data(
+    builtin: &mut BuiltinRunner,
+    additional_data: &BuiltinAdditionalData,
+) -> Result<(), RunnerError> {
+    match additional_data {
+        // Empty data may be deserialized as any variant
+        BuiltinAdditionalData::None | BuiltinAdditionalData::Empty(_) => Ok(()),
+        BuiltinAdditionalData::Hash(data) if data.is_empty() => Ok(()),
+        BuiltinAdditionalData::Signature(data) if data.is_empty() => Ok(()),
+        data => builtin.extend_additional_data(data),
+    }
+}
+
+impl SegmentsSnapshot {
+    fn new(segments: &MemorySegmentManager) -> Self {
+        SegmentsSnapshot {
+            data: cells_snapshot(&segments.memory.data),
+            temp_data: cells_snapshot(&segments.memory.temp_data),
+            relocation_rules: segments.memory.relocation_rules.clone(),
+            segment_sizes: segments.segment_sizes.clone(),
+            segment_used_sizes: segments.segment_used_sizes.clone(),
+            public_memory_offsets: segments.public_memory_offsets.clone(),
+            zero_segment_index: segments.zero_segment().0,
+            zero_segment_size: segments.zero_segment().1,
+        }
+    }
+
+    // Replaces the current memory with the snapshot's cells, which are validated by the memory's
+    // validation rules as they are inserted
+    fn restore(&self, segments: &mut MemorySegmentManager) -> Result<(), MemoryError> {
+        segments.memory.clear();
+        while segments.num_segments() < self.data.len() {
+            segments.add();
+        }
+        while segments.num_temp_segments() < self.temp_data.len() {
+            segments.add_temporary_segment();
+        }
+        let temp_cells = self.temp_data.iter().enumerate().map(|(i, segment)| {
+            // Temporary segment indexes begin at -1
+            (-(i as isize) - 1, segment)
+        });
+        let cells = self
+            .data
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| (i as isize, segment))
+            .chain(temp_cells);
+        for (segment_index, segment) in cells {
+            for (offset, cell) in segment.iter().enumerate() {
+                let Some((value, accessed)) = cell else {
+                    continue;
+                };
+                let addr = Relocatable::from((segment_index, offset));
+                segments.memory.insert(addr, value)?;
+                if *accessed {
+                    segments.memory.mark_as_accessed(addr);
+                }
+            }
+        }
+        segments
+            .memory
+            .relocation_rules
+            .clone_from(&self.relocation_rules);
+        segments.segment_sizes.clone_from(&self.segment_sizes);
+        segments
+            .segment_used_sizes
+            .clone_from(&self.segment_used_sizes);
+        segments
+            .public_memory_offsets
+            .clone_from(&self.public_memory_offsets);
+        segments.set_zero_segment(self.zero_segment_index, self.zero_segment_size);
+        Ok(())
+    }
+}
+
+fn cells_snapshot(data: &dyn MemoryBackend) -> Vec<Vec<Option<(MaybeRelocatable, bool)>>> {
+    (0..data.num_segments())
+        .map(|segment| {
+            data.segment_cells(segment)
+                .unwrap_or_default()
+                .iter()
+                .map(|cell| Some((cell.get_value()?, cell.is_accessed())))
+                .collect()
+        })
+        .collect()
+}
+
+impl ExecScopesSnapshot {
+    pub(crate) fn new(exec_scopes: &ExecutionScopes) -> Result<Self, RunnerError> {
+        let mut dict_managers = Vec::<Shared<SharedCell<DictManager>>>::new();
+        let mut scopes = Vec::new();
+        for scope in &exec_scopes.data {
+            let mut variables = Vec::new();
+            for (name, value) in scope {
+                let value = scope_value(value.as_ref(), &mut dict_managers).ok_or_else(|| {
+                    RunnerError::SnapshotUnsupportedScopeVariable(Box::new(name.clone()))
+                })?;
+                variables.push((name.clone(), value));
+            }
+            scopes.push(variables);
+        }
+        let dict_managers = dict_managers
+            .iter()
+            .map(|dict_manager| DictManagerSnapshot::new(&dict_manager.borrow()))
+            .collect();
+        Ok(ExecScopesSnapshot {
+            scopes,
+            dict_managers,
+        })
+    }
+
+    pub(crate) fn restore(&self) -> Result<ExecutionScopes, RunnerError> {
+        let dict_managers: Vec<Shared<SharedCell<DictManager>>> = self
+            .dict_managers
+            .iter()
+            .map(|dict_manager| Shared::new(SharedCell::new(dict_manager.restore())))
+            .collect();
+        let mut exec_scopes = ExecutionScopes { data: Vec::new() };
+        for variables in &self.scopes {
+            let mut scope = HashMap::<String, AnyBox>::new();
+            for (name, value) in variables {
+                let value: AnyBox = match value {
+                    ScopeValue::Felt(value) => Box::new(*value),
+                    ScopeValue::BigInt(value) => Box::new(value.clone()),
+                    ScopeValue::BigUint(value) => Box::new(value.clone()),
+                    ScopeValue::U64(value) => Box::new(*value),
+                    ScopeValue::Usize(value) => Box::new(*value),
+                    ScopeValue::Bool(value) => Box::new(*value),
+                    ScopeValue::Relocatable(value) => Box::new(*value),
+                    ScopeValue::FeltVec(value) => Box::new(value.clone()),
+                    ScopeValue::UsizeVec(value) => Box::new(value.clone()),
+                    ScopeValue::U64Vec(value) => Box::new(value.clone()),
+                    ScopeValue::BigIntVec(value) => Box::new(value.clone()),
+                    ScopeValue::MaybeRelocatableVec(value) => Box::new(value.clone()),
+                    ScopeValue::FeltVecMap(value) => {
+                        Box::new(value.iter().cloned().collect::<HashMap<_, _>>())
+                    }
+                    ScopeValue::UsizeVecMap(value) => {
+                        Box::new(value.iter().cloned().collect::<HashMap<_, _>>())
+                    }
+                    ScopeValue::U64VecMap(value) => {
+                        Box::new(value.iter().cloned().collect::<HashMap<_, _>>())
+                    }
+                    ScopeValue::DictManager(index) => {
+                        let dict_manager = dict_managers.get(*index).ok_or_else(|| {
+                            RunnerError::SnapshotMismatch(Box::new(format!(
+                                "missing dict manager {index}"
+                            )))
+                        })?;
+                        Box::new(Shared::clone(dict_manager))
+                    }
+                    #[cfg(feature = "cairo-1-hints")]
+                    ScopeValue::Cairo1DictManager(value) => Box::new(value.restore()),
+                    #[cfg(feature = "cairo-1-hints")]
+                    ScopeValue::Cairo1DictSquash(value) => Box::new(value.restore()),
+                };
+                scope.insert(name.clone(), value);
+            }
+            exec_scopes.data.push(scope);
+        }
+        Ok(exec_scopes)
+    }
+}
+
+fn scope_value(
+    value: &dyn Any,
+    dict_managers: &mut Vec<Shared<SharedCell<DictManager>>>,
+) -> Option<ScopeValue> {
+    if let Some(value) = value.downcast_ref::<Felt252>() {
+        return Some(ScopeValue::Felt(*value));
+    }
+    if let Some(value) = value.downcast_ref::<BigInt>() {
+        return Some(ScopeValue::BigInt(value.clone()));
+    }
+    if let Some(value) = value.downcast_ref::<BigUint>() {
+        return Some(ScopeValue::BigUint(value.clone()));
+    }
+    if let Some(value) = value.downcast_ref::<u64>() {
+        return Some(ScopeValue::U64(*value));
+    }
+    if let Some(value) = value.downcast_ref::<usize>() {
+        return Some(ScopeValue::Usize(*value));
+    }
+    if let Some(value) = value.downcast_ref::<bool>() {
+        return Some(ScopeValue::Bool(*value));
+    }
+    if let Some(value) = value.downcast_ref::<Relocatable>() {
+        return Some(ScopeValue::Relocatable(*value));
+    }
+    if let Some(value) = value.downcast_ref::<Vec<Felt252>>() {
+        return Some(ScopeValue::FeltVec(value.clone()));
+    }
+    if let Some(value) = value.downcast_ref::<Vec<usize>>() {
+        return Some(ScopeValue::UsizeVec(value.clone()));
+    }
+    if let Some(value) = value.downcast_ref::<Vec<u64>>() {
+        return Some(ScopeValue::U64Vec(value.clone()));
+    }
+    if let Some(value) = value.downcast_ref::<Vec<BigInt>>() {
+        return Some(ScopeValue::BigIntVec(value.clone()));
+    }
+    if let Some(value) = value.downcast_ref::<Vec<MaybeRelocatable>>() {
+        return Some(ScopeValue::MaybeRelocatableVec(value.clone()));
+    }
+    if let Some(value) = value.downcast_ref::<HashMap<Felt252, Vec<Felt252>>>() {
+        return Some(ScopeValue::FeltVecMap(sorted_entries(value)));
+    }
+    if let Some(value) = value.downcast_ref::<HashMap<Felt252, Vec<usize>>>() {
+        return Some(ScopeValue::UsizeVecMap(sorted_entries(value)));
+    }
+    if let Some(value) = value.downcast_ref::<HashMap<Felt252, Vec<u64>>>() {
+        return Some(ScopeValue::U64VecMap(sorted_entries(value)));
+    }
+    if let Some(value) = value.downcast_ref::<Shared<SharedCell<DictManager>>>() {
+        let index = match dict_managers.iter().position(|d| Shared::ptr_eq(d, value)) {
+            Some(index) => index,
+            None => {
+                dict_managers.push(Shared::clone(value));
+                dict_managers.len() - 1
+            }
+        };
+        return Some(ScopeValue::DictManager(index));
+    }
+    #[cfg(feature = "cairo-1-hints")]
+    if let Some(value) = value.downcast_ref::<DictManagerExecScope>() {
+        return Some(ScopeValue::Cairo1DictManager(
+            Cairo1DictManagerSnapshot::new(value),
+        ));
+    }
+    #[cfg(feature = "cairo-1-hints")]
+    if let Some(value) = value.downcast_ref::<DictSquashExecScope>() {
+        return Some(ScopeValue::Cairo1DictSquash(Cairo1DictSquashSnapshot::new(
+            value,
+        )));
+    }
+    None
+}
+
+// Entries of a map sorted by key, so that equal maps have equal snapshots
+fn sorted_entries<T: Clone>(map: &HashMap<Felt252, T>) -> Vec<(Felt252, T)> {
+    let mut entries: Vec<_> = map
+        .iter()
+        .map(|(key, value)| (*key, value.clone()))
+        .collect();
+    entries.sort_by_key(|(key, _)| *key);
+    entries
+}
+
+impl DictManagerSnapshot {
+    fn new(dict_manager: &DictManager) -> Self {
+        let mut trackers: Vec<_> = dict_manager
+            .trackers
+            .iter()
+            .map(|(segment, tracker)| {
+                let (dict, default_value) = match &tracker.data {
+                    Dictionary::SimpleDictionary(dict) => (dict, None),
+                    Dictionary::DefaultDictionary {
+                        dict,
+                        default_value,
+                    } => (dict, Some(default_value.clone())),
+                };
+                let data = dict
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                (
+                    *segment,
+                    DictTrackerSnapshot {
+                        data,
+                        default_value,
+                        current_ptr: tracker.current_ptr,
+                    },
+                )
+            })
+            .collect();
+        trackers.sort_by_key(|(segment, _)| *segment);
+        DictManagerSnapshot { trackers }
+    }
+
+    fn restore(&self) -> DictManager {
+        let trackers = self
+            .trackers
+            .iter()
+            .map(|(segment, tracker)| {
+                let dict = tracker.data.iter().cloned().collect();
+                let data = match &tracker.default_value {
+                    Some(default_value) => Dictionary::DefaultDictionary {
+                        dict,
+                        default_value: default_value.clone(),
+                    },
+                    None => Dictionary::SimpleDictionary(dict),
+                };
+                (
+                    *segment,
+                    DictTracker {
+                        data,
+                        current_ptr: tracker.current_ptr,
+                    },
+                )
+            })
+            .collect();
+        DictManager { trackers }
+    }
+}
+
+#[cfg(feature = "cairo-1-hints")]
+impl Cairo1DictManagerSnapshot {
+    fn new(dict_manager: &DictManagerExecScope) -> Self {
+        let mut segment_to_tracker: Vec<_> = dict_manager
+            .segment_to_tracker()
+            .iter()
+            .map(|(segment, tracker)| (*segment, *tracker))
+            .collect();
+        segment_to_tracker.sort();
+        Cairo1DictManagerSnapshot {
+            segment_to_tracker,
+            trackers: dict_manager
+                .trackers()
+                .iter()
+                .map(|tracker| Cairo1DictTrackerSnapshot {
+                    data: tracker
+                        .data()
+                        .iter()
+                        .map(|(key, value)| (*key, value.clone()))
+                        .collect(),
+                    start: tracker.start(),
+                    end: tracker.end(),
+                })
+                .collect(),
+            use_temporary_segments: dict_manager.use_temporary_segments(),
+        }
+    }
+
+    fn restore(&self) -> DictManagerExecScope {
+        DictManagerExecScope::from_parts(
+            self.segment_to_tracker.iter().copied().collect(),
+            self.trackers
+                .iter()
+                .map(|tracker| {
+                    DictTrackerExecScope::from_parts(
+                        tracker.data.iter().cloned().collect(),
+                        tracker.start,
+                        tracker.end,
+                    )
+                })
+                .collect(),
+            self.use_temporary_segments,
+        )
+    }
+}
+
+#[cfg(feature = "cairo-1-hints")]
+impl Cairo1DictSquashSnapshot {
+    fn new(dict_squash: &DictSquashExecScope) -> Self {
+        Cairo1DictSquashSnapshot {
+            access_indices: dict_squash
+                .access_indices
+                .iter()
+                .map(|(key, indices)| (*key, indices.clone()))
+                .collect(),
+            keys: dict_squash.keys.clone(),
+        }
+    }
+
+    fn restore(&self) -> DictSquashExecScope {
+        DictSquashExecScope {
+            access_indices: self.access_indices.iter().cloned().collect(),
+            keys: self.keys.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::{layout_name::LayoutName, program::Program},
+        utils::test_utils::program_b,
+        vm::runners::cairo_runner::CairoRunner,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn initialized_runner(program: &Program) -> (CairoRunner, Relocatable) {
+        let mut runner = CairoRunner::new(program, LayoutName::small, false, true).unwrap();
+        let end = runner.initialize(false).unwrap();
+        (runner, end)
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn snapshot_and_resume_run() {
+        let program = program_b();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+
+        // Uninterrupted run
+        let (mut expected, end) = initialized_runner(&program);
+        expected.run_until_pc(end, &mut hint_processor).unwrap();
+
+        // Paused run, serialized and resumed on a new runner
+        let (mut paused, end) = initialized_runner(&program);
+        paused.run_for_steps(3, &mut hint_processor).unwrap();
+        let bytes = paused.take_snapshot().unwrap().to_bytes().unwrap();
+        let snapshot = RunnerSnapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(snapshot, paused.take_snapshot().unwrap());
+
+        let (mut resumed, end_b) = initialized_runner(&program);
+        assert_eq!(end, end_b);
+        resumed.restore_snapshot(&snapshot).unwrap();
+        assert_eq!(resumed.vm.current_step, 3);
+        assert_eq!(resumed.vm.get_pc(), paused.vm.get_pc());
+        resumed.run_until_pc(end, &mut hint_processor).unwrap();
+
+        assert_eq!(
+            resumed.take_snapshot().unwrap(),
+            expected.take_snapshot().unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn restore_earlier_snapshot() {
+        let program = program_b();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let (mut runner, end) = initialized_runner(&program);
+        runner.run_for_steps(1, &mut hint_processor).unwrap();
+        let snapshot = runner.take_snapshot().unwrap();
+
+        // The cells written after the snapshot are dropped when it is restored
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        runner.restore_snapshot(&snapshot).unwrap();
+        assert_eq!(runner.take_snapshot().unwrap(), snapshot);
+
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        let (mut expected, _) = initialized_runner(&program);
+        expected.run_until_pc(end, &mut hint_processor).unwrap();
+        assert_eq!(
+            runner.take_snapshot().unwrap(),
+            expected.take_snapshot().unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn restore_snapshot_into_different_runner() {
+        let program = program_b();
+        let (runner, _) = initialized_runner(&program);
+        let snapshot = runner.take_snapshot().unwrap();
+
+        // Not initialized
+        let mut other = CairoRunner::new(&program, LayoutName::small, false, true).unwrap();
+        assert_matches!(
+            other.restore_snapshot(&snapshot),
+            Err(RunnerError::SnapshotMismatch(_))
+        );
+        // Different builtins
+        let (mut other, _) = initialized_runner(&program);
+        let mut snapshot = snapshot;
+        snapshot.vm.builtins[0].name = BuiltinName::pedersen;
+        assert_matches!(
+            other.restore_snapshot(&snapshot),
+            Err(RunnerError::SnapshotMismatch(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn restore_snapshot_drops_later_builtin_data() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/pedersen_extra_builtins.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runner = CairoRunner::new(&program, LayoutName::all_cairo, false, true).unwrap();
+        let end = runner.initialize(false).unwrap();
+        let snapshot = runner.take_snapshot().unwrap();
+
+        // The run verifies a pedersen hash, and reading its return values sets the stop pointers
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        runner.end_run(false, false, &mut hint_processor).unwrap();
+        runner.read_return_values(false).unwrap();
+        // The program doesn't add output pages nor signatures
+        for builtin in runner.vm.builtin_runners.iter_mut() {
+            let base = builtin.base() as isize;
+            match builtin {
+                BuiltinRunner::Output(output) => {
+                    output.add_page(1, (base, 0).into(), 1).unwrap();
+                }
+                BuiltinRunner::Signature(signature) => {
+                    signature
+                        .add_signature((base, 0).into(), &(Felt252::ONE, Felt252::TWO))
+                        .unwrap();
+                }
+                _ => {}
+            }
+        }
+        let later_snapshot = runner.take_snapshot().unwrap();
+        for builtin in later_snapshot.vm.builtins.iter() {
+            assert!(builtin.stop_ptr.is_some());
+            match &builtin.additional_data {
+                BuiltinAdditionalData::Hash(data) => assert!(!data.is_empty()),
+                BuiltinAdditionalData::Signature(data) => assert!(!data.is_empty()),
+                BuiltinAdditionalData::Output(data) => assert!(!data.pages.is_empty()),
+                _ => {}
+            }
+        }
+
+        runner.restore_snapshot(&snapshot).unwrap();
+        assert_eq!(runner.take_snapshot().unwrap(), snapshot);
+        for builtin in runner.vm.builtin_runners.iter() {
+            assert_eq!(builtin.stop_ptr(), None);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn exec_scopes_snapshot() {
+        let dict_manager = Shared::new(SharedCell::new(DictManager::new()));
+        dict_manager.borrow_mut().trackers.insert(
+            2,
+            DictTracker::new_default_dict(
+                (2, 0).into(),
+                &MaybeRelocatable::from(7),
+                Some(HashMap::from([(
+                    MaybeRelocatable::from(1),
+                    MaybeRelocatable::from((3, 4)),
+                )])),
+            ),
+        );
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value("dict_manager", dict_manager.clone());
+        exec_scopes.insert_value("n", 5_usize);
+        exec_scopes.insert_value("value", BigInt::from(-3));
+        exec_scopes.insert_value("keys", vec![Felt252::ONE, Felt252::TWO]);
+        exec_scopes.insert_value(
+            "access_indices",
+            HashMap::from([
+                (Felt252::TWO, vec![Felt252::ONE]),
+                (Felt252::ONE, vec![Felt252::ZERO, Felt252::TWO]),
+            ]),
+        );
+        exec_scopes.insert_value(
+            "positions_dict",
+            HashMap::from([(Felt252::ONE, vec![0_u64, 3])]),
+        );
+        exec_scopes.insert_value("positions", vec![3_u64, 0]);
+        exec_scopes.insert_value("elements", vec![MaybeRelocatable::from((1, 2))]);
+        exec_scopes.insert_value("limbs", vec![BigInt::from(-1), BigInt::from(2)]);
+        exec_scopes.enter_scope(HashMap::from([(
+            "dict_manager".to_string(),
+            Box::new(dict_manager) as AnyBox,
+        )]));
+
+        let snapshot = ExecScopesSnapshot::new(&exec_scopes).unwrap();
+        assert_eq!(snapshot.dict_managers.len(), 1);
+        let bytes = serde_json::to_vec(&snapshot).unwrap();
+        let snapshot: ExecScopesSnapshot = serde_json::from_slice(&bytes).unwrap();
+        let mut restored = snapshot.restore().unwrap();
+
+        assert_eq!(restored.data.len(), 2);
+        let inner = restored.get_dict_manager().unwrap();
+        restored.exit_scope().unwrap();
+        let outer = restored.get_dict_manager().unwrap();
+        assert!(Shared::ptr_eq(&inner, &outer));
+        assert_eq!(
+            outer
+                .borrow_mut()
+                .trackers
+                .get_mut(&2)
+                .unwrap()
+                .get_value(&MaybeRelocatable::from(1))
+                .unwrap(),
+            &MaybeRelocatable::from((3, 4))
+        );
+        assert_eq!(restored.get::<usize>("n").unwrap(), 5);
+        assert_eq!(restored.get::<BigInt>("value").unwrap(), BigInt::from(-3));
+        assert_eq!(
+            restored.get::<Vec<Felt252>>("keys").unwrap(),
+            vec![Felt252::ONE, Felt252::TWO]
+        );
+        assert_eq!(
+            restored
+                .get::<HashMap<Felt252, Vec<Felt252>>>("access_indices")
+                .unwrap(),
+            HashMap::from([
+                (Felt252::TWO, vec![Felt252::ONE]),
+                (Felt252::ONE, vec![Felt252::ZERO, Felt252::TWO]),
+            ])
+        );
+        assert_eq!(
+            restored
+                .get::<HashMap<Felt252, Vec<u64>>>("positions_dict")
+                .unwrap(),
+            HashMap::from([(Felt252::ONE, vec![0_u64, 3])])
+        );
+        assert_eq!(restored.get::<Vec<u64>>("positions").unwrap(), vec![3, 0]);
+        assert_eq!(
+            restored.get::<Vec<MaybeRelocatable>>("elements").unwrap(),
+            vec![MaybeRelocatable::from((1, 2))]
+        );
+        assert_eq!(
+            restored.get::<Vec<BigInt>>("limbs").unwrap(),
+            vec![BigInt::from(-1), BigInt::from(2)]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn exec_scopes_snapshot_unsupported_type() {
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value("positions", vec![vec![1_u32]]);
+        assert_matches!(
+            ExecScopesSnapshot::new(&exec_scopes),
+            Err(RunnerError::SnapshotUnsupportedScopeVariable(name)) if *name == "positions"
+        );
+    }
+}
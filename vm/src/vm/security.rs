@@ -30,13 +30,7 @@ pub fn verify_secure_runner(
     };
     // Check builtin segment out of bounds.
     for (index, stop_ptr) in builtins_segment_info {
-        let current_size = runner
-            .vm
-            .segments
-            .memory
-            .data
-            .get(index)
-            .map(|segment| segment.len());
+        let current_size = runner.vm.segments.memory.data.segment_len(index);
         // + 1 here accounts for maximum segment offset being segment.len() -1
         if current_size >= Some(stop_ptr + 1) {
             return Err(VirtualMachineError::OutOfBoundsBuiltinSegmentAccess);
@@ -54,8 +48,7 @@ pub fn verify_secure_runner(
         .segments
         .memory
         .data
-        .get(program_segment_index)
-        .map(|segment| segment.len());
+        .segment_len(program_segment_index);
     // + 1 here accounts for maximum segment offset being segment.len() -1
     if program_length >= Some(program_segment_size + 1) {
         return Err(VirtualMachineError::OutOfBoundsProgramSegmentAccess);
@@ -64,7 +57,9 @@ pub fn verify_secure_runner(
     // This means that every temporary address has been properly relocated to a real address
     // Asumption: If temporary memory is empty, this means no temporary memory addresses were generated and all addresses in memory are real
     if !runner.vm.segments.memory.temp_data.is_empty() {
-        for value in runner.vm.segments.memory.data.iter().flatten() {
+        let memory = &runner.vm.segments.memory.data;
+        let cells = (0..memory.num_segments()).flat_map(|index| memory.written_cells(index));
+        for (_, value) in cells {
             match value.get_value() {
                 Some(MaybeRelocatable::RelocatableValue(addr)) if addr.segment_index < 0 => {
                     return Err(VirtualMachineError::InvalidMemoryValueTemporaryAddress(
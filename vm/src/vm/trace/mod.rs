@@ -40,3 +40,295 @@ pub mod trace_entry {
         Ok(relocation_table[segment_index] + value.offset)
     }
 }
+
+pub mod trace_sink {
+    #[cfg(feature = "std")]
+    use crate::{types::relocatable::Relocatable, vm::trace::trace_entry::relocate_trace_register};
+    use crate::{
+        types::shared::MaybeSendSync,
+        vm::{errors::trace_errors::TraceError, trace::trace_entry::TraceEntry},
+    };
+    #[cfg(feature = "std")]
+    use bincode::enc::write::Writer;
+
+    /// Size in bytes of each entry written by a [RawTraceWriter]
+    pub const RAW_TRACE_ENTRY_SIZE: usize = 32;
+
+    /// Receives the trace entries of a run while it is executed, so that they don't have to be
+    /// kept in memory until the end of the run.
+    /// The entries are received before relocation, in execution order.
+    pub trait TraceSink: MaybeSendSync {
+        fn write_entries(&mut self, entries: &[TraceEntry]) -> Result<(), TraceError>;
+
+        /// Called once the last entries of the run have been written
+        fn flush(&mut self) -> Result<(), TraceError> {
+            Ok(())
+        }
+    }
+
+    /// A [TraceSink] writing the entries without relocating them, as 4 little endian 64 bit
+    /// values: the pc segment index, the pc offset, ap and fp.
+    /// The output can be relocated once the run is finished with [write_relocated_raw_trace].
+    #[cfg(feature = "std")]
+    pub struct RawTraceWriter<W: std::io::Write + MaybeSendSync> {
+        dest: W,
+    }
+
+    #[cfg(feature = "std")]
+    impl<W: std::io::Write + MaybeSendSync> RawTraceWriter<W> {
+        pub fn new(dest: W) -> Self {
+            RawTraceWriter { dest }
+        }
+
+        pub fn into_inner(self) -> W {
+            self.dest
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl<W: std::io::Write + MaybeSendSync> TraceSink for RawTraceWriter<W> {
+        fn write_entries(&mut self, entries: &[TraceEntry]) -> Result<(), TraceError> {
+            for entry in entries {
+                let mut bytes = [0; RAW_TRACE_ENTRY_SIZE];
+                bytes[0..8].copy_from_slice(&(entry.pc.segment_index as i64).to_le_bytes());
+                bytes[8..16].copy_from_slice(&(entry.pc.offset as u64).to_le_bytes());
+                bytes[16..24].copy_from_slice(&(entry.ap as u64).to_le_bytes());
+                bytes[24..32].copy_from_slice(&(entry.fp as u64).to_le_bytes());
+                self.dest
+                    .write_all(&bytes)
+                    .map_err(|e| TraceError::Stream(e.to_string().into_boxed_str()))?;
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), TraceError> {
+            self.dest
+                .flush()
+                .map_err(|e| TraceError::Stream(e.to_string().into_boxed_str()))
+        }
+    }
+
+    /// Reads the entries written by a [RawTraceWriter] and writes them relocated, in the same
+    /// format as [write_encoded_trace](crate::cairo_run::write_encoded_trace), one entry at a time.
+    /// Returns the amount of entries written.
+    #[cfg(feature = "std")]
+    pub fn write_relocated_raw_trace(
+        raw_trace: &mut impl std::io::Read,
+        relocation_table: &[usize],
+        dest: &mut impl Writer,
+    ) -> Result<usize, TraceError> {
+        let stream_error = |e: &dyn std::fmt::Display| TraceError::Stream(e.to_string().into());
+        let segment_1_base = *relocation_table
+            .get(1)
+            .ok_or(TraceError::NoRelocationFound)?;
+        let read_u64 = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().unwrap_or_default());
+        let mut n_entries = 0;
+        let mut bytes = [0; RAW_TRACE_ENTRY_SIZE];
+        loop {
+            // Read the entry, stopping at the end of the input if no byte of the entry was read
+            let mut read = 0;
+            while read < RAW_TRACE_ENTRY_SIZE {
+                match raw_trace.read(&mut bytes[read..]) {
+                    Ok(0) => break,
+                    Ok(n) => read += n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(stream_error(&e)),
+                }
+            }
+            match read {
+                0 => return Ok(n_entries),
+                RAW_TRACE_ENTRY_SIZE => {}
+                _ => return Err(stream_error(&"truncated trace entry")),
+            }
+            let pc = Relocatable::from((
+                read_u64(&bytes[0..8]) as i64 as isize,
+                read_u64(&bytes[8..16]) as usize,
+            ));
+            let pc = relocate_trace_register(pc, relocation_table)?;
+            let ap = read_u64(&bytes[16..24]) as usize + segment_1_base;
+            let fp = read_u64(&bytes[24..32]) as usize + segment_1_base;
+            for register in [ap, fp, pc] {
+                dest.write(&(register as u64).to_le_bytes())
+                    .map_err(|e| stream_error(&e))?;
+            }
+            n_entries += 1;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{trace_entry::TraceEntry, trace_sink::*};
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        stdlib::sync::{Arc, Mutex},
+        types::{layout_name::LayoutName, program::Program},
+        vm::{
+            errors::trace_errors::TraceError, runners::cairo_runner::CairoRunner,
+            vm_core::TRACE_SINK_BUFFER_SIZE,
+        },
+    };
+    use assert_matches::assert_matches;
+    use bincode::enc::write::Writer;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    /// Writer whose contents can be read after moving it into a trace sink
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Writer for SharedBuffer {
+        fn write(&mut self, bytes: &[u8]) -> Result<(), bincode::error::EncodeError> {
+            self.0.lock().unwrap().extend_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn len(&self) -> usize {
+            self.0.lock().unwrap().len()
+        }
+    }
+
+    // func main() { jmp rel 0; }
+    fn loop_runner() -> CairoRunner {
+        let program = serde_json::json!({
+            "attributes": [],
+            "builtins": [],
+            "data": ["0x10780017fff7fff", "0x0"],
+            "debug_info": null,
+            "hints": {},
+            "identifiers": {
+                "__main__.main": { "decorators": [], "pc": 0, "type": "function" }
+            },
+            "main_scope": "__main__",
+            "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+            "reference_manager": { "references": [] },
+        });
+        let program =
+            Program::from_bytes(&serde_json::to_vec(&program).unwrap(), Some("main")).unwrap();
+        let mut runner = CairoRunner::new(&program, LayoutName::plain, false, true).unwrap();
+        runner.initialize(false).unwrap();
+        runner
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn relocate_raw_trace() {
+        let entries = [
+            TraceEntry {
+                pc: (0, 3).into(),
+                ap: 7,
+                fp: 5,
+            },
+            TraceEntry {
+                pc: (2, 1).into(),
+                ap: 8,
+                fp: 5,
+            },
+        ];
+        let mut raw_writer = RawTraceWriter::new(SharedBuffer::default());
+        raw_writer.write_entries(&entries).unwrap();
+        let raw_trace = raw_writer.into_inner().0.lock().unwrap().clone();
+        assert_eq!(raw_trace.len(), 2 * RAW_TRACE_ENTRY_SIZE);
+
+        let mut relocated = SharedBuffer::default();
+        assert_eq!(
+            write_relocated_raw_trace(&mut raw_trace.as_slice(), &[1, 10, 30], &mut relocated),
+            Ok(2)
+        );
+        let relocated: Vec<u64> = relocated
+            .0
+            .lock()
+            .unwrap()
+            .chunks(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        // ap, fp and pc of each entry
+        assert_eq!(relocated, [17, 15, 4, 18, 15, 31]);
+
+        assert_matches!(
+            write_relocated_raw_trace(
+                &mut &raw_trace[..40],
+                &[1, 10, 30],
+                &mut SharedBuffer::default()
+            ),
+            Err(TraceError::Stream(_))
+        );
+        assert_matches!(
+            write_relocated_raw_trace(
+                &mut raw_trace.as_slice(),
+                &[1, 10],
+                &mut SharedBuffer::default()
+            ),
+            Err(TraceError::NoRelocationFound)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn trace_sink_receives_buffered_entries() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runner = loop_runner();
+        let buffer = SharedBuffer::default();
+        runner
+            .vm
+            .set_trace_sink(Box::new(RawTraceWriter::new(buffer.clone())))
+            .unwrap();
+
+        runner
+            .run_for_steps(TRACE_SINK_BUFFER_SIZE + 3, &mut hint_processor)
+            .unwrap();
+        assert_eq!(buffer.len(), TRACE_SINK_BUFFER_SIZE * RAW_TRACE_ENTRY_SIZE);
+        assert_eq!(runner.vm.trace.as_ref().map(Vec::len), Some(3));
+        assert_matches!(runner.get_profile(), Err(TraceError::TraceStreamed));
+
+        // Journaled steps keep their entries in memory
+        runner.vm.enable_journal();
+        runner
+            .run_for_steps(TRACE_SINK_BUFFER_SIZE, &mut hint_processor)
+            .unwrap();
+        assert_eq!(buffer.len(), TRACE_SINK_BUFFER_SIZE * RAW_TRACE_ENTRY_SIZE);
+        runner.vm.step_back(2).unwrap();
+        assert_eq!(
+            runner.vm.trace.as_ref().map(Vec::len),
+            Some(TRACE_SINK_BUFFER_SIZE + 1)
+        );
+
+        runner.relocate(false).unwrap();
+        assert_eq!(
+            buffer.len(),
+            (2 * TRACE_SINK_BUFFER_SIZE + 1) * RAW_TRACE_ENTRY_SIZE
+        );
+        assert!(runner.relocated_trace.is_none());
+        assert_eq!(
+            runner.get_execution_resources().unwrap().n_steps,
+            2 * TRACE_SINK_BUFFER_SIZE + 1
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn trace_sink_requires_trace() {
+        let mut runner =
+            CairoRunner::new(&Program::default(), LayoutName::plain, false, false).unwrap();
+        assert_matches!(
+            runner
+                .vm
+                .set_trace_sink(Box::new(RawTraceWriter::new(SharedBuffer::default()))),
+            Err(TraceError::TraceNotEnabled)
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::math_utils::signed_felt;
-use crate::stdlib::{any::Any, borrow::Cow, collections::HashMap, prelude::*};
+use crate::stdlib::{any::Any, borrow::Cow, collections::HashMap, prelude::*, sync::Arc};
 use crate::types::builtin_name::BuiltinName;
 #[cfg(feature = "extensive_hints")]
 use crate::types::program::HintRange;
@@ -18,12 +18,13 @@ use crate::{
         decoding::decoder::decode_instruction,
         errors::{
             exec_scope_errors::ExecScopeError, memory_errors::MemoryError,
-            vm_errors::VirtualMachineError,
+            trace_errors::TraceError, vm_errors::VirtualMachineError,
         },
+        journal::{MemoryJournalEntry, StepJournalEntry},
         runners::builtin_runner::{
             BuiltinRunner, OutputBuiltinRunner, RangeCheckBuiltinRunner, SignatureBuiltinRunner,
         },
-        trace::trace_entry::TraceEntry,
+        trace::{trace_entry::TraceEntry, trace_sink::TraceSink},
         vm_memory::memory_segments::MemorySegmentManager,
     },
 };
@@ -39,6 +40,8 @@ use super::runners::builtin_runner::{ModBuiltinRunner, RC_N_PARTS_STANDARD};
 use super::runners::cairo_pie::CairoPie;
 
 const MAX_TRACEBACK_ENTRIES: u32 = 20;
+/// Amount of trace entries kept in memory before writing them to the trace sink
+pub(crate) const TRACE_SINK_BUFFER_SIZE: usize = 1 << 16;
 
 #[derive(PartialEq, Eq, Debug)]
 pub struct Operands {
@@ -85,14 +88,22 @@ pub struct VirtualMachine {
     pub builtin_runners: Vec<BuiltinRunner>,
     pub segments: MemorySegmentManager,
     pub(crate) trace: Option<Vec<TraceEntry>>,
+    /// Receives the trace entries instead of keeping them in `trace`, see [VirtualMachine::set_trace_sink]
+    pub(crate) trace_sink: Option<Box<dyn TraceSink>>,
+    /// Amount of trace entries already written to the trace sink
+    pub(crate) trace_flushed: usize,
     pub(crate) current_step: usize,
     pub(crate) rc_limits: Option<(isize, isize)>,
     skip_instruction_execution: bool,
     run_finished: bool,
-    instruction_cache: Vec<Option<Instruction>>,
-    #[cfg(feature = "test_utils")]
+    /// Decoded instructions of the program segment, indexed by offset.
+    /// It can be shared between runs of the same program, and is only cloned when a new instruction
+    /// has to be added to a shared cache.
+    pub(crate) instruction_cache: Arc<Vec<Option<Instruction>>>,
+    #[cfg(feature = "hooks")]
     pub(crate) hooks: crate::vm::hooks::Hooks,
     pub(crate) relocation_table: Option<Vec<usize>>,
+    pub(crate) journal: Option<Vec<StepJournalEntry>>,
 }
 
 impl VirtualMachine {
@@ -113,15 +124,18 @@ impl VirtualMachine {
             run_context,
             builtin_runners: Vec::new(),
             trace,
+            trace_sink: None,
+            trace_flushed: 0,
             current_step: 0,
             skip_instruction_execution: false,
             segments: MemorySegmentManager::new(),
             rc_limits: None,
             run_finished: false,
-            instruction_cache: Vec::new(),
-            #[cfg(feature = "test_utils")]
+            instruction_cache: Arc::default(),
+            #[cfg(feature = "hooks")]
             hooks: Default::default(),
             relocation_table: None,
+            journal: None,
         }
     }
 
@@ -294,7 +308,13 @@ impl VirtualMachine {
         for builtin in self.builtin_runners.iter() {
             if builtin.base() as isize == address.segment_index {
                 match builtin.deduce_memory_cell(address, &self.segments.memory) {
-                    Ok(maybe_reloc) => return Ok(maybe_reloc),
+                    Ok(maybe_reloc) => {
+                        #[cfg(feature = "hooks")]
+                        if let Some(ref value) = maybe_reloc {
+                            self.execute_builtin_deduction(address, value)?;
+                        }
+                        return Ok(maybe_reloc);
+                    }
                     Err(error) => return Err(VirtualMachineError::RunnerError(error)),
                 };
             }
@@ -397,6 +417,8 @@ impl VirtualMachine {
                 .insert(operands_addresses.dst_addr, &operands.dst)
                 .map_err(VirtualMachineError::Memory)?;
         }
+        #[cfg(feature = "hooks")]
+        self.execute_memory_writes()?;
 
         Ok(())
     }
@@ -414,6 +436,13 @@ impl VirtualMachine {
                 fp: self.run_context.fp,
             });
         }
+        // Steps that can be undone keep their trace entries in memory
+        if self.trace_sink.is_some()
+            && self.journal.is_none()
+            && self.trace.as_ref().map_or(0, Vec::len) >= TRACE_SINK_BUFFER_SIZE
+        {
+            self.flush_trace()?;
+        }
 
         // Update range check limits
         const OFFSET_BITS: u32 = 16;
@@ -444,7 +473,7 @@ impl VirtualMachine {
         Ok(())
     }
 
-    fn decode_current_instruction(&self) -> Result<Instruction, VirtualMachineError> {
+    pub(crate) fn decode_current_instruction(&self) -> Result<Instruction, VirtualMachineError> {
         let instruction = self
             .segments
             .memory
@@ -463,9 +492,18 @@ impl VirtualMachine {
         constants: &HashMap<String, Felt252>,
     ) -> Result<(), VirtualMachineError> {
         for (hint_index, hint_data) in hint_datas.iter().enumerate() {
-            hint_processor
-                .execute_hint(self, exec_scopes, hint_data, constants)
-                .map_err(|err| VirtualMachineError::Hint(Box::new((hint_index, err))))?
+            #[cfg(feature = "hooks")]
+            self.execute_pre_hint_execution(exec_scopes, hint_data.as_ref(), constants)?;
+            let result = hint_processor.execute_hint(self, exec_scopes, hint_data, constants);
+            #[cfg(feature = "hooks")]
+            if result.is_err() {
+                self.discard_memory_writes();
+            }
+            result.map_err(|err| VirtualMachineError::Hint(Box::new((hint_index, err))))?;
+            #[cfg(feature = "hooks")]
+            self.execute_memory_writes()?;
+            #[cfg(feature = "hooks")]
+            self.execute_post_hint_execution(exec_scopes, hint_data.as_ref(), constants)?;
         }
         Ok(())
     }
@@ -485,14 +523,21 @@ impl VirtualMachine {
             let s = *s;
             // Execute each hint for the given range
             for idx in s..(s + l.get()) {
-                let hint_extension = hint_processor
-                    .execute_hint_extensive(
-                        self,
-                        exec_scopes,
-                        hint_datas.get(idx).ok_or(VirtualMachineError::Unexpected)?,
-                        constants,
-                    )
-                    .map_err(|err| VirtualMachineError::Hint(Box::new((idx - s, err))))?;
+                let hint_data = hint_datas.get(idx).ok_or(VirtualMachineError::Unexpected)?;
+                #[cfg(feature = "hooks")]
+                self.execute_pre_hint_execution(exec_scopes, hint_data.as_ref(), constants)?;
+                let result =
+                    hint_processor.execute_hint_extensive(self, exec_scopes, hint_data, constants);
+                #[cfg(feature = "hooks")]
+                if result.is_err() {
+                    self.discard_memory_writes();
+                }
+                let hint_extension =
+                    result.map_err(|err| VirtualMachineError::Hint(Box::new((idx - s, err))))?;
+                #[cfg(feature = "hooks")]
+                self.execute_memory_writes()?;
+                #[cfg(feature = "hooks")]
+                self.execute_post_hint_execution(exec_scopes, hint_data.as_ref(), constants)?;
                 // Update the hint_ranges & hint_datas with the hints added by the executed hint
                 for (hint_pc, hints) in hint_extension {
                     if let Ok(len) = NonZeroUsize::try_from(hints.len()) {
@@ -510,26 +555,28 @@ impl VirtualMachine {
             // Run instructions from program segment, using instruction cache
             let pc = self.run_context.pc.offset;
 
-            if self.segments.memory.data[0].len() <= pc {
+            if self.segments.memory.data.segment_len(0).unwrap_or_default() <= pc {
                 return Err(MemoryError::UnknownMemoryCell(Box::new((0, pc).into())))?;
             }
 
-            let mut inst_cache = core::mem::take(&mut self.instruction_cache);
-            inst_cache.resize((pc + 1).max(inst_cache.len()), None);
-
-            let instruction = inst_cache.get_mut(pc).unwrap();
-            if instruction.is_none() {
-                *instruction = Some(self.decode_current_instruction()?);
-            }
-            let instruction = instruction.as_ref().unwrap();
+            // The shared cache is only written, and copied if shared, on a cache miss
+            let instruction = match self.instruction_cache.get(pc).copied().flatten() {
+                Some(instruction) => instruction,
+                None => {
+                    let instruction = self.decode_current_instruction()?;
+                    let inst_cache = Arc::make_mut(&mut self.instruction_cache);
+                    inst_cache.resize((pc + 1).max(inst_cache.len()), None);
+                    inst_cache[pc] = Some(instruction);
+                    instruction
+                }
+            };
 
             if !self.skip_instruction_execution {
-                self.run_instruction(instruction)?;
+                self.run_instruction(&instruction)?;
             } else {
                 self.run_context.pc += instruction.size();
                 self.skip_instruction_execution = false;
             }
-            self.instruction_cache = inst_cache;
         } else {
             // Run instructions from programs loaded in other segments, without instruction cache
             let instruction = self.decode_current_instruction()?;
@@ -553,6 +600,9 @@ impl VirtualMachine {
         #[cfg(feature = "extensive_hints")] hint_ranges: &mut HashMap<Relocatable, HintRange>,
         constants: &HashMap<String, Felt252>,
     ) -> Result<(), VirtualMachineError> {
+        #[cfg(feature = "hooks")]
+        self.record_memory_writes();
+        self.record_step();
         self.step_hint(
             hint_processor,
             exec_scopes,
@@ -562,15 +612,163 @@ impl VirtualMachine {
             constants,
         )?;
 
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         self.execute_pre_step_instruction(hint_processor, exec_scopes, hint_datas, constants)?;
-        self.step_instruction()?;
-        #[cfg(feature = "test_utils")]
+        let result = self.step_instruction();
+        #[cfg(feature = "hooks")]
+        if result.is_err() {
+            self.discard_memory_writes();
+        }
+        result?;
+        #[cfg(feature = "hooks")]
         self.execute_post_step_instruction(hint_processor, exec_scopes, hint_datas, constants)?;
 
         Ok(())
     }
 
+    // Records the state of the vm before a step in the journal, if enabled
+    fn record_step(&mut self) {
+        let trace_len = self.trace_len();
+        let Some(journal) = &mut self.journal else {
+            return;
+        };
+        let memory = &self.segments.memory;
+        journal.push(StepJournalEntry {
+            pc: self.run_context.pc,
+            ap: self.run_context.ap,
+            fp: self.run_context.fp,
+            current_step: self.current_step,
+            rc_limits: self.rc_limits,
+            skip_instruction_execution: self.skip_instruction_execution,
+            trace_len,
+            num_segments: memory.data.num_segments(),
+            num_temp_segments: memory.temp_data.len(),
+            memory_journal_len: memory.journal.as_ref().map_or(0, Vec::len),
+        });
+    }
+
+    /// Starts recording the changes made by each step, so that they can be undone with
+    /// [VirtualMachine::step_back]. Steps executed before enabling the journal can't be undone.
+    pub fn enable_journal(&mut self) {
+        if self.journal.is_none() {
+            self.journal = Some(Vec::new());
+            self.segments.memory.journal = Some(Vec::new());
+        }
+    }
+
+    /// Stops recording the changes made by each step and discards the journal.
+    pub fn disable_journal(&mut self) {
+        self.journal = None;
+        self.segments.memory.journal = None;
+    }
+
+    /// Writes the trace entries of the following steps to `sink` while the VM runs, keeping at most
+    /// a fixed amount of them in memory, instead of accumulating the whole trace.
+    /// Fails if the trace is not enabled.
+    ///
+    /// The trace entries are written unrelocated, and [CairoRunner::relocate](crate::vm::runners::cairo_runner::CairoRunner::relocate)
+    /// flushes the remaining entries to the sink instead of relocating the trace in memory. The
+    /// features that need the whole trace, such as the profiler, the coverage report or the AIR
+    /// public input, can't be used on streamed runs. Steps written to the sink can't be undone with
+    /// [VirtualMachine::step_back], so entries aren't written while the journal is enabled.
+    pub fn set_trace_sink(&mut self, sink: Box<dyn TraceSink>) -> Result<(), TraceError> {
+        if self.trace.is_none() {
+            return Err(TraceError::TraceNotEnabled);
+        }
+        self.trace_sink = Some(sink);
+        Ok(())
+    }
+
+    /// Writes the trace entries kept in memory to the trace sink, if any, and flushes it.
+    pub fn flush_trace(&mut self) -> Result<(), TraceError> {
+        let (Some(sink), Some(trace)) = (&mut self.trace_sink, &mut self.trace) else {
+            return Ok(());
+        };
+        sink.write_entries(trace)?;
+        self.trace_flushed += trace.len();
+        trace.clear();
+        sink.flush()
+    }
+
+    /// Returns the trace kept in memory, which is the whole trace unless it was written to a sink.
+    pub(crate) fn get_trace(&self) -> Result<&[TraceEntry], TraceError> {
+        if self.trace_sink.is_some() {
+            return Err(TraceError::TraceStreamed);
+        }
+        self.trace.as_deref().ok_or(TraceError::TraceNotEnabled)
+    }
+
+    /// Returns the amount of trace entries of the run, including the ones written to the trace sink.
+    pub(crate) fn trace_len(&self) -> Option<usize> {
+        self.trace
+            .as_ref()
+            .map(|trace| trace.len() + self.trace_flushed)
+    }
+
+    /// Returns the amount of steps that can be undone with [VirtualMachine::step_back].
+    pub fn journaled_steps(&self) -> usize {
+        self.journal.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Undoes the last `steps` steps, restoring the registers, the trace and the memory cells to
+    /// their state before them.
+    /// Execution scopes and builtin runner state modified by hints are not restored.
+    pub fn step_back(&mut self, steps: usize) -> Result<(), VirtualMachineError> {
+        let journal = self
+            .journal
+            .as_mut()
+            .ok_or(VirtualMachineError::JournalNotEnabled)?;
+        if steps > journal.len() {
+            return Err(VirtualMachineError::StepBackOutOfJournal(Box::new((
+                steps,
+                journal.len(),
+            ))));
+        }
+        let Some(entry) = journal.drain(journal.len() - steps..).next() else {
+            return Ok(());
+        };
+        self.segments
+            .memory
+            .revert_journal(entry.memory_journal_len)?;
+        self.segments
+            .memory
+            .data
+            .truncate_segments(entry.num_segments);
+        self.segments
+            .memory
+            .temp_data
+            .truncate(entry.num_temp_segments);
+        self.run_context.pc = entry.pc;
+        self.run_context.ap = entry.ap;
+        self.run_context.fp = entry.fp;
+        self.current_step = entry.current_step;
+        self.rc_limits = entry.rc_limits;
+        self.skip_instruction_execution = entry.skip_instruction_execution;
+        if let (Some(trace), Some(len)) = (&mut self.trace, entry.trace_len) {
+            trace.truncate(len.saturating_sub(self.trace_flushed));
+        }
+        self.run_finished = false;
+        Ok(())
+    }
+
+    /// Returns the step during which a value was written to `address`, if it was written while
+    /// the journal was enabled.
+    pub fn find_write_step(&self, address: Relocatable) -> Option<usize> {
+        let steps = self.journal.as_ref()?;
+        let index = self
+            .segments
+            .memory
+            .journal
+            .as_ref()?
+            .iter()
+            .position(|entry| {
+                matches!(entry, MemoryJournalEntry::Cell { address: addr, previous, .. }
+                    if *addr == address && previous.is_none())
+            })?;
+        let step = steps.partition_point(|step| step.memory_journal_len <= index);
+        Some(steps.get(step.checked_sub(1)?)?.current_step)
+    }
+
     fn compute_op0_deductions(
         &self,
         op0_addr: Relocatable,
@@ -687,7 +885,7 @@ impl VirtualMachine {
     pub fn verify_auto_deductions(&self) -> Result<(), VirtualMachineError> {
         for builtin in self.builtin_runners.iter() {
             let index: usize = builtin.base();
-            for (offset, value) in self.segments.memory.data[index].iter().enumerate() {
+            for (offset, value) in self.segments.memory.data.written_cells(index) {
                 if let Some(deduced_memory_cell) = builtin
                     .deduce_memory_cell(
                         Relocatable::from((index as isize, offset)),
@@ -742,6 +940,15 @@ impl VirtualMachine {
         }
     }
 
+    /// Returns true once `end_run` has been called
+    pub fn is_run_finished(&self) -> bool {
+        self.run_finished
+    }
+
+    pub(crate) fn set_run_finished(&mut self, run_finished: bool) {
+        self.run_finished = run_finished;
+    }
+
     pub fn mark_address_range_as_accessed(
         &mut self,
         base: Relocatable,
@@ -864,7 +1071,11 @@ impl VirtualMachine {
         key: Relocatable,
         val: T,
     ) -> Result<(), MemoryError> {
-        self.segments.memory.insert_value(key, val)
+        let val = val.into();
+        self.segments.memory.insert(key, &val)?;
+        #[cfg(feature = "hooks")]
+        self.execute_memory_writes_from_api()?;
+        Ok(())
     }
 
     ///Writes data into the memory from address ptr and returns the first address after the data.
@@ -873,10 +1084,22 @@ impl VirtualMachine {
         ptr: Relocatable,
         data: &[MaybeRelocatable],
     ) -> Result<Relocatable, MemoryError> {
-        if ptr.segment_index == 0 {
-            self.instruction_cache.resize(data.len(), None);
+        if ptr.segment_index == 0 && self.instruction_cache.len() != data.len() {
+            Arc::make_mut(&mut self.instruction_cache).resize(data.len(), None);
         }
-        self.segments.load_data(ptr, data)
+        let end = self.segments.load_data(ptr, data)?;
+        #[cfg(feature = "hooks")]
+        self.execute_memory_writes_from_api()?;
+        Ok(end)
+    }
+
+    /// Replaces the instruction cache of the program segment with one decoded ahead of time.
+    /// The cache must have been built from the data loaded at the start of segment 0.
+    pub(crate) fn set_instruction_cache(
+        &mut self,
+        instruction_cache: Arc<Vec<Option<Instruction>>>,
+    ) {
+        self.instruction_cache = instruction_cache;
     }
 
     /// Writes args into the memory from address ptr and returns the first address after the data.
@@ -1165,7 +1388,7 @@ pub struct VirtualMachineBuilder {
     pub(crate) current_step: usize,
     skip_instruction_execution: bool,
     run_finished: bool,
-    #[cfg(feature = "test_utils")]
+    #[cfg(feature = "hooks")]
     pub(crate) hooks: crate::vm::hooks::Hooks,
 }
 
@@ -1185,7 +1408,7 @@ impl Default for VirtualMachineBuilder {
             skip_instruction_execution: false,
             segments: MemorySegmentManager::new(),
             run_finished: false,
-            #[cfg(feature = "test_utils")]
+            #[cfg(feature = "hooks")]
             hooks: Default::default(),
         }
     }
@@ -1230,27 +1453,36 @@ impl VirtualMachineBuilder {
         self
     }
 
-    #[cfg(feature = "test_utils")]
+    #[cfg(feature = "hooks")]
     pub fn hooks(mut self, hooks: crate::vm::hooks::Hooks) -> VirtualMachineBuilder {
         self.hooks = hooks;
         self
     }
 
     pub fn build(self) -> VirtualMachine {
-        VirtualMachine {
+        #[cfg(feature = "hooks")]
+        let hooks = self.hooks;
+        #[cfg_attr(not(feature = "hooks"), allow(unused_mut))]
+        let mut vm = VirtualMachine {
             run_context: self.run_context,
             builtin_runners: self.builtin_runners,
             trace: self.trace,
+            trace_sink: None,
+            trace_flushed: 0,
             current_step: self.current_step,
             skip_instruction_execution: self.skip_instruction_execution,
             segments: self.segments,
             rc_limits: None,
             run_finished: self.run_finished,
-            instruction_cache: Vec::new(),
-            #[cfg(feature = "test_utils")]
-            hooks: self.hooks,
+            instruction_cache: Arc::default(),
+            #[cfg(feature = "hooks")]
+            hooks: Default::default(),
             relocation_table: None,
-        }
+            journal: None,
+        };
+        #[cfg(feature = "hooks")]
+        vm.set_hooks(hooks);
+        vm
     }
 }
 
@@ -2646,7 +2878,7 @@ mod tests {
             vm.segments.add();
         }
 
-        vm.segments.memory.data.push(Vec::new());
+        vm.segments.memory.data.add_segment();
         let dst_addr = Relocatable::from((1, 0));
         let dst_addr_value = MaybeRelocatable::Int(Felt252::from(5));
         let op0_addr = Relocatable::from((1, 1));
@@ -2705,7 +2937,7 @@ mod tests {
         for _ in 0..2 {
             vm.segments.add();
         }
-        vm.segments.memory.data.push(Vec::new());
+        vm.segments.memory.data.add_segment();
         let dst_addr = relocatable!(1, 0);
         let dst_addr_value = mayberelocatable!(6);
         let op0_addr = relocatable!(1, 1);
@@ -3035,7 +3267,7 @@ mod tests {
 
         //Check that the following addresses have been accessed:
         // Addresses have been copied from python execution:
-        let mem = vm.segments.memory.data;
+        let mem = vm.segments.memory.data.cells();
         assert!(mem[1][0].is_accessed());
         assert!(mem[1][1].is_accessed());
     }
@@ -3133,7 +3365,7 @@ mod tests {
         );
         //Check that the following addresses have been accessed:
         // Addresses have been copied from python execution:
-        let mem = &vm.segments.memory.data;
+        let mem = vm.segments.memory.data.cells();
         assert!(mem[0][1].is_accessed());
         assert!(mem[0][4].is_accessed());
         assert!(mem[0][6].is_accessed());
@@ -4269,7 +4501,7 @@ mod tests {
         vm.mark_address_range_as_accessed((1, 1).into(), 1).unwrap();
         //Check that the following addresses have been accessed:
         // Addresses have been copied from python execution:
-        let mem = &vm.segments.memory.data;
+        let mem = vm.segments.memory.data.cells();
         assert!(mem[0][0].is_accessed());
         assert!(mem[0][1].is_accessed());
         assert!(mem[0][2].is_accessed());
@@ -4375,14 +4607,14 @@ mod tests {
                 fp: 1,
             }]));
 
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         fn before_first_step_hook(
             _vm: &mut VirtualMachine,
             _hint_data: &[Box<dyn Any>],
         ) -> Result<(), VirtualMachineError> {
             Err(VirtualMachineError::Unexpected)
         }
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         let virtual_machine_builder = virtual_machine_builder.hooks(crate::vm::hooks::Hooks::new(
             Some(std::sync::Arc::new(before_first_step_hook)),
             None,
@@ -4416,7 +4648,7 @@ mod tests {
                 fp: 1,
             }])
         );
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         {
             let program = crate::types::program::Program::from_bytes(
                 include_bytes!("../../../cairo_programs/sqrt.json"),
@@ -4485,7 +4717,7 @@ mod tests {
 
         //Check that the following addresses have been accessed:
         // Addresses have been copied from python execution:
-        let mem = vm.segments.memory.data;
+        let mem = vm.segments.memory.data.cells();
         assert!(mem[1][0].is_accessed());
         assert!(mem[1][1].is_accessed());
     }
@@ -4586,7 +4818,7 @@ mod tests {
         );
         //Check that the following addresses have been accessed:
         // Addresses have been copied from python execution:
-        let mem = &vm.segments.memory.data;
+        let mem = vm.segments.memory.data.cells();
         assert!(mem[4][1].is_accessed());
         assert!(mem[4][4].is_accessed());
         assert!(mem[4][6].is_accessed());
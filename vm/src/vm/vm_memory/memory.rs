@@ -1,7 +1,9 @@
 use crate::stdlib::{borrow::Cow, collections::HashMap, fmt, prelude::*};
 
 use crate::types::errors::math_errors::MathError;
+use crate::vm::journal::MemoryJournalEntry;
 use crate::vm::runners::cairo_pie::CairoPieMemory;
+use crate::vm::vm_memory::memory_backend::{MemoryBackend, MemoryStorage};
 use crate::Felt252;
 use crate::{
     types::relocatable::{MaybeRelocatable, Relocatable},
@@ -12,10 +14,15 @@ use bitvec::prelude as bv;
 use core::cmp::Ordering;
 use num_traits::ToPrimitive;
 
-pub struct ValidationRule(
-    #[allow(clippy::type_complexity)]
-    pub  Box<dyn Fn(&Memory, Relocatable) -> Result<Vec<Relocatable>, MemoryError>>,
-);
+#[cfg(not(feature = "thread_safe"))]
+type ValidationFn = dyn Fn(&Memory, Relocatable) -> Result<Vec<Relocatable>, MemoryError>;
+#[cfg(feature = "thread_safe")]
+type ValidationFn =
+    dyn Fn(&Memory, Relocatable) -> Result<Vec<Relocatable>, MemoryError> + Send + Sync;
+
+/// Validation rule of the cells of a segment.
+/// It has to be `Send + Sync` with the `thread_safe` feature.
+pub struct ValidationRule(pub Box<ValidationFn>);
 
 /// [`MemoryCell`] represents an optimized storage layout for the VM memory.
 /// It's specified to have both size an alignment of 32 bytes to optimize cache access.
@@ -38,7 +45,7 @@ pub struct ValidationRule(
 ///   and the 4th word storing the offset.
 #[derive(Copy, Clone, Eq, Ord, PartialEq, PartialOrd, Debug)]
 #[repr(align(32))]
-pub(crate) struct MemoryCell([u64; 4]);
+pub struct MemoryCell([u64; 4]);
 
 impl MemoryCell {
     pub const NONE_MASK: u64 = 1 << 63;
@@ -126,6 +133,19 @@ impl AddressSet {
             .unwrap_or(false)
     }
 
+    pub(crate) fn remove(&mut self, addr: &Relocatable) {
+        let Some(segment) = addr
+            .segment_index
+            .to_usize()
+            .and_then(|segment| self.0.get_mut(segment))
+        else {
+            return;
+        };
+        if addr.offset < segment.len() {
+            segment.replace(addr.offset, false);
+        }
+    }
+
     pub(crate) fn extend(&mut self, addresses: &[Relocatable]) {
         for addr in addresses {
             let segment = addr.segment_index;
@@ -156,24 +176,51 @@ impl AddressSet {
     }
 }
 
+// Evaluates `$body` with `$data` bound to the temporary or the real segments, depending on the
+// sign of `$segment_index`. The body is expanded for each of them so that the calls to the
+// storage are dispatched statically.
+macro_rules! with_storage {
+    ($segment_index:expr, $data:ident = $temp:expr, $real:expr => $body:expr) => {
+        if $segment_index.is_negative() {
+            let $data = $temp;
+            $body
+        } else {
+            let $data = $real;
+            $body
+        }
+    };
+}
+
 pub struct Memory {
-    pub(crate) data: Vec<Vec<MemoryCell>>,
+    pub(crate) data: MemoryStorage,
     pub(crate) temp_data: Vec<Vec<MemoryCell>>,
     // relocation_rules's keys map to temp_data's indices and therefore begin at
     // zero; that is, segment_index = -1 maps to key 0, -2 to key 1...
     pub(crate) relocation_rules: HashMap<usize, Relocatable>,
     pub validated_addresses: AddressSet,
     validation_rules: Vec<Option<ValidationRule>>,
+    pub(crate) journal: Option<Vec<MemoryJournalEntry>>,
+    // Cells written since the memory_write hook of the VM last ran, recorded while it is set
+    #[cfg(feature = "hooks")]
+    pub(crate) hook_writes: Option<Vec<(Relocatable, MaybeRelocatable)>>,
 }
 
 impl Memory {
     pub fn new() -> Memory {
+        Memory::with_backend(MemoryStorage::default())
+    }
+
+    /// Creates an empty memory which stores its segments in the given backend
+    pub fn with_backend(backend: impl Into<MemoryStorage>) -> Memory {
         Memory {
-            data: Vec::new(),
+            data: backend.into(),
             temp_data: Vec::new(),
             relocation_rules: HashMap::new(),
             validated_addresses: AddressSet::new(),
             validation_rules: Vec::with_capacity(7),
+            journal: None,
+            #[cfg(feature = "hooks")]
+            hook_writes: None,
         }
     }
 
@@ -188,44 +235,44 @@ impl Memory {
         let val = MaybeRelocatable::from(val);
         let (value_index, value_offset) = from_relocatable_to_indexes(key);
 
-        let data = if key.segment_index.is_negative() {
-            &mut self.temp_data
-        } else {
-            &mut self.data
-        };
-
-        let data_len = data.len();
-        let segment = data
-            .get_mut(value_index)
-            .ok_or_else(|| MemoryError::UnallocatedSegment(Box::new((value_index, data_len))))?;
-
-        //Check if the element is inserted next to the last one on the segment
-        //Forgoing this check would allow data to be inserted in a different index
-        let (len, capacity) = (segment.len(), segment.capacity());
-        if len <= value_offset {
-            let new_len = value_offset
-                .checked_add(1)
-                .ok_or(MemoryError::VecCapacityExceeded)?;
-            segment
-                .try_reserve(new_len.saturating_sub(capacity))
-                .map_err(|_| MemoryError::VecCapacityExceeded)?;
-            segment.resize(new_len, MemoryCell::NONE);
-        }
-        // At this point there's *something* in there
-
-        match segment[value_offset].get_value() {
-            None => segment[value_offset] = MemoryCell::new(val),
-            Some(current_cell) => {
-                if current_cell != val {
-                    //Existing memory cannot be changed
-                    return Err(MemoryError::InconsistentMemory(Box::new((
-                        key,
-                        current_cell,
-                        val,
-                    ))));
+        with_storage!(key.segment_index, data = &mut self.temp_data, &mut self.data => {
+            let num_segments = data.num_segments();
+            let segment_len = data.segment_len(value_index).ok_or_else(|| {
+                MemoryError::UnallocatedSegment(Box::new((value_index, num_segments)))
+            })?;
+            let previous = data
+                .get(value_index, value_offset)
+                .unwrap_or(MemoryCell::NONE);
+
+            match previous.get_value() {
+                None => {
+                    #[cfg(feature = "hooks")]
+                    let hook_write = self.hook_writes.is_some().then(|| val.clone());
+                    data.set(value_index, value_offset, MemoryCell::new(val))?;
+                    #[cfg(feature = "hooks")]
+                    if let (Some(hook_writes), Some(value)) = (&mut self.hook_writes, hook_write) {
+                        hook_writes.push((key, value));
+                    }
+                    if let Some(journal) = &mut self.journal {
+                        journal.push(MemoryJournalEntry::Cell {
+                            address: key,
+                            previous,
+                            segment_len,
+                        });
+                    }
                 }
-            }
-        };
+                Some(current_cell) => {
+                    if current_cell != val {
+                        //Existing memory cannot be changed
+                        return Err(MemoryError::InconsistentMemory(Box::new((
+                            key,
+                            current_cell,
+                            val,
+                        ))));
+                    }
+                }
+            };
+        });
         self.validate_memory_cell(key)
     }
 
@@ -236,13 +283,13 @@ impl Memory {
     {
         let relocatable: Relocatable = key.try_into().ok()?;
 
-        let data = if relocatable.segment_index.is_negative() {
-            &self.temp_data
-        } else {
-            &self.data
-        };
         let (i, j) = from_relocatable_to_indexes(relocatable);
-        let value = data.get(i)?.get(j)?.get_value()?;
+        let cell = if relocatable.segment_index.is_negative() {
+            self.temp_data.get(i, j)
+        } else {
+            self.data.get(i, j)
+        }?;
+        let value = cell.get_value()?;
         Some(Cow::Owned(self.relocate_value(&value).ok()?.into_owned()))
     }
 
@@ -267,34 +314,40 @@ impl Memory {
             return Ok(());
         }
         // Relocate temporary addresses in memory
-        for segment in self.data.iter_mut().chain(self.temp_data.iter_mut()) {
-            for cell in segment.iter_mut() {
-                let value = cell.get_value();
-                match value {
-                    Some(MaybeRelocatable::RelocatableValue(addr)) if addr.segment_index < 0 => {
-                        let mut new_cell = MemoryCell::new(Memory::relocate_address(
-                            addr,
-                            &self.relocation_rules,
-                        )?);
-                        if cell.is_accessed() {
-                            new_cell.mark_accessed();
+        for data in [
+            &mut self.data as &mut dyn MemoryBackend,
+            &mut self.temp_data,
+        ] {
+            for segment in 0..data.num_segments() {
+                let temporary_addresses: Vec<_> = data
+                    .written_cells(segment)
+                    .filter_map(|(offset, cell)| match cell.get_value() {
+                        Some(MaybeRelocatable::RelocatableValue(addr))
+                            if addr.segment_index < 0 =>
+                        {
+                            Some((offset, cell, addr))
                         }
-                        *cell = new_cell;
+                        _ => None,
+                    })
+                    .collect();
+                for (offset, cell, addr) in temporary_addresses {
+                    let mut new_cell =
+                        MemoryCell::new(Memory::relocate_address(addr, &self.relocation_rules)?);
+                    if cell.is_accessed() {
+                        new_cell.mark_accessed();
                     }
-                    _ => {}
+                    data.set(segment, offset, new_cell)?;
                 }
             }
         }
         // Move relocated temporary memory into the real memory
         for index in (0..self.temp_data.len()).rev() {
-            if let Some(base_addr) = self.relocation_rules.get(&index) {
-                let data_segment = self.temp_data.remove(index);
+            if let Some(base_addr) = self.relocation_rules.get(&index).copied() {
+                let data_segment: Vec<_> = self.temp_data.written_cells(index).collect();
+                self.temp_data.remove(index);
                 // Insert the to-be relocated segment into the real memory
-                let mut addr = *base_addr;
-                if let Some(s) = self.data.get_mut(addr.segment_index as usize) {
-                    s.reserve_exact(data_segment.len())
-                }
-                for cell in data_segment {
+                for (offset, cell) in data_segment {
+                    let addr = (base_addr + offset)?;
                     if let Some(v) = cell.get_value() {
                         // Rely on Memory::insert to catch memory inconsistencies
                         self.insert(addr, v)?;
@@ -303,7 +356,6 @@ impl Memory {
                             self.mark_as_accessed(addr)
                         }
                     }
-                    addr = (addr + 1)?;
                 }
             }
         }
@@ -339,6 +391,9 @@ impl Memory {
         }
 
         self.relocation_rules.insert(segment_index, dst_ptr);
+        if let Some(journal) = &mut self.journal {
+            journal.push(MemoryJournalEntry::RelocationRule(segment_index));
+        }
         Ok(())
     }
 
@@ -402,8 +457,16 @@ impl Memory {
             .and_then(|x| self.validation_rules.get(x))
         {
             if !self.validated_addresses.contains(&addr) {
-                self.validated_addresses
-                    .extend(rule.0(self, addr)?.as_slice());
+                let addresses = rule.0(self, addr)?;
+                if let Some(journal) = &mut self.journal {
+                    journal.extend(
+                        addresses
+                            .iter()
+                            .filter(|addr| !self.validated_addresses.contains(addr))
+                            .map(|addr| MemoryJournalEntry::Validated(*addr)),
+                    );
+                }
+                self.validated_addresses.extend(addresses.as_slice());
             }
         }
         Ok(())
@@ -412,13 +475,13 @@ impl Memory {
     ///Applies validation_rules to the current memory
     pub fn validate_existing_memory(&mut self) -> Result<(), MemoryError> {
         for (index, rule) in self.validation_rules.iter().enumerate() {
-            if index >= self.data.len() {
+            let Some(segment_len) = self.data.segment_len(index) else {
                 continue;
-            }
+            };
             let Some(rule) = rule else {
                 continue;
             };
-            for offset in 0..self.data[index].len() {
+            for offset in 0..segment_len {
                 let addr = Relocatable::from((index as isize, offset));
                 if !self.validated_addresses.contains(&addr) {
                     self.validated_addresses
@@ -443,16 +506,9 @@ impl Memory {
         rhs: Relocatable,
         len: usize,
     ) -> (Ordering, usize) {
-        let get_segment = |idx: isize| {
-            if idx.is_negative() {
-                self.temp_data.get(-(idx + 1) as usize)
-            } else {
-                self.data.get(idx as usize)
-            }
-        };
         match (
-            get_segment(lhs.segment_index),
-            get_segment(rhs.segment_index),
+            self.get_segment(lhs.segment_index),
+            self.get_segment(rhs.segment_index),
         ) {
             (None, None) => {
                 return (Ordering::Equal, 0);
@@ -463,12 +519,12 @@ impl Memory {
             (None, Some(_)) => {
                 return (Ordering::Less, 0);
             }
-            (Some(lhs_segment), Some(rhs_segment)) => {
+            (Some((lhs_data, lhs_segment)), Some((rhs_data, rhs_segment))) => {
                 let (lhs_start, rhs_start) = (lhs.offset, rhs.offset);
                 for i in 0..len {
                     let (lhs, rhs) = (
-                        lhs_segment.get(lhs_start + i),
-                        rhs_segment.get(rhs_start + i),
+                        lhs_data.get(lhs_segment, lhs_start + i),
+                        rhs_data.get(rhs_segment, rhs_start + i),
                     );
                     let ord = lhs.cmp(&rhs);
                     if ord == Ordering::Equal {
@@ -493,29 +549,38 @@ impl Memory {
         if lhs == rhs {
             return true;
         }
-        let get_segment = |idx: isize| {
-            if idx.is_negative() {
-                self.temp_data.get(-(idx + 1) as usize)
-            } else {
-                self.data.get(idx as usize)
-            }
+        // Returns the segment's backend and index, and the amount of cells after the offset
+        let get_range = |addr: Relocatable| {
+            let (data, segment) = self.get_segment(addr.segment_index)?;
+            let remaining = data.segment_len(segment)?.checked_sub(addr.offset)?;
+            Some((data, segment, remaining))
         };
-        match (
-            get_segment(lhs.segment_index).and_then(|s| s.get(lhs.offset..)),
-            get_segment(rhs.segment_index).and_then(|s| s.get(rhs.offset..)),
-        ) {
-            (Some(lhs), Some(rhs)) => {
-                let (lhs_len, rhs_len) = (lhs.len().min(len), rhs.len().min(len));
+        match (get_range(lhs), get_range(rhs)) {
+            (Some((lhs_data, lhs_segment, lhs_len)), Some((rhs_data, rhs_segment, rhs_len))) => {
+                let (lhs_len, rhs_len) = (lhs_len.min(len), rhs_len.min(len));
                 if lhs_len != rhs_len {
                     return false;
                 }
-                lhs[..lhs_len] == rhs[..rhs_len]
+                (0..lhs_len).all(|i| {
+                    lhs_data.get(lhs_segment, lhs.offset + i)
+                        == rhs_data.get(rhs_segment, rhs.offset + i)
+                })
             }
             (None, None) => true,
             _ => false,
         }
     }
 
+    // Returns the backend holding a segment and the segment's index within it, if it exists
+    fn get_segment(&self, segment_index: isize) -> Option<(&dyn MemoryBackend, usize)> {
+        let (data, index): (&dyn MemoryBackend, usize) = if segment_index.is_negative() {
+            (&self.temp_data, -(segment_index + 1) as usize)
+        } else {
+            (&self.data, segment_index as usize)
+        };
+        data.segment_len(index).map(|_| (data, index))
+    }
+
     /// Gets a range of memory values from addr to addr + size
     /// The outputed range may contain gaps if the original memory has them
     pub fn get_range(&self, addr: Relocatable, size: usize) -> Vec<Option<Cow<MaybeRelocatable>>> {
@@ -566,14 +631,68 @@ impl Memory {
 
     pub fn mark_as_accessed(&mut self, addr: Relocatable) {
         let (i, j) = from_relocatable_to_indexes(addr);
-        let data = if addr.segment_index < 0 {
-            &mut self.temp_data
-        } else {
-            &mut self.data
+        with_storage!(addr.segment_index, data = &mut self.temp_data, &mut self.data => {
+            let Some(cell) = data.get(i, j) else {
+                return;
+            };
+            if cell.is_accessed() {
+                return;
+            }
+            if let Some(journal) = &mut self.journal {
+                journal.push(MemoryJournalEntry::Cell {
+                    address: addr,
+                    previous: cell,
+                    segment_len: data.segment_len(i).unwrap_or_default(),
+                });
+            }
+            data.mark_accessed(i, j)
+        })
+    }
+
+    /// Undoes the changes recorded in the journal after its first `len` entries.
+    pub(crate) fn revert_journal(&mut self, len: usize) -> Result<(), MemoryError> {
+        let Some(journal) = &mut self.journal else {
+            return Ok(());
         };
-        let cell = data.get_mut(i).and_then(|x| x.get_mut(j));
-        if let Some(cell) = cell {
-            cell.mark_accessed()
+        for entry in journal.drain(len..).rev() {
+            match entry {
+                MemoryJournalEntry::Cell {
+                    address,
+                    previous,
+                    segment_len,
+                } => {
+                    let (i, j) = from_relocatable_to_indexes(address);
+                    let data: &mut dyn MemoryBackend = if address.segment_index < 0 {
+                        &mut self.temp_data
+                    } else {
+                        &mut self.data
+                    };
+                    if data.segment_len(i).is_some() {
+                        data.set(i, j, previous)?;
+                        data.truncate_segment(i, segment_len);
+                    }
+                }
+                MemoryJournalEntry::Validated(address) => self.validated_addresses.remove(&address),
+                MemoryJournalEntry::RelocationRule(key) => {
+                    self.relocation_rules.remove(&key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes every segment, along with its cells and relocation rules, keeping the validation rules.
+    pub(crate) fn clear(&mut self) {
+        self.data.truncate_segments(0);
+        self.temp_data.clear();
+        self.relocation_rules.clear();
+        self.validated_addresses = AddressSet::new();
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+        #[cfg(feature = "hooks")]
+        if let Some(hook_writes) = &mut self.hook_writes {
+            hook_writes.clear();
         }
     }
 
@@ -581,13 +700,7 @@ impl Memory {
         &self,
         segment_index: usize,
     ) -> Option<usize> {
-        let segment = self.data.get(segment_index)?;
-        Some(
-            segment
-                .iter()
-                .filter(|x| x.is_some() && x.is_accessed())
-                .count(),
-        )
+        self.data.count_accessed(segment_index)
     }
 
     // Inserts a value into memory & inmediately marks it as accessed if insertion was succesful
@@ -609,8 +722,8 @@ impl Memory {
 impl From<&Memory> for CairoPieMemory {
     fn from(mem: &Memory) -> CairoPieMemory {
         let mut pie_memory = Vec::default();
-        for (i, segment) in mem.data.iter().enumerate() {
-            for (j, cell) in segment.iter().enumerate() {
+        for i in 0..mem.data.num_segments() {
+            for (j, cell) in mem.data.written_cells(i) {
                 if let Some(value) = cell.get_value() {
                     pie_memory.push(((i, j), value))
                 }
@@ -622,16 +735,16 @@ impl From<&Memory> for CairoPieMemory {
 
 impl fmt::Display for Memory {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (i, segment) in self.temp_data.iter().enumerate() {
-            for (j, cell) in segment.iter().enumerate() {
+        for i in 0..self.temp_data.len() {
+            for (j, cell) in self.temp_data.written_cells(i) {
                 if let Some(elem) = cell.get_value() {
                     let temp_segment = i + 1;
                     writeln!(f, "(-{temp_segment},{j}) : {elem}")?;
                 }
             }
         }
-        for (i, segment) in self.data.iter().enumerate() {
-            for (j, cell) in segment.iter().enumerate() {
+        for i in 0..self.data.num_segments() {
+            for (j, cell) in self.data.written_cells(i) {
                 if let Some(elem) = cell.get_value() {
                     writeln!(f, "({i},{j}) : {elem}")?;
                 }
@@ -718,7 +831,7 @@ mod memory_tests {
         let key = Relocatable::from((0, 0));
         let val = MaybeRelocatable::from(Felt252::from(5_u64));
         let mut memory = Memory::new();
-        memory.data.push(Vec::new());
+        memory.data.add_segment();
         memory.insert(key, &val).unwrap();
         assert_eq!(
             memory.get(&key).unwrap().as_ref(),
@@ -824,7 +937,7 @@ mod memory_tests {
         let val_a = MaybeRelocatable::from(Felt252::from(5_u64));
         let val_b = MaybeRelocatable::from(Felt252::from(6_u64));
         let mut memory = Memory::new();
-        memory.data.push(Vec::new());
+        memory.data.add_segment();
         memory
             .insert(key, &val_a)
             .expect("Unexpected memory insert fail");
@@ -844,7 +957,7 @@ mod memory_tests {
         let key_b = Relocatable::from((0, 2));
         let val = MaybeRelocatable::from(Felt252::from(5_u64));
         let mut memory = Memory::new();
-        memory.data.push(Vec::new());
+        memory.data.add_segment();
         memory.insert(key_a, &val).unwrap();
         memory.insert(key_b, &val).unwrap();
         assert_eq!(memory.get(&key_b).unwrap().as_ref(), &val);
@@ -857,7 +970,7 @@ mod memory_tests {
         let key_b = Relocatable::from((0, 5));
         let val = MaybeRelocatable::from(Felt252::from(5_u64));
         let mut memory = Memory::new();
-        memory.data.push(Vec::new());
+        memory.data.add_segment();
         memory.insert(key_a, &val).unwrap();
         memory.insert(key_b, &val).unwrap();
         assert_eq!(memory.get(&key_b).unwrap().as_ref(), &val);
@@ -1053,7 +1166,7 @@ mod memory_tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn default_memory() {
         let mem: Memory = Default::default();
-        assert_eq!(mem.data.len(), 0);
+        assert_eq!(mem.data.num_segments(), 0);
     }
 
     #[test]
@@ -1291,7 +1404,7 @@ mod memory_tests {
         memory
             .add_relocation_rule((-1, 0).into(), (2, 1).into())
             .unwrap();
-        memory.data.push(vec![]);
+        memory.data.add_segment();
 
         assert_eq!(memory.relocate_memory(), Ok(()));
         check_memory!(
@@ -1326,7 +1439,7 @@ mod memory_tests {
         memory
             .add_relocation_rule((-1, 0).into(), (2, 0).into())
             .unwrap();
-        memory.data.push(vec![]);
+        memory.data.add_segment();
 
         assert_eq!(memory.relocate_memory(), Ok(()));
 
@@ -1451,7 +1564,7 @@ mod memory_tests {
         memory
             .add_relocation_rule((-1, 0).into(), (2, 0).into())
             .unwrap();
-        memory.data.push(vec![]);
+        memory.data.add_segment();
 
         assert_eq!(memory.relocate_memory(), Ok(()));
         check_memory!(
@@ -1486,11 +1599,11 @@ mod memory_tests {
             ((-2, 0), 10),
             ((-2, 1), 11)
         ];
-        memory.data.push(vec![]);
+        memory.data.add_segment();
         memory
             .add_relocation_rule((-1, 0).into(), (2, 0).into())
             .unwrap();
-        memory.data.push(vec![]);
+        memory.data.add_segment();
         memory
             .add_relocation_rule((-2, 0).into(), (3, 0).into())
             .unwrap();
@@ -1620,9 +1733,9 @@ mod memory_tests {
     #[test]
     fn mark_address_as_accessed() {
         let mut memory = memory![((0, 0), 0)];
-        assert!(!memory.data[0][0].is_accessed());
+        assert!(!memory.data.cells()[0][0].is_accessed());
         memory.mark_as_accessed(relocatable!(0, 0));
-        assert!(memory.data[0][0].is_accessed());
+        assert!(memory.data.cells()[0][0].is_accessed());
     }
 
     #[test]
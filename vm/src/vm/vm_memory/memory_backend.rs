@@ -0,0 +1,932 @@
+//! Storage of the memory segments
+//!
+//! [Memory](super::memory::Memory) stores the cells of its real segments in a [MemoryStorage],
+//! which can be replaced with
+//! [MemorySegmentManager::set_memory_backend](super::memory_segments::MemorySegmentManager::set_memory_backend)
+//! before initializing a runner. The following backends are available:
+//! - [VecMemoryBackend]: the default one, stores each segment in a contiguous vector
+//! - [PagedMemoryBackend]: allocates segments in fixed size pages, for programs with huge segments
+//!   that are only written at scattered offsets
+//! - [SharedMemoryBackend]: shares read-only copies of the first segments (usually the program
+//!   segment) between many runners, copying them only if they are written
+//!
+//! Other backends can be plugged in by implementing [MemoryBackend] and boxing them into
+//! [MemoryStorage::Custom].
+//!
+//! Temporary segments are always stored in vectors, as they are relocated into the real segments
+//! at the end of the run.
+
+use crate::stdlib::{borrow::Cow, collections::HashMap, fmt, prelude::*, sync::Arc};
+
+use bitvec::prelude as bv;
+
+use crate::{
+    types::{program::Program, shared::MaybeSendSync},
+    vm::{errors::memory_errors::MemoryError, vm_memory::memory::MemoryCell},
+};
+
+/// Storage for a list of segments of [MemoryCell]s, indexed from zero.
+/// The length of a segment is one past its highest written offset, lower offsets that were never
+/// written hold [MemoryCell::NONE].
+/// It has to be `Send + Sync` with the `thread_safe` feature.
+pub trait MemoryBackend: MaybeSendSync + fmt::Debug {
+    /// Returns the amount of segments.
+    fn num_segments(&self) -> usize;
+
+    /// Adds an empty segment after the last one.
+    fn add_segment(&mut self);
+
+    /// Removes every segment after the first `len` ones.
+    fn truncate_segments(&mut self, len: usize);
+
+    /// Returns the length of a segment, or None if it doesn't exist.
+    fn segment_len(&self, segment: usize) -> Option<usize>;
+
+    /// Returns the cell at the given offset of a segment, or None if it is out of bounds.
+    fn get(&self, segment: usize, offset: usize) -> Option<MemoryCell>;
+
+    /// Overwrites the cell at the given offset of an existing segment, growing it with empty
+    /// cells if needed.
+    fn set(&mut self, segment: usize, offset: usize, cell: MemoryCell) -> Result<(), MemoryError>;
+
+    /// Marks the cell at the given offset as accessed, if it is within bounds.
+    fn mark_accessed(&mut self, segment: usize, offset: usize);
+
+    /// Shortens a segment to `len` cells.
+    fn truncate_segment(&mut self, segment: usize, len: usize);
+
+    /// Returns every cell of a segment, or None if it doesn't exist.
+    fn segment_cells(&self, segment: usize) -> Option<Cow<[MemoryCell]>>;
+
+    /// Counts the cells of a segment which hold a value and were accessed.
+    fn count_accessed(&self, segment: usize) -> Option<usize> {
+        Some(
+            self.segment_cells(segment)?
+                .iter()
+                .filter(|cell| cell.is_some() && cell.is_accessed())
+                .count(),
+        )
+    }
+
+    /// Returns the offsets and cells of a segment which hold a value, in increasing order of
+    /// offset, or nothing if the segment doesn't exist.
+    fn written_cells(&self, segment: usize) -> Box<dyn Iterator<Item = (usize, MemoryCell)> + '_> {
+        match self.segment_cells(segment) {
+            Some(Cow::Borrowed(cells)) => Box::new(
+                cells
+                    .iter()
+                    .copied()
+                    .enumerate()
+                    .filter(|(_, cell)| cell.is_some()),
+            ),
+            Some(Cow::Owned(cells)) => Box::new(
+                cells
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, cell)| cell.is_some()),
+            ),
+            None => Box::new(core::iter::empty()),
+        }
+    }
+}
+
+/// Backend which stores each segment in a contiguous vector, the default one.
+#[derive(Clone, Debug, Default)]
+pub struct VecMemoryBackend(pub(crate) Vec<Vec<MemoryCell>>);
+
+/// Storage of the real segments of a [Memory](super::memory::Memory), one of the backends of
+/// this module or a custom one. Calls to the built-in backends are dispatched statically.
+#[derive(Debug)]
+pub enum MemoryStorage {
+    Vec(VecMemoryBackend),
+    Paged(PagedMemoryBackend),
+    Shared(SharedMemoryBackend),
+    Custom(Box<dyn MemoryBackend>),
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        MemoryStorage::Vec(VecMemoryBackend::default())
+    }
+}
+
+impl From<Vec<Vec<MemoryCell>>> for MemoryStorage {
+    fn from(segments: Vec<Vec<MemoryCell>>) -> Self {
+        MemoryStorage::Vec(VecMemoryBackend(segments))
+    }
+}
+
+impl From<VecMemoryBackend> for MemoryStorage {
+    fn from(backend: VecMemoryBackend) -> Self {
+        MemoryStorage::Vec(backend)
+    }
+}
+
+impl From<PagedMemoryBackend> for MemoryStorage {
+    fn from(backend: PagedMemoryBackend) -> Self {
+        MemoryStorage::Paged(backend)
+    }
+}
+
+impl From<SharedMemoryBackend> for MemoryStorage {
+    fn from(backend: SharedMemoryBackend) -> Self {
+        MemoryStorage::Shared(backend)
+    }
+}
+
+impl From<Box<dyn MemoryBackend>> for MemoryStorage {
+    fn from(backend: Box<dyn MemoryBackend>) -> Self {
+        MemoryStorage::Custom(backend)
+    }
+}
+
+macro_rules! dispatch {
+    ($storage:expr, $backend:ident => $call:expr) => {
+        match $storage {
+            MemoryStorage::Vec(VecMemoryBackend($backend)) => $call,
+            MemoryStorage::Paged($backend) => $call,
+            MemoryStorage::Shared($backend) => $call,
+            MemoryStorage::Custom($backend) => $call,
+        }
+    };
+}
+
+// Forwards to the stored backend, so that callers don't need the trait in scope
+impl MemoryStorage {
+    #[inline]
+    pub fn num_segments(&self) -> usize {
+        dispatch!(self, backend => backend.num_segments())
+    }
+
+    #[inline]
+    pub fn add_segment(&mut self) {
+        dispatch!(self, backend => backend.add_segment())
+    }
+
+    #[inline]
+    pub fn truncate_segments(&mut self, len: usize) {
+        dispatch!(self, backend => backend.truncate_segments(len))
+    }
+
+    #[inline]
+    pub fn segment_len(&self, segment: usize) -> Option<usize> {
+        dispatch!(self, backend => backend.segment_len(segment))
+    }
+
+    #[inline]
+    pub fn get(&self, segment: usize, offset: usize) -> Option<MemoryCell> {
+        dispatch!(self, backend => backend.get(segment, offset))
+    }
+
+    #[inline]
+    pub fn set(
+        &mut self,
+        segment: usize,
+        offset: usize,
+        cell: MemoryCell,
+    ) -> Result<(), MemoryError> {
+        dispatch!(self, backend => backend.set(segment, offset, cell))
+    }
+
+    #[inline]
+    pub fn mark_accessed(&mut self, segment: usize, offset: usize) {
+        dispatch!(self, backend => backend.mark_accessed(segment, offset))
+    }
+
+    #[inline]
+    pub fn truncate_segment(&mut self, segment: usize, len: usize) {
+        dispatch!(self, backend => backend.truncate_segment(segment, len))
+    }
+
+    #[inline]
+    pub fn segment_cells(&self, segment: usize) -> Option<Cow<[MemoryCell]>> {
+        dispatch!(self, backend => backend.segment_cells(segment))
+    }
+
+    #[inline]
+    pub fn count_accessed(&self, segment: usize) -> Option<usize> {
+        dispatch!(self, backend => backend.count_accessed(segment))
+    }
+
+    #[inline]
+    pub fn written_cells(
+        &self,
+        segment: usize,
+    ) -> Box<dyn Iterator<Item = (usize, MemoryCell)> + '_> {
+        dispatch!(self, backend => backend.written_cells(segment))
+    }
+}
+
+impl MemoryBackend for MemoryStorage {
+    #[inline]
+    fn num_segments(&self) -> usize {
+        MemoryStorage::num_segments(self)
+    }
+
+    #[inline]
+    fn add_segment(&mut self) {
+        MemoryStorage::add_segment(self)
+    }
+
+    #[inline]
+    fn truncate_segments(&mut self, len: usize) {
+        MemoryStorage::truncate_segments(self, len)
+    }
+
+    #[inline]
+    fn segment_len(&self, segment: usize) -> Option<usize> {
+        MemoryStorage::segment_len(self, segment)
+    }
+
+    #[inline]
+    fn get(&self, segment: usize, offset: usize) -> Option<MemoryCell> {
+        MemoryStorage::get(self, segment, offset)
+    }
+
+    #[inline]
+    fn set(&mut self, segment: usize, offset: usize, cell: MemoryCell) -> Result<(), MemoryError> {
+        MemoryStorage::set(self, segment, offset, cell)
+    }
+
+    #[inline]
+    fn mark_accessed(&mut self, segment: usize, offset: usize) {
+        MemoryStorage::mark_accessed(self, segment, offset)
+    }
+
+    #[inline]
+    fn truncate_segment(&mut self, segment: usize, len: usize) {
+        MemoryStorage::truncate_segment(self, segment, len)
+    }
+
+    #[inline]
+    fn segment_cells(&self, segment: usize) -> Option<Cow<[MemoryCell]>> {
+        MemoryStorage::segment_cells(self, segment)
+    }
+
+    #[inline]
+    fn count_accessed(&self, segment: usize) -> Option<usize> {
+        MemoryStorage::count_accessed(self, segment)
+    }
+
+    #[inline]
+    fn written_cells(&self, segment: usize) -> Box<dyn Iterator<Item = (usize, MemoryCell)> + '_> {
+        MemoryStorage::written_cells(self, segment)
+    }
+}
+
+impl MemoryBackend for Vec<Vec<MemoryCell>> {
+    #[inline]
+    fn num_segments(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn add_segment(&mut self) {
+        self.push(Vec::new());
+    }
+
+    #[inline]
+    fn truncate_segments(&mut self, len: usize) {
+        self.truncate(len);
+    }
+
+    #[inline]
+    fn segment_len(&self, segment: usize) -> Option<usize> {
+        self.as_slice().get(segment).map(Vec::len)
+    }
+
+    #[inline]
+    fn get(&self, segment: usize, offset: usize) -> Option<MemoryCell> {
+        self.as_slice().get(segment)?.get(offset).copied()
+    }
+
+    #[inline]
+    fn set(&mut self, segment: usize, offset: usize, cell: MemoryCell) -> Result<(), MemoryError> {
+        let num_segments = self.len();
+        let segment = self
+            .get_mut(segment)
+            .ok_or_else(|| MemoryError::UnallocatedSegment(Box::new((segment, num_segments))))?;
+        // Check if the element is inserted next to the last one on the segment
+        // Forgoing this check would allow data to be inserted in a different index
+        let (len, capacity) = (segment.len(), segment.capacity());
+        if len <= offset {
+            let new_len = offset
+                .checked_add(1)
+                .ok_or(MemoryError::VecCapacityExceeded)?;
+            segment
+                .try_reserve(new_len.saturating_sub(capacity))
+                .map_err(|_| MemoryError::VecCapacityExceeded)?;
+            segment.resize(new_len, MemoryCell::NONE);
+        }
+        segment[offset] = cell;
+        Ok(())
+    }
+
+    #[inline]
+    fn mark_accessed(&mut self, segment: usize, offset: usize) {
+        if let Some(cell) = self.get_mut(segment).and_then(|s| s.get_mut(offset)) {
+            cell.mark_accessed()
+        }
+    }
+
+    #[inline]
+    fn truncate_segment(&mut self, segment: usize, len: usize) {
+        if let Some(segment) = self.get_mut(segment) {
+            segment.truncate(len)
+        }
+    }
+
+    #[inline]
+    fn segment_cells(&self, segment: usize) -> Option<Cow<[MemoryCell]>> {
+        self.as_slice()
+            .get(segment)
+            .map(|segment| Cow::Borrowed(segment.as_slice()))
+    }
+}
+
+/// Amount of cells in each page of a [PagedMemoryBackend]
+pub const PAGE_SIZE: usize = 1 << 10;
+
+#[derive(Clone, Debug, Default)]
+struct PagedSegment {
+    len: usize,
+    pages: HashMap<usize, Box<[MemoryCell]>>,
+}
+
+/// Backend which allocates the cells of each segment in pages of [PAGE_SIZE] cells the first
+/// time one of them is written, so that segments with huge gaps between their values don't
+/// allocate memory for the gaps.
+#[derive(Clone, Debug, Default)]
+pub struct PagedMemoryBackend {
+    segments: Vec<PagedSegment>,
+}
+
+impl PagedMemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the amount of allocated pages, across all segments.
+    pub fn allocated_pages(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| segment.pages.len())
+            .sum()
+    }
+}
+
+impl MemoryBackend for PagedMemoryBackend {
+    fn num_segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    fn add_segment(&mut self) {
+        self.segments.push(PagedSegment::default());
+    }
+
+    fn truncate_segments(&mut self, len: usize) {
+        self.segments.truncate(len);
+    }
+
+    fn segment_len(&self, segment: usize) -> Option<usize> {
+        self.segments.get(segment).map(|segment| segment.len)
+    }
+
+    fn get(&self, segment: usize, offset: usize) -> Option<MemoryCell> {
+        let segment = self.segments.get(segment)?;
+        if offset >= segment.len {
+            return None;
+        }
+        Some(
+            segment
+                .pages
+                .get(&(offset / PAGE_SIZE))
+                .map_or(MemoryCell::NONE, |page| page[offset % PAGE_SIZE]),
+        )
+    }
+
+    fn set(&mut self, segment: usize, offset: usize, cell: MemoryCell) -> Result<(), MemoryError> {
+        let num_segments = self.segments.len();
+        let segment = self
+            .segments
+            .get_mut(segment)
+            .ok_or_else(|| MemoryError::UnallocatedSegment(Box::new((segment, num_segments))))?;
+        let len = offset
+            .checked_add(1)
+            .ok_or(MemoryError::VecCapacityExceeded)?;
+        segment.len = segment.len.max(len);
+        segment
+            .pages
+            .entry(offset / PAGE_SIZE)
+            .or_insert_with(|| vec![MemoryCell::NONE; PAGE_SIZE].into_boxed_slice())
+            [offset % PAGE_SIZE] = cell;
+        Ok(())
+    }
+
+    fn mark_accessed(&mut self, segment: usize, offset: usize) {
+        let Some(segment) = self.segments.get_mut(segment) else {
+            return;
+        };
+        if offset < segment.len {
+            if let Some(page) = segment.pages.get_mut(&(offset / PAGE_SIZE)) {
+                page[offset % PAGE_SIZE].mark_accessed()
+            }
+        }
+    }
+
+    fn truncate_segment(&mut self, segment: usize, len: usize) {
+        let Some(segment) = self.segments.get_mut(segment) else {
+            return;
+        };
+        if len >= segment.len {
+            return;
+        }
+        segment.len = len;
+        segment.pages.retain(|index, _| index * PAGE_SIZE < len);
+        // Clear the cells after the new end in the last page
+        if let Some(page) = segment.pages.get_mut(&(len / PAGE_SIZE)) {
+            page[len % PAGE_SIZE..].fill(MemoryCell::NONE);
+        }
+    }
+
+    fn segment_cells(&self, segment: usize) -> Option<Cow<[MemoryCell]>> {
+        let segment = self.segments.get(segment)?;
+        let mut cells = vec![MemoryCell::NONE; segment.len];
+        for (index, page) in &segment.pages {
+            let start = index * PAGE_SIZE;
+            let end = (start + PAGE_SIZE).min(segment.len);
+            cells[start..end].copy_from_slice(&page[..end - start]);
+        }
+        Some(Cow::Owned(cells))
+    }
+
+    fn written_cells(&self, segment: usize) -> Box<dyn Iterator<Item = (usize, MemoryCell)> + '_> {
+        let Some(segment) = self.segments.get(segment) else {
+            return Box::new(core::iter::empty());
+        };
+        let mut pages: Vec<_> = segment.pages.iter().collect();
+        pages.sort_unstable_by_key(|(index, _)| **index);
+        Box::new(
+            pages
+                .into_iter()
+                .flat_map(|(index, page)| {
+                    let start = index * PAGE_SIZE;
+                    page.iter()
+                        .copied()
+                        .enumerate()
+                        .map(move |(offset, cell)| (start + offset, cell))
+                })
+                .filter(|(_, cell)| cell.is_some()),
+        )
+    }
+
+    fn count_accessed(&self, segment: usize) -> Option<usize> {
+        let segment = self.segments.get(segment)?;
+        Some(
+            segment
+                .pages
+                .values()
+                .flat_map(|page| page.iter())
+                .filter(|cell| cell.is_some() && cell.is_accessed())
+                .count(),
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct SharedSegment {
+    cells: Arc<Vec<MemoryCell>>,
+    // Kept apart from the cells so that accessing a shared cell doesn't copy the segment
+    accessed: bv::BitVec,
+}
+
+/// Backend which initializes its first segments with shared copies of the given ones, which are
+/// only copied if a runner writes a new value into them.
+/// Cloning this backend is cheap, a clone is meant to be given to each runner executing the same
+/// program:
+/// ```
+/// # use cairo_vm::types::{layout_name::LayoutName, program::Program};
+/// # use cairo_vm::vm::{runners::cairo_runner::CairoRunner, vm_memory::memory_backend::SharedMemoryBackend};
+/// let program = Program::from_bytes(
+///     include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+///     Some("main"),
+/// )
+/// .unwrap();
+/// let backend = SharedMemoryBackend::from_program(&program);
+/// for _ in 0..2 {
+///     let mut runner = CairoRunner::new(&program, LayoutName::small, false, false).unwrap();
+///     runner
+///         .vm
+///         .segments
+///         .set_memory_backend(backend.clone())
+///         .unwrap();
+///     runner.initialize(false).unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SharedMemoryBackend {
+    templates: Vec<Arc<Vec<MemoryCell>>>,
+    segments: Vec<SharedSegment>,
+}
+
+impl SharedMemoryBackend {
+    /// Creates a backend whose first segments start as shared copies of `templates`.
+    pub(crate) fn new(templates: Vec<Arc<Vec<MemoryCell>>>) -> Self {
+        SharedMemoryBackend {
+            templates,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Creates a backend whose program segment (the first one) starts as a shared copy of the
+    /// program's data.
+    pub fn from_program(program: &Program) -> Self {
+        let program_segment = program
+            .shared_program_data
+            .data
+            .iter()
+            .map(|value| MemoryCell::new(value.clone()))
+            .collect();
+        Self::new(vec![Arc::new(program_segment)])
+    }
+
+    /// Returns true if the given segment is still shared with the backend it was cloned from.
+    pub fn is_shared(&self, segment: usize) -> bool {
+        match (self.segments.get(segment), self.templates.get(segment)) {
+            (Some(segment), Some(template)) => Arc::ptr_eq(&segment.cells, template),
+            _ => false,
+        }
+    }
+}
+
+impl MemoryBackend for SharedMemoryBackend {
+    fn num_segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    fn add_segment(&mut self) {
+        let cells = self
+            .templates
+            .get(self.segments.len())
+            .cloned()
+            .unwrap_or_default();
+        let accessed = bv::BitVec::repeat(false, cells.len());
+        self.segments.push(SharedSegment { cells, accessed });
+    }
+
+    fn truncate_segments(&mut self, len: usize) {
+        self.segments.truncate(len);
+    }
+
+    fn segment_len(&self, segment: usize) -> Option<usize> {
+        self.segments
+            .get(segment)
+            .map(|segment| segment.cells.len())
+    }
+
+    fn get(&self, segment: usize, offset: usize) -> Option<MemoryCell> {
+        let segment = self.segments.get(segment)?;
+        let mut cell = *segment.cells.get(offset)?;
+        if segment.accessed[offset] {
+            cell.mark_accessed();
+        }
+        Some(cell)
+    }
+
+    fn set(&mut self, segment: usize, offset: usize, cell: MemoryCell) -> Result<(), MemoryError> {
+        let num_segments = self.segments.len();
+        let segment = self
+            .segments
+            .get_mut(segment)
+            .ok_or_else(|| MemoryError::UnallocatedSegment(Box::new((segment, num_segments))))?;
+        let cells = Arc::make_mut(&mut segment.cells);
+        if cells.len() <= offset {
+            let new_len = offset
+                .checked_add(1)
+                .ok_or(MemoryError::VecCapacityExceeded)?;
+            cells
+                .try_reserve(new_len - cells.len())
+                .map_err(|_| MemoryError::VecCapacityExceeded)?;
+            cells.resize(new_len, MemoryCell::NONE);
+            segment.accessed.resize(new_len, false);
+        }
+        cells[offset] = cell;
+        segment.accessed.set(offset, cell.is_accessed());
+        Ok(())
+    }
+
+    fn mark_accessed(&mut self, segment: usize, offset: usize) {
+        if let Some(segment) = self.segments.get_mut(segment) {
+            if offset < segment.accessed.len() {
+                segment.accessed.set(offset, true);
+            }
+        }
+    }
+
+    fn truncate_segment(&mut self, segment: usize, len: usize) {
+        let Some(segment) = self.segments.get_mut(segment) else {
+            return;
+        };
+        if len < segment.cells.len() {
+            Arc::make_mut(&mut segment.cells).truncate(len);
+            segment.accessed.truncate(len);
+        }
+    }
+
+    fn segment_cells(&self, segment: usize) -> Option<Cow<[MemoryCell]>> {
+        let segment = self.segments.get(segment)?;
+        if segment.accessed.not_any() {
+            return Some(Cow::Borrowed(segment.cells.as_slice()));
+        }
+        let mut cells = segment.cells.to_vec();
+        for offset in segment.accessed.iter_ones() {
+            cells[offset].mark_accessed();
+        }
+        Some(Cow::Owned(cells))
+    }
+
+    fn written_cells(&self, segment: usize) -> Box<dyn Iterator<Item = (usize, MemoryCell)> + '_> {
+        let Some(segment) = self.segments.get(segment) else {
+            return Box::new(core::iter::empty());
+        };
+        Box::new(
+            segment
+                .cells
+                .iter()
+                .copied()
+                .enumerate()
+                .filter(|(_, cell)| cell.is_some())
+                .map(|(offset, mut cell)| {
+                    if segment.accessed[offset] {
+                        cell.mark_accessed();
+                    }
+                    (offset, cell)
+                }),
+        )
+    }
+
+    fn count_accessed(&self, segment: usize) -> Option<usize> {
+        let segment = self.segments.get(segment)?;
+        Some(
+            segment
+                .accessed
+                .iter_ones()
+                .filter(|offset| segment.cells[*offset].is_some())
+                .count(),
+        )
+    }
+}
+
+#[cfg(test)]
+impl dyn MemoryBackend {
+    /// Returns a copy of every segment
+    pub(crate) fn cells(&self) -> Vec<Vec<MemoryCell>> {
+        (0..self.num_segments())
+            .map(|segment| self.segment_cells(segment).unwrap_or_default().into_owned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl MemoryStorage {
+    /// Returns a copy of every segment
+    pub(crate) fn cells(&self) -> Vec<Vec<MemoryCell>> {
+        (self as &dyn MemoryBackend).cells()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::{layout_name::LayoutName, relocatable::MaybeRelocatable},
+        utils::test_utils::program_b,
+        vm::{runners::cairo_runner::CairoRunner, security::verify_secure_runner},
+        Felt252,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn cell(value: u64) -> MemoryCell {
+        MemoryCell::new(MaybeRelocatable::from(Felt252::from(value)))
+    }
+
+    // Runs the same operations on a backend and returns its resulting segments
+    fn exercise(mut backend: Box<dyn MemoryBackend>) -> Vec<Vec<MemoryCell>> {
+        backend.add_segment();
+        backend.add_segment();
+        assert_matches!(
+            backend.set(2, 0, cell(1)),
+            Err(MemoryError::UnallocatedSegment(bx)) if *bx == (2, 2)
+        );
+        backend.set(0, 3, cell(4)).unwrap();
+        backend.set(0, 1, cell(2)).unwrap();
+        backend.set(1, 5 * PAGE_SIZE / 2, cell(5)).unwrap();
+        backend.mark_accessed(0, 1);
+        backend.mark_accessed(0, 2);
+        backend.mark_accessed(0, 7);
+        assert_eq!(backend.segment_len(0), Some(4));
+        assert_eq!(backend.segment_len(2), None);
+        assert_eq!(backend.get(0, 0), Some(MemoryCell::NONE));
+        assert_eq!(backend.get(0, 4), None);
+        assert!(backend.get(0, 1).unwrap().is_accessed());
+        assert_eq!(backend.count_accessed(0), Some(1));
+        assert_eq!(
+            backend.written_cells(1).collect::<Vec<_>>(),
+            vec![(5 * PAGE_SIZE / 2, cell(5))]
+        );
+        assert_eq!(backend.written_cells(2).count(), 0);
+        backend.truncate_segment(1, 3);
+        backend.set(1, 4, cell(6)).unwrap();
+        backend.add_segment();
+        backend.truncate_segments(2);
+        backend.cells()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn backends_behave_like_vectors() {
+        let mut accessed = cell(2);
+        accessed.mark_accessed();
+        let mut empty_accessed = MemoryCell::NONE;
+        empty_accessed.mark_accessed();
+        let expected = vec![
+            vec![MemoryCell::NONE, accessed, empty_accessed, cell(4)],
+            vec![
+                MemoryCell::NONE,
+                MemoryCell::NONE,
+                MemoryCell::NONE,
+                MemoryCell::NONE,
+                cell(6),
+            ],
+        ];
+        assert_eq!(exercise(Box::<Vec<Vec<MemoryCell>>>::default()), expected);
+        assert_eq!(exercise(Box::new(PagedMemoryBackend::new())), expected);
+        assert_eq!(exercise(Box::new(SharedMemoryBackend::default())), expected);
+        assert_eq!(
+            exercise(Box::new(MemoryStorage::from(PagedMemoryBackend::new()))),
+            expected
+        );
+        assert_eq!(
+            exercise(Box::new(MemoryStorage::Custom(Box::new(
+                PagedMemoryBackend::new()
+            )))),
+            expected
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn paged_backend_allocates_written_pages() {
+        let mut backend = PagedMemoryBackend::new();
+        backend.add_segment();
+        backend.set(0, 0, cell(1)).unwrap();
+        backend.set(0, 1 << 40, cell(2)).unwrap();
+        assert_eq!(backend.allocated_pages(), 2);
+        assert_eq!(backend.segment_len(0), Some((1 << 40) + 1));
+        assert_eq!(backend.get(0, 1 << 40), Some(cell(2)));
+        assert_eq!(backend.get(0, 1 << 20), Some(MemoryCell::NONE));
+        assert_eq!(
+            backend.written_cells(0).collect::<Vec<_>>(),
+            vec![(0, cell(1)), (1 << 40, cell(2))]
+        );
+        backend.truncate_segment(0, 1);
+        assert_eq!(backend.allocated_pages(), 1);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn shared_backend_copies_written_segments() {
+        let template = Arc::new(vec![cell(1), cell(2)]);
+        let shared = SharedMemoryBackend::new(vec![template.clone()]);
+        let mut backend = shared.clone();
+        backend.add_segment();
+        backend.add_segment();
+        backend.mark_accessed(0, 1);
+        assert!(backend.is_shared(0));
+        assert!(backend.get(0, 1).unwrap().is_accessed());
+        assert!(!template[1].is_accessed());
+
+        backend.set(0, 2, cell(3)).unwrap();
+        assert!(!backend.is_shared(0));
+        assert_eq!(template.len(), 2);
+        assert_eq!(
+            backend.segment_cells(0).unwrap().len(),
+            3,
+            "written segment is copied"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_sparse_program_with_each_backend() {
+        // Writes a single value 30000 cells past the start of the execution segment
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/sparse_memory.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let shared = SharedMemoryBackend::from_program(&program);
+        let run = |backend: MemoryStorage| {
+            let mut runner = CairoRunner::new(&program, LayoutName::plain, false, false).unwrap();
+            runner.vm.segments.set_memory_backend(backend).unwrap();
+            let end = runner.initialize(false).unwrap();
+            runner
+                .run_until_pc(end, &mut BuiltinHintProcessor::new_empty())
+                .unwrap();
+            runner
+                .end_run(false, false, &mut BuiltinHintProcessor::new_empty())
+                .unwrap();
+            verify_secure_runner(&runner, true, None).unwrap();
+            runner.relocate(true).unwrap();
+            runner
+        };
+        let expected = run(MemoryStorage::default());
+        let execution_base = expected.program.shared_program_data.data.len() + 1;
+        assert_eq!(expected.relocated_memory.len(), execution_base + 30003);
+        assert_eq!(
+            expected.relocated_memory[execution_base + 30002],
+            Some(Felt252::from(7))
+        );
+        let custom: Box<dyn MemoryBackend> = Box::<Vec<Vec<MemoryCell>>>::default();
+        for backend in [
+            PagedMemoryBackend::new().into(),
+            shared.into(),
+            custom.into(),
+        ] {
+            let runner = run(backend);
+            assert_eq!(runner.relocated_memory, expected.relocated_memory);
+            assert_eq!(
+                runner.get_memory_holes().unwrap(),
+                expected.get_memory_holes().unwrap()
+            );
+            if let MemoryStorage::Paged(backend) = &runner.vm.segments.memory.data {
+                // The program page and the first and last pages of the execution segment
+                assert_eq!(backend.allocated_pages(), 3);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn relocate_sparse_temporary_segment() {
+        let mut runner = CairoRunner::new(&program_b(), LayoutName::small, false, false).unwrap();
+        runner
+            .vm
+            .segments
+            .set_memory_backend(PagedMemoryBackend::new())
+            .unwrap();
+        let first = runner.vm.add_memory_segment();
+        let second = runner.vm.add_memory_segment();
+        let temp = runner.vm.add_temporary_segment();
+        let far = (first + 5 * PAGE_SIZE).unwrap();
+        runner.vm.insert_value(first, Felt252::ONE).unwrap();
+        runner.vm.insert_value(far, temp).unwrap();
+        runner
+            .vm
+            .insert_value((temp + 2_usize).unwrap(), Felt252::from(7))
+            .unwrap();
+        let memory = &mut runner.vm.segments.memory;
+        memory.add_relocation_rule(temp, second).unwrap();
+        memory.relocate_memory().unwrap();
+        assert_eq!(runner.vm.get_relocatable(far), Ok(second));
+        assert_matches!(
+            &runner.vm.segments.memory.data,
+            MemoryStorage::Paged(backend) if backend.allocated_pages() == 3
+        );
+
+        runner.relocate(true).unwrap();
+        let second_base = 5 * PAGE_SIZE + 2;
+        assert_eq!(runner.relocated_memory.len(), second_base + 3);
+        assert_eq!(runner.relocated_memory[1], Some(Felt252::ONE));
+        assert_eq!(
+            runner.relocated_memory[5 * PAGE_SIZE + 1],
+            Some(Felt252::from(second_base))
+        );
+        assert_eq!(
+            runner.relocated_memory[second_base + 2],
+            Some(Felt252::from(7))
+        );
+        assert_eq!(runner.relocated_memory.iter().flatten().count(), 3);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn set_memory_backend_after_adding_segments() {
+        let mut segments = crate::vm::vm_memory::memory_segments::MemorySegmentManager::new();
+        let mut backend = PagedMemoryBackend::new();
+        backend.add_segment();
+        assert_matches!(
+            segments.set_memory_backend(backend),
+            Err(MemoryError::NonEmptyMemoryBackend)
+        );
+        segments.add();
+        assert_matches!(
+            segments.set_memory_backend(PagedMemoryBackend::new()),
+            Err(MemoryError::NonEmptyMemoryBackend)
+        );
+    }
+}
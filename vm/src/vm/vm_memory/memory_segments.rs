@@ -13,8 +13,9 @@ use crate::vm::runners::cairo_runner::CairoArg;
 use crate::{
     types::relocatable::{MaybeRelocatable, Relocatable},
     vm::{
-        errors::memory_errors::MemoryError, errors::vm_errors::VirtualMachineError,
-        vm_memory::memory::Memory,
+        errors::memory_errors::MemoryError,
+        errors::vm_errors::VirtualMachineError,
+        vm_memory::{memory::Memory, memory_backend::MemoryStorage},
     },
 };
 
@@ -37,7 +38,22 @@ pub struct MemorySegmentManager {
 impl MemorySegmentManager {
     /// Number of segments in the real memory
     pub fn num_segments(&self) -> usize {
-        self.memory.data.len()
+        self.memory.data.num_segments()
+    }
+
+    /// Replaces the storage of the real memory segments, see
+    /// [memory_backend](crate::vm::vm_memory::memory_backend).
+    /// Both the current and the new backend must be empty.
+    pub fn set_memory_backend(
+        &mut self,
+        backend: impl Into<MemoryStorage>,
+    ) -> Result<(), MemoryError> {
+        let backend = backend.into();
+        if self.memory.data.num_segments() != 0 || backend.num_segments() != 0 {
+            return Err(MemoryError::NonEmptyMemoryBackend);
+        }
+        self.memory.data = backend;
+        Ok(())
     }
 
     /// Number of segments in the temporary memory
@@ -47,9 +63,9 @@ impl MemorySegmentManager {
 
     ///Adds a new segment and returns its starting location as a Relocatable value. Its segment index will always be positive.
     pub fn add(&mut self) -> Relocatable {
-        self.memory.data.push(Vec::new());
+        self.memory.data.add_segment();
         Relocatable {
-            segment_index: (self.memory.data.len() - 1) as isize,
+            segment_index: (self.memory.data.num_segments() - 1) as isize,
             offset: 0,
         }
     }
@@ -91,8 +107,12 @@ impl MemorySegmentManager {
 
     /// Calculates the size of each memory segment.
     pub fn compute_effective_sizes(&mut self) -> &Vec<usize> {
-        self.segment_used_sizes
-            .get_or_insert_with(|| self.memory.data.iter().map(Vec::len).collect())
+        self.segment_used_sizes.get_or_insert_with(|| {
+            let data = &self.memory.data;
+            (0..data.num_segments())
+                .map(|segment| data.segment_len(segment).unwrap_or_default())
+                .collect()
+        })
     }
 
     ///Returns the number of used segments if they have been computed.
@@ -207,9 +227,8 @@ impl MemorySegmentManager {
         &self,
         builtin_segment_indexes: HashSet<usize>,
     ) -> Result<usize, MemoryError> {
-        let data = &self.memory.data;
         let mut memory_holes = 0;
-        for i in 0..data.len() {
+        for i in 0..self.memory.data.num_segments() {
             // Instead of marking all of the builtin segment's address as accessed, we just skip them when counting memory holes
             // Output builtin is extempt from this behaviour
             if builtin_segment_indexes.contains(&i) {
@@ -285,19 +304,38 @@ impl MemorySegmentManager {
     // Creates the zero segment if it wasn't previously created
     // Fills the segment with the value 0 until size is reached
     // Returns the index of the zero segment
-    pub(crate) fn add_zero_segment(&mut self, size: usize) -> usize {
+    pub(crate) fn add_zero_segment(&mut self, size: usize) -> Result<usize, MemoryError> {
         if !self.has_zero_segment() {
             self.zero_segment_index = self.add().segment_index as usize;
         }
 
         // Fil zero segment with zero values until size is reached
+        let data = &mut self.memory.data;
         for _ in 0..(size.saturating_sub(self.zero_segment_size)) {
             // As zero_segment_index is only accessible to the segment manager
-            // we can asume that it is always valid and index direcly into it
-            self.memory.data[self.zero_segment_index].push(MemoryCell::new(Felt252::ZERO.into()))
+            // we can asume that it is always valid and append to it directly
+            let len = data
+                .segment_len(self.zero_segment_index)
+                .unwrap_or_default();
+            data.set(
+                self.zero_segment_index,
+                len,
+                MemoryCell::new(Felt252::ZERO.into()),
+            )?;
         }
         self.zero_segment_size = max(self.zero_segment_size, size);
-        self.zero_segment_index
+        Ok(self.zero_segment_index)
+    }
+
+    /// Returns the index and size of the zero segment, with index 0 if it wasn't created
+    pub(crate) fn zero_segment(&self) -> (usize, usize) {
+        (self.zero_segment_index, self.zero_segment_size)
+    }
+
+    /// Sets the tracking data of the zero segment, as returned by `zero_segment`
+    pub(crate) fn set_zero_segment(&mut self, index: usize, size: usize) {
+        self.zero_segment_index = index;
+        self.zero_segment_size = size;
     }
 
     // Finalizes the zero segment and clears it's tracking data from the manager
@@ -607,7 +645,7 @@ mod tests {
 
         assert_eq!(exec, Ok(MaybeRelocatable::from((1, 3))));
         assert_eq!(
-            segments.memory.data[1],
+            segments.memory.data.cells()[1],
             vec![
                 MemoryCell::new(MaybeRelocatable::from((0, 1))),
                 MemoryCell::new(MaybeRelocatable::from((0, 2))),
@@ -911,7 +949,7 @@ mod tests {
     fn finalize_no_size_nor_memory() {
         let mut segments = MemorySegmentManager::new();
         segments.finalize(None, 0, None);
-        assert!(segments.memory.data.is_empty());
+        assert!(segments.memory.data.num_segments() == 0);
         assert!(segments.memory.temp_data.is_empty());
         assert_eq!(segments.public_memory_offsets, HashMap::from([(0, vec![])]));
         assert_eq!(segments.num_segments(), 0);
@@ -1019,11 +1057,11 @@ mod tests {
         memory_segment_manager.add();
 
         // Add zero segment
-        memory_segment_manager.add_zero_segment(3);
+        memory_segment_manager.add_zero_segment(3).unwrap();
         assert_eq!(memory_segment_manager.zero_segment_index, 2);
         assert_eq!(memory_segment_manager.zero_segment_size, 3);
         assert_eq!(
-            &memory_segment_manager.memory.data[2],
+            &memory_segment_manager.memory.data.cells()[2],
             &Vec::from([
                 MemoryCell::new(MaybeRelocatable::from(0)),
                 MemoryCell::new(MaybeRelocatable::from(0)),
@@ -1032,12 +1070,12 @@ mod tests {
         );
 
         // Resize zero segment
-        memory_segment_manager.add_zero_segment(5);
+        memory_segment_manager.add_zero_segment(5).unwrap();
         assert_eq!(memory_segment_manager.zero_segment_index, 2);
         assert_eq!(memory_segment_manager.zero_segment_size, 5);
 
         assert_eq!(
-            &memory_segment_manager.memory.data[2],
+            &memory_segment_manager.memory.data.cells()[2],
             &Vec::from([
                 MemoryCell::new(MaybeRelocatable::from(0)),
                 MemoryCell::new(MaybeRelocatable::from(0)),
@@ -31,7 +31,7 @@ pub mod without_std {
     pub use alloc::vec;
 
     pub mod collections {
-        pub use alloc::collections::BTreeMap;
+        pub use alloc::collections::{BTreeMap, BTreeSet};
         pub use hashbrown::{HashMap, HashSet};
     }
 